@@ -0,0 +1,117 @@
+//! Session data pipeline benchmarks
+//!
+//! Pumps synthetic terminal output through the same path as a live session:
+//! backend screen model (vt100), output frames (output_frame.rs) and the IPC
+//! channel they are streamed on.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::ipc::{Channel, InvokeResponseBody};
+
+#[allow(dead_code)]
+#[path = "../src/output_frame.rs"]
+mod output_frame;
+
+use output_frame::{Frame, FRAME_HEADER_LEN, READ_CHUNK_SIZE};
+
+/// Output pumped per iteration
+const PUMP_BYTES: usize = 100 * 1024 * 1024;
+
+/// Colored `ls -l`-style output, one PTY read worth
+fn chunk() -> Vec<u8> {
+    let line = b"\x1b[0m-rw-r--r-- 1 user user  4096 Jan  1 12:00 \x1b[01;34mfile.txt\x1b[0m\r\n";
+    line.iter().copied().cycle().take(READ_CHUNK_SIZE).collect()
+}
+
+/// Frontend channel that counts the payload bytes it receives, and the
+/// sequence number of the next frame
+struct Stream {
+    channel: Channel<InvokeResponseBody>,
+    received: Arc<AtomicUsize>,
+    sequence: u64,
+}
+
+impl Stream {
+    fn new() -> Self {
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&received);
+        let channel = Channel::new(move |body| {
+            if let InvokeResponseBody::Raw(frame) = body {
+                counter.fetch_add(frame.len() - FRAME_HEADER_LEN, Ordering::Relaxed);
+            }
+            Ok(())
+        });
+        Self {
+            channel,
+            received,
+            sequence: 0,
+        }
+    }
+
+    /// Send a frame the way the session output does
+    fn send(&mut self, frame: Frame) {
+        let body = InvokeResponseBody::Raw(frame.encode(self.sequence));
+        self.channel.send(body).unwrap();
+        self.sequence += 1;
+    }
+
+    fn received(&self) -> usize {
+        self.received.load(Ordering::Relaxed)
+    }
+}
+
+fn bench_pipeline(c: &mut Criterion) {
+    let chunk = chunk();
+    let chunks = PUMP_BYTES / chunk.len();
+
+    let mut group = c.benchmark_group("pipeline");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes((chunks * chunk.len()) as u64));
+
+    // Output framing alone: PTY reads land directly in frames
+    group.bench_function("pty_frames_100mb", |b| {
+        b.iter(|| {
+            let mut stream = Stream::new();
+            for _ in 0..chunks {
+                let mut frame = Frame::default();
+                frame.read_buf().copy_from_slice(&chunk);
+                stream.send(frame);
+            }
+            assert_eq!(stream.received(), chunks * chunk.len());
+        })
+    });
+
+    // Output framing alone: SSH data is copied into frames
+    group.bench_function("ssh_copy_100mb", |b| {
+        b.iter(|| {
+            let mut stream = Stream::new();
+            for _ in 0..chunks {
+                let mut frame = Frame::default();
+                frame.extend_from_slice(&chunk);
+                stream.send(frame);
+            }
+            assert_eq!(stream.received(), chunks * chunk.len());
+        })
+    });
+
+    // Full path: screen model plus framing, as in the session read loops
+    group.bench_function("screen_and_frames_100mb", |b| {
+        b.iter(|| {
+            let mut stream = Stream::new();
+            let mut screen = vt100::Parser::new(24, 80, 0);
+            for _ in 0..chunks {
+                screen.process(&chunk);
+                let mut frame = Frame::default();
+                frame.extend_from_slice(&chunk);
+                stream.send(frame);
+            }
+            assert_eq!(stream.received(), chunks * chunk.len());
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_pipeline);
+criterion_main!(benches);
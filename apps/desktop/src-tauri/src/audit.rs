@@ -0,0 +1,136 @@
+/**
+ * Audit Log Module
+ *
+ * Persistent record of security-relevant actions (e.g. insecure host key
+ * acceptance) for later review
+ */
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Audit event: Quick SSH accepted a host key without verification
+pub const EVENT_INSECURE_HOST_KEY: &str = "insecure_host_key_accepted";
+
+/// Audit event: stored credentials were revealed to the user
+pub const EVENT_CREDENTIALS_REVEALED: &str = "credentials_revealed";
+
+/// Audit event: the master password was changed (vault re-encrypted)
+pub const EVENT_MASTER_PASSWORD_CHANGED: &str = "master_password_changed";
+
+/// Audit event: the vault was replaced with a backup
+pub const EVENT_VAULT_RESTORED: &str = "vault_restored";
+
+/// Audit event: full database encryption was switched on or off
+pub const EVENT_DATABASE_ENCRYPTION: &str = "database_encryption_changed";
+
+/// Audit event: a private key stored in the vault was written to a file
+pub const EVENT_KEY_EXPORTED: &str = "private_key_exported";
+
+/// Audit event: a terminal session was shared on the local network
+pub const EVENT_SESSION_SHARED: &str = "session_shared";
+
+/// Audit event: a dangerous command was confirmed on a protected host
+pub const EVENT_DANGEROUS_COMMAND: &str = "dangerous_command_confirmed";
+
+/// Audit event: a destructive SFTP operation was confirmed on a protected host
+pub const EVENT_FILE_OPERATION_CONFIRMED: &str = "file_operation_confirmed";
+
+/// Audit event: a time-boxed session was disconnected at its time limit
+pub const EVENT_SESSION_EXPIRED: &str = "session_expired";
+
+/// Audit event: a port forward was started on an SSH session
+pub const EVENT_PORT_FORWARD: &str = "port_forward_started";
+
+/// Audit event: a changed host key was accepted by the user
+pub const EVENT_HOST_KEY_REPLACED: &str = "host_key_replaced";
+
+/// Audit event: a host was removed from the known hosts
+pub const EVENT_KNOWN_HOST_REMOVED: &str = "known_host_removed";
+
+/// Audit event: a stored secret was typed into a terminal session
+pub const EVENT_SECRET_SENT: &str = "stored_secret_sent";
+
+/// Audit event: a stored secret was copied to the clipboard
+pub const EVENT_SECRET_COPIED: &str = "stored_secret_copied";
+
+/// Audit event: a command was run on a connection over an exec channel
+pub const EVENT_COMMAND_EXECUTED: &str = "remote_command_executed";
+
+/// Audit event: a remote task ran on a connection
+pub const EVENT_REMOTE_TASK_RUN: &str = "remote_task_run";
+
+/// Audit log entry
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub event: String,
+    pub detail: String,
+}
+
+/// Record an audit event
+pub async fn record(db: &SqlitePool, event: &str, detail: &str) -> Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    sqlx::query("INSERT INTO audit_log (timestamp, event, detail) VALUES (?, ?, ?)")
+        .bind(timestamp)
+        .bind(event)
+        .bind(detail)
+        .execute(db)
+        .await?;
+
+    tracing::warn!("[audit] {}: {}", event, detail);
+    Ok(())
+}
+
+/// Get the most recent audit entries (newest first)
+pub async fn recent(db: &SqlitePool, limit: i64) -> Result<Vec<AuditEntry>> {
+    let entries = sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, timestamp, event, detail FROM audit_log ORDER BY id DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rite_vault::Database;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_record_and_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+
+        record(
+            db.pool(),
+            EVENT_INSECURE_HOST_KEY,
+            "example.com:22 ssh-ed25519 SHA256:abc",
+        )
+        .await
+        .unwrap();
+        record(
+            db.pool(),
+            EVENT_INSECURE_HOST_KEY,
+            "example.org:22 ssh-ed25519 SHA256:def",
+        )
+        .await
+        .unwrap();
+
+        let entries = recent(db.pool(), 10).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].detail.starts_with("example.org"));
+        assert_eq!(recent(db.pool(), 1).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+/**
+ * Keyboard-Interactive Authentication Prompts
+ *
+ * Relays keyboard-interactive prompts (PAM, OTP codes) to the frontend while
+ * connecting: each round is sent as an `ssh:auth-prompt` event and waits for
+ * the `answer_auth_prompt` command.
+ *
+ * Password prompts are answered with the stored password (once), so the user
+ * only sees the other prompts, typically the verification code.
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// How long the user has to answer a prompt
+pub const ANSWER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// One prompt of a keyboard-interactive round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPrompt {
+    pub prompt: String,
+    /// Whether the answer may be shown while typed (false for secrets)
+    pub echo: bool,
+}
+
+/// Prompts shown to the user (`ssh:auth-prompt` payload)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptRequest {
+    pub prompt_id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Name and instructions sent by the server (often empty)
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPrompt>,
+}
+
+/// Answers rounds with the stored password where possible
+pub struct Responder {
+    /// Stored password, until it has been used once
+    password: Option<Zeroizing<String>>,
+}
+
+impl Responder {
+    pub fn new(password: Option<&str>) -> Self {
+        Self {
+            password: password
+                .filter(|p| !p.is_empty())
+                .map(|p| Zeroizing::new(p.to_string())),
+        }
+    }
+
+    /// Answers for a round; None for the prompts the user has to answer
+    pub fn prefill(&mut self, prompts: &[AuthPrompt]) -> Vec<Option<Zeroizing<String>>> {
+        prompts
+            .iter()
+            .map(|prompt| match is_password_prompt(prompt) {
+                true => self.password.take(),
+                false => None,
+            })
+            .collect()
+    }
+}
+
+/// Fill the unanswered prompts with the user's answers, in order
+pub fn complete(answers: Vec<Option<Zeroizing<String>>>, typed: &[String]) -> Result<Vec<String>> {
+    let missing = answers.iter().filter(|a| a.is_none()).count();
+    if typed.len() != missing {
+        return Err(anyhow!("Expected {} answers, got {}", missing, typed.len()));
+    }
+
+    let mut typed = typed.iter();
+    Ok(answers
+        .into_iter()
+        .map(|answer| match answer {
+            Some(answer) => answer.to_string(),
+            None => typed.next().cloned().unwrap_or_default(),
+        })
+        .collect())
+}
+
+fn is_password_prompt(prompt: &AuthPrompt) -> bool {
+    !prompt.echo && prompt.prompt.to_lowercase().contains("password")
+}
+
+/// Answers from the frontend (None = cancelled)
+type Reply = oneshot::Sender<Option<Vec<String>>>;
+
+/// Prompts waiting for an answer from the frontend
+#[derive(Clone, Default)]
+pub struct AuthPrompts {
+    pending: Arc<Mutex<HashMap<String, Reply>>>,
+}
+
+impl AuthPrompts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show prompts to the user and wait for the answers
+    pub async fn ask(
+        &self,
+        app: &AppHandle,
+        mut request: AuthPromptRequest,
+    ) -> Result<Zeroizing<Vec<String>>> {
+        let prompt_id = Uuid::new_v4().to_string();
+        let (reply, answer) = oneshot::channel();
+        self.pending
+            .lock()
+            .map_err(|_| anyhow!("Prompt state poisoned"))?
+            .insert(prompt_id.clone(), reply);
+
+        request.prompt_id = prompt_id.clone();
+        let _ = app.emit("ssh:auth-prompt", &request);
+
+        let result = tokio::time::timeout(ANSWER_TIMEOUT, answer).await;
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&prompt_id);
+        }
+        match result {
+            Ok(Ok(Some(answers))) => Ok(Zeroizing::new(answers)),
+            Ok(Ok(None)) | Ok(Err(_)) => Err(anyhow!("Authentication cancelled")),
+            Err(_) => Err(anyhow!("No answer to the authentication prompt")),
+        }
+    }
+
+    /// Answer a prompt (None cancels the authentication)
+    pub fn answer(&self, prompt_id: &str, answers: Option<Vec<String>>) -> Result<()> {
+        let reply = self
+            .pending
+            .lock()
+            .map_err(|_| anyhow!("Prompt state poisoned"))?
+            .remove(prompt_id)
+            .ok_or_else(|| anyhow!("Authentication prompt not found or expired"))?;
+        let _ = reply.send(answers);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(text: &str, echo: bool) -> AuthPrompt {
+        AuthPrompt {
+            prompt: text.to_string(),
+            echo,
+        }
+    }
+
+    #[test]
+    fn test_password_prefilled_once() {
+        let mut responder = Responder::new(Some("hunter2"));
+
+        // PAM password + OTP in one round: only the code is asked
+        let round = [
+            prompt("Password: ", false),
+            prompt("Verification code: ", false),
+        ];
+        let answers = responder.prefill(&round);
+        assert_eq!(answers[0].as_deref().map(String::as_str), Some("hunter2"));
+        assert!(answers[1].is_none());
+        assert_eq!(
+            complete(answers, &["123456".to_string()]).unwrap(),
+            vec!["hunter2".to_string(), "123456".to_string()]
+        );
+
+        // Asked again (wrong password): the user answers this time
+        let answers = responder.prefill(&[prompt("Password: ", false)]);
+        assert!(answers[0].is_none());
+        assert!(complete(answers, &[]).is_err());
+    }
+
+    #[test]
+    fn test_no_stored_password() {
+        let mut responder = Responder::new(None);
+        let answers = responder.prefill(&[prompt("Password: ", false), prompt("Token: ", true)]);
+        assert!(answers.iter().all(Option::is_none));
+        assert_eq!(
+            complete(answers, &["secret".to_string(), "42".to_string()]).unwrap(),
+            vec!["secret".to_string(), "42".to_string()]
+        );
+
+        // Empty password (e.g. imported from SSH config) is not used
+        let mut responder = Responder::new(Some(""));
+        assert!(responder.prefill(&[prompt("Password: ", false)])[0].is_none());
+    }
+}
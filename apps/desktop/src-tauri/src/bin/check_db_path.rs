@@ -13,14 +13,15 @@ fn main() {
     #[cfg(target_os = "windows")]
     println!("🪟 Compiled for: Windows");
 
-    // Runtime detection via dirs
-    if let Some(data_dir) = dirs::data_dir() {
-        let db_path = data_dir.join("rite").join("vault.db");
+    // Runtime detection via dirs (same lookup as the app)
+    if let Ok(db_path) = rite_vault::default_path() {
         println!("📁 Database will be created at:");
         println!("   {}", db_path.display());
 
-        println!("\n📂 Parent directory:");
-        println!("   {}", data_dir.display());
+        if let Some(data_dir) = dirs::data_dir() {
+            println!("\n📂 Parent directory:");
+            println!("   {}", data_dir.display());
+        }
     } else {
         eprintln!("❌ Could not determine data directory!");
     }
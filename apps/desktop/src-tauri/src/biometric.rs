@@ -0,0 +1,273 @@
+/**
+ * Biometric Unlock
+ *
+ * Keeps a copy of the master key in the OS credential store, released only
+ * after a biometric check:
+ * - macOS: Keychain item with a Touch ID access control (bound to the
+ *   currently enrolled fingerprints)
+ * - Windows: the key is encrypted with a key derived from a Windows Hello
+ *   key credential's signature, which needs the Hello check every time
+ *
+ * The saved secret is the key derivation salt followed by the key, so a key
+ * saved before a master password change is refused by the vault.
+ */
+use anyhow::{anyhow, Result};
+use rite_vault::MasterKey;
+use zeroize::Zeroizing;
+
+/// Setting: biometric unlock enabled ("true" / "false")
+pub const SETTING_ENABLED: &str = "biometric_unlock";
+
+/// Reason shown in the system prompt (where the platform supports one)
+pub const PROMPT_REASON: &str = "unlock the RITE vault";
+
+const KEY_LEN: usize = 32;
+
+/// Whether this device can verify the user biometrically
+pub fn is_available() -> bool {
+    platform::is_available()
+}
+
+/// Save the master key behind a biometric check
+pub fn store(salt: &[u8], master_key: &MasterKey) -> Result<()> {
+    platform::store(&encode(salt, master_key))
+}
+
+/// Ask for a biometric check and return the saved salt and master key
+///
+/// Blocks until the user answers the system prompt.
+pub fn load() -> Result<(Vec<u8>, MasterKey)> {
+    let secret = platform::load(PROMPT_REASON)?;
+    decode(&secret)
+}
+
+/// Delete the saved master key (missing is fine)
+pub fn remove() -> Result<()> {
+    platform::remove()
+}
+
+fn encode(salt: &[u8], master_key: &MasterKey) -> Zeroizing<Vec<u8>> {
+    let mut secret = Zeroizing::new(Vec::with_capacity(salt.len() + KEY_LEN));
+    secret.extend_from_slice(salt);
+    secret.extend_from_slice(master_key.as_bytes());
+    secret
+}
+
+fn decode(secret: &[u8]) -> Result<(Vec<u8>, MasterKey)> {
+    if secret.len() <= KEY_LEN {
+        return Err(anyhow!("Saved master key is corrupted"));
+    }
+    let (salt, key) = secret.split_at(secret.len() - KEY_LEN);
+    Ok((salt.to_vec(), MasterKey::from_bytes(key)?))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use anyhow::{anyhow, Result};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+    use security_framework::passwords::{
+        delete_generic_password, generic_password, set_generic_password_options,
+        AccessControlOptions, PasswordOptions,
+    };
+    use zeroize::Zeroizing;
+
+    const SERVICE: &str = "com.rite.vault";
+    const ACCOUNT: &str = "master-key";
+
+    /// errSecItemNotFound
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    pub fn is_available() -> bool {
+        let context = unsafe { LAContext::new() };
+        unsafe {
+            context.canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthenticationWithBiometrics)
+        }
+        .is_ok()
+    }
+
+    fn options() -> PasswordOptions {
+        let mut options = PasswordOptions::new_generic_password(SERVICE, ACCOUNT);
+        options.set_access_control_options(AccessControlOptions::BIOMETRY_CURRENT_SET);
+        options
+    }
+
+    pub fn store(secret: &[u8]) -> Result<()> {
+        // An existing item keeps its old access control: replace it
+        remove()?;
+        set_generic_password_options(secret, options())
+            .map_err(|e| anyhow!("Failed to save the key in the Keychain: {}", e))
+    }
+
+    pub fn load(_reason: &str) -> Result<Zeroizing<Vec<u8>>> {
+        // Reading the item shows the Touch ID prompt
+        generic_password(options())
+            .map(Zeroizing::new)
+            .map_err(|e| anyhow!("Biometric unlock failed: {}", e))
+    }
+
+    pub fn remove() -> Result<()> {
+        match delete_generic_password(SERVICE, ACCOUNT) {
+            Err(e) if e.code() != ITEM_NOT_FOUND => {
+                Err(anyhow!("Failed to delete the Keychain item: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use anyhow::{anyhow, Context, Result};
+    use rand::RngCore;
+    use rite_vault::{EncryptedData, MasterKey};
+    use sha2::{Digest, Sha256};
+    use std::path::PathBuf;
+    use windows::core::{Array, HSTRING};
+    use windows::Security::Credentials::{
+        KeyCredential, KeyCredentialCreationOption, KeyCredentialManager,
+        KeyCredentialRetrievalResult, KeyCredentialStatus,
+    };
+    use windows::Security::Cryptography::CryptographicBuffer;
+    use zeroize::Zeroizing;
+
+    /// Windows Hello key credential the saved key is wrapped with
+    const CREDENTIAL: &str = "RITE vault";
+
+    const CHALLENGE_LEN: usize = 32;
+
+    pub fn is_available() -> bool {
+        KeyCredentialManager::IsSupportedAsync()
+            .and_then(|operation| operation.get())
+            .unwrap_or(false)
+    }
+
+    /// Wrapped key, next to the vault: the challenge, then the key
+    /// encrypted with the credential's signature of it
+    fn key_path() -> Result<PathBuf> {
+        Ok(rite_vault::default_path()?.with_file_name("biometric.key"))
+    }
+
+    pub fn store(secret: &[u8]) -> Result<()> {
+        // Creating the credential asks for Windows Hello
+        let credential = credential(KeyCredentialManager::RequestCreateAsync(
+            &HSTRING::from(CREDENTIAL),
+            KeyCredentialCreationOption::ReplaceExisting,
+        ))?;
+
+        let mut challenge = [0u8; CHALLENGE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut challenge);
+        let key = wrapping_key(&credential, &challenge)?;
+        let wrapped = rite_crypto::encrypt(&key, secret)?;
+
+        let mut file = challenge.to_vec();
+        file.extend_from_slice(&wrapped.to_bytes());
+        std::fs::write(key_path()?, file).context("Failed to save the wrapped key")
+    }
+
+    pub fn load(_reason: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let file = std::fs::read(key_path()?).context("No saved key")?;
+        if file.len() <= CHALLENGE_LEN {
+            return Err(anyhow!("Saved master key is corrupted"));
+        }
+        let (challenge, wrapped) = file.split_at(CHALLENGE_LEN);
+
+        let credential = credential(KeyCredentialManager::OpenAsync(&HSTRING::from(CREDENTIAL)))?;
+        let key = wrapping_key(&credential, challenge)?;
+        rite_crypto::decrypt(&key, &EncryptedData::from_bytes(wrapped)?)
+            .map(Zeroizing::new)
+            .map_err(|_| anyhow!("The Windows Hello credential doesn't match the saved key"))
+    }
+
+    pub fn remove() -> Result<()> {
+        match std::fs::remove_file(key_path()?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(anyhow!("Failed to delete the wrapped key: {}", e));
+            }
+            _ => {}
+        }
+        // Missing credential is fine
+        let _ = KeyCredentialManager::DeleteAsync(&HSTRING::from(CREDENTIAL))
+            .and_then(|operation| operation.get());
+        Ok(())
+    }
+
+    fn credential(
+        operation: windows::core::Result<
+            windows::Foundation::IAsyncOperation<KeyCredentialRetrievalResult>,
+        >,
+    ) -> Result<KeyCredential> {
+        let result = operation
+            .and_then(|operation| operation.get())
+            .map_err(|e| anyhow!("Windows Hello failed: {}", e))?;
+        match result.Status()? {
+            KeyCredentialStatus::Success => Ok(result.Credential()?),
+            KeyCredentialStatus::UserCanceled => {
+                Err(anyhow!("Windows Hello verification was not completed"))
+            }
+            KeyCredentialStatus::NotFound => Err(anyhow!("No Windows Hello credential")),
+            status => Err(anyhow!("Windows Hello failed (status {})", status.0)),
+        }
+    }
+
+    /// Key from the credential's signature of the challenge
+    ///
+    /// Signing asks for Windows Hello, and the private key never leaves
+    /// the TPM or Hello container. The signatures are RSA PKCS#1 v1.5, so
+    /// the same challenge always gives the same key.
+    fn wrapping_key(credential: &KeyCredential, challenge: &[u8]) -> Result<MasterKey> {
+        let buffer = CryptographicBuffer::CreateFromByteArray(challenge)?;
+        let result = credential
+            .RequestSignAsync(&buffer)
+            .and_then(|operation| operation.get())
+            .map_err(|e| anyhow!("Windows Hello failed: {}", e))?;
+        if result.Status()? != KeyCredentialStatus::Success {
+            return Err(anyhow!("Windows Hello verification was not completed"));
+        }
+
+        let mut signature = Array::<u8>::new();
+        CryptographicBuffer::CopyToByteArray(&result.Result()?, &mut signature)?;
+        let digest = Zeroizing::new(Sha256::digest(&signature[..]).to_vec());
+        MasterKey::from_bytes(&digest)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use anyhow::{anyhow, Result};
+    use zeroize::Zeroizing;
+
+    const UNSUPPORTED: &str = "Biometric unlock is not supported on this platform";
+
+    pub fn is_available() -> bool {
+        false
+    }
+
+    pub fn store(_secret: &[u8]) -> Result<()> {
+        Err(anyhow!(UNSUPPORTED))
+    }
+
+    pub fn load(_reason: &str) -> Result<Zeroizing<Vec<u8>>> {
+        Err(anyhow!(UNSUPPORTED))
+    }
+
+    pub fn remove() -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_roundtrip() {
+        let salt = [7u8; 16];
+        let key = MasterKey::derive("biometric-test", &salt).unwrap();
+
+        let (decoded_salt, decoded_key) = decode(&encode(&salt, &key)).unwrap();
+        assert_eq!(decoded_salt, salt);
+        assert_eq!(decoded_key.as_bytes(), key.as_bytes());
+
+        assert!(decode(&[0u8; KEY_LEN]).is_err());
+    }
+}
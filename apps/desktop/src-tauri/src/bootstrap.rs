@@ -0,0 +1,66 @@
+/**
+ * Remote Environment Bootstrap
+ *
+ * Pushes a small user-selected script (aliases, PS1, editor settings) to a
+ * temporary file on the host and sources it in the session shell. The file
+ * is removed right after sourcing, so the server's dotfiles are never
+ * modified and nothing is left behind.
+ *
+ * The script travels base64-encoded on a single command line typed at the
+ * first prompt (no SFTP needed), which works with POSIX shells (sh, bash,
+ * zsh, ksh).
+ */
+use anyhow::{anyhow, Result};
+use base64::Engine as _;
+
+/// Maximum script size; the command line must stay below the tty line limit
+/// (4096 bytes on Linux) once base64-encoded
+pub const MAX_SCRIPT_LEN: usize = 2048;
+
+/// Command line that pushes and sources the script (None for an empty script)
+///
+/// The leading space keeps the line out of shell history when
+/// `HISTCONTROL=ignorespace` (or `ignoreboth`) is set.
+pub fn bootstrap_command(script: &str) -> Result<Option<String>> {
+    let script = script.replace("\r\n", "\n");
+    if script.trim().is_empty() {
+        return Ok(None);
+    }
+    if script.len() > MAX_SCRIPT_LEN {
+        return Err(anyhow!(
+            "Bootstrap script is too long ({} bytes, at most {})",
+            script.len(),
+            MAX_SCRIPT_LEN
+        ));
+    }
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(script.as_bytes());
+    Ok(Some(format!(
+        " __rite_f=$(mktemp \"${{TMPDIR:-/tmp}}/rite.XXXXXX\") && printf '%s' '{}' | base64 -d > \"$__rite_f\" && . \"$__rite_f\"; rm -f \"$__rite_f\"; unset __rite_f",
+        encoded
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap_command() {
+        assert_eq!(bootstrap_command("  \n").unwrap(), None);
+
+        let script = "alias ll='ls -l'\r\nexport PS1='\\u@\\h \\$ '\n";
+        let command = bootstrap_command(script).unwrap().unwrap();
+        assert!(command.starts_with(" __rite_f=$(mktemp"));
+        assert!(command.ends_with("rm -f \"$__rite_f\"; unset __rite_f"));
+
+        // The payload is the script (with Unix line endings), quoting-safe
+        let encoded = command.split('\'').nth(3).unwrap();
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert_eq!(decoded, script.replace("\r\n", "\n").as_bytes());
+
+        assert!(bootstrap_command(&"x".repeat(MAX_SCRIPT_LEN + 1)).is_err());
+    }
+}
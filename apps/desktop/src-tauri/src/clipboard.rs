@@ -0,0 +1,138 @@
+/**
+ * Secret Clipboard Module
+ *
+ * Copies credentials to the system clipboard from the Rust side, so the
+ * plaintext never reaches the webview, and clears them again after a
+ * timeout or when the application locks
+ */
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+/// Default delay before a copied secret is cleared (clipboard_clear_timeout)
+pub const DEFAULT_CLEAR_TIMEOUT_SECS: u64 = 30;
+
+/// Secret currently on the clipboard (only its digest is kept)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PendingClear {
+    generation: u64,
+    digest: [u8; 32],
+}
+
+/// Tracks secrets copied to the clipboard so they can be cleared later
+#[derive(Clone, Default)]
+pub struct SecretClipboard {
+    pending: Arc<Mutex<Option<PendingClear>>>,
+    next_generation: Arc<AtomicU64>,
+}
+
+impl SecretClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy a secret to the clipboard and schedule it to be cleared
+    pub async fn copy(&self, app: &AppHandle, secret: &str, clear_after: Duration) -> Result<()> {
+        app.clipboard()
+            .write_text(secret)
+            .map_err(|e| anyhow!("Failed to write clipboard: {}", e))?;
+
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed) + 1;
+        *self.pending.lock().await = Some(PendingClear {
+            generation,
+            digest: digest(secret),
+        });
+
+        let clipboard = self.clone();
+        let app = app.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(clear_after).await;
+            clipboard.clear_if(&app, Some(generation)).await;
+        });
+
+        tracing::debug!(
+            "[clipboard.rs] Secret copied, clearing in {}s",
+            clear_after.as_secs()
+        );
+        Ok(())
+    }
+
+    /// Clear any secret still on the clipboard (e.g. on lock)
+    pub async fn clear(&self, app: &AppHandle) {
+        self.clear_if(app, None).await;
+    }
+
+    /// Clear the clipboard if it still holds the copied secret
+    ///
+    /// A timer only acts on its own copy (`generation`), and the clipboard is
+    /// left alone if the user has copied something else since.
+    async fn clear_if(&self, app: &AppHandle, generation: Option<u64>) {
+        let Some(pending) = take_pending(&mut *self.pending.lock().await, generation) else {
+            return;
+        };
+
+        let current = match app.clipboard().read_text() {
+            Ok(text) => Zeroizing::new(text),
+            Err(_) => return,
+        };
+        if digest(&current) != pending.digest {
+            return;
+        }
+
+        match app.clipboard().clear() {
+            Ok(()) => tracing::debug!("[clipboard.rs] Cleared secret from clipboard"),
+            Err(e) => tracing::warn!("[clipboard.rs] Failed to clear clipboard: {}", e),
+        }
+    }
+}
+
+/// Take the pending secret if it matches `generation` (None = any)
+fn take_pending(
+    pending: &mut Option<PendingClear>,
+    generation: Option<u64>,
+) -> Option<PendingClear> {
+    match (*pending, generation) {
+        (Some(current), Some(generation)) if current.generation != generation => None,
+        _ => pending.take(),
+    }
+}
+
+fn digest(text: &str) -> [u8; 32] {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_pending() {
+        let first = PendingClear {
+            generation: 1,
+            digest: digest("secret"),
+        };
+
+        // A stale timer doesn't clear a newer copy
+        let mut pending = Some(PendingClear {
+            generation: 2,
+            ..first
+        });
+        assert_eq!(take_pending(&mut pending, Some(1)), None);
+        assert!(pending.is_some());
+
+        // The matching timer does
+        assert_eq!(take_pending(&mut pending, Some(2)).unwrap().generation, 2);
+        assert!(pending.is_none());
+
+        // Lock clears whatever is pending
+        let mut pending = Some(first);
+        assert_eq!(take_pending(&mut pending, None), Some(first));
+        assert_eq!(take_pending(&mut pending, None), None);
+    }
+}
@@ -0,0 +1,198 @@
+/**
+ * Remote Clipboard Bridge
+ *
+ * Lets remote commands use the local clipboard through OSC 52 escape
+ * sequences: `rpbcopy` (shell function defined at the first prompt) writes
+ * `ESC ] 52 ; c ; <base64> BEL` to the terminal and the text lands on the
+ * local clipboard; `rpbpaste` sends the `?` query and reads the answer.
+ *
+ * Opt-in per connection. Reading the local clipboard from the host is a
+ * separate mode, since any program on the host can send the query.
+ */
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+/// Start of an OSC 52 sequence
+const OSC_52: &[u8] = b"\x1b]52;";
+
+const BEL: u8 = 0x07;
+const ESC: u8 = 0x1b;
+
+/// Largest sequence accepted (about 768 KiB of copied text)
+const MAX_SEQUENCE_LEN: usize = 1024 * 1024;
+
+/// What the host may do with the local clipboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BridgeMode {
+    #[default]
+    Off,
+    /// Host can write the local clipboard (rpbcopy)
+    Copy,
+    /// Host can write and read the local clipboard (rpbcopy and rpbpaste)
+    CopyPaste,
+}
+
+/// Clipboard request from the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardRequest {
+    /// Put the text on the local clipboard
+    Copy(String),
+    /// Answer with the local clipboard content
+    Paste,
+}
+
+/// Finds OSC 52 sequences in session output (sequences may be split across
+/// output chunks)
+#[derive(Debug, Default)]
+pub struct Osc52Scanner {
+    /// Unfinished sequence (or partial start marker) from the previous chunk
+    pending: Vec<u8>,
+}
+
+impl Osc52Scanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed session output and return the complete requests found
+    pub fn feed(&mut self, data: &[u8]) -> Vec<ClipboardRequest> {
+        let mut buffer = std::mem::take(&mut self.pending);
+        buffer.extend_from_slice(data);
+
+        let mut requests = Vec::new();
+        let mut position = 0;
+        loop {
+            let Some(start) = find(&buffer[position..], OSC_52).map(|i| position + i) else {
+                // Keep a trailing partial marker for the next chunk
+                let keep = (1..OSC_52.len())
+                    .rev()
+                    .find(|n| buffer.ends_with(&OSC_52[..*n]))
+                    .unwrap_or(0);
+                self.pending = buffer[buffer.len() - keep..].to_vec();
+                break;
+            };
+
+            let body_start = start + OSC_52.len();
+            let Some((body_end, sequence_end)) = terminator(&buffer[body_start..]) else {
+                if buffer.len() - start <= MAX_SEQUENCE_LEN {
+                    self.pending = buffer[start..].to_vec();
+                }
+                break;
+            };
+
+            if let Some(request) = parse_body(&buffer[body_start..body_start + body_end]) {
+                requests.push(request);
+            }
+            position = body_start + sequence_end;
+        }
+
+        requests
+    }
+}
+
+/// Answer to a paste query
+pub fn paste_response(text: &str) -> Vec<u8> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text.as_bytes());
+    let mut response = OSC_52.to_vec();
+    response.extend_from_slice(b"c;");
+    response.extend_from_slice(encoded.as_bytes());
+    response.push(BEL);
+    response
+}
+
+/// Shell functions defined at the first prompt (None when the bridge is off)
+///
+/// `rpbpaste` switches the tty to non-canonical mode to read the answer and
+/// gives up after 5 seconds. The leading space keeps the line out of history.
+pub fn helper_command(mode: BridgeMode) -> Option<String> {
+    let copy = r#"rpbcopy() { printf '\033]52;c;%s\a' "$(base64 | tr -d '\n')" > /dev/tty; }"#;
+    let paste = r#"rpbpaste() { __rite_s=$(stty -g < /dev/tty); stty -icanon -echo min 0 time 50 < /dev/tty; printf '\033]52;c;?\a' > /dev/tty; awk 'BEGIN { RS = "\a" } { print; exit }' < /dev/tty | sed 's/^.*;//' | base64 -d; stty "$__rite_s" < /dev/tty; unset __rite_s; }"#;
+
+    match mode {
+        BridgeMode::Off => None,
+        BridgeMode::Copy => Some(format!(" {}", copy)),
+        BridgeMode::CopyPaste => Some(format!(" {}; {}", copy, paste)),
+    }
+}
+
+/// Request in the body of a sequence (`<selection>;<base64 or ?>`)
+fn parse_body(body: &[u8]) -> Option<ClipboardRequest> {
+    let separator = body.iter().position(|b| *b == b';')?;
+    let data = &body[separator + 1..];
+    if data == b"?" {
+        return Some(ClipboardRequest::Paste);
+    }
+
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .ok()?;
+    Some(ClipboardRequest::Copy(
+        String::from_utf8_lossy(&decoded).into_owned(),
+    ))
+}
+
+/// End of the body and of the whole sequence (BEL or ST = ESC \)
+fn terminator(data: &[u8]) -> Option<(usize, usize)> {
+    let end = data.iter().position(|b| *b == BEL || *b == ESC)?;
+    match data[end] {
+        BEL => Some((end, end + 1)),
+        _ => match data.get(end + 1) {
+            Some(b'\\') => Some((end, end + 2)),
+            // Another escape sequence interrupted this one: ignore it
+            Some(_) => Some((end, end)),
+            None => None,
+        },
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_requests() {
+        let mut scanner = Osc52Scanner::new();
+
+        // "hello" in one chunk, terminated by BEL; plain output around it
+        let requests = scanner.feed(b"$ echo hello | rpbcopy\r\n\x1b]52;c;aGVsbG8=\x07$ ");
+        assert_eq!(requests, vec![ClipboardRequest::Copy("hello".to_string())]);
+
+        // Split across chunks (even inside the start marker), terminated by ST
+        assert!(scanner.feed(b"output\x1b]5").is_empty());
+        assert!(scanner.feed(b"2;;d29y").is_empty());
+        assert_eq!(
+            scanner.feed(b"bGQ=\x1b\\\x1b]52;c;?\x07"),
+            vec![
+                ClipboardRequest::Copy("world".to_string()),
+                ClipboardRequest::Paste
+            ]
+        );
+
+        // Invalid base64 is ignored, other OSC sequences too
+        assert!(scanner
+            .feed(b"\x1b]52;c;!!!\x07\x1b]0;title\x07")
+            .is_empty());
+        assert!(scanner.pending.is_empty());
+    }
+
+    #[test]
+    fn test_paste_response() {
+        let response = paste_response("secret text");
+        assert_eq!(response, b"\x1b]52;c;c2VjcmV0IHRleHQ=\x07");
+        // The host sees what rpbcopy would have sent
+        assert_eq!(
+            Osc52Scanner::new().feed(&response),
+            vec![ClipboardRequest::Copy("secret text".to_string())]
+        );
+
+        assert_eq!(helper_command(BridgeMode::Off), None);
+        assert!(!helper_command(BridgeMode::Copy)
+            .unwrap()
+            .contains("rpbpaste"));
+    }
+}
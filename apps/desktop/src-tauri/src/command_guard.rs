@@ -0,0 +1,318 @@
+/**
+ * Dangerous Command Guard
+ *
+ * On protected hosts, keystrokes are tracked up to Enter and the line is
+ * matched against dangerous-command patterns (rm -rf /, mkfs, shutdown, ...).
+ * On a match the Enter (and anything typed after it) is held back until the
+ * user confirms or cancels the command.
+ *
+ * The typed text doesn't include commands recalled from history or
+ * completed with Tab, so the cursor line of the screen is checked as well.
+ */
+use anyhow::{anyhow, Result};
+use regex::Regex;
+
+/// Setting holding the patterns (one regex per line, `#` starts a comment)
+pub const SETTING_PATTERNS: &str = "dangerous_command_patterns";
+
+/// Patterns used when the setting is not set
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    r"\brm\s+(-\S+\s+)*-[a-zA-Z]*[rR][a-zA-Z]*\s+(-\S+\s+)*/(\*|\s|$)",
+    r"\bmkfs(\.\w+)?\b",
+    r"(^|[\s;&|(])(shutdown|poweroff|halt|reboot|init\s+[06])(\s|;|$)",
+    r"\bdd\b.*\bof=/dev/",
+    r"(?i)\b(drop|truncate)\s+(table|database|schema)\b",
+];
+
+const ENTER_CR: u8 = b'\r';
+const ENTER_LF: u8 = b'\n';
+const BACKSPACE: u8 = 0x7f;
+const CTRL_H: u8 = 0x08;
+const CTRL_C: u8 = 0x03;
+const CTRL_U: u8 = 0x15;
+const CTRL_W: u8 = 0x17;
+const ESC: u8 = 0x1b;
+
+/// A command waiting for the user's confirmation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    pub command: String,
+    /// Pattern that matched
+    pub pattern: String,
+}
+
+/// Result of feeding input to the guard
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GuardOutput {
+    /// Bytes to forward to the host
+    pub send: Vec<u8>,
+    /// Set when a command is held back for confirmation
+    pub confirm: Option<Confirmation>,
+}
+
+/// Input filter holding back dangerous commands
+#[derive(Debug)]
+pub struct CommandGuard {
+    patterns: Vec<Regex>,
+    /// Text typed since the last Enter
+    line: Vec<u8>,
+    /// Held input (starting with the Enter) while waiting for confirmation
+    pending: Option<Vec<u8>>,
+}
+
+impl CommandGuard {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p.as_ref()).map_err(|e| {
+                    anyhow!("Invalid dangerous command pattern '{}': {}", p.as_ref(), e)
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            patterns,
+            line: Vec::new(),
+            pending: None,
+        })
+    }
+
+    /// Guard from the setting value (None = default patterns)
+    pub fn from_setting(setting: Option<&str>) -> Result<Self> {
+        match setting {
+            Some(value) => Self::new(&parse_patterns(value)),
+            None => Self::new(DEFAULT_PATTERNS),
+        }
+    }
+
+    /// Whether a command is waiting for confirmation
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Feed keystrokes; `screen_line` is the cursor line of the screen
+    /// before this input was sent
+    pub fn process(&mut self, input: &[u8], screen_line: Option<&str>) -> GuardOutput {
+        if let Some(pending) = self.pending.as_mut() {
+            pending.extend_from_slice(input);
+            return GuardOutput::default();
+        }
+
+        let mut screen_line = screen_line;
+        let mut i = 0;
+        while i < input.len() {
+            match input[i] {
+                ENTER_CR | ENTER_LF => {
+                    let typed =
+                        String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+                    // The screen only shows the line of the first Enter
+                    let confirm = self
+                        .check(&typed)
+                        .or_else(|| screen_line.take().and_then(|l| self.check(l)));
+                    if let Some(confirm) = confirm {
+                        self.pending = Some(input[i..].to_vec());
+                        return GuardOutput {
+                            send: input[..i].to_vec(),
+                            confirm: Some(confirm),
+                        };
+                    }
+                }
+                BACKSPACE | CTRL_H => {
+                    // Remove a whole UTF-8 character
+                    while let Some(byte) = self.line.pop() {
+                        if byte & 0xc0 != 0x80 {
+                            break;
+                        }
+                    }
+                }
+                CTRL_C | CTRL_U => self.line.clear(),
+                CTRL_W => {
+                    while self.line.last() == Some(&b' ') {
+                        self.line.pop();
+                    }
+                    while self.line.last().is_some_and(|b| *b != b' ') {
+                        self.line.pop();
+                    }
+                }
+                // Escape sequences (arrows, function keys) don't change the text
+                ESC => {
+                    i += escape_len(&input[i..]) - 1;
+                }
+                byte if byte >= 0x20 || byte == b'\t' => self.line.push(byte),
+                _ => {}
+            }
+            i += 1;
+        }
+
+        GuardOutput {
+            send: input.to_vec(),
+            confirm: None,
+        }
+    }
+
+    /// Release (approved) or drop (cancelled) the held command
+    pub fn resolve(&mut self, approved: bool) -> GuardOutput {
+        let Some(held) = self.pending.take() else {
+            return GuardOutput::default();
+        };
+
+        if !approved {
+            // Interrupt the shell so the command line is discarded
+            return GuardOutput {
+                send: vec![CTRL_C],
+                confirm: None,
+            };
+        }
+
+        // Send the Enter, then check what was typed while waiting
+        let mut output = self.process(&held[1..], None);
+        output.send.insert(0, held[0]);
+        output
+    }
+
+    /// Check a whole command (each of its lines), for commands that are
+    /// run without a terminal to hold them in
+    pub fn check_command(&self, command: &str) -> Option<Confirmation> {
+        command.lines().find_map(|line| self.check(line))
+    }
+
+    fn check(&self, line: &str) -> Option<Confirmation> {
+        self.patterns
+            .iter()
+            .find(|p| p.is_match(line))
+            .map(|p| Confirmation {
+                command: line.trim().to_string(),
+                pattern: p.as_str().to_string(),
+            })
+    }
+}
+
+/// Patterns from the setting value (blank lines and `#` comments skipped)
+pub fn parse_patterns(value: &str) -> Vec<String> {
+    value
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Length of the escape sequence at the start of `input`
+fn escape_len(input: &[u8]) -> usize {
+    match input.get(1) {
+        // CSI: parameters up to a final byte
+        Some(b'[') => input[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map_or(input.len(), |end| end + 3),
+        // SS3 (application cursor keys)
+        Some(b'O') => input.len().min(3),
+        Some(_) => 2,
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard() -> CommandGuard {
+        CommandGuard::from_setting(None).unwrap()
+    }
+
+    #[test]
+    fn test_default_patterns() {
+        let guard = guard();
+        for command in [
+            "rm -rf /",
+            "sudo rm -fr /*",
+            "rm --no-preserve-root -rf /",
+            "mkfs.ext4 /dev/sdb1",
+            "sudo shutdown -h now",
+            "reboot",
+            "dd if=/dev/zero of=/dev/sda bs=1M",
+            "DROP TABLE users;",
+        ] {
+            assert!(guard.check(command).is_some(), "{} should match", command);
+        }
+        for command in [
+            "rm -rf /tmp/build",
+            "ls /",
+            "echo rebooting",
+            "cat shutdown.log",
+        ] {
+            assert!(
+                guard.check(command).is_none(),
+                "{} should not match",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn test_holds_dangerous_command() {
+        let mut guard = guard();
+
+        // Harmless commands go straight through
+        assert_eq!(guard.process(b"ls -l\r", None).send, b"ls -l\r");
+
+        // Typed key by key: everything but the Enter is forwarded
+        assert_eq!(guard.process(b"rm -rf /", None).send, b"rm -rf /");
+        let output = guard.process(b"\rls\r", None);
+        assert!(output.send.is_empty());
+        assert_eq!(output.confirm.unwrap().command, "rm -rf /");
+        assert!(guard.is_pending());
+
+        // Input typed while waiting is held as well
+        assert!(guard.process(b"pwd\r", None).send.is_empty());
+
+        let output = guard.resolve(true);
+        assert_eq!(output.send, b"\rls\rpwd\r");
+        assert!(!guard.is_pending());
+
+        // Cancel interrupts the line and drops the held input
+        guard.process(b"reboot", None);
+        assert!(guard.process(b"\r", None).confirm.is_some());
+        assert_eq!(guard.resolve(false).send, vec![CTRL_C]);
+        assert_eq!(guard.resolve(true), GuardOutput::default());
+    }
+
+    #[test]
+    fn test_line_editing_and_screen() {
+        let mut guard = guard();
+
+        // Erased text doesn't count
+        guard.process(b"shutdown\x15echo ok", None);
+        assert!(guard.process(b"\r", None).confirm.is_none());
+        guard.process(b"rm -rf /\x7f\x7ftmp", None);
+        assert!(guard.process(b"\r", None).confirm.is_none());
+
+        // Recalled from history: only the screen shows the command
+        let output = guard.process(b"\x1b[A\r", Some("root@db1:~# mkfs.xfs /dev/sdc"));
+        assert_eq!(output.send, b"\x1b[A");
+        assert_eq!(
+            output.confirm.unwrap().command,
+            "root@db1:~# mkfs.xfs /dev/sdc"
+        );
+    }
+
+    #[test]
+    fn test_patterns_setting() {
+        assert_eq!(
+            parse_patterns("# production\n\n  \\bsystemctl stop\\b  \n"),
+            vec!["\\bsystemctl stop\\b"]
+        );
+        let guard = CommandGuard::from_setting(Some("\\bsystemctl stop\\b")).unwrap();
+        assert!(guard.check("systemctl stop nginx").is_some());
+        assert!(guard.check("rm -rf /").is_none());
+        assert!(CommandGuard::from_setting(Some("(")).is_err());
+    }
+
+    #[test]
+    fn test_check_command() {
+        let guard = guard();
+        assert!(guard.check_command("cd /var/log\nls -l").is_none());
+        let confirm = guard.check_command("apt-get update\nreboot\n").unwrap();
+        assert_eq!(confirm.command, "reboot");
+    }
+}
@@ -1,20 +1,17 @@
 /// Tauri commands
 ///
 /// Backend functions callable from the frontend
-use crate::auth::UnlockResult;
 use crate::connection::{AuthMethod, Connection};
 use crate::state::AppState;
 use base64::Engine as _;
-use rite_crypto::validate_password_strength;
+use rite_crypto::{
+    validate_password_strength, GeneratedPassword, PasswordOptions, PasswordStrength,
+};
+use rite_vault::UnlockResult;
 use serde::{Deserialize, Serialize};
-use tauri::State;
-
-#[derive(Serialize)]
-pub struct PasswordStrength {
-    pub is_valid: bool,
-    pub score: u8,
-    pub feedback: Vec<String>,
-}
+use tauri::ipc::{Channel, InvokeResponseBody, Response};
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
 
 #[derive(Serialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -24,6 +21,20 @@ pub enum UnlockResponse {
     RateLimited { wait_seconds: u64 },
 }
 
+/// Error of `update_connection`, so a conflict can be told from a failure
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum UpdateConnectionError {
+    /// Updated elsewhere since it was read: the stored version, to merge with
+    Conflict {
+        message: String,
+        current: crate::connection::ConnectionInfo,
+    },
+    Failed {
+        message: String,
+    },
+}
+
 /// Health check command
 #[tauri::command]
 pub fn health_check() -> String {
@@ -33,13 +44,14 @@ pub fn health_check() -> String {
 /// Validate password strength
 #[tauri::command]
 pub fn validate_password(password: String) -> PasswordStrength {
-    let (is_valid, score, feedback) = validate_password_strength(&password);
+    validate_password_strength(&password)
+}
 
-    PasswordStrength {
-        is_valid,
-        score,
-        feedback,
-    }
+/// Generate a random password or diceware passphrase
+#[tauri::command]
+pub fn generate_password(options: PasswordOptions) -> Result<GeneratedPassword, String> {
+    rite_crypto::generate_password(&options)
+        .map_err(|e| format!("Failed to generate password: {}", e))
 }
 
 // ============================================================================
@@ -78,17 +90,23 @@ pub async fn setup_master_password(
 /// Unlock the application
 #[tauri::command]
 pub async fn unlock(
+    app_handle: tauri::AppHandle,
     password: String,
     state: State<'_, AppState>,
 ) -> Result<UnlockResponse, String> {
     let result = state
         .auth
-        .unlock(&password)
+        .unlock_with_progress(&password, |progress| {
+            let _ = app_handle.emit("unlock:progress", serde_json::json!({ "stage": progress }));
+        })
         .await
         .map_err(|e| format!("Unlock failed: {}", e))?;
 
     let response = match result {
-        UnlockResult::Success => UnlockResponse::Success,
+        UnlockResult::Success => {
+            after_unlock(app_handle, &state).await;
+            UnlockResponse::Success
+        }
         UnlockResult::InvalidPassword => UnlockResponse::InvalidPassword,
         UnlockResult::RateLimited { wait_seconds } => UnlockResponse::RateLimited { wait_seconds },
     };
@@ -96,9 +114,287 @@ pub async fn unlock(
     Ok(response)
 }
 
+/// Enforce the policy and start the background work that needs the vault
+/// unlocked (password or biometric unlock)
+async fn after_unlock(app_handle: tauri::AppHandle, state: &AppState) {
+    apply_policy(state).await;
+    start_warm_up(app_handle.clone(), state).await;
+    start_scheduled_backups(state).await;
+    start_trash_purge(state).await;
+    start_remote_task_scheduler(app_handle.clone(), state).await;
+    start_ssh_config_sync(app_handle, state).await;
+    start_recordings_upgrade(state).await;
+}
+
+/// Enforce the organization policy on an encrypted vault (after unlocking)
+///
+/// Plain vaults get it at startup; encrypted ones can't be written before.
+async fn apply_policy(state: &AppState) {
+    if !state.db.is_encrypted() {
+        return;
+    }
+    if let Err(e) = state.policy.apply(&state.db).await {
+        tracing::warn!("[commands.rs] Failed to apply policy: {}", e);
+    }
+}
+
+/// Set up warm connections in the background (after unlocking)
+async fn start_warm_up(app_handle: tauri::AppHandle, state: &AppState) {
+    let sessions = std::sync::Arc::clone(&state.sessions);
+    state
+        .tasks
+        .spawn("warm-connections", move || {
+            let sessions = std::sync::Arc::clone(&sessions);
+            let app_handle = app_handle.clone();
+            async move { sessions.warm_up(app_handle).await }
+        })
+        .await;
+}
+
+/// How often the scheduled backup task checks whether a backup is due
+const BACKUP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Make scheduled vault backups in the background (after unlocking)
+///
+/// Backups are encrypted with the master key, so runs while locked are
+/// skipped.
+async fn start_scheduled_backups(state: &AppState) {
+    let db = state.db.clone();
+    let auth = std::sync::Arc::clone(&state.auth);
+    state
+        .tasks
+        .spawn_periodic("vault-backup", BACKUP_CHECK_INTERVAL, move || {
+            let db = db.clone();
+            let auth = std::sync::Arc::clone(&auth);
+            async move {
+                let Ok(master_key) = auth.get_master_key().await else {
+                    return Ok(());
+                };
+                if let Some(backup) = rite_vault::backup::run_scheduled(&db, &master_key).await? {
+                    tracing::info!(
+                        "[commands.rs] Scheduled backup written: {}",
+                        backup.file_name
+                    );
+                }
+                Ok(())
+            }
+        })
+        .await;
+}
+
+/// How often the trash is checked for connections past the retention
+const TRASH_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Purge the trash in the background (after unlocking)
+///
+/// Connections are deleted for good once they have been in the trash longer
+/// than the `trash_retention_days` setting.
+async fn start_trash_purge(state: &AppState) {
+    let db = state.db.clone();
+    state
+        .tasks
+        .spawn_periodic("trash-purge", TRASH_PURGE_INTERVAL, move || {
+            let db = db.clone();
+            async move {
+                rite_vault::trash::purge_expired(&db).await?;
+                Ok(())
+            }
+        })
+        .await;
+}
+
+/// Start scheduled remote tasks in the background (after unlocking)
+///
+/// Task commands are encrypted, so checks while locked are skipped; the
+/// minutes passed while locked are not caught up.
+async fn start_remote_task_scheduler(app_handle: tauri::AppHandle, state: &AppState) {
+    let auth = std::sync::Arc::clone(&state.auth);
+    let last_check = std::sync::Arc::new(tokio::sync::Mutex::new(None));
+    state
+        .tasks
+        .spawn_periodic(
+            "remote-tasks",
+            crate::remote_tasks::CHECK_INTERVAL,
+            move || {
+                let auth = std::sync::Arc::clone(&auth);
+                let last_check = std::sync::Arc::clone(&last_check);
+                let app_handle = app_handle.clone();
+                async move {
+                    let now = chrono::Local::now();
+                    let mut last_check = last_check.lock().await;
+                    if auth.get_master_key().await.is_err() {
+                        *last_check = None;
+                        return Ok(());
+                    }
+                    let since = last_check.replace(now);
+                    crate::remote_tasks::run_due(&app_handle, since, now).await
+                }
+            },
+        )
+        .await;
+}
+
+/// Watch ~/.ssh/config in the background (after unlocking)
+///
+/// Runs only while SSH config sync is enabled; `ssh-config-changed` is
+/// emitted when the suggestions change.
+async fn start_ssh_config_sync(app_handle: tauri::AppHandle, state: &AppState) {
+    let db = state.db.clone();
+    let connections = std::sync::Arc::clone(&state.connections);
+    let last = std::sync::Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    state
+        .tasks
+        .spawn_periodic(
+            "ssh-config-sync",
+            crate::ssh_config_sync::CHECK_INTERVAL,
+            move || {
+                let db = db.clone();
+                let connections = std::sync::Arc::clone(&connections);
+                let last = std::sync::Arc::clone(&last);
+                let app_handle = app_handle.clone();
+                async move {
+                    if !crate::ssh_config_sync::is_enabled(&db).await {
+                        return Ok(());
+                    }
+                    let suggestions =
+                        crate::ssh_config_sync::suggestions(&db, &connections).await?;
+                    let mut last = last.lock().await;
+                    if *last != suggestions {
+                        let _ =
+                            app_handle.emit(crate::ssh_config_sync::EVENT_CHANGED, &suggestions);
+                        *last = suggestions;
+                    }
+                    Ok(())
+                }
+            },
+        )
+        .await;
+}
+
+/// Re-encrypt recordings made with the master key itself in the background
+/// (after unlocking)
+async fn start_recordings_upgrade(state: &AppState) {
+    let Ok(master_key) = state.auth.get_master_key().await else {
+        return;
+    };
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = crate::recording::upgrade(&master_key) {
+            tracing::warn!("[commands.rs] Failed to upgrade recordings: {:#}", e);
+        }
+    });
+}
+
+/// Check if this device supports biometric unlock (Touch ID, Windows Hello)
+#[tauri::command]
+pub fn is_biometric_available() -> bool {
+    crate::biometric::is_available()
+}
+
+/// Unlock with Touch ID / Windows Hello (requires biometric unlock enabled)
+#[tauri::command]
+pub async fn unlock_with_biometric(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if !is_biometric_enabled(&state).await {
+        return Err("Unlock failed: biometric unlock is not enabled".to_string());
+    }
+
+    let (salt, master_key) = tokio::task::spawn_blocking(crate::biometric::load)
+        .await
+        .map_err(|e| format!("Unlock failed: {}", e))?
+        .map_err(|e| format!("Unlock failed: {}", e))?;
+
+    if let Err(e) = state.auth.unlock_with_key(master_key, &salt).await {
+        // Stale key (master password changed elsewhere): ask for the password
+        tracing::warn!("[commands.rs] Saved master key refused: {}", e);
+        disable_biometric(&state).await;
+        return Err(format!("Unlock failed: {}", e));
+    }
+
+    after_unlock(app_handle, &state).await;
+    Ok(())
+}
+
+/// Enable biometric unlock (re-enter the master password)
+#[tauri::command]
+pub async fn enable_biometric_unlock(
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    state
+        .policy
+        .check_setting(crate::biometric::SETTING_ENABLED, "true")
+        .map_err(|e| format!("Failed to enable biometric unlock: {}", e))?;
+    state
+        .auth
+        .reauthenticate(&password)
+        .await
+        .map_err(|e| format!("Failed to enable biometric unlock: {}", e))?;
+
+    save_biometric_key(&state)
+        .await
+        .map_err(|e| format!("Failed to enable biometric unlock: {}", e))?;
+    state
+        .db
+        .set_setting(crate::biometric::SETTING_ENABLED, "true")
+        .await
+        .map_err(|e| format!("Failed to enable biometric unlock: {}", e))?;
+
+    tracing::info!("[commands.rs] Biometric unlock enabled");
+    Ok(())
+}
+
+/// Disable biometric unlock (deletes the saved master key)
+#[tauri::command]
+pub async fn disable_biometric_unlock(state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .policy
+        .check_setting(crate::biometric::SETTING_ENABLED, "false")
+        .map_err(|e| format!("Failed to disable biometric unlock: {}", e))?;
+    crate::biometric::remove().map_err(|e| format!("Failed to disable biometric unlock: {}", e))?;
+    state
+        .db
+        .set_setting(crate::biometric::SETTING_ENABLED, "false")
+        .await
+        .map_err(|e| format!("Failed to disable biometric unlock: {}", e))
+}
+
+async fn is_biometric_enabled(state: &AppState) -> bool {
+    matches!(
+        state.db.get_setting(crate::biometric::SETTING_ENABLED).await,
+        Ok(Some(value)) if value == "true"
+    )
+}
+
+/// Save the current master key behind the biometric check
+async fn save_biometric_key(state: &AppState) -> anyhow::Result<()> {
+    let master_key = state.auth.get_master_key().await?;
+    let (_, salt) = state
+        .db
+        .get_master_password()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No master password set"))?;
+
+    tokio::task::spawn_blocking(move || crate::biometric::store(&salt, &master_key)).await?
+}
+
+/// Delete the saved master key and turn biometric unlock off
+async fn disable_biometric(state: &AppState) {
+    if let Err(e) = crate::biometric::remove() {
+        tracing::warn!("[commands.rs] Failed to delete saved master key: {}", e);
+    }
+    let _ = state
+        .db
+        .set_setting(crate::biometric::SETTING_ENABLED, "false")
+        .await;
+}
+
 /// Lock the application
 #[tauri::command]
-pub async fn lock(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn lock(app_handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    drop_unlocked_state(&app_handle, &state).await;
     state
         .auth
         .lock()
@@ -106,14 +402,349 @@ pub async fn lock(state: State<'_, AppState>) -> Result<(), String> {
         .map_err(|e| format!("Lock failed: {}", e))
 }
 
+/// Drop everything that depends on the master key (before locking)
+async fn drop_unlocked_state(app_handle: &tauri::AppHandle, state: &AppState) {
+    // Last chance to save the scrollback of open terminals with the key
+    state.save_open_sessions().await;
+    state.clipboard.clear(app_handle).await;
+    state.shares.revoke_all().await;
+    state.sessions.clear_warm(app_handle).await;
+    state.sessions.stop_encrypted_recordings();
+    state.sessions.clear_quick_connections();
+}
+
+/// Change the master password, re-encrypting the vault
+#[tauri::command]
+pub async fn change_master_password(
+    old_password: String,
+    new_password: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let old_password = Zeroizing::new(old_password);
+    let new_password = Zeroizing::new(new_password);
+    // Recordings are re-encrypted with the vault: none may be written meanwhile
+    state.sessions.stop_encrypted_recordings();
+    let mut recordings = crate::recording::RecordingsRekey::default();
+    let mut restore = state.restore.rekey();
+    state
+        .auth
+        .change_master_password_with(
+            &old_password,
+            &new_password,
+            &mut [&mut recordings, &mut restore],
+        )
+        .await
+        .map_err(|e| format!("Failed to change master password: {}", e))?;
+
+    // The saved key belongs to the old password
+    if is_biometric_enabled(&state).await {
+        if let Err(e) = save_biometric_key(&state).await {
+            tracing::warn!("[commands.rs] Failed to update saved master key: {}", e);
+            disable_biometric(&state).await;
+        }
+    }
+
+    if let Err(e) = crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_MASTER_PASSWORD_CHANGED,
+        "vault re-encrypted",
+    )
+    .await
+    {
+        tracing::warn!("[commands.rs] Failed to record audit event: {}", e);
+    }
+    Ok(())
+}
+
+/// Time a master key derivation should take after calibration
+const KDF_CALIBRATION_TARGET: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Benchmark this machine and suggest KDF parameters (~500ms per unlock)
+///
+/// The suggestion is saved and used the next time the master password is
+/// changed; the current key is left as it is.
+#[tauri::command]
+pub async fn calibrate_kdf(state: State<'_, AppState>) -> Result<rite_crypto::KdfParams, String> {
+    let params = tokio::task::spawn_blocking(|| rite_crypto::calibrate_kdf(KDF_CALIBRATION_TARGET))
+        .await
+        .map_err(|e| format!("Failed to calibrate KDF: {}", e))?
+        .map_err(|e| format!("Failed to calibrate KDF: {}", e))?;
+
+    tracing::info!(
+        "[commands.rs] KDF calibrated: {} KiB, {} iterations, {} lanes",
+        params.memory_kib,
+        params.iterations,
+        params.parallelism
+    );
+
+    let value =
+        serde_json::to_string(&params).map_err(|e| format!("Failed to calibrate KDF: {}", e))?;
+    state
+        .db
+        .set_setting(rite_vault::auth::SETTING_KDF_PARAMS, &value)
+        .await
+        .map_err(|e| format!("Failed to save KDF parameters: {}", e))?;
+
+    state.events.settings(
+        crate::events::ChangeKind::Updated,
+        vec![rite_vault::auth::SETTING_KDF_PARAMS.to_string()],
+    );
+    Ok(params)
+}
+
+/// Get the status of pre-established (warm) connections
+#[tauri::command]
+pub async fn get_warm_connections(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::warm::WarmStatus>, String> {
+    Ok(state.sessions.warm_statuses().await)
+}
+
+/// Re-enter the master password to obtain a capability token for
+/// sensitive commands (valid for a few minutes, dropped on lock)
+#[tauri::command]
+pub async fn authorize_sensitive_action(
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    state
+        .auth
+        .authorize_sensitive_action(&password)
+        .await
+        .map_err(|e| format!("Authorization failed: {}", e))
+}
+
 /// Reset the database (EMERGENCY ONLY - deletes all data)
+///
+/// While unlocked this requires a capability token, while locked the master
+/// password. A backup of the vault is always written first.
+///
+/// An encrypted vault is set aside rather than emptied, and the application
+/// restarts to create a new one.
 #[tauri::command]
-pub async fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn reset_database(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    token: Option<String>,
+    password: Option<String>,
+) -> Result<(), String> {
+    authorize_recovery(&state, token, password)
+        .await
+        .map_err(|e| format!("Database reset failed: {}", e))?;
+
+    let encrypted = state.db.is_encrypted();
+    if encrypted {
+        drop_unlocked_state(&app_handle, &state).await;
+        // The database is closed by the reset, so settings can't be written
+        if let Err(e) = crate::biometric::remove() {
+            tracing::warn!("[commands.rs] Failed to delete saved master key: {}", e);
+        }
+    }
+
     state
         .auth
         .reset_database()
         .await
-        .map_err(|e| format!("Database reset failed: {}", e))
+        .map_err(|e| format!("Database reset failed: {}", e))?;
+
+    if encrypted {
+        tracing::info!("[commands.rs] Encrypted vault set aside, restarting");
+        app_handle.restart();
+    }
+
+    disable_biometric(&state).await;
+    state.connections.invalidate_cache();
+    Ok(())
+}
+
+/// Authorize a recovery action: the capability token while unlocked, the
+/// master password while locked
+async fn authorize_recovery(
+    state: &AppState,
+    token: Option<String>,
+    password: Option<String>,
+) -> anyhow::Result<()> {
+    if state.auth.is_locked().await {
+        let password = Zeroizing::new(password.unwrap_or_default());
+        state.auth.verify_while_locked(&password).await
+    } else {
+        state
+            .auth
+            .check_capability(token.as_deref().unwrap_or_default())
+            .await
+    }
+}
+
+/// Get the vault format and schema versions
+#[tauri::command]
+pub async fn get_vault_format(
+    state: State<'_, AppState>,
+) -> Result<rite_vault::VaultFormatInfo, String> {
+    rite_vault::format::info(&state.db)
+        .await
+        .map_err(|e| format!("Failed to read vault format: {}", e))
+}
+
+/// Write an encrypted backup of the vault (requires a capability token)
+#[tauri::command]
+pub async fn backup_vault(
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<rite_vault::BackupInfo, String> {
+    state
+        .auth
+        .check_capability(&token)
+        .await
+        .map_err(|e| format!("Failed to back up vault: {}", e))?;
+    let master_key = state
+        .auth
+        .get_master_key()
+        .await
+        .map_err(|e| format!("Failed to back up vault: {}", e))?;
+    rite_vault::backup::create(&state.db, &master_key, rite_vault::backup::LABEL_MANUAL)
+        .await
+        .map_err(|e| format!("Failed to back up vault: {}", e))
+}
+
+/// List vault backups, newest first
+#[tauri::command]
+pub async fn list_backups(
+    state: State<'_, AppState>,
+) -> Result<Vec<rite_vault::BackupInfo>, String> {
+    rite_vault::backup::list(&state.db)
+        .await
+        .map_err(|e| format!("Failed to list backups: {}", e))
+}
+
+/// Replace the vault with a backup, then lock
+///
+/// While unlocked this requires a capability token, while locked the
+/// current master password (`master_password`). `password` is the master
+/// password the backup was made with; it unlocks the vault from then on.
+#[tauri::command]
+pub async fn restore_vault(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    password: String,
+    token: Option<String>,
+    master_password: Option<String>,
+) -> Result<(), String> {
+    authorize_recovery(&state, token, master_password)
+        .await
+        .map_err(|e| format!("Restore failed: {}", e))?;
+
+    let password = Zeroizing::new(password);
+    rite_vault::backup::restore(&state.db, std::path::Path::new(&path), &password)
+        .await
+        .map_err(|e| format!("Restore failed: {}", e))?;
+
+    // The master key, the saved biometric key and the cached connections
+    // all belong to the replaced vault
+    drop_unlocked_state(&app_handle, &state).await;
+    state
+        .auth
+        .lock()
+        .await
+        .map_err(|e| format!("Lock failed: {}", e))?;
+    disable_biometric(&state).await;
+    state.connections.invalidate_cache();
+
+    if let Err(e) =
+        crate::audit::record(state.db.pool(), crate::audit::EVENT_VAULT_RESTORED, &path).await
+    {
+        tracing::warn!("[commands.rs] Failed to record audit event: {}", e);
+    }
+    Ok(())
+}
+
+/// Whether the whole vault file is encrypted (SQLCipher)
+#[tauri::command]
+pub async fn is_database_encrypted(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.db.is_encrypted())
+}
+
+/// Encrypt the whole vault file, then restart the application
+///
+/// Requires a capability token. The vault must be unlocked again after the
+/// restart.
+#[tauri::command]
+pub async fn encrypt_database(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<(), String> {
+    switch_database_encryption(app_handle, &state, &token, true)
+        .await
+        .map_err(|e| format!("Failed to encrypt database: {}", e))
+}
+
+/// Decrypt the vault file, then restart the application
+///
+/// Requires a capability token. The vault must be unlocked again after the
+/// restart.
+#[tauri::command]
+pub async fn decrypt_database(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<(), String> {
+    switch_database_encryption(app_handle, &state, &token, false)
+        .await
+        .map_err(|e| format!("Failed to decrypt database: {}", e))
+}
+
+async fn switch_database_encryption(
+    app_handle: tauri::AppHandle,
+    state: &AppState,
+    token: &str,
+    encrypt: bool,
+) -> anyhow::Result<()> {
+    state.auth.check_capability(token).await?;
+    let master_key = state.auth.get_master_key().await?;
+
+    // Recorded first: the database is closed by the switch
+    let details = if encrypt { "enabled" } else { "disabled" };
+    if let Err(e) = crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_DATABASE_ENCRYPTION,
+        details,
+    )
+    .await
+    {
+        tracing::warn!("[commands.rs] Failed to record audit event: {}", e);
+    }
+
+    drop_unlocked_state(&app_handle, state).await;
+    if encrypt {
+        rite_vault::encryption::enable(&state.db, &master_key).await?;
+    } else {
+        rite_vault::encryption::disable(&state.db, &master_key).await?;
+    }
+
+    tracing::info!("[commands.rs] Database encryption {}, restarting", details);
+    app_handle.restart();
+}
+
+/// List background tasks with their state, last run and last error
+#[tauri::command]
+pub async fn list_background_tasks(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::tasks::TaskStatus>, String> {
+    Ok(state.tasks.list().await)
+}
+
+/// Restart a background task by name
+#[tauri::command]
+pub async fn restart_background_task(
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<(), String> {
+    state
+        .tasks
+        .restart(&name)
+        .await
+        .map_err(|e| format!("Failed to restart background task: {}", e))
 }
 
 // ===== Connection Management Commands =====
@@ -156,316 +787,2160 @@ pub async fn get_connection(
     }
 }
 
-/// Update a connection
+/// Reveal a connection's stored credentials (re-enter master password)
 #[tauri::command]
-pub async fn update_connection(
+pub async fn reveal_credentials(
     state: State<'_, AppState>,
-    input: crate::connection::UpdateConnectionInput,
-) -> Result<crate::connection::ConnectionInfo, String> {
+    connection_id: String,
+    master_password: String,
+) -> Result<AuthMethod, String> {
     state
-        .connections
-        .update_connection(input)
+        .auth
+        .reauthenticate(&master_password)
         .await
-        .map_err(|e| format!("Failed to update connection: {}", e))
-}
+        .map_err(|e| format!("Failed to reveal credentials: {}", e))?;
 
-/// Delete a connection
-#[tauri::command]
-pub async fn delete_connection(state: State<'_, AppState>, id: String) -> Result<(), String> {
     state
         .connections
-        .delete_connection(&id)
+        .reveal_credentials(&connection_id)
         .await
-        .map_err(|e| format!("Failed to delete connection: {}", e))
+        .map_err(|e| format!("Failed to reveal credentials: {}", e))
 }
 
-/// Parse SSH config file and return entries for preview
+/// Store a connection's private key file inside the vault
+///
+/// The connection then authenticates with the stored key, which is
+/// encrypted with the master key and included in vault backups.
 #[tauri::command]
-pub async fn parse_ssh_config(
-    config_path: String,
-) -> Result<Vec<crate::ssh_config::SshConfigEntry>, String> {
-    crate::ssh_config::parse_ssh_config(&config_path)
-        .map_err(|e| format!("Failed to parse SSH config: {}", e))
+pub async fn import_private_key(
+    state: State<'_, AppState>,
+    connection_id: String,
+    key_path: String,
+    passphrase: Option<String>,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    state
+        .connections
+        .import_private_key(&connection_id, &key_path, passphrase)
+        .await
+        .map_err(|e| format!("Failed to import private key: {}", e))
 }
 
-/// Import selected SSH config entries as connections
+/// Write a vault-stored private key to a new file (requires a capability
+/// token)
 #[tauri::command]
-pub async fn import_ssh_config_entries(
+pub async fn export_private_key(
     state: State<'_, AppState>,
-    entries: Vec<crate::ssh_config::SshConfigEntry>,
-) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
-    let mut imported = Vec::new();
+    connection_id: String,
+    path: String,
+    token: String,
+) -> Result<(), String> {
+    state
+        .auth
+        .check_capability(&token)
+        .await
+        .map_err(|e| format!("Failed to export private key: {}", e))?;
 
-    for entry in entries {
-        let input = entry.to_connection_input();
-        match state.connections.create_connection(input).await {
-            Ok(info) => imported.push(info),
-            Err(e) => {
-                tracing::warn!(
-                    "[commands.rs] Failed to import entry '{}': {}",
-                    entry.host,
-                    e
-                );
-            }
+    state
+        .connections
+        .export_private_key(&connection_id, &path)
+        .await
+        .map_err(|e| format!("Failed to export private key: {}", e))
+}
+
+/// Copy a connection's password or key passphrase to the clipboard
+/// (requires a capability token)
+///
+/// The secret never reaches the webview and is cleared after
+/// `clipboard_clear_timeout` seconds or on lock. Every copy is audit-logged.
+#[tauri::command]
+pub async fn copy_secret_to_clipboard(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+    token: String,
+) -> Result<(), String> {
+    state
+        .auth
+        .check_capability(&token)
+        .await
+        .map_err(|e| format!("Failed to copy secret: {}", e))?;
+
+    let connection = state
+        .connections
+        .get_connection(&connection_id)
+        .await
+        .map_err(|e| format!("Failed to get connection: {}", e))?
+        .ok_or_else(|| "Connection not found".to_string())?;
+
+    let secret = match connection.auth_method {
+        AuthMethod::Password { password } if !password.is_empty() => Zeroizing::new(password),
+        AuthMethod::PublicKey {
+            passphrase: Some(passphrase),
+            ..
         }
+        | AuthMethod::StoredKey {
+            passphrase: Some(passphrase),
+            ..
+        } if !passphrase.is_empty() => Zeroizing::new(passphrase),
+        _ => return Err("Connection has no stored password or passphrase".to_string()),
+    };
+
+    let clear_after = state
+        .db
+        .get_setting("clipboard_clear_timeout")
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(crate::clipboard::DEFAULT_CLEAR_TIMEOUT_SECS);
+
+    state
+        .clipboard
+        .copy(
+            &app_handle,
+            &secret,
+            std::time::Duration::from_secs(clear_after),
+        )
+        .await
+        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+
+    let detail = format!("{} ({})", connection.name, connection.id);
+    if let Err(e) =
+        crate::audit::record(state.db.pool(), crate::audit::EVENT_SECRET_COPIED, &detail).await
+    {
+        tracing::error!("[commands.rs] Failed to write audit log entry: {}", e);
     }
+    Ok(())
+}
 
-    Ok(imported)
+/// Update a connection
+#[tauri::command]
+pub async fn update_connection(
+    state: State<'_, AppState>,
+    input: crate::connection::UpdateConnectionInput,
+) -> Result<crate::connection::ConnectionInfo, UpdateConnectionError> {
+    state
+        .connections
+        .update_connection(input)
+        .await
+        .map_err(
+            |e| match e.downcast_ref::<crate::connection::UpdateConflict>() {
+                Some(conflict) => UpdateConnectionError::Conflict {
+                    message: conflict.to_string(),
+                    current: conflict.current.to_info(),
+                },
+                None => UpdateConnectionError::Failed {
+                    message: format!("Failed to update connection: {}", e),
+                },
+            },
+        )
 }
 
-/// Get default SSH config path
+/// Copy a connection, credentials included (name defaults to "<name> (copy)")
 #[tauri::command]
-pub fn get_default_ssh_config_path() -> String {
-    crate::ssh_config::get_default_ssh_config_path()
+pub async fn duplicate_connection(
+    state: State<'_, AppState>,
+    id: String,
+    name: Option<String>,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    state
+        .connections
+        .duplicate_connection(&id, name)
+        .await
+        .map_err(|e| format!("Failed to duplicate connection: {}", e))
 }
 
-/// Get connections by folder
+/// Delete a connection (it goes to the trash and can be restored)
 #[tauri::command]
-pub async fn get_connections_by_folder(
+pub async fn delete_connection(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .connections
+        .delete_connection(&id)
+        .await
+        .map_err(|e| format!("Failed to delete connection: {}", e))
+}
+
+/// List the connections in the trash, most recently deleted first
+#[tauri::command]
+pub async fn list_trashed_connections(
     state: State<'_, AppState>,
-    folder: String,
 ) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
     state
         .connections
-        .get_connections_by_folder(&folder)
+        .list_trashed_connections()
         .await
-        .map_err(|e| format!("Failed to get connections by folder: {}", e))
+        .map_err(|e| format!("Failed to list trashed connections: {}", e))
 }
 
-/// Count saved connections (for UI badge)
+/// Take a connection out of the trash (undo delete)
 #[tauri::command]
-pub async fn count_saved_connections(state: State<'_, AppState>) -> Result<usize, String> {
-    match state.connections.get_all_connections().await {
-        Ok(connections) => Ok(connections.len()),
-        Err(e) => {
-            // If we can't access connections (e.g., DB not unlocked), return 0
-            tracing::debug!("[commands.rs] Failed to count connections: {}", e);
-            Ok(0)
+pub async fn restore_connection(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    state
+        .connections
+        .restore_connection(&id)
+        .await
+        .map_err(|e| format!("Failed to restore connection: {}", e))
+}
+
+/// Permanently delete the connections in the trash, returns their IDs
+#[tauri::command]
+pub async fn purge_trash(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state
+        .connections
+        .purge_trash()
+        .await
+        .map_err(|e| format!("Failed to empty the trash: {}", e))
+}
+
+/// Check a saved SSH connection stage by stage (DNS, TCP, banner, host
+/// key, authentication) without opening a shell
+#[tauri::command]
+pub async fn test_connection(
+    app_handle: tauri::AppHandle,
+    connection_id: String,
+) -> Result<crate::connection_test::ConnectionTestReport, String> {
+    crate::connection_test::run(&app_handle, &connection_id)
+        .await
+        .map_err(|e| format!("Failed to test connection: {}", e))
+}
+
+/// Get recent audit log entries (newest first)
+#[tauri::command]
+pub async fn get_audit_log(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<crate::audit::AuditEntry>, String> {
+    crate::audit::recent(state.db.pool(), limit.unwrap_or(100))
+        .await
+        .map_err(|e| format!("Failed to get audit log: {}", e))
+}
+
+// ===== Known Hosts Commands =====
+
+/// List the known hosts (stored SSH host keys)
+#[tauri::command]
+pub async fn list_known_hosts(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::known_hosts::KnownHost>, String> {
+    crate::known_hosts::list_known_hosts(state.db.pool())
+        .await
+        .map_err(|e| format!("Failed to list known hosts: {}", e))
+}
+
+/// Remove a known host (its key is asked about again on the next connection)
+#[tauri::command]
+pub async fn remove_known_host(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let removed = crate::known_hosts::remove_known_host(state.db.pool(), &id)
+        .await
+        .map_err(|e| format!("Failed to remove known host: {}", e))?
+        .ok_or_else(|| "Known host not found".to_string())?;
+
+    if let Err(e) = crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_KNOWN_HOST_REMOVED,
+        &format!("{}:{} {}", removed.host, removed.port, removed.fingerprint),
+    )
+    .await
+    {
+        tracing::error!("[commands.rs] Failed to write audit log entry: {}", e);
+    }
+    Ok(())
+}
+
+/// Accept the host key a connection was rejected for (changed key, or
+/// unknown host in strict mode)
+///
+/// `fingerprint` is the one shown to the user: the key is only stored if
+/// the server presented exactly that key. Replacing the key of a known host
+/// (accepting a possible man-in-the-middle) requires a capability token, and
+/// is audit-logged.
+#[tauri::command]
+pub async fn update_known_host(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+    fingerprint: String,
+    token: Option<String>,
+) -> Result<(), String> {
+    let previous = crate::known_hosts::get_known_host(state.db.pool(), &host, port)
+        .await
+        .map_err(|e| format!("Failed to update known host: {}", e))?;
+    if previous.is_some() {
+        state
+            .auth
+            .check_capability(token.as_deref().unwrap_or_default())
+            .await
+            .map_err(|e| format!("Failed to update known host: {}", e))?;
+    }
+
+    let pending = state
+        .pending_host_keys
+        .accept(&host, port, &fingerprint)
+        .await
+        .ok_or_else(|| "No pending host key with this fingerprint".to_string())?;
+
+    let key = russh::keys::PublicKey::from_bytes(&pending.public_key_data)
+        .map_err(|e| format!("Failed to update known host: {}", e))?;
+
+    crate::known_hosts::add_host_key(state.db.pool(), &host, port, &key)
+        .await
+        .map_err(|e| format!("Failed to update known host: {}", e))?;
+
+    if let Some(previous) = previous {
+        if let Err(e) = crate::audit::record(
+            state.db.pool(),
+            crate::audit::EVENT_HOST_KEY_REPLACED,
+            &format!(
+                "{}:{} {} {} -> {}",
+                host, port, pending.key_type, previous.fingerprint, pending.fingerprint
+            ),
+        )
+        .await
+        {
+            tracing::error!("[commands.rs] Failed to write audit log entry: {}", e);
         }
     }
+    Ok(())
 }
 
-// ============================================================================
-// Terminal Session Commands
-// ============================================================================
+/// Export the known hosts in OpenSSH `known_hosts` format
+#[tauri::command]
+pub async fn export_known_hosts(state: State<'_, AppState>) -> Result<String, String> {
+    crate::known_hosts::export_known_hosts(state.db.pool())
+        .await
+        .map_err(|e| format!("Failed to export known hosts: {}", e))
+}
 
-/// Create a new SSH terminal session
+/// Import an OpenSSH known_hosts file
+///
+/// Hashed host names are matched against the hosts of saved connections.
 #[tauri::command]
-pub async fn connect_terminal(
+pub async fn import_known_hosts(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<crate::known_hosts::KnownHostsImport, String> {
+    let entries = crate::openssh_known_hosts::parse_known_hosts(&path)
+        .map_err(|e| format!("Failed to import known hosts: {}", e))?;
+
+    let candidates: Vec<(String, u16)> = state
+        .connections
+        .get_all_connections()
+        .await
+        .map_err(|e| format!("Failed to import known hosts: {}", e))?
+        .into_iter()
+        .map(|connection| (connection.hostname.to_lowercase(), connection.port))
+        .collect();
+
+    crate::known_hosts::import_openssh(state.db.pool(), &entries, &candidates)
+        .await
+        .map_err(|e| format!("Failed to import known hosts: {}", e))
+}
+
+/// Get default OpenSSH known_hosts path
+#[tauri::command]
+pub fn get_default_known_hosts_path() -> String {
+    crate::openssh_known_hosts::get_default_known_hosts_path()
+}
+
+/// Get all connection templates
+#[tauri::command]
+pub async fn list_connection_templates(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::template::ConnectionTemplate>, String> {
+    state
+        .connections
+        .get_all_templates()
+        .await
+        .map_err(|e| format!("Failed to get connection templates: {}", e))
+}
+
+/// Create a connection template
+#[tauri::command]
+pub async fn create_connection_template(
+    state: State<'_, AppState>,
+    input: crate::template::CreateTemplateInput,
+) -> Result<crate::template::ConnectionTemplate, String> {
+    state
+        .connections
+        .create_template(input)
+        .await
+        .map_err(|e| format!("Failed to create connection template: {}", e))
+}
+
+/// Delete a connection template
+#[tauri::command]
+pub async fn delete_connection_template(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state
+        .connections
+        .delete_template(&id)
+        .await
+        .map_err(|e| format!("Failed to delete connection template: {}", e))
+}
+
+/// Make a connection template from an existing connection
+#[tauri::command]
+pub async fn create_template_from_connection(
     state: State<'_, AppState>,
     connection_id: String,
+    name: String,
+) -> Result<crate::template::ConnectionTemplate, String> {
+    state
+        .connections
+        .create_template_from_connection(&connection_id, name)
+        .await
+        .map_err(|e| format!("Failed to create connection template: {}", e))
+}
+
+/// Create a connection to a new host from a template (name defaults to the host)
+#[tauri::command]
+pub async fn create_from_template(
+    state: State<'_, AppState>,
+    template_id: String,
+    host: String,
+    name: Option<String>,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    state
+        .connections
+        .create_from_template(&template_id, &host, name)
+        .await
+        .map_err(|e| format!("Failed to create connection from template: {}", e))
+}
+
+/// Get all command snippets
+#[tauri::command]
+pub async fn list_snippets(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::snippets::Snippet>, String> {
+    state
+        .connections
+        .get_all_snippets()
+        .await
+        .map_err(|e| format!("Failed to get snippets: {}", e))
+}
+
+/// Create a command snippet
+#[tauri::command]
+pub async fn create_snippet(
+    state: State<'_, AppState>,
+    input: crate::snippets::CreateSnippetInput,
+) -> Result<crate::snippets::Snippet, String> {
+    state
+        .connections
+        .create_snippet(input)
+        .await
+        .map_err(|e| format!("Failed to create snippet: {}", e))
+}
+
+/// Update a command snippet
+#[tauri::command]
+pub async fn update_snippet(
+    state: State<'_, AppState>,
+    input: crate::snippets::UpdateSnippetInput,
+) -> Result<crate::snippets::Snippet, String> {
+    state
+        .connections
+        .update_snippet(input)
+        .await
+        .map_err(|e| format!("Failed to update snippet: {}", e))
+}
+
+/// Delete a command snippet
+#[tauri::command]
+pub async fn delete_snippet(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .connections
+        .delete_snippet(&id)
+        .await
+        .map_err(|e| format!("Failed to delete snippet: {}", e))
+}
+
+/// Variables a snippet asks for before it runs
+#[tauri::command]
+pub async fn get_snippet_variables(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<String>, String> {
+    let snippet = state
+        .connections
+        .get_snippet(&id)
+        .await
+        .map_err(|e| format!("Failed to get snippet: {}", e))?;
+    Ok(crate::snippets::variables(&snippet.command))
+}
+
+/// Fill in a snippet's variables and type it into one or more sessions
+///
+/// With `execute`, Enter is pressed after the command. Returns the outcome
+/// for each session.
+#[tauri::command]
+pub async fn run_snippet(
+    state: State<'_, AppState>,
+    id: String,
+    variables: std::collections::HashMap<String, String>,
+    session_ids: Vec<String>,
+    execute: bool,
+) -> Result<Vec<crate::snippets::SnippetDelivery>, String> {
+    let snippet = state
+        .connections
+        .get_snippet(&id)
+        .await
+        .map_err(|e| format!("Failed to get snippet: {}", e))?;
+    let command = crate::snippets::render(&snippet.command, &variables)
+        .map_err(|e| format!("Failed to run snippet: {}", e))?;
+    Ok(crate::snippets::run(&state.sessions, &command, &session_ids, execute).await)
+}
+
+/// Run a command on an SSH connection over an exec channel (no PTY)
+///
+/// stdout and stderr are returned separately with the exit status; a
+/// command still running after `timeout_secs` (default 60) is stopped.
+/// Dangerous commands are refused on protected connections, and every run
+/// is audit-logged.
+#[tauri::command]
+pub async fn exec_command(
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    tracing::info!(
-        "[commands.rs] connect_terminal called with connection_id: {}",
-        connection_id
-    );
+    state: State<'_, AppState>,
+    connection_id: String,
+    command: String,
+    timeout_secs: Option<u64>,
+) -> Result<crate::exec::ExecOutput, String> {
+    let timeout = crate::exec::timeout_from_secs(timeout_secs)
+        .map_err(|e| format!("Failed to run command: {}", e))?;
+    let result =
+        crate::exec::exec_command(&app_handle, &connection_id, &command, timeout, false).await;
+
+    if let Err(e) = crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_COMMAND_EXECUTED,
+        &crate::exec::audit_detail(&connection_id, &command, &result),
+    )
+    .await
+    {
+        tracing::warn!("[commands.rs] Failed to record audit event: {}", e);
+    }
+
+    result.map_err(|e| format!("Failed to run command: {}", e))
+}
+
+/// Get all remote tasks
+#[tauri::command]
+pub async fn list_remote_tasks(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::remote_tasks::RemoteTask>, String> {
+    state
+        .connections
+        .get_all_remote_tasks()
+        .await
+        .map_err(|e| format!("Failed to get remote tasks: {}", e))
+}
+
+/// Create a remote task
+#[tauri::command]
+pub async fn create_remote_task(
+    state: State<'_, AppState>,
+    input: crate::remote_tasks::CreateRemoteTaskInput,
+) -> Result<crate::remote_tasks::RemoteTask, String> {
+    crate::remote_tasks::validate(
+        Some(&input.name),
+        Some(&input.command),
+        Some(&input.connection_ids),
+        input.schedule.as_deref(),
+    )
+    .map_err(|e| format!("Failed to create remote task: {}", e))?;
+    state
+        .connections
+        .create_remote_task(input)
+        .await
+        .map_err(|e| format!("Failed to create remote task: {}", e))
+}
+
+/// Update a remote task
+#[tauri::command]
+pub async fn update_remote_task(
+    state: State<'_, AppState>,
+    input: crate::remote_tasks::UpdateRemoteTaskInput,
+) -> Result<crate::remote_tasks::RemoteTask, String> {
+    crate::remote_tasks::validate(
+        input.name.as_deref(),
+        input.command.as_deref(),
+        input.connection_ids.as_deref(),
+        input.schedule.as_deref(),
+    )
+    .map_err(|e| format!("Failed to update remote task: {}", e))?;
+    state
+        .connections
+        .update_remote_task(input)
+        .await
+        .map_err(|e| format!("Failed to update remote task: {}", e))
+}
+
+/// Delete a remote task and its runs
+#[tauri::command]
+pub async fn delete_remote_task(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .connections
+        .delete_remote_task(&id)
+        .await
+        .map_err(|e| format!("Failed to delete remote task: {}", e))
+}
+
+/// Run a remote task now on all its connections
+///
+/// Returns the connections it was started on (not those where it is still
+/// running); each run ends with a `remote-task-completed` event.
+#[tauri::command]
+pub async fn run_remote_task(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<String>, String> {
+    let task = state
+        .connections
+        .get_remote_task(&id)
+        .await
+        .map_err(|e| format!("Failed to run remote task: {}", e))?;
+    Ok(state.remote_tasks.start(&app_handle, &task, false))
+}
+
+/// Latest runs of a remote task (with their output), most recent first
+#[tauri::command]
+pub async fn list_remote_task_runs(
+    state: State<'_, AppState>,
+    id: String,
+    limit: Option<i64>,
+) -> Result<Vec<crate::remote_tasks::RemoteTaskRun>, String> {
+    state
+        .connections
+        .get_remote_task_runs(&id, limit.unwrap_or(20))
+        .await
+        .map_err(|e| format!("Failed to get remote task runs: {}", e))
+}
+
+/// Health metrics of hosts (uptime, load, memory, disks, users)
+///
+/// The last metrics are returned unless `refresh` is set; hosts without
+/// any are probed now.
+#[tauri::command]
+pub async fn get_host_metrics(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    connection_ids: Vec<String>,
+    refresh: bool,
+) -> Result<Vec<crate::monitoring::HostMetrics>, String> {
+    state
+        .auth
+        .get_master_key()
+        .await
+        .map_err(|e| format!("Failed to get host metrics: {}", e))?;
+    Ok(state
+        .monitor
+        .metrics(&app_handle, &connection_ids, refresh)
+        .await)
+}
+
+/// Probe hosts every `interval_secs` (replacing the previous selection);
+/// each round is sent as a `host-metrics` event
+#[tauri::command]
+pub async fn start_host_monitoring(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    connection_ids: Vec<String>,
+    interval_secs: u64,
+) -> Result<(), String> {
+    let interval = std::time::Duration::from_secs(interval_secs);
+    crate::monitoring::validate(&connection_ids, interval)
+        .map_err(|e| format!("Failed to start monitoring: {}", e))?;
+
+    state.monitor.watch(connection_ids);
+    let monitor = state.monitor.clone();
+    let auth = std::sync::Arc::clone(&state.auth);
+    state
+        .tasks
+        .spawn_periodic(crate::monitoring::TASK_NAME, interval, move || {
+            let monitor = monitor.clone();
+            let auth = std::sync::Arc::clone(&auth);
+            let app_handle = app_handle.clone();
+            async move {
+                // Credentials are needed to connect: skip rounds while locked
+                if auth.get_master_key().await.is_ok() {
+                    monitor.run_round(&app_handle).await;
+                }
+                Ok(())
+            }
+        })
+        .await;
+    Ok(())
+}
+
+/// Stop periodic host monitoring
+#[tauri::command]
+pub async fn stop_host_monitoring(state: State<'_, AppState>) -> Result<(), String> {
+    state.tasks.stop(crate::monitoring::TASK_NAME).await;
+    state.monitor.watch(Vec::new());
+    Ok(())
+}
+
+/// Parse SSH config file and return entries for preview
+#[tauri::command]
+pub async fn parse_ssh_config(
+    config_path: String,
+) -> Result<Vec<crate::ssh_config::SshConfigEntry>, String> {
+    crate::ssh_config::parse_ssh_config(&config_path)
+        .map_err(|e| format!("Failed to parse SSH config: {}", e))
+}
+
+/// Import selected SSH config entries as connections (with their
+/// ProxyJump hops as jump hosts)
+#[tauri::command]
+pub async fn import_ssh_config_entries(
+    state: State<'_, AppState>,
+    entries: Vec<crate::ssh_config::SshConfigEntry>,
+) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
+    let imported = state
+        .connections
+        .import_ssh_config(&entries)
+        .await
+        .map_err(|e| format!("Failed to import SSH config: {}", e))?;
+
+    // Linked for SSH config sync
+    if let Err(e) = crate::ssh_config_sync::record_import(&state.db, &entries, &imported).await {
+        tracing::warn!("[commands.rs] Failed to record SSH config import: {}", e);
+    }
+    Ok(imported)
+}
+
+/// Changes between ~/.ssh/config and the connections imported from it
+#[tauri::command]
+pub async fn get_ssh_config_sync_suggestions(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::ssh_config_sync::SyncSuggestion>, String> {
+    crate::ssh_config_sync::suggestions(&state.db, &state.connections)
+        .await
+        .map_err(|e| format!("Failed to check SSH config: {}", e))
+}
+
+/// Apply SSH config sync suggestions (add, update or remove connections)
+#[tauri::command]
+pub async fn apply_ssh_config_sync(
+    state: State<'_, AppState>,
+    suggestions: Vec<crate::ssh_config_sync::SyncSuggestion>,
+) -> Result<(), String> {
+    crate::ssh_config_sync::apply(&state.db, &state.connections, &suggestions)
+        .await
+        .map_err(|e| format!("Failed to sync SSH config: {}", e))
+}
+
+/// Dismiss SSH config sync suggestions without changing the connections
+#[tauri::command]
+pub async fn dismiss_ssh_config_sync(
+    state: State<'_, AppState>,
+    suggestions: Vec<crate::ssh_config_sync::SyncSuggestion>,
+) -> Result<(), String> {
+    crate::ssh_config_sync::dismiss(&state.db, &suggestions)
+        .await
+        .map_err(|e| format!("Failed to dismiss SSH config changes: {}", e))
+}
+
+/// Read the sessions saved by another SSH client for preview
+#[tauri::command]
+pub async fn preview_import(
+    source: crate::importers::ImportSource,
+    path: String,
+) -> Result<Vec<crate::importers::ImportEntry>, String> {
+    crate::importers::parse_import(source, &path)
+        .map_err(|e| format!("Failed to read {} sessions: {}", source.label(), e))
+}
+
+/// Import selected sessions from another SSH client as connections
+#[tauri::command]
+pub async fn run_import(
+    state: State<'_, AppState>,
+    entries: Vec<crate::importers::ImportEntry>,
+) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
+    let mut imported = Vec::new();
+
+    for entry in entries {
+        let input = crate::connection::CreateConnectionInput::from(&entry);
+        match state.connections.create_connection(input).await {
+            Ok(info) => imported.push(info),
+            Err(e) => {
+                tracing::warn!(
+                    "[commands.rs] Failed to import {} session '{}': {}",
+                    entry.source.label(),
+                    entry.name,
+                    e
+                );
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Get default SSH config path
+#[tauri::command]
+pub fn get_default_ssh_config_path() -> String {
+    crate::ssh_config::get_default_ssh_config_path()
+}
+
+/// Get the OpenSSH user certificate next to a key file (`<key>-cert.pub`)
+///
+/// Returns None if the key has no certificate.
+#[tauri::command]
+pub fn get_key_certificate(
+    key_path: String,
+) -> Result<Option<crate::key_format::CertificateInfo>, String> {
+    crate::key_format::certificate_info(&key_path)
+        .map_err(|e| format!("Failed to read certificate: {:#}", e))
+}
+
+/// Convert a private key file (e.g. PuTTY .ppk) to another format
+///
+/// Returns the path of the converted key, written next to the original.
+#[tauri::command]
+pub fn convert_key(
+    path: String,
+    target_format: String,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    let target = crate::key_format::KeyFormat::from_str(&target_format)
+        .map_err(|e| format!("Failed to convert key: {}", e))?;
+    crate::key_format::convert_key(std::path::Path::new(&path), target, passphrase.as_deref())
+        .map(|output| output.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to convert key: {}", e))
+}
+
+/// Where a generated private key goes
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum KeyOutput {
+    /// `path` and `path.pub`, never overwriting existing files
+    File { path: String },
+    /// Stored in the vault as the connection's key
+    #[serde(rename_all = "camelCase")]
+    Vault { connection_id: String },
+}
+
+/// Generated key, as shown to the user
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedKeyInfo {
+    pub public_key: String,
+    pub fingerprint: String,
+    /// Private key file (file output only)
+    pub path: Option<String>,
+}
+
+/// Generate a new SSH keypair and return its public key
+#[tauri::command]
+pub async fn generate_ssh_key(
+    state: State<'_, AppState>,
+    key_type: Option<crate::keygen::KeyType>,
+    comment: Option<String>,
+    passphrase: Option<String>,
+    output: KeyOutput,
+) -> Result<GeneratedKeyInfo, String> {
+    let key_type = key_type.unwrap_or_default();
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let key_passphrase = passphrase.clone();
+    // RSA-4096 takes a few seconds
+    let key = tokio::task::spawn_blocking(move || {
+        crate::keygen::generate(key_type, comment.as_deref(), key_passphrase.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Failed to generate key: {}", e))?
+    .map_err(|e| format!("Failed to generate key: {}", e))?;
+
+    let path = match output {
+        KeyOutput::File { path } => {
+            let written = crate::keygen::write_key_files(&key, std::path::Path::new(&path))
+                .map_err(|e| format!("Failed to save key: {}", e))?;
+            Some(written.to_string_lossy().to_string())
+        }
+        KeyOutput::Vault { connection_id } => {
+            state
+                .connections
+                .store_private_key(&connection_id, &key.private_key, passphrase)
+                .await
+                .map_err(|e| format!("Failed to save key: {}", e))?;
+            None
+        }
+    };
+
+    tracing::info!(
+        "[commands.rs] Generated {:?} key {}",
+        key_type,
+        key.fingerprint
+    );
+    Ok(GeneratedKeyInfo {
+        public_key: key.public_key,
+        fingerprint: key.fingerprint,
+        path,
+    })
+}
+
+/// Search connections by name, hostname, username, notes, folder and tags
+/// (every word matches a word start), best matches first
+#[tauri::command]
+pub async fn search_connections(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
+    state
+        .connections
+        .search_connections(&query, limit)
+        .await
+        .map_err(|e| format!("Failed to search connections: {}", e))
+}
+
+/// Get the connections in a folder (and its subfolders if `recursive`)
+#[tauri::command]
+pub async fn get_connections_by_folder(
+    state: State<'_, AppState>,
+    folder_id: String,
+    recursive: Option<bool>,
+) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
+    state
+        .connections
+        .get_connections_by_folder(&folder_id, recursive.unwrap_or(false))
+        .await
+        .map_err(|e| format!("Failed to get connections by folder: {}", e))
+}
+
+/// Move a connection to a folder (no folder = top level)
+#[tauri::command]
+pub async fn move_connection_to_folder(
+    state: State<'_, AppState>,
+    id: String,
+    folder_id: Option<String>,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    state
+        .connections
+        .move_connection_to_folder(&id, folder_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to move connection: {}", e))
+}
+
+/// List all folders (flat, in display order)
+#[tauri::command]
+pub async fn list_folders(state: State<'_, AppState>) -> Result<Vec<rite_vault::Folder>, String> {
+    state
+        .connections
+        .list_folders()
+        .await
+        .map_err(|e| format!("Failed to list folders: {}", e))
+}
+
+/// Get the folder tree, with paths and connection counts
+#[tauri::command]
+pub async fn get_folder_tree(
+    state: State<'_, AppState>,
+) -> Result<Vec<rite_vault::FolderNode>, String> {
+    state
+        .connections
+        .folder_tree()
+        .await
+        .map_err(|e| format!("Failed to get folder tree: {}", e))
+}
+
+/// Create a folder
+#[tauri::command]
+pub async fn create_folder(
+    state: State<'_, AppState>,
+    input: rite_vault::CreateFolderInput,
+) -> Result<rite_vault::Folder, String> {
+    state
+        .connections
+        .create_folder(input)
+        .await
+        .map_err(|e| format!("Failed to create folder: {}", e))
+}
+
+/// Rename, recolor or reorder a folder
+#[tauri::command]
+pub async fn update_folder(
+    state: State<'_, AppState>,
+    input: rite_vault::UpdateFolderInput,
+) -> Result<rite_vault::Folder, String> {
+    state
+        .connections
+        .update_folder(input)
+        .await
+        .map_err(|e| format!("Failed to update folder: {}", e))
+}
+
+/// Move a folder under another one (no parent = top level)
+#[tauri::command]
+pub async fn move_folder(
+    state: State<'_, AppState>,
+    id: String,
+    parent_id: Option<String>,
+    sort_order: Option<i64>,
+) -> Result<rite_vault::Folder, String> {
+    state
+        .connections
+        .move_folder(&id, parent_id.as_deref(), sort_order)
+        .await
+        .map_err(|e| format!("Failed to move folder: {}", e))
+}
+
+/// Delete a folder; its connections and subfolders are moved to `move_to`
+/// (no folder = top level), never deleted
+#[tauri::command]
+pub async fn delete_folder(
+    state: State<'_, AppState>,
+    id: String,
+    move_to: Option<String>,
+) -> Result<(), String> {
+    state
+        .connections
+        .delete_folder(&id, move_to.as_deref())
+        .await
+        .map_err(|e| format!("Failed to delete folder: {}", e))
+}
+
+/// List all tags, with their connection counts
+#[tauri::command]
+pub async fn list_tags(state: State<'_, AppState>) -> Result<Vec<rite_vault::Tag>, String> {
+    state
+        .connections
+        .list_tags()
+        .await
+        .map_err(|e| format!("Failed to list tags: {}", e))
+}
+
+/// Create a tag
+#[tauri::command]
+pub async fn create_tag(
+    state: State<'_, AppState>,
+    input: rite_vault::CreateTagInput,
+) -> Result<rite_vault::Tag, String> {
+    state
+        .connections
+        .create_tag(input)
+        .await
+        .map_err(|e| format!("Failed to create tag: {}", e))
+}
+
+/// Rename or recolor a tag
+#[tauri::command]
+pub async fn update_tag(
+    state: State<'_, AppState>,
+    input: rite_vault::UpdateTagInput,
+) -> Result<rite_vault::Tag, String> {
+    state
+        .connections
+        .update_tag(input)
+        .await
+        .map_err(|e| format!("Failed to update tag: {}", e))
+}
+
+/// Delete a tag (its connections are kept, without it)
+#[tauri::command]
+pub async fn delete_tag(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state
+        .connections
+        .delete_tag(&id)
+        .await
+        .map_err(|e| format!("Failed to delete tag: {}", e))
+}
+
+/// Get the connections tagged with all (`mode` "all", the default) or any
+/// ("any") of `tags`
+#[tauri::command]
+pub async fn search_connections_by_tags(
+    state: State<'_, AppState>,
+    tags: Vec<String>,
+    mode: Option<rite_vault::TagMatch>,
+) -> Result<Vec<crate::connection::ConnectionInfo>, String> {
+    state
+        .connections
+        .search_connections_by_tags(&tags, mode.unwrap_or_default())
+        .await
+        .map_err(|e| format!("Failed to search connections by tags: {}", e))
+}
+
+/// Count saved connections (for UI badge)
+#[tauri::command]
+pub async fn count_saved_connections(state: State<'_, AppState>) -> Result<usize, String> {
+    match state.connections.count_connections().await {
+        Ok(count) => Ok(count),
+        Err(e) => {
+            // If we can't access connections (e.g., DB not unlocked), return 0
+            tracing::debug!("[commands.rs] Failed to count connections: {}", e);
+            Ok(0)
+        }
+    }
+}
+
+/// Usage statistics of a connection (sessions, time connected, bytes)
+#[tauri::command]
+pub async fn get_connection_stats(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<rite_vault::ConnectionStats, String> {
+    state
+        .connections
+        .get_connection_stats(&id)
+        .await
+        .map_err(|e| format!("Failed to get connection stats: {}", e))
+}
+
+/// Latest sessions of a connection, most recent first
+#[tauri::command]
+pub async fn get_connection_history(
+    state: State<'_, AppState>,
+    id: String,
+    limit: Option<i64>,
+) -> Result<Vec<rite_vault::SessionRecord>, String> {
+    state
+        .connections
+        .get_connection_history(&id, limit.unwrap_or(50))
+        .await
+        .map_err(|e| format!("Failed to get connection history: {}", e))
+}
+
+/// Connections with the most sessions in the last `days` days (default 30)
+#[tauri::command]
+pub async fn get_most_used_connections(
+    state: State<'_, AppState>,
+    days: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<rite_vault::ConnectionUsage>, String> {
+    state
+        .connections
+        .get_most_used_connections(days.unwrap_or(30), limit.unwrap_or(10))
+        .await
+        .map_err(|e| format!("Failed to get most used connections: {}", e))
+}
+
+/// Connections by their last session, most recent first
+#[tauri::command]
+pub async fn get_recent_connections(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<rite_vault::ConnectionUsage>, String> {
+    state
+        .connections
+        .get_recent_connections(limit.unwrap_or(10))
+        .await
+        .map_err(|e| format!("Failed to get recent connections: {}", e))
+}
+
+// ============================================================================
+// Terminal Session Commands
+// ============================================================================
+
+/// Create a new SSH terminal session
+#[tauri::command]
+pub async fn connect_terminal(
+    state: State<'_, AppState>,
+    connection_id: String,
+    app_handle: tauri::AppHandle,
+    debug: Option<bool>,
+) -> Result<String, String> {
+    tracing::info!(
+        "[commands.rs] connect_terminal called with connection_id: {}",
+        connection_id
+    );
+
+    match state
+        .sessions
+        .create_session(connection_id.clone(), app_handle, debug.unwrap_or(false))
+        .await
+    {
+        Ok(session_id) => {
+            tracing::info!("[commands.rs] Session created successfully: {}", session_id);
+            Ok(session_id)
+        }
+        Err(e) => {
+            tracing::error!(
+                "[commands.rs] Failed to create session for connection {}: {}",
+                connection_id,
+                e
+            );
+            Err(format!("Failed to connect: {}", e))
+        }
+    }
+}
+
+/// Create a new local terminal session (with the environment variables of
+/// `connection_id` if set, in the `cwd` directory if it exists)
+#[tauri::command]
+pub async fn connect_local_terminal(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    shell: Option<String>,
+    connection_id: Option<String>,
+    cwd: Option<String>,
+) -> Result<String, String> {
+    tracing::info!("[commands.rs] connect_local_terminal called");
+
+    match state
+        .sessions
+        .create_local_session(app_handle, shell, connection_id, cwd)
+        .await
+    {
+        Ok(session_id) => {
+            tracing::info!(
+                "[commands.rs] Local session created successfully: {}",
+                session_id
+            );
+            Ok(session_id)
+        }
+        Err(e) => {
+            tracing::error!("[commands.rs] Failed to create local session: {}", e);
+            Err(format!("Failed to create local terminal: {}", e))
+        }
+    }
+}
+
+/// Check which shells are installed on the system
+#[tauri::command]
+pub fn get_installed_shells(shells: Vec<String>) -> Vec<String> {
+    tracing::debug!("[commands.rs] Checking installed shells: {:?}", shells);
+
+    shells
+        .into_iter()
+        .filter(|shell_path| {
+            let exists = std::path::Path::new(shell_path).exists();
+            tracing::debug!("[commands.rs] Shell {} exists: {}", shell_path, exists);
+            exists
+        })
+        .collect()
+}
+
+/// Quick SSH authentication method (credentials not encrypted)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum QuickAuthMethod {
+    Password {
+        password: String,
+    },
+    PublicKey {
+        key_path: String,
+        passphrase: Option<String>,
+    },
+}
+
+impl From<QuickAuthMethod> for AuthMethod {
+    fn from(quick: QuickAuthMethod) -> Self {
+        match quick {
+            QuickAuthMethod::Password { password } => AuthMethod::Password { password },
+            QuickAuthMethod::PublicKey {
+                key_path,
+                passphrase,
+            } => AuthMethod::PublicKey {
+                key_path,
+                passphrase,
+            },
+        }
+    }
+}
+
+/// Quick SSH connect (credentials not saved, no unlock required)
+///
+/// For ad-hoc SSH connections without saving credentials to vault.
+/// Host keys are verified per the global verification mode, with the same
+/// unknown/changed key prompts as saved connections. Passing
+/// `accept_host_key_once = true` accepts the server's key for this session
+/// only, without saving it to known_hosts; it is audit-logged.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn quick_ssh_connect(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+    username: String,
+    auth_method: QuickAuthMethod,
+    app_handle: tauri::AppHandle,
+    accept_host_key_once: Option<bool>,
+    debug: Option<bool>,
+) -> Result<String, String> {
+    tracing::info!(
+        "[commands.rs] quick_ssh_connect called for {}@{}:{}",
+        username,
+        host,
+        port
+    );
+
+    let accept_host_key_once = accept_host_key_once.unwrap_or(false);
+    if accept_host_key_once && !state.policy.allows_quick_ssh_force_accept() {
+        return Err(
+            "Accepting unverified host keys is disabled by organization policy".to_string(),
+        );
+    }
+
+    // Build a temporary Connection object (not saved to DB)
+    let connection = Connection {
+        id: format!("quick-{}", uuid::Uuid::new_v4()),
+        name: format!("{}@{}", username, host),
+        protocol: crate::connection::Protocol::SSH,
+        hostname: host,
+        port,
+        username,
+        auth_method: auth_method.clone().into(),
+        proxy_password: None,
+        metadata: crate::connection::ConnectionMetadata {
+            color: None,
+            icon: Some("⚡".to_string()), // Quick connect indicator
+            folder_id: None,
+            folder: None,
+            notes: Some("Quick connect (not saved)".to_string()),
+            tags: Vec::new(),
+        },
+        ssh_keep_alive_override: None,
+        ssh_keep_alive_interval: None,
+        session_options: Default::default(),
+        revision: 1,
+        last_used_at: None,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64,
+        updated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64,
+    };
+
+    // Create SSH session directly (no database, no encryption needed)
+    match state
+        .sessions
+        .create_quick_ssh_session(
+            connection,
+            auth_method.into(),
+            app_handle,
+            accept_host_key_once,
+            debug.unwrap_or(false),
+        )
+        .await
+    {
+        Ok(session_id) => {
+            tracing::info!("[commands.rs] Quick SSH session created: {}", session_id);
+            Ok(session_id)
+        }
+        Err(e) => {
+            tracing::error!("[commands.rs] Failed to create quick SSH session: {}", e);
+            Err(format!("Failed to connect: {}", e))
+        }
+    }
+}
+
+/// Metadata for saving a Quick SSH session as a connection
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickSessionMetadata {
+    /// Defaults to user@host
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder_id: Option<String>,
+    pub folder: Option<String>, // Folder path, created if missing (when no folder_id)
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Answer a keyboard-interactive prompt (`ssh:auth-prompt` event) while
+/// connecting; no answers cancels the authentication
+#[tauri::command]
+pub async fn answer_auth_prompt(
+    state: State<'_, AppState>,
+    prompt_id: String,
+    answers: Option<Vec<String>>,
+) -> Result<(), String> {
+    state
+        .auth_prompts
+        .answer(&prompt_id, answers)
+        .map_err(|e| format!("Failed to answer prompt: {}", e))
+}
+
+/// Save a live Quick SSH session as a connection
+///
+/// Host, port, user and credentials are taken from the session; credentials
+/// are encrypted with the master key, so the vault must be unlocked. Locking
+/// wipes them: a session started before can't be saved afterwards.
+#[tauri::command]
+pub async fn save_quick_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    metadata: QuickSessionMetadata,
+) -> Result<crate::connection::ConnectionInfo, String> {
+    if state.auth.is_locked().await {
+        return Err("Vault is locked: unlock to save the connection".to_string());
+    }
+
+    let connection = state
+        .sessions
+        .take_quick_connection(&session_id)
+        .map_err(|e| format!("Failed to save session: {}", e))?
+        .ok_or_else(|| "Session is not an unsaved Quick SSH session".to_string())?;
+
+    let input = crate::connection::CreateConnectionInput {
+        name: metadata.name.unwrap_or_else(|| connection.name.clone()),
+        protocol: connection.protocol.as_str().to_string(),
+        hostname: connection.hostname.clone(),
+        port: connection.port,
+        username: connection.username.clone(),
+        auth_method: connection.auth_method.clone(),
+        proxy_password: connection.proxy_password.clone(),
+        color: metadata.color,
+        icon: metadata.icon,
+        folder_id: metadata.folder_id,
+        folder: metadata.folder,
+        notes: metadata.notes,
+        tags: metadata.tags,
+        ssh_keep_alive_override: None,
+        ssh_keep_alive_interval: None,
+        session_options: None,
+    };
+    let saved = match state.connections.create_connection(input).await {
+        Ok(saved) => saved,
+        Err(e) => {
+            // Saving can be tried again, unless the vault was locked meanwhile
+            if !state.auth.is_locked().await {
+                if let Err(e) = state
+                    .sessions
+                    .restore_quick_connection(&session_id, connection)
+                {
+                    tracing::warn!("[commands.rs] Failed to keep Quick SSH details: {}", e);
+                }
+            }
+            return Err(format!("Failed to save session: {}", e));
+        }
+    };
+    tracing::info!(
+        "[commands.rs] Quick SSH session {} saved as connection {}",
+        session_id,
+        saved.id
+    );
+    Ok(saved)
+}
+
+/// Send input to a terminal session
+#[tauri::command]
+pub async fn send_terminal_input(
+    state: State<'_, AppState>,
+    session_id: String,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    state
+        .sessions
+        .send_input(&session_id, data)
+        .await
+        .map_err(|e| format!("Failed to send input: {}", e))
+}
+
+/// Type a stored secret of the session's connection (password, key
+/// passphrase or proxy password) into the terminal, followed by Enter
+///
+/// The secret is decrypted and written on the backend side: it never
+/// crosses IPC.
+#[tauri::command]
+pub async fn send_stored_secret(
+    state: State<'_, AppState>,
+    session_id: String,
+    kind: crate::connection::SecretKind,
+) -> Result<(), String> {
+    state
+        .sessions
+        .send_stored_secret(&session_id, kind)
+        .await
+        .map_err(|e| format!("Failed to send secret: {}", e))
+}
+
+/// Resize a terminal session
+#[tauri::command]
+pub async fn resize_terminal(
+    state: State<'_, AppState>,
+    session_id: String,
+    cols: u32,
+    rows: u32,
+) -> Result<(), String> {
+    state
+        .sessions
+        .resize_terminal(&session_id, cols, rows)
+        .await
+        .map_err(|e| format!("Failed to resize terminal: {}", e))
+}
+
+/// Close a terminal session
+#[tauri::command]
+pub async fn disconnect_terminal(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    state.shares.revoke(&session_id).await;
+    state.forwards.stop_session(&session_id).await;
+    state
+        .sessions
+        .close_session(&session_id)
+        .await
+        .map_err(|e| format!("Failed to disconnect: {}", e))
+}
+
+/// Share a terminal session as a read-only live view
+///
+/// Opt-in (`session_sharing_enabled`); served on this machine only unless
+/// `session_sharing_lan` is set. Returns the one-time URL to hand to the
+/// viewer; sharing the session again replaces it.
+#[tauri::command]
+pub async fn share_session(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<crate::share::ShareInfo, String> {
+    let enabled = state
+        .db
+        .get_setting(crate::share::SETTING_ENABLED)
+        .await
+        .map_err(|e| format!("Failed to share session: {}", e))?;
+    if enabled.as_deref() != Some("true") {
+        return Err("Failed to share session: session sharing is disabled".to_string());
+    }
+
+    let lan = state
+        .db
+        .get_setting(crate::share::SETTING_LAN)
+        .await
+        .map_err(|e| format!("Failed to share session: {}", e))?
+        .as_deref()
+        == Some("true");
+
+    let screen = state
+        .sessions
+        .session_screen(&session_id)
+        .map_err(|e| format!("Failed to share session: {}", e))?;
+    let share = state
+        .shares
+        .share(&app_handle, &session_id, screen, lan)
+        .await
+        .map_err(|e| format!("Failed to share session: {}", e))?;
+
+    crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_SESSION_SHARED,
+        &format!("session {}", session_id),
+    )
+    .await
+    .map_err(|e| format!("Failed to share session: {}", e))?;
+
+    Ok(share)
+}
+
+/// Stop sharing a terminal session (viewers are disconnected)
+#[tauri::command]
+pub async fn stop_sharing_session(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    state.shares.revoke(&session_id).await;
+    Ok(())
+}
+
+/// List shared sessions with their viewer counts
+#[tauri::command]
+pub async fn list_session_shares(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::share::ShareInfo>, String> {
+    Ok(state.shares.list().await)
+}
+
+/// Start a local (-L), remote (-R) or dynamic SOCKS5 (-D) port forward on
+/// an SSH session
+#[tauri::command]
+pub async fn start_port_forward(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    spec: crate::port_forward::ForwardSpec,
+) -> Result<crate::port_forward::ForwardInfo, String> {
+    let handle = state
+        .sessions
+        .forward_handle(&session_id)
+        .map_err(|e| format!("Failed to start port forward: {}", e))?;
+    let forward = state
+        .forwards
+        .start(&app_handle, &session_id, handle, spec)
+        .await
+        .map_err(|e| format!("Failed to start port forward: {}", e))?;
+
+    crate::audit::record(
+        state.db.pool(),
+        crate::audit::EVENT_PORT_FORWARD,
+        &format!(
+            "session {} {:?} {}:{}",
+            session_id,
+            forward.spec.kind,
+            forward.spec.bind_address(),
+            forward.bound_port
+        ),
+    )
+    .await
+    .map_err(|e| format!("Failed to start port forward: {}", e))?;
+
+    Ok(forward)
+}
+
+/// Stop a port forward (its open connections are closed)
+#[tauri::command]
+pub async fn stop_port_forward(
+    state: State<'_, AppState>,
+    forward_id: String,
+) -> Result<(), String> {
+    state
+        .forwards
+        .stop(&forward_id)
+        .await
+        .map_err(|e| format!("Failed to stop port forward: {}", e))
+}
+
+/// List active port forwards with their traffic counters (of one session,
+/// or all)
+#[tauri::command]
+pub async fn list_port_forwards(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+) -> Result<Vec<crate::port_forward::ForwardInfo>, String> {
+    Ok(state.forwards.list(session_id.as_deref()).await)
+}
+
+/// Open an SFTP session on a saved SSH connection (FTP on an FTP/FTPS one)
+///
+/// Returns the session ID and the remote home directory to start browsing.
+#[tauri::command]
+pub async fn open_sftp_session(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    connection_id: String,
+) -> Result<crate::sftp::SftpSessionInfo, String> {
+    state
+        .sessions
+        .create_sftp_session(connection_id, app_handle)
+        .await
+        .map_err(|e| format!("Failed to open SFTP session: {}", e))
+}
+
+/// List a remote directory
+#[tauri::command]
+pub async fn sftp_list_dir(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+) -> Result<Vec<crate::sftp::RemoteEntry>, String> {
+    let session = sftp_session(&state, &session_id)?;
+    session
+        .list_dir(&path)
+        .await
+        .map_err(|e| format!("Failed to list directory: {}", e))
+}
+
+/// Get a remote file's attributes (symlinks are not followed)
+#[tauri::command]
+pub async fn sftp_stat(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+) -> Result<crate::sftp::RemoteEntry, String> {
+    let session = sftp_session(&state, &session_id)?;
+    session
+        .stat(&path)
+        .await
+        .map_err(|e| format!("Failed to stat file: {}", e))
+}
+
+/// Rename or move a remote file. On protected connections `confirm_host`
+/// must be the host name.
+#[tauri::command]
+pub async fn sftp_rename(
+    state: State<'_, AppState>,
+    session_id: String,
+    from: String,
+    to: String,
+    confirm_host: Option<String>,
+) -> Result<(), String> {
+    let session = sftp_session(&state, &session_id)?;
+    confirm_sftp_operation(
+        &state,
+        &session,
+        confirm_host.as_deref(),
+        &format!("rename {} to {}", from, to),
+    )
+    .await?;
+    session
+        .rename(&from, &to)
+        .await
+        .map_err(|e| format!("Failed to rename file: {}", e))
+}
+
+/// Change a remote file's permission bits. On protected connections
+/// `confirm_host` must be the host name.
+#[tauri::command]
+pub async fn sftp_chmod(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+    mode: u32,
+    confirm_host: Option<String>,
+) -> Result<(), String> {
+    let session = sftp_session(&state, &session_id)?;
+    confirm_sftp_operation(
+        &state,
+        &session,
+        confirm_host.as_deref(),
+        &format!("chmod {:o} {}", mode, path),
+    )
+    .await?;
+    session
+        .chmod(&path, mode)
+        .await
+        .map_err(|e| format!("Failed to change permissions: {}", e))
+}
+
+/// Delete a remote file or empty directory. On protected connections
+/// `confirm_host` must be the host name.
+#[tauri::command]
+pub async fn sftp_delete(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+    confirm_host: Option<String>,
+) -> Result<(), String> {
+    let session = sftp_session(&state, &session_id)?;
+    confirm_sftp_operation(
+        &state,
+        &session,
+        confirm_host.as_deref(),
+        &format!("delete {}", path),
+    )
+    .await?;
+    session
+        .delete(&path)
+        .await
+        .map_err(|e| format!("Failed to delete file: {}", e))
+}
+
+/// Create a remote directory
+#[tauri::command]
+pub async fn sftp_mkdir(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+) -> Result<(), String> {
+    let session = sftp_session(&state, &session_id)?;
+    session
+        .mkdir(&path)
+        .await
+        .map_err(|e| format!("Failed to create directory: {}", e))
+}
+
+/// Read a remote symlink's target
+#[tauri::command]
+pub async fn sftp_read_link(
+    state: State<'_, AppState>,
+    session_id: String,
+    path: String,
+) -> Result<String, String> {
+    let session = sftp_session(&state, &session_id)?;
+    session
+        .read_link(&path)
+        .await
+        .map_err(|e| format!("Failed to read link: {}", e))
+}
+
+/// Refuse a destructive operation on a protected connection unless the host
+/// name was typed, and audit-log it when it was
+async fn confirm_sftp_operation(
+    state: &AppState,
+    session: &crate::sftp::SftpSession,
+    confirm_host: Option<&str>,
+    operation: &str,
+) -> Result<(), String> {
+    let confirmed = session
+        .confirm_destructive(confirm_host)
+        .map_err(|e| e.to_string())?;
+    if confirmed {
+        let detail = format!("{}: {}", session.hostname, operation);
+        if let Err(e) = crate::audit::record(
+            state.db.pool(),
+            crate::audit::EVENT_FILE_OPERATION_CONFIRMED,
+            &detail,
+        )
+        .await
+        {
+            tracing::error!("[commands.rs] Failed to write audit log entry: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn sftp_session(
+    state: &AppState,
+    session_id: &str,
+) -> Result<std::sync::Arc<crate::sftp::SftpSession>, String> {
+    state
+        .sessions
+        .sftp_session(session_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Close an SFTP session (its transfers are stopped)
+#[tauri::command]
+pub async fn close_sftp_session(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    state.transfers.stop_session(&session_id);
+    state
+        .sessions
+        .close_sftp_session(&session_id)
+        .await
+        .map_err(|e| format!("Failed to close SFTP session: {}", e))
+}
+
+/// Queue an SFTP upload or download
+///
+/// Progress is reported with `transfer-progress` events.
+#[tauri::command]
+pub async fn queue_transfer(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    request: crate::transfers::TransferRequest,
+) -> Result<crate::transfers::TransferInfo, String> {
+    let session = sftp_session(&state, &session_id)?;
+    state
+        .transfers
+        .enqueue(&app_handle, session, request)
+        .map_err(|e| format!("Failed to queue transfer: {}", e))
+}
+
+/// Pause a transfer (the partial file is kept)
+#[tauri::command]
+pub async fn pause_transfer(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    state
+        .transfers
+        .pause(&app_handle, &transfer_id)
+        .map_err(|e| format!("Failed to pause transfer: {}", e))
+}
+
+/// Resume a paused or failed transfer from where it stopped
+#[tauri::command]
+pub async fn resume_transfer(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    state
+        .transfers
+        .resume(&app_handle, &transfer_id)
+        .map_err(|e| format!("Failed to resume transfer: {}", e))
+}
+
+/// Cancel a transfer (the partial file is deleted) or clear a finished one
+#[tauri::command]
+pub async fn cancel_transfer(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    state
+        .transfers
+        .cancel(&app_handle, &transfer_id)
+        .await
+        .map_err(|e| format!("Failed to cancel transfer: {}", e))
+}
+
+/// List transfers (of one SFTP session, or all) in queue order
+#[tauri::command]
+pub async fn list_transfers(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+) -> Result<Vec<crate::transfers::TransferInfo>, String> {
+    Ok(state.transfers.list(session_id.as_deref()))
+}
+
+/// Attach the frontend's output channel to a terminal session.
+///
+/// Returns all data that arrived before the frontend attached, as a raw binary
+/// response. Switches the session to streaming mode so future data is sent
+/// over `on_data` as binary frames (8-byte big-endian sequence number + bytes).
+/// Called again on remount, the new channel replaces the old one.
+#[tauri::command]
+pub async fn attach_session_output(
+    state: State<'_, AppState>,
+    session_id: String,
+    on_data: Channel<InvokeResponseBody>,
+) -> Result<Response, String> {
+    let data = state
+        .sessions
+        .attach_session_output(&session_id, on_data)
+        .map_err(|e| format!("Failed to attach session output: {}", e))?;
+    Ok(Response::new(data))
+}
+
+/// Pause a terminal session's output (the terminal fell behind)
+///
+/// The session stops reading until `resume_output`: a runaway command stalls
+/// on the SSH channel window or the PTY instead of flooding the frontend.
+#[tauri::command]
+pub async fn pause_output(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    state
+        .sessions
+        .pause_output(&session_id)
+        .map_err(|e| format!("Failed to pause output: {}", e))
+}
+
+/// Resume a terminal session's output after `pause_output`
+#[tauri::command]
+pub async fn resume_output(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    state
+        .sessions
+        .resume_output(&session_id)
+        .map_err(|e| format!("Failed to resume output: {}", e))
+}
+
+/// Get the recent output of a terminal session (last 10,000 lines, at most
+/// 4 MiB) as a raw binary response
+///
+/// Lets a reloaded frontend replay the history before attaching again.
+#[tauri::command]
+pub async fn get_session_scrollback(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Response, String> {
+    let data = state
+        .sessions
+        .session_scrollback(&session_id)
+        .map_err(|e| format!("Failed to get scrollback: {}", e))?;
+    Ok(Response::new(data))
+}
+
+/// Render a snapshot of a terminal session's current screen (SVG or PNG)
+///
+/// Rendered server-side from the backend screen state with the active theme.
+#[tauri::command]
+pub async fn snapshot_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    format: String,
+) -> Result<crate::snapshot::SessionSnapshot, String> {
+    let format = crate::snapshot::SnapshotFormat::from_str(&format).map_err(|e| e.to_string())?;
+
+    let theme_name = state
+        .db
+        .get_setting("theme")
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string());
+    let theme = crate::theme::load_theme(theme_name)
+        .or_else(|_| crate::theme::load_theme("default".to_string()))?;
 
-    match state
+    state
         .sessions
-        .create_session(connection_id.clone(), app_handle)
+        .snapshot_session(&session_id, format, &theme)
         .await
-    {
-        Ok(session_id) => {
-            tracing::info!("[commands.rs] Session created successfully: {}", session_id);
-            Ok(session_id)
-        }
-        Err(e) => {
-            tracing::error!(
-                "[commands.rs] Failed to create session for connection {}: {}",
-                connection_id,
-                e
-            );
-            Err(format!("Failed to connect: {}", e))
-        }
-    }
+        .map_err(|e| format!("Failed to snapshot session: {}", e))
 }
 
-/// Create a new local terminal session
+/// Export a session transcript with secrets masked
+///
+/// Returns the redacted text and the list of masked items for review.
 #[tauri::command]
-pub async fn connect_local_terminal(
+pub async fn export_session_transcript(
     state: State<'_, AppState>,
-    app_handle: tauri::AppHandle,
-    shell: Option<String>,
-) -> Result<String, String> {
-    tracing::info!("[commands.rs] connect_local_terminal called");
+    session_id: String,
+) -> Result<crate::redact::RedactedText, String> {
+    let patterns = state
+        .db
+        .get_setting(crate::redact::SETTING_PATTERNS)
+        .await
+        .ok()
+        .flatten();
+    let redactor = crate::redact::Redactor::from_setting(patterns.as_deref())
+        .map_err(|e| format!("Failed to export transcript: {}", e))?;
 
-    match state.sessions.create_local_session(app_handle, shell).await {
-        Ok(session_id) => {
-            tracing::info!(
-                "[commands.rs] Local session created successfully: {}",
-                session_id
-            );
-            Ok(session_id)
-        }
-        Err(e) => {
-            tracing::error!("[commands.rs] Failed to create local session: {}", e);
-            Err(format!("Failed to create local terminal: {}", e))
-        }
-    }
+    let transcript = state
+        .sessions
+        .session_transcript(&session_id)
+        .map_err(|e| format!("Failed to export transcript: {}", e))?;
+    Ok(redactor.redact(&transcript))
 }
 
-/// Check which shells are installed on the system
+/// Current working directory of a terminal session, as reported by its
+/// shell (OSC 7); None when the shell has no integration
 #[tauri::command]
-pub fn get_installed_shells(shells: Vec<String>) -> Vec<String> {
-    tracing::debug!("[commands.rs] Checking installed shells: {:?}", shells);
-
-    shells
-        .into_iter()
-        .filter(|shell_path| {
-            let exists = std::path::Path::new(shell_path).exists();
-            tracing::debug!("[commands.rs] Shell {} exists: {}", shell_path, exists);
-            exists
-        })
-        .collect()
+pub async fn get_session_cwd(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<String>, String> {
+    state
+        .sessions
+        .session_cwd(&session_id)
+        .map_err(|e| format!("Failed to get working directory: {}", e))
 }
 
-/// Quick SSH authentication method (credentials not encrypted)
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(tag = "type", rename_all = "camelCase")]
-pub enum QuickAuthMethod {
-    Password {
-        password: String,
-    },
-    PublicKey {
-        key_path: String,
-        passphrase: Option<String>,
-    },
+/// Traffic and latency of a terminal session (updated every few seconds,
+/// also sent as `session-stats` events; latency only while watched); None
+/// for local terminals
+#[tauri::command]
+pub async fn get_session_stats(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<crate::session_stats::SessionStats>, String> {
+    state
+        .sessions
+        .session_stats(&session_id)
+        .map_err(|e| format!("Failed to get session stats: {}", e))
 }
 
-impl From<QuickAuthMethod> for AuthMethod {
-    fn from(quick: QuickAuthMethod) -> Self {
-        match quick {
-            QuickAuthMethod::Password { password } => AuthMethod::Password { password },
-            QuickAuthMethod::PublicKey {
-                key_path,
-                passphrase,
-            } => AuthMethod::PublicKey {
-                key_path,
-                passphrase,
-            },
-        }
-    }
+/// Measure a session's latency while its stats are shown (the ping badge
+/// is visible); traffic is reported either way
+#[tauri::command]
+pub async fn watch_session_stats(
+    state: State<'_, AppState>,
+    session_id: String,
+    watch: bool,
+) -> Result<(), String> {
+    state
+        .sessions
+        .watch_session_stats(&session_id, watch)
+        .map_err(|e| format!("Failed to watch session stats: {}", e))
 }
 
-/// Quick SSH connect (credentials not saved, no unlock required)
-///
-/// For ad-hoc SSH connections without saving credentials to vault
+/// Confirm or cancel a dangerous command held back on a protected host
 #[tauri::command]
-pub async fn quick_ssh_connect(
+pub async fn confirm_terminal_command(
     state: State<'_, AppState>,
-    host: String,
-    port: u16,
-    username: String,
-    auth_method: QuickAuthMethod,
-    app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    tracing::info!(
-        "[commands.rs] quick_ssh_connect called for {}@{}:{}",
-        username,
-        host,
-        port
-    );
-
-    // Build a temporary Connection object (not saved to DB)
-    let connection = Connection {
-        id: format!("quick-{}", uuid::Uuid::new_v4()),
-        name: format!("{}@{}", username, host),
-        protocol: crate::connection::Protocol::SSH,
-        hostname: host,
-        port,
-        username,
-        auth_method: auth_method.clone().into(),
-        metadata: crate::connection::ConnectionMetadata {
-            color: None,
-            icon: Some("⚡".to_string()), // Quick connect indicator
-            folder: None,
-            notes: Some("Quick connect (not saved)".to_string()),
-        },
-        ssh_keep_alive_override: None,
-        ssh_keep_alive_interval: None,
-        last_used_at: None,
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64,
-        updated_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64,
-    };
-
-    // Create SSH session directly (no database, no encryption needed)
-    match state
+    session_id: String,
+    approved: bool,
+) -> Result<(), String> {
+    state
         .sessions
-        .create_quick_ssh_session(connection, auth_method.into(), app_handle)
+        .confirm_command(&session_id, approved)
         .await
-    {
-        Ok(session_id) => {
-            tracing::info!("[commands.rs] Quick SSH session created: {}", session_id);
-            Ok(session_id)
-        }
-        Err(e) => {
-            tracing::error!("[commands.rs] Failed to create quick SSH session: {}", e);
-            Err(format!("Failed to connect: {}", e))
-        }
-    }
+        .map_err(|e| format!("Failed to confirm command: {}", e))
 }
 
-/// Send input to a terminal session
+/// Answer the ZMODEM transfer started by `sz` or `rz` in a session: where to
+/// save the offered file, the files to upload, or cancel
 #[tauri::command]
-pub async fn send_terminal_input(
+pub async fn answer_zmodem(
     state: State<'_, AppState>,
     session_id: String,
-    data: Vec<u8>,
+    reply: crate::zmodem::Reply,
 ) -> Result<(), String> {
     state
         .sessions
-        .send_input(&session_id, data)
+        .answer_zmodem(&session_id, reply)
         .await
-        .map_err(|e| format!("Failed to send input: {}", e))
+        .map_err(|e| format!("Failed to answer transfer: {}", e))
 }
 
-/// Resize a terminal session
+/// Enable or disable protocol-level SSH debug capture for a session
 #[tauri::command]
-pub async fn resize_terminal(
+pub async fn set_session_debug(
     state: State<'_, AppState>,
     session_id: String,
-    cols: u32,
-    rows: u32,
+    enabled: bool,
 ) -> Result<(), String> {
     state
         .sessions
-        .resize_terminal(&session_id, cols, rows)
+        .set_session_debug(&session_id, enabled)
         .await
-        .map_err(|e| format!("Failed to resize terminal: {}", e))
+        .map_err(|e| format!("Failed to set session debug: {}", e))
 }
 
-/// Close a terminal session
+/// Get the protocol-level debug trace of a session
 #[tauri::command]
-pub async fn disconnect_terminal(
+pub async fn get_session_trace(
     state: State<'_, AppState>,
     session_id: String,
-) -> Result<(), String> {
+) -> Result<Vec<crate::session_trace::TraceEvent>, String> {
     state
         .sessions
-        .close_session(&session_id)
+        .get_session_trace(&session_id)
         .await
-        .map_err(|e| format!("Failed to disconnect: {}", e))
+        .map_err(|e| format!("Failed to get session trace: {}", e))
 }
 
-/// Claim the initial output buffer for a terminal session.
-///
-/// Returns all SSH data that arrived before the frontend registered its event
-/// listener, encoded as base64. Switches the session to streaming mode so
-/// future data is emitted as `terminal-data` events.
+/// Start recording a terminal session (asciinema v2 cast file)
+#[tauri::command]
+pub async fn start_session_recording(
+    state: State<'_, AppState>,
+    session_id: String,
+    options: Option<crate::recording::RecordingOptions>,
+) -> Result<crate::recording::RecordingInfo, String> {
+    state
+        .sessions
+        .start_recording(&session_id, options.unwrap_or_default())
+        .await
+        .map_err(|e| format!("Failed to start recording: {}", e))
+}
+
+/// Stop recording a terminal session
+#[tauri::command]
+pub async fn stop_session_recording(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<crate::recording::RecordingInfo, String> {
+    state
+        .sessions
+        .stop_recording(&session_id)
+        .map_err(|e| format!("Failed to stop recording: {}", e))
+}
+
+/// Get the running recording of a session (null when not recording)
 #[tauri::command]
-pub async fn claim_session_output(
+pub async fn get_session_recording(
     state: State<'_, AppState>,
     session_id: String,
+) -> Result<Option<crate::recording::RecordingInfo>, String> {
+    state
+        .sessions
+        .recording(&session_id)
+        .map_err(|e| format!("Failed to get recording: {}", e))
+}
+
+/// List the recordings of a connection ("local" for local terminals)
+#[tauri::command]
+pub async fn list_recordings(
+    connection_id: String,
+) -> Result<Vec<crate::recording::RecordingInfo>, String> {
+    crate::recording::list(&connection_id).map_err(|e| format!("Failed to list recordings: {}", e))
+}
+
+/// Read a recording as asciicast v2 text (decrypted for playback)
+#[tauri::command]
+pub async fn read_recording(
+    state: State<'_, AppState>,
+    connection_id: String,
+    recording_id: String,
 ) -> Result<String, String> {
-    let data = state.sessions.claim_session_output(&session_id).await;
-    Ok(base64::engine::general_purpose::STANDARD.encode(&data))
+    let key = state.auth.get_master_key().await.ok();
+    crate::recording::read(&connection_id, &recording_id, key.as_deref())
+        .map_err(|e| format!("Failed to read recording: {}", e))
+}
+
+/// Delete a recording
+#[tauri::command]
+pub async fn delete_recording(connection_id: String, recording_id: String) -> Result<(), String> {
+    crate::recording::delete(&connection_id, &recording_id)
+        .map_err(|e| format!("Failed to delete recording: {}", e))
 }
 
 /// List all active terminal sessions
@@ -474,10 +2949,94 @@ pub async fn list_terminal_sessions(state: State<'_, AppState>) -> Result<Vec<St
     Ok(state.sessions.list_sessions().await)
 }
 
+// ============================================================================
+// Session Restore Commands
+// ============================================================================
+
+/// Get the terminals open when the app last exited (offered for reopening)
+#[tauri::command]
+pub async fn get_restorable_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::session_restore::RestorableSession>, String> {
+    Ok(state.restore.restorable())
+}
+
+/// Get the saved scrollback of a terminal of the last run (requires unlock)
+#[tauri::command]
+pub async fn get_restored_scrollback(
+    state: State<'_, AppState>,
+    index: usize,
+) -> Result<Response, String> {
+    let master_key = state
+        .auth
+        .get_master_key()
+        .await
+        .map_err(|e| format!("Failed to get master key: {}", e))?;
+    let data = state
+        .restore
+        .scrollback(index, &master_key)
+        .map_err(|e| format!("Failed to get restored scrollback: {}", e))?;
+    Ok(Response::new(data))
+}
+
+/// Report the open terminals, saved to be reopened on next launch
+#[tauri::command]
+pub async fn set_open_sessions(
+    state: State<'_, AppState>,
+    sessions: Vec<crate::session_restore::SessionDescriptor>,
+) -> Result<(), String> {
+    state.restore.set_open(sessions);
+    state.save_open_sessions().await;
+    Ok(())
+}
+
+/// Report the working directory of a terminal (OSC 7 from the shell)
+#[tauri::command]
+pub async fn set_session_cwd(
+    state: State<'_, AppState>,
+    session_id: String,
+    cwd: Option<String>,
+) -> Result<(), String> {
+    state.restore.set_cwd(&session_id, cwd);
+    Ok(())
+}
+
 // ============================================================================
 // Settings Commands
 // ============================================================================
 
+/// Get the detected local system locale (default for locale forwarding)
+#[tauri::command]
+pub fn get_local_locale() -> String {
+    crate::locale::detect_local_locale()
+}
+
+/// Get the character encodings offered for legacy hosts
+#[tauri::command]
+pub fn get_session_encodings() -> Vec<&'static str> {
+    crate::encoding::COMMON_ENCODINGS.to_vec()
+}
+
+/// Get the available SSH client identification presets
+#[tauri::command]
+pub fn get_ssh_client_id_presets() -> Vec<crate::session_options::ClientIdPreset> {
+    crate::session_options::client_id_presets()
+}
+
+/// Get the prompt detection profiles (built-in and custom)
+#[tauri::command]
+pub async fn get_prompt_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::prompt::PromptProfile>, String> {
+    let custom = state
+        .db
+        .get_setting(crate::prompt::SETTING_PROFILES)
+        .await
+        .map_err(|e| format!("Failed to get prompt profiles: {}", e))?;
+    crate::prompt::load_profiles(custom.as_deref())
+        .map_err(|e| format!("Failed to get prompt profiles: {}", e))
+}
+
 /// Get a setting value
 #[tauri::command]
 pub async fn get_setting(
@@ -498,11 +3057,49 @@ pub async fn set_setting(
     key: String,
     value: String,
 ) -> Result<(), String> {
+    state
+        .policy
+        .check_setting(&key, &value)
+        .map_err(|e| format!("Failed to set setting: {}", e))?;
+
+    // Reject profiles that would only fail once a session starts
+    if key == crate::prompt::SETTING_PROFILES {
+        crate::prompt::load_profiles(Some(&value))
+            .map_err(|e| format!("Failed to set setting: {}", e))?;
+    }
+
+    // Backup settings are only read by the background task
+    if key == rite_vault::backup::SETTING_SCHEDULE {
+        rite_vault::BackupSchedule::parse(&value)
+            .map_err(|e| format!("Failed to set setting: {}", e))?;
+    }
+    if key == rite_vault::backup::SETTING_RETENTION
+        && !value.parse::<usize>().is_ok_and(|keep| keep > 0)
+    {
+        return Err(format!(
+            "Failed to set setting: invalid backup retention: {}",
+            value
+        ));
+    }
+
+    // Parameters are only read on the next password change
+    if key == rite_vault::auth::SETTING_KDF_PARAMS {
+        serde_json::from_str::<rite_crypto::KdfParams>(&value)
+            .map_err(anyhow::Error::from)
+            .and_then(|params| params.validate())
+            .map_err(|e| format!("Failed to set setting: {}", e))?;
+    }
+
     state
         .db
         .set_setting(&key, &value)
         .await
-        .map_err(|e| format!("Failed to set setting: {}", e))
+        .map_err(|e| format!("Failed to set setting: {}", e))?;
+
+    state
+        .events
+        .settings(crate::events::ChangeKind::Updated, vec![key]);
+    Ok(())
 }
 
 /// Get all settings
@@ -510,9 +3107,25 @@ pub async fn set_setting(
 pub async fn get_all_settings(
     state: State<'_, AppState>,
 ) -> Result<std::collections::HashMap<String, String>, String> {
-    state
+    let settings = state
+        .db
+        .get_all_settings()
+        .await
+        .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+    Ok(state.policy.report(settings).values)
+}
+
+/// Get all settings along with the keys locked by organization policy
+#[tauri::command]
+pub async fn get_settings(
+    state: State<'_, AppState>,
+) -> Result<crate::policy::SettingsReport, String> {
+    let settings = state
         .db
         .get_all_settings()
         .await
-        .map_err(|e| format!("Failed to get settings: {}", e))
+        .map_err(|e| format!("Failed to get settings: {}", e))?;
+
+    Ok(state.policy.report(settings))
 }
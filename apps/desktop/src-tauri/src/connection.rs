@@ -1,266 +1,168 @@
 /**
- * Connection Management Module
+ * Connection Module
  *
- * Handles SSH connection data with encrypted credentials
+ * Vault connection types with the app's session options, and SSH config and
+ * other clients' session import
  */
-use anyhow::Result;
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use serde::Deserialize;
+use zeroize::Zeroizing;
 
-use crate::auth::MasterKey;
-use rite_crypto::{decrypt, encrypt, EncryptedData};
+use crate::importers::ImportEntry;
+use crate::session_options::SessionOptions;
+use crate::ssh_config::{expand_tilde, SshConfigEntry};
 
-/// SSH connection protocol type
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-#[allow(clippy::upper_case_acronyms)]
-pub enum Protocol {
-    SSH,
-    SFTP,
-    Local,
-}
+pub use rite_vault::connection::{AuthMethod, ConnectionMetadata, Protocol};
 
-impl Protocol {
-    pub fn as_str(&self) -> &str {
-        match self {
-            Protocol::SSH => "ssh",
-            Protocol::SFTP => "sftp",
-            Protocol::Local => "local",
-        }
-    }
+/// Full connection data (with decrypted credentials)
+pub type Connection = rite_vault::Connection<SessionOptions>;
 
-    pub fn from_str(s: &str) -> Result<Self> {
-        match s.to_lowercase().as_str() {
-            "ssh" => Ok(Protocol::SSH),
-            "sftp" => Ok(Protocol::SFTP),
-            "local" => Ok(Protocol::Local),
-            _ => Err(anyhow::anyhow!("Invalid protocol: {}", s)),
-        }
-    }
-}
+/// Connection data for frontend (without sensitive credentials)
+pub type ConnectionInfo = rite_vault::ConnectionInfo<SessionOptions>;
 
-/// Authentication method for SSH
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "camelCase")]
-pub enum AuthMethod {
-    Password {
-        password: String,
-    },
-    PublicKey {
-        key_path: String,
-        passphrase: Option<String>,
-    },
-}
+/// Update rejected because the connection changed since it was read
+pub type UpdateConflict = rite_vault::UpdateConflict<SessionOptions>;
 
-/// Connection metadata (not encrypted)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ConnectionMetadata {
-    pub color: Option<String>,
-    pub icon: Option<String>,
-    pub folder: Option<String>,
-    pub notes: Option<String>,
+/// Stored secret of a connection that can be typed into its terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretKind {
+    /// Password of password authentication
+    Password,
+    /// Passphrase of the private key
+    Passphrase,
+    /// Password of the SOCKS5/HTTP proxy
+    ProxyPassword,
 }
 
-/// Full connection data (for database storage)
-#[derive(Debug, Clone)]
-pub struct Connection {
-    pub id: String,
-    pub name: String,
-    pub protocol: Protocol,
-    pub hostname: String,
-    pub port: u16,
-    pub username: String,
-    pub auth_method: AuthMethod,
-    pub metadata: ConnectionMetadata,
-    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
-    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds, NULL = use global
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub last_used_at: Option<i64>,
+impl SecretKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SecretKind::Password => "password",
+            SecretKind::Passphrase => "key passphrase",
+            SecretKind::ProxyPassword => "proxy password",
+        }
+    }
 }
 
-/// Connection data for frontend (without sensitive credentials)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ConnectionInfo {
-    pub id: String,
-    pub name: String,
-    pub protocol: String,
-    pub hostname: String,
-    pub port: u16,
-    pub username: String,
-    pub auth_type: String, // "password" or "publicKey"
-    pub color: Option<String>,
-    pub icon: Option<String>,
-    pub folder: Option<String>,
-    pub notes: Option<String>,
-    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
-    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub last_used_at: Option<i64>,
+/// Decrypted secret of a connection (None when it has none of that kind)
+pub fn stored_secret(connection: &Connection, kind: SecretKind) -> Option<Zeroizing<String>> {
+    let secret = match (kind, &connection.auth_method) {
+        (SecretKind::Password, AuthMethod::Password { password }) => Some(password),
+        (
+            SecretKind::Passphrase,
+            AuthMethod::PublicKey { passphrase, .. } | AuthMethod::StoredKey { passphrase, .. },
+        ) => passphrase.as_ref(),
+        (SecretKind::ProxyPassword, _) => connection.proxy_password.as_ref(),
+        _ => None,
+    };
+    secret
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| Zeroizing::new(secret.clone()))
 }
 
 /// Input for creating a new connection
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CreateConnectionInput {
-    pub name: String,
-    pub protocol: String,
-    pub hostname: String,
-    pub port: u16,
-    pub username: String,
-    pub auth_method: AuthMethod,
-    pub color: Option<String>,
-    pub icon: Option<String>,
-    pub folder: Option<String>,
-    pub notes: Option<String>,
-    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
-    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds
-}
+pub type CreateConnectionInput = rite_vault::CreateConnectionInput<SessionOptions>;
 
 /// Input for updating a connection
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UpdateConnectionInput {
-    pub id: String,
-    pub name: Option<String>,
-    pub protocol: Option<String>,
-    pub hostname: Option<String>,
-    pub port: Option<u16>,
-    pub username: Option<String>,
-    pub auth_method: Option<AuthMethod>,
-    pub color: Option<String>,
-    pub icon: Option<String>,
-    pub folder: Option<String>,
-    pub notes: Option<String>,
-    pub ssh_keep_alive_override: Option<Option<String>>, // Nested Option to allow setting to NULL
-    pub ssh_keep_alive_interval: Option<Option<i64>>,    // Nested Option to allow setting to NULL
-}
-
-impl Connection {
-    /// Create a new connection
-    pub fn new(input: CreateConnectionInput) -> Result<Self> {
-        let now = Utc::now().timestamp_millis();
-        let protocol = Protocol::from_str(&input.protocol)?;
+pub type UpdateConnectionInput = rite_vault::UpdateConnectionInput<SessionOptions>;
+
+impl From<&SshConfigEntry> for CreateConnectionInput {
+    /// Convert an SSH config entry for import
+    fn from(entry: &SshConfigEntry) -> Self {
+        let hostname = entry.hostname.clone().unwrap_or_else(|| entry.host.clone());
+
+        let username = entry.user.clone().unwrap_or_else(|| "root".to_string());
+
+        let port = entry.port.unwrap_or(22);
+
+        // Determine auth method
+        let auth_method = if let Some(identity_file) = &entry.identity_file {
+            // Expand ~ to home directory
+            let key_path = expand_tilde(identity_file);
+            AuthMethod::PublicKey {
+                key_path,
+                passphrase: None, // User will be prompted if needed
+            }
+        } else {
+            // Default to password auth with empty password
+            AuthMethod::Password {
+                password: String::new(),
+            }
+        };
 
-        Ok(Connection {
-            id: Uuid::new_v4().to_string(),
-            name: input.name,
-            protocol,
-            hostname: input.hostname,
-            port: input.port,
-            username: input.username,
-            auth_method: input.auth_method,
-            metadata: ConnectionMetadata {
-                color: input.color,
-                icon: input.icon,
-                folder: input.folder,
-                notes: input.notes,
+        // Convert server_alive_interval to seconds (if present)
+        let ssh_keep_alive_interval = entry.server_alive_interval.map(|seconds| seconds as i64);
+
+        CreateConnectionInput {
+            name: entry.host.clone(),
+            protocol: "ssh".to_string(),
+            hostname,
+            port,
+            username,
+            auth_method,
+            proxy_password: None,
+            color: None,
+            icon: None,
+            folder_id: None,
+            folder: None,
+            notes: Some("Imported from SSH config".to_string()),
+            tags: Vec::new(),
+            ssh_keep_alive_override: if ssh_keep_alive_interval.is_some() {
+                Some("enabled".to_string())
+            } else {
+                None
             },
-            ssh_keep_alive_override: input.ssh_keep_alive_override,
-            ssh_keep_alive_interval: input.ssh_keep_alive_interval,
-            created_at: now,
-            updated_at: now,
-            last_used_at: None,
-        })
-    }
-
-    /// Encrypt credentials for database storage
-    pub fn encrypt_credentials(&self, master_key: &MasterKey) -> Result<(Vec<u8>, Vec<u8>)> {
-        let credentials_json = serde_json::to_string(&self.auth_method)?;
-        let encrypted = encrypt(master_key, credentials_json.as_bytes())?;
-        Ok((encrypted.data, encrypted.nonce.to_vec()))
-    }
-
-    /// Decrypt credentials from database
-    pub fn decrypt_credentials(
-        encrypted_credentials: &[u8],
-        nonce: &[u8],
-        master_key: &MasterKey,
-    ) -> Result<AuthMethod> {
-        let nonce_array: [u8; 12] = nonce
-            .try_into()
-            .map_err(|_| anyhow::anyhow!("Invalid nonce length"))?;
-        let encrypted_data = EncryptedData {
-            data: encrypted_credentials.to_vec(),
-            nonce: nonce_array,
-            salt: None,
-        };
-        let decrypted = decrypt(master_key, &encrypted_data)?;
-        let credentials_json = String::from_utf8(decrypted)?;
-        let auth_method: AuthMethod = serde_json::from_str(&credentials_json)?;
-        Ok(auth_method)
+            ssh_keep_alive_interval,
+            session_options: None,
+        }
     }
+}
 
-    /// Convert to ConnectionInfo (safe for frontend)
-    pub fn to_info(&self) -> ConnectionInfo {
-        let auth_type = match &self.auth_method {
-            AuthMethod::Password { .. } => "password".to_string(),
-            AuthMethod::PublicKey { .. } => "publicKey".to_string(),
+impl From<&ImportEntry> for CreateConnectionInput {
+    /// Convert a session imported from another client
+    fn from(entry: &ImportEntry) -> Self {
+        // Passwords aren't imported: the user is prompted on connect
+        let auth_method = match &entry.key_path {
+            Some(key_path) => AuthMethod::PublicKey {
+                key_path: expand_tilde(key_path),
+                passphrase: None,
+            },
+            None => AuthMethod::Password {
+                password: String::new(),
+            },
         };
 
-        ConnectionInfo {
-            id: self.id.clone(),
-            name: self.name.clone(),
-            protocol: self.protocol.as_str().to_string(),
-            hostname: self.hostname.clone(),
-            port: self.port,
-            username: self.username.clone(),
-            auth_type,
-            color: self.metadata.color.clone(),
-            icon: self.metadata.icon.clone(),
-            folder: self.metadata.folder.clone(),
-            notes: self.metadata.notes.clone(),
-            ssh_keep_alive_override: self.ssh_keep_alive_override.clone(),
-            ssh_keep_alive_interval: self.ssh_keep_alive_interval,
-            created_at: self.created_at,
-            updated_at: self.updated_at,
-            last_used_at: self.last_used_at,
-        }
-    }
-
-    /// Update connection with partial data
-    pub fn update(&mut self, input: UpdateConnectionInput) -> Result<()> {
-        if let Some(name) = input.name {
-            self.name = name;
-        }
-        if let Some(protocol) = input.protocol {
-            self.protocol = Protocol::from_str(&protocol)?;
+        let notes = entry
+            .notes
+            .clone()
+            .unwrap_or_else(|| format!("Imported from {}", entry.source.label()));
+
+        // Tags the vault would reject (commas) are dropped rather than
+        // failing the whole connection
+        let tags = entry
+            .tags
+            .iter()
+            .filter(|tag| rite_vault::tag::validate_name(tag).is_ok())
+            .cloned()
+            .collect();
+
+        CreateConnectionInput {
+            name: entry.name.clone(),
+            protocol: "ssh".to_string(),
+            hostname: entry.hostname.clone(),
+            port: entry.port.unwrap_or(22),
+            username: entry.username.clone().unwrap_or_else(|| "root".to_string()),
+            auth_method,
+            proxy_password: None,
+            color: None,
+            icon: None,
+            folder_id: None,
+            folder: entry.folder.clone(),
+            notes: Some(notes),
+            tags,
+            ssh_keep_alive_override: None,
+            ssh_keep_alive_interval: None,
+            session_options: None,
         }
-        if let Some(hostname) = input.hostname {
-            self.hostname = hostname;
-        }
-        if let Some(port) = input.port {
-            self.port = port;
-        }
-        if let Some(username) = input.username {
-            self.username = username;
-        }
-        if let Some(auth_method) = input.auth_method {
-            self.auth_method = auth_method;
-        }
-        if let Some(color) = input.color {
-            self.metadata.color = Some(color);
-        }
-        if let Some(icon) = input.icon {
-            self.metadata.icon = Some(icon);
-        }
-        if let Some(folder) = input.folder {
-            self.metadata.folder = Some(folder);
-        }
-        if let Some(notes) = input.notes {
-            self.metadata.notes = Some(notes);
-        }
-        if let Some(ssh_keep_alive_override) = input.ssh_keep_alive_override {
-            self.ssh_keep_alive_override = ssh_keep_alive_override;
-        }
-        if let Some(ssh_keep_alive_interval) = input.ssh_keep_alive_interval {
-            self.ssh_keep_alive_interval = ssh_keep_alive_interval;
-        }
-
-        self.updated_at = Utc::now().timestamp_millis();
-        Ok(())
     }
 }
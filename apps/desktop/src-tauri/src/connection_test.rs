@@ -0,0 +1,453 @@
+/**
+ * Connection Test
+ *
+ * Checks a saved SSH connection stage by stage without opening a shell:
+ * DNS resolution, TCP connect, SSH banner, key exchange with the host key
+ * check, then authentication. Each stage is timed and reports what it
+ * found or why it failed, so a connection that won't open can be debugged
+ * without a terminal tab.
+ *
+ * DNS, TCP and banner are checked on a connection of their own, closed
+ * once the banner is read; connections through a proxy or jump hosts skip
+ * them. The handshake then runs like a terminal's (same host key policy
+ * and prompts), and is split into its two stages by the stage markers it
+ * leaves in the session trace.
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::connection::{Connection, Protocol};
+use crate::session_trace::{HandshakeStage, SessionTrace, TraceEvent};
+use crate::state::AppState;
+use crate::terminal::SshSession;
+
+/// Longest DNS resolution, TCP connect or banner wait
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest handshake (includes host key and authentication prompts)
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Lines a server may send before its banner (RFC 4253 §4.2)
+const MAX_PRE_BANNER_LINES: usize = 20;
+
+/// Stages of a connection test, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Dns,
+    Tcp,
+    Banner,
+    HostKey,
+    Auth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Ok,
+    Failed,
+    /// Not run (an earlier stage failed, or it doesn't apply)
+    Skipped,
+}
+
+/// Outcome of one stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResult {
+    pub stage: Stage,
+    pub status: StageStatus,
+    pub duration_ms: u64,
+    /// What the stage found (addresses, banner, fingerprint) or why it failed
+    pub detail: Option<String>,
+}
+
+/// Outcome of a connection test
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTestReport {
+    pub connection_id: String,
+    /// Every stage that ran succeeded
+    pub success: bool,
+    pub stages: Vec<StageResult>,
+    pub duration_ms: u64,
+}
+
+impl StageResult {
+    fn ok(stage: Stage, duration: Duration, detail: impl Into<String>) -> Self {
+        Self {
+            stage,
+            status: StageStatus::Ok,
+            duration_ms: duration.as_millis() as u64,
+            detail: Some(detail.into()),
+        }
+    }
+
+    fn failed(stage: Stage, duration: Duration, error: impl std::fmt::Display) -> Self {
+        Self {
+            stage,
+            status: StageStatus::Failed,
+            duration_ms: duration.as_millis() as u64,
+            detail: Some(error.to_string()),
+        }
+    }
+
+    fn skipped(stage: Stage, reason: Option<&str>) -> Self {
+        Self {
+            stage,
+            status: StageStatus::Skipped,
+            duration_ms: 0,
+            detail: reason.map(str::to_string),
+        }
+    }
+}
+
+/// Test a saved SSH connection
+///
+/// Errors are reserved for connections that can't be tested (unknown,
+/// trashed, not SSH, invalid options); connection failures are reported in
+/// the stages.
+pub async fn run(app_handle: &AppHandle, connection_id: &str) -> Result<ConnectionTestReport> {
+    let connection = app_handle
+        .state::<AppState>()
+        .connections
+        .get_connection(connection_id)
+        .await?
+        .filter(|connection| connection.deleted_at.is_none())
+        .ok_or_else(|| anyhow!("Connection not found"))?;
+    if !matches!(connection.protocol, Protocol::SSH) {
+        return Err(anyhow!("{} is not an SSH connection", connection.name));
+    }
+    tracing::info!(
+        "[connection_test.rs] Testing connection {}",
+        connection.name
+    );
+
+    let started = Instant::now();
+    let mut stages = network_stages(&connection).await?;
+    if stages.iter().any(|s| s.status == StageStatus::Failed) {
+        stages.push(StageResult::skipped(Stage::HostKey, None));
+        stages.push(StageResult::skipped(Stage::Auth, None));
+    } else {
+        stages.extend(handshake_stages(app_handle, &connection).await);
+    }
+
+    let success = stages.iter().all(|s| s.status != StageStatus::Failed);
+    tracing::info!(
+        "[connection_test.rs] Test of {} {}",
+        connection.name,
+        if success { "succeeded" } else { "failed" }
+    );
+    Ok(ConnectionTestReport {
+        connection_id: connection.id,
+        success,
+        stages,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// DNS, TCP and banner stages (skipped through a proxy or jump hosts)
+async fn network_stages(connection: &Connection) -> Result<Vec<StageResult>> {
+    let indirect = if !connection
+        .session_options
+        .jump_chain(&connection.id)?
+        .is_empty()
+    {
+        Some("Connected through jump hosts".to_string())
+    } else {
+        connection
+            .session_options
+            .proxy_config(connection.proxy_password.as_deref())?
+            .map(|proxy| {
+                format!(
+                    "Connected through {:?} proxy {}:{}",
+                    proxy.kind, proxy.hostname, proxy.port
+                )
+            })
+    };
+    if let Some(reason) = indirect {
+        return Ok([Stage::Dns, Stage::Tcp, Stage::Banner]
+            .into_iter()
+            .map(|stage| StageResult::skipped(stage, Some(&reason)))
+            .collect());
+    }
+
+    let started = Instant::now();
+    let host = (connection.hostname.as_str(), connection.port);
+    let resolved = tokio::time::timeout(NETWORK_TIMEOUT, tokio::net::lookup_host(host))
+        .await
+        .unwrap_or_else(|_| Err(std::io::Error::other("timed out")));
+    let addresses = match resolved {
+        Ok(addresses) => addresses.collect::<Vec<SocketAddr>>(),
+        Err(e) => {
+            let error = format!("Cannot resolve {}: {}", connection.hostname, e);
+            return Ok(dns_failed(started.elapsed(), error));
+        }
+    };
+    if addresses.is_empty() {
+        let error = format!("No address for {}", connection.hostname);
+        return Ok(dns_failed(started.elapsed(), error));
+    }
+    let mut stages = vec![StageResult::ok(
+        Stage::Dns,
+        started.elapsed(),
+        join_addresses(&addresses),
+    )];
+
+    // Addresses are tried in order, like the SSH connection does
+    let started = Instant::now();
+    let mut last_error = None;
+    let mut stream = None;
+    for address in &addresses {
+        match tokio::time::timeout(NETWORK_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(connected)) => {
+                stream = Some((connected, *address));
+                break;
+            }
+            Ok(Err(e)) => last_error = Some(format!("{}: {}", address, e)),
+            Err(_) => last_error = Some(format!("{}: timed out", address)),
+        }
+    }
+    let Some((stream, address)) = stream else {
+        stages.push(StageResult::failed(
+            Stage::Tcp,
+            started.elapsed(),
+            last_error.unwrap_or_default(),
+        ));
+        stages.push(StageResult::skipped(Stage::Banner, None));
+        return Ok(stages);
+    };
+    stages.push(StageResult::ok(
+        Stage::Tcp,
+        started.elapsed(),
+        format!("Connected to {}", address),
+    ));
+
+    let started = Instant::now();
+    let mut reader = BufReader::new(stream);
+    let banner = tokio::time::timeout(NETWORK_TIMEOUT, read_banner(&mut reader))
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("No banner within {}s", NETWORK_TIMEOUT.as_secs())));
+    stages.push(match banner {
+        Ok(banner) => StageResult::ok(Stage::Banner, started.elapsed(), banner),
+        Err(e) => StageResult::failed(Stage::Banner, started.elapsed(), e),
+    });
+    Ok(stages)
+}
+
+/// Network stages when the name doesn't resolve
+fn dns_failed(duration: Duration, error: String) -> Vec<StageResult> {
+    vec![
+        StageResult::failed(Stage::Dns, duration, error),
+        StageResult::skipped(Stage::Tcp, None),
+        StageResult::skipped(Stage::Banner, None),
+    ]
+}
+
+/// Host key and authentication stages, from a regular handshake
+async fn handshake_stages(app_handle: &AppHandle, connection: &Connection) -> Vec<StageResult> {
+    let trace = SessionTrace::new(true);
+    let started = Instant::now();
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let result = tokio::time::timeout(
+        HANDSHAKE_TIMEOUT,
+        SshSession::establish(
+            connection,
+            &connection.auth_method,
+            app_handle,
+            false,
+            false,
+            &trace,
+        ),
+    )
+    .await
+    .unwrap_or_else(|_| Err(anyhow!("Timed out")));
+    let duration = started.elapsed();
+
+    let error = match result {
+        Ok(transport) => {
+            let _ = transport
+                .disconnect(russh::Disconnect::ByApplication, "", "")
+                .await;
+            None
+        }
+        Err(e) => Some(format!("{:#}", e)),
+    };
+    split_handshake(&trace.events(), started_at, duration, error)
+}
+
+/// Split a handshake into its host key and authentication stages
+///
+/// The host key stage ends when the key exchange with the target completes
+/// (the last `KexDone` marker, after any jump hosts).
+fn split_handshake(
+    events: &[TraceEvent],
+    started_at: i64,
+    duration: Duration,
+    error: Option<String>,
+) -> Vec<StageResult> {
+    let kex_done = events
+        .iter()
+        .rev()
+        .find(|e| e.stage == Some(HandshakeStage::KexDone));
+    let host_key = events
+        .iter()
+        .filter(|e| e.kind == "host_key")
+        .map(|e| e.detail.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    let host_key = Some(host_key).filter(|detail| !detail.is_empty());
+
+    let Some(kex_done) = kex_done else {
+        let error = error.unwrap_or_else(|| "Key exchange didn't complete".to_string());
+        let detail = match host_key {
+            Some(host_key) => format!("{} ({})", error, host_key),
+            None => error,
+        };
+        return vec![
+            StageResult::failed(Stage::HostKey, duration, detail),
+            StageResult::skipped(Stage::Auth, None),
+        ];
+    };
+
+    let host_key_duration =
+        Duration::from_millis((kex_done.timestamp - started_at).max(0) as u64).min(duration);
+    let auth_duration = duration - host_key_duration;
+    let methods = events
+        .iter()
+        .filter_map(|e| match &e.stage {
+            Some(HandshakeStage::AuthAttempt { method }) => Some(method.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    vec![
+        StageResult::ok(
+            Stage::HostKey,
+            host_key_duration,
+            host_key.unwrap_or_else(|| "Host key accepted".to_string()),
+        ),
+        match error {
+            None => StageResult::ok(
+                Stage::Auth,
+                auth_duration,
+                format!("Authenticated with {}", methods.join(", ")),
+            ),
+            Some(error) => StageResult::failed(Stage::Auth, auth_duration, error),
+        },
+    ]
+}
+
+/// Read the server's identification line (`SSH-2.0-...`)
+async fn read_banner<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String> {
+    let mut line = Vec::new();
+    for _ in 0..MAX_PRE_BANNER_LINES {
+        line.clear();
+        // Identification lines are at most 255 bytes
+        let read = (&mut *reader)
+            .take(256)
+            .read_until(b'\n', &mut line)
+            .await?;
+        if read == 0 {
+            return Err(anyhow!("Connection closed before the SSH banner"));
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        if text.starts_with("SSH-") {
+            return Ok(text);
+        }
+    }
+    Err(anyhow!("Not an SSH server (no banner)"))
+}
+
+fn join_addresses(addresses: &[SocketAddr]) -> String {
+    addresses
+        .iter()
+        .map(|address| address.ip().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(
+        timestamp: i64,
+        kind: &str,
+        detail: &str,
+        stage: Option<HandshakeStage>,
+    ) -> TraceEvent {
+        TraceEvent {
+            timestamp,
+            kind: kind.to_string(),
+            detail: detail.to_string(),
+            stage,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_banner() {
+        let mut input: &[u8] = b"Welcome\r\nSSH-2.0-OpenSSH_9.6 Ubuntu\r\nkex";
+        assert_eq!(
+            read_banner(&mut input).await.unwrap(),
+            "SSH-2.0-OpenSSH_9.6 Ubuntu"
+        );
+
+        let mut input: &[u8] = b"HTTP/1.1 400 Bad Request\r\n";
+        assert!(read_banner(&mut input).await.is_err());
+    }
+
+    #[test]
+    fn test_split_handshake() {
+        let events = vec![
+            event(
+                1_010,
+                "host_key",
+                "Server host key: ssh-ed25519 SHA256:abc",
+                None,
+            ),
+            event(
+                1_012,
+                "host_key",
+                "Host key matches known_hosts entry",
+                None,
+            ),
+            // Wording alone doesn't mark a stage
+            event(1_020, "kex", "Key exchange completed with bastion:22", None),
+            event(1_040, "kex", "Connected", Some(HandshakeStage::KexDone)),
+            event(
+                1_041,
+                "auth",
+                "Trying password authentication",
+                Some(HandshakeStage::AuthAttempt {
+                    method: "password".to_string(),
+                }),
+            ),
+        ];
+        let stages = split_handshake(&events, 1_000, Duration::from_millis(100), None);
+        assert_eq!(stages[0].status, StageStatus::Ok);
+        assert_eq!(stages[0].duration_ms, 40);
+        assert!(stages[0]
+            .detail
+            .as_deref()
+            .unwrap()
+            .contains("matches known_hosts"));
+        assert_eq!(stages[1].duration_ms, 60);
+        assert_eq!(
+            stages[1].detail.as_deref(),
+            Some("Authenticated with password")
+        );
+
+        let stages = split_handshake(
+            &events[..2],
+            1_000,
+            Duration::from_millis(20),
+            Some("Disconnected".to_string()),
+        );
+        assert_eq!(stages[0].status, StageStatus::Failed);
+        assert_eq!(stages[1].status, StageStatus::Skipped);
+    }
+}
@@ -4,202 +4,1064 @@
  * Manages SSH connections with encrypted credentials storage
  */
 use anyhow::Result;
-use tracing::{debug, info};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
 
-use crate::auth::AuthManager;
-use crate::connection::{Connection, ConnectionInfo, CreateConnectionInput, UpdateConnectionInput};
-use crate::db::{ConnectionRow, Database};
+use crate::audit;
+use crate::connection::{
+    AuthMethod, Connection, ConnectionInfo, CreateConnectionInput, UpdateConnectionInput,
+};
+use crate::events::{ChangeKind, VaultEvents};
+use crate::key_format::{decode_private_key, write_private_file};
+use crate::policy::Policy;
+use crate::remote_tasks::{
+    CreateRemoteTaskInput, RemoteTask, RemoteTaskRun, UpdateRemoteTaskInput,
+};
+use crate::session_options::SessionOptions;
+use crate::snippets::{CreateSnippetInput, Snippet, UpdateSnippetInput};
+use crate::ssh_config::SshConfigEntry;
+use crate::template::{ConnectionTemplate, CreateTemplateInput};
+use rite_vault::{
+    ConnectionStats, ConnectionUsage, CreateFolderInput, CreateTagInput, Database, Folder,
+    FolderNode, SessionRecord, Tag, TagMatch, UpdateFolderInput, UpdateTagInput, Vault,
+};
+use zeroize::Zeroizing;
+
+/// Largest key file accepted for import (real keys are a few KiB)
+const MAX_KEY_FILE_LEN: u64 = 64 * 1024;
 
 pub struct ConnectionsManager {
-    db: Database,
-    auth: AuthManager,
+    vault: Vault,
+    policy: Arc<Policy>,
+    events: VaultEvents,
+    cache: Mutex<ListingCache>,
+}
+
+/// Connection listing kept in memory between writes
+///
+/// `generation` is bumped on every invalidation, so a listing loaded while
+/// a write was in progress is never stored.
+#[derive(Default)]
+struct ListingCache {
+    connections: Option<Arc<Vec<ConnectionInfo>>>,
+    generation: u64,
 }
 
 impl ConnectionsManager {
-    pub fn new(db: Database, auth: AuthManager) -> Self {
-        Self { db, auth }
+    pub fn new(vault: Vault, policy: Arc<Policy>, events: VaultEvents) -> Self {
+        Self {
+            vault,
+            policy,
+            events,
+            cache: Mutex::default(),
+        }
+    }
+
+    fn db(&self) -> &Database {
+        self.vault.database()
     }
 
     /// Create a new connection
     pub async fn create_connection(&self, input: CreateConnectionInput) -> Result<ConnectionInfo> {
-        info!("Creating new connection: {}", input.name);
-
-        // Get the master key (requires application to be unlocked)
-        let master_key = self.auth.get_master_key().await?;
-
-        // Create connection object
-        let connection = Connection::new(input)?;
-
-        // Encrypt credentials
-        let (encrypted_credentials, nonce) = connection.encrypt_credentials(&master_key)?;
-
-        // Store in database
-        self.db
-            .create_connection(
-                &connection.id,
-                &connection.name,
-                connection.protocol.as_str(),
-                &connection.hostname,
-                connection.port,
-                &connection.username,
-                &encrypted_credentials,
-                &nonce,
-                connection.metadata.color.as_deref(),
-                connection.metadata.icon.as_deref(),
-                connection.metadata.folder.as_deref(),
-                connection.metadata.notes.as_deref(),
-                connection.ssh_keep_alive_override.as_deref(),
-                connection.ssh_keep_alive_interval,
-                connection.created_at,
-                connection.updated_at,
-            )
-            .await?;
+        self.policy.check_credentials(&input.auth_method)?;
 
-        debug!("Connection created with ID: {}", connection.id);
+        let folders = self
+            .folders_before(input.folder_id.is_none() && input.folder.is_some())
+            .await?;
+        let tags = self.tags_before(!input.tags.is_empty()).await?;
+        let connection = self.vault.create_connection(input).await?;
+        self.invalidate_cache();
+        self.events
+            .connections(ChangeKind::Created, vec![connection.id.clone()]);
+        self.folders_created(folders).await;
+        self.tags_created(tags).await;
         Ok(connection.to_info())
     }
 
+    /// Import SSH config entries as connections
+    ///
+    /// ProxyJump hops become the connection's jump hosts. A hop that is
+    /// already saved (same hostname, port and user) is reused; others are
+    /// created, going through the hops before them. Entries that fail are
+    /// logged and skipped.
+    pub async fn import_ssh_config(
+        &self,
+        entries: &[SshConfigEntry],
+    ) -> Result<Vec<ConnectionInfo>> {
+        let mut saved = self.get_all_connections().await?;
+        let mut imported: Vec<ConnectionInfo> = Vec::new();
+        let mut hop_ids = Vec::new();
+
+        for entry in entries {
+            // Selected too, but already created as another entry's hop
+            let created_as_hop = imported
+                .iter()
+                .any(|info| hop_ids.contains(&info.id) && same_host(info, entry));
+            if created_as_hop {
+                continue;
+            }
+
+            let mut jump_hosts = Vec::new();
+            let mut failed = None;
+            for hop in &entry.proxy_jump {
+                let hop = hop.to_entry();
+                if let Some(info) = saved.iter().find(|info| same_host(info, &hop)) {
+                    jump_hosts.push(info.id.clone());
+                    continue;
+                }
+                match self.create_imported(&hop, jump_hosts.clone()).await {
+                    Ok(info) => {
+                        jump_hosts.push(info.id.clone());
+                        hop_ids.push(info.id.clone());
+                        saved.push(info.clone());
+                        imported.push(info);
+                    }
+                    Err(e) => {
+                        failed = Some(e);
+                        break;
+                    }
+                }
+            }
+
+            let result = match failed {
+                Some(e) => Err(e),
+                None => self.create_imported(entry, jump_hosts).await,
+            };
+            match result {
+                Ok(info) => {
+                    saved.push(info.clone());
+                    imported.push(info);
+                }
+                Err(e) => {
+                    warn!("Failed to import entry '{}': {}", entry.host, e);
+                }
+            }
+        }
+
+        Ok(imported)
+    }
+
+    async fn create_imported(
+        &self,
+        entry: &SshConfigEntry,
+        jump_hosts: Vec<String>,
+    ) -> Result<ConnectionInfo> {
+        let mut input = CreateConnectionInput::from(entry);
+        if !jump_hosts.is_empty() {
+            input.session_options = Some(SessionOptions {
+                jump_hosts,
+                ..SessionOptions::default()
+            });
+        }
+        self.create_connection(input).await
+    }
+
+    /// Copy a connection (credentials included); the copy is named
+    /// "<name> (copy)" unless a name is given
+    pub async fn duplicate_connection(
+        &self,
+        id: &str,
+        name: Option<String>,
+    ) -> Result<ConnectionInfo> {
+        let connection = self
+            .vault
+            .get_connection(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection not found"))?;
+        let name = name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| format!("{} (copy)", connection.name));
+
+        info!("Duplicating connection {} as {}", connection.name, name);
+        self.create_connection(connection.duplicate_input(name))
+            .await
+    }
+
     /// Get all connections (without decrypted credentials)
     pub async fn get_all_connections(&self) -> Result<Vec<ConnectionInfo>> {
-        debug!("Fetching all connections");
-        let rows = self.db.get_all_connections().await?;
-        let connections: Vec<ConnectionInfo> =
-            rows.iter().map(|row| self.row_to_info(row)).collect();
-        Ok(connections)
+        Ok(self.cached_connections().await?.to_vec())
+    }
+
+    /// Count saved connections
+    pub async fn count_connections(&self) -> Result<usize> {
+        Ok(self.cached_connections().await?.len())
     }
 
     /// Get connection by ID (with decrypted credentials)
     pub async fn get_connection(&self, id: &str) -> Result<Option<Connection>> {
         debug!("Fetching connection: {}", id);
-        let row = self.db.get_connection(id).await?;
+        self.vault.get_connection(id).await
+    }
 
-        match row {
-            Some(row) => {
-                let master_key = self.auth.get_master_key().await?;
-                let connection = self.row_to_connection(&row, &master_key)?;
-                Ok(Some(connection))
-            }
-            None => Ok(None),
-        }
+    /// Full-text search (name, hostname, username, notes, folder, tags),
+    /// best matches first
+    pub async fn search_connections(
+        &self,
+        query: &str,
+        limit: Option<u32>,
+    ) -> Result<Vec<ConnectionInfo>> {
+        self.vault.search_connections(query, limit).await
+    }
+
+    /// Usage statistics of a connection
+    pub async fn get_connection_stats(&self, id: &str) -> Result<ConnectionStats> {
+        self.vault.connection_stats(id).await
+    }
+
+    /// Latest sessions of a connection, most recent first
+    pub async fn get_connection_history(&self, id: &str, limit: i64) -> Result<Vec<SessionRecord>> {
+        self.vault.connection_history(id, limit).await
     }
 
-    /// Get connections by folder
-    pub async fn get_connections_by_folder(&self, folder: &str) -> Result<Vec<ConnectionInfo>> {
-        debug!("Fetching connections in folder: {}", folder);
-        let rows = self.db.get_connections_by_folder(folder).await?;
-        let connections: Vec<ConnectionInfo> =
-            rows.iter().map(|row| self.row_to_info(row)).collect();
+    /// Connections with the most sessions in the last `days` days
+    pub async fn get_most_used_connections(
+        &self,
+        days: i64,
+        limit: i64,
+    ) -> Result<Vec<ConnectionUsage>> {
+        self.vault.most_used_connections(days, limit).await
+    }
+
+    /// Connections by their last session, most recent first
+    pub async fn get_recent_connections(&self, limit: i64) -> Result<Vec<ConnectionUsage>> {
+        self.vault.recent_connections(limit).await
+    }
+
+    /// Get the connections in a folder (and its subfolders if `recursive`)
+    pub async fn get_connections_by_folder(
+        &self,
+        folder_id: &str,
+        recursive: bool,
+    ) -> Result<Vec<ConnectionInfo>> {
+        debug!("Fetching connections in folder: {}", folder_id);
+        let folders = if recursive {
+            self.vault.folder_subtree(folder_id).await?
+        } else {
+            vec![folder_id.to_string()]
+        };
+        Ok(self
+            .cached_connections()
+            .await?
+            .iter()
+            .filter(|connection| {
+                connection
+                    .folder_id
+                    .as_ref()
+                    .is_some_and(|id| folders.contains(id))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Drop the cached listing (after any write to the connections table)
+    pub fn invalidate_cache(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.connections = None;
+        cache.generation += 1;
+    }
+
+    /// All connections, from the cache or loaded from the vault
+    async fn cached_connections(&self) -> Result<Arc<Vec<ConnectionInfo>>> {
+        let generation = {
+            let cache = self.cache.lock().unwrap();
+            if let Some(connections) = &cache.connections {
+                return Ok(Arc::clone(connections));
+            }
+            cache.generation
+        };
+
+        debug!("Loading connection listing");
+        let connections = Arc::new(self.vault.list_connections().await?);
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.generation == generation {
+            cache.connections = Some(Arc::clone(&connections));
+        }
         Ok(connections)
     }
 
     /// Update a connection
     pub async fn update_connection(&self, input: UpdateConnectionInput) -> Result<ConnectionInfo> {
-        info!("Updating connection: {}", input.id);
+        let folders = self
+            .folders_before(input.folder_id.is_none() && input.folder.is_some())
+            .await?;
+        let tags = self.tags_before(input.tags.is_some()).await?;
+
+        // The merged connection is checked against the policy before saving
+        let connection = self
+            .vault
+            .update_connection_with(input, |connection| {
+                self.policy.check_credentials(&connection.auth_method)
+            })
+            .await?;
+        self.invalidate_cache();
+
+        debug!("Connection updated: {}", connection.id);
+        self.events
+            .connections(ChangeKind::Updated, vec![connection.id.clone()]);
+        self.folders_created(folders).await;
+        self.tags_created(tags).await;
+        Ok(connection.to_info())
+    }
+
+    /// Folder IDs before a write that may create folders (from a path)
+    async fn folders_before(&self, by_path: bool) -> Result<Option<HashSet<String>>> {
+        if !by_path {
+            return Ok(None);
+        }
+        let folders = self.vault.list_folders().await?;
+        Ok(Some(folders.into_iter().map(|folder| folder.id).collect()))
+    }
+
+    /// Emit an event for the folders created since `folders_before`
+    async fn folders_created(&self, before: Option<HashSet<String>>) {
+        let Some(before) = before else {
+            return;
+        };
+        if let Ok(folders) = self.vault.list_folders().await {
+            let created = folders
+                .into_iter()
+                .map(|folder| folder.id)
+                .filter(|id| !before.contains(id))
+                .collect();
+            self.events.folders(ChangeKind::Created, created);
+        }
+    }
+
+    /// Tag IDs before a write that may create tags
+    async fn tags_before(&self, tagging: bool) -> Result<Option<HashSet<String>>> {
+        if !tagging {
+            return Ok(None);
+        }
+        let tags = self.vault.list_tags().await?;
+        Ok(Some(tags.into_iter().map(|tag| tag.id).collect()))
+    }
+
+    /// Emit an event for the tags created since `tags_before`
+    async fn tags_created(&self, before: Option<HashSet<String>>) {
+        let Some(before) = before else {
+            return;
+        };
+        if let Ok(tags) = self.vault.list_tags().await {
+            let created = tags
+                .into_iter()
+                .map(|tag| tag.id)
+                .filter(|id| !before.contains(id))
+                .collect();
+            self.events.tags(ChangeKind::Created, created);
+        }
+    }
+
+    /// Delete a connection (moved to the trash, see `restore_connection`)
+    pub async fn delete_connection(&self, id: &str) -> Result<()> {
+        self.vault.delete_connection(id).await?;
+        self.invalidate_cache();
+        debug!("Connection moved to the trash: {}", id);
+        self.events
+            .connections(ChangeKind::Deleted, vec![id.to_string()]);
+        Ok(())
+    }
+
+    /// Connections in the trash, most recently deleted first
+    pub async fn list_trashed_connections(&self) -> Result<Vec<ConnectionInfo>> {
+        self.vault.list_trash().await
+    }
 
-        // Get existing connection
-        let mut connection = self
-            .get_connection(&input.id)
+    /// Take a connection out of the trash
+    pub async fn restore_connection(&self, id: &str) -> Result<ConnectionInfo> {
+        let connection = self.vault.restore_connection(id).await?;
+        self.invalidate_cache();
+        self.events
+            .connections(ChangeKind::Created, vec![connection.id.clone()]);
+        Ok(connection)
+    }
+
+    /// Permanently delete the connections in the trash
+    pub async fn purge_trash(&self) -> Result<Vec<String>> {
+        self.vault.purge_trash().await
+    }
+
+    /// Move a connection to a folder (None = top level)
+    pub async fn move_connection_to_folder(
+        &self,
+        id: &str,
+        folder_id: Option<&str>,
+    ) -> Result<ConnectionInfo> {
+        let connection = self.vault.move_connection_to_folder(id, folder_id).await?;
+        self.invalidate_cache();
+        self.events
+            .connections(ChangeKind::Updated, vec![connection.id.clone()]);
+        Ok(connection)
+    }
+
+    /// All folders, in display order
+    pub async fn list_folders(&self) -> Result<Vec<Folder>> {
+        self.vault.list_folders().await
+    }
+
+    /// Folder tree with paths and connection counts
+    pub async fn folder_tree(&self) -> Result<Vec<FolderNode>> {
+        self.vault.folder_tree().await
+    }
+
+    /// Create a folder
+    pub async fn create_folder(&self, input: CreateFolderInput) -> Result<Folder> {
+        let folder = self.vault.create_folder(input).await?;
+        self.events
+            .folders(ChangeKind::Created, vec![folder.id.clone()]);
+        Ok(folder)
+    }
+
+    /// Rename, recolor or reorder a folder
+    pub async fn update_folder(&self, input: UpdateFolderInput) -> Result<Folder> {
+        let folder = self.vault.update_folder(input).await?;
+        // Listed connections carry the folder path
+        self.invalidate_cache();
+        self.events
+            .folders(ChangeKind::Updated, vec![folder.id.clone()]);
+        Ok(folder)
+    }
+
+    /// Move a folder under another one (None = top level)
+    pub async fn move_folder(
+        &self,
+        id: &str,
+        parent_id: Option<&str>,
+        sort_order: Option<i64>,
+    ) -> Result<Folder> {
+        let folder = self.vault.move_folder(id, parent_id, sort_order).await?;
+        self.invalidate_cache();
+        self.events
+            .folders(ChangeKind::Updated, vec![folder.id.clone()]);
+        Ok(folder)
+    }
+
+    /// Delete a folder, moving its connections and subfolders to `move_to`
+    /// (None = top level)
+    pub async fn delete_folder(&self, id: &str, move_to: Option<&str>) -> Result<()> {
+        let subfolders: Vec<String> = self
+            .vault
+            .list_folders()
+            .await?
+            .into_iter()
+            .filter(|folder| folder.parent_id.as_deref() == Some(id))
+            .map(|folder| folder.id)
+            .collect();
+
+        let moved = self.vault.delete_folder(id, move_to).await?;
+        self.invalidate_cache();
+        self.events
+            .folders(ChangeKind::Deleted, vec![id.to_string()]);
+        self.events.folders(ChangeKind::Updated, subfolders);
+        self.events.connections(ChangeKind::Updated, moved);
+        Ok(())
+    }
+
+    /// All tags, by name, with their connection counts
+    pub async fn list_tags(&self) -> Result<Vec<Tag>> {
+        self.vault.list_tags().await
+    }
+
+    /// Create a tag
+    pub async fn create_tag(&self, input: CreateTagInput) -> Result<Tag> {
+        let tag = self.vault.create_tag(input).await?;
+        self.events.tags(ChangeKind::Created, vec![tag.id.clone()]);
+        Ok(tag)
+    }
+
+    /// Rename or recolor a tag
+    pub async fn update_tag(&self, input: UpdateTagInput) -> Result<Tag> {
+        let tag = self.vault.update_tag(input).await?;
+        // Listed connections carry the tag names
+        self.invalidate_cache();
+        self.events.tags(ChangeKind::Updated, vec![tag.id.clone()]);
+        Ok(tag)
+    }
+
+    /// Delete a tag, removing it from its connections
+    pub async fn delete_tag(&self, id: &str) -> Result<()> {
+        let untagged = self.vault.delete_tag(id).await?;
+        self.invalidate_cache();
+        self.events.tags(ChangeKind::Deleted, vec![id.to_string()]);
+        self.events.connections(ChangeKind::Updated, untagged);
+        Ok(())
+    }
+
+    /// Connections tagged with all (AND) or any (OR) of `tags`
+    /// (case-insensitive; no tags matches every connection)
+    pub async fn search_connections_by_tags(
+        &self,
+        tags: &[String],
+        mode: TagMatch,
+    ) -> Result<Vec<ConnectionInfo>> {
+        let tags = rite_vault::tag::normalize_names(tags)?;
+        Ok(self
+            .cached_connections()
+            .await?
+            .iter()
+            .filter(|connection| {
+                let tagged = |tag: &String| {
+                    connection
+                        .tags
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(tag))
+                };
+                match mode {
+                    TagMatch::All => tags.iter().all(tagged),
+                    TagMatch::Any => tags.is_empty() || tags.iter().any(tagged),
+                }
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Decrypt a connection's credentials for display
+    ///
+    /// The caller must have re-authenticated. Every reveal is audit-logged,
+    /// and nothing is kept after the credentials are returned.
+    pub async fn reveal_credentials(&self, id: &str) -> Result<AuthMethod> {
+        let connection = self
+            .get_connection(id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Connection not found"))?;
 
-        // Update fields
-        connection.update(input)?;
-
-        // Get master key and re-encrypt credentials
-        let master_key = self.auth.get_master_key().await?;
-        let (encrypted_credentials, nonce) = connection.encrypt_credentials(&master_key)?;
-
-        // Update in database
-        self.db
-            .update_connection(
-                &connection.id,
-                &connection.name,
-                connection.protocol.as_str(),
-                &connection.hostname,
-                connection.port,
-                &connection.username,
-                &encrypted_credentials,
-                &nonce,
-                connection.metadata.color.as_deref(),
-                connection.metadata.icon.as_deref(),
-                connection.metadata.folder.as_deref(),
-                connection.metadata.notes.as_deref(),
-                connection.ssh_keep_alive_override.as_deref(),
-                connection.ssh_keep_alive_interval,
-                connection.updated_at,
+        audit::record(
+            self.db().pool(),
+            audit::EVENT_CREDENTIALS_REVEALED,
+            &format!("{} ({})", connection.name, connection.id),
+        )
+        .await?;
+
+        Ok(connection.auth_method)
+    }
+
+    /// Move a connection's key file into the vault
+    ///
+    /// The key is checked (and the passphrase with it) before it is stored;
+    /// the file on disk is left as it is.
+    pub async fn import_private_key(
+        &self,
+        id: &str,
+        key_path: &str,
+        passphrase: Option<String>,
+    ) -> Result<ConnectionInfo> {
+        let metadata = tokio::fs::metadata(key_path).await?;
+        if metadata.len() > MAX_KEY_FILE_LEN {
+            return Err(anyhow::anyhow!("Not a private key file (too large)"));
+        }
+        let data = Zeroizing::new(tokio::fs::read(key_path).await?);
+        let private_key = std::str::from_utf8(&data)
+            .map_err(|_| anyhow::anyhow!("Key file is not a text key file"))?;
+
+        let info = self.store_private_key(id, private_key, passphrase).await?;
+        info!("Private key imported into the vault for {}", info.name);
+        Ok(info)
+    }
+
+    /// Make a connection authenticate with a key kept in the vault
+    pub async fn store_private_key(
+        &self,
+        id: &str,
+        private_key: &str,
+        passphrase: Option<String>,
+    ) -> Result<ConnectionInfo> {
+        let connection = self
+            .get_connection(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection not found"))?;
+
+        let passphrase = passphrase.filter(|p| !p.is_empty());
+        decode_private_key(private_key, passphrase.as_deref())?;
+
+        self.update_connection(UpdateConnectionInput {
+            id: connection.id,
+            name: None,
+            protocol: None,
+            hostname: None,
+            port: None,
+            username: None,
+            auth_method: Some(AuthMethod::StoredKey {
+                private_key: private_key.to_string(),
+                passphrase,
+            }),
+            proxy_password: None,
+            color: None,
+            icon: None,
+            folder_id: None,
+            folder: None,
+            notes: None,
+            tags: None,
+            ssh_keep_alive_override: None,
+            ssh_keep_alive_interval: None,
+            session_options: None,
+            revision: connection.revision,
+        })
+        .await
+    }
+
+    /// Write a vault-stored key back to a file (owner-only permissions)
+    ///
+    /// The caller must have checked a capability token. Existing files are
+    /// never overwritten, and every export is audit-logged.
+    pub async fn export_private_key(&self, id: &str, path: &str) -> Result<()> {
+        let connection = self
+            .get_connection(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection not found"))?;
+        let AuthMethod::StoredKey { private_key, .. } = &connection.auth_method else {
+            return Err(anyhow::anyhow!("Connection has no key stored in the vault"));
+        };
+
+        write_private_file(Path::new(path), private_key.as_bytes())?;
+
+        audit::record(
+            self.db().pool(),
+            audit::EVENT_KEY_EXPORTED,
+            &format!("{} ({}) to {}", connection.name, connection.id, path),
+        )
+        .await?;
+        info!("Private key of {} exported", connection.name);
+        Ok(())
+    }
+
+    /// Create a connection template
+    pub async fn create_template(&self, input: CreateTemplateInput) -> Result<ConnectionTemplate> {
+        info!("Creating connection template: {}", input.name);
+
+        let template = ConnectionTemplate::new(input)?;
+        let tags = serde_json::to_string(&template.tags)?;
+        let session_options = template.session_options.to_json()?;
+
+        self.db()
+            .create_connection_template(
+                &template.id,
+                &template.name,
+                &template.protocol,
+                template.port,
+                &template.username,
+                &template.auth_type,
+                template.key_path.as_deref(),
+                template.color.as_deref(),
+                template.icon.as_deref(),
+                template.folder.as_deref(),
+                template.notes.as_deref(),
+                Some(&tags),
+                template.ssh_keep_alive_override.as_deref(),
+                template.ssh_keep_alive_interval,
+                Some(&session_options),
+                template.created_at,
+                template.updated_at,
             )
             .await?;
 
-        debug!("Connection updated: {}", connection.id);
-        Ok(connection.to_info())
+        debug!("Connection template created with ID: {}", template.id);
+        Ok(template)
     }
 
-    /// Delete a connection
-    pub async fn delete_connection(&self, id: &str) -> Result<()> {
-        info!("Deleting connection: {}", id);
-        self.db.delete_connection(id).await?;
-        debug!("Connection deleted: {}", id);
+    /// Make a template from an existing connection (host and secrets left
+    /// out)
+    pub async fn create_template_from_connection(
+        &self,
+        id: &str,
+        name: String,
+    ) -> Result<ConnectionTemplate> {
+        let connection = self
+            .vault
+            .get_connection(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Connection not found"))?;
+        self.create_template(CreateTemplateInput::from_connection(&connection, name)?)
+            .await
+    }
+
+    /// Get all connection templates
+    pub async fn get_all_templates(&self) -> Result<Vec<ConnectionTemplate>> {
+        debug!("Fetching all connection templates");
+        let rows = self.db().get_all_connection_templates().await?;
+        Ok(rows.iter().map(ConnectionTemplate::from_row).collect())
+    }
+
+    /// Delete a connection template (connections created from it are kept)
+    pub async fn delete_template(&self, id: &str) -> Result<()> {
+        info!("Deleting connection template: {}", id);
+        self.db().delete_connection_template(id).await?;
         Ok(())
     }
 
-    // Helper methods
+    /// Create a connection to `host` from a template
+    pub async fn create_from_template(
+        &self,
+        template_id: &str,
+        host: &str,
+        name: Option<String>,
+    ) -> Result<ConnectionInfo> {
+        let row = self
+            .db()
+            .get_connection_template(template_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Template not found"))?;
+        let template = ConnectionTemplate::from_row(&row);
+
+        info!(
+            "Creating connection to {} from template: {}",
+            host, template.name
+        );
+        self.create_connection(template.to_connection_input(host, name)?)
+            .await
+    }
+
+    /// Create a command snippet
+    pub async fn create_snippet(&self, input: CreateSnippetInput) -> Result<Snippet> {
+        self.vault.create_snippet(input).await
+    }
+
+    /// Get all command snippets (decrypted)
+    pub async fn get_all_snippets(&self) -> Result<Vec<Snippet>> {
+        debug!("Fetching all snippets");
+        self.vault.list_snippets().await
+    }
+
+    /// Get a command snippet (decrypted)
+    pub async fn get_snippet(&self, id: &str) -> Result<Snippet> {
+        self.vault
+            .get_snippet(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Snippet not found"))
+    }
+
+    /// Update a command snippet
+    pub async fn update_snippet(&self, input: UpdateSnippetInput) -> Result<Snippet> {
+        self.vault.update_snippet(input).await
+    }
+
+    /// Delete a command snippet
+    pub async fn delete_snippet(&self, id: &str) -> Result<()> {
+        self.vault.delete_snippet(id).await
+    }
+
+    /// Create a remote task
+    pub async fn create_remote_task(&self, input: CreateRemoteTaskInput) -> Result<RemoteTask> {
+        self.vault.create_remote_task(input).await
+    }
+
+    /// Get all remote tasks (decrypted)
+    pub async fn get_all_remote_tasks(&self) -> Result<Vec<RemoteTask>> {
+        debug!("Fetching all remote tasks");
+        self.vault.list_remote_tasks().await
+    }
+
+    /// Get a remote task (decrypted)
+    pub async fn get_remote_task(&self, id: &str) -> Result<RemoteTask> {
+        self.vault
+            .get_remote_task(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Remote task not found"))
+    }
 
-    /// Convert database row to Connection (with decrypted credentials)
-    fn row_to_connection(
+    /// Update a remote task
+    pub async fn update_remote_task(&self, input: UpdateRemoteTaskInput) -> Result<RemoteTask> {
+        self.vault.update_remote_task(input).await
+    }
+
+    /// Delete a remote task and its runs
+    pub async fn delete_remote_task(&self, id: &str) -> Result<()> {
+        self.vault.delete_remote_task(id).await
+    }
+
+    /// Record a finished run of a remote task
+    pub async fn record_remote_task_run(&self, run: &RemoteTaskRun) -> Result<()> {
+        self.vault.record_remote_task_run(run).await
+    }
+
+    /// Latest runs of a remote task (decrypted output), most recent first
+    pub async fn get_remote_task_runs(
         &self,
-        row: &ConnectionRow,
-        master_key: &crate::auth::MasterKey,
-    ) -> Result<Connection> {
-        let protocol = crate::connection::Protocol::from_str(&row.protocol)?;
-        let auth_method =
-            Connection::decrypt_credentials(&row.encrypted_credentials, &row.nonce, master_key)?;
-
-        Ok(Connection {
-            id: row.id.clone(),
-            name: row.name.clone(),
-            protocol,
-            hostname: row.hostname.clone(),
-            port: row.port as u16,
-            username: row.username.clone(),
-            auth_method,
-            metadata: crate::connection::ConnectionMetadata {
-                color: row.color.clone(),
-                icon: row.icon.clone(),
-                folder: row.folder.clone(),
-                notes: row.notes.clone(),
+        task_id: &str,
+        limit: i64,
+    ) -> Result<Vec<RemoteTaskRun>> {
+        self.vault.remote_task_runs(task_id, limit).await
+    }
+}
+
+/// Whether a saved connection is the host of an SSH config entry
+fn same_host(info: &ConnectionInfo, entry: &SshConfigEntry) -> bool {
+    let hostname = entry.hostname.as_deref().unwrap_or(&entry.host);
+    info.protocol == "ssh"
+        && info.hostname.eq_ignore_ascii_case(hostname)
+        && info.port == entry.port.unwrap_or(22)
+        && entry
+            .user
+            .as_deref()
+            .is_none_or(|user| user == info.username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn create_test_manager() -> (ConnectionsManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let vault = Vault::open(&temp_dir.path().join("vault.db"))
+            .await
+            .unwrap();
+        vault.initialize("MyStr0ng!P@ssw0rd#2024").await.unwrap();
+        let manager =
+            ConnectionsManager::new(vault, Arc::new(Policy::default()), VaultEvents::new());
+        (manager, temp_dir)
+    }
+
+    fn input(name: &str, folder: Option<&str>) -> CreateConnectionInput {
+        CreateConnectionInput {
+            name: name.to_string(),
+            protocol: "ssh".to_string(),
+            hostname: format!("{}.example.com", name),
+            port: 22,
+            username: "admin".to_string(),
+            auth_method: AuthMethod::Password {
+                password: "hunter2".to_string(),
             },
-            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
-            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-            last_used_at: row.last_used_at,
-        })
+            proxy_password: None,
+            color: None,
+            icon: None,
+            folder_id: None,
+            folder: folder.map(String::from),
+            notes: None,
+            tags: Vec::new(),
+            ssh_keep_alive_override: None,
+            ssh_keep_alive_interval: None,
+            session_options: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listing_cache() {
+        let (manager, _temp) = create_test_manager().await;
+        let web = manager
+            .create_connection(input("web", Some("Prod")))
+            .await
+            .unwrap();
+        manager.create_connection(input("db", None)).await.unwrap();
+
+        assert_eq!(manager.count_connections().await.unwrap(), 2);
+        let prod_id = web.folder_id.clone().unwrap();
+        let prod = manager
+            .get_connections_by_folder(&prod_id, false)
+            .await
+            .unwrap();
+        assert_eq!(prod.len(), 1);
+        assert_eq!(prod[0].id, web.id);
+
+        // Writes that bypass the manager are only seen after invalidation
+        manager.db().delete_connection(&web.id).await.unwrap();
+        assert_eq!(manager.count_connections().await.unwrap(), 2);
+        manager.invalidate_cache();
+        assert_eq!(manager.count_connections().await.unwrap(), 1);
+
+        // Writes through the manager invalidate it themselves
+        manager
+            .create_connection(input("cache", Some("Prod")))
+            .await
+            .unwrap();
+        let all = manager.get_all_connections().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(
+            manager
+                .get_connections_by_folder(&prod_id, false)
+                .await
+                .unwrap()[0]
+                .name,
+            "cache"
+        );
+
+        // Renaming a folder updates the listed paths
+        manager
+            .update_folder(UpdateFolderInput {
+                id: prod_id.clone(),
+                name: Some("Production".to_string()),
+                color: None,
+                sort_order: None,
+            })
+            .await
+            .unwrap();
+        let all = manager.get_all_connections().await.unwrap();
+        assert!(all
+            .iter()
+            .any(|c| c.folder.as_deref() == Some("Production")));
+    }
+
+    #[tokio::test]
+    async fn test_import_proxy_jump() {
+        let (manager, _temp_dir) = create_test_manager().await;
+        let existing = manager
+            .create_connection(input("outer", None))
+            .await
+            .unwrap();
+
+        let entries = crate::ssh_config::parse_ssh_config_str(
+            "Host outer\n    HostName outer.example.com\n    User admin\n\
+             Host bastion\n    HostName 10.0.0.1\n    ProxyJump outer\n\
+             Host app\n    HostName app.internal\n    ProxyJump bastion\n",
+        );
+        let app = entries.iter().find(|e| e.host == "app").unwrap();
+        let bastion = entries.iter().find(|e| e.host == "bastion").unwrap();
+
+        // app first: bastion is created as its hop, then not imported twice
+        let imported = manager
+            .import_ssh_config(&[app.clone(), bastion.clone()])
+            .await
+            .unwrap();
+        let names: Vec<_> = imported.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(names, ["bastion", "app"]);
+        assert_eq!(
+            imported[0].session_options.jump_hosts,
+            vec![existing.id.clone()]
+        );
+        assert_eq!(
+            imported[1].session_options.jump_hosts,
+            vec![existing.id, imported[0].id.clone()]
+        );
+        assert_eq!(manager.count_connections().await.unwrap(), 3);
     }
 
-    /// Convert database row to ConnectionInfo (without credentials)
-    fn row_to_info(&self, row: &ConnectionRow) -> ConnectionInfo {
-        // Determine auth type from encrypted credentials (we can't decrypt without master key)
-        // For now, we'll parse the encrypted JSON to get the type
-        // In production, you might want to store the auth type separately
-        let auth_type = "password".to_string(); // Default, will be overridden if we can determine
-
-        ConnectionInfo {
-            id: row.id.clone(),
-            name: row.name.clone(),
-            protocol: row.protocol.clone(),
-            hostname: row.hostname.clone(),
-            port: row.port as u16,
-            username: row.username.clone(),
-            auth_type,
-            color: row.color.clone(),
-            icon: row.icon.clone(),
-            folder: row.folder.clone(),
-            notes: row.notes.clone(),
-            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
-            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-            last_used_at: row.last_used_at,
+    #[tokio::test]
+    async fn test_duplicate_and_template_from_connection() {
+        let (manager, _temp_dir) = create_test_manager().await;
+        let mut web = input("web", Some("Production/Web"));
+        web.tags = vec!["prod".to_string()];
+        let web = manager.create_connection(web).await.unwrap();
+
+        let copy = manager.duplicate_connection(&web.id, None).await.unwrap();
+        assert_ne!(copy.id, web.id);
+        assert_eq!(copy.name, "web (copy)");
+        assert_eq!(copy.hostname, web.hostname);
+        assert_eq!(copy.folder_id, web.folder_id);
+        assert_eq!(copy.tags, ["prod"]);
+        let stored = manager.get_connection(&copy.id).await.unwrap().unwrap();
+        assert!(
+            matches!(stored.auth_method, AuthMethod::Password { ref password } if password == "hunter2")
+        );
+
+        let template = manager
+            .create_template_from_connection(&web.id, "Web servers".to_string())
+            .await
+            .unwrap();
+        assert_eq!(template.folder.as_deref(), Some("Production/Web"));
+        let created = manager
+            .create_from_template(&template.id, "web-02.example.com", None)
+            .await
+            .unwrap();
+        assert_eq!(created.folder_id, web.folder_id);
+        assert_eq!(created.username, "admin");
+    }
+
+    #[tokio::test]
+    async fn test_tag_search() {
+        let (manager, _temp) = create_test_manager().await;
+        for (name, tags) in [("web", ["prod", "web"]), ("db", ["prod", "db"])] {
+            manager
+                .create_connection(CreateConnectionInput {
+                    tags: tags.map(String::from).to_vec(),
+                    ..input(name, None)
+                })
+                .await
+                .unwrap();
         }
+        let search = |tags: &[&str], mode| {
+            let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+            let manager = &manager;
+            async move {
+                let found = manager
+                    .search_connections_by_tags(&tags, mode)
+                    .await
+                    .unwrap();
+                found.into_iter().map(|c| c.name).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(search(&["Prod", "db"], TagMatch::All).await, ["db"]);
+        assert_eq!(search(&["web", "db"], TagMatch::Any).await.len(), 2);
+
+        // Renaming a tag updates the cached listing
+        let db_tag = manager
+            .list_tags()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|tag| tag.name == "db")
+            .unwrap();
+        manager
+            .update_tag(UpdateTagInput {
+                id: db_tag.id,
+                name: Some("database".to_string()),
+                color: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(search(&["database"], TagMatch::All).await, ["db"]);
+        assert!(search(&["db"], TagMatch::All).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_private_key_import_export() {
+        let (manager, temp_dir) = create_test_manager().await;
+        let web = manager.create_connection(input("web", None)).await.unwrap();
+        let key =
+            crate::keygen::generate(crate::keygen::KeyType::Ed25519, None, Some("correct horse"))
+                .unwrap();
+        let key_path = temp_dir.path().join("id_ed25519");
+        std::fs::write(&key_path, key.private_key.as_bytes()).unwrap();
+        let key_path = key_path.to_str().unwrap();
+
+        // Nothing to export until a key is stored
+        let export_path = temp_dir.path().join("exported");
+        let export_path = export_path.to_str().unwrap();
+        assert!(manager
+            .export_private_key(&web.id, export_path)
+            .await
+            .is_err());
+
+        // The passphrase is checked before the key is stored
+        assert!(manager
+            .import_private_key(&web.id, key_path, Some("wrong".to_string()))
+            .await
+            .is_err());
+        let info = manager
+            .import_private_key(&web.id, key_path, Some("correct horse".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(info.auth_type, "storedKey");
+        let stored = manager.get_connection(&web.id).await.unwrap().unwrap();
+        assert!(matches!(
+            stored.auth_method,
+            AuthMethod::StoredKey { ref private_key, ref passphrase }
+                if private_key == key.private_key.as_str()
+                    && passphrase.as_deref() == Some("correct horse")
+        ));
+
+        manager
+            .export_private_key(&web.id, export_path)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(export_path).unwrap(),
+            key.private_key.as_str()
+        );
+        // Never overwritten
+        assert!(manager
+            .export_private_key(&web.id, export_path)
+            .await
+            .is_err());
+
+        let entries = audit::recent(manager.db().pool(), 10).await.unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|entry| entry.event == audit::EVENT_KEY_EXPORTED)
+                .count(),
+            1
+        );
     }
 }
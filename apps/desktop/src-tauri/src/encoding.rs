@@ -0,0 +1,164 @@
+/**
+ * Encoding Module
+ *
+ * Character set conversion for sessions with legacy (non-UTF-8) hosts
+ */
+use anyhow::{anyhow, Result};
+use encoding_rs::{Decoder, Encoding, UTF_8};
+
+/// Encodings offered in the connection editor (any WHATWG label is accepted)
+pub const COMMON_ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "ISO-8859-1",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "windows-1252",
+    "KOI8-R",
+    "GBK",
+    "gb18030",
+    "Big5",
+    "Shift_JIS",
+    "EUC-JP",
+    "EUC-KR",
+];
+
+/// Look up an encoding by label (e.g. "latin1", "cp1251", "gbk")
+pub fn lookup(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| anyhow!("Unsupported encoding: {}", label))
+}
+
+/// Converts session output to UTF-8 and user input back to the host encoding
+///
+/// The decoder is stateful so multi-byte characters split across SSH packets
+/// are reassembled instead of turning into replacement characters. Input
+/// works the same way: a UTF-8 character cut off at the end of a chunk is
+/// held back until the rest of it arrives.
+pub struct EncodingConverter {
+    encoding: &'static Encoding,
+    decoder: Decoder,
+    pending: Vec<u8>,
+}
+
+impl EncodingConverter {
+    /// Create a converter (None when the host already speaks UTF-8)
+    pub fn new(label: &str) -> Result<Option<Self>> {
+        let encoding = lookup(label)?;
+        if encoding == UTF_8 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            encoding,
+            decoder: encoding.new_decoder_without_bom_handling(),
+            pending: Vec::new(),
+        }))
+    }
+
+    /// Encoding name
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    /// Convert host output to UTF-8
+    pub fn decode(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = String::with_capacity(
+            self.decoder
+                .max_utf8_buffer_length(data.len())
+                .unwrap_or(data.len() * 3),
+        );
+        let _ = self.decoder.decode_to_string(data, &mut output, false);
+        output.into_bytes()
+    }
+
+    /// Convert UTF-8 user input to the host encoding
+    ///
+    /// Characters the host encoding can't represent become HTML numeric
+    /// references, which is what encoding_rs produces for unmappables.
+    pub fn encode(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(data);
+        let complete = input.len() - incomplete_tail(&input);
+        self.pending = input.split_off(complete);
+
+        let text = String::from_utf8_lossy(&input);
+        let (bytes, _, _) = self.encoding.encode(&text);
+        bytes.into_owned()
+    }
+}
+
+/// Length of the UTF-8 character cut off at the end of `data` (0 when the
+/// last character is complete)
+fn incomplete_tail(data: &[u8]) -> usize {
+    for back in 1..=data.len().min(3) {
+        let byte = data[data.len() - back];
+        // Continuation byte: keep looking for the lead byte
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 1,
+        };
+        return if needed > back { back } else { 0 };
+    }
+    0
+}
+
+/// Convert user input for the host (pass-through when no converter is configured)
+pub fn encode_input(converter: Option<&mut EncodingConverter>, data: Vec<u8>) -> Vec<u8> {
+    match converter {
+        Some(converter) => converter.encode(&data),
+        None => data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_needs_no_converter() {
+        assert!(EncodingConverter::new("utf-8").unwrap().is_none());
+        assert!(EncodingConverter::new("not-an-encoding").is_err());
+    }
+
+    #[test]
+    fn test_cp1251_roundtrip() {
+        let mut converter = EncodingConverter::new("cp1251").unwrap().unwrap();
+        assert_eq!(converter.name(), "windows-1251");
+
+        // "Привет" in windows-1251
+        let host_bytes = [0xCF, 0xF0, 0xE8, 0xE2, 0xE5, 0xF2];
+        assert_eq!(converter.decode(&host_bytes), "Привет".as_bytes());
+        assert_eq!(converter.encode("Привет".as_bytes()), host_bytes);
+    }
+
+    #[test]
+    fn test_input_split_across_chunks() {
+        let mut converter = EncodingConverter::new("gbk").unwrap().unwrap();
+
+        // "中" is E4 B8 AD in UTF-8, D6 D0 in GBK
+        assert_eq!(converter.encode(&[b'a', 0xE4]), b"a");
+        assert!(converter.encode(&[0xB8]).is_empty());
+        assert_eq!(converter.encode(&[0xAD, b'b']), [0xD6, 0xD0, b'b']);
+
+        // Invalid input is still replaced rather than held back
+        assert_eq!(converter.encode(&[0xFF]), b"&#65533;");
+    }
+
+    #[test]
+    fn test_gbk_split_across_chunks() {
+        let mut converter = EncodingConverter::new("gbk").unwrap().unwrap();
+
+        // "中文" in GBK, split in the middle of the first character
+        let first = converter.decode(&[0xD6]);
+        let second = converter.decode(&[0xD0, 0xCE, 0xC4]);
+        assert!(first.is_empty());
+        assert_eq!(second, "中文".as_bytes());
+    }
+}
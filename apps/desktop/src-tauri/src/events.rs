@@ -0,0 +1,103 @@
+/**
+ * Vault Change Events
+ *
+ * Notifies every window after vault data is written, so open windows stay
+ * in sync without polling:
+ * - vault:connections-changed (connection IDs)
+ * - vault:settings-changed (setting keys)
+ * - vault:folders-changed (folder IDs)
+ * - vault:tags-changed (tag IDs)
+ */
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+pub const CONNECTIONS_CHANGED: &str = "vault:connections-changed";
+pub const SETTINGS_CHANGED: &str = "vault:settings-changed";
+pub const FOLDERS_CHANGED: &str = "vault:folders-changed";
+pub const TAGS_CHANGED: &str = "vault:tags-changed";
+
+/// What happened to the items in a change event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Payload of the vault change events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultChange {
+    pub change: ChangeKind,
+    /// Connection IDs, setting keys, folder IDs or tag IDs
+    pub ids: Vec<String>,
+}
+
+/// Emits vault change events once the app handle is available
+///
+/// The managers are created before Tauri starts; events written before
+/// `attach` have no window to notify and are dropped.
+#[derive(Clone, Default)]
+pub struct VaultEvents {
+    app: Arc<OnceLock<AppHandle>>,
+}
+
+impl VaultEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start emitting through the app handle (called from setup)
+    pub fn attach(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
+    pub fn connections(&self, change: ChangeKind, ids: Vec<String>) {
+        self.emit(CONNECTIONS_CHANGED, change, ids);
+    }
+
+    pub fn settings(&self, change: ChangeKind, keys: Vec<String>) {
+        self.emit(SETTINGS_CHANGED, change, keys);
+    }
+
+    pub fn folders(&self, change: ChangeKind, folders: Vec<String>) {
+        self.emit(FOLDERS_CHANGED, change, folders);
+    }
+
+    pub fn tags(&self, change: ChangeKind, tags: Vec<String>) {
+        self.emit(TAGS_CHANGED, change, tags);
+    }
+
+    fn emit(&self, event: &str, change: ChangeKind, ids: Vec<String>) {
+        if ids.is_empty() {
+            return;
+        }
+        let Some(app) = self.app.get() else {
+            return;
+        };
+
+        tracing::debug!("[events.rs] {} {:?} {:?}", event, change, ids);
+        if let Err(e) = app.emit(event, VaultChange { change, ids }) {
+            tracing::warn!("[events.rs] Failed to emit {}: {}", event, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_format() {
+        let payload = VaultChange {
+            change: ChangeKind::Deleted,
+            ids: vec!["abc".to_string()],
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({ "change": "deleted", "ids": ["abc"] })
+        );
+    }
+}
@@ -0,0 +1,322 @@
+/**
+ * Exec Channel Commands
+ *
+ * Runs a single command on a saved SSH connection over its own transport
+ * and exec channel (no PTY, no shell session): stdout and stderr are
+ * captured separately, along with the exit status or signal. Used by the
+ * `exec_command` API, the remote task runner and host monitoring.
+ *
+ * The timeout covers the command once the channel is open; connecting is
+ * bounded by the connection's own timeouts.
+ *
+ * Nobody can confirm a command held back by the dangerous command guard
+ * here, so on protected connections matching commands are refused.
+ */
+use anyhow::{anyhow, Result};
+use russh::ChannelMsg;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::command_guard::{self, CommandGuard};
+use crate::connection::{Connection, Protocol};
+use crate::session_options::ProtectionLevel;
+use crate::session_trace::SessionTrace;
+use crate::state::AppState;
+use crate::terminal::{SshSession, SshTransport};
+
+/// Timeout of `exec_command` when none is given
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest timeout `exec_command` accepts
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Output kept per stream (the end is kept)
+const MAX_OUTPUT: usize = 1024 * 1024;
+
+/// Result of a command run on an exec channel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// None when the command was killed by a signal or timed out
+    pub exit_status: Option<u32>,
+    /// Signal that killed the command (e.g. "KILL")
+    pub exit_signal: Option<String>,
+    /// The command was still running when the timeout expired
+    pub timed_out: bool,
+    /// The start of stdout or stderr was dropped (over 1 MiB)
+    pub truncated: bool,
+    pub duration_ms: u64,
+}
+
+impl ExecOutput {
+    /// Exit status 0
+    pub fn success(&self) -> bool {
+        self.exit_status == Some(0)
+    }
+
+    /// Why the command didn't exit (timeout, signal, closed channel)
+    pub fn failure(&self) -> Option<String> {
+        if self.exit_status.is_some() {
+            None
+        } else if self.timed_out {
+            Some(format!(
+                "Timed out after {}",
+                format_duration(Duration::from_millis(self.duration_ms))
+            ))
+        } else if let Some(signal) = &self.exit_signal {
+            Some(format!("Killed by signal {}", signal))
+        } else {
+            Some("Channel closed without an exit status".to_string())
+        }
+    }
+}
+
+/// End of a stream, up to `MAX_OUTPUT`
+#[derive(Default)]
+struct Captured {
+    data: Vec<u8>,
+    truncated: bool,
+}
+
+impl Captured {
+    fn push(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+        if self.data.len() > MAX_OUTPUT {
+            let excess = self.data.len() - MAX_OUTPUT;
+            self.data.drain(..excess);
+            self.truncated = true;
+        }
+    }
+
+    fn into_string(self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+}
+
+/// Check a timeout given by the frontend (None = `DEFAULT_TIMEOUT`)
+pub fn timeout_from_secs(secs: Option<u64>) -> Result<Duration> {
+    let timeout = secs.map(Duration::from_secs).unwrap_or(DEFAULT_TIMEOUT);
+    if timeout.is_zero() || timeout > MAX_TIMEOUT {
+        return Err(anyhow!(
+            "Timeout must be between 1 second and {}",
+            format_duration(MAX_TIMEOUT)
+        ));
+    }
+    Ok(timeout)
+}
+
+/// Connect to a saved SSH connection and run a command on an exec channel
+///
+/// Errors are reserved for failures before the command starts (unknown
+/// connection, dangerous command on a protected connection, connection or
+/// authentication failure, exec refused).
+/// `background` runs skip hosts whose key isn't known instead of asking.
+pub async fn exec_command(
+    app_handle: &AppHandle,
+    connection_id: &str,
+    command: &str,
+    timeout: Duration,
+    background: bool,
+) -> Result<ExecOutput> {
+    let connection = app_handle
+        .state::<AppState>()
+        .connections
+        .get_connection(connection_id)
+        .await?
+        .filter(|connection| connection.deleted_at.is_none())
+        .ok_or_else(|| anyhow!("Connection not found"))?;
+    if !matches!(connection.protocol, Protocol::SSH) {
+        return Err(anyhow!("{} is not an SSH connection", connection.name));
+    }
+    check_protection(app_handle, &connection, command).await?;
+
+    let trace = SessionTrace::new(false);
+    let transport = SshSession::establish(
+        &connection,
+        &connection.auth_method,
+        app_handle,
+        false,
+        background,
+        &trace,
+    )
+    .await?;
+
+    let result = exec_on(&transport, command, timeout).await;
+
+    let _ = transport
+        .disconnect(russh::Disconnect::ByApplication, "", "")
+        .await;
+    result
+}
+
+/// Refuse a dangerous command on a protected connection
+async fn check_protection(
+    app_handle: &AppHandle,
+    connection: &Connection,
+    command: &str,
+) -> Result<()> {
+    if connection.session_options.protection != ProtectionLevel::Protected {
+        return Ok(());
+    }
+    let patterns = app_handle
+        .state::<AppState>()
+        .db
+        .get_setting(command_guard::SETTING_PATTERNS)
+        .await?;
+    let guard = CommandGuard::from_setting(patterns.as_deref())?;
+    match guard.check_command(command) {
+        Some(confirm) => Err(anyhow!(
+            "{} is protected and '{}' matches a dangerous command pattern",
+            connection.name,
+            confirm.command
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Audit log detail of a run: connection, command and how it ended
+pub fn audit_detail(connection_id: &str, command: &str, result: &Result<ExecOutput>) -> String {
+    let outcome = match result {
+        Ok(output) => match (output.exit_status, output.failure()) {
+            (Some(status), _) => format!("exit status {}", status),
+            (None, failure) => failure.unwrap_or_default(),
+        },
+        Err(e) => format!("not run: {}", e),
+    };
+    format!("{}: {} ({})", connection_id, command, outcome)
+}
+
+/// Run a command on an exec channel of an authenticated transport
+pub(crate) async fn exec_on(
+    transport: &SshTransport,
+    command: &str,
+    timeout: Duration,
+) -> Result<ExecOutput> {
+    let mut channel = transport.channel_open_session().await?;
+    channel.exec(true, command).await?;
+
+    let started = Instant::now();
+    let mut stdout = Captured::default();
+    let mut stderr = Captured::default();
+    let mut exit_status = None;
+    let mut exit_signal = None;
+    let collect = async {
+        while let Some(message) = channel.wait().await {
+            match message {
+                ChannelMsg::Data { data } => stdout.push(&data),
+                // Extended data type 1 is stderr (the only one defined)
+                ChannelMsg::ExtendedData { data, .. } => stderr.push(&data),
+                ChannelMsg::ExitStatus {
+                    exit_status: status,
+                } => exit_status = Some(status),
+                ChannelMsg::ExitSignal { signal_name, .. } => {
+                    exit_signal = Some(signal_name_of(&signal_name))
+                }
+                _ => {}
+            }
+        }
+    };
+    let timed_out = tokio::time::timeout(timeout, collect).await.is_err();
+    let duration = started.elapsed();
+    if timed_out {
+        let _ = channel.close().await;
+    }
+
+    Ok(ExecOutput {
+        truncated: stdout.truncated || stderr.truncated,
+        stdout: stdout.into_string(),
+        stderr: stderr.into_string(),
+        exit_status: exit_status.filter(|_| !timed_out),
+        exit_signal,
+        timed_out,
+        duration_ms: duration.as_millis() as u64,
+    })
+}
+
+/// Signal name without the `SIG` prefix
+fn signal_name_of(signal: &russh::Sig) -> String {
+    match signal {
+        russh::Sig::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// "30 minutes", "1 hour", "45 seconds"
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (value, unit) = if secs >= 3600 && secs.is_multiple_of(3600) {
+        (secs / 3600, "hour")
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        (secs / 60, "minute")
+    } else {
+        (secs, "second")
+    };
+    format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured_keeps_the_end() {
+        let mut captured = Captured::default();
+        captured.push(&vec![b'a'; MAX_OUTPUT]);
+        assert!(!captured.truncated);
+        captured.push(b"end");
+        assert!(captured.truncated);
+        let output = captured.into_string();
+        assert_eq!(output.len(), MAX_OUTPUT);
+        assert!(output.ends_with("aend"));
+    }
+
+    #[test]
+    fn test_failure() {
+        let mut output = ExecOutput {
+            exit_status: Some(2),
+            ..ExecOutput::default()
+        };
+        assert!(!output.success());
+        assert_eq!(output.failure(), None);
+
+        output.exit_status = None;
+        output.exit_signal = Some("KILL".to_string());
+        assert_eq!(output.failure().as_deref(), Some("Killed by signal KILL"));
+
+        output.timed_out = true;
+        output.duration_ms = 1_800_000;
+        assert_eq!(
+            output.failure().as_deref(),
+            Some("Timed out after 30 minutes")
+        );
+    }
+
+    #[test]
+    fn test_timeout_from_secs() {
+        assert_eq!(timeout_from_secs(None).unwrap(), DEFAULT_TIMEOUT);
+        assert_eq!(timeout_from_secs(Some(5)).unwrap(), Duration::from_secs(5));
+        assert!(timeout_from_secs(Some(0)).is_err());
+        assert!(timeout_from_secs(Some(MAX_TIMEOUT.as_secs() + 1)).is_err());
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1 hour");
+        assert_eq!(format_duration(Duration::from_secs(61)), "61 seconds");
+    }
+
+    #[test]
+    fn test_audit_detail() {
+        let output = ExecOutput {
+            exit_status: Some(0),
+            ..ExecOutput::default()
+        };
+        assert_eq!(
+            audit_detail("web", "uptime", &Ok(output)),
+            "web: uptime (exit status 0)"
+        );
+        assert_eq!(
+            audit_detail("web", "reboot", &Err(anyhow!("web is protected"))),
+            "web: reboot (not run: web is protected)"
+        );
+    }
+}
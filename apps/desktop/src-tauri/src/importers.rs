@@ -0,0 +1,901 @@
+/**
+ * Importers Module
+ *
+ * Reads the sessions saved by other SSH clients into entries that can be
+ * imported as Rite connections (see `CreateConnectionInput::from`):
+ * - PuTTY: registry export (.reg) or session files (~/.putty/sessions)
+ * - Termius: JSON export
+ * - mRemoteNG: confCons.xml (not encrypted with a master password)
+ * - SecureCRT: session .ini files (a Sessions folder or a single file)
+ *
+ * Only SSH sessions are imported. Passwords aren't: PuTTY doesn't save them
+ * and the others keep them encrypted. Free of app-internal dependencies so
+ * the fuzz targets can include it directly.
+ */
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Largest file read by an importer
+const MAX_FILE_LEN: u64 = 16 * 1024 * 1024;
+
+/// Deepest folder nesting followed (Termius groups, SecureCRT folders)
+const MAX_DEPTH: usize = 32;
+
+/// Application the sessions are imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    Putty,
+    Termius,
+    MRemoteNg,
+    SecureCrt,
+}
+
+impl ImportSource {
+    /// Display name
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportSource::Putty => "PuTTY",
+            ImportSource::Termius => "Termius",
+            ImportSource::MRemoteNg => "mRemoteNG",
+            ImportSource::SecureCrt => "SecureCRT",
+        }
+    }
+}
+
+/// Session read from another client, for preview before import
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEntry {
+    pub source: ImportSource,
+    pub name: String,
+    pub hostname: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub key_path: Option<String>,
+    /// Folder path ("Production/Databases"), created on import
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+}
+
+impl ImportEntry {
+    fn new(source: ImportSource, name: &str, hostname: &str) -> Self {
+        Self {
+            source,
+            name: name.trim().to_string(),
+            hostname: hostname.trim().to_string(),
+            port: None,
+            username: None,
+            key_path: None,
+            folder: None,
+            tags: Vec::new(),
+            notes: None,
+        }
+    }
+}
+
+/// Read the sessions at `path` (a file, or a folder of session files for
+/// PuTTY and SecureCRT)
+pub fn parse_import<P: AsRef<Path>>(source: ImportSource, path: P) -> Result<Vec<ImportEntry>> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        return match source {
+            ImportSource::Putty => parse_putty_dir(path),
+            ImportSource::SecureCrt => {
+                let mut entries = Vec::new();
+                parse_securecrt_dir(path, &[], &mut entries)?;
+                Ok(entries)
+            }
+            _ => Err(anyhow!("{} imports a file, not a folder", source.label())),
+        };
+    }
+
+    let content = read_text(path)?;
+    let stem = file_stem(path);
+    Ok(match source {
+        ImportSource::Putty => parse_putty_str(&stem, &content),
+        ImportSource::Termius => parse_termius_str(&content)?,
+        ImportSource::MRemoteNg => parse_mremoteng_str(&content)?,
+        ImportSource::SecureCrt => parse_securecrt_session(&stem, None, &content)
+            .into_iter()
+            .collect(),
+    })
+}
+
+/// Read a text file (UTF-8, or UTF-16 as written by regedit)
+fn read_text(path: &Path) -> Result<String> {
+    let len = fs::metadata(path)
+        .with_context(|| format!("Failed to read {:?}", path))?
+        .len();
+    if len > MAX_FILE_LEN {
+        return Err(anyhow!("{:?} is too large to import", path));
+    }
+    let data = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    Ok(decode_text(&data))
+}
+
+/// Decode file content, following its byte order mark
+fn decode_text(data: &[u8]) -> String {
+    if let Some(utf16) = data.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let data = data.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(data);
+    String::from_utf8_lossy(data).into_owned()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Files of a folder, sorted by name
+fn sorted_entries(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Decode %XX escapes (PuTTY session names)
+fn percent_decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let (b'%', Some(byte)) = (bytes[i], hex) {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn non_empty(value: Option<&String>) -> Option<String> {
+    value
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+// ============================================================================
+// PuTTY
+// ============================================================================
+
+/// Session files of a folder (~/.putty/sessions, names are %-encoded)
+fn parse_putty_dir(dir: &Path) -> Result<Vec<ImportEntry>> {
+    let mut entries = Vec::new();
+    for path in sorted_entries(dir)? {
+        if !path.is_file() {
+            continue;
+        }
+        let content = read_text(&path)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        entries.extend(putty_entry(
+            &percent_decode(&name),
+            &parse_putty_values(&content),
+        ));
+    }
+    Ok(entries)
+}
+
+/// Parse a PuTTY registry export, or a single session file named `name`
+pub fn parse_putty_str(name: &str, content: &str) -> Vec<ImportEntry> {
+    if !content.trim_start().starts_with("Windows Registry Editor")
+        && !content.trim_start().starts_with("REGEDIT4")
+    {
+        return putty_entry(&percent_decode(name), &parse_putty_values(content))
+            .into_iter()
+            .collect();
+    }
+
+    let mut sessions: Vec<(String, HashMap<String, String>)> = Vec::new();
+    let mut in_session = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            // [HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\My%20Server]
+            let lower = key.to_ascii_lowercase();
+            in_session = match lower.find("\\putty\\sessions\\") {
+                // Subkeys of a session aren't sessions
+                Some(start) => {
+                    let name = &key[start + "\\putty\\sessions\\".len()..];
+                    if !name.contains('\\') {
+                        sessions.push((percent_decode(name), HashMap::new()));
+                    }
+                    !name.contains('\\')
+                }
+                None => false,
+            };
+            continue;
+        }
+        if !in_session {
+            continue;
+        }
+        if let (Some((key, value)), Some((_, values))) =
+            (parse_reg_value(line), sessions.last_mut())
+        {
+            values.insert(key, value);
+        }
+    }
+
+    sessions
+        .iter()
+        .filter_map(|(name, values)| putty_entry(name, values))
+        .collect()
+}
+
+/// `"Key"="string"` or `"Key"=dword:0000001f` (as decimal)
+fn parse_reg_value(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.strip_prefix('"')?.split_once("\"=")?;
+    let value = if let Some(dword) = value.strip_prefix("dword:") {
+        u32::from_str_radix(dword.trim(), 16).ok()?.to_string()
+    } else {
+        let quoted = value.strip_prefix('"')?.strip_suffix('"')?;
+        quoted.replace("\\\"", "\"").replace("\\\\", "\\")
+    };
+    Some((key.to_string(), value))
+}
+
+/// `Key=value` lines of a session file
+fn parse_putty_values(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn putty_entry(name: &str, values: &HashMap<String, String>) -> Option<ImportEntry> {
+    // PuTTY's defaults, not a session
+    if name == "Default Settings" {
+        return None;
+    }
+    let protocol = values.get("Protocol").map(String::as_str).unwrap_or("ssh");
+    if !protocol.eq_ignore_ascii_case("ssh") {
+        return None;
+    }
+
+    // The host name may carry the user (user@host)
+    let host = non_empty(values.get("HostName"))?;
+    let (user, hostname) = match host.rsplit_once('@') {
+        Some((user, hostname)) => (Some(user.to_string()), hostname.to_string()),
+        None => (None, host),
+    };
+
+    let mut entry = ImportEntry::new(ImportSource::Putty, name, &hostname);
+    entry.port = values.get("PortNumber").and_then(|p| p.parse().ok());
+    entry.username = non_empty(values.get("UserName")).or(user);
+    entry.key_path = non_empty(values.get("PublicKeyFile"));
+    Some(entry)
+}
+
+// ============================================================================
+// Termius
+// ============================================================================
+
+/// Parse a Termius JSON export
+///
+/// Hosts are read from a `hosts` array (or a top-level array) with
+/// `label`, `address`, `port`, `username`, `group` and `tags`; a group is a
+/// path, an object with a `label`, or the ID of an entry of `groups`
+/// (nested through `parent_group`). Credentials may also sit in
+/// `ssh_config` / `identity` objects.
+pub fn parse_termius_str(content: &str) -> Result<Vec<ImportEntry>> {
+    let root: Value = serde_json::from_str(content).context("Not a Termius JSON export")?;
+    let hosts = match &root {
+        Value::Array(hosts) => hosts,
+        Value::Object(object) => object
+            .get("hosts")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("No hosts in the Termius export"))?,
+        _ => return Err(anyhow!("Not a Termius JSON export")),
+    };
+    let groups: HashMap<String, &Value> = root
+        .get("groups")
+        .and_then(Value::as_array)
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|group| Some((id_string(group.get("id")?)?, group)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(hosts
+        .iter()
+        .filter_map(|host| termius_entry(host, &groups))
+        .collect())
+}
+
+fn termius_entry(host: &Value, groups: &HashMap<String, &Value>) -> Option<ImportEntry> {
+    let ssh_config = host.get("ssh_config");
+    let identity = ssh_config
+        .and_then(|config| config.get("identity"))
+        .or_else(|| host.get("identity"));
+
+    let hostname = str_field(host, &["address", "hostname", "host"])?;
+    let name = str_field(host, &["label", "name"]).unwrap_or_else(|| hostname.clone());
+    let mut entry = ImportEntry::new(ImportSource::Termius, &name, &hostname);
+    entry.port = [Some(host), ssh_config]
+        .into_iter()
+        .flatten()
+        .find_map(|value| value.get("port")?.as_u64())
+        .and_then(|port| u16::try_from(port).ok());
+    entry.username = str_field(host, &["username", "user"])
+        .or_else(|| identity.and_then(|identity| str_field(identity, &["username"])));
+    entry.folder = host
+        .get("group")
+        .or_else(|| host.get("group_id"))
+        .and_then(|group| termius_group_path(group, groups));
+    entry.tags = host
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|tag| match tag {
+                    Value::String(label) => Some(label.trim().to_string()),
+                    tag => str_field(tag, &["label", "name"]),
+                })
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    entry.notes = str_field(host, &["notes", "description"]);
+    Some(entry)
+}
+
+fn termius_group_path(group: &Value, groups: &HashMap<String, &Value>) -> Option<String> {
+    let mut names = Vec::new();
+    let mut visited = Vec::new();
+    let mut current = Some(group);
+    // Bounded: a broken export may list groups as their own parents
+    for _ in 0..MAX_DEPTH {
+        let Some(group) = current.take() else {
+            break;
+        };
+        let group = match id_string(group) {
+            Some(id) if visited.contains(&id) => break,
+            Some(id) => match groups.get(&id) {
+                Some(listed) => {
+                    visited.push(id);
+                    *listed
+                }
+                None => group,
+            },
+            None => group,
+        };
+        match group {
+            Value::String(path) => names.push(path.trim().to_string()),
+            Value::Object(_) => {
+                names.extend(str_field(group, &["label", "name"]));
+                current = group.get("parent_group").or_else(|| group.get("parent"));
+            }
+            _ => {}
+        }
+    }
+    names.reverse();
+    let path = names.join("/");
+    Some(path).filter(|path| !path.trim_matches('/').trim().is_empty())
+}
+
+/// First non-empty string among `keys`
+fn str_field(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| value.get(*key)?.as_str())
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Group reference by ID (number or string)
+fn id_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(id) => Some(id.to_string()),
+        Value::String(id) if !id.contains('/') => Some(id.clone()),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// mRemoteNG
+// ============================================================================
+
+/// Parse an mRemoteNG confCons.xml
+///
+/// Containers become folders. Files encrypted as a whole can't be read:
+/// mRemoteNG can export them unencrypted.
+pub fn parse_mremoteng_str(content: &str) -> Result<Vec<ImportEntry>> {
+    let mut entries = Vec::new();
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut found_root = false;
+
+    for tag in XmlTags::new(content) {
+        match tag {
+            XmlTag::Open {
+                name,
+                attributes,
+                self_closing,
+            } => {
+                if name.ends_with("Connections") && !name.contains("Node") {
+                    found_root = true;
+                    if attributes
+                        .get("FullFileEncryption")
+                        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+                    {
+                        return Err(anyhow!(
+                            "The mRemoteNG file is encrypted; export it without encryption"
+                        ));
+                    }
+                    continue;
+                }
+                if name != "Node" {
+                    continue;
+                }
+
+                let kind = attributes.get("Type").map(String::as_str).unwrap_or("");
+                if kind == "Container" {
+                    if !self_closing {
+                        folders.push(non_empty(attributes.get("Name")));
+                    }
+                    continue;
+                }
+                if kind == "Connection" {
+                    let folder = folders.iter().flatten().cloned().collect::<Vec<_>>();
+                    entries.extend(mremoteng_entry(&attributes, &folder));
+                }
+                if !self_closing {
+                    // Connections don't contain nodes, but keep the nesting
+                    folders.push(None);
+                }
+            }
+            XmlTag::Close { name } => {
+                if name == "Node" {
+                    folders.pop();
+                }
+            }
+        }
+    }
+
+    if !found_root {
+        return Err(anyhow!("Not an mRemoteNG connections file"));
+    }
+    Ok(entries)
+}
+
+fn mremoteng_entry(
+    attributes: &HashMap<String, String>,
+    folders: &[String],
+) -> Option<ImportEntry> {
+    let protocol = attributes.get("Protocol")?;
+    if !protocol.to_ascii_uppercase().starts_with("SSH") {
+        return None;
+    }
+    let hostname = non_empty(attributes.get("Hostname"))?;
+    let name = non_empty(attributes.get("Name")).unwrap_or_else(|| hostname.clone());
+
+    let mut entry = ImportEntry::new(ImportSource::MRemoteNg, &name, &hostname);
+    entry.port = attributes.get("Port").and_then(|port| port.parse().ok());
+    entry.username = non_empty(attributes.get("Username"));
+    entry.folder = Some(folders.join("/")).filter(|folder| !folder.is_empty());
+    entry.notes = non_empty(attributes.get("Descr"));
+    Some(entry)
+}
+
+/// Start or end tag of an XML document
+enum XmlTag {
+    Open {
+        name: String,
+        attributes: HashMap<String, String>,
+        self_closing: bool,
+    },
+    Close {
+        name: String,
+    },
+}
+
+/// Minimal XML tag reader: enough for mRemoteNG files, whose data is all
+/// in attributes (text, comments and declarations are skipped)
+struct XmlTags<'a> {
+    rest: &'a str,
+}
+
+impl<'a> XmlTags<'a> {
+    fn new(content: &'a str) -> Self {
+        Self { rest: content }
+    }
+}
+
+impl Iterator for XmlTags<'_> {
+    type Item = XmlTag;
+
+    fn next(&mut self) -> Option<XmlTag> {
+        loop {
+            let start = self.rest.find('<')?;
+            self.rest = &self.rest[start..];
+
+            for (open, close) in [
+                ("<!--", "-->"),
+                ("<![CDATA[", "]]>"),
+                ("<?", "?>"),
+                ("<!", ">"),
+            ] {
+                if self.rest.starts_with(open) {
+                    let end = self.rest.find(close).map(|end| end + close.len());
+                    self.rest = &self.rest[end.unwrap_or(self.rest.len())..];
+                    break;
+                }
+            }
+            if !self.rest.starts_with('<')
+                || self.rest.starts_with("<!")
+                || self.rest.starts_with("<?")
+            {
+                continue;
+            }
+
+            let end = tag_end(self.rest)?;
+            let tag = &self.rest[1..end];
+            self.rest = &self.rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(XmlTag::Close {
+                    name: name.trim().to_string(),
+                });
+            }
+            let self_closing = tag.ends_with('/');
+            let tag = tag.strip_suffix('/').unwrap_or(tag);
+            let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+            return Some(XmlTag::Open {
+                name: tag[..name_end].to_string(),
+                attributes: parse_attributes(&tag[name_end..]),
+                self_closing,
+            });
+        }
+    }
+}
+
+/// Position of the `>` closing the tag at the start of `s` (outside quotes)
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `name="value"` pairs (entities decoded)
+fn parse_attributes(s: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = s;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().to_string();
+        let value = rest[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        attributes.insert(name, decode_entities(&value[1..end + 1]));
+        rest = &value[end + 2..];
+    }
+    attributes
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#')?.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (c, entity) {
+            (Some(c), Some(entity)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// ============================================================================
+// SecureCRT
+// ============================================================================
+
+/// Session files of a SecureCRT Sessions folder; subfolders become folders
+fn parse_securecrt_dir(
+    dir: &Path,
+    folders: &[String],
+    entries: &mut Vec<ImportEntry>,
+) -> Result<()> {
+    if folders.len() > MAX_DEPTH {
+        return Ok(());
+    }
+    for path in sorted_entries(dir)? {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if path.is_dir() {
+            let mut folders = folders.to_vec();
+            folders.push(name);
+            parse_securecrt_dir(&path, &folders, entries)?;
+            continue;
+        }
+
+        let is_ini = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ini"));
+        // Folder settings and the default session aren't sessions
+        if !is_ini
+            || name.eq_ignore_ascii_case("__FolderData__.ini")
+            || name.eq_ignore_ascii_case("Default.ini")
+        {
+            continue;
+        }
+        let content = read_text(&path)?;
+        let folder = Some(folders.join("/")).filter(|folder| !folder.is_empty());
+        entries.extend(parse_securecrt_session(&file_stem(&path), folder, &content));
+    }
+    Ok(())
+}
+
+/// Parse a SecureCRT session file named `name`
+///
+/// Lines are `S:"Key"=string`, `D:"Key"=hex number` and `Z:"Key"=line
+/// count` followed by that many lines.
+pub fn parse_securecrt_session(
+    name: &str,
+    folder: Option<String>,
+    content: &str,
+) -> Option<ImportEntry> {
+    let mut strings: HashMap<String, String> = HashMap::new();
+    let mut numbers: HashMap<String, u32> = HashMap::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((kind, rest)) = line.split_once(":\"") else {
+            continue;
+        };
+        let Some((key, value)) = rest.split_once("\"=") else {
+            continue;
+        };
+        match kind.trim() {
+            "S" => {
+                strings.insert(key.to_string(), value.to_string());
+            }
+            "D" => {
+                if let Ok(number) = u32::from_str_radix(value.trim(), 16) {
+                    numbers.insert(key.to_string(), number);
+                }
+            }
+            "Z" => {
+                let count = usize::from_str_radix(value.trim(), 16).unwrap_or(0);
+                let text: Vec<&str> = lines.by_ref().take(count).collect();
+                strings.insert(key.to_string(), text.join("\n"));
+            }
+            _ => {}
+        }
+    }
+
+    let protocol = strings.get("Protocol Name")?;
+    if !protocol.to_ascii_uppercase().starts_with("SSH") {
+        return None;
+    }
+    let hostname = non_empty(strings.get("Hostname"))?;
+
+    let mut entry = ImportEntry::new(ImportSource::SecureCrt, name, &hostname);
+    entry.port = numbers
+        .get(&format!("[{}] Port", protocol.to_ascii_uppercase()))
+        .and_then(|port| u16::try_from(*port).ok());
+    entry.username = non_empty(strings.get("Username"));
+    // Paths may end with the key format (`C:\keys\id_rsa::rawkey`)
+    entry.key_path = ["Identity Filename V2", "Identity Filename"]
+        .into_iter()
+        .filter_map(|key| non_empty(strings.get(key)))
+        .map(|path| match path.split_once("::") {
+            Some((path, _)) => path.to_string(),
+            None => path,
+        })
+        .find(|path| !path.is_empty());
+    entry.folder = folder;
+    entry.notes = non_empty(strings.get("Description"));
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_putty_registry_export() {
+        let reg = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Prod%20Web]
+"HostName"="deploy@web.example.com"
+"PortNumber"=dword:00000d3d
+"Protocol"="ssh"
+"PublicKeyFile"="C:\\Users\\me\\keys\\web.ppk"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Router]
+"HostName"="192.168.1.1"
+"Protocol"="telnet"
+"#;
+        let entries = parse_putty_str("export", reg);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Prod Web");
+        assert_eq!(entries[0].hostname, "web.example.com");
+        assert_eq!(entries[0].username.as_deref(), Some("deploy"));
+        assert_eq!(entries[0].port, Some(3389));
+        assert_eq!(
+            entries[0].key_path.as_deref(),
+            Some("C:\\Users\\me\\keys\\web.ppk")
+        );
+
+        // regedit writes UTF-16
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend(reg.encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(parse_putty_str("export", &decode_text(&utf16)), entries);
+    }
+
+    #[test]
+    fn test_putty_session_file() {
+        let session = "HostName=db.example.com\nPortNumber=2222\nUserName=admin\nProtocol=ssh\n";
+        let entries = parse_putty_str("My%20DB", session);
+        assert_eq!(entries[0].name, "My DB");
+        assert_eq!(entries[0].port, Some(2222));
+        assert_eq!(entries[0].username.as_deref(), Some("admin"));
+        assert_eq!(entries[0].key_path, None);
+    }
+
+    #[test]
+    fn test_termius_export() {
+        let export = r#"{
+            "groups": [
+                { "id": 1, "label": "Production" },
+                { "id": 2, "label": "Databases", "parent_group": 1 }
+            ],
+            "hosts": [
+                {
+                    "label": "pg-main",
+                    "address": "10.0.0.5",
+                    "group": 2,
+                    "tags": ["postgres", { "label": "prod" }],
+                    "ssh_config": { "port": 2222, "identity": { "username": "postgres" } }
+                },
+                { "address": "lab.example.com", "username": "me", "group": "Lab/Test" },
+                { "label": "no address" }
+            ]
+        }"#;
+        let entries = parse_termius_str(export).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].folder.as_deref(), Some("Production/Databases"));
+        assert_eq!(entries[0].port, Some(2222));
+        assert_eq!(entries[0].username.as_deref(), Some("postgres"));
+        assert_eq!(entries[0].tags, ["postgres", "prod"]);
+        assert_eq!(entries[1].name, "lab.example.com");
+        assert_eq!(entries[1].folder.as_deref(), Some("Lab/Test"));
+        assert!(parse_termius_str("[1, 2").is_err());
+    }
+
+    #[test]
+    fn test_mremoteng_confcons() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<mrng:Connections xmlns:mrng="http://mremoteng.org" Name="Connections" FullFileEncryption="false" ConfVersion="2.6">
+    <!-- <Node Name="commented" Type="Connection" Protocol="SSH2" Hostname="x" /> -->
+    <Node Name="Prod &amp; Staging" Type="Container" Expanded="true">
+        <Node Name="Web" Type="Container">
+            <Node Name="web-1" Type="Connection" Descr="Front &lt;1&gt;" Hostname="web1.example.com" Protocol="SSH2" Port="2200" Username="deploy" Password="c2VjcmV0" />
+        </Node>
+        <Node Name="dc" Type="Connection" Hostname="dc.example.com" Protocol="RDP" Port="3389" />
+    </Node>
+    <Node Name="lab" Type="Connection" Hostname="lab.example.com" Protocol="SSH1" Port="22" Username="" />
+</mrng:Connections>"#;
+        let entries = parse_mremoteng_str(xml).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "web-1");
+        assert_eq!(entries[0].folder.as_deref(), Some("Prod & Staging/Web"));
+        assert_eq!(entries[0].port, Some(2200));
+        assert_eq!(entries[0].notes.as_deref(), Some("Front <1>"));
+        assert_eq!(entries[1].folder, None);
+        assert_eq!(entries[1].username, None);
+
+        let encrypted = r#"<mrng:Connections FullFileEncryption="true">AAAA</mrng:Connections>"#;
+        assert!(parse_mremoteng_str(encrypted).is_err());
+        assert!(parse_mremoteng_str("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_securecrt_session() {
+        let ini = "S:\"Protocol Name\"=SSH2\n\
+                   S:\"Hostname\"=bastion.example.com\n\
+                   S:\"Username\"=ops\n\
+                   D:\"[SSH2] Port\"=00000016\n\
+                   S:\"Identity Filename V2\"=/home/ops/.ssh/id_ed25519::rawkey\n\
+                   Z:\"Description\"=00000002\n\
+                   Jump host\n\
+                   Ask ops before use\n\
+                   B:\"Color Scheme\"=00000002\n \
+                   01 02\n";
+        let entry = parse_securecrt_session("bastion", Some("Prod".to_string()), ini).unwrap();
+        assert_eq!(entry.port, Some(22));
+        assert_eq!(entry.username.as_deref(), Some("ops"));
+        assert_eq!(entry.key_path.as_deref(), Some("/home/ops/.ssh/id_ed25519"));
+        assert_eq!(
+            entry.notes.as_deref(),
+            Some("Jump host\nAsk ops before use")
+        );
+        assert_eq!(entry.folder.as_deref(), Some("Prod"));
+
+        let telnet = "S:\"Protocol Name\"=Telnet\nS:\"Hostname\"=switch\n";
+        assert!(parse_securecrt_session("switch", None, telnet).is_none());
+    }
+
+    #[test]
+    fn test_securecrt_sessions_folder() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let prod = dir.path().join("Prod");
+        fs::create_dir(&prod).unwrap();
+        let session = "S:\"Protocol Name\"=SSH2\nS:\"Hostname\"=web.example.com\n";
+        fs::write(prod.join("web.ini"), session).unwrap();
+        fs::write(prod.join("__FolderData__.ini"), "").unwrap();
+        fs::write(dir.path().join("Default.ini"), session).unwrap();
+
+        let entries = parse_import(ImportSource::SecureCrt, dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "web");
+        assert_eq!(entries[0].folder.as_deref(), Some("Prod"));
+        assert!(parse_import(ImportSource::Termius, dir.path()).is_err());
+    }
+}
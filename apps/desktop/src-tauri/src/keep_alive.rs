@@ -0,0 +1,87 @@
+/**
+ * SSH Keep-Alive
+ *
+ * Keep-alives are SSH global requests (`keepalive@openssh.com`, like
+ * OpenSSH's ServerAliveInterval) sent by the transport after the interval
+ * without traffic from the server. Servers answer them without touching the
+ * terminal, and a peer that leaves `MAX_UNANSWERED` of them in a row
+ * unanswered is considered dead: the transport is closed, which ends (or
+ * reconnects) the session.
+ *
+ * The interval comes from the connection (override "enabled"/"disabled"),
+ * or from the global settings when the connection has no override.
+ */
+use std::time::Duration;
+
+/// Global setting: keep-alive for connections without an override
+pub const SETTING_ENABLED: &str = "ssh_keep_alive_enabled";
+
+/// Global setting: keep-alive interval in seconds
+pub const SETTING_INTERVAL: &str = "ssh_keep_alive_interval";
+
+/// Interval when none (or an invalid one) is configured
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Unanswered keep-alives after which the server is considered dead
+pub const MAX_UNANSWERED: usize = 3;
+
+/// Keep-alive interval of a connection, None when disabled
+///
+/// `override_mode` and `interval` are the connection's own settings,
+/// `global_enabled` and `global_interval` the global setting values.
+pub fn resolve(
+    override_mode: Option<&str>,
+    interval: Option<i64>,
+    global_enabled: Option<&str>,
+    global_interval: Option<&str>,
+) -> Option<Duration> {
+    match override_mode {
+        Some("enabled") => Some(seconds(interval)),
+        Some("disabled") => None,
+        Some(other) => {
+            tracing::warn!(
+                "[keep_alive.rs] Unknown ssh_keep_alive_override value: '{}', disabling keep-alive",
+                other
+            );
+            None
+        }
+        None if global_enabled == Some("true") => {
+            Some(seconds(global_interval.and_then(|s| s.trim().parse().ok())))
+        }
+        None => None,
+    }
+}
+
+fn seconds(interval: Option<i64>) -> Duration {
+    match interval {
+        Some(secs) if secs > 0 => Duration::from_secs(secs as u64),
+        _ => DEFAULT_INTERVAL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let secs = |s| Some(Duration::from_secs(s));
+
+        // Connection override wins over the global settings
+        assert_eq!(resolve(Some("enabled"), Some(15), None, None), secs(15));
+        assert_eq!(resolve(Some("enabled"), None, None, None), secs(30));
+        assert_eq!(resolve(Some("enabled"), Some(0), None, None), secs(30));
+        assert_eq!(
+            resolve(Some("disabled"), Some(15), Some("true"), Some("10")),
+            None
+        );
+        assert_eq!(resolve(Some("bogus"), Some(15), Some("true"), None), None);
+
+        // No override: global settings
+        assert_eq!(resolve(None, None, Some("true"), Some("45")), secs(45));
+        assert_eq!(resolve(None, Some(15), Some("true"), Some("x")), secs(30));
+        assert_eq!(resolve(None, None, Some("true"), None), secs(30));
+        assert_eq!(resolve(None, None, Some("false"), Some("45")), None);
+        assert_eq!(resolve(None, None, None, None), None);
+    }
+}
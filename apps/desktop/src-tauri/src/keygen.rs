@@ -0,0 +1,173 @@
+/**
+ * SSH Key Generation
+ *
+ * Creates new keypairs: ed25519 by default, RSA-4096 or ECDSA for servers
+ * that require them. Private keys are encoded in OpenSSH format (encrypted
+ * when a passphrase is given) and either written to disk with owner-only
+ * permissions or stored in the vault for a connection. The public key is
+ * returned in `authorized_keys` format for copy/paste.
+ */
+use anyhow::{anyhow, Context, Result};
+use russh::keys::ssh_key::{Algorithm, EcdsaCurve, LineEnding};
+use russh::keys::{HashAlg, PrivateKey};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::key_format::write_private_file;
+
+/// Comment used when none is given
+pub const DEFAULT_COMMENT: &str = "rite";
+
+/// Key type to generate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyType {
+    #[default]
+    Ed25519,
+    /// RSA, 4096 bits
+    Rsa,
+    EcdsaP256,
+    EcdsaP384,
+    EcdsaP521,
+}
+
+impl KeyType {
+    fn algorithm(self) -> Algorithm {
+        match self {
+            Self::Ed25519 => Algorithm::Ed25519,
+            Self::Rsa => Algorithm::Rsa { hash: None },
+            Self::EcdsaP256 => Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP256,
+            },
+            Self::EcdsaP384 => Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP384,
+            },
+            Self::EcdsaP521 => Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP521,
+            },
+        }
+    }
+}
+
+/// Newly generated keypair
+pub struct GeneratedKey {
+    /// Private key in OpenSSH format
+    pub private_key: Zeroizing<String>,
+    /// Public key line for `authorized_keys`
+    pub public_key: String,
+    /// SHA-256 fingerprint of the public key
+    pub fingerprint: String,
+}
+
+/// Generate a keypair (encrypted with the passphrase, if any)
+pub fn generate(
+    key_type: KeyType,
+    comment: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<GeneratedKey> {
+    let mut rng = rand::rngs::OsRng;
+    let mut key = PrivateKey::random(&mut rng, key_type.algorithm())
+        .map_err(|e| anyhow!("Failed to generate {:?} key: {}", key_type, e))?;
+
+    let comment = comment
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .unwrap_or(DEFAULT_COMMENT);
+    if comment.contains(['\r', '\n']) {
+        return Err(anyhow!("Key comment must be a single line"));
+    }
+    key.set_comment(comment);
+
+    let public_key = key.public_key().to_openssh()?;
+    let fingerprint = key.public_key().fingerprint(HashAlg::Sha256).to_string();
+    let key = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => key.encrypt(&mut rng, passphrase)?,
+        None => key,
+    };
+
+    Ok(GeneratedKey {
+        private_key: key.to_openssh(LineEnding::LF)?,
+        public_key,
+        fingerprint,
+    })
+}
+
+/// Write a keypair as `path` (private, owner-only) and `path.pub`
+///
+/// Existing files are never overwritten. Returns the private key path.
+pub fn write_key_files(key: &GeneratedKey, path: &Path) -> Result<PathBuf> {
+    let public_path = public_key_path(path);
+    if path.exists() || public_path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+
+    write_private_file(path, key.private_key.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let mut public = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&public_path)
+        .with_context(|| format!("Failed to write {}", public_path.display()))?;
+    writeln!(public, "{}", key.public_key)?;
+
+    tracing::info!("[keygen.rs] Key written to {}", path.display());
+    Ok(path.to_path_buf())
+}
+
+/// Public key file next to a private key (`id_ed25519` -> `id_ed25519.pub`)
+fn public_key_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".pub");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_format::decode_private_key;
+
+    #[test]
+    fn test_generate_ed25519() {
+        let key = generate(KeyType::Ed25519, Some(" alice@laptop "), None).unwrap();
+        assert!(key.public_key.starts_with("ssh-ed25519 "));
+        assert!(key.public_key.ends_with(" alice@laptop"));
+        assert!(key.fingerprint.starts_with("SHA256:"));
+
+        let decoded = decode_private_key(&key.private_key, None).unwrap();
+        assert_eq!(decoded.public_key().to_openssh().unwrap(), key.public_key);
+
+        assert!(generate(KeyType::Ed25519, Some("two\nlines"), None).is_err());
+    }
+
+    #[test]
+    fn test_generate_encrypted_ecdsa() {
+        let key = generate(KeyType::EcdsaP256, None, Some("correct horse")).unwrap();
+        assert!(key.public_key.starts_with("ecdsa-sha2-nistp256 "));
+        assert!(key.public_key.ends_with(DEFAULT_COMMENT));
+
+        assert!(decode_private_key(&key.private_key, Some("wrong")).is_err());
+        assert!(decode_private_key(&key.private_key, Some("correct horse")).is_ok());
+    }
+
+    #[test]
+    fn test_write_key_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("id_ed25519");
+        let key = generate(KeyType::Ed25519, None, None).unwrap();
+
+        write_key_files(&key, &path).unwrap();
+        let public = std::fs::read_to_string(dir.path().join("id_ed25519.pub")).unwrap();
+        assert_eq!(public.trim_end(), key.public_key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Never overwritten
+        assert!(write_key_files(&key, &path).is_err());
+    }
+}
@@ -4,12 +4,15 @@
  * Manages SSH server host key verification for MITM protection
  */
 use anyhow::Result;
-use russh::keys::{HashAlg, PublicKey};
+use russh::keys::{Algorithm, HashAlg, PublicKey};
 use serde::Serialize;
 use sqlx::SqlitePool;
+use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use crate::openssh_known_hosts::KnownHostsEntry;
+
 /// Result of host key verification
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "status")]
@@ -36,6 +39,34 @@ pub enum HostKeyVerificationResult {
     },
 }
 
+/// Stored host key (as listed in the known hosts manager)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHost {
+    pub id: String,
+    pub host: String,
+    pub port: i64,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub added_at: i64,
+    pub last_seen_at: i64,
+}
+
+/// Result of importing an OpenSSH known_hosts file
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsImport {
+    /// Hosts added to the known hosts
+    pub imported: usize,
+    /// Hosts already known with one of the file's keys
+    pub already_known: usize,
+    /// Hosts known with a key the file doesn't list (`host:port`, kept as is)
+    pub conflicts: Vec<String>,
+    /// Lines naming no importable host (wildcards, hashed names that match
+    /// no saved connection)
+    pub skipped: usize,
+}
+
 /// Get current Unix timestamp in seconds
 fn current_timestamp() -> i64 {
     SystemTime::now()
@@ -165,3 +196,220 @@ async fn update_last_seen(db: &SqlitePool, id: &str) -> Result<()> {
         .await?;
     Ok(())
 }
+
+/// List all known hosts (by host, then port)
+pub async fn list_known_hosts(db: &SqlitePool) -> Result<Vec<KnownHost>> {
+    let hosts = sqlx::query_as::<_, KnownHost>(
+        "SELECT id, host, port, key_type, fingerprint, added_at, last_seen_at
+         FROM known_hosts ORDER BY host, port",
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(hosts)
+}
+
+/// Get the known host entry for a host and port
+pub async fn get_known_host(db: &SqlitePool, host: &str, port: u16) -> Result<Option<KnownHost>> {
+    let known = sqlx::query_as::<_, KnownHost>(
+        "SELECT id, host, port, key_type, fingerprint, added_at, last_seen_at
+         FROM known_hosts WHERE host = ? AND port = ?",
+    )
+    .bind(host)
+    .bind(port as i64)
+    .fetch_optional(db)
+    .await?;
+    Ok(known)
+}
+
+/// Remove a known host (the next connection asks about the key again)
+///
+/// Returns the removed entry, None if there was no such entry.
+pub async fn remove_known_host(db: &SqlitePool, id: &str) -> Result<Option<KnownHost>> {
+    let host = sqlx::query_as::<_, KnownHost>(
+        "SELECT id, host, port, key_type, fingerprint, added_at, last_seen_at
+         FROM known_hosts WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+
+    if let Some(host) = &host {
+        tracing::info!(
+            "[known_hosts] Removing host key for {}:{}",
+            host.host,
+            host.port
+        );
+        sqlx::query("DELETE FROM known_hosts WHERE id = ?")
+            .bind(id)
+            .execute(db)
+            .await?;
+    }
+    Ok(host)
+}
+
+/// Known hosts as an OpenSSH `known_hosts` file
+///
+/// Non-standard ports use the `[host]:port` form, like OpenSSH.
+pub async fn export_known_hosts(db: &SqlitePool) -> Result<String> {
+    let rows = sqlx::query_as::<_, (String, i64, Vec<u8>)>(
+        "SELECT host, port, public_key_data FROM known_hosts ORDER BY host, port",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut output = String::new();
+    for (host, port, public_key_data) in rows {
+        let key = PublicKey::from_bytes(&public_key_data)?;
+        output.push_str(&known_hosts_line(&host, port, &key.to_openssh()?));
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Import the entries of an OpenSSH known_hosts file
+///
+/// Hashed host names can't be read back, so they are matched against
+/// `candidates` (the hosts of saved connections). Only one key per host is
+/// stored: with several, the type russh prefers in the handshake wins, and
+/// connections ask the server for that type first (see
+/// [`prefer_known_key_type`]).
+/// Existing entries are never replaced; a host known with a key the file
+/// doesn't list is reported as a conflict. `@cert-authority` and `@revoked`
+/// lines are skipped (host certificates are not negotiated).
+pub async fn import_openssh(
+    db: &SqlitePool,
+    entries: &[KnownHostsEntry],
+    candidates: &[(String, u16)],
+) -> Result<KnownHostsImport> {
+    let mut result = KnownHostsImport::default();
+    let mut keys: BTreeMap<(String, u16), Vec<&PublicKey>> = BTreeMap::new();
+
+    for entry in entries.iter().filter(|entry| entry.marker.is_none()) {
+        let mut hosts = entry.plain_hosts();
+        if entry.is_hashed() {
+            hosts.extend(
+                candidates
+                    .iter()
+                    .filter(|(host, port)| entry.matches(host, *port))
+                    .cloned(),
+            );
+        }
+        if hosts.is_empty() {
+            result.skipped += 1;
+        }
+        for host in hosts {
+            keys.entry(host).or_default().push(&entry.key);
+        }
+    }
+
+    for ((host, port), mut host_keys) in keys {
+        let fingerprints: Vec<String> = host_keys
+            .iter()
+            .map(|key| calculate_fingerprint(key))
+            .collect();
+        match get_known_host(db, &host, port).await? {
+            Some(known) if fingerprints.contains(&known.fingerprint) => {
+                result.already_known += 1;
+            }
+            Some(_) => result.conflicts.push(format!("{}:{}", host, port)),
+            None => {
+                host_keys.sort_by_key(|key| key_preference(key.algorithm()));
+                add_host_key(db, &host, port, host_keys[0]).await?;
+                result.imported += 1;
+            }
+        }
+    }
+
+    tracing::info!(
+        "[known_hosts] Imported {} hosts ({} already known, {} conflicts, {} lines skipped)",
+        result.imported,
+        result.already_known,
+        result.conflicts.len(),
+        result.skipped
+    );
+    Ok(result)
+}
+
+/// Host key algorithms in `preferred` order, those of the known key's type
+/// first
+///
+/// A server with several host keys presents the first type the client asks
+/// for, so a host known by one of its keys always presents that one instead
+/// of raising a false "host key changed". RSA keys keep their signature
+/// hashes in order.
+pub fn prefer_known_key_type(preferred: &[Algorithm], known_type: &str) -> Vec<Algorithm> {
+    let mut algorithms = preferred.to_vec();
+    let Ok(known) = known_type.parse::<Algorithm>() else {
+        return algorithms;
+    };
+    algorithms.sort_by_key(|algorithm| match (algorithm, &known) {
+        (Algorithm::Rsa { .. }, Algorithm::Rsa { .. }) => false,
+        _ => *algorithm != known,
+    });
+    algorithms
+}
+
+/// Rank of a host key type in russh's preferred order (lower first)
+fn key_preference(algorithm: Algorithm) -> usize {
+    match algorithm {
+        Algorithm::Ed25519 => 0,
+        Algorithm::Ecdsa { .. } => 1,
+        Algorithm::Rsa { .. } => 2,
+        _ => 3,
+    }
+}
+
+/// One `known_hosts` line for a key in OpenSSH format (`type base64`)
+fn known_hosts_line(host: &str, port: i64, openssh_key: &str) -> String {
+    if port == 22 {
+        format!("{} {}", host, openssh_key)
+    } else {
+        format!("[{}]:{} {}", host, port, openssh_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefer_known_key_type() {
+        let preferred = [
+            Algorithm::Ed25519,
+            "ecdsa-sha2-nistp256".parse().unwrap(),
+            Algorithm::Rsa {
+                hash: Some(HashAlg::Sha512),
+            },
+            Algorithm::Rsa {
+                hash: Some(HashAlg::Sha256),
+            },
+            Algorithm::Rsa { hash: None },
+        ];
+
+        let rsa_first = prefer_known_key_type(&preferred, "ssh-rsa");
+        assert_eq!(rsa_first[..3], preferred[2..]);
+        assert_eq!(rsa_first[3..], preferred[..2]);
+
+        let ecdsa_first = prefer_known_key_type(&preferred, "ecdsa-sha2-nistp256");
+        assert_eq!(ecdsa_first[0], preferred[1]);
+        assert_eq!(ecdsa_first[1], preferred[0]);
+
+        // Unknown to russh: unchanged
+        assert_eq!(prefer_known_key_type(&preferred, "ssh-unknown"), preferred);
+        assert_eq!(prefer_known_key_type(&preferred, "ssh-ed25519"), preferred);
+    }
+
+    #[test]
+    fn test_known_hosts_line() {
+        let key =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+        assert_eq!(
+            known_hosts_line("example.com", 22, key),
+            format!("example.com {}", key)
+        );
+        assert_eq!(
+            known_hosts_line("10.0.0.5", 2222, key),
+            format!("[10.0.0.5]:2222 {}", key)
+        );
+    }
+}
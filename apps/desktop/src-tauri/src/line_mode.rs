@@ -0,0 +1,259 @@
+/**
+ * Line Mode Module
+ *
+ * Local line editing for devices that can't handle character-at-a-time input
+ * (switches, PDUs, serial consoles): keystrokes are echoed locally and the
+ * line is only sent to the host on Enter.
+ *
+ * The PTY is requested without echo, but many devices echo anyway; their
+ * echo of a sent line is taken out of the output (`strip_echo`) so the line
+ * isn't shown twice.
+ */
+use std::collections::VecDeque;
+
+/// Maximum number of lines kept in the line-mode history
+const MAX_HISTORY: usize = 100;
+
+/// Result of feeding keystrokes to the line editor
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LineOutput {
+    /// Bytes to display locally (as if they came from the host)
+    pub echo: Vec<u8>,
+    /// Bytes to send to the host
+    pub send: Vec<u8>,
+}
+
+/// Line editor with history, used as an input transform on the session
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    line: String,
+    history: Vec<String>,
+    /// Position while browsing history (None = editing a new line)
+    history_index: Option<usize>,
+    /// Sent lines (as echoed locally) the host may still echo back
+    unechoed: VecDeque<u8>,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed raw keystrokes from the terminal
+    pub fn process(&mut self, input: &[u8]) -> LineOutput {
+        let mut output = LineOutput::default();
+        let text = String::from_utf8_lossy(input);
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                // Enter: send the buffered line
+                '\r' | '\n' => {
+                    // Treat CR LF as a single Enter
+                    if c == '\r' && chars.peek() == Some(&'\n') {
+                        chars.next();
+                    }
+                    let line = std::mem::take(&mut self.line);
+                    self.unechoed.extend(line.as_bytes());
+                    self.unechoed.extend(b"\r\n");
+                    output.echo.extend_from_slice(b"\r\n");
+                    output.send.extend_from_slice(line.as_bytes());
+                    output.send.push(b'\r');
+                    self.push_history(line);
+                }
+                // Backspace / Delete
+                '\x7f' | '\x08' => {
+                    if self.line.pop().is_some() {
+                        output.echo.extend_from_slice(b"\x08 \x08");
+                    }
+                }
+                // Ctrl+C: drop the line and interrupt the host
+                '\x03' => {
+                    self.line.clear();
+                    self.history_index = None;
+                    output.echo.extend_from_slice(b"^C\r\n");
+                    output.send.push(0x03);
+                }
+                // Ctrl+D on an empty line is passed through (logout/EOF)
+                '\x04' => {
+                    if self.line.is_empty() {
+                        output.send.push(0x04);
+                    }
+                }
+                // Ctrl+U: clear the line
+                '\x15' => {
+                    let erase = self.erase_line();
+                    output.echo.extend_from_slice(&erase);
+                }
+                // Escape sequences: Up/Down browse history, others are ignored
+                '\x1b' => {
+                    let intro = chars.next();
+                    if matches!(intro, Some('[') | Some('O')) {
+                        let mut final_char = None;
+                        for c in chars.by_ref() {
+                            if ('@'..='~').contains(&c) {
+                                final_char = Some(c);
+                                break;
+                            }
+                        }
+                        match final_char {
+                            Some('A') => self.history_previous(&mut output.echo),
+                            Some('B') => self.history_next(&mut output.echo),
+                            _ => {}
+                        }
+                    }
+                }
+                c if c.is_control() && c != '\t' => {}
+                c => {
+                    self.line.push(c);
+                    let mut buf = [0u8; 4];
+                    output
+                        .echo
+                        .extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+
+        output
+    }
+
+    /// Take the host's own echo of sent lines off the start of its output
+    ///
+    /// Output that doesn't match is passed through whole: the host doesn't
+    /// echo, and nothing more is expected.
+    pub fn strip_echo<'a>(&mut self, output: &'a [u8]) -> &'a [u8] {
+        let mut rest = output;
+        while let (Some(&expected), Some((&byte, tail))) =
+            (self.unechoed.front(), rest.split_first())
+        {
+            if byte != expected {
+                self.unechoed.clear();
+                return output;
+            }
+            self.unechoed.pop_front();
+            rest = tail;
+        }
+        rest
+    }
+
+    /// Lines entered so far, oldest first
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    fn push_history(&mut self, line: String) {
+        self.history_index = None;
+        if line.trim().is_empty() || self.history.last() == Some(&line) {
+            return;
+        }
+        self.history.push(line);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    fn history_previous(&mut self, echo: &mut Vec<u8>) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.replace_line(self.history[index].clone(), echo);
+    }
+
+    fn history_next(&mut self, echo: &mut Vec<u8>) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.replace_line(self.history[index + 1].clone(), echo);
+        } else {
+            self.history_index = None;
+            self.replace_line(String::new(), echo);
+        }
+    }
+
+    fn replace_line(&mut self, line: String, echo: &mut Vec<u8>) {
+        echo.extend_from_slice(&self.erase_line());
+        echo.extend_from_slice(line.as_bytes());
+        self.line = line;
+    }
+
+    /// Clear the current line, returning the echo that erases it on screen
+    fn erase_line(&mut self) -> Vec<u8> {
+        let width = self.line.chars().count();
+        self.line.clear();
+        let mut erase = Vec::with_capacity(width * 3);
+        for _ in 0..width {
+            erase.extend_from_slice(b"\x08 \x08");
+        }
+        erase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_buffering_and_echo() {
+        let mut editor = LineEditor::new();
+
+        let output = editor.process(b"shw");
+        assert_eq!(output.echo, b"shw");
+        assert!(output.send.is_empty());
+
+        let output = editor.process(b"\x7fow run\r");
+        assert_eq!(output.echo, b"\x08 \x08ow run\r\n");
+        assert_eq!(output.send, b"show run\r");
+    }
+
+    #[test]
+    fn test_history_navigation() {
+        let mut editor = LineEditor::new();
+        editor.process(b"show version\r");
+        editor.process(b"show ip int brief\r");
+        editor.process(b"show ip int brief\r");
+        assert_eq!(editor.history().len(), 2);
+
+        editor.process(b"\x1b[A\x1b[A");
+        let output = editor.process(b"\r");
+        assert_eq!(output.send, b"show version\r");
+
+        // Down past the newest entry returns to an empty line
+        editor.process(b"\x1b[A\x1b[B");
+        let output = editor.process(b"\r");
+        assert_eq!(output.send, b"\r");
+    }
+
+    #[test]
+    fn test_control_keys() {
+        let mut editor = LineEditor::new();
+        let output = editor.process(b"reload\x03");
+        assert_eq!(output.send, b"\x03");
+
+        let output = editor.process("conf t\x15exit\r".as_bytes());
+        assert_eq!(output.send, b"exit\r");
+    }
+
+    #[test]
+    fn test_strip_echo() {
+        // Host echoing the line, split across reads
+        let mut editor = LineEditor::new();
+        editor.process(b"show clock\r");
+        assert_eq!(editor.strip_echo(b"show c"), b"");
+        assert_eq!(
+            editor.strip_echo(b"lock\r\n12:00:00 UTC\r\n"),
+            b"12:00:00 UTC\r\n"
+        );
+        assert_eq!(editor.strip_echo(b"show clock\r\n"), b"show clock\r\n");
+
+        // Host without echo: output is left alone
+        editor.process(b"show clock\r");
+        assert_eq!(editor.strip_echo(b"12:00:01 UTC\r\n"), b"12:00:01 UTC\r\n");
+        assert_eq!(editor.strip_echo(b"show clock\r\n"), b"show clock\r\n");
+    }
+}
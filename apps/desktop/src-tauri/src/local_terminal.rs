@@ -4,7 +4,6 @@
  * Manages local shell sessions using portable-pty
  */
 use anyhow::{anyhow, Result};
-use base64::Engine as _;
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex as StdMutex};
@@ -12,6 +11,10 @@ use tauri::{AppHandle, Emitter};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use crate::output::SessionOutput;
+use crate::output_frame::Frame;
+use crate::shell_integration::{self, CwdState};
+use crate::snapshot::{self, ScreenState};
 use crate::terminal::SessionCommand;
 
 pub type SessionId = String;
@@ -20,17 +23,26 @@ pub type SessionId = String;
 pub struct LocalSession {
     pub id: SessionId,
     command_tx: mpsc::Sender<SessionCommand>,
-    /// Buffer for initial shell output (prompt, fastfetch, etc.).
-    /// `Some(bytes)` = buffering; `None` = streaming mode (frontend has claimed).
-    /// Uses std::sync::Mutex because the PTY reader runs in spawn_blocking.
-    initial_buffer: Arc<StdMutex<Option<Vec<u8>>>>,
+    /// Output sink; buffers the initial shell output (prompt, fastfetch, etc.)
+    /// until the frontend attaches its channel
+    output: Arc<SessionOutput>,
+    /// Backend screen state (used for snapshots)
+    screen: ScreenState,
+    /// Working directory reported by the shell (OSC 7)
+    cwd: CwdState,
 }
 
 impl LocalSession {
     /// Create a new local terminal session
     ///
-    /// Spawns a local shell (bash/zsh/fish) using portable-pty
-    pub async fn spawn(app_handle: AppHandle, shell: Option<String>) -> Result<Self> {
+    /// Spawns a local shell (bash/zsh/fish) using portable-pty, with `env`
+    /// added to its environment, in `cwd` when it is an existing directory
+    pub async fn spawn(
+        app_handle: AppHandle,
+        shell: Option<String>,
+        env: Vec<(String, String)>,
+        cwd: Option<String>,
+    ) -> Result<Self> {
         let session_id = Uuid::new_v4().to_string();
         tracing::info!("Creating local session: {}", session_id);
 
@@ -109,6 +121,17 @@ impl LocalSession {
         cmd.env("TERM_PROGRAM", "vscode"); // Pretend we're VSCode (fish trusts it)
         cmd.env("TERM_PROGRAM_VERSION", "1.0.0"); // Version for compatibility
 
+        // Connection environment (values may be secrets: only names are logged)
+        for (name, value) in &env {
+            tracing::debug!("Setting {} for the local shell", name);
+            cmd.env(name, value);
+        }
+
+        if let Some(cwd) = cwd.filter(|dir| std::path::Path::new(dir).is_dir()) {
+            tracing::debug!("Starting shell in {}", cwd);
+            cmd.cwd(cwd);
+        }
+
         let mut child = pair
             .slave
             .spawn_command(cmd)
@@ -122,12 +145,18 @@ impl LocalSession {
         // Buffer for initial shell output (prompt, fastfetch, etc.).
         // Same rationale as SshSession: the shell starts writing immediately when
         // the PTY opens, before the React frontend has had time to mount the
-        // Terminal component and register its event listener. We buffer everything
-        // until the frontend calls claim_session_output(), which drains the buffer
-        // and switches to streaming mode.
-        let initial_buffer: Arc<StdMutex<Option<Vec<u8>>>> =
-            Arc::new(StdMutex::new(Some(Vec::new())));
-        let initial_buffer_clone = Arc::clone(&initial_buffer);
+        // Terminal component and attach its channel. We buffer everything until
+        // the frontend calls attach_session_output(), which drains the buffer and
+        // switches to streaming mode.
+        let output = Arc::new(SessionOutput::new());
+        let output_clone = Arc::clone(&output);
+
+        let screen = snapshot::new_screen_state();
+        let screen_clone = Arc::clone(&screen);
+        let screen_resize = Arc::clone(&screen);
+
+        let mut shell = shell_integration::Tracker::new();
+        let cwd = shell.cwd();
 
         // Clone reader before taking writer
         let mut reader = pair
@@ -177,11 +206,18 @@ impl LocalSession {
                                 tracing::error!("Failed to resize PTY: {}", e);
                             } else {
                                 tracing::debug!("Terminal resized successfully");
+                                if let Ok(mut screen) = screen_resize.lock() {
+                                    screen.set_size(rows as u16, cols as u16);
+                                }
                             }
                         } else {
                             tracing::error!("Failed to lock master PTY mutex for resize");
                         }
                     }
+                    // Local terminals have no protection level
+                    SessionCommand::ConfirmCommand(_)
+                    | SessionCommand::Forward(_)
+                    | SessionCommand::Zmodem(_) => {}
                     SessionCommand::Close => {
                         tracing::debug!("Closing session {}", session_id_clone);
                         break;
@@ -196,27 +232,23 @@ impl LocalSession {
         let app_handle_clone2 = app_handle.clone();
         tokio::task::spawn_blocking(move || {
             tracing::debug!("PTY reader loop starting for session {}", session_id_clone2);
-            let mut buffer = [0u8; 8192];
 
             loop {
-                match reader.read(&mut buffer) {
+                // Paused by the frontend: the shell blocks once the PTY is full
+                output_clone.block_while_paused();
+                // Read straight into a frame so streaming needs no copy
+                let mut frame = Frame::default();
+                match reader.read(frame.read_buf()) {
                     Ok(n) if n > 0 => {
-                        let mut buf_guard = initial_buffer_clone.lock().unwrap();
-                        if let Some(ref mut buf) = *buf_guard {
-                            // Buffering mode: accumulate until frontend calls claim.
-                            buf.extend_from_slice(&buffer[..n]);
-                        } else {
-                            // Streaming mode: frontend has claimed the buffer.
-                            let data_base64 =
-                                base64::engine::general_purpose::STANDARD.encode(&buffer[..n]);
-                            let _ = app_handle_clone2.emit(
-                                "terminal-data",
-                                serde_json::json!({
-                                    "sessionId": session_id_clone2,
-                                    "data": data_base64,
-                                }),
-                            );
+                        frame.truncate(n);
+                        if let Ok(mut screen) = screen_clone.lock() {
+                            screen.process(frame.payload());
                         }
+                        for event in shell.feed(frame.payload()) {
+                            shell_integration::emit(&app_handle_clone2, &session_id_clone2, &event);
+                        }
+
+                        output_clone.deliver_frame(frame);
                     }
                     Ok(_) => {
                         tracing::info!("PTY EOF detected for session {}", session_id_clone2);
@@ -259,16 +291,25 @@ impl LocalSession {
         Ok(Self {
             id: session_id,
             command_tx,
-            initial_buffer,
+            output,
+            screen,
+            cwd,
         })
     }
 
-    /// Drain the initial output buffer and switch to streaming mode.
-    /// Returns all bytes received before the frontend registered its listener.
-    /// After this call, new PTY data is emitted as `terminal-data` events.
-    pub fn claim_initial_output(&self) -> Vec<u8> {
-        let mut guard = self.initial_buffer.lock().unwrap();
-        guard.take().unwrap_or_default()
+    /// Output sink of this session
+    pub fn output(&self) -> &SessionOutput {
+        &self.output
+    }
+
+    /// Backend screen state of the session
+    pub fn screen(&self) -> &ScreenState {
+        &self.screen
+    }
+
+    /// Working directory reported by the shell
+    pub fn cwd(&self) -> &CwdState {
+        &self.cwd
     }
 
     /// Send input to the local terminal
@@ -290,7 +331,7 @@ impl LocalSession {
     }
 
     /// Close the session
-    pub async fn close(self) -> Result<()> {
+    pub async fn close(&self) -> Result<()> {
         self.command_tx
             .send(SessionCommand::Close)
             .await
@@ -0,0 +1,57 @@
+/**
+ * Locale Module
+ *
+ * Local locale detection and validation for remote LANG/LC_ALL forwarding
+ */
+
+/// Fallback locale when the local system has none (or only C/POSIX)
+pub const DEFAULT_LOCALE: &str = "en_US.UTF-8";
+
+/// Detect the local system locale from the environment
+///
+/// Checks LC_ALL, LC_CTYPE and LANG in order (POSIX precedence), ignoring
+/// the C/POSIX locales that cause mojibake on remote hosts.
+pub fn detect_local_locale() -> String {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| is_valid_locale(value) && !is_posix_locale(value))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// Check that a locale name is safe to send to a remote host
+///
+/// Locale names may end up in a shell command line, so only the characters
+/// used by real locale names are accepted (e.g. `fr_FR.UTF-8`, `sr_RS@latin`).
+pub fn is_valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= 64
+        && locale
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '@'))
+}
+
+fn is_posix_locale(locale: &str) -> bool {
+    matches!(locale, "C" | "POSIX") || locale.starts_with("C.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_validation() {
+        assert!(is_valid_locale("en_US.UTF-8"));
+        assert!(is_valid_locale("sr_RS@latin"));
+        assert!(!is_valid_locale(""));
+        assert!(!is_valid_locale("en_US; rm -rf /"));
+        assert!(!is_valid_locale("$(whoami)"));
+    }
+
+    #[test]
+    fn test_posix_locale_detection() {
+        assert!(is_posix_locale("C"));
+        assert!(is_posix_locale("C.UTF-8"));
+        assert!(!is_posix_locale("de_DE.UTF-8"));
+    }
+}
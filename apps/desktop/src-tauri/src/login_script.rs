@@ -0,0 +1,306 @@
+/**
+ * Login Script Module
+ *
+ * Expect-style login automation (expect pattern -> send response) for
+ * devices that prompt for credentials after the transport is connected.
+ * A step without a pattern is sent right away (e.g. Enter to wake a
+ * console server port); a step's failure pattern (e.g. "Login incorrect")
+ * stops the script without waiting for its timeout.
+ */
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+use crate::prompt::strip_ansi;
+
+/// Default time to wait for each expected pattern
+pub const DEFAULT_STEP_TIMEOUT_SECS: u64 = 15;
+
+/// Maximum amount of trailing output kept for matching
+const MAX_BUFFER_LEN: usize = 8192;
+
+/// One step of a login script, stored in the connection's session options
+///
+/// `send` may contain `{{username}}` and `{{password}}` placeholders which are
+/// filled in from the connection's encrypted credentials at connect time, so
+/// secrets never end up in the (unencrypted) script itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExpectStep {
+    /// Regex matched against the output received since the previous step
+    /// (empty = send without waiting)
+    pub expect: String,
+    /// Response sent (followed by Enter) once the pattern matches
+    pub send: String,
+    /// Seconds to wait for the pattern (None = default)
+    pub timeout_secs: Option<u64>,
+    /// Regex that makes the script fail when it shows up while waiting
+    pub fail: Option<String>,
+}
+
+struct CompiledStep {
+    /// None = send without waiting
+    pattern: Option<Regex>,
+    fail: Option<Regex>,
+    send: Zeroizing<String>,
+    timeout: Duration,
+}
+
+/// Running login script for one session
+pub struct LoginScript {
+    steps: VecDeque<CompiledStep>,
+    buffer: Vec<u8>,
+    completed: usize,
+}
+
+impl LoginScript {
+    /// Compile a script and expand credential placeholders
+    /// (None when the script has no steps)
+    pub fn new(
+        steps: &[ExpectStep],
+        username: &str,
+        password: Option<&str>,
+    ) -> Result<Option<Self>> {
+        if steps.is_empty() {
+            return Ok(None);
+        }
+
+        let steps = steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let pattern = (!step.expect.is_empty())
+                    .then(|| Regex::new(&step.expect))
+                    .transpose()
+                    .map_err(|e| {
+                        anyhow!("Invalid pattern in login script step {}: {}", index + 1, e)
+                    })?;
+                let fail = step
+                    .fail
+                    .as_deref()
+                    .filter(|fail| !fail.is_empty())
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| {
+                        anyhow!(
+                            "Invalid failure pattern in login script step {}: {}",
+                            index + 1,
+                            e
+                        )
+                    })?;
+
+                let mut send = Zeroizing::new(step.send.replace("{{username}}", username));
+                if send.contains("{{password}}") {
+                    let password = password.ok_or_else(|| {
+                        anyhow!("Login script uses {{{{password}}}} but the connection has no stored password")
+                    })?;
+                    send = Zeroizing::new(send.replace("{{password}}", password));
+                }
+
+                let timeout = step
+                    .timeout_secs
+                    .filter(|secs| *secs > 0)
+                    .unwrap_or(DEFAULT_STEP_TIMEOUT_SECS);
+
+                Ok(CompiledStep {
+                    pattern,
+                    fail,
+                    send,
+                    timeout: Duration::from_secs(timeout),
+                })
+            })
+            .collect::<Result<VecDeque<_>>>()?;
+
+        Ok(Some(Self {
+            steps,
+            buffer: Vec::new(),
+            completed: 0,
+        }))
+    }
+
+    /// Timeout of the step currently waiting for its pattern
+    pub fn timeout(&self) -> Option<Duration> {
+        self.steps.front().map(|step| step.timeout)
+    }
+
+    /// 1-based number of the step currently waiting for its pattern
+    pub fn current_step(&self) -> usize {
+        self.completed + 1
+    }
+
+    /// Responses of the leading steps that don't wait for output, sent
+    /// as soon as the shell is started
+    pub fn start(&mut self) -> Option<Zeroizing<Vec<u8>>> {
+        let mut response = Zeroizing::new(Vec::new());
+        self.send_immediate(&mut response);
+        (!response.is_empty()).then_some(response)
+    }
+
+    /// Feed session output; returns the response to send once the current
+    /// step's pattern matches (with the responses of the steps after it
+    /// that don't wait), or an error when its failure pattern shows up
+    pub fn on_output(&mut self, data: &[u8]) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        let Some(step) = self.steps.front() else {
+            return Ok(None);
+        };
+
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() > MAX_BUFFER_LEN {
+            let excess = self.buffer.len() - MAX_BUFFER_LEN;
+            self.buffer.drain(..excess);
+        }
+
+        let text = strip_ansi(&String::from_utf8_lossy(&self.buffer));
+        if let Some(found) = step.fail.as_ref().and_then(|fail| fail.find(&text)) {
+            let error = anyhow!("Login failed: \"{}\"", found.as_str().trim());
+            self.steps.clear();
+            return Err(error);
+        }
+        if !step.pattern.as_ref().is_some_and(|p| p.is_match(&text)) {
+            return Ok(None);
+        }
+
+        let mut response = Zeroizing::new(Vec::new());
+        self.respond(&mut response);
+        self.send_immediate(&mut response);
+        Ok(Some(response))
+    }
+
+    /// Complete the current step, appending its response
+    fn respond(&mut self, response: &mut Vec<u8>) {
+        if let Some(step) = self.steps.pop_front() {
+            self.buffer.clear();
+            self.completed += 1;
+            response.extend_from_slice(step.send.as_bytes());
+            response.push(b'\r');
+        }
+    }
+
+    /// Complete the steps without a pattern at the front of the script
+    fn send_immediate(&mut self, response: &mut Vec<u8>) {
+        while self
+            .steps
+            .front()
+            .is_some_and(|step| step.pattern.is_none())
+        {
+            self.respond(response);
+        }
+    }
+
+    /// True once every step has matched
+    pub fn is_done(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(expect: &str, send: &str) -> ExpectStep {
+        ExpectStep {
+            expect: expect.to_string(),
+            send: send.to_string(),
+            timeout_secs: None,
+            fail: None,
+        }
+    }
+
+    #[test]
+    fn test_login_sequence() {
+        let steps = vec![
+            step("(?i)username:", "{{username}}"),
+            step("(?i)password:", "{{password}}"),
+            step(r">\s*$", "enable"),
+        ];
+        let mut script = LoginScript::new(&steps, "admin", Some("s3cret"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(script.current_step(), 1);
+        assert!(script.start().is_none());
+        assert!(script
+            .on_output(b"\r\nUser Access Verification\r\n")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            script.on_output(b"Username: ").unwrap().unwrap().as_slice(),
+            b"admin\r"
+        );
+        assert_eq!(
+            script
+                .on_output(b"admin\r\nPassword: ")
+                .unwrap()
+                .unwrap()
+                .as_slice(),
+            b"s3cret\r"
+        );
+        assert_eq!(
+            script
+                .on_output(b"\r\n\x1b[1mswitch01\x1b[0m> ")
+                .unwrap()
+                .unwrap()
+                .as_slice(),
+            b"enable\r"
+        );
+        assert!(script.is_done());
+        assert!(script.on_output(b"Password: ").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_send_steps_and_failure() {
+        let steps = vec![
+            // Wake the console port, then log in
+            step("", ""),
+            step("login:", "{{username}}"),
+            ExpectStep {
+                fail: Some("(?i)login incorrect".to_string()),
+                ..step("Password:", "{{password}}")
+            },
+            step("", "terminal length 0"),
+        ];
+        let mut script = LoginScript::new(&steps, "admin", Some("s3cret"))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(script.start().unwrap().as_slice(), b"\r");
+        assert_eq!(script.current_step(), 2);
+        assert_eq!(
+            script.on_output(b"login: ").unwrap().unwrap().as_slice(),
+            b"admin\r"
+        );
+        // The step without a pattern goes out with the password
+        assert_eq!(
+            script.on_output(b"Password: ").unwrap().unwrap().as_slice(),
+            b"s3cret\rterminal length 0\r"
+        );
+        assert!(script.is_done());
+
+        let mut script = LoginScript::new(&steps, "admin", Some("wrong"))
+            .unwrap()
+            .unwrap();
+        script.start();
+        script.on_output(b"login: ").unwrap();
+        let error = script
+            .on_output(b"admin\r\n\r\nLogin incorrect\r\nlogin: ")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Login failed: \"Login incorrect\"");
+        assert_eq!(script.current_step(), 3);
+        assert!(script.timeout().is_none());
+    }
+
+    #[test]
+    fn test_invalid_scripts() {
+        assert!(LoginScript::new(&[], "admin", None).unwrap().is_none());
+        assert!(LoginScript::new(&[step("([bad", "x")], "admin", None).is_err());
+        let bad_fail = ExpectStep {
+            fail: Some("([bad".to_string()),
+            ..step("login:", "x")
+        };
+        assert!(LoginScript::new(&[bad_fail], "admin", None).is_err());
+        assert!(LoginScript::new(&[step("Password:", "{{password}}")], "admin", None).is_err());
+    }
+}
@@ -1,20 +1,70 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use tauri::Manager;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
-mod auth;
+mod audit;
+mod auth_prompt;
+mod biometric;
+mod bootstrap;
+mod clipboard;
+mod clipboard_bridge;
+mod command_guard;
 mod commands;
 mod connection;
+mod connection_test;
 mod connections_manager;
-mod db;
+mod encoding;
+mod events;
+mod exec;
+mod importers;
+mod keep_alive;
+mod key_format;
+mod keygen;
 mod known_hosts;
+mod line_mode;
 mod local_terminal;
+mod locale;
+mod login_script;
+mod monitoring;
+mod openssh_known_hosts;
+mod output;
+mod output_frame;
+mod pending_host_keys;
+mod policy;
+mod port_forward;
+mod power;
+mod prompt;
+mod reconnect;
+mod recording;
+mod redact;
+mod remote_tasks;
+mod scrollback;
+mod security_key;
+mod session_options;
+mod session_restore;
+mod session_stats;
+mod session_trace;
+mod sftp;
+mod share;
+mod shell_integration;
+mod snapshot;
+mod snippets;
 mod ssh_config;
+mod ssh_config_sync;
+mod sshfp;
 mod state;
+mod tasks;
+mod template;
 mod terminal;
 mod theme;
+mod time_limit;
+mod transfers;
+mod triggers;
+mod warm;
+mod zmodem;
 
 use state::AppState;
 
@@ -51,41 +101,182 @@ fn main() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(app_state)
+        .setup(|app| {
+            app.state::<AppState>().events.attach(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::health_check,
             commands::validate_password,
+            commands::generate_password,
             commands::is_first_run,
             commands::is_locked,
             commands::setup_master_password,
             commands::unlock,
             commands::lock,
+            commands::is_biometric_available,
+            commands::unlock_with_biometric,
+            commands::enable_biometric_unlock,
+            commands::disable_biometric_unlock,
+            commands::change_master_password,
+            commands::calibrate_kdf,
+            commands::get_warm_connections,
+            commands::authorize_sensitive_action,
             commands::reset_database,
+            commands::get_vault_format,
+            commands::backup_vault,
+            commands::list_backups,
+            commands::restore_vault,
+            commands::is_database_encrypted,
+            commands::encrypt_database,
+            commands::decrypt_database,
+            commands::list_background_tasks,
+            commands::restart_background_task,
             commands::create_connection,
             commands::get_all_connections,
             commands::get_connection,
             commands::update_connection,
+            commands::reveal_credentials,
+            commands::import_private_key,
+            commands::export_private_key,
+            commands::copy_secret_to_clipboard,
+            commands::duplicate_connection,
             commands::delete_connection,
+            commands::list_trashed_connections,
+            commands::restore_connection,
+            commands::purge_trash,
+            commands::test_connection,
+            commands::list_connection_templates,
+            commands::create_connection_template,
+            commands::delete_connection_template,
+            commands::create_template_from_connection,
+            commands::create_from_template,
+            commands::list_snippets,
+            commands::create_snippet,
+            commands::update_snippet,
+            commands::delete_snippet,
+            commands::get_snippet_variables,
+            commands::run_snippet,
+            commands::exec_command,
+            commands::list_remote_tasks,
+            commands::create_remote_task,
+            commands::update_remote_task,
+            commands::delete_remote_task,
+            commands::run_remote_task,
+            commands::list_remote_task_runs,
+            commands::get_host_metrics,
+            commands::start_host_monitoring,
+            commands::stop_host_monitoring,
             commands::parse_ssh_config,
             commands::import_ssh_config_entries,
+            commands::get_ssh_config_sync_suggestions,
+            commands::apply_ssh_config_sync,
+            commands::dismiss_ssh_config_sync,
+            commands::preview_import,
+            commands::run_import,
             commands::get_default_ssh_config_path,
+            commands::convert_key,
+            commands::get_key_certificate,
+            commands::generate_ssh_key,
+            commands::search_connections,
             commands::get_connections_by_folder,
+            commands::move_connection_to_folder,
+            commands::list_folders,
+            commands::get_folder_tree,
+            commands::create_folder,
+            commands::update_folder,
+            commands::move_folder,
+            commands::delete_folder,
+            commands::list_tags,
+            commands::create_tag,
+            commands::update_tag,
+            commands::delete_tag,
+            commands::search_connections_by_tags,
             commands::count_saved_connections,
+            commands::get_connection_stats,
+            commands::get_connection_history,
+            commands::get_most_used_connections,
+            commands::get_recent_connections,
             commands::connect_terminal,
             commands::connect_local_terminal,
             commands::get_installed_shells,
             commands::quick_ssh_connect,
+            commands::answer_auth_prompt,
+            commands::save_quick_session,
             commands::send_terminal_input,
+            commands::send_stored_secret,
             commands::resize_terminal,
             commands::disconnect_terminal,
-            commands::claim_session_output,
+            commands::share_session,
+            commands::stop_sharing_session,
+            commands::list_session_shares,
+            commands::start_port_forward,
+            commands::stop_port_forward,
+            commands::list_port_forwards,
+            commands::open_sftp_session,
+            commands::sftp_list_dir,
+            commands::sftp_stat,
+            commands::sftp_rename,
+            commands::sftp_chmod,
+            commands::sftp_delete,
+            commands::sftp_mkdir,
+            commands::sftp_read_link,
+            commands::close_sftp_session,
+            commands::queue_transfer,
+            commands::pause_transfer,
+            commands::resume_transfer,
+            commands::cancel_transfer,
+            commands::list_transfers,
+            commands::attach_session_output,
+            commands::pause_output,
+            commands::resume_output,
+            commands::get_session_scrollback,
             commands::list_terminal_sessions,
+            commands::get_restorable_sessions,
+            commands::get_restored_scrollback,
+            commands::set_open_sessions,
+            commands::set_session_cwd,
+            commands::snapshot_session,
+            commands::export_session_transcript,
+            commands::get_session_cwd,
+            commands::get_session_stats,
+            commands::watch_session_stats,
+            commands::confirm_terminal_command,
+            commands::answer_zmodem,
+            commands::set_session_debug,
+            commands::get_session_trace,
+            commands::start_session_recording,
+            commands::stop_session_recording,
+            commands::get_session_recording,
+            commands::list_recordings,
+            commands::read_recording,
+            commands::delete_recording,
             commands::get_setting,
             commands::set_setting,
             commands::get_all_settings,
+            commands::get_settings,
+            commands::get_audit_log,
+            commands::list_known_hosts,
+            commands::remove_known_host,
+            commands::update_known_host,
+            commands::export_known_hosts,
+            commands::import_known_hosts,
+            commands::get_default_known_hosts_path,
+            commands::get_ssh_client_id_presets,
+            commands::get_prompt_profiles,
+            commands::get_local_locale,
+            commands::get_session_encodings,
             theme::load_theme,
             theme::list_themes,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Save the open terminals (and their scrollback) for the next launch
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(app.state::<AppState>().save_open_sessions());
+            }
+        });
 }
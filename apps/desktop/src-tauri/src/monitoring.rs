@@ -0,0 +1,430 @@
+/**
+ * Host Monitoring
+ *
+ * Lightweight health probes for the dashboard: one exec channel per host
+ * runs a short POSIX script (uptime, load, memory, disks, logged-in users)
+ * and its output is parsed into `HostMetrics`. Probes run on demand, or
+ * every few seconds for the watched connections, in which case each round
+ * is sent as a `host-metrics` event.
+ *
+ * Memory and uptime come from /proc and are only known on Linux hosts;
+ * load, disks and users come from `uptime`, `df -P` and `who`.
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::task::JoinSet;
+
+use crate::exec;
+
+/// Event carrying the `HostMetrics` of a monitoring round
+pub const EVENT_METRICS: &str = "host-metrics";
+
+/// Name of the periodic monitoring task
+pub const TASK_NAME: &str = "host-monitoring";
+
+/// Shortest interval between two monitoring rounds
+pub const MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Longest a probe may take (connection included)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Sections are introduced by `@@<name>` lines
+const PROBE_SCRIPT: &str = "sh -c 'echo @@uptime; cat /proc/uptime 2>/dev/null; \
+    echo @@load; uptime 2>/dev/null; \
+    echo @@memory; cat /proc/meminfo 2>/dev/null; \
+    echo @@disks; df -P -k 2>/dev/null; \
+    echo @@users; who 2>/dev/null'";
+
+/// Pseudo filesystems left out of the disk list
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "tmpfs", "devtmpfs", "udev", "overlay", "shm", "none", "devfs", "map",
+];
+
+/// Memory of a host (KiB)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    pub total_kib: u64,
+    pub available_kib: u64,
+    pub swap_total_kib: u64,
+    pub swap_free_kib: u64,
+}
+
+/// Mounted filesystem of a host (KiB)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub filesystem: String,
+    pub mount: String,
+    pub total_kib: u64,
+    pub used_kib: u64,
+    pub available_kib: u64,
+}
+
+/// Logged-in user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSession {
+    pub name: String,
+    pub tty: String,
+    /// Remote host, for remote logins
+    pub from: Option<String>,
+}
+
+/// Result of probing a host
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMetrics {
+    pub connection_id: String,
+    /// Unix timestamp in milliseconds
+    pub collected_at: i64,
+    pub uptime_secs: Option<u64>,
+    /// 1, 5 and 15 minute load averages
+    pub load: Option<[f64; 3]>,
+    pub memory: Option<MemoryUsage>,
+    pub disks: Vec<DiskUsage>,
+    pub users: Vec<UserSession>,
+    /// Why the probe failed (the other fields are empty then)
+    pub error: Option<String>,
+}
+
+/// Latest metrics per connection, and the connections monitored periodically
+#[derive(Clone, Default)]
+pub struct HostMonitor {
+    latest: Arc<StdMutex<HashMap<String, HostMetrics>>>,
+    watched: Arc<StdMutex<Vec<String>>>,
+}
+
+impl HostMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metrics of the connections, probed now when `refresh` is set or
+    /// when a connection has none yet
+    pub async fn metrics(
+        &self,
+        app_handle: &AppHandle,
+        connection_ids: &[String],
+        refresh: bool,
+    ) -> Vec<HostMetrics> {
+        let cached = self.cached(connection_ids);
+        let missing: Vec<String> = connection_ids
+            .iter()
+            .filter(|id| refresh || !cached.contains_key(*id))
+            .cloned()
+            .collect();
+        let mut probed: HashMap<String, HostMetrics> = self
+            .probe_all(app_handle, &missing)
+            .await
+            .into_iter()
+            .map(|metrics| (metrics.connection_id.clone(), metrics))
+            .collect();
+
+        connection_ids
+            .iter()
+            .filter_map(|id| probed.remove(id).or_else(|| cached.get(id).cloned()))
+            .collect()
+    }
+
+    /// Probe the connections in parallel and keep the results
+    pub async fn probe_all(
+        &self,
+        app_handle: &AppHandle,
+        connection_ids: &[String],
+    ) -> Vec<HostMetrics> {
+        let mut probes = JoinSet::new();
+        for (index, connection_id) in connection_ids.iter().enumerate() {
+            let app_handle = app_handle.clone();
+            let connection_id = connection_id.clone();
+            probes.spawn(async move { (index, probe(&app_handle, &connection_id).await) });
+        }
+
+        let mut results = Vec::with_capacity(connection_ids.len());
+        while let Some(result) = probes.join_next().await {
+            if let Ok(result) = result {
+                results.push(result);
+            }
+        }
+        results.sort_by_key(|(index, _)| *index);
+        let results: Vec<HostMetrics> = results.into_iter().map(|(_, metrics)| metrics).collect();
+
+        if let Ok(mut latest) = self.latest.lock() {
+            for metrics in &results {
+                latest.insert(metrics.connection_id.clone(), metrics.clone());
+            }
+        }
+        results
+    }
+
+    /// Replace the connections monitored periodically
+    pub fn watch(&self, connection_ids: Vec<String>) {
+        if let Ok(mut watched) = self.watched.lock() {
+            *watched = connection_ids;
+        }
+    }
+
+    /// Connections monitored periodically
+    pub fn watched(&self) -> Vec<String> {
+        self.watched
+            .lock()
+            .map(|watched| watched.clone())
+            .unwrap_or_default()
+    }
+
+    /// One periodic round: probe the watched connections and send the results
+    pub async fn run_round(&self, app_handle: &AppHandle) {
+        let watched = self.watched();
+        if watched.is_empty() {
+            return;
+        }
+        let metrics = self.probe_all(app_handle, &watched).await;
+        let _ = app_handle.emit(EVENT_METRICS, &metrics);
+    }
+
+    fn cached(&self, connection_ids: &[String]) -> HashMap<String, HostMetrics> {
+        let Ok(latest) = self.latest.lock() else {
+            return HashMap::new();
+        };
+        connection_ids
+            .iter()
+            .filter_map(|id| latest.get(id).map(|metrics| (id.clone(), metrics.clone())))
+            .collect()
+    }
+}
+
+/// Probe one host (a failed probe is reported in `error`)
+pub async fn probe(app_handle: &AppHandle, connection_id: &str) -> HostMetrics {
+    let result = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        exec::exec_command(app_handle, connection_id, PROBE_SCRIPT, PROBE_TIMEOUT, true),
+    )
+    .await
+    .unwrap_or_else(|_| Err(anyhow!("Timed out")));
+
+    let mut metrics = match result {
+        Ok(output) if !output.timed_out => parse(&output.stdout),
+        Ok(_) => failed(connection_id, anyhow!("Timed out")),
+        Err(e) => failed(connection_id, e),
+    };
+    metrics.connection_id = connection_id.to_string();
+    metrics.collected_at = chrono::Utc::now().timestamp_millis();
+    metrics
+}
+
+/// Metrics of a failed probe
+fn failed(connection_id: &str, error: anyhow::Error) -> HostMetrics {
+    tracing::warn!(
+        "[monitoring.rs] Probe of {} failed: {}",
+        connection_id,
+        error
+    );
+    HostMetrics {
+        error: Some(error.to_string()),
+        ..HostMetrics::default()
+    }
+}
+
+/// Check the connections and interval of periodic monitoring
+pub fn validate(connection_ids: &[String], interval: Duration) -> Result<()> {
+    if connection_ids.is_empty() {
+        return Err(anyhow!("Select at least one connection"));
+    }
+    if interval < MIN_INTERVAL {
+        return Err(anyhow!(
+            "Interval must be at least {} seconds",
+            MIN_INTERVAL.as_secs()
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the output of the probe script
+fn parse(output: &str) -> HostMetrics {
+    let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut current = None;
+    for line in output.lines() {
+        match line.trim_end().strip_prefix("@@") {
+            Some(name) => current = Some(name),
+            None => {
+                if let Some(name) = current {
+                    sections.entry(name).or_default().push(line);
+                }
+            }
+        }
+    }
+    let section = |name: &str| sections.get(name).map(Vec::as_slice).unwrap_or_default();
+
+    HostMetrics {
+        uptime_secs: section("uptime")
+            .first()
+            .and_then(|line| line.split_whitespace().next())
+            .and_then(|secs| secs.parse::<f64>().ok())
+            .map(|secs| secs as u64),
+        load: section("load").first().and_then(|line| parse_load(line)),
+        memory: parse_memory(section("memory")),
+        disks: section("disks")
+            .iter()
+            .skip(1)
+            .filter_map(|line| parse_disk(line))
+            .collect(),
+        users: section("users")
+            .iter()
+            .filter_map(|line| parse_user(line))
+            .collect(),
+        ..HostMetrics::default()
+    }
+}
+
+/// Load averages of an `uptime` line (`load average: 0.10, 0.20, 0.30`, or
+/// `load averages: 0.10 0.20 0.30` on BSD and macOS)
+fn parse_load(line: &str) -> Option<[f64; 3]> {
+    let (_, averages) = line.rsplit_once("load average")?;
+    let averages = averages.split_once(':')?.1.replace(',', " ");
+    let mut values = averages.split_whitespace().map(|v| v.parse::<f64>().ok());
+    Some([values.next()??, values.next()??, values.next()??])
+}
+
+/// Memory from /proc/meminfo (None when the host has no /proc)
+fn parse_memory(lines: &[&str]) -> Option<MemoryUsage> {
+    let fields: HashMap<&str, u64> = lines
+        .iter()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name, value.split_whitespace().next()?.parse().ok()?))
+        })
+        .collect();
+
+    let total_kib = *fields.get("MemTotal")?;
+    // MemAvailable is missing before Linux 3.14
+    let available_kib = fields.get("MemAvailable").copied().unwrap_or_else(|| {
+        ["MemFree", "Buffers", "Cached"]
+            .iter()
+            .filter_map(|name| fields.get(name))
+            .sum()
+    });
+    Some(MemoryUsage {
+        total_kib,
+        available_kib,
+        swap_total_kib: fields.get("SwapTotal").copied().unwrap_or(0),
+        swap_free_kib: fields.get("SwapFree").copied().unwrap_or(0),
+    })
+}
+
+/// Filesystem of a `df -P -k` line (mount points may contain spaces)
+fn parse_disk(line: &str) -> Option<DiskUsage> {
+    let mut fields = line.split_whitespace();
+    let filesystem = fields.next()?;
+    let total_kib: u64 = fields.next()?.parse().ok()?;
+    let used_kib = fields.next()?.parse().ok()?;
+    let available_kib = fields.next()?.parse().ok()?;
+    let _capacity = fields.next()?;
+    let mount = fields.collect::<Vec<_>>().join(" ");
+
+    if total_kib == 0 || mount.is_empty() || PSEUDO_FILESYSTEMS.contains(&filesystem) {
+        return None;
+    }
+    Some(DiskUsage {
+        filesystem: filesystem.to_string(),
+        mount,
+        total_kib,
+        used_kib,
+        available_kib,
+    })
+}
+
+/// User of a `who` line (`deploy pts/0 2026-10-16 09:12 (10.0.0.5)`)
+fn parse_user(line: &str) -> Option<UserSession> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?.to_string();
+    let tty = fields.next()?.to_string();
+    let from = line
+        .trim_end()
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once('('))
+        .map(|(_, from)| from.to_string())
+        .filter(|from| !from.is_empty());
+    Some(UserSession { name, tty, from })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_linux_probe() {
+        let output = "@@uptime\n\
+            93784.51 371001.02\n\
+            @@load\n\
+            \x2009:12:01 up 1 day,  2:03,  2 users,  load average: 0.52, 0.58, 0.59\n\
+            @@memory\n\
+            MemTotal:        8046136 kB\n\
+            MemFree:          512000 kB\n\
+            MemAvailable:    4023068 kB\n\
+            SwapTotal:       2097148 kB\n\
+            SwapFree:        2097148 kB\n\
+            @@disks\n\
+            Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+            /dev/sda1         41152736 12582912  26465792      33% /\n\
+            tmpfs              4023068        0   4023068       0% /dev/shm\n\
+            /dev/sdb1        103081248  1048576  96772544       2% /mnt/My Data\n\
+            @@users\n\
+            deploy   pts/0        2026-10-16 09:12 (10.0.0.5)\n\
+            root     tty1         2026-10-15 07:00\n";
+
+        let metrics = parse(output);
+        assert_eq!(metrics.uptime_secs, Some(93784));
+        assert_eq!(metrics.load, Some([0.52, 0.58, 0.59]));
+        assert_eq!(
+            metrics.memory,
+            Some(MemoryUsage {
+                total_kib: 8046136,
+                available_kib: 4023068,
+                swap_total_kib: 2097148,
+                swap_free_kib: 2097148,
+            })
+        );
+        let mounts: Vec<_> = metrics.disks.iter().map(|d| d.mount.as_str()).collect();
+        assert_eq!(mounts, vec!["/", "/mnt/My Data"]);
+        assert_eq!(metrics.disks[0].used_kib, 12582912);
+        assert_eq!(
+            metrics.users,
+            vec![
+                UserSession {
+                    name: "deploy".to_string(),
+                    tty: "pts/0".to_string(),
+                    from: Some("10.0.0.5".to_string()),
+                },
+                UserSession {
+                    name: "root".to_string(),
+                    tty: "tty1".to_string(),
+                    from: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_host_without_proc() {
+        // macOS: no /proc, BSD load format, no users logged in
+        let output = "@@uptime\n@@load\n\
+            10:00  up 3 days, 4:05, 1 user, load averages: 1.23 1.45 1.67\n\
+            @@memory\n@@disks\n\
+            Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+            /dev/disk3s1s1 482797652 10276864 254163780 4% /\n\
+            map auto_home 0 0 0 100% /System/Volumes/Data/home\n\
+            @@users\n";
+
+        let metrics = parse(output);
+        assert_eq!(metrics.uptime_secs, None);
+        assert_eq!(metrics.load, Some([1.23, 1.45, 1.67]));
+        assert_eq!(metrics.memory, None);
+        assert_eq!(metrics.disks.len(), 1);
+        assert!(metrics.users.is_empty());
+        assert!(parse("").load.is_none());
+    }
+}
@@ -0,0 +1,326 @@
+/**
+ * OpenSSH Known Hosts Module
+ *
+ * Parses OpenSSH `known_hosts` files (plain, wildcard and hashed `|1|`
+ * host patterns, markers, several keys per host) for importing into the
+ * known_hosts table and for checking host keys against the system files.
+ */
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use russh::keys::PublicKey;
+use sha1::Sha1;
+use std::fs;
+use std::path::Path;
+
+/// Global known hosts file (consulted after the user's file)
+const GLOBAL_KNOWN_HOSTS_PATH: &str = "/etc/ssh/ssh_known_hosts";
+
+/// Line marker (`@cert-authority`, `@revoked`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    CertAuthority,
+    Revoked,
+}
+
+/// Host pattern of a known_hosts line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostPattern {
+    /// Host name or `[host]:port`, may contain `*` and `?` wildcards
+    Plain { pattern: String, negated: bool },
+    /// `|1|salt|hash`: HMAC-SHA1 of the host name, keyed with the salt
+    Hashed { salt: Vec<u8>, hash: Vec<u8> },
+}
+
+/// Parsed known_hosts line
+#[derive(Debug, Clone)]
+pub struct KnownHostsEntry {
+    pub marker: Option<Marker>,
+    pub patterns: Vec<HostPattern>,
+    pub key: PublicKey,
+}
+
+impl KnownHostsEntry {
+    /// Whether the line applies to a host and port
+    ///
+    /// A matching negated pattern (`!host`) excludes the host even when
+    /// another pattern matches it.
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let name = host_name(host, port);
+        let mut matched = false;
+        for pattern in &self.patterns {
+            match pattern {
+                HostPattern::Plain { pattern, negated } => {
+                    if wildcard_match(&pattern.to_lowercase(), &name) {
+                        if *negated {
+                            return false;
+                        }
+                        matched = true;
+                    }
+                }
+                HostPattern::Hashed { salt, hash } => {
+                    matched |= hash_host_name(salt, &name).is_some_and(|h| &h == hash);
+                }
+            }
+        }
+        matched
+    }
+
+    /// Host and port of the plain patterns that name exactly one host
+    /// (no wildcards, not negated)
+    pub fn plain_hosts(&self) -> Vec<(String, u16)> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| match pattern {
+                HostPattern::Plain {
+                    pattern,
+                    negated: false,
+                } if !pattern.contains(['*', '?']) => split_host_port(pattern),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the line has hashed host patterns
+    pub fn is_hashed(&self) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| matches!(pattern, HostPattern::Hashed { .. }))
+    }
+}
+
+/// Parse a known_hosts file
+pub fn parse_known_hosts<P: AsRef<Path>>(path: P) -> Result<Vec<KnownHostsEntry>> {
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read known_hosts file: {:?}", path.as_ref()))?;
+
+    Ok(parse_known_hosts_str(&content))
+}
+
+/// Parse known_hosts content
+///
+/// Lines that can't be parsed (unknown key types, broken base64) are
+/// skipped, like OpenSSH does.
+pub fn parse_known_hosts_str(content: &str) -> Vec<KnownHostsEntry> {
+    content.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<KnownHostsEntry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut fields = line.split_whitespace();
+    let mut hosts = fields.next()?;
+    let marker = match hosts {
+        "@cert-authority" => Some(Marker::CertAuthority),
+        "@revoked" => Some(Marker::Revoked),
+        _ if hosts.starts_with('@') => return None,
+        _ => None,
+    };
+    if marker.is_some() {
+        hosts = fields.next()?;
+    }
+
+    let key_type = fields.next()?;
+    let key_data = fields.next()?;
+    let key = PublicKey::from_openssh(&format!("{} {}", key_type, key_data)).ok()?;
+
+    let patterns = hosts
+        .split(',')
+        .filter(|pattern| !pattern.is_empty())
+        .map(parse_pattern)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(KnownHostsEntry {
+        marker,
+        patterns,
+        key,
+    })
+}
+
+fn parse_pattern(pattern: &str) -> Option<HostPattern> {
+    if let Some(hashed) = pattern.strip_prefix("|1|") {
+        let (salt, hash) = hashed.split_once('|')?;
+        return Some(HostPattern::Hashed {
+            salt: BASE64.decode(salt).ok()?,
+            hash: BASE64.decode(hash).ok()?,
+        });
+    }
+    match pattern.strip_prefix('!') {
+        Some(negated) => Some(HostPattern::Plain {
+            pattern: negated.to_string(),
+            negated: true,
+        }),
+        None => Some(HostPattern::Plain {
+            pattern: pattern.to_string(),
+            negated: false,
+        }),
+    }
+}
+
+/// Name a host is listed under: `host` on port 22, `[host]:port` otherwise
+fn host_name(host: &str, port: u16) -> String {
+    let host = host.to_lowercase();
+    if port == 22 {
+        host
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// Host and port of a plain `host` or `[host]:port` name
+fn split_host_port(name: &str) -> Option<(String, u16)> {
+    match name.strip_prefix('[') {
+        Some(rest) => {
+            let (host, port) = rest.split_once("]:")?;
+            Some((host.to_lowercase(), port.parse().ok()?))
+        }
+        None => Some((name.to_lowercase(), 22)),
+    }
+}
+
+fn hash_host_name(salt: &[u8], name: &str) -> Option<Vec<u8>> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(salt).ok()?;
+    mac.update(name.as_bytes());
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+/// Match `*` (any run of characters) and `?` (one character)
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Check a host key against the user's and the global known_hosts files
+///
+/// True if a line for the host lists this key and no `@revoked` line does.
+/// Missing or unreadable files count as empty.
+pub fn system_trusts_key(host: &str, port: u16, key: &PublicKey) -> bool {
+    let mut trusted = false;
+    for path in [
+        get_default_known_hosts_path(),
+        GLOBAL_KNOWN_HOSTS_PATH.to_string(),
+    ] {
+        let Ok(entries) = parse_known_hosts(&path) else {
+            continue;
+        };
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.key.key_data() == key.key_data())
+        {
+            match entry.marker {
+                Some(Marker::Revoked) => return false,
+                Some(Marker::CertAuthority) => {}
+                None => trusted |= entry.matches(host, port),
+            }
+        }
+    }
+    trusted
+}
+
+/// Get default known_hosts path
+pub fn get_default_known_hosts_path() -> String {
+    if let Some(home) = std::env::var_os("HOME") {
+        format!("{}/.ssh/known_hosts", home.to_string_lossy())
+    } else {
+        "~/.ssh/known_hosts".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+    #[test]
+    fn test_parse_plain_entries() {
+        let content = format!(
+            "# comment\n\
+             example.com,192.0.2.10 {key} user@host\n\
+             [git.example.com]:2222 {key}\n\
+             @revoked old.example.com {key}\n\
+             bad.example.com ssh-ed25519 not-base64\n\
+             @unknown-marker x.example.com {key}\n",
+            key = ED25519
+        );
+        let entries = parse_known_hosts_str(&content);
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(
+            entries[0].plain_hosts(),
+            vec![
+                ("example.com".to_string(), 22),
+                ("192.0.2.10".to_string(), 22)
+            ]
+        );
+        assert!(entries[0].matches("EXAMPLE.com", 22));
+        assert!(!entries[0].matches("example.com", 2222));
+
+        assert_eq!(
+            entries[1].plain_hosts(),
+            vec![("git.example.com".to_string(), 2222)]
+        );
+        assert!(entries[1].matches("git.example.com", 2222));
+
+        assert_eq!(entries[2].marker, Some(Marker::Revoked));
+    }
+
+    #[test]
+    fn test_wildcards_and_negation() {
+        let entries =
+            parse_known_hosts_str(&format!("*.example.com,!bastion.example.com {}", ED25519));
+        let entry = &entries[0];
+        assert!(entry.matches("web1.example.com", 22));
+        assert!(!entry.matches("bastion.example.com", 22));
+        assert!(!entry.matches("example.org", 22));
+        assert!(entry.plain_hosts().is_empty());
+
+        assert!(wildcard_match("db?.*", "db1.internal"));
+        assert!(!wildcard_match("db?.*", "db10.internal"));
+        assert!(wildcard_match("*", ""));
+    }
+
+    #[test]
+    fn test_hashed_entry() {
+        // `ssh-keygen -H` output for [example.com]:2222
+        let entries = parse_known_hosts_str(
+            "|1|HYdRYkEQB4nII1Dwz7RVps2ULJk=|xO+ztHwjMP8khKFdc94Z0zjioDo= \
+             ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMGPs4ghb2L6+g07e4VpkxvLvaTKuABI+2Bo4RXHu+WW",
+        );
+        assert!(entries[0].is_hashed());
+        assert!(entries[0].matches("example.com", 2222));
+        assert!(!entries[0].matches("example.com", 22));
+        assert!(!entries[0].matches("example.org", 2222));
+        assert!(entries[0].plain_hosts().is_empty());
+    }
+}
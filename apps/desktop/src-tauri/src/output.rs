@@ -0,0 +1,509 @@
+/**
+ * Session Output Module
+ *
+ * Streams terminal output to the frontend as binary frames over a Tauri IPC
+ * channel. Output is buffered until the frontend attaches its channel, so
+ * nothing written before the Terminal component mounts is lost.
+ *
+ * Frames (output_frame.rs) are built with room for the header, so PTY
+ * output is read straight into the frame that gets sent; data is only
+ * copied when it arrives in a buffer we don't own (SSH) or while buffering. SSH output arrives in many small channel messages and is
+ * batched (`OutputBatch`) before it is sent.
+ *
+ * Flow control: the frontend pauses the output when xterm.js falls behind
+ * (`pause`/`resume`). The read loops stop reading while paused, so a
+ * runaway command stalls on the SSH channel window or the PTY instead of
+ * piling output up in memory. Output buffered before the frontend attaches
+ * is bounded; older output is dropped with a marker.
+ */
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::output_frame::Frame;
+use crate::recording::{Recorder, RecordingInfo};
+use crate::scrollback::Scrollback;
+
+/// How long SSH output may wait for more before it is sent
+pub const BATCH_DELAY: Duration = Duration::from_millis(4);
+
+/// Batched SSH output is sent right away once it reaches this size
+pub const BATCH_MAX_LEN: usize = 64 * 1024;
+
+/// Output buffered before the frontend attaches is cut back to half of
+/// this, at a line start, when it grows beyond it
+pub const MAX_BUFFERED_OUTPUT: usize = 4 * 1024 * 1024;
+
+/// Put in front of buffered output whose beginning was dropped
+const DROPPED_MARKER: &[u8] = b"\x1b[33m[Earlier output dropped]\x1b[0m\r\n";
+
+/// How often a blocking reader checks whether paused output was resumed
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// SSH output waiting to be sent
+///
+/// Fast output (`cat` of a large file) arrives as a stream of small channel
+/// messages; each one sent on its own costs an IPC message and a terminal
+/// write in the frontend. Chunks arriving within `BATCH_DELAY` of the first
+/// pending one go out as one frame.
+#[derive(Default)]
+pub struct OutputBatch {
+    data: Vec<u8>,
+    deadline: Option<Instant>,
+}
+
+impl OutputBatch {
+    /// Add a chunk; returns true when the batch is full and must be flushed
+    pub fn push(&mut self, data: &[u8]) -> bool {
+        if data.is_empty() {
+            return false;
+        }
+        if self.data.is_empty() {
+            self.deadline = Some(Instant::now() + BATCH_DELAY);
+        }
+        self.data.extend_from_slice(data);
+        self.data.len() >= BATCH_MAX_LEN
+    }
+
+    /// When the pending output is due, None when there is none
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Hand the pending output to `deliver` (not called when empty)
+    pub fn flush(&mut self, deliver: impl FnOnce(&[u8])) {
+        self.deadline = None;
+        if !self.data.is_empty() {
+            deliver(&self.data);
+            self.data.clear();
+        }
+    }
+}
+
+enum Sink {
+    /// No frontend attached yet: accumulate output
+    Buffering(Vec<u8>),
+    /// Streaming frames to the frontend's channel
+    Streaming(Channel<InvokeResponseBody>),
+}
+
+struct OutputState {
+    sink: Sink,
+    next_sequence: u64,
+    /// Buffered output was cut (see `MAX_BUFFERED_OUTPUT`)
+    dropped: bool,
+}
+
+impl OutputState {
+    /// Add output while no frontend is attached, dropping the oldest
+    /// beyond `MAX_BUFFERED_OUTPUT`
+    fn buffer(&mut self, data: &[u8]) {
+        let Sink::Buffering(buffer) = &mut self.sink else {
+            return;
+        };
+        buffer.extend_from_slice(data);
+        if buffer.len() <= MAX_BUFFERED_OUTPUT {
+            return;
+        }
+        let cut = buffer.len() - MAX_BUFFERED_OUTPUT / 2;
+        let cut = buffer[cut..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(cut, |newline| cut + newline + 1);
+        buffer.drain(..cut);
+        self.dropped = true;
+    }
+}
+
+/// Output sink of a terminal session (SSH or local)
+///
+/// Uses std::sync::Mutex because the local PTY reader runs in spawn_blocking;
+/// the lock is never held across an await.
+pub struct SessionOutput {
+    state: Mutex<OutputState>,
+    /// Recent output, kept for replay after a frontend reload
+    scrollback: Mutex<Scrollback>,
+    /// Cast recording in progress (checked without locking when off)
+    recording: AtomicBool,
+    recorder: Mutex<Option<Recorder>>,
+    /// The frontend asked to stop the output (see `pause`)
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl Default for SessionOutput {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(OutputState {
+                sink: Sink::Buffering(Vec::new()),
+                next_sequence: 0,
+                dropped: false,
+            }),
+            scrollback: Mutex::new(Scrollback::new()),
+            recording: AtomicBool::new(false),
+            recorder: Mutex::new(None),
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
+        }
+    }
+}
+
+impl SessionOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffer or stream a chunk of output we don't own (copied into a frame)
+    pub fn deliver(&self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        self.keep(data);
+        let Ok(mut guard) = self.state.lock() else {
+            return;
+        };
+        if let Sink::Buffering(_) = guard.sink {
+            guard.buffer(data);
+            return;
+        }
+        drop(guard);
+
+        let mut frame = Frame::default();
+        frame.extend_from_slice(data);
+        self.deliver_frame(frame);
+    }
+
+    /// Buffer or stream a filled frame without copying its payload
+    pub fn deliver_frame(&self, frame: Frame) {
+        if frame.payload().is_empty() {
+            return;
+        }
+        self.keep(frame.payload());
+        let Ok(mut guard) = self.state.lock() else {
+            return;
+        };
+        let state = &mut *guard;
+        let sequence = state.next_sequence;
+
+        match &mut state.sink {
+            Sink::Buffering(_) => state.buffer(frame.payload()),
+            Sink::Streaming(channel) => {
+                // Sent under the lock so frames leave in sequence order
+                let body = InvokeResponseBody::Raw(frame.encode(sequence));
+                if let Err(e) = channel.send(body) {
+                    tracing::debug!(
+                        "[output.rs] Failed to send output frame {}: {}",
+                        sequence,
+                        e
+                    );
+                }
+                state.next_sequence += 1;
+            }
+        }
+    }
+
+    /// Recent output (raw, starting at a line boundary)
+    pub fn scrollback(&self) -> Vec<u8> {
+        self.scrollback
+            .lock()
+            .map(|scrollback| scrollback.contents())
+            .unwrap_or_default()
+    }
+
+    /// Add output to the scrollback and the recording
+    fn keep(&self, data: &[u8]) {
+        if let Ok(mut scrollback) = self.scrollback.lock() {
+            scrollback.push(data);
+        }
+        self.record(|recorder| recorder.output(data));
+    }
+
+    /// Start recording the session (fails if a recording is running)
+    pub fn start_recording(&self, recorder: Recorder) -> anyhow::Result<RecordingInfo> {
+        let mut current = self
+            .recorder
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Recorder poisoned"))?;
+        if current.is_some() {
+            return Err(anyhow::anyhow!("Session is already being recorded"));
+        }
+        let info = recorder.info().clone();
+        *current = Some(recorder);
+        self.recording.store(true, Ordering::Release);
+        Ok(info)
+    }
+
+    /// Stop the running recording, if any
+    pub fn stop_recording(&self) -> Option<anyhow::Result<RecordingInfo>> {
+        let recorder = self.recorder.lock().ok()?.take()?;
+        self.recording.store(false, Ordering::Release);
+        Some(recorder.finish())
+    }
+
+    /// The running recording, if any
+    pub fn recording(&self) -> Option<RecordingInfo> {
+        if !self.recording.load(Ordering::Acquire) {
+            return None;
+        }
+        let recorder = self.recorder.lock().ok()?;
+        recorder.as_ref().map(|recorder| recorder.info().clone())
+    }
+
+    /// Record input sent to the session (kept only if the recording asks)
+    pub fn record_input(&self, data: &[u8]) {
+        self.record(|recorder| recorder.input(data));
+    }
+
+    /// Record a terminal resize
+    pub fn record_resize(&self, cols: u32, rows: u32) {
+        self.record(|recorder| recorder.resize(cols, rows));
+    }
+
+    fn record(&self, event: impl FnOnce(&mut Recorder)) {
+        if !self.recording.load(Ordering::Acquire) {
+            return;
+        }
+        if let Ok(mut recorder) = self.recorder.lock() {
+            if let Some(recorder) = recorder.as_mut() {
+                event(recorder);
+            }
+        }
+    }
+
+    /// Attach the frontend's channel and switch to streaming mode
+    ///
+    /// Returns the output buffered so far (empty when re-attaching after a
+    /// remount, in which case the new channel replaces the old one). A new
+    /// channel starts with the output resumed.
+    pub fn attach(&self, channel: Channel<InvokeResponseBody>) -> Vec<u8> {
+        self.resume();
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::new();
+        };
+        match std::mem::replace(&mut state.sink, Sink::Streaming(channel)) {
+            Sink::Buffering(buffer) if std::mem::take(&mut state.dropped) => {
+                [DROPPED_MARKER, &buffer].concat()
+            }
+            Sink::Buffering(buffer) => buffer,
+            Sink::Streaming(_) => Vec::new(),
+        }
+    }
+
+    /// Stop reading the session's output until `resume` (the frontend is
+    /// behind)
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Read the session's output again after `pause`
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+        self.resumed.notify_waiters();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Wait until the output is not paused
+    pub async fn resumed(&self) {
+        loop {
+            let notified = self.resumed.notified();
+            tokio::pin!(notified);
+            // Registered before the check, so a resume in between wakes us
+            notified.as_mut().enable();
+            if !self.is_paused() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Block the calling thread (a PTY reader) until the output is not
+    /// paused
+    pub fn block_while_paused(&self) {
+        while self.is_paused() {
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output_frame::{FRAME_HEADER_LEN, READ_CHUNK_SIZE};
+    use std::sync::Arc;
+
+    fn encode_frame(sequence: u64, data: &[u8]) -> Vec<u8> {
+        [&sequence.to_be_bytes()[..], data].concat()
+    }
+
+    type Frames = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    fn capture() -> (Channel<InvokeResponseBody>, Frames) {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&frames);
+        let channel = Channel::new(move |body| {
+            if let InvokeResponseBody::Raw(frame) = body {
+                sink.lock().unwrap().push(frame);
+            }
+            Ok(())
+        });
+        (channel, frames)
+    }
+
+    #[test]
+    fn test_frame_handover() {
+        let output = SessionOutput::new();
+
+        // Buffering copies the payload out
+        let mut frame = Frame::default();
+        frame.read_buf()[..5].copy_from_slice(b"hello");
+        frame.truncate(5);
+        output.deliver_frame(frame);
+
+        // Streamed frames are handed over without a copy
+        let (channel, frames) = capture();
+        assert_eq!(output.attach(channel), b"hello");
+        let mut frame = Frame::default();
+        frame.extend_from_slice(b"world");
+        let ptr = frame.payload().as_ptr();
+        output.deliver_frame(frame);
+        assert_eq!(frames.lock().unwrap()[0][FRAME_HEADER_LEN..].as_ptr(), ptr);
+        assert_eq!(frames.lock().unwrap()[0], encode_frame(0, b"world"));
+    }
+
+    #[test]
+    fn test_output_batch() {
+        let mut batch = OutputBatch::default();
+        assert!(batch.deadline().is_none());
+        assert!(!batch.push(b""));
+        assert!(batch.deadline().is_none());
+
+        assert!(!batch.push(b"total 8\r\n"));
+        let deadline = batch.deadline().unwrap();
+        assert!(!batch.push(b"-rw-r--r-- a\r\n"));
+        assert_eq!(batch.deadline(), Some(deadline));
+
+        let mut sent = Vec::new();
+        batch.flush(|data| sent.push(data.to_vec()));
+        batch.flush(|data| sent.push(data.to_vec()));
+        assert_eq!(sent, vec![b"total 8\r\n-rw-r--r-- a\r\n".to_vec()]);
+        assert!(batch.deadline().is_none());
+
+        assert!(!batch.push(&[b'x'; BATCH_MAX_LEN - 1]));
+        assert!(batch.push(b"x"));
+    }
+
+    /// Bulk output through the streaming paths (see `benches/pipeline.rs`
+    /// for timings): PTY reads are sent as one frame each, small SSH chunks
+    /// are batched, and nothing is lost or reordered
+    #[test]
+    fn test_streaming_pump() {
+        let output = SessionOutput::new();
+        let (channel, frames) = capture();
+        output.attach(channel);
+        let line = b"\x1b[0m-rw-r--r-- 1 user user  4096 \x1b[01;34mfile.txt\x1b[0m\r\n";
+        let data: Vec<u8> = line.iter().copied().cycle().take(8 * 1024 * 1024).collect();
+
+        // PTY: read straight into full frames
+        for chunk in data.chunks(READ_CHUNK_SIZE) {
+            let mut frame = Frame::default();
+            frame.read_buf()[..chunk.len()].copy_from_slice(chunk);
+            frame.truncate(chunk.len());
+            output.deliver_frame(frame);
+        }
+        let pty_frames = frames.lock().unwrap().len();
+        assert_eq!(pty_frames, data.len().div_ceil(READ_CHUNK_SIZE));
+
+        // SSH: many small channel messages, batched
+        let mut batch = OutputBatch::default();
+        for chunk in data.chunks(512) {
+            if batch.push(chunk) {
+                batch.flush(|data| output.deliver(data));
+            }
+        }
+        batch.flush(|data| output.deliver(data));
+        let ssh_frames = frames.lock().unwrap().len() - pty_frames;
+        assert_eq!(ssh_frames, data.len().div_ceil(BATCH_MAX_LEN));
+
+        let frames = frames.lock().unwrap();
+        let mut received = Vec::with_capacity(2 * data.len());
+        for (sequence, frame) in frames.iter().enumerate() {
+            assert_eq!(frame[..FRAME_HEADER_LEN], (sequence as u64).to_be_bytes());
+            received.extend_from_slice(&frame[FRAME_HEADER_LEN..]);
+        }
+        assert_eq!(received, [&data[..], &data[..]].concat());
+    }
+
+    #[test]
+    fn test_buffer_limit() {
+        let output = SessionOutput::new();
+        let line = [b'x'; 1023];
+        for _ in 0..(MAX_BUFFERED_OUTPUT / 1024 + 1) {
+            output.deliver(&line);
+            output.deliver(b"\n");
+        }
+
+        let (channel, _frames) = capture();
+        let buffered = output.attach(channel);
+        assert!(buffered.starts_with(DROPPED_MARKER));
+        let kept = &buffered[DROPPED_MARKER.len()..];
+        assert!(kept.len() <= MAX_BUFFERED_OUTPUT);
+        assert!(kept.len() >= MAX_BUFFERED_OUTPUT / 2 - 1024);
+        // Cut at a line start
+        assert_eq!(kept.len() % 1024, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let output = Arc::new(SessionOutput::new());
+        output.resumed().await;
+
+        output.pause();
+        assert!(output.is_paused());
+        let waiter = tokio::spawn({
+            let output = Arc::clone(&output);
+            async move { output.resumed().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        output.resume();
+        waiter.await.unwrap();
+        assert!(!output.is_paused());
+
+        // Attaching a new channel resumes
+        output.pause();
+        let (channel, _frames) = capture();
+        output.attach(channel);
+        assert!(!output.is_paused());
+    }
+
+    #[test]
+    fn test_buffer_then_stream() {
+        let output = SessionOutput::new();
+        output.deliver(b"Welcome\r\n");
+        output.deliver(b"$ ");
+
+        let (channel, frames) = capture();
+        assert_eq!(output.attach(channel), b"Welcome\r\n$ ");
+
+        output.deliver(b"ls");
+        output.deliver(b"");
+        output.deliver(b"\r\n");
+        assert_eq!(
+            *frames.lock().unwrap(),
+            vec![encode_frame(0, b"ls"), encode_frame(1, b"\r\n")]
+        );
+
+        // Re-attaching after a remount keeps the sequence going
+        let (channel, frames) = capture();
+        assert!(output.attach(channel).is_empty());
+        output.deliver(b"x");
+        assert_eq!(*frames.lock().unwrap(), vec![encode_frame(2, b"x")]);
+    }
+}
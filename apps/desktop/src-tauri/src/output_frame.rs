@@ -0,0 +1,77 @@
+//! Output Frames
+//!
+//! Binary frames carrying terminal output to the frontend (see output.rs):
+//! an 8-byte big-endian sequence number followed by the raw bytes. A frame
+//! is built with room for the header, so PTY output is read straight into
+//! the frame that gets sent.
+//!
+//! Kept free of other app modules so the pipeline benchmark can build it on
+//! its own.
+
+/// Length of the sequence number header in front of every frame
+pub const FRAME_HEADER_LEN: usize = 8;
+
+/// Payload size of a full frame (one PTY read)
+pub const READ_CHUNK_SIZE: usize = 8192;
+
+/// Output frame being filled: header room followed by the payload
+pub struct Frame {
+    buf: Vec<u8>,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        let mut buf = Vec::with_capacity(FRAME_HEADER_LEN + READ_CHUNK_SIZE);
+        buf.resize(FRAME_HEADER_LEN, 0);
+        Self { buf }
+    }
+}
+
+impl Frame {
+    /// Full-size payload area to read into; call `truncate` afterwards
+    pub fn read_buf(&mut self) -> &mut [u8] {
+        self.buf.resize(FRAME_HEADER_LEN + READ_CHUNK_SIZE, 0);
+        &mut self.buf[FRAME_HEADER_LEN..]
+    }
+
+    /// Keep the first `len` payload bytes
+    pub fn truncate(&mut self, len: usize) {
+        self.buf.truncate(FRAME_HEADER_LEN + len);
+    }
+
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.buf[FRAME_HEADER_LEN..]
+    }
+
+    /// Write the sequence number and hand over the encoded frame
+    pub fn encode(mut self, sequence: u64) -> Vec<u8> {
+        self.buf[..FRAME_HEADER_LEN].copy_from_slice(&sequence.to_be_bytes());
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let mut frame = Frame::default();
+        frame.extend_from_slice(b"ls\r\n");
+        let frame = frame.encode(258);
+        assert_eq!(&frame[..FRAME_HEADER_LEN], &[0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(&frame[FRAME_HEADER_LEN..], b"ls\r\n");
+    }
+
+    #[test]
+    fn test_read_buf() {
+        let mut frame = Frame::default();
+        frame.read_buf()[..5].copy_from_slice(b"hello");
+        frame.truncate(5);
+        assert_eq!(frame.payload(), b"hello");
+    }
+}
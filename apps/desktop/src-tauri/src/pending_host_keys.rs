@@ -1,7 +1,7 @@
 /// Pending Host Keys Manager
 ///
-/// Manages temporary acceptance of unknown SSH host keys in strict mode
-
+/// Keeps the host keys of rejected connections (unknown host in strict
+/// mode, changed key) so the user can accept exactly the key they were shown
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -16,72 +16,39 @@ pub struct PendingHostKeyInfo {
 }
 
 /// Manager for pending host key acceptances
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct PendingHostKeysManager {
     /// Map of (host, port) -> PendingHostKeyInfo
     pending: Arc<RwLock<HashMap<(String, u16), PendingHostKeyInfo>>>,
-    /// Map of (host, port) -> accepted (with timestamp)
-    accepted: Arc<RwLock<HashMap<(String, u16), std::time::Instant>>>,
 }
 
 impl PendingHostKeysManager {
     pub fn new() -> Self {
-        Self {
-            pending: Arc::new(RwLock::new(HashMap::new())),
-            accepted: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::default()
     }
 
-    /// Add a pending host key
+    /// Add a pending host key (replaces an older one for the same host)
     pub async fn add_pending(&self, info: PendingHostKeyInfo) {
         let key = (info.host.clone(), info.port);
         let mut pending = self.pending.write().await;
         pending.insert(key, info);
     }
 
-    /// Mark a host key as accepted temporarily (30 seconds TTL)
-    pub async fn accept(&self, host: &str, port: u16) -> Option<PendingHostKeyInfo> {
+    /// Take the pending host key, if it is the one the user confirmed
+    ///
+    /// A different fingerprint (the server sent another key since the
+    /// prompt) leaves the pending key in place and returns None.
+    pub async fn accept(
+        &self,
+        host: &str,
+        port: u16,
+        fingerprint: &str,
+    ) -> Option<PendingHostKeyInfo> {
         let key = (host.to_string(), port);
-
-        // Get the pending info
         let mut pending = self.pending.write().await;
-        let info = pending.remove(&key)?;
-
-        // Add to accepted with timestamp
-        let mut accepted = self.accepted.write().await;
-        accepted.insert(key, std::time::Instant::now());
-
-        Some(info)
-    }
-
-    /// Check if a host key is temporarily accepted (and not expired)
-    pub async fn is_accepted(&self, host: &str, port: u16) -> bool {
-        let key = (host.to_string(), port);
-        let mut accepted = self.accepted.write().await;
-
-        if let Some(timestamp) = accepted.get(&key) {
-            // Check if still valid (30 seconds TTL)
-            if timestamp.elapsed().as_secs() < 30 {
-                return true;
-            } else {
-                // Expired, remove it
-                accepted.remove(&key);
-            }
+        if pending.get(&key)?.fingerprint != fingerprint {
+            return None;
         }
-
-        false
-    }
-
-    /// Remove a pending host key (when rejected)
-    pub async fn reject(&self, host: &str, port: u16) {
-        let key = (host.to_string(), port);
-        let mut pending = self.pending.write().await;
-        pending.remove(&key);
-    }
-
-    /// Clean up expired acceptances
-    pub async fn cleanup_expired(&self) {
-        let mut accepted = self.accepted.write().await;
-        accepted.retain(|_, timestamp| timestamp.elapsed().as_secs() < 30);
+        pending.remove(&key)
     }
 }
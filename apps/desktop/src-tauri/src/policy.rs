@@ -0,0 +1,275 @@
+/**
+ * Policy Module
+ *
+ * Organization policy file (JSON or TOML in a system-wide location) that
+ * administrators use to lock settings and restrict risky features
+ */
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::connection::AuthMethod;
+use rite_vault::Database;
+
+/// Organization policy
+///
+/// Loaded once at startup. A missing file means no policy; a malformed file
+/// is a hard error so a broken deployment never silently runs unrestricted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Settings forced to a value (e.g. host_key_verification_mode = "strict")
+    pub settings: HashMap<String, String>,
+
+    /// Quick SSH must verify host keys like saved connections
+    pub disable_quick_ssh_force_accept: bool,
+
+    /// Saved connections may not store passwords (keys only)
+    pub forbid_password_storage: bool,
+
+    /// Auto-lock must stay enabled
+    pub require_auto_lock: bool,
+
+    /// Maximum auto-lock timeout in minutes (requires auto-lock)
+    pub max_auto_lock_timeout: Option<u64>,
+
+    /// File the policy was loaded from (not part of the file format)
+    #[serde(skip_deserializing)]
+    pub source: Option<PathBuf>,
+}
+
+/// Settings as reported to the frontend, with policy-locked keys flagged
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsReport {
+    pub values: HashMap<String, String>,
+    pub locked: Vec<String>,
+}
+
+impl Policy {
+    /// Load the policy from the first system policy file that exists
+    pub fn load() -> Result<Self> {
+        for path in policy_paths() {
+            if path.exists() {
+                let policy = Self::load_from(&path)?;
+                tracing::info!(
+                    "[policy.rs] Organization policy loaded from {}",
+                    path.display()
+                );
+                return Ok(policy);
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    /// Load a policy file (format chosen by extension: .toml or .json)
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+
+        let mut policy: Policy = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .with_context(|| format!("Invalid policy file {}", path.display()))?,
+            _ => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid policy file {}", path.display()))?,
+        };
+        policy.source = Some(path.to_path_buf());
+        Ok(policy)
+    }
+
+    /// Settings locked by this policy and their enforced values
+    pub fn locked_settings(&self) -> HashMap<String, String> {
+        let mut locked = self.settings.clone();
+        if self.require_auto_lock {
+            locked.insert("auto_lock_enabled".to_string(), "true".to_string());
+        }
+        locked
+    }
+
+    /// Check a setting change against the policy
+    pub fn check_setting(&self, key: &str, value: &str) -> Result<()> {
+        if let Some(enforced) = self.locked_settings().get(key) {
+            if enforced != value {
+                return Err(anyhow!(
+                    "Setting '{}' is locked by organization policy",
+                    key
+                ));
+            }
+        }
+
+        if key == "auto_lock_timeout" {
+            if let Some(max) = self.max_auto_lock_timeout {
+                let timeout: u64 = value
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid auto-lock timeout: {}", value))?;
+                if timeout == 0 || timeout > max {
+                    return Err(anyhow!(
+                        "Organization policy requires an auto-lock timeout of at most {} minutes",
+                        max
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that connection credentials are allowed to be stored
+    pub fn check_credentials(&self, auth_method: &AuthMethod) -> Result<()> {
+        if self.forbid_password_storage {
+            if let AuthMethod::Password { password } = auth_method {
+                if !password.is_empty() {
+                    return Err(anyhow!(
+                        "Storing passwords is forbidden by organization policy"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether Quick SSH may bypass host key verification (accept_host_key_once)
+    pub fn allows_quick_ssh_force_accept(&self) -> bool {
+        !self.disable_quick_ssh_force_accept
+    }
+
+    /// Write enforced values to the settings table so every reader sees them
+    pub async fn apply(&self, db: &Database) -> Result<()> {
+        for (key, value) in self.locked_settings() {
+            db.set_setting(&key, &value).await?;
+        }
+
+        if let Some(max) = self.max_auto_lock_timeout {
+            let current = db
+                .get_setting("auto_lock_timeout")
+                .await?
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            if current == 0 || current > max {
+                db.set_setting("auto_lock_timeout", &max.to_string())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Overlay locked values onto stored settings for reporting
+    pub fn report(&self, mut values: HashMap<String, String>) -> SettingsReport {
+        let locked_settings = self.locked_settings();
+        let mut locked: Vec<String> = locked_settings.keys().cloned().collect();
+        if self.max_auto_lock_timeout.is_some() {
+            locked.push("auto_lock_timeout".to_string());
+        }
+        locked.sort();
+
+        values.extend(locked_settings);
+        SettingsReport { values, locked }
+    }
+}
+
+/// System-wide policy file locations (TOML preferred over JSON)
+fn policy_paths() -> Vec<PathBuf> {
+    #[cfg(target_os = "linux")]
+    let dir = Some(PathBuf::from("/etc/rite"));
+    #[cfg(target_os = "macos")]
+    let dir = Some(PathBuf::from("/Library/Application Support/rite"));
+    #[cfg(target_os = "windows")]
+    let dir = std::env::var_os("ProgramData").map(|p| PathBuf::from(p).join("rite"));
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let dir: Option<PathBuf> = None;
+
+    dir.map(|dir| vec![dir.join("policy.toml"), dir.join("policy.json")])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_toml_and_json() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let toml_path = temp_dir.path().join("policy.toml");
+        std::fs::write(
+            &toml_path,
+            "forbid_password_storage = true\n\n[settings]\nhost_key_verification_mode = \"strict\"\n",
+        )
+        .unwrap();
+        let policy = Policy::load_from(&toml_path).unwrap();
+        assert!(policy.forbid_password_storage);
+        assert_eq!(
+            policy
+                .settings
+                .get("host_key_verification_mode")
+                .map(String::as_str),
+            Some("strict")
+        );
+
+        let json_path = temp_dir.path().join("policy.json");
+        std::fs::write(&json_path, r#"{"require_auto_lock": true}"#).unwrap();
+        assert!(Policy::load_from(&json_path).unwrap().require_auto_lock);
+
+        std::fs::write(&json_path, "{not json").unwrap();
+        assert!(Policy::load_from(&json_path).is_err());
+    }
+
+    #[test]
+    fn test_setting_checks() {
+        let policy = Policy {
+            settings: HashMap::from([(
+                "host_key_verification_mode".to_string(),
+                "strict".to_string(),
+            )]),
+            require_auto_lock: true,
+            max_auto_lock_timeout: Some(15),
+            ..Default::default()
+        };
+
+        assert!(policy
+            .check_setting("host_key_verification_mode", "strict")
+            .is_ok());
+        assert!(policy
+            .check_setting("host_key_verification_mode", "accept")
+            .is_err());
+        assert!(policy.check_setting("auto_lock_enabled", "false").is_err());
+        assert!(policy.check_setting("auto_lock_timeout", "10").is_ok());
+        assert!(policy.check_setting("auto_lock_timeout", "60").is_err());
+        assert!(policy.check_setting("theme", "dracula").is_ok());
+
+        let report = policy.report(HashMap::from([(
+            "auto_lock_enabled".to_string(),
+            "false".to_string(),
+        )]));
+        assert_eq!(report.values["auto_lock_enabled"], "true");
+        assert_eq!(
+            report.locked,
+            vec![
+                "auto_lock_enabled",
+                "auto_lock_timeout",
+                "host_key_verification_mode"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_password_storage() {
+        let policy = Policy {
+            forbid_password_storage: true,
+            ..Default::default()
+        };
+        let password = AuthMethod::Password {
+            password: "hunter2".to_string(),
+        };
+        let empty = AuthMethod::Password {
+            password: String::new(),
+        };
+        assert!(policy.check_credentials(&password).is_err());
+        assert!(policy.check_credentials(&empty).is_ok());
+        assert!(Policy::default().check_credentials(&password).is_ok());
+    }
+}
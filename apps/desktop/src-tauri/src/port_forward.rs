@@ -0,0 +1,770 @@
+/**
+ * Port Forwarding Module
+ *
+ * Local (-L), remote (-R) and dynamic SOCKS5 (-D) forwards over SSH
+ * sessions. Local and dynamic forwards listen on this machine and open a
+ * direct-tcpip channel per connection; remote forwards ask the server to
+ * listen and connect the forwarded-tcpip channels it opens to a local target.
+ *
+ * Traffic is counted per forward and reported to the frontend as
+ * `port-forward:traffic` events while it changes. When a session
+ * reconnects, its remote forwards are requested again on the new
+ * connection (local and dynamic ones open their channels on it anyway).
+ */
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::{JoinHandle, JoinSet};
+use uuid::Uuid;
+
+use crate::terminal::ForwardHandle;
+
+/// How often traffic counters are reported (only sent when they changed)
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Copy buffer size per direction
+const COPY_BUFFER_SIZE: usize = 16 * 1024;
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_NO_AUTH: u8 = 0x00;
+const SOCKS_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+const SOCKS_REPLY_SUCCESS: u8 = 0x00;
+const SOCKS_REPLY_FAILURE: u8 = 0x01;
+const SOCKS_REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS_REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+/// Forward type (named after the OpenSSH flags)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    /// -L: local port to a destination reached from the server
+    Local,
+    /// -R: server port to a destination reached from this machine
+    Remote,
+    /// -D: local SOCKS5 proxy, destinations reached from the server
+    Dynamic,
+}
+
+/// Forward requested by the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardSpec {
+    pub kind: ForwardKind,
+    /// Listen address (this machine for -L/-D, the server for -R).
+    /// None = loopback.
+    #[serde(default)]
+    pub bind_address: Option<String>,
+    /// Listen port (0 = any free port)
+    pub bind_port: u16,
+    /// Destination host (not used for -D)
+    #[serde(default)]
+    pub target_host: Option<String>,
+    /// Destination port (not used for -D)
+    #[serde(default)]
+    pub target_port: Option<u16>,
+}
+
+impl ForwardSpec {
+    /// Listen address, defaulting to loopback
+    pub fn bind_address(&self) -> &str {
+        match self.bind_address.as_deref().map(str::trim) {
+            Some(address) if !address.is_empty() => address,
+            _ if self.kind == ForwardKind::Remote => "localhost",
+            _ => "127.0.0.1",
+        }
+    }
+
+    /// Destination of a local or remote forward
+    fn target(&self) -> Result<(String, u16)> {
+        let host = self
+            .target_host
+            .as_deref()
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .ok_or_else(|| anyhow!("A destination host is required"))?;
+        let port = self
+            .target_port
+            .filter(|p| *p != 0)
+            .ok_or_else(|| anyhow!("A destination port is required"))?;
+        Ok((host.to_string(), port))
+    }
+}
+
+/// Traffic counters of a forward
+#[derive(Debug, Default)]
+pub struct Traffic {
+    /// Bytes sent towards the destination
+    sent: AtomicU64,
+    /// Bytes received from the destination
+    received: AtomicU64,
+    active: AtomicU64,
+    total: AtomicU64,
+}
+
+impl Traffic {
+    pub fn stats(&self) -> TrafficStats {
+        TrafficStats {
+            bytes_sent: self.sent.load(Ordering::Relaxed),
+            bytes_received: self.received.load(Ordering::Relaxed),
+            active_connections: self.active.load(Ordering::Relaxed),
+            total_connections: self.total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Snapshot of a forward's traffic counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub active_connections: u64,
+    pub total_connections: u64,
+}
+
+/// An active forward, as reported to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardInfo {
+    pub id: String,
+    pub session_id: String,
+    #[serde(flatten)]
+    pub spec: ForwardSpec,
+    /// Port actually listened on (differs from `bind_port` when it was 0)
+    pub bound_port: u16,
+    #[serde(flatten)]
+    pub traffic: TrafficStats,
+}
+
+struct Forward {
+    session_id: String,
+    spec: ForwardSpec,
+    bound_port: u16,
+    handle: ForwardHandle,
+    traffic: Arc<Traffic>,
+    /// Listener and reporter tasks (local and dynamic forwards close their
+    /// connections with the listener)
+    tasks: Vec<JoinHandle<()>>,
+    /// Connections of a remote forward, closed when it is dropped
+    connections: JoinSet<()>,
+}
+
+impl Forward {
+    fn info(&self, id: &str) -> ForwardInfo {
+        ForwardInfo {
+            id: id.to_string(),
+            session_id: self.session_id.clone(),
+            spec: self.spec.clone(),
+            bound_port: self.bound_port,
+            traffic: self.traffic.stats(),
+        }
+    }
+
+    fn abort(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        self.connections.abort_all();
+    }
+}
+
+/// Active port forwards of all sessions
+#[derive(Clone, Default)]
+pub struct PortForwardManager {
+    forwards: Arc<Mutex<HashMap<String, Forward>>>,
+}
+
+impl PortForwardManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a forward on a session
+    pub async fn start(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        handle: ForwardHandle,
+        spec: ForwardSpec,
+    ) -> Result<ForwardInfo> {
+        let id = Uuid::new_v4().to_string();
+        let traffic = Arc::new(Traffic::default());
+        let mut tasks = Vec::new();
+
+        let bound_port = match spec.kind {
+            ForwardKind::Local | ForwardKind::Dynamic => {
+                let target = match spec.kind {
+                    ForwardKind::Local => Some(spec.target()?),
+                    _ => None,
+                };
+                let listener = TcpListener::bind((spec.bind_address(), spec.bind_port))
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Cannot listen on {}:{}: {}",
+                            spec.bind_address(),
+                            spec.bind_port,
+                            e
+                        )
+                    })?;
+                let bound_port = listener.local_addr()?.port();
+
+                let opener = handle.clone();
+                let open = move |host: String, port: u16, originator: SocketAddr| {
+                    let opener = opener.clone();
+                    async move { opener.open_direct_tcpip(host, port, originator).await }
+                };
+                tasks.push(match target {
+                    Some(target) => {
+                        tokio::spawn(serve_local(listener, target, Arc::clone(&traffic), open))
+                    }
+                    None => tokio::spawn(serve_dynamic(listener, Arc::clone(&traffic), open)),
+                });
+                bound_port
+            }
+            ForwardKind::Remote => {
+                // Checked before asking the server to listen
+                spec.target()?;
+                let port = handle
+                    .listen(spec.bind_address(), spec.bind_port)
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Server refused to listen on {}:{}: {}",
+                            spec.bind_address(),
+                            spec.bind_port,
+                            e
+                        )
+                    })?;
+                // The server only reports the port when it picked one
+                match u16::try_from(port) {
+                    Ok(port) if port != 0 => port,
+                    _ => spec.bind_port,
+                }
+            }
+        };
+
+        tasks.push(tokio::spawn(report_traffic(
+            app.clone(),
+            id.clone(),
+            session_id.to_string(),
+            Arc::clone(&traffic),
+        )));
+
+        let forward = Forward {
+            session_id: session_id.to_string(),
+            spec,
+            bound_port,
+            handle,
+            traffic,
+            tasks,
+            connections: JoinSet::new(),
+        };
+        let info = forward.info(&id);
+        tracing::info!(
+            "[port_forward.rs] Forward {} started on session {}: {:?} port {}",
+            id,
+            session_id,
+            info.spec.kind,
+            bound_port
+        );
+        self.forwards.lock().await.insert(id, forward);
+
+        Ok(info)
+    }
+
+    /// Stop a forward (open connections are closed)
+    pub async fn stop(&self, forward_id: &str) -> Result<()> {
+        let mut forward = self
+            .forwards
+            .lock()
+            .await
+            .remove(forward_id)
+            .ok_or_else(|| anyhow!("Port forward not found"))?;
+        forward.abort();
+
+        if forward.spec.kind == ForwardKind::Remote {
+            // Best effort: the session may already be gone
+            if let Err(e) = forward
+                .handle
+                .cancel_listen(forward.spec.bind_address(), forward.bound_port)
+                .await
+            {
+                tracing::warn!("[port_forward.rs] Failed to cancel remote forward: {}", e);
+            }
+        }
+
+        tracing::info!("[port_forward.rs] Forward {} stopped", forward_id);
+        Ok(())
+    }
+
+    /// Stop every forward of a session (when it is closed or ends)
+    pub async fn stop_session(&self, session_id: &str) {
+        self.forwards.lock().await.retain(|_, forward| {
+            if forward.session_id != session_id {
+                return true;
+            }
+            forward.abort();
+            false
+        });
+    }
+
+    /// Listen again for the remote forwards of a reconnected session: the
+    /// server forgot them with the old connection. A forward it refuses now
+    /// is stopped and reported with a `port-forward:failed` event.
+    pub async fn restore_remote(&self, app: &AppHandle, session_id: &str) {
+        let remote: Vec<(String, ForwardHandle, String, u16)> = self
+            .forwards
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, f)| f.session_id == session_id && f.spec.kind == ForwardKind::Remote)
+            .map(|(id, f)| {
+                let address = f.spec.bind_address().to_string();
+                (id.clone(), f.handle.clone(), address, f.bound_port)
+            })
+            .collect();
+
+        for (id, handle, address, port) in remote {
+            let Err(e) = handle.listen(&address, port).await else {
+                tracing::info!("[port_forward.rs] Forward {} restored", id);
+                continue;
+            };
+            tracing::warn!("[port_forward.rs] Failed to restore forward {}: {}", id, e);
+            if let Some(mut forward) = self.forwards.lock().await.remove(&id) {
+                forward.abort();
+            }
+            let _ = app.emit(
+                "port-forward:failed",
+                serde_json::json!({
+                    "forwardId": id,
+                    "sessionId": session_id,
+                    "error": format!("Server refused to listen on {}:{}: {}", address, port, e),
+                }),
+            );
+        }
+    }
+
+    /// Active forwards (of one session, or all)
+    pub async fn list(&self, session_id: Option<&str>) -> Vec<ForwardInfo> {
+        let forwards = self.forwards.lock().await;
+        let mut list: Vec<ForwardInfo> = forwards
+            .iter()
+            .filter(|(_, f)| session_id.is_none_or(|id| f.session_id == id))
+            .map(|(id, forward)| forward.info(id))
+            .collect();
+        list.sort_by(|a, b| (&a.session_id, a.bound_port).cmp(&(&b.session_id, b.bound_port)));
+        list
+    }
+
+    /// Route a forwarded-tcpip channel opened by the server (`host`/`port`
+    /// identify the SSH server) to the local destination of its forward
+    ///
+    /// Returns false when no remote forward listens on `connected_port`.
+    pub async fn accept_remote<S>(
+        &self,
+        host: &str,
+        port: u16,
+        connected_port: u32,
+        stream: S,
+    ) -> bool
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut forwards = self.forwards.lock().await;
+        let Some(forward) = forwards.values_mut().find(|forward| {
+            forward.spec.kind == ForwardKind::Remote
+                && u32::from(forward.bound_port) == connected_port
+                && forward.handle.endpoint() == (host, port)
+        }) else {
+            return false;
+        };
+        let Ok((target_host, target_port)) = forward.spec.target() else {
+            return false;
+        };
+        let traffic = Arc::clone(&forward.traffic);
+
+        // Reap the connections that have ended
+        while forward.connections.try_join_next().is_some() {}
+        forward.connections.spawn(async move {
+            match TcpStream::connect((target_host.as_str(), target_port)).await {
+                Ok(local) => pipe(local, stream, &traffic).await,
+                Err(e) => tracing::warn!(
+                    "[port_forward.rs] Cannot connect to {}:{}: {}",
+                    target_host,
+                    target_port,
+                    e
+                ),
+            }
+        });
+        true
+    }
+}
+
+/// Accept loop of a local forward: every connection goes to `target`
+pub async fn serve_local<F, Fut, S>(
+    listener: TcpListener,
+    target: (String, u16),
+    traffic: Arc<Traffic>,
+    open: F,
+) where
+    F: Fn(String, u16, SocketAddr) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<S>> + Send,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let open = Arc::new(open);
+    // Dropped with the accept loop when the forward stops: open connections
+    // are closed with it
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("[port_forward.rs] Accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let open = Arc::clone(&open);
+        let traffic = Arc::clone(&traffic);
+        let (host, port) = target.clone();
+        while connections.try_join_next().is_some() {}
+        connections.spawn(async move {
+            match open(host.clone(), port, peer).await {
+                Ok(remote) => pipe(stream, remote, &traffic).await,
+                Err(e) => {
+                    tracing::warn!("[port_forward.rs] Cannot open {}:{}: {}", host, port, e)
+                }
+            }
+        });
+    }
+}
+
+/// Accept loop of a dynamic forward: every connection starts with a SOCKS5
+/// request naming its destination
+pub async fn serve_dynamic<F, Fut, S>(listener: TcpListener, traffic: Arc<Traffic>, open: F)
+where
+    F: Fn(String, u16, SocketAddr) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<S>> + Send,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let open = Arc::new(open);
+    // Dropped with the accept loop, see serve_local
+    let mut connections = JoinSet::new();
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("[port_forward.rs] Accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let open = Arc::clone(&open);
+        let traffic = Arc::clone(&traffic);
+        while connections.try_join_next().is_some() {}
+        connections.spawn(async move {
+            let (host, port) = match socks5_handshake(&mut stream).await {
+                Ok(target) => target,
+                Err(e) => {
+                    tracing::debug!("[port_forward.rs] SOCKS5 handshake failed: {}", e);
+                    return;
+                }
+            };
+            match open(host.clone(), port, peer).await {
+                Ok(remote) => {
+                    if socks5_reply(&mut stream, SOCKS_REPLY_SUCCESS).await.is_ok() {
+                        pipe(stream, remote, &traffic).await;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("[port_forward.rs] Cannot open {}:{}: {}", host, port, e);
+                    let _ = socks5_reply(&mut stream, SOCKS_REPLY_FAILURE).await;
+                }
+            }
+        });
+    }
+}
+
+/// Server side of a SOCKS5 handshake (no authentication, CONNECT only);
+/// returns the requested destination
+pub async fn socks5_handshake<S>(stream: &mut S) -> Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    if header[0] != SOCKS_VERSION {
+        return Err(anyhow!("Not a SOCKS5 client (version {})", header[0]));
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&SOCKS_NO_AUTH) {
+        stream
+            .write_all(&[SOCKS_VERSION, SOCKS_NO_ACCEPTABLE_METHOD])
+            .await?;
+        return Err(anyhow!("SOCKS5 client requires authentication"));
+    }
+    stream.write_all(&[SOCKS_VERSION, SOCKS_NO_AUTH]).await?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    if request[1] != SOCKS_CONNECT {
+        socks5_reply(stream, SOCKS_REPLY_COMMAND_NOT_SUPPORTED).await?;
+        return Err(anyhow!("Unsupported SOCKS5 command {}", request[1]));
+    }
+
+    let host = match request[3] {
+        SOCKS_ATYP_IPV4 => {
+            let mut address = [0u8; 4];
+            stream.read_exact(&mut address).await?;
+            std::net::Ipv4Addr::from(address).to_string()
+        }
+        SOCKS_ATYP_IPV6 => {
+            let mut address = [0u8; 16];
+            stream.read_exact(&mut address).await?;
+            std::net::Ipv6Addr::from(address).to_string()
+        }
+        SOCKS_ATYP_DOMAIN => {
+            let length = stream.read_u8().await?;
+            let mut domain = vec![0u8; length as usize];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8(domain).map_err(|_| anyhow!("Invalid SOCKS5 domain name"))?
+        }
+        other => {
+            socks5_reply(stream, SOCKS_REPLY_ADDRESS_NOT_SUPPORTED).await?;
+            return Err(anyhow!("Unsupported SOCKS5 address type {}", other));
+        }
+    };
+    let port = stream.read_u16().await?;
+
+    Ok((host, port))
+}
+
+/// SOCKS5 reply (the bound address is not meaningful through SSH)
+async fn socks5_reply<S>(stream: &mut S, reply: u8) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&[SOCKS_VERSION, reply, 0, SOCKS_ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+/// Copy both ways between the client side and the destination side,
+/// counting the traffic
+pub async fn pipe<A, B>(client: A, destination: B, traffic: &Traffic)
+where
+    A: AsyncRead + AsyncWrite + Unpin,
+    B: AsyncRead + AsyncWrite + Unpin,
+{
+    traffic.active.fetch_add(1, Ordering::Relaxed);
+    traffic.total.fetch_add(1, Ordering::Relaxed);
+
+    let (client_read, client_write) = tokio::io::split(client);
+    let (destination_read, destination_write) = tokio::io::split(destination);
+    if let Err(e) = tokio::try_join!(
+        copy_counted(client_read, destination_write, &traffic.sent),
+        copy_counted(destination_read, client_write, &traffic.received),
+    ) {
+        tracing::debug!("[port_forward.rs] Forwarded connection ended: {}", e);
+    }
+
+    traffic.active.fetch_sub(1, Ordering::Relaxed);
+}
+
+async fn copy_counted<R, W>(
+    mut reader: R,
+    mut writer: W,
+    counter: &AtomicU64,
+) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).await?;
+        counter.fetch_add(read as u64, Ordering::Relaxed);
+    }
+    // Half-close: the other direction keeps going
+    writer.shutdown().await
+}
+
+/// Emit the traffic counters of a forward whenever they change
+async fn report_traffic(app: AppHandle, id: String, session_id: String, traffic: Arc<Traffic>) {
+    let mut last = TrafficStats::default();
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+        let stats = traffic.stats();
+        if stats == last {
+            continue;
+        }
+        last = stats;
+        let _ = app.emit(
+            "port-forward:traffic",
+            serde_json::json!({
+                "forwardId": id,
+                "sessionId": session_id,
+                "bytesSent": stats.bytes_sent,
+                "bytesReceived": stats.bytes_received,
+                "activeConnections": stats.active_connections,
+                "totalConnections": stats.total_connections,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_defaults_and_validation() {
+        let spec: ForwardSpec = serde_json::from_str(
+            r#"{"kind": "local", "bindPort": 5432, "targetHost": "db.internal", "targetPort": 5432}"#,
+        )
+        .unwrap();
+        assert_eq!(spec.bind_address(), "127.0.0.1");
+        assert_eq!(spec.target().unwrap(), ("db.internal".to_string(), 5432));
+
+        let spec: ForwardSpec =
+            serde_json::from_str(r#"{"kind": "remote", "bindPort": 8080, "targetHost": " "}"#)
+                .unwrap();
+        assert_eq!(spec.bind_address(), "localhost");
+        assert!(spec.target().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_socks5_handshake() {
+        let (mut client, mut server) = tokio::io::duplex(256);
+        let handshake = tokio::spawn(async move {
+            let target = socks5_handshake(&mut server).await;
+            (target, server)
+        });
+
+        // No auth offered, CONNECT example.com:443
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut choice = [0u8; 2];
+        client.read_exact(&mut choice).await.unwrap();
+        assert_eq!(choice, [5, 0]);
+        client.write_all(&[5, 1, 0, 3, 11]).await.unwrap();
+        client.write_all(b"example.com").await.unwrap();
+        client.write_all(&443u16.to_be_bytes()).await.unwrap();
+
+        let (target, _server) = handshake.await.unwrap();
+        assert_eq!(target.unwrap(), ("example.com".to_string(), 443));
+
+        // Clients that require authentication are turned away
+        let (mut client, mut server) = tokio::io::duplex(256);
+        client.write_all(&[5, 1, 2]).await.unwrap();
+        assert!(socks5_handshake(&mut server).await.is_err());
+        client.read_exact(&mut choice).await.unwrap();
+        assert_eq!(choice, [5, SOCKS_NO_ACCEPTABLE_METHOD]);
+    }
+
+    #[tokio::test]
+    async fn test_local_forward_counts_traffic() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let traffic = Arc::new(Traffic::default());
+
+        // The "SSH channel" is an in-memory pipe to an upper-casing echo server
+        let open = |host: String, port: u16, _originator: SocketAddr| async move {
+            assert_eq!((host.as_str(), port), ("db.internal", 5432));
+            let (channel, mut destination) = tokio::io::duplex(1024);
+            tokio::spawn(async move {
+                let mut buffer = [0u8; 5];
+                destination.read_exact(&mut buffer).await.unwrap();
+                destination
+                    .write_all(&buffer.to_ascii_uppercase())
+                    .await
+                    .unwrap();
+            });
+            Ok(channel)
+        };
+        let server = tokio::spawn(serve_local(
+            listener,
+            ("db.internal".to_string(), 5432),
+            Arc::clone(&traffic),
+            open,
+        ));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+        let mut reply = [0u8; 5];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"HELLO");
+        drop(client);
+
+        // Counters are updated as data flows
+        for _ in 0..50 {
+            if traffic.stats().active_connections == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            traffic.stats(),
+            TrafficStats {
+                bytes_sent: 5,
+                bytes_received: 5,
+                active_connections: 0,
+                total_connections: 1,
+            }
+        );
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_stopping_closes_connections() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let traffic = Arc::new(Traffic::default());
+
+        // The destination stays open: only stopping the forward ends the pipe
+        let (destinations, mut opened) = tokio::sync::mpsc::unbounded_channel();
+        let open = move |_host: String, _port: u16, _originator: SocketAddr| {
+            let destinations = destinations.clone();
+            async move {
+                let (channel, destination) = tokio::io::duplex(1024);
+                destinations.send(destination).unwrap();
+                Ok(channel)
+            }
+        };
+        let server = tokio::spawn(serve_local(
+            listener,
+            ("db.internal".to_string(), 5432),
+            Arc::clone(&traffic),
+            open,
+        ));
+
+        let mut client = TcpStream::connect(address).await.unwrap();
+        let _destination = opened.recv().await.unwrap();
+        server.abort();
+
+        let mut buffer = [0u8; 1];
+        let read = tokio::time::timeout(Duration::from_secs(5), client.read(&mut buffer))
+            .await
+            .expect("connection left open");
+        assert!(matches!(read, Ok(0) | Err(_)));
+    }
+}
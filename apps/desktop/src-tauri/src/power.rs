@@ -0,0 +1,79 @@
+/**
+ * System Sleep Detection
+ *
+ * Detects that the machine was suspended (sleep/hibernate) from the wall
+ * clock: timers don't run while the system sleeps, so after resume the
+ * wall-clock time since the last check is much longer than the check
+ * interval. Works the same on Linux, macOS and Windows, without platform
+ * power APIs.
+ *
+ * A sleep is therefore only noticed once the system has resumed: sessions
+ * report it as `session-sleep-detected` and probe their connection, instead
+ * of reporting every dead keep-alive at once.
+ */
+use std::time::{Duration, SystemTime};
+
+/// How often sessions check for a sleep
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Extra delay (on top of the check interval) that counts as a sleep
+pub const SLEEP_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// How long a session waits for the server to answer after resume
+pub const RESUME_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Compares successive wall-clock observations to detect a sleep
+#[derive(Debug, Clone)]
+pub struct SleepDetector {
+    last_seen: SystemTime,
+    max_gap: Duration,
+}
+
+impl SleepDetector {
+    /// Detector for a caller observing at least every `interval` while awake
+    pub fn new(interval: Duration) -> Self {
+        Self::starting_at(SystemTime::now(), interval)
+    }
+
+    fn starting_at(now: SystemTime, interval: Duration) -> Self {
+        Self {
+            last_seen: now,
+            max_gap: interval + SLEEP_THRESHOLD,
+        }
+    }
+
+    /// Record the current time; returns how long the system slept, if it did
+    pub fn check(&mut self) -> Option<Duration> {
+        self.observe(SystemTime::now())
+    }
+
+    fn observe(&mut self, now: SystemTime) -> Option<Duration> {
+        // A clock set backwards is not a sleep
+        let gap = now.duration_since(self.last_seen).unwrap_or_default();
+        self.last_seen = now;
+        (gap > self.max_gap).then_some(gap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_sleep() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut detector = SleepDetector::starting_at(start, CHECK_INTERVAL);
+
+        // Regular (and somewhat late) checks
+        assert_eq!(detector.observe(start + Duration::from_secs(5)), None);
+        assert_eq!(detector.observe(start + Duration::from_secs(17)), None);
+
+        // Laptop lid closed for an hour
+        let resumed = start + Duration::from_secs(17 + 3600);
+        assert_eq!(detector.observe(resumed), Some(Duration::from_secs(3600)));
+        assert_eq!(detector.observe(resumed + Duration::from_secs(5)), None);
+
+        // Clock set backwards
+        assert_eq!(detector.observe(start), None);
+    }
+}
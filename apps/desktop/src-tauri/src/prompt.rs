@@ -0,0 +1,403 @@
+/**
+ * Prompt Detection Module
+ *
+ * Detects when a remote shell is ready for input (regex on the last output
+ * line, or an OSC 133 shell-integration prompt marker)
+ *
+ * Prompts differ a lot between shells and devices, so detection is driven by
+ * profiles (prompt regexes, shell integration, timeout). Built-in profiles
+ * cover common shells and network OSes; custom ones are stored as JSON in the
+ * `prompt_profiles` setting and override built-ins of the same name.
+ */
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Default prompt pattern: last line ends with a common prompt character
+pub const DEFAULT_PROMPT_PATTERN: &str = r"[$#%>❯]\s*$";
+
+/// OSC 133 "prompt start" marker emitted by shell integration scripts
+const OSC_133_PROMPT_START: &[u8] = b"\x1b]133;A";
+
+/// Maximum amount of trailing output kept for matching
+const MAX_TAIL_LEN: usize = 4096;
+
+/// Setting holding the custom profiles (JSON array of `PromptProfile`)
+pub const SETTING_PROFILES: &str = "prompt_profiles";
+
+/// Profile used when a connection doesn't name one
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// How to detect the prompt of a kind of shell or device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptProfile {
+    pub name: String,
+    /// Regexes matched against the last output line (any match is a prompt)
+    pub patterns: Vec<String>,
+    /// Recognize OSC 133 prompt markers from shell integration scripts
+    #[serde(default = "default_true")]
+    pub shell_integration: bool,
+    /// Seconds to wait for a prompt before giving up (None = caller default)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Set for profiles shipped with the app
+    #[serde(default, skip_deserializing)]
+    pub builtin: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Profiles shipped with the app
+pub fn builtin_profiles() -> Vec<PromptProfile> {
+    let profile = |name: &str, patterns: &[&str], timeout_secs: Option<u64>| PromptProfile {
+        name: name.to_string(),
+        patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        shell_integration: true,
+        timeout_secs,
+        builtin: true,
+    };
+
+    vec![
+        profile(DEFAULT_PROFILE, &[DEFAULT_PROMPT_PATTERN], None),
+        profile("bash", &[r"[$#]\s*$"], None),
+        profile("zsh", &[r"[%#❯]\s*$"], None),
+        profile("fish", &[r"[>#❯]\s*$"], None),
+        profile("powershell", &[r"^PS [^>]*>\s*$"], None),
+        profile("cmd", &[r"^[A-Za-z]:\\[^>]*>$"], None),
+        // Network OSes answer slowly right after login
+        profile("cisco", &[r"^[\w.\-]+(\([\w\-]+\))?[>#]$"], Some(30)),
+        profile("junos", &[r"^[\w.\-]+@[\w.\-]+[>#%]\s*$"], Some(30)),
+        profile("mikrotik", &[r"\[[^\]]+\] >\s*$"], Some(30)),
+    ]
+}
+
+/// Built-in profiles plus the custom ones from the setting value
+///
+/// A custom profile replaces the built-in profile of the same name.
+pub fn load_profiles(setting: Option<&str>) -> Result<Vec<PromptProfile>> {
+    let mut profiles = builtin_profiles();
+
+    let custom: Vec<PromptProfile> = match setting.map(str::trim) {
+        Some(json) if !json.is_empty() => {
+            serde_json::from_str(json).map_err(|e| anyhow!("Invalid prompt profiles: {}", e))?
+        }
+        _ => Vec::new(),
+    };
+    for profile in custom {
+        if profile.name.trim().is_empty() {
+            return Err(anyhow!("Prompt profile without a name"));
+        }
+        // Fail on bad patterns here rather than when a session starts
+        PromptDetector::from_profile(&profile)?;
+
+        match profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// Words that make a line ending with ':' a password prompt
+const PASSWORD_PROMPT_WORDS: &[&str] = &["password", "passphrase", "passcode", "mot de passe"];
+
+/// Whether a line of output asks for a password (e.g. "[sudo] password for
+/// alice:", "Enter passphrase for key '/home/alice/.ssh/id_ed25519':")
+pub fn is_password_prompt(line: &str) -> bool {
+    let line = strip_ansi(line).trim().to_lowercase();
+    line.ends_with(':') && PASSWORD_PROMPT_WORDS.iter().any(|word| line.contains(word))
+}
+
+/// Incremental prompt detector fed with raw session output
+pub struct PromptDetector {
+    patterns: Vec<Regex>,
+    shell_integration: bool,
+    tail: Vec<u8>,
+}
+
+impl PromptDetector {
+    /// Create a detector (None = default prompt pattern)
+    pub fn new(pattern: Option<&str>) -> Result<Self> {
+        let pattern = pattern
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .unwrap_or(DEFAULT_PROMPT_PATTERN);
+
+        Self::with_patterns(&[pattern], true)
+    }
+
+    /// Create a detector from a profile
+    pub fn from_profile(profile: &PromptProfile) -> Result<Self> {
+        if profile.patterns.is_empty() && !profile.shell_integration {
+            return Err(anyhow!(
+                "Prompt profile '{}' has no way to detect a prompt",
+                profile.name
+            ));
+        }
+
+        Self::with_patterns(&profile.patterns, profile.shell_integration)
+    }
+
+    fn with_patterns<S: AsRef<str>>(patterns: &[S], shell_integration: bool) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                Regex::new(p.as_ref())
+                    .map_err(|e| anyhow!("Invalid prompt pattern '{}': {}", p.as_ref(), e))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            patterns,
+            shell_integration,
+            tail: Vec::new(),
+        })
+    }
+
+    /// Feed output and return true if the shell is now waiting at a prompt
+    pub fn feed(&mut self, data: &[u8]) -> bool {
+        self.tail.extend_from_slice(data);
+        if self.tail.len() > MAX_TAIL_LEN {
+            let excess = self.tail.len() - MAX_TAIL_LEN;
+            self.tail.drain(..excess);
+        }
+
+        if self.shell_integration
+            && self
+                .tail
+                .windows(OSC_133_PROMPT_START.len())
+                .any(|w| w == OSC_133_PROMPT_START)
+        {
+            return true;
+        }
+
+        let text = strip_ansi(&String::from_utf8_lossy(&self.tail));
+        let last_line = text.rsplit(['\n', '\r']).next().unwrap_or("");
+        !last_line.trim().is_empty() && self.patterns.iter().any(|p| p.is_match(last_line))
+    }
+
+    /// Forget accumulated output (call after sending a command)
+    pub fn reset(&mut self) {
+        self.tail.clear();
+    }
+}
+
+/// Startup commands waiting to be sent, one per detected prompt
+pub struct StartupSequence {
+    detector: PromptDetector,
+    commands: VecDeque<String>,
+    timeout: Duration,
+}
+
+impl StartupSequence {
+    /// Build a sequence (None when there are no commands to send)
+    pub fn new(commands: &[String], detector: PromptDetector, timeout: Duration) -> Option<Self> {
+        let commands: VecDeque<String> = commands
+            .iter()
+            // A leading space is kept: shells use it to skip history
+            .map(|c| c.trim_end())
+            .filter(|c| !c.trim_start().is_empty())
+            .map(str::to_string)
+            .collect();
+        if commands.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            detector,
+            commands,
+            timeout,
+        })
+    }
+
+    /// How long to wait for a prompt before sending the next command anyway
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Feed session output; returns the next command line once a prompt is seen
+    pub fn on_output(&mut self, data: &[u8]) -> Option<String> {
+        if self.detector.feed(data) {
+            self.next_command()
+        } else {
+            None
+        }
+    }
+
+    /// No prompt within the timeout: send the next command anyway
+    pub fn on_timeout(&mut self) -> Option<String> {
+        self.next_command()
+    }
+
+    /// True once every command has been sent
+    pub fn is_done(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    fn next_command(&mut self) -> Option<String> {
+        self.detector.reset();
+        self.commands.pop_front().map(|c| format!("{}\r", c))
+    }
+}
+
+/// Remove ANSI escape sequences (CSI, OSC and two-byte escapes) from text
+pub fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: ESC [ params final-byte
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ESC ] ... terminated by BEL or ST (ESC \)
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Other two-byte escape sequences
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_pattern_detects_common_prompts() {
+        let mut detector = PromptDetector::new(None).unwrap();
+        assert!(!detector.feed(b"Last login: Mon Jan 1 from 10.0.0.1\r\n"));
+        assert!(detector.feed(b"\x1b[32muser@host\x1b[0m:~$ "));
+
+        detector.reset();
+        assert!(detector.feed(b"root@host:/# "));
+    }
+
+    #[test]
+    fn test_custom_pattern_and_partial_output() {
+        let mut detector = PromptDetector::new(Some(r"^switch\d*>$")).unwrap();
+        assert!(!detector.feed(b"Welcome\r\nswi"));
+        assert!(detector.feed(b"tch01>"));
+    }
+
+    #[test]
+    fn test_osc_133_marker() {
+        let mut detector = PromptDetector::new(Some("never-matches-xyz")).unwrap();
+        assert!(detector.feed(b"\x1b]133;A\x07anything"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(PromptDetector::new(Some("([unclosed")).is_err());
+    }
+
+    #[test]
+    fn test_startup_sequence() {
+        let commands = vec!["cd /srv".to_string(), "  ".to_string(), "ls".to_string()];
+        let detector = PromptDetector::new(None).unwrap();
+        let mut sequence =
+            StartupSequence::new(&commands, detector, Duration::from_secs(5)).unwrap();
+
+        assert_eq!(sequence.on_output(b"Welcome to host\r\n"), None);
+        assert_eq!(sequence.on_output(b"$ "), Some("cd /srv\r".to_string()));
+        // Echo of the command alone is not a prompt
+        assert_eq!(sequence.on_output(b"cd /srv\r\n"), None);
+        assert_eq!(sequence.on_timeout(), Some("ls\r".to_string()));
+        assert!(sequence.is_done());
+
+        let detector = PromptDetector::new(None).unwrap();
+        assert!(StartupSequence::new(&[], detector, Duration::from_secs(5)).is_none());
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = load_profiles(None).unwrap();
+        let detector = |name: &str| {
+            let profile = profiles.iter().find(|p| p.name == name).unwrap();
+            PromptDetector::from_profile(profile).unwrap()
+        };
+
+        assert!(detector("cisco").feed(b"\r\nswitch01(config-if)#"));
+        assert!(!detector("cisco").feed(b"switch01#show running-config"));
+        assert!(detector("junos").feed(b"admin@mx480> "));
+        assert!(detector("mikrotik").feed(b"[admin@MikroTik] > "));
+        assert!(detector("powershell").feed(b"PS C:\\Users\\bob> "));
+        assert!(detector("cmd").feed(b"C:\\Windows\\system32>"));
+        assert!(detector("zsh").feed(b"bob@mac ~ % "));
+    }
+
+    #[test]
+    fn test_custom_profiles() {
+        let setting = r#"[
+            {"name": "appliance", "patterns": ["^menu> $", "\\(y/n\\) $"], "shellIntegration": false},
+            {"name": "bash", "patterns": ["\\$ $"], "timeoutSecs": 3, "builtin": true}
+        ]"#;
+        let profiles = load_profiles(Some(setting)).unwrap();
+
+        let bash = profiles.iter().find(|p| p.name == "bash").unwrap();
+        assert_eq!(bash.timeout_secs, Some(3));
+        assert!(!bash.builtin);
+        assert_eq!(profiles.iter().filter(|p| p.name == "bash").count(), 1);
+
+        let appliance = profiles.iter().find(|p| p.name == "appliance").unwrap();
+        let mut detector = PromptDetector::from_profile(appliance).unwrap();
+        assert!(detector.feed(b"Reboot? (y/n) "));
+        // Shell integration is off: markers alone are not a prompt
+        detector.reset();
+        assert!(!detector.feed(b"\x1b]133;A\x07loading"));
+
+        assert!(load_profiles(Some(r#"[{"name": "x", "patterns": ["("]}]"#)).is_err());
+        assert!(load_profiles(Some(
+            r#"[{"name": "x", "patterns": [], "shellIntegration": false}]"#
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_password_prompt() {
+        assert!(is_password_prompt("[sudo] password for alice: "));
+        assert!(is_password_prompt("Password:"));
+        assert!(is_password_prompt(
+            "Enter passphrase for key '/root/.ssh/id_rsa':"
+        ));
+        assert!(is_password_prompt("\x1b[1mMot de passe :\x1b[0m"));
+        assert!(!is_password_prompt("alice@host:~$ echo password"));
+        assert!(!is_password_prompt("Password changed."));
+        assert!(!is_password_prompt(""));
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\x1b[1;31mred\x1b[0m \x1b]0;title\x07text"),
+            "red text"
+        );
+    }
+}
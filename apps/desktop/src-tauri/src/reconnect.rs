@@ -0,0 +1,126 @@
+/**
+ * Reconnect Module
+ *
+ * Automatic reconnection of SSH sessions after a network drop (keep-alive
+ * failure, connection lost during system sleep, transport closed): how often
+ * to retry and how long to wait between attempts (exponential backoff).
+ *
+ * A plain shell does not survive the drop. With tmux/screen resume the shell
+ * is started inside a named remote tmux/screen session, and reconnecting
+ * attaches to it again, so running programs and the screen are kept.
+ */
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Remote session name used when none is configured
+pub const DEFAULT_RESUME_SESSION: &str = "rite";
+
+/// Longest accepted remote session name
+const MAX_SESSION_NAME_LEN: usize = 64;
+
+/// Automatic reconnection policy of a connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReconnectPolicy {
+    /// Reconnect automatically when the connection drops
+    pub enabled: bool,
+    /// Attempts before giving up
+    pub max_attempts: u32,
+    /// Delay before the first attempt, doubled after each failure
+    pub initial_delay_secs: u64,
+    /// Upper bound of the delay between attempts
+    pub max_delay_secs: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: 5,
+            initial_delay_secs: 1,
+            max_delay_secs: 30,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before attempt `attempt` (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(32);
+        let secs = self
+            .initial_delay_secs
+            .saturating_mul(factor)
+            .min(self.max_delay_secs.max(self.initial_delay_secs));
+        Duration::from_secs(secs)
+    }
+}
+
+/// Remote terminal multiplexer the shell runs in, so a reconnect resumes it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    /// Plain shell: a reconnect starts a new one
+    #[default]
+    Off,
+    Tmux,
+    Screen,
+}
+
+/// Command starting (or attaching to) the remote session `name`
+///
+/// Returns None when resume is off.
+pub fn resume_command(mode: ResumeMode, name: Option<&str>) -> Result<Option<String>> {
+    let name = name
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or(DEFAULT_RESUME_SESSION);
+    if name.len() > MAX_SESSION_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!(
+            "Invalid resume session name: {} (letters, digits, '-' and '_' only)",
+            name
+        ));
+    }
+
+    Ok(match mode {
+        ResumeMode::Off => None,
+        // -A: attach if the session exists, create it otherwise
+        ResumeMode::Tmux => Some(format!("tmux new-session -A -s {}", name)),
+        // -D -RR: detach it elsewhere and attach, create it if missing
+        ResumeMode::Screen => Some(format!("screen -D -RR {}", name)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_backoff() {
+        let policy = ReconnectPolicy::default();
+        let delays: Vec<u64> = (1..=7).map(|a| policy.delay(a).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+
+        // Huge attempt counts don't overflow
+        assert_eq!(policy.delay(u32::MAX).as_secs(), 30);
+    }
+
+    #[test]
+    fn test_resume_command() {
+        assert_eq!(resume_command(ResumeMode::Off, Some("main")).unwrap(), None);
+        assert_eq!(
+            resume_command(ResumeMode::Tmux, None).unwrap(),
+            Some("tmux new-session -A -s rite".to_string())
+        );
+        assert_eq!(
+            resume_command(ResumeMode::Screen, Some(" work ")).unwrap(),
+            Some("screen -D -RR work".to_string())
+        );
+        assert!(resume_command(ResumeMode::Tmux, Some("a; reboot")).is_err());
+        assert!(resume_command(ResumeMode::Tmux, Some("a.b")).is_err());
+    }
+}
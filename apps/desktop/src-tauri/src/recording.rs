@@ -0,0 +1,533 @@
+/**
+ * Session Recording Module
+ *
+ * Opt-in recording of terminal sessions as asciinema v2 cast files
+ * (https://docs.asciinema.org/manual/asciicast/v2/): a JSON header line, then
+ * one `[time, "o"|"i"|"r", data]` event per line. Input is only recorded
+ * when asked for (it includes typed passwords).
+ *
+ * Recordings live under `<data dir>/rite/recordings/<connection>/` (`local`
+ * for local terminals). Encrypted recordings (`.cast.enc`) hold each line
+ * encrypted with the recordings subkey (base64 of the envelope), so they are
+ * written as they go and only readable while the vault is unlocked. They are
+ * re-encrypted along with the vault when the master password changes.
+ */
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use rite_crypto::subkey;
+use rite_vault::{EncryptedData, MasterKey};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Recordings directory (next to the vault)
+const DIR_NAME: &str = "recordings";
+
+/// Directory of local terminal recordings
+pub const LOCAL_DIR: &str = "local";
+
+const EXTENSION: &str = "cast";
+const ENCRYPTED_EXTENSION: &str = "cast.enc";
+
+/// Extension of a re-encrypted copy waiting to replace its recording
+const REKEY_EXTENSION: &str = "enc.rekey";
+
+/// Recording options chosen by the user
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingOptions {
+    /// Also record keystrokes (includes passwords typed in the terminal)
+    #[serde(default)]
+    pub record_input: bool,
+    /// Encrypt with the master key
+    #[serde(default)]
+    pub encrypt: bool,
+}
+
+/// A recording on disk, as listed to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub id: String,
+    pub connection_id: String,
+    /// Start time (Unix seconds)
+    pub started_at: i64,
+    /// File size in bytes
+    pub size: u64,
+    pub encrypted: bool,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: i64,
+    title: &'a str,
+    env: HeaderEnv,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct HeaderEnv {
+    term: &'static str,
+}
+
+/// Recording in progress
+pub struct Recorder {
+    info: RecordingInfo,
+    writer: BufWriter<File>,
+    /// Recordings subkey, for encrypted recordings
+    key: Option<MasterKey>,
+    record_input: bool,
+    started: Instant,
+    /// Incomplete UTF-8 sequences at the end of the last chunk
+    pending_output: Vec<u8>,
+    pending_input: Vec<u8>,
+}
+
+impl Recorder {
+    /// Start a recording (`key`: the master key, for encrypted recordings)
+    pub fn start(
+        connection_id: &str,
+        title: &str,
+        size: (u16, u16),
+        options: RecordingOptions,
+        key: Option<Arc<MasterKey>>,
+    ) -> Result<Self> {
+        if options.encrypt && key.is_none() {
+            return Err(anyhow!("Encrypted recording requires an unlocked vault"));
+        }
+        let dir = connection_dir(connection_id)?;
+        std::fs::create_dir_all(&dir).context("Failed to create recordings directory")?;
+
+        let started_at = chrono::Utc::now().timestamp();
+        let id = format!(
+            "{}-{}",
+            started_at,
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let extension = if options.encrypt {
+            ENCRYPTED_EXTENSION
+        } else {
+            EXTENSION
+        };
+        let path = dir.join(format!("{}.{}", id, extension));
+        let file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let (cols, rows) = size;
+        let mut recorder = Self {
+            info: RecordingInfo {
+                id,
+                connection_id: connection_id.to_string(),
+                started_at,
+                size: 0,
+                encrypted: options.encrypt,
+            },
+            writer: BufWriter::new(file),
+            key: key
+                .filter(|_| options.encrypt)
+                .map(|key| key.derive_subkey(subkey::RECORDINGS)),
+            record_input: options.record_input,
+            started: Instant::now(),
+            pending_output: Vec::new(),
+            pending_input: Vec::new(),
+        };
+        let header = serde_json::to_string(&Header {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: started_at,
+            title,
+            env: HeaderEnv {
+                term: "xterm-256color",
+            },
+        })?;
+        recorder.write_line(&header)?;
+        tracing::info!(
+            "[recording.rs] Recording {} started ({})",
+            recorder.info.id,
+            connection_id
+        );
+        Ok(recorder)
+    }
+
+    pub fn info(&self) -> &RecordingInfo {
+        &self.info
+    }
+
+    /// Record terminal output
+    pub fn output(&mut self, data: &[u8]) {
+        let text = take_utf8(&mut self.pending_output, data);
+        self.event("o", &text);
+    }
+
+    /// Record user input (when enabled)
+    pub fn input(&mut self, data: &[u8]) {
+        if !self.record_input {
+            return;
+        }
+        let text = take_utf8(&mut self.pending_input, data);
+        self.event("i", &text);
+    }
+
+    /// Record a terminal resize
+    pub fn resize(&mut self, cols: u32, rows: u32) {
+        self.event("r", &format!("{}x{}", cols, rows));
+    }
+
+    /// Flush and close the file
+    pub fn finish(mut self) -> Result<RecordingInfo> {
+        self.writer.flush()?;
+        tracing::info!("[recording.rs] Recording {} stopped", self.info.id);
+        Ok(self.info)
+    }
+
+    fn event(&mut self, kind: &str, data: &str) {
+        if data.is_empty() {
+            return;
+        }
+        let time = self.started.elapsed().as_secs_f64();
+        let line = serde_json::json!([(time * 1e6).round() / 1e6, kind, data]).to_string();
+        if let Err(e) = self.write_line(&line) {
+            tracing::warn!("[recording.rs] Failed to write recording: {}", e);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        let line = match &self.key {
+            Some(key) => encrypt_line(key, line)?,
+            None => line.to_string(),
+        };
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.info.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Recordings of a connection, newest first
+pub fn list(connection_id: &str) -> Result<Vec<RecordingInfo>> {
+    let dir = connection_dir(connection_id)?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read recordings directory"),
+    };
+
+    let mut recordings: Vec<RecordingInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let (id, encrypted) = parse_file_name(&name).filter(|(id, _)| is_safe_name(id))?;
+            let started_at = id.split('-').next()?.parse().ok()?;
+            Some(RecordingInfo {
+                id: id.to_string(),
+                connection_id: connection_id.to_string(),
+                started_at,
+                size: entry.metadata().ok()?.len(),
+                encrypted,
+            })
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+    Ok(recordings)
+}
+
+/// Cast file contents, decrypted if needed
+pub fn read(connection_id: &str, id: &str, key: Option<&MasterKey>) -> Result<String> {
+    let (path, encrypted) = find(connection_id, id)?;
+    let contents = std::fs::read_to_string(&path).context("Failed to read recording")?;
+    if !encrypted {
+        return Ok(contents);
+    }
+
+    let master_key = key.ok_or_else(|| anyhow!("Application is locked"))?;
+    let key = master_key.derive_subkey(subkey::RECORDINGS);
+    let mut cast = String::with_capacity(contents.len());
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        cast.push_str(&decrypt_line(&key, line)?);
+        cast.push('\n');
+    }
+    Ok(cast)
+}
+
+/// Encrypted recordings, re-encrypted when the master password changes
+/// (encrypted recordings in progress must be stopped first)
+#[derive(Default)]
+pub struct RecordingsRekey {
+    /// Re-encrypted copies and the recordings they replace
+    staged: Vec<(PathBuf, PathBuf)>,
+}
+
+impl rite_vault::Rekey for RecordingsRekey {
+    fn prepare(&mut self, old_key: &MasterKey, new_key: &MasterKey) -> Result<()> {
+        let old_key = old_key.derive_subkey(subkey::RECORDINGS);
+        let new_key = new_key.derive_subkey(subkey::RECORDINGS);
+        for path in encrypted_files()? {
+            let copy = path.with_extension(REKEY_EXTENSION);
+            self.staged.push((copy.clone(), path.clone()));
+            reencrypt_file(&path, &copy, &old_key, &new_key)
+                .with_context(|| format!("Failed to re-encrypt {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        for (copy, path) in self.staged.drain(..) {
+            std::fs::rename(&copy, &path)
+                .with_context(|| format!("Failed to replace {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn abort(&mut self) {
+        for (copy, _) in self.staged.drain(..) {
+            let _ = std::fs::remove_file(copy);
+        }
+    }
+}
+
+/// Re-encrypt recordings made before the recordings subkey, which used the
+/// master key itself
+pub fn upgrade(master_key: &MasterKey) -> Result<()> {
+    let key = master_key.derive_subkey(subkey::RECORDINGS);
+    for path in encrypted_files()? {
+        let file = File::open(&path).context("Failed to read recording")?;
+        let Some(first_line) = BufReader::new(file).lines().next().transpose()? else {
+            continue;
+        };
+        if decrypt_line(&key, &first_line).is_ok() || decrypt_line(master_key, &first_line).is_err()
+        {
+            continue;
+        }
+
+        let copy = path.with_extension(REKEY_EXTENSION);
+        reencrypt_file(&path, &copy, master_key, &key)
+            .and_then(|()| std::fs::rename(&copy, &path).map_err(Into::into))
+            .with_context(|| format!("Failed to upgrade {}", path.display()))?;
+        tracing::info!("[recording.rs] Upgraded {}", path.display());
+    }
+    Ok(())
+}
+
+/// Delete a recording
+pub fn delete(connection_id: &str, id: &str) -> Result<()> {
+    let (path, _) = find(connection_id, id)?;
+    std::fs::remove_file(&path).context("Failed to delete recording")
+}
+
+/// Encrypted recordings of every connection
+fn encrypted_files() -> Result<Vec<PathBuf>> {
+    let dir = recordings_dir()?;
+    let connections = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read recordings directory"),
+    };
+
+    let mut files = Vec::new();
+    for connection in connections.filter_map(|entry| entry.ok()) {
+        let Ok(entries) = std::fs::read_dir(connection.path()) else {
+            continue;
+        };
+        files.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .and_then(parse_file_name)
+                        .is_some_and(|(_, encrypted)| encrypted)
+                })
+                .map(|entry| entry.path()),
+        );
+    }
+    Ok(files)
+}
+
+/// Write a copy of an encrypted recording with its lines re-encrypted
+fn reencrypt_file(path: &Path, copy: &Path, from: &MasterKey, to: &MasterKey) -> Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut writer = BufWriter::new(File::create(copy)?);
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let plaintext = zeroize::Zeroizing::new(decrypt_line(from, &line)?);
+        writer.write_all(encrypt_line(to, &plaintext)?.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+fn recordings_dir() -> Result<PathBuf> {
+    Ok(rite_vault::default_path()?.with_file_name(DIR_NAME))
+}
+
+fn connection_dir(connection_id: &str) -> Result<PathBuf> {
+    if !is_safe_name(connection_id) {
+        return Err(anyhow!("Invalid connection ID"));
+    }
+    Ok(recordings_dir()?.join(connection_id))
+}
+
+/// Path of a recording and whether it is encrypted
+fn find(connection_id: &str, id: &str) -> Result<(PathBuf, bool)> {
+    if !is_safe_name(id) {
+        return Err(anyhow!("Invalid recording ID"));
+    }
+    let dir = connection_dir(connection_id)?;
+    [(EXTENSION, false), (ENCRYPTED_EXTENSION, true)]
+        .into_iter()
+        .map(|(extension, encrypted)| (dir.join(format!("{}.{}", id, extension)), encrypted))
+        .find(|(path, _)| Path::exists(path))
+        .ok_or_else(|| anyhow!("Recording not found"))
+}
+
+/// Recording ID and encryption of a file name
+fn parse_file_name(name: &str) -> Option<(&str, bool)> {
+    if let Some(id) = name.strip_suffix(&format!(".{}", ENCRYPTED_EXTENSION)) {
+        return Some((id, true));
+    }
+    name.strip_suffix(&format!(".{}", EXTENSION))
+        .map(|id| (id, false))
+}
+
+/// IDs become path components: no separators or dots
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Decode complete UTF-8 text, keeping a trailing incomplete sequence for
+/// the next chunk (invalid bytes are replaced)
+fn take_utf8(pending: &mut Vec<u8>, data: &[u8]) -> String {
+    pending.extend_from_slice(data);
+    let complete = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        // error_len() is None when the input ends mid-sequence
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    text
+}
+
+fn encrypt_line(key: &MasterKey, line: &str) -> Result<String> {
+    let encrypted = rite_crypto::encrypt(key, line.as_bytes())?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(encrypted.to_bytes()))
+}
+
+/// Decrypt a line with the recordings subkey
+fn decrypt_line(key: &MasterKey, line: &str) -> Result<String> {
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(line.trim())
+        .context("Corrupted recording")?;
+    // Lines written before the envelope format are nonce + ciphertext
+    let encrypted = EncryptedData::from_bytes(&blob).context("Corrupted recording")?;
+    let plaintext = rite_crypto::decrypt(key, &encrypted)
+        .map_err(|_| anyhow!("Recording was encrypted with another master key"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_utf8() {
+        let mut pending = Vec::new();
+        let euro = "€".as_bytes();
+        assert_eq!(take_utf8(&mut pending, &[b'a', euro[0], euro[1]]), "a");
+        assert_eq!(pending.len(), 2);
+        assert_eq!(take_utf8(&mut pending, &[euro[2], b'b']), "€b");
+        assert!(pending.is_empty());
+
+        // Invalid bytes don't block the stream
+        assert_eq!(take_utf8(&mut pending, &[0xff, b'c']), "\u{fffd}c");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_line_roundtrip() {
+        let master_key = MasterKey::derive("recording-test", &[3u8; 16]).unwrap();
+        let key = master_key.derive_subkey(subkey::RECORDINGS);
+        let other = MasterKey::derive("other-password", &[3u8; 16]).unwrap();
+        let other_key = other.derive_subkey(subkey::RECORDINGS);
+        let line = r#"[0.5,"o","ls\r\n"]"#;
+
+        let encrypted = encrypt_line(&key, line).unwrap();
+        assert!(!encrypted.contains("ls"));
+        assert_eq!(decrypt_line(&key, &encrypted).unwrap(), line);
+        assert!(decrypt_line(&other_key, &encrypted).is_err());
+
+        // Recordings made before subkeys used the master key itself
+        let legacy = encrypt_line(&master_key, line).unwrap();
+        assert!(decrypt_line(&key, &legacy).is_err());
+    }
+
+    #[test]
+    fn test_reencrypt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("1700000000-ab12cd34.cast.enc");
+        let copy = path.with_extension(REKEY_EXTENSION);
+        let old_key = MasterKey::derive("recording-test", &[3u8; 16]).unwrap();
+        let new_key = MasterKey::derive("other-password", &[3u8; 16]).unwrap();
+        let lines = [r#"{"version":2}"#, r#"[0.5,"o","ls\r\n"]"#];
+
+        let encrypted: Vec<String> = lines
+            .iter()
+            .map(|line| encrypt_line(&old_key, line).unwrap())
+            .collect();
+        std::fs::write(&path, encrypted.join("\n") + "\n").unwrap();
+
+        reencrypt_file(&path, &copy, &old_key, &new_key).unwrap();
+        let contents = std::fs::read_to_string(&copy).unwrap();
+        let decrypted: Vec<String> = contents
+            .lines()
+            .map(|line| decrypt_line(&new_key, line).unwrap())
+            .collect();
+        assert_eq!(decrypted, lines);
+        assert_eq!(
+            parse_file_name(copy.file_name().unwrap().to_str().unwrap()),
+            None
+        );
+
+        assert!(reencrypt_file(&path, &copy, &new_key, &old_key).is_err());
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(
+            parse_file_name("1700000000-ab12cd34.cast"),
+            Some(("1700000000-ab12cd34", false))
+        );
+        assert_eq!(
+            parse_file_name("1700000000-ab12cd34.cast.enc"),
+            Some(("1700000000-ab12cd34", true))
+        );
+        assert_eq!(parse_file_name("notes.txt"), None);
+
+        assert!(is_safe_name("3f2b9c1e-0d4a-4c55-9a77-1e2f3a4b5c6d"));
+        assert!(is_safe_name(LOCAL_DIR));
+        assert!(!is_safe_name("../vault"));
+        assert!(!is_safe_name(""));
+    }
+}
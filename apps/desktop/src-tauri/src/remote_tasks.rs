@@ -0,0 +1,463 @@
+/**
+ * Remote Tasks
+ *
+ * Commands run over SSH on a set of saved connections, on demand or on a
+ * cron-like schedule (a lightweight `ansible -m shell` inside RITE). Each
+ * connection gets its own connection and exec channel in the background;
+ * the exit status and output of every run are stored in the vault and a
+ * `remote-task-completed` event is sent when it ends. Every run is
+ * audit-logged; on protected connections, dangerous commands are refused.
+ *
+ * Schedules are the 5 usual cron fields (minute, hour, day of month, month,
+ * day of week) in local time, with `*`, lists, ranges, steps and 3-letter
+ * names, or one of `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`.
+ * Runs missed while the app was closed, locked or asleep are not caught up.
+ */
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, Timelike};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audit;
+use crate::exec;
+use crate::state::AppState;
+
+pub use rite_vault::{CreateRemoteTaskInput, RemoteTask, RemoteTaskRun, UpdateRemoteTaskInput};
+
+/// Event sent when a run ends (on one connection)
+pub const EVENT_COMPLETED: &str = "remote-task-completed";
+
+/// How often the scheduler looks for due tasks
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest a run may take before it is stopped
+const RUN_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Output kept per run (the end is kept)
+const MAX_OUTPUT: usize = 64 * 1024;
+
+/// Furthest back the scheduler looks for due minutes (after a sleep, the
+/// runs missed meanwhile are skipped rather than all started at once)
+const MAX_CATCH_UP_MINUTES: i64 = 2;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Parsed cron schedule (one bit per allowed value)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month or day of week given as `*`: both must match then,
+    /// otherwise either does (like cron)
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    /// Parse a 5-field cron expression or an `@` alias
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(anyhow!(
+                "Schedule needs 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+
+        // Sunday is 0 or 7
+        let mut weekdays =
+            field(weekday, 0, 7, &WEEKDAYS).map_err(|e| anyhow!("Weekday: {}", e))?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes: field(minute, 0, 59, &[]).map_err(|e| anyhow!("Minute: {}", e))?,
+            hours: field(hour, 0, 23, &[]).map_err(|e| anyhow!("Hour: {}", e))?,
+            days: field(day, 1, 31, &[]).map_err(|e| anyhow!("Day: {}", e))?,
+            months: field(month, 1, 12, &MONTHS).map_err(|e| anyhow!("Month: {}", e))?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires at this minute
+    pub fn matches<T: Datelike + Timelike>(&self, time: &T) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes & (1 << time.minute()) != 0
+            && self.hours & (1 << time.hour()) != 0
+            && self.months & (1 << time.month()) != 0
+            && day_matches
+    }
+
+    /// Whether the schedule fires at a minute in `(since, now]`
+    pub fn due_between(&self, since: DateTime<Local>, now: DateTime<Local>) -> bool {
+        let since = since.max(now - ChronoDuration::minutes(MAX_CATCH_UP_MINUTES));
+        let mut minute = start_of_minute(since) + ChronoDuration::minutes(1);
+        while minute <= now {
+            if self.matches(&minute) {
+                return true;
+            }
+            minute += ChronoDuration::minutes(1);
+        }
+        false
+    }
+}
+
+/// Bits of the values allowed by a cron field (`*`, `5`, `1-5`, `*/15`,
+/// `mon-fri`, lists of those)
+fn field(text: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |text: &str| -> Result<u32> {
+        let lower = text.to_ascii_lowercase();
+        let value = match names.iter().position(|name| *name == lower) {
+            // Names start at the field's minimum (jan = 1, sun = 0)
+            Some(index) => index as u32 + min,
+            None => text
+                .parse()
+                .map_err(|_| anyhow!("invalid value '{}'", text))?,
+        };
+        if value < min || value > max {
+            return Err(anyhow!("{} is out of range {}-{}", value, min, max));
+        }
+        Ok(value)
+    };
+
+    let mut bits = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow!("invalid step '{}'", step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/10` runs from 5 to the end
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(anyhow!("invalid range '{}'", range));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+fn start_of_minute(time: DateTime<Local>) -> DateTime<Local> {
+    time - ChronoDuration::seconds(time.second() as i64)
+        - ChronoDuration::nanoseconds(time.nanosecond() as i64)
+}
+
+/// Check a task before it is saved
+pub fn validate(
+    name: Option<&str>,
+    command: Option<&str>,
+    connection_ids: Option<&[String]>,
+    schedule: Option<&str>,
+) -> Result<()> {
+    if name.is_some_and(|name| name.trim().is_empty()) {
+        return Err(anyhow!("Task name is required"));
+    }
+    if command.is_some_and(|command| command.trim().is_empty()) {
+        return Err(anyhow!("Command is required"));
+    }
+    if connection_ids.is_some_and(|ids| ids.is_empty()) {
+        return Err(anyhow!("Select at least one connection"));
+    }
+    if let Some(schedule) = schedule.filter(|s| !s.trim().is_empty()) {
+        Schedule::parse(schedule).map_err(|e| anyhow!("Invalid schedule: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Runs in progress, so a task doesn't run twice at once on a connection
+#[derive(Clone, Default)]
+pub struct RemoteTaskRunner {
+    running: Arc<StdMutex<HashSet<(String, String)>>>,
+}
+
+impl RemoteTaskRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a task on each of its connections in the background
+    ///
+    /// Connections where the task is still running are skipped; returns
+    /// the IDs of the connections it was started on. Scheduled runs
+    /// (`background`) skip hosts whose key isn't known instead of asking.
+    pub fn start(
+        &self,
+        app_handle: &AppHandle,
+        task: &RemoteTask,
+        background: bool,
+    ) -> Vec<String> {
+        let mut started = Vec::new();
+        for connection_id in &task.connection_ids {
+            let key = (task.id.clone(), connection_id.clone());
+            let Ok(mut running) = self.running.lock() else {
+                break;
+            };
+            if !running.insert(key.clone()) {
+                tracing::info!(
+                    "[remote_tasks.rs] Task {} still running on {}, skipped",
+                    task.name,
+                    connection_id
+                );
+                continue;
+            }
+            drop(running);
+            started.push(connection_id.clone());
+
+            let running = Arc::clone(&self.running);
+            let app_handle = app_handle.clone();
+            let task = task.clone();
+            let connection_id = connection_id.clone();
+            tokio::spawn(async move {
+                let run = run_on(&app_handle, &task, &connection_id, background).await;
+                if let Ok(mut running) = running.lock() {
+                    running.remove(&key);
+                }
+                finish(&app_handle, &task, run).await;
+            });
+        }
+        started
+    }
+}
+
+/// Run a task's command on one connection
+async fn run_on(
+    app_handle: &AppHandle,
+    task: &RemoteTask,
+    connection_id: &str,
+    background: bool,
+) -> RemoteTaskRun {
+    let mut run = RemoteTaskRun::new(&task.id, connection_id);
+    tracing::info!(
+        "[remote_tasks.rs] Running task {} on {}",
+        task.name,
+        connection_id
+    );
+
+    let result = exec::exec_command(
+        app_handle,
+        connection_id,
+        &task.command,
+        RUN_TIMEOUT,
+        background,
+    )
+    .await;
+
+    if let Err(e) = audit::record(
+        app_handle.state::<AppState>().db.pool(),
+        audit::EVENT_REMOTE_TASK_RUN,
+        &format!(
+            "{}: {}",
+            task.name,
+            exec::audit_detail(connection_id, &task.command, &result)
+        ),
+    )
+    .await
+    {
+        tracing::warn!("[remote_tasks.rs] Failed to record audit event: {}", e);
+    }
+
+    match result {
+        Ok(output) => {
+            run.exit_status = output.exit_status.map(i64::from);
+            run.error = output.failure();
+            run.output = stored_output(&output.stdout, &output.stderr);
+        }
+        Err(e) => run.error = Some(e.to_string()),
+    }
+
+    run.ended_at = chrono::Utc::now().timestamp_millis();
+    run
+}
+
+/// stdout then stderr, cut to the last `MAX_OUTPUT` bytes
+fn stored_output(stdout: &str, stderr: &str) -> String {
+    let mut output = format!("{}{}", stdout, stderr);
+    if output.len() > MAX_OUTPUT {
+        let mut start = output.len() - MAX_OUTPUT;
+        while !output.is_char_boundary(start) {
+            start += 1;
+        }
+        output.drain(..start);
+    }
+    output
+}
+
+/// Store a finished run and tell the frontend
+async fn finish(app_handle: &AppHandle, task: &RemoteTask, run: RemoteTaskRun) {
+    match &run.error {
+        Some(error) => tracing::warn!(
+            "[remote_tasks.rs] Task {} failed on {}: {}",
+            task.name,
+            run.connection_id,
+            error
+        ),
+        None => tracing::info!(
+            "[remote_tasks.rs] Task {} exited with {:?} on {}",
+            task.name,
+            run.exit_status,
+            run.connection_id
+        ),
+    }
+
+    let state = app_handle.state::<AppState>();
+    if let Err(e) = state.connections.record_remote_task_run(&run).await {
+        tracing::warn!(
+            "[remote_tasks.rs] Failed to record run of {}: {}",
+            task.name,
+            e
+        );
+    }
+
+    let _ = app_handle.emit(
+        EVENT_COMPLETED,
+        serde_json::json!({
+            "taskId": task.id,
+            "taskName": task.name,
+            "runId": run.id,
+            "connectionId": run.connection_id,
+            "exitStatus": run.exit_status,
+            "error": run.error,
+        }),
+    );
+}
+
+/// Start the enabled tasks whose schedule is due since the last check
+///
+/// Called by the scheduler every `CHECK_INTERVAL` with the time of its
+/// previous check (None on the first check: nothing is due yet).
+pub async fn run_due(
+    app_handle: &AppHandle,
+    since: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Result<()> {
+    let Some(since) = since else {
+        return Ok(());
+    };
+    let state = app_handle.state::<AppState>();
+    for task in state.connections.get_all_remote_tasks().await? {
+        let Some(expression) = task.schedule.as_deref().filter(|_| task.enabled) else {
+            continue;
+        };
+        match Schedule::parse(expression) {
+            Ok(schedule) if schedule.due_between(since, now) => {
+                state.remote_tasks.start(app_handle, &task, true);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(
+                "[remote_tasks.rs] Task {} has an invalid schedule: {}",
+                task.name,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_matches() {
+        // Every 15 minutes during office hours on weekdays
+        let office = Schedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        assert!(office.matches(&at(2026, 10, 16, 9, 45))); // Friday
+        assert!(!office.matches(&at(2026, 10, 16, 9, 50)));
+        assert!(!office.matches(&at(2026, 10, 16, 18, 0)));
+        assert!(!office.matches(&at(2026, 10, 17, 9, 0))); // Saturday
+
+        // Day of month and weekday both given: either matches; 7 is Sunday
+        let either = Schedule::parse("30 2 1,15 * 7").unwrap();
+        assert!(either.matches(&at(2026, 10, 1, 2, 30)));
+        assert!(either.matches(&at(2026, 10, 18, 2, 30))); // Sunday
+        assert!(!either.matches(&at(2026, 10, 19, 2, 30)));
+
+        assert_eq!(
+            Schedule::parse("@daily").unwrap(),
+            Schedule::parse("0 0 * * *").unwrap()
+        );
+        let yearly = Schedule::parse("0 12 25 DEC *").unwrap();
+        assert!(yearly.matches(&at(2026, 12, 25, 12, 0)));
+
+        for invalid in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(Schedule::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_due_between() {
+        let hourly = Schedule::parse("@hourly").unwrap();
+        let before = at(2026, 10, 16, 9, 59) + ChronoDuration::seconds(30);
+        let after = at(2026, 10, 16, 10, 0) + ChronoDuration::seconds(30);
+
+        // The minute is due once, in the check that passes it
+        assert!(hourly.due_between(before, after));
+        assert!(!hourly.due_between(after, after + ChronoDuration::seconds(60)));
+
+        // After a long sleep, only the last minutes are looked at
+        assert!(!hourly.due_between(at(2026, 10, 16, 8, 30), at(2026, 10, 16, 9, 30)));
+    }
+
+    #[test]
+    fn test_stored_output() {
+        assert_eq!(stored_output("out\n", "err\n"), "out\nerr\n");
+        let long = "é".repeat(MAX_OUTPUT);
+        let stored = stored_output(&long, "end");
+        assert!(stored.len() <= MAX_OUTPUT);
+        assert!(stored.ends_with("éend"));
+    }
+}
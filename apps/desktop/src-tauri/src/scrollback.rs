@@ -0,0 +1,173 @@
+/**
+ * Scrollback Module
+ *
+ * Bounded history of a session's raw output (escape sequences included), so
+ * a frontend that lost its terminal (window reload) can replay it, and so
+ * search and export have the data without asking the frontend.
+ *
+ * The oldest output is dropped past `MAX_LINES` lines or `MAX_BYTES` bytes,
+ * a line at a time, so replay never starts inside an escape sequence cut in
+ * half.
+ */
+use std::collections::VecDeque;
+
+/// Lines kept per session
+pub const MAX_LINES: usize = 10_000;
+
+/// Bytes kept per session
+pub const MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Small chunks are merged up to this size (fewer allocations)
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Ring buffer of output chunks
+pub struct Scrollback {
+    chunks: VecDeque<Vec<u8>>,
+    bytes: usize,
+    lines: usize,
+    max_bytes: usize,
+    max_lines: usize,
+    /// The oldest output starts in the middle of a line
+    partial_start: bool,
+}
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Self::with_limits(MAX_BYTES, MAX_LINES)
+    }
+}
+
+impl Scrollback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(max_bytes: usize, max_lines: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            bytes: 0,
+            lines: 0,
+            max_bytes,
+            max_lines,
+            partial_start: false,
+        }
+    }
+
+    /// Append output, dropping the oldest lines past the limits
+    pub fn push(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        match self.chunks.back_mut() {
+            Some(last) if last.len() + data.len() <= CHUNK_SIZE => last.extend_from_slice(data),
+            _ => self.chunks.push_back(data.to_vec()),
+        }
+        self.bytes += data.len();
+        self.lines += count_lines(data);
+
+        while self.bytes > self.max_bytes || self.lines > self.max_lines {
+            let Some(front) = self.chunks.front_mut() else {
+                break;
+            };
+            let excess_lines = self.lines.saturating_sub(self.max_lines);
+            let excess_bytes = self.bytes.saturating_sub(self.max_bytes);
+            match line_cut(front, excess_lines, excess_bytes) {
+                Some(cut) => {
+                    self.lines -= count_lines(&front[..cut]);
+                    self.bytes -= cut;
+                    front.drain(..cut);
+                    self.partial_start = false;
+                }
+                None => {
+                    self.lines -= count_lines(front);
+                    self.bytes -= front.len();
+                    self.partial_start = front.last() != Some(&b'\n');
+                    self.chunks.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Buffered output, starting at a line boundary
+    pub fn contents(&self) -> Vec<u8> {
+        let mut contents = Vec::with_capacity(self.bytes);
+        for chunk in &self.chunks {
+            contents.extend_from_slice(chunk);
+        }
+        if self.partial_start {
+            if let Some(newline) = contents.iter().position(|&b| b == b'\n') {
+                contents.drain(..=newline);
+            }
+        }
+        contents
+    }
+
+    /// Number of complete lines buffered
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+}
+
+fn count_lines(data: &[u8]) -> usize {
+    data.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Shortest prefix of `chunk` ending with a newline that holds at least
+/// `lines` lines and `bytes` bytes (None: the whole chunk must go)
+fn line_cut(chunk: &[u8], lines: usize, bytes: usize) -> Option<usize> {
+    let mut seen = 0;
+    for (i, &b) in chunk.iter().enumerate() {
+        if b != b'\n' {
+            continue;
+        }
+        seen += 1;
+        let cut = i + 1;
+        if seen >= lines && cut >= bytes && cut < chunk.len() {
+            return Some(cut);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_everything_under_limits() {
+        let mut scrollback = Scrollback::with_limits(1024, 10);
+        scrollback.push(b"one\r\n");
+        scrollback.push(b"two\r\nthr");
+        scrollback.push(b"ee");
+        assert_eq!(scrollback.contents(), b"one\r\ntwo\r\nthree");
+        assert_eq!(scrollback.lines(), 2);
+    }
+
+    #[test]
+    fn test_drops_oldest_lines() {
+        let mut scrollback = Scrollback::with_limits(1024, 2);
+        for line in ["a\n", "b\n", "c\n", "d\n"] {
+            scrollback.push(line.as_bytes());
+        }
+        assert_eq!(scrollback.contents(), b"c\nd\n");
+        assert_eq!(scrollback.lines(), 2);
+    }
+
+    #[test]
+    fn test_byte_limit_cuts_at_line_boundary() {
+        let mut scrollback = Scrollback::with_limits(8, 100);
+        scrollback.push(b"\x1b[31mred\n");
+        scrollback.push(b"ok\n");
+        // The colored line doesn't fit: replay starts at the next line
+        assert_eq!(scrollback.contents(), b"ok\n");
+    }
+
+    #[test]
+    fn test_partial_first_line_is_skipped() {
+        let mut scrollback = Scrollback::with_limits(10, 100);
+        // Separate chunks (over CHUNK_SIZE) so the first is dropped whole
+        scrollback.push(&vec![b'x'; CHUNK_SIZE]);
+        scrollback.push(b"tail\nlast\n");
+        assert_eq!(scrollback.contents(), b"last\n");
+    }
+}
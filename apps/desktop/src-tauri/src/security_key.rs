@@ -0,0 +1,101 @@
+/**
+ * Security Key Module
+ *
+ * FIDO2 keys (`sk-ssh-ed25519@openssh.com`, `sk-ecdsa-sha2-nistp256@openssh.com`)
+ * can't sign in-process: the key file only holds a handle for the device.
+ * Signing goes through ssh-agent, which talks to the YubiKey; while the
+ * agent waits for the user to touch the key an `ssh:security-key-touch`
+ * event is emitted so the UI can ask for it.
+ */
+use anyhow::{anyhow, Result};
+use russh::keys::agent::client::AgentClient;
+use russh::keys::{Algorithm, HashAlg, PublicKey};
+use serde::Serialize;
+
+/// Windows OpenSSH agent pipe
+#[cfg(windows)]
+const WINDOWS_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
+/// `ssh:security-key-touch` payload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityKeyTouch {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub fingerprint: String,
+    /// True while the signature is pending, false once it is done
+    pub waiting: bool,
+}
+
+/// Whether a key lives on a FIDO2 security key
+pub fn is_security_key(key: &PublicKey) -> bool {
+    matches!(
+        key.algorithm(),
+        Algorithm::SkEd25519 | Algorithm::SkEcdsaSha2NistP256
+    )
+}
+
+/// SHA256 fingerprint shown in the touch prompt
+pub fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// Connect to the user's ssh-agent
+#[cfg(unix)]
+pub async fn connect_agent() -> Result<AgentClient<tokio::net::UnixStream>> {
+    AgentClient::connect_env().await.map_err(|e| {
+        anyhow!(
+            "Security keys need a running ssh-agent (SSH_AUTH_SOCK): {}",
+            e
+        )
+    })
+}
+
+/// Connect to the user's ssh-agent
+#[cfg(windows)]
+pub async fn connect_agent() -> Result<AgentClient<tokio::net::windows::named_pipe::NamedPipeClient>>
+{
+    AgentClient::connect_named_pipe(WINDOWS_AGENT_PIPE)
+        .await
+        .map_err(|e| anyhow!("Security keys need the OpenSSH agent service: {}", e))
+}
+
+/// Check that the agent holds the security key
+pub async fn ensure_agent_identity<S>(agent: &mut AgentClient<S>, key: &PublicKey) -> Result<()>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let identities = agent.request_identities().await?;
+    if identities
+        .iter()
+        .any(|identity| identity.key_data() == key.key_data())
+    {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Security key {} is not loaded in ssh-agent (run ssh-add with the key file)",
+            fingerprint(key)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_security_key() {
+        let sk = PublicKey::from_openssh(
+            "sk-ssh-ed25519@openssh.com AAAAGnNrLXNzaC1lZDI1NTE5QG9wZW5zc2guY29tAAAAIAABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4fAAAABHNzaDo= yubikey",
+        )
+        .unwrap();
+        assert!(is_security_key(&sk));
+
+        let ed25519 = PublicKey::from_openssh(
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMGPs4ghb2L6+g07e4VpkxvLvaTKuABI+2Bo4RXHu+WW",
+        )
+        .unwrap();
+        assert!(!is_security_key(&ed25519));
+    }
+}
@@ -0,0 +1,756 @@
+/**
+ * Session Options Module
+ *
+ * Per-connection session behavior (stored as JSON in connections.session_options)
+ */
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::bootstrap;
+use crate::clipboard_bridge::{self, BridgeMode};
+use crate::encoding::EncodingConverter;
+use crate::locale;
+use crate::login_script::ExpectStep;
+use crate::prompt::{PromptDetector, PromptProfile, StartupSequence, DEFAULT_PROFILE};
+use crate::reconnect::{self, ReconnectPolicy, ResumeMode};
+use crate::triggers::TriggerRule;
+use rite_protocols::proxy::{ProxyConfig, ProxyKind};
+
+/// Client identification string used by OpenSSH-mimicking preset
+pub const OPENSSH_CLIENT_ID: &str = "SSH-2.0-OpenSSH_9.6";
+
+/// Default time to wait for a prompt before sending a startup command anyway
+pub const DEFAULT_PROMPT_TIMEOUT_SECS: u64 = 10;
+
+/// Most jump hosts a connection can go through
+pub const MAX_JUMP_HOSTS: usize = 8;
+
+/// Most environment variables a connection can set
+pub const MAX_ENV_VARS: usize = 64;
+
+/// Per-connection session options
+///
+/// All fields are optional so that older rows (and partial frontend input)
+/// deserialize to defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionOptions {
+    /// SSH client identification string presented during handshake.
+    /// None = use the global `ssh_client_id` setting.
+    pub client_id: Option<String>,
+
+    /// How LANG/LC_ALL are forwarded to the remote host
+    pub locale_mode: LocaleMode,
+
+    /// Locale to forward. None = detected local system locale.
+    pub locale: Option<String>,
+
+    /// Environment variables set before the shell starts (SSH env requests,
+    /// local shell environment); LANG/LC_ALL here override the locale
+    pub env: Vec<EnvVar>,
+
+    /// Command to run instead of the default login shell
+    /// (e.g. `tmux new -A -s main`, `/usr/bin/zsh`, a restricted menu program)
+    pub remote_command: Option<String>,
+
+    /// Commands typed into the shell after login, each one sent once a
+    /// prompt is detected
+    pub startup_commands: Vec<String>,
+
+    /// Script (aliases, PS1, ...) pushed to a temp file and sourced at the
+    /// first prompt, before the startup commands; the server's dotfiles are
+    /// left untouched
+    pub bootstrap: Option<String>,
+
+    /// Remote clipboard access through OSC 52 (`rpbcopy` / `rpbpaste`)
+    pub clipboard_bridge: BridgeMode,
+
+    /// Prompt detection profile ("bash", "cisco", a custom profile name).
+    /// None = default profile.
+    pub prompt_profile: Option<String>,
+
+    /// Regex matched against the last output line to detect the prompt,
+    /// overriding the profile's patterns
+    pub prompt_pattern: Option<String>,
+
+    /// Seconds to wait for a prompt before sending the next startup command,
+    /// overriding the profile's timeout
+    pub prompt_timeout_secs: Option<u64>,
+
+    /// Character encoding used by the host (e.g. "windows-1251", "GBK").
+    /// None = UTF-8, no conversion.
+    pub encoding: Option<String>,
+
+    /// Buffer input locally (with echo and history) and send it line by line
+    pub line_mode: bool,
+
+    /// Expect/send steps run after connect (device logins outside SSH auth)
+    pub login_script: Vec<ExpectStep>,
+
+    /// Rules matched against the output (auto-answers, alerts, highlights)
+    pub triggers: Vec<TriggerRule>,
+
+    /// Pre-establish the SSH transport right after unlock (warm connections)
+    pub warm_on_unlock: bool,
+
+    /// Vault connections to go through (ProxyJump), first hop first; each
+    /// hop authenticates with its own stored credentials
+    pub jump_hosts: Vec<String>,
+
+    /// Protection level (protected = production host, dangerous commands
+    /// need confirmation)
+    pub protection: ProtectionLevel,
+
+    /// Maximum session length in minutes; the session is disconnected when
+    /// it is reached (None or 0 = unlimited)
+    pub time_limit_minutes: Option<u64>,
+
+    /// Automatic reconnection after a network drop
+    pub reconnect: ReconnectPolicy,
+
+    /// Run the shell inside a remote tmux/screen session, attached again
+    /// on reconnect
+    pub resume: ResumeMode,
+
+    /// Name of the remote tmux/screen session (None = "rite")
+    pub resume_session: Option<String>,
+
+    /// SOCKS5/HTTP proxy to connect through (for a jump host chain, the
+    /// first hop's proxy is used)
+    pub proxy: Option<ProxySettings>,
+}
+
+/// Proxy settings of a connection
+///
+/// The proxy password is not part of the options: it is stored with the
+/// connection's encrypted credentials.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    pub kind: ProxyKind,
+    pub hostname: String,
+    pub port: u16,
+    pub username: Option<String>,
+}
+
+/// Environment variable of a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+}
+
+/// Locale forwarding mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocaleMode {
+    /// Don't forward the locale (server default)
+    #[default]
+    Off,
+    /// Send LANG/LC_ALL via SSH env channel requests (requires AcceptEnv on the server)
+    Env,
+    /// Set LANG/LC_ALL on the command line that starts the remote shell
+    Command,
+}
+
+/// Protection level of a host
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtectionLevel {
+    #[default]
+    Standard,
+    /// Dangerous commands need confirmation before they are sent
+    Protected,
+}
+
+impl SessionOptions {
+    /// Parse options from the database column (NULL or invalid JSON = defaults)
+    pub fn from_json(json: Option<&str>) -> Self {
+        match json {
+            Some(json) if !json.trim().is_empty() => {
+                serde_json::from_str(json).unwrap_or_else(|e| {
+                    tracing::warn!(
+                        "[session_options] Invalid session options, using defaults: {}",
+                        e
+                    );
+                    Self::default()
+                })
+            }
+            _ => Self::default(),
+        }
+    }
+
+    /// Locale to forward (configured value or detected local locale)
+    pub fn resolved_locale(&self) -> Result<String> {
+        match &self.locale {
+            Some(locale) if !locale.trim().is_empty() => {
+                let locale = locale.trim();
+                if !locale::is_valid_locale(locale) {
+                    return Err(anyhow!("Invalid locale: {}", locale));
+                }
+                Ok(locale.to_string())
+            }
+            _ => Ok(locale::detect_local_locale()),
+        }
+    }
+
+    /// The connection's environment variables, checked (rows with an empty
+    /// name are skipped)
+    pub fn environment(&self) -> Result<Vec<(String, String)>> {
+        let mut vars: Vec<(String, String)> = Vec::new();
+        for var in &self.env {
+            let name = var.name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            if !is_valid_env_name(name) {
+                return Err(anyhow!("Invalid environment variable name: {}", name));
+            }
+            if var.value.contains('\0') {
+                return Err(anyhow!("Environment variable {} contains a NUL byte", name));
+            }
+            if vars.iter().any(|(n, _)| n == name) {
+                return Err(anyhow!("Environment variable {} is set twice", name));
+            }
+            vars.push((name.to_string(), var.value.clone()));
+        }
+
+        if vars.len() > MAX_ENV_VARS {
+            return Err(anyhow!(
+                "Too many environment variables ({}, at most {})",
+                vars.len(),
+                MAX_ENV_VARS
+            ));
+        }
+        Ok(vars)
+    }
+
+    /// Variables sent with SSH env requests: LANG/LC_ALL when the locale is
+    /// forwarded that way, then the connection's variables
+    pub fn env_requests(&self) -> Result<Vec<(String, String)>> {
+        let locale = match self.locale_mode {
+            LocaleMode::Env => Some(self.resolved_locale()?),
+            LocaleMode::Off | LocaleMode::Command => None,
+        };
+        self.environment_with_locale(locale)
+    }
+
+    /// Variables set on a local shell: LANG/LC_ALL unless locale forwarding
+    /// is off, then the connection's variables
+    pub fn local_environment(&self) -> Result<Vec<(String, String)>> {
+        let locale = match self.locale_mode {
+            LocaleMode::Env | LocaleMode::Command => Some(self.resolved_locale()?),
+            LocaleMode::Off => None,
+        };
+        self.environment_with_locale(locale)
+    }
+
+    fn environment_with_locale(&self, locale: Option<String>) -> Result<Vec<(String, String)>> {
+        let custom = self.environment()?;
+        let mut vars: Vec<(String, String)> = locale
+            .into_iter()
+            .flat_map(|locale| ["LANG", "LC_ALL"].map(|name| (name.to_string(), locale.clone())))
+            .filter(|(name, _)| !custom.iter().any(|(n, _)| n == name))
+            .collect();
+        vars.extend(custom);
+        Ok(vars)
+    }
+
+    /// Command to exec on the session channel instead of requesting a shell
+    ///
+    /// Returns None when the server's default shell should be requested.
+    pub fn shell_command(&self) -> Result<Option<String>> {
+        let remote_command = self
+            .remote_command
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+        let resume_command =
+            reconnect::resume_command(self.resume, self.resume_session.as_deref())?;
+        let command = match (remote_command, resume_command.as_deref()) {
+            (Some(_), Some(_)) => {
+                return Err(anyhow!(
+                    "A remote command cannot be combined with tmux/screen resume"
+                ))
+            }
+            (command, resume) => command.or(resume),
+        };
+
+        let locale = match self.locale_mode {
+            LocaleMode::Command => Some(self.resolved_locale()?),
+            LocaleMode::Off | LocaleMode::Env => None,
+        };
+
+        Ok(match (locale, command) {
+            (Some(locale), Some(command)) => {
+                Some(format!("export LANG={0} LC_ALL={0}; {1}", locale, command))
+            }
+            (Some(locale), None) => Some(format!(
+                "LANG={0} LC_ALL={0} exec \"${{SHELL:-/bin/sh}}\" -l",
+                locale
+            )),
+            (None, Some(command)) => Some(command.to_string()),
+            (None, None) => None,
+        })
+    }
+
+    /// Prompt profile of this session among `profiles` (built-in and custom)
+    ///
+    /// An unknown profile name (e.g. a deleted custom profile) falls back to
+    /// the default profile.
+    pub fn resolved_prompt_profile<'a>(
+        &self,
+        profiles: &'a [PromptProfile],
+    ) -> Option<&'a PromptProfile> {
+        let name = self
+            .prompt_profile
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(DEFAULT_PROFILE);
+
+        profiles.iter().find(|p| p.name == name).or_else(|| {
+            tracing::warn!(
+                "[session_options] Unknown prompt profile '{}', using the default",
+                name
+            );
+            profiles.iter().find(|p| p.name == DEFAULT_PROFILE)
+        })
+    }
+
+    /// Command lines typed after login: the bootstrap and the clipboard
+    /// helpers (if enabled), then the startup commands
+    pub fn startup_commands(&self) -> Result<Vec<String>> {
+        let bootstrap = match self.bootstrap.as_deref() {
+            Some(script) => bootstrap::bootstrap_command(script)?,
+            None => None,
+        };
+
+        Ok(bootstrap
+            .into_iter()
+            .chain(clipboard_bridge::helper_command(self.clipboard_bridge))
+            .chain(self.startup_commands.iter().cloned())
+            .collect())
+    }
+
+    /// Startup command sequence for this session (None if there are no commands)
+    pub fn startup_sequence(&self, profiles: &[PromptProfile]) -> Result<Option<StartupSequence>> {
+        let profile = self.resolved_prompt_profile(profiles);
+
+        let detector = match (self.prompt_pattern.as_deref(), profile) {
+            (Some(pattern), _) if !pattern.trim().is_empty() => PromptDetector::new(Some(pattern))?,
+            (_, Some(profile)) => PromptDetector::from_profile(profile)?,
+            (_, None) => PromptDetector::new(None)?,
+        };
+        let timeout = self
+            .prompt_timeout_secs
+            .or(profile.and_then(|p| p.timeout_secs))
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_PROMPT_TIMEOUT_SECS);
+
+        Ok(StartupSequence::new(
+            &self.startup_commands()?,
+            detector,
+            Duration::from_secs(timeout),
+        ))
+    }
+
+    /// Output/input converter for non-UTF-8 hosts (None = no conversion)
+    pub fn encoding_converter(&self) -> Result<Option<EncodingConverter>> {
+        match self.encoding.as_deref().map(str::trim) {
+            Some(label) if !label.is_empty() => EncodingConverter::new(label),
+            _ => Ok(None),
+        }
+    }
+
+    /// Maximum session length (None = unlimited)
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit_minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+
+    /// Jump host connection IDs, first hop first
+    ///
+    /// Rejects chains that go through the connection itself or through the
+    /// same host twice.
+    pub fn jump_chain(&self, connection_id: &str) -> Result<Vec<String>> {
+        let mut chain: Vec<String> = Vec::new();
+        for id in self.jump_hosts.iter().map(|id| id.trim()) {
+            if id.is_empty() {
+                continue;
+            }
+            if id == connection_id {
+                return Err(anyhow!("A connection cannot be its own jump host"));
+            }
+            if chain.iter().any(|hop| hop == id) {
+                return Err(anyhow!("Jump host {} appears twice in the chain", id));
+            }
+            chain.push(id.to_string());
+        }
+
+        if chain.len() > MAX_JUMP_HOSTS {
+            return Err(anyhow!(
+                "Too many jump hosts ({}, at most {})",
+                chain.len(),
+                MAX_JUMP_HOSTS
+            ));
+        }
+        Ok(chain)
+    }
+
+    /// Proxy to connect through, with the connection's proxy password
+    pub fn proxy_config(&self, password: Option<&str>) -> Result<Option<ProxyConfig>> {
+        let Some(proxy) = &self.proxy else {
+            return Ok(None);
+        };
+        let hostname = proxy.hostname.trim();
+        if hostname.is_empty() || proxy.port == 0 {
+            return Err(anyhow!("Proxy host and port are required"));
+        }
+        let username = proxy
+            .username
+            .as_deref()
+            .map(str::trim)
+            .filter(|u| !u.is_empty());
+        if username.is_none() && password.is_some() {
+            return Err(anyhow!("Proxy password set without a proxy username"));
+        }
+
+        Ok(Some(ProxyConfig {
+            kind: proxy.kind,
+            hostname: hostname.to_string(),
+            port: proxy.port,
+            username: username.map(String::from),
+            password: password.map(String::from),
+        }))
+    }
+
+    /// Serialize options for database storage
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// POSIX environment variable name (letters, digits and `_`, no leading digit)
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Named client identification preset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientIdPreset {
+    pub name: String,
+    pub value: String,
+}
+
+/// Available client identification presets
+pub fn client_id_presets() -> Vec<ClientIdPreset> {
+    vec![
+        ClientIdPreset {
+            name: "rite".to_string(),
+            value: format!("SSH-2.0-RITE_{}", env!("CARGO_PKG_VERSION")),
+        },
+        ClientIdPreset {
+            name: "openssh".to_string(),
+            value: OPENSSH_CLIENT_ID.to_string(),
+        },
+    ]
+}
+
+/// Resolve a client identification setting into a full banner
+///
+/// Accepts a preset name ("rite", "openssh"), a full banner
+/// ("SSH-2.0-Foo_1.0") or a bare software version ("Foo_1.0").
+/// Returns None for an empty value (use the library default).
+pub fn resolve_client_id(value: &str) -> Result<Option<String>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(preset) = client_id_presets()
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(value))
+    {
+        return Ok(Some(preset.value));
+    }
+
+    let banner = if value.starts_with("SSH-2.0-") {
+        value.to_string()
+    } else {
+        format!("SSH-2.0-{}", value)
+    };
+
+    // RFC 4253 section 4.2: printable US-ASCII, max 255 chars including CR LF
+    if banner.len() > 253 {
+        return Err(anyhow!("Client identification string is too long"));
+    }
+    if !banner.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return Err(anyhow!(
+            "Client identification string must be printable ASCII"
+        ));
+    }
+
+    Ok(Some(banner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triggers::TriggerAction;
+
+    #[test]
+    fn test_resolve_client_id() {
+        assert_eq!(resolve_client_id("").unwrap(), None);
+        assert_eq!(
+            resolve_client_id("OpenSSH").unwrap(),
+            Some(OPENSSH_CLIENT_ID.to_string())
+        );
+        assert_eq!(
+            resolve_client_id("PuTTY_Release_0.81").unwrap(),
+            Some("SSH-2.0-PuTTY_Release_0.81".to_string())
+        );
+        assert_eq!(
+            resolve_client_id("SSH-2.0-Custom_1.0 comment").unwrap(),
+            Some("SSH-2.0-Custom_1.0 comment".to_string())
+        );
+        assert!(resolve_client_id("bad\r\nbanner").is_err());
+    }
+
+    #[test]
+    fn test_shell_command() {
+        let mut options = SessionOptions::default();
+        assert_eq!(options.shell_command().unwrap(), None);
+
+        options.remote_command = Some("  tmux new -A -s main ".to_string());
+        assert_eq!(
+            options.shell_command().unwrap(),
+            Some("tmux new -A -s main".to_string())
+        );
+
+        options.locale_mode = LocaleMode::Command;
+        options.locale = Some("de_DE.UTF-8".to_string());
+        assert_eq!(
+            options.shell_command().unwrap(),
+            Some("export LANG=de_DE.UTF-8 LC_ALL=de_DE.UTF-8; tmux new -A -s main".to_string())
+        );
+
+        options.remote_command = None;
+        assert!(options
+            .shell_command()
+            .unwrap()
+            .unwrap()
+            .starts_with("LANG=de_DE.UTF-8 LC_ALL=de_DE.UTF-8 exec"));
+
+        options.locale = Some("$(reboot)".to_string());
+        assert!(options.shell_command().is_err());
+
+        let mut options = SessionOptions {
+            resume: ResumeMode::Tmux,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.shell_command().unwrap(),
+            Some("tmux new-session -A -s rite".to_string())
+        );
+        options.remote_command = Some("htop".to_string());
+        assert!(options.shell_command().is_err());
+    }
+
+    #[test]
+    fn test_environment() {
+        let var = |name: &str, value: &str| EnvVar {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let mut options = SessionOptions {
+            env: vec![
+                var(" FLAGS ", "-v --color"),
+                var("", "ignored"),
+                var("LC_ALL", "C"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            options.env_requests().unwrap(),
+            vec![
+                ("FLAGS".to_string(), "-v --color".to_string()),
+                ("LC_ALL".to_string(), "C".to_string()),
+            ]
+        );
+
+        // Forwarded locale first, the connection's LC_ALL wins
+        options.locale_mode = LocaleMode::Env;
+        options.locale = Some("de_DE.UTF-8".to_string());
+        let pairs: Vec<String> = options
+            .env_requests()
+            .unwrap()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        assert_eq!(pairs, ["LANG=de_DE.UTF-8", "FLAGS=-v --color", "LC_ALL=C"]);
+
+        // The command line carries the locale over SSH, local shells get it
+        options.locale_mode = LocaleMode::Command;
+        assert_eq!(options.env_requests().unwrap().len(), 2);
+        assert_eq!(options.local_environment().unwrap().len(), 3);
+
+        for name in ["1ABC", "A-B", "A B", "A=B"] {
+            options.env = vec![var(name, "x")];
+            assert!(options.environment().is_err(), "{}", name);
+        }
+        options.env = vec![var("A", "x\0y")];
+        assert!(options.environment().is_err());
+        options.env = vec![var("A", "1"), var("A", "2")];
+        assert!(options.environment().is_err());
+        options.env = (0..=MAX_ENV_VARS)
+            .map(|i| var(&format!("V{}", i), "x"))
+            .collect();
+        assert!(options.environment().is_err());
+    }
+
+    #[test]
+    fn test_startup_sequence_profile() {
+        let profiles = crate::prompt::load_profiles(None).unwrap();
+        let mut options = SessionOptions {
+            startup_commands: vec!["terminal length 0".to_string()],
+            prompt_profile: Some("cisco".to_string()),
+            ..Default::default()
+        };
+
+        // Profile timeout, unless the connection overrides it
+        let sequence = options.startup_sequence(&profiles).unwrap().unwrap();
+        assert_eq!(sequence.timeout(), Duration::from_secs(30));
+        options.prompt_timeout_secs = Some(5);
+        let mut sequence = options.startup_sequence(&profiles).unwrap().unwrap();
+        assert_eq!(sequence.timeout(), Duration::from_secs(5));
+        assert_eq!(
+            sequence.on_output(b"router1#"),
+            Some("terminal length 0\r".to_string())
+        );
+
+        // Unknown profile: default profile
+        options.prompt_profile = Some("deleted".to_string());
+        let mut sequence = options.startup_sequence(&profiles).unwrap().unwrap();
+        assert!(sequence.on_output(b"$ ").is_some());
+
+        // The bootstrap goes first
+        options.bootstrap = Some("alias ll='ls -l'".to_string());
+        let commands = options.startup_commands().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(commands[0].contains("base64 -d"));
+        assert_eq!(commands[1], "terminal length 0");
+    }
+
+    #[test]
+    fn test_jump_chain() {
+        let mut options = SessionOptions::default();
+        assert!(options.jump_chain("target").unwrap().is_empty());
+
+        options.jump_hosts = vec![" bastion ".to_string(), "".to_string(), "inner".to_string()];
+        assert_eq!(
+            options.jump_chain("target").unwrap(),
+            vec!["bastion".to_string(), "inner".to_string()]
+        );
+
+        // Loops and oversized chains
+        assert!(options.jump_chain("inner").is_err());
+        options.jump_hosts.push("bastion".to_string());
+        assert!(options.jump_chain("target").is_err());
+        options.jump_hosts = (0..=MAX_JUMP_HOSTS).map(|i| format!("hop{}", i)).collect();
+        assert!(options.jump_chain("target").is_err());
+    }
+
+    #[test]
+    fn test_proxy_config() {
+        let mut options = SessionOptions::default();
+        assert_eq!(options.proxy_config(None).unwrap(), None);
+
+        options.proxy = Some(ProxySettings {
+            kind: ProxyKind::Http,
+            hostname: " proxy.corp ".to_string(),
+            port: 3128,
+            username: Some("".to_string()),
+        });
+        let proxy = options.proxy_config(None).unwrap().unwrap();
+        assert_eq!(proxy.hostname, "proxy.corp");
+        assert_eq!(proxy.username, None);
+        assert!(options.proxy_config(Some("secret")).is_err());
+
+        options.proxy.as_mut().unwrap().username = Some("jdoe".to_string());
+        let proxy = options.proxy_config(Some("secret")).unwrap().unwrap();
+        assert_eq!(proxy.password.as_deref(), Some("secret"));
+
+        options.proxy.as_mut().unwrap().port = 0;
+        assert!(options.proxy_config(None).is_err());
+    }
+
+    #[test]
+    fn test_options_json_roundtrip() {
+        assert_eq!(SessionOptions::from_json(None), SessionOptions::default());
+        assert_eq!(
+            SessionOptions::from_json(Some("not json")),
+            SessionOptions::default()
+        );
+
+        let options = SessionOptions {
+            client_id: Some("openssh".to_string()),
+            locale_mode: LocaleMode::Env,
+            locale: Some("fr_FR.UTF-8".to_string()),
+            env: vec![EnvVar {
+                name: "FLAGS".to_string(),
+                value: "-v".to_string(),
+            }],
+            remote_command: Some("tmux new -A -s main".to_string()),
+            startup_commands: vec!["cd /srv/app".to_string()],
+            bootstrap: Some("alias ll='ls -l'".to_string()),
+            clipboard_bridge: BridgeMode::CopyPaste,
+            prompt_profile: Some("cisco".to_string()),
+            prompt_pattern: Some(r"\$ $".to_string()),
+            prompt_timeout_secs: Some(5),
+            encoding: Some("windows-1251".to_string()),
+            line_mode: true,
+            login_script: vec![ExpectStep {
+                expect: "Password:".to_string(),
+                send: "{{password}}".to_string(),
+                timeout_secs: Some(10),
+                fail: Some("Access denied".to_string()),
+            }],
+            triggers: vec![TriggerRule {
+                pattern: r"\(yes/no\)\?".to_string(),
+                action: TriggerAction::SendText {
+                    text: "yes".to_string(),
+                },
+                disabled: false,
+            }],
+            warm_on_unlock: true,
+            jump_hosts: vec!["bastion".to_string()],
+            protection: ProtectionLevel::Protected,
+            time_limit_minutes: Some(30),
+            reconnect: ReconnectPolicy {
+                enabled: true,
+                max_attempts: 3,
+                initial_delay_secs: 2,
+                max_delay_secs: 10,
+            },
+            resume: ResumeMode::Screen,
+            resume_session: Some("work".to_string()),
+            proxy: Some(ProxySettings {
+                kind: ProxyKind::Socks5,
+                hostname: "proxy.corp".to_string(),
+                port: 1080,
+                username: Some("jdoe".to_string()),
+            }),
+        };
+        let json = options.to_json().unwrap();
+        assert_eq!(SessionOptions::from_json(Some(&json)), options);
+    }
+}
@@ -0,0 +1,485 @@
+/**
+ * Session Restore Module
+ *
+ * Remembers the open terminals (connection, title, working directory when
+ * the shell reports it, end of the scrollback) so the next launch can offer
+ * to reopen them. The frontend reports its terminals as they change; the
+ * list is written to `<data dir>/rite/session_restore.json` then, and again
+ * before the vault locks and when the app exits, so the scrollback tails
+ * are recent.
+ *
+ * Scrollback tails are encrypted with the session restore subkey: they are
+ * only saved while the vault is unlocked (the last ones are kept while it
+ * is locked) and only shown again after unlock. They are re-encrypted when
+ * the master password changes. Without a vault, terminals are restored
+ * without their scrollback.
+ */
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use rite_crypto::subkey;
+use rite_vault::{EncryptedData, MasterKey, Rekey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+
+/// Restore file (next to the vault)
+const FILE_NAME: &str = "session_restore.json";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Scrollback kept per terminal (cut at a line start)
+pub const TAIL_BYTES: usize = 64 * 1024;
+
+/// An open terminal, as reported by the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDescriptor {
+    pub session_id: String,
+    /// Saved connection ID, or "local" for local terminals
+    pub connection_id: String,
+    pub title: String,
+    /// Shell of a local terminal
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// Working directory, when the shell reports it (OSC 7)
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+/// A terminal offered for restore
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorableSession {
+    pub connection_id: String,
+    pub title: String,
+    pub shell: Option<String>,
+    pub cwd: Option<String>,
+    pub has_scrollback: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedSession {
+    connection_id: String,
+    title: String,
+    #[serde(default)]
+    shell: Option<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    /// Encrypted scrollback tail (base64 of the envelope)
+    #[serde(default)]
+    scrollback: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedSessions {
+    version: u32,
+    /// Save time (Unix seconds)
+    saved_at: i64,
+    sessions: Vec<SavedSession>,
+}
+
+/// Terminals saved by the previous run and the ones open in this run
+pub struct SessionRestore {
+    path: Option<PathBuf>,
+    /// Loaded at startup, offered until the app exits
+    saved: StdMutex<Vec<SavedSession>>,
+    /// None until the frontend reports its terminals (nothing is written
+    /// before, so an unanswered offer survives the next exit)
+    open: StdMutex<Option<Vec<SessionDescriptor>>>,
+    /// Last encrypted scrollback tail of each open terminal
+    tails: StdMutex<HashMap<String, String>>,
+}
+
+impl SessionRestore {
+    /// Load the terminals saved by the previous run
+    pub fn load() -> Self {
+        let path = rite_vault::default_path()
+            .map(|path| path.with_file_name(FILE_NAME))
+            .map_err(|e| tracing::warn!("[session_restore.rs] No restore file: {}", e))
+            .ok();
+        Self::load_from(path)
+    }
+
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let saved = match path.as_deref().map(read) {
+            Some(Ok(saved)) => saved,
+            Some(Err(e)) => {
+                tracing::warn!("[session_restore.rs] Ignoring saved sessions: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        Self {
+            path,
+            saved: StdMutex::new(saved),
+            open: StdMutex::new(None),
+            tails: StdMutex::new(HashMap::new()),
+        }
+    }
+
+    /// Terminals of the previous run
+    pub fn restorable(&self) -> Vec<RestorableSession> {
+        let Ok(saved) = self.saved.lock() else {
+            return Vec::new();
+        };
+        saved
+            .iter()
+            .map(|session| RestorableSession {
+                connection_id: session.connection_id.clone(),
+                title: session.title.clone(),
+                shell: session.shell.clone(),
+                cwd: session.cwd.clone(),
+                has_scrollback: session.scrollback.is_some(),
+            })
+            .collect()
+    }
+
+    /// Scrollback tail of a terminal of the previous run (empty when none)
+    pub fn scrollback(&self, index: usize, master_key: &MasterKey) -> Result<Vec<u8>> {
+        let saved = self.saved.lock().map_err(|_| anyhow!("Lock poisoned"))?;
+        let session = saved
+            .get(index)
+            .ok_or_else(|| anyhow!("No saved session {}", index))?;
+        match &session.scrollback {
+            Some(tail) => decrypt_tail(master_key, tail),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Record the terminals open in the frontend (keeping the working
+    /// directories already reported)
+    pub fn set_open(&self, mut sessions: Vec<SessionDescriptor>) {
+        if let Ok(mut tails) = self.tails.lock() {
+            tails.retain(|id, _| sessions.iter().any(|s| &s.session_id == id));
+        }
+        if let Ok(mut open) = self.open.lock() {
+            for session in sessions.iter_mut().filter(|s| s.cwd.is_none()) {
+                session.cwd = open
+                    .iter()
+                    .flatten()
+                    .find(|s| s.session_id == session.session_id)
+                    .and_then(|s| s.cwd.clone());
+            }
+            *open = Some(sessions);
+        }
+    }
+
+    /// Record the working directory reported by a terminal
+    pub fn set_cwd(&self, session_id: &str, cwd: Option<String>) {
+        if let Ok(mut open) = self.open.lock() {
+            if let Some(session) = open
+                .iter_mut()
+                .flatten()
+                .find(|s| s.session_id == session_id)
+            {
+                session.cwd = cwd;
+            }
+        }
+    }
+
+    /// Write the open terminals; `scrollback` returns a terminal's output
+    /// and `master_key` (None while locked) encrypts the tails
+    pub fn save(
+        &self,
+        scrollback: impl Fn(&str) -> Option<Vec<u8>>,
+        master_key: Option<&MasterKey>,
+    ) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let open = self
+            .open
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))?
+            .clone();
+        let Some(open) = open else {
+            return Ok(());
+        };
+        let mut tails = self.tails.lock().map_err(|_| anyhow!("Lock poisoned"))?;
+        if let Some(master_key) = master_key {
+            for session in &open {
+                if let Some(data) = scrollback(&session.session_id) {
+                    tails.insert(session.session_id.clone(), encrypt_tail(master_key, &data)?);
+                }
+            }
+        }
+
+        let saved = SavedSessions {
+            version: FORMAT_VERSION,
+            saved_at: chrono::Utc::now().timestamp(),
+            sessions: open
+                .into_iter()
+                .map(|session| SavedSession {
+                    scrollback: tails.get(&session.session_id).cloned(),
+                    connection_id: session.connection_id,
+                    title: session.title,
+                    shell: session.shell,
+                    cwd: session.cwd,
+                })
+                .collect(),
+        };
+        write(path, &saved)
+    }
+}
+
+/// Scrollback tails staged for a master password change
+///
+/// A tail that no longer decrypts is discarded rather than failing the
+/// change.
+pub struct SessionRestoreRekey<'a> {
+    restore: &'a SessionRestore,
+    saved: Vec<Option<String>>,
+    tails: HashMap<String, String>,
+}
+
+impl SessionRestore {
+    /// Re-encryption of the tails, for `AuthManager::change_master_password_with`
+    pub fn rekey(&self) -> SessionRestoreRekey<'_> {
+        SessionRestoreRekey {
+            restore: self,
+            saved: Vec::new(),
+            tails: HashMap::new(),
+        }
+    }
+}
+
+impl Rekey for SessionRestoreRekey<'_> {
+    fn prepare(&mut self, old_key: &MasterKey, new_key: &MasterKey) -> Result<()> {
+        let reencrypt = |tail: &str| match decrypt_tail(old_key, tail) {
+            Ok(data) => encrypt_tail(new_key, &zeroize::Zeroizing::new(data)).map(Some),
+            Err(e) => {
+                tracing::warn!("[session_restore.rs] Discarding scrollback: {}", e);
+                Ok(None)
+            }
+        };
+
+        let saved = self
+            .restore
+            .saved
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))?;
+        self.saved = saved
+            .iter()
+            .map(|session| match &session.scrollback {
+                Some(tail) => reencrypt(tail),
+                None => Ok(None),
+            })
+            .collect::<Result<_>>()?;
+
+        let tails = self
+            .restore
+            .tails
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))?;
+        self.tails = HashMap::new();
+        for (session_id, tail) in tails.iter() {
+            if let Some(tail) = reencrypt(tail)? {
+                self.tails.insert(session_id.clone(), tail);
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        let mut saved = self
+            .restore
+            .saved
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))?;
+        for (session, tail) in saved.iter_mut().zip(self.saved.drain(..)) {
+            session.scrollback = tail;
+        }
+        *self
+            .restore
+            .tails
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))? = std::mem::take(&mut self.tails);
+
+        // The file still holds the previous run's terminals until this run's
+        // are reported
+        let reported = self
+            .restore
+            .open
+            .lock()
+            .map_err(|_| anyhow!("Lock poisoned"))?
+            .is_some();
+        match &self.restore.path {
+            Some(path) if !reported && path.exists() => write(
+                path,
+                &SavedSessions {
+                    version: FORMAT_VERSION,
+                    saved_at: chrono::Utc::now().timestamp(),
+                    sessions: saved.clone(),
+                },
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn abort(&mut self) {
+        self.saved.clear();
+        self.tails.clear();
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<SavedSession>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read restore file"),
+    };
+    let saved: SavedSessions = serde_json::from_str(&contents).context("Corrupted restore file")?;
+    if saved.version != FORMAT_VERSION {
+        return Err(anyhow!(
+            "Unsupported restore file version {}",
+            saved.version
+        ));
+    }
+    Ok(saved.sessions)
+}
+
+fn write(path: &Path, saved: &SavedSessions) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+    }
+    // Write then rename, so an exit mid-write doesn't lose the previous file
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_vec(saved)?).context("Failed to write restore file")?;
+    std::fs::rename(&temp, path).context("Failed to write restore file")
+}
+
+/// End of the output, starting at a line
+fn tail(data: &[u8]) -> &[u8] {
+    if data.len() <= TAIL_BYTES {
+        return data;
+    }
+    let tail = &data[data.len() - TAIL_BYTES..];
+    match tail.iter().position(|&b| b == b'\n') {
+        Some(newline) => &tail[newline + 1..],
+        None => tail,
+    }
+}
+
+fn encrypt_tail(master_key: &MasterKey, data: &[u8]) -> Result<String> {
+    let key = master_key.derive_subkey(subkey::SESSION_RESTORE);
+    let encrypted = rite_crypto::encrypt(&key, tail(data))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(encrypted.to_bytes()))
+}
+
+fn decrypt_tail(master_key: &MasterKey, tail: &str) -> Result<Vec<u8>> {
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(tail)
+        .context("Corrupted scrollback")?;
+    let encrypted = EncryptedData::from_bytes(&blob).context("Corrupted scrollback")?;
+    let key = master_key.derive_subkey(subkey::SESSION_RESTORE);
+    rite_crypto::decrypt(&key, &encrypted)
+        .map_err(|_| anyhow!("Scrollback was saved with another master key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(session_id: &str, connection_id: &str) -> SessionDescriptor {
+        SessionDescriptor {
+            session_id: session_id.to_string(),
+            connection_id: connection_id.to_string(),
+            title: format!("Terminal {}", session_id),
+            shell: None,
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(tail(b"short\n"), b"short\n");
+
+        let mut data = vec![b'x'; TAIL_BYTES];
+        data.extend_from_slice(b"\nlast line\n");
+        assert_eq!(tail(&data), b"last line\n");
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        let master_key = MasterKey::derive("restore-test", &[5u8; 16]).unwrap();
+        let output = |id: &str| (id == "a").then(|| b"$ ls\r\nsecret.txt\r\n".to_vec());
+
+        // Nothing is written until the frontend reports its terminals
+        let restore = SessionRestore::load_from(Some(path.clone()));
+        restore.save(output, Some(&master_key)).unwrap();
+        assert!(!path.exists());
+
+        let mut local = descriptor("b", "local");
+        local.shell = Some("/bin/bash".to_string());
+        restore.set_open(vec![descriptor("a", "conn-1"), local.clone()]);
+        restore.set_cwd("b", Some("/tmp".to_string()));
+        // Reported again without the working directory: it is kept
+        restore.set_open(vec![descriptor("a", "conn-1"), local]);
+        restore.save(output, Some(&master_key)).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+
+        // Locked: the last tails are kept
+        restore.save(|_| None, None).unwrap();
+
+        let next = SessionRestore::load_from(Some(path.clone()));
+        let sessions = next.restorable();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].connection_id, "conn-1");
+        assert!(sessions[0].has_scrollback);
+        assert_eq!(sessions[1].shell.as_deref(), Some("/bin/bash"));
+        assert_eq!(sessions[1].cwd.as_deref(), Some("/tmp"));
+        assert!(!sessions[1].has_scrollback);
+
+        assert_eq!(
+            next.scrollback(0, &master_key).unwrap(),
+            b"$ ls\r\nsecret.txt\r\n"
+        );
+        assert!(next.scrollback(1, &master_key).unwrap().is_empty());
+        assert!(next.scrollback(2, &master_key).is_err());
+        let other = MasterKey::derive("other-password", &[5u8; 16]).unwrap();
+        assert!(next.scrollback(0, &other).is_err());
+
+        // The offer stays while this run's terminals are saved
+        next.set_open(Vec::new());
+        next.save(|_| None, None).unwrap();
+        assert_eq!(next.restorable().len(), 2);
+        let after = SessionRestore::load_from(Some(path));
+        assert!(after.restorable().is_empty());
+    }
+
+    #[test]
+    fn test_rekey() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        let old_key = MasterKey::derive("restore-test", &[5u8; 16]).unwrap();
+        let new_key = MasterKey::derive("other-password", &[5u8; 16]).unwrap();
+
+        let restore = SessionRestore::load_from(Some(path.clone()));
+        restore.set_open(vec![descriptor("a", "conn-1")]);
+        restore
+            .save(|_| Some(b"$ uptime\r\n".to_vec()), Some(&old_key))
+            .unwrap();
+
+        // Previous run's terminals, not reported again yet
+        let next = SessionRestore::load_from(Some(path.clone()));
+        let mut rekey = next.rekey();
+        rekey.prepare(&old_key, &new_key).unwrap();
+        rekey.abort();
+        assert!(next.scrollback(0, &old_key).is_ok());
+
+        let mut rekey = next.rekey();
+        rekey.prepare(&old_key, &new_key).unwrap();
+        rekey.commit().unwrap();
+        assert!(next.scrollback(0, &old_key).is_err());
+        assert_eq!(next.scrollback(0, &new_key).unwrap(), b"$ uptime\r\n");
+
+        // The file was rewritten with the new key
+        let after = SessionRestore::load_from(Some(path));
+        assert_eq!(after.scrollback(0, &new_key).unwrap(), b"$ uptime\r\n");
+    }
+}
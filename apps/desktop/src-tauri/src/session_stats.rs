@@ -0,0 +1,132 @@
+/**
+ * Session Statistics
+ *
+ * Traffic and latency of SSH sessions, for the ping badge and transfer
+ * rates shown on each tab. Every `REPORT_INTERVAL` the session loop
+ * reports its byte counters; the result is kept for `get_session_stats`
+ * and sent as a `session-stats` event.
+ *
+ * While the frontend watches the stats (`watch_session_stats`), each report
+ * also starts a round trip to the server: a `keepalive@openssh.com` request
+ * with want-reply, timed in a task of its own so the session loop never
+ * waits for it. Its result goes into the next report.
+ */
+use serde::Serialize;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+
+/// Event sent with each report
+pub const EVENT_STATS: &str = "session-stats";
+
+/// How often traffic is reported and latency measured
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Round trips longer than this count as unanswered
+pub const LATENCY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Traffic and latency of a session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    pub session_id: String,
+    /// Bytes received and sent since the session started
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Bytes per second since the previous report
+    pub rate_in: u64,
+    pub rate_out: u64,
+    /// Last round trip to the server (None until measured, or unanswered)
+    pub latency_ms: Option<u64>,
+    /// Unix timestamp in milliseconds
+    pub updated_at: i64,
+}
+
+/// Latest stats of a session, shared with the session handle
+pub type StatsState = Arc<StdMutex<SessionStats>>;
+
+/// Turns the session's counters into reports
+#[derive(Debug)]
+pub struct StatsMeter {
+    state: StatsState,
+    previous: (u64, u64),
+    previous_at: Instant,
+}
+
+impl StatsMeter {
+    pub fn new(session_id: &str) -> Self {
+        Self {
+            state: Arc::new(StdMutex::new(SessionStats {
+                session_id: session_id.to_string(),
+                ..SessionStats::default()
+            })),
+            previous: (0, 0),
+            previous_at: Instant::now(),
+        }
+    }
+
+    /// Stats shared with the session handle
+    pub fn state(&self) -> StatsState {
+        Arc::clone(&self.state)
+    }
+
+    /// Record the counters and the last round trip, and return the report
+    pub fn update(
+        &mut self,
+        bytes_in: u64,
+        bytes_out: u64,
+        latency: Option<Duration>,
+        now: Instant,
+    ) -> SessionStats {
+        let elapsed = now.duration_since(self.previous_at).as_secs_f64();
+        let rate = |current: u64, previous: u64| {
+            if elapsed > 0.0 {
+                (current.saturating_sub(previous) as f64 / elapsed).round() as u64
+            } else {
+                0
+            }
+        };
+        let rate_in = rate(bytes_in, self.previous.0);
+        let rate_out = rate(bytes_out, self.previous.1);
+        self.previous = (bytes_in, bytes_out);
+        self.previous_at = now;
+
+        let Ok(mut stats) = self.state.lock() else {
+            return SessionStats::default();
+        };
+        stats.bytes_in = bytes_in;
+        stats.bytes_out = bytes_out;
+        stats.rate_in = rate_in;
+        stats.rate_out = rate_out;
+        stats.latency_ms = latency.map(|latency| latency.as_millis() as u64);
+        stats.updated_at = chrono::Utc::now().timestamp_millis();
+        stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_and_latency() {
+        let mut meter = StatsMeter::new("session");
+        let start = meter.previous_at;
+
+        let stats = meter.update(
+            10_000,
+            500,
+            Some(Duration::from_millis(42)),
+            start + Duration::from_secs(5),
+        );
+        assert_eq!((stats.rate_in, stats.rate_out), (2_000, 100));
+        assert_eq!(stats.latency_ms, Some(42));
+        assert_eq!(stats.session_id, "session");
+
+        let stats = meter.update(10_000, 1_500, None, start + Duration::from_secs(10));
+        assert_eq!((stats.bytes_in, stats.bytes_out), (10_000, 1_500));
+        assert_eq!((stats.rate_in, stats.rate_out), (0, 200));
+        assert_eq!(stats.latency_ms, None);
+
+        assert_eq!(*meter.state().lock().unwrap(), stats);
+    }
+}
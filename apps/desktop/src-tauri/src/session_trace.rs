@@ -0,0 +1,134 @@
+/**
+ * Session Trace Module
+ *
+ * Records protocol-level SSH events per session when debug capture is enabled.
+ * Events that mark a handshake stage also carry it as data, so the handshake
+ * can be timed (connection test) without reading the details.
+ */
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+
+/// Maximum number of events kept per session (oldest are dropped first)
+const MAX_TRACE_EVENTS: usize = 2000;
+
+/// A single protocol event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEvent {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// Event category (e.g. "kex", "host_key", "auth", "channel", "window")
+    pub kind: String,
+    /// Human-readable details
+    pub detail: String,
+    /// Handshake stage the event marks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<HandshakeStage>,
+}
+
+/// Handshake milestones, in the order they happen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HandshakeStage {
+    /// Key exchange done, host key accepted
+    KexDone,
+    /// Authentication method being tried (e.g. "password authentication")
+    AuthAttempt { method: String },
+}
+
+/// Bounded, toggleable event recorder shared by a session and its SSH handler
+#[derive(Debug, Default)]
+pub struct SessionTrace {
+    enabled: AtomicBool,
+    events: StdMutex<VecDeque<TraceEvent>>,
+}
+
+impl SessionTrace {
+    pub fn new(enabled: bool) -> Arc<Self> {
+        Arc::new(Self {
+            enabled: AtomicBool::new(enabled),
+            events: StdMutex::new(VecDeque::new()),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Record an event (no-op when debug capture is disabled)
+    pub fn record(&self, kind: &str, detail: impl Into<String>) {
+        self.push(kind, detail.into(), None);
+    }
+
+    /// Record an event that marks a handshake stage
+    pub fn record_stage(&self, kind: &str, detail: impl Into<String>, stage: HandshakeStage) {
+        self.push(kind, detail.into(), Some(stage));
+    }
+
+    fn push(&self, kind: &str, detail: String, stage: Option<HandshakeStage>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let event = TraceEvent {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            kind: kind.to_string(),
+            detail,
+            stage,
+        };
+
+        if let Ok(mut events) = self.events.lock() {
+            if events.len() >= MAX_TRACE_EVENTS {
+                events.pop_front();
+            }
+            events.push_back(event);
+        }
+    }
+
+    /// Get a copy of all recorded events
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events
+            .lock()
+            .map(|events| events.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_trace_records_nothing() {
+        let trace = SessionTrace::new(false);
+        trace.record("auth", "password");
+        assert!(trace.events().is_empty());
+
+        trace.set_enabled(true);
+        trace.record("auth", "password");
+        assert_eq!(trace.events().len(), 1);
+        assert_eq!(trace.events()[0].kind, "auth");
+        assert_eq!(trace.events()[0].stage, None);
+
+        trace.record_stage("kex", "Key exchange completed", HandshakeStage::KexDone);
+        assert_eq!(trace.events()[1].stage, Some(HandshakeStage::KexDone));
+    }
+
+    #[test]
+    fn test_trace_is_bounded() {
+        let trace = SessionTrace::new(true);
+        for i in 0..(MAX_TRACE_EVENTS + 10) {
+            trace.record("window", format!("{}", i));
+        }
+
+        let events = trace.events();
+        assert_eq!(events.len(), MAX_TRACE_EVENTS);
+        assert_eq!(events[0].detail, "10");
+    }
+}
@@ -0,0 +1,385 @@
+/**
+ * SFTP Sessions
+ *
+ * File browser sessions on saved connections. Each session has an SSH
+ * transport of its own (host key verification, jump hosts and
+ * authentication as for terminals) with the SFTP subsystem on one channel,
+ * so browsing never competes with terminal traffic.
+ *
+ * Servers without the SFTP subsystem still get transfers, over SCP; the
+ * browsing commands then fail.
+ *
+ * FTP/FTPS connections get the same sessions: browsing on one control
+ * connection, each transfer on a control connection of its own. FTP has no
+ * chmod or symlink reading.
+ *
+ * On protected connections, destructive operations (rename, chmod, delete)
+ * need the host name typed by the user, like dangerous terminal commands
+ * need confirming.
+ */
+use anyhow::{anyhow, Result};
+use rite_protocols::ftp::FtpClient;
+use rite_protocols::scp::ScpClient;
+use rite_protocols::ssh::SftpClient;
+use rite_protocols::{ConnectionConfig, FileEntry, FileTransferProtocol, Protocol, ProtocolType};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::connection::{AuthMethod, Connection};
+use crate::session_options::ProtectionLevel;
+use crate::terminal::{SessionId, SshClientHandler, SshTransport};
+
+/// Entry of a remote directory (or a single `stat`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    /// Modification time (Unix seconds)
+    pub modified: Option<i64>,
+    /// Mode bits, including the file type
+    pub permissions: Option<u32>,
+}
+
+impl From<FileEntry> for RemoteEntry {
+    fn from(entry: FileEntry) -> Self {
+        Self {
+            name: entry.name,
+            path: entry.path,
+            is_dir: entry.is_dir,
+            is_symlink: entry.is_symlink,
+            size: entry.size,
+            modified: entry.modified,
+            permissions: entry.permissions,
+        }
+    }
+}
+
+/// Opened SFTP session (`open_sftp_session` result)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SftpSessionInfo {
+    pub session_id: SessionId,
+    pub connection_id: String,
+    /// Login directory, where browsing starts
+    pub home: String,
+    /// SFTP is unavailable: transfers only (over SCP), no browsing
+    pub scp_only: bool,
+    /// Rename, chmod and delete need the host name typed
+    pub protected: bool,
+}
+
+/// Client for one upload or download
+pub enum TransferClient {
+    Sftp(SftpClient),
+    /// No resume: transfers start over
+    Scp(ScpClient<SshClientHandler>),
+    /// No resume either (a connected client of its own)
+    Ftp(FtpClient),
+}
+
+enum Backend {
+    Ssh {
+        /// Requests are sent one at a time (None = SCP only)
+        client: Option<Mutex<SftpClient>>,
+        transport: Arc<SshTransport>,
+    },
+    Ftp {
+        /// Browsing connection, requests one at a time
+        client: Mutex<FtpClient>,
+        /// To connect transfer clients
+        config: ConnectionConfig,
+        tls: bool,
+    },
+}
+
+/// SFTP (or FTP) session on a saved connection
+pub struct SftpSession {
+    pub id: SessionId,
+    pub connection_id: String,
+    pub hostname: String,
+    pub protected: bool,
+    backend: Backend,
+}
+
+impl SftpSession {
+    /// Start the SFTP subsystem on an authenticated transport, falling back
+    /// to SCP when the server refuses it
+    pub async fn open(
+        id: SessionId,
+        connection: &Connection,
+        transport: SshTransport,
+    ) -> Result<Self> {
+        let client = match open_sftp(&transport).await {
+            Ok(client) => Some(Mutex::new(client)),
+            Err(e) => {
+                tracing::warn!(
+                    "[sftp.rs] SFTP unavailable on {}, using SCP for transfers: {}",
+                    connection.id,
+                    e
+                );
+                None
+            }
+        };
+        Ok(Self {
+            id,
+            connection_id: connection.id.clone(),
+            hostname: connection.hostname.clone(),
+            protected: is_protected(connection),
+            backend: Backend::Ssh {
+                client,
+                transport: Arc::new(transport),
+            },
+        })
+    }
+
+    /// Connect to an FTP server (explicit FTPS with `tls`)
+    pub async fn open_ftp(
+        id: SessionId,
+        connection: &Connection,
+        config: ConnectionConfig,
+        tls: bool,
+    ) -> Result<Self> {
+        let client = connect_ftp(&config, tls).await?;
+        Ok(Self {
+            id,
+            connection_id: connection.id.clone(),
+            hostname: connection.hostname.clone(),
+            protected: is_protected(connection),
+            backend: Backend::Ftp {
+                client: Mutex::new(client),
+                config,
+                tls,
+            },
+        })
+    }
+
+    pub fn is_scp_only(&self) -> bool {
+        matches!(self.backend, Backend::Ssh { client: None, .. })
+    }
+
+    /// Transfers can continue a partial copy (SFTP); over SCP and FTP they
+    /// start over
+    pub fn supports_resume(&self) -> bool {
+        matches!(
+            self.backend,
+            Backend::Ssh {
+                client: Some(_),
+                ..
+            }
+        )
+    }
+
+    fn client(&self) -> Result<&Mutex<SftpClient>> {
+        match &self.backend {
+            Backend::Ssh {
+                client: Some(client),
+                ..
+            } => Ok(client),
+            Backend::Ssh { client: None, .. } => Err(anyhow!(
+                "SFTP is not available on this server (SCP transfers only)"
+            )),
+            Backend::Ftp { .. } => Err(anyhow!("Not supported over FTP")),
+        }
+    }
+
+    fn ftp(&self) -> Option<&Mutex<FtpClient>> {
+        match &self.backend {
+            Backend::Ftp { client, .. } => Some(client),
+            Backend::Ssh { .. } => None,
+        }
+    }
+
+    /// Client for a transfer, on a new channel of the session's transport
+    /// (transfers don't hold up browsing)
+    ///
+    /// Falls back to SCP when no SFTP channel can be opened.
+    pub async fn open_transfer_client(&self) -> Result<TransferClient> {
+        let (client, transport) = match &self.backend {
+            Backend::Ssh { client, transport } => (client, transport),
+            Backend::Ftp { config, tls, .. } => {
+                return Ok(TransferClient::Ftp(connect_ftp(config, *tls).await?));
+            }
+        };
+        if client.is_some() {
+            match open_sftp(transport).await {
+                Ok(client) => return Ok(TransferClient::Sftp(client)),
+                Err(e) => tracing::warn!(
+                    "[sftp.rs] SFTP channel failed on session {}, using SCP: {}",
+                    self.id,
+                    e
+                ),
+            }
+        }
+        Ok(TransferClient::Scp(ScpClient::from_handle(Arc::clone(
+            transport,
+        ))))
+    }
+
+    /// Login directory ("." when only SCP is available: paths are then
+    /// relative to it)
+    pub async fn home(&self) -> Result<String> {
+        match &self.backend {
+            Backend::Ssh {
+                client: Some(client),
+                ..
+            } => Ok(client.lock().await.canonicalize(".").await?),
+            Backend::Ssh { client: None, .. } => Ok(".".to_string()),
+            Backend::Ftp { client, .. } => Ok(client.lock().await.pwd().await?),
+        }
+    }
+
+    pub async fn list_dir(&self, path: &str) -> Result<Vec<RemoteEntry>> {
+        let entries = match self.ftp() {
+            Some(ftp) => ftp.lock().await.list_dir(path).await?,
+            None => self.client()?.lock().await.list_dir(path).await?,
+        };
+        Ok(entries.into_iter().map(RemoteEntry::from).collect())
+    }
+
+    pub async fn stat(&self, path: &str) -> Result<RemoteEntry> {
+        let entry = match self.ftp() {
+            Some(ftp) => ftp.lock().await.stat(path).await?,
+            None => self.client()?.lock().await.stat(path).await?,
+        };
+        Ok(entry.into())
+    }
+
+    /// Check the confirmation of a destructive operation (rename, chmod,
+    /// delete); returns whether the host name was typed (protected
+    /// connection), so the operation can be audit-logged
+    pub fn confirm_destructive(&self, confirm_host: Option<&str>) -> Result<bool> {
+        check_confirmation(&self.hostname, self.protected, confirm_host)
+    }
+
+    pub async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        match self.ftp() {
+            Some(ftp) => Ok(ftp.lock().await.rename(from, to).await?),
+            None => Ok(self.client()?.lock().await.rename(from, to).await?),
+        }
+    }
+
+    pub async fn chmod(&self, path: &str, mode: u32) -> Result<()> {
+        Ok(self.client()?.lock().await.chmod(path, mode).await?)
+    }
+
+    /// Delete a file, or an empty directory
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        match self.ftp() {
+            Some(ftp) => Ok(ftp.lock().await.delete(path).await?),
+            None => Ok(self.client()?.lock().await.delete(path).await?),
+        }
+    }
+
+    pub async fn mkdir(&self, path: &str) -> Result<()> {
+        match self.ftp() {
+            Some(ftp) => Ok(ftp.lock().await.mkdir(path).await?),
+            None => Ok(self.client()?.lock().await.mkdir(path).await?),
+        }
+    }
+
+    pub async fn read_link(&self, path: &str) -> Result<String> {
+        Ok(self.client()?.lock().await.read_link(path).await?)
+    }
+
+    /// Close the SFTP channel and the transport (or the FTP connection)
+    pub async fn close(&self) -> Result<()> {
+        let (client, transport) = match &self.backend {
+            Backend::Ssh { client, transport } => (client, transport),
+            Backend::Ftp { client, .. } => {
+                client.lock().await.disconnect().await?;
+                return Ok(());
+            }
+        };
+        if let Some(client) = client {
+            if let Err(e) = client.lock().await.disconnect().await {
+                tracing::warn!("[sftp.rs] Failed to close SFTP session {}: {}", self.id, e);
+            }
+        }
+        transport
+            .disconnect(russh::Disconnect::ByApplication, "", "")
+            .await?;
+        Ok(())
+    }
+}
+
+fn is_protected(connection: &Connection) -> bool {
+    connection.session_options.protection == ProtectionLevel::Protected
+}
+
+/// Destructive operations on a protected connection need its host name
+/// typed exactly; other connections need no confirmation
+fn check_confirmation(hostname: &str, protected: bool, confirm_host: Option<&str>) -> Result<bool> {
+    if !protected {
+        return Ok(false);
+    }
+    match confirm_host {
+        Some(typed) if typed == hostname => Ok(true),
+        Some(_) => Err(anyhow!("The host name typed doesn't match {}", hostname)),
+        None => Err(anyhow!(
+            "{} is a protected host: type its host name to confirm",
+            hostname
+        )),
+    }
+}
+
+/// FTP settings of a saved connection (FTP logs in with a password)
+pub fn ftp_config(connection: &Connection) -> Result<ConnectionConfig> {
+    let AuthMethod::Password { password } = &connection.auth_method else {
+        return Err(anyhow!("FTP connections need password authentication"));
+    };
+    if connection.session_options.proxy.is_some() {
+        // Passive data connections would bypass it
+        return Err(anyhow!("FTP connections cannot go through a proxy"));
+    }
+    Ok(ConnectionConfig {
+        protocol: ProtocolType::Ftp,
+        hostname: connection.hostname.clone(),
+        port: connection.port,
+        username: connection.username.clone(),
+        auth: rite_protocols::AuthMethod::Password {
+            password: password.clone(),
+        },
+        jump_host: None,
+        timeout: None,
+        keepalive: None,
+        proxy: None,
+    })
+}
+
+async fn connect_ftp(config: &ConnectionConfig, tls: bool) -> Result<FtpClient> {
+    let mut client = if tls {
+        FtpClient::secure()
+    } else {
+        FtpClient::new()
+    };
+    client.connect(config).await?;
+    Ok(client)
+}
+
+async fn open_sftp(transport: &SshTransport) -> Result<SftpClient> {
+    let channel = transport.channel_open_session().await?;
+    Ok(SftpClient::from_channel(channel).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destructive_confirmation() {
+        // Standard connections need no confirmation
+        assert!(!check_confirmation("db.example.com", false, None).unwrap());
+
+        // Protected connections refuse a missing or wrong host name
+        assert!(check_confirmation("db.example.com", true, None).is_err());
+        assert!(check_confirmation("db.example.com", true, Some("db")).is_err());
+        assert!(check_confirmation("db.example.com", true, Some("db.example.com ")).is_err());
+
+        assert!(check_confirmation("db.example.com", true, Some("db.example.com")).unwrap());
+    }
+}
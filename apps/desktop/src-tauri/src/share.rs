@@ -0,0 +1,529 @@
+/**
+ * Session Sharing Module
+ *
+ * Read-only live view of a terminal session, without screen sharing: a
+ * small HTTP server streams the rendered screen (Server-Sent Events) to the
+ * viewer holding the share URL.
+ *
+ * Opt-in via the `session_sharing_enabled` setting. Every share gets a new
+ * random token that admits a single viewer: the first one to open the
+ * stream claims it, and the share ends when that viewer leaves. Revoking it,
+ * the session ending or locking the app cuts the viewer off at the next
+ * frame. The server only listens while something is shared.
+ *
+ * The view is plain HTTP, so the server listens on loopback unless
+ * `session_sharing_lan` is also set.
+ */
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::snapshot::ScreenState;
+
+/// Setting that enables session sharing (off by default)
+pub const SETTING_ENABLED: &str = "session_sharing_enabled";
+
+/// Setting that serves shares on the LAN instead of loopback (off by default)
+pub const SETTING_LAN: &str = "session_sharing_lan";
+
+/// How often viewers get the screen (only sent when it changed)
+const FRAME_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Keep-alive comment interval, so closed viewers are noticed
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Longest accepted request head
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// A shared session, as reported to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    pub session_id: String,
+    pub url: String,
+    pub viewers: usize,
+}
+
+struct Share {
+    session_id: String,
+    screen: ScreenState,
+    /// Set once a viewer opened the stream; the token admits no one else
+    claimed: bool,
+}
+
+struct Server {
+    port: u16,
+    /// Listening on every interface rather than loopback
+    lan: bool,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Shares keyed by token
+    shares: HashMap<String, Share>,
+    server: Option<Server>,
+    app: Option<AppHandle>,
+}
+
+/// Active session shares and the server streaming them
+#[derive(Clone, Default)]
+pub struct ShareManager {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ShareManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share a session read-only; sharing it again replaces the old URL
+    ///
+    /// `lan` serves it to the local network instead of this machine only.
+    /// Switching between the two restarts the server, which ends the other
+    /// shares.
+    pub async fn share(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        screen: ScreenState,
+        lan: bool,
+    ) -> Result<ShareInfo> {
+        let mut inner = self.inner.lock().await;
+        inner.app = Some(app.clone());
+        inner
+            .shares
+            .retain(|_, share| share.session_id != session_id);
+
+        if inner
+            .server
+            .as_ref()
+            .is_some_and(|server| server.lan != lan)
+        {
+            inner.shares.clear();
+            stop_server_if_idle(&mut inner);
+        }
+
+        let port = match &inner.server {
+            Some(server) => server.port,
+            None => {
+                let address = if lan {
+                    Ipv4Addr::UNSPECIFIED
+                } else {
+                    Ipv4Addr::LOCALHOST
+                };
+                let listener = TcpListener::bind((address, 0)).await?;
+                let port = listener.local_addr()?.port();
+                tracing::info!("[share.rs] Share server listening on {}:{}", address, port);
+                let handle = tokio::spawn(serve(listener, self.clone()));
+                inner.server = Some(Server { port, lan, handle });
+                port
+            }
+        };
+
+        let token = new_token();
+        let url = share_url(share_address(lan), port, &token);
+        inner.shares.insert(
+            token,
+            Share {
+                session_id: session_id.to_string(),
+                screen,
+                claimed: false,
+            },
+        );
+
+        tracing::info!("[share.rs] Session {} shared", session_id);
+        Ok(ShareInfo {
+            session_id: session_id.to_string(),
+            url,
+            viewers: 0,
+        })
+    }
+
+    /// Stop sharing a session (viewers are cut off at the next frame)
+    pub async fn revoke(&self, session_id: &str) {
+        let mut inner = self.inner.lock().await;
+        let before = inner.shares.len();
+        inner
+            .shares
+            .retain(|_, share| share.session_id != session_id);
+        if inner.shares.len() != before {
+            tracing::info!("[share.rs] Stopped sharing session {}", session_id);
+        }
+        stop_server_if_idle(&mut inner);
+    }
+
+    /// Stop every share (on lock)
+    pub async fn revoke_all(&self) {
+        let mut inner = self.inner.lock().await;
+        inner.shares.clear();
+        stop_server_if_idle(&mut inner);
+    }
+
+    /// Active shares with their viewer counts
+    pub async fn list(&self) -> Vec<ShareInfo> {
+        let inner = self.inner.lock().await;
+        let Some(server) = &inner.server else {
+            return Vec::new();
+        };
+        let address = share_address(server.lan);
+        inner
+            .shares
+            .iter()
+            .map(|(token, share)| ShareInfo {
+                session_id: share.session_id.clone(),
+                url: share_url(address, server.port, token),
+                viewers: usize::from(share.claimed),
+            })
+            .collect()
+    }
+
+    /// Whether the token opens the viewer page (shared and not yet claimed)
+    async fn is_open(&self, token: &str) -> bool {
+        let inner = self.inner.lock().await;
+        inner.shares.get(token).is_some_and(|share| !share.claimed)
+    }
+
+    /// Claim a share for its one viewer
+    async fn claim(&self, token: &str) -> Option<(String, ScreenState)> {
+        let mut inner = self.inner.lock().await;
+        let share = inner.shares.get_mut(token).filter(|share| !share.claimed)?;
+        share.claimed = true;
+        let claimed = (share.session_id.clone(), Arc::clone(&share.screen));
+        if let Some(app) = &inner.app {
+            let _ = app.emit(
+                "session-share:viewers",
+                serde_json::json!({ "sessionId": claimed.0, "viewers": 1 }),
+            );
+        }
+        Some(claimed)
+    }
+
+    async fn is_shared(&self, token: &str) -> bool {
+        self.inner.lock().await.shares.contains_key(token)
+    }
+
+    /// End a share whose viewer left: the token is spent
+    async fn release(&self, token: &str) {
+        let mut inner = self.inner.lock().await;
+        let Some(share) = inner.shares.remove(token) else {
+            return;
+        };
+        tracing::info!(
+            "[share.rs] Viewer left, stopped sharing session {}",
+            share.session_id
+        );
+        if let Some(app) = &inner.app {
+            let _ = app.emit(
+                "session-share:ended",
+                serde_json::json!({ "sessionId": share.session_id }),
+            );
+        }
+        stop_server_if_idle(&mut inner);
+    }
+}
+
+fn stop_server_if_idle(inner: &mut Inner) {
+    if inner.shares.is_empty() {
+        if let Some(server) = inner.server.take() {
+            server.handle.abort();
+            tracing::info!("[share.rs] Share server on port {} stopped", server.port);
+        }
+    }
+}
+
+/// Requests understood by the share server
+#[derive(Debug, PartialEq, Eq)]
+enum Route<'a> {
+    /// Viewer page
+    Page(&'a str),
+    /// Screen updates (Server-Sent Events)
+    Events(&'a str),
+}
+
+/// Route a request line (`GET /s/<token> HTTP/1.1`)
+fn route(request_line: &str) -> Option<Route<'_>> {
+    let mut parts = request_line.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let path = parts.next()?.strip_prefix("/s/")?;
+    let route = match path.strip_suffix("/events") {
+        Some(token) => Route::Events(token),
+        None => Route::Page(path),
+    };
+    let token = match route {
+        Route::Page(token) | Route::Events(token) => token,
+    };
+    (!token.is_empty() && token.bytes().all(|b| b.is_ascii_hexdigit())).then_some(route)
+}
+
+async fn serve(listener: TcpListener, shares: ShareManager) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                tracing::debug!("[share.rs] Viewer connection from {}", peer);
+                let shares = shares.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, shares).await {
+                        tracing::debug!("[share.rs] Viewer connection ended: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                tracing::warn!("[share.rs] Failed to accept viewer: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream, shares: ShareManager) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let request_line = tokio::time::timeout(Duration::from_secs(5), read_request_head(&mut reader))
+        .await
+        .map_err(|_| anyhow!("Request timed out"))??;
+    let mut stream = reader.into_inner();
+
+    let Some(route) = route(&request_line) else {
+        return respond(&mut stream, "404 Not Found", "text/plain", "Not found").await;
+    };
+
+    match route {
+        Route::Page(token) => {
+            if !shares.is_open(token).await {
+                return respond(&mut stream, "404 Not Found", "text/plain", "Not found").await;
+            }
+            respond(
+                &mut stream,
+                "200 OK",
+                "text/html; charset=utf-8",
+                &viewer_page(token),
+            )
+            .await
+        }
+        Route::Events(token) => stream_screen(stream, &shares, token).await,
+    }
+}
+
+/// Read the request head; returns its first line
+async fn read_request_head(reader: &mut BufReader<TcpStream>) -> Result<String> {
+    let mut request_line = String::new();
+    let mut total = 0;
+    loop {
+        let mut line = String::new();
+        let read = reader.read_line(&mut line).await?;
+        total += read;
+        if read == 0 || total > MAX_REQUEST_BYTES {
+            return Err(anyhow!("Invalid request"));
+        }
+        if request_line.is_empty() {
+            request_line = line.trim_end().to_string();
+        } else if line.trim_end().is_empty() {
+            return Ok(request_line);
+        }
+    }
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Send the screen to the viewer until the share is revoked or the viewer
+/// leaves
+async fn stream_screen(mut stream: TcpStream, shares: &ShareManager, token: &str) -> Result<()> {
+    let Some((_, screen)) = shares.claim(token).await else {
+        return respond(&mut stream, "404 Not Found", "text/plain", "Not found").await;
+    };
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .await?;
+
+    let result = async {
+        let mut last_frame = String::new();
+        let mut last_ping = tokio::time::Instant::now();
+        while shares.is_shared(token).await {
+            let frame = screen
+                .lock()
+                .map_err(|_| anyhow!("Screen state poisoned"))?
+                .screen()
+                .contents();
+            if frame != last_frame {
+                stream.write_all(screen_event(&frame).as_bytes()).await?;
+                last_frame = frame;
+            } else if last_ping.elapsed() >= PING_INTERVAL {
+                stream.write_all(b": ping\n\n").await?;
+                last_ping = tokio::time::Instant::now();
+            }
+            tokio::time::sleep(FRAME_INTERVAL).await;
+        }
+        // Revoked: tell the page to stop reconnecting
+        stream.write_all(b"event: revoked\ndata: \n\n").await?;
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+
+    shares.release(token).await;
+    result
+}
+
+/// Server-Sent Event carrying the screen text (JSON-encoded, one line)
+fn screen_event(contents: &str) -> String {
+    format!(
+        "data: {}\n\n",
+        serde_json::to_string(contents).unwrap_or_default()
+    )
+}
+
+fn viewer_page(token: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>RITE shared session</title>
+<style>
+body {{ margin: 0; background: #1e1e1e; color: #d4d4d4; font-family: sans-serif; }}
+header {{ padding: 8px 12px; background: #2d2d2d; font-size: 13px; }}
+pre {{ margin: 0; padding: 12px; font-family: monospace; font-size: 14px; line-height: 1.2; }}
+</style>
+</head>
+<body>
+<header id="status">Read-only view &middot; connecting&hellip;</header>
+<pre id="screen"></pre>
+<script>
+const source = new EventSource('/s/{token}/events');
+const status = document.getElementById('status');
+source.onopen = () => {{ status.textContent = 'Read-only view · live'; }};
+source.onmessage = (event) => {{ document.getElementById('screen').textContent = JSON.parse(event.data); }};
+source.addEventListener('revoked', () => {{ source.close(); status.textContent = 'Sharing has ended'; }});
+source.onerror = () => {{ source.close(); status.textContent = 'Read-only view · disconnected'; }};
+</script>
+</body>
+</html>
+"#
+    )
+}
+
+/// Random share token (128 bits, hex)
+fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes
+        .iter()
+        .fold(String::with_capacity(32), |mut token, b| {
+            let _ = write!(token, "{:02x}", b);
+            token
+        })
+}
+
+fn share_url(address: IpAddr, port: u16, token: &str) -> String {
+    format!("http://{}:{}/s/{}", address, port, token)
+}
+
+/// Address viewers reach the server at
+fn share_address(lan: bool) -> IpAddr {
+    if lan {
+        lan_address()
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    }
+}
+
+/// Address of this machine on the LAN (localhost if there is no network)
+fn lan_address() -> IpAddr {
+    // Connecting a UDP socket sends nothing; it only picks the interface
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect(("192.0.2.1", 9))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("GET /s/0a1b HTTP/1.1"), Some(Route::Page("0a1b")));
+        assert_eq!(
+            route("GET /s/0a1b/events HTTP/1.1"),
+            Some(Route::Events("0a1b"))
+        );
+        assert_eq!(route("POST /s/0a1b HTTP/1.1"), None);
+        assert_eq!(route("GET / HTTP/1.1"), None);
+        assert_eq!(route("GET /s/ HTTP/1.1"), None);
+        assert_eq!(route("GET /s/../etc HTTP/1.1"), None);
+        assert_eq!(route(""), None);
+    }
+
+    #[test]
+    fn test_screen_event_is_single_line() {
+        let event = screen_event("$ ls\nfile \"a\"");
+        assert_eq!(event, "data: \"$ ls\\nfile \\\"a\\\"\"\n\n");
+    }
+
+    #[test]
+    fn test_tokens_are_unique() {
+        let token = new_token();
+        assert_eq!(token.len(), 32);
+        assert!(route(&format!("GET /s/{} HTTP/1.1", token)).is_some());
+        assert_ne!(token, new_token());
+    }
+
+    #[tokio::test]
+    async fn test_token_admits_one_viewer() {
+        let shares = ShareManager::new();
+        let token = new_token();
+        shares.inner.lock().await.shares.insert(
+            token.clone(),
+            Share {
+                session_id: "session".to_string(),
+                screen: crate::snapshot::new_screen_state(),
+                claimed: false,
+            },
+        );
+
+        assert!(shares.is_open(&token).await);
+        assert_eq!(
+            shares.claim(&token).await.map(|(id, _)| id).as_deref(),
+            Some("session")
+        );
+        assert!(!shares.is_open(&token).await);
+        assert!(shares.claim(&token).await.is_none());
+        assert!(shares.is_shared(&token).await);
+
+        shares.release(&token).await;
+        assert!(!shares.is_shared(&token).await);
+        assert!(shares.claim(&token).await.is_none());
+    }
+}
@@ -0,0 +1,327 @@
+/**
+ * Shell Integration
+ *
+ * Follows the shell through the escape sequences it writes around prompts:
+ * OSC 7 (`ESC ] 7 ; file://host/path`) reports the working directory, and
+ * OSC 133 marks the prompt (`A`), the command line (`B`), the start of the
+ * command output (`C`) and the end of the command with its exit code
+ * (`D;<code>`). Shells emit them when configured for it (VTE prompt hooks,
+ * starship, oh-my-posh, the iTerm2/WezTerm integration scripts...).
+ *
+ * Used to answer "where is this terminal?" (open SFTP there) and to time
+ * commands for the duration badge. The sequences are left in the output for
+ * the frontend terminal.
+ */
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Start of an OSC sequence
+const OSC: &[u8] = b"\x1b]";
+
+const BEL: u8 = 0x07;
+const ESC: u8 = 0x1b;
+
+/// Longest sequence kept across chunks (longer ones are not ours)
+const MAX_SEQUENCE_LEN: usize = 4096;
+
+/// Working directory last reported by the shell of a session
+pub type CwdState = Arc<StdMutex<Option<String>>>;
+
+/// Mark found in session output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mark {
+    /// OSC 7: working directory
+    Cwd(String),
+    /// OSC 133;A: prompt starts
+    PromptStart,
+    /// OSC 133;B: command line starts (end of the prompt)
+    CommandStart,
+    /// OSC 133;C: command runs (its output follows)
+    CommandExecuted,
+    /// OSC 133;D: command finished, with its exit code when given
+    CommandFinished(Option<i32>),
+}
+
+/// Finds shell integration marks in session output (sequences may be split
+/// across output chunks)
+#[derive(Debug, Default)]
+pub struct Scanner {
+    /// Unfinished sequence (or trailing ESC) from the previous chunk
+    pending: Vec<u8>,
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed session output and return the complete marks found
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Mark> {
+        let mut buffer = std::mem::take(&mut self.pending);
+        buffer.extend_from_slice(data);
+
+        let mut marks = Vec::new();
+        let mut position = 0;
+        loop {
+            let Some(start) = find(&buffer[position..], OSC).map(|i| position + i) else {
+                if buffer.last() == Some(&ESC) {
+                    self.pending.push(ESC);
+                }
+                break;
+            };
+
+            let body_start = start + OSC.len();
+            let Some((body_end, sequence_end)) = terminator(&buffer[body_start..]) else {
+                if buffer.len() - start <= MAX_SEQUENCE_LEN {
+                    self.pending = buffer[start..].to_vec();
+                }
+                break;
+            };
+
+            if let Some(mark) = parse_body(&buffer[body_start..body_start + body_end]) {
+                marks.push(mark);
+            }
+            position = body_start + sequence_end;
+        }
+
+        marks
+    }
+}
+
+/// Change worth telling the frontend about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    CommandStarted,
+    CommandFinished {
+        exit_code: Option<i32>,
+        duration: Duration,
+    },
+}
+
+/// Shell state of one session, updated from its output
+#[derive(Debug)]
+pub struct Tracker {
+    scanner: Scanner,
+    cwd: CwdState,
+    /// When the running command started (between OSC 133 C and D)
+    running_since: Option<Instant>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self {
+            scanner: Scanner::new(),
+            cwd: Arc::new(StdMutex::new(None)),
+            running_since: None,
+        }
+    }
+
+    /// Working directory shared with the session handle
+    pub fn cwd(&self) -> CwdState {
+        Arc::clone(&self.cwd)
+    }
+
+    /// Feed session output and return the command events it completes
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Event> {
+        // Plain output (the common case) has no OSC to look for
+        if self.scanner.pending.is_empty() && !data.contains(&ESC) {
+            return Vec::new();
+        }
+        self.feed_at(data, Instant::now())
+    }
+
+    fn feed_at(&mut self, data: &[u8], now: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        for mark in self.scanner.feed(data) {
+            match mark {
+                Mark::Cwd(path) => {
+                    if let Ok(mut cwd) = self.cwd.lock() {
+                        *cwd = Some(path);
+                    }
+                }
+                Mark::CommandExecuted => {
+                    self.running_since = Some(now);
+                    events.push(Event::CommandStarted);
+                }
+                Mark::CommandFinished(exit_code) => {
+                    // D also follows empty command lines: only report commands that ran
+                    if let Some(started) = self.running_since.take() {
+                        events.push(Event::CommandFinished {
+                            exit_code,
+                            duration: now.duration_since(started),
+                        });
+                    }
+                }
+                Mark::PromptStart | Mark::CommandStart => {}
+            }
+        }
+        events
+    }
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send a command event to the frontend
+pub fn emit(app_handle: &AppHandle, session_id: &str, event: &Event) {
+    let _ = match event {
+        Event::CommandStarted => app_handle.emit(
+            "command-started",
+            serde_json::json!({
+                "sessionId": session_id,
+            }),
+        ),
+        Event::CommandFinished {
+            exit_code,
+            duration,
+        } => app_handle.emit(
+            "command-finished",
+            serde_json::json!({
+                "sessionId": session_id,
+                "exitCode": exit_code,
+                "durationMs": duration.as_millis() as u64,
+            }),
+        ),
+    };
+}
+
+/// Mark in the body of an OSC sequence (None for other sequences)
+fn parse_body(body: &[u8]) -> Option<Mark> {
+    let body = std::str::from_utf8(body).ok()?;
+    let (code, rest) = body.split_once(';')?;
+    match code {
+        "7" => parse_cwd(rest).map(Mark::Cwd),
+        "133" => {
+            let mut fields = rest.split(';');
+            match fields.next()? {
+                "A" => Some(Mark::PromptStart),
+                "B" => Some(Mark::CommandStart),
+                "C" => Some(Mark::CommandExecuted),
+                "D" => Some(Mark::CommandFinished(
+                    fields.next().and_then(|code| code.parse().ok()),
+                )),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Path of a `file://host/path` URL (percent-decoded)
+fn parse_cwd(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let decoded = percent_decode(path)?;
+    (!decoded.is_empty()).then_some(decoded)
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// End of the body and of the whole sequence (BEL or ST = ESC \)
+fn terminator(data: &[u8]) -> Option<(usize, usize)> {
+    let end = data.iter().position(|b| *b == BEL || *b == ESC)?;
+    match data[end] {
+        BEL => Some((end, end + 1)),
+        _ => match data.get(end + 1) {
+            Some(b'\\') => Some((end, end + 2)),
+            // Another escape sequence interrupted this one: ignore it
+            Some(_) => Some((end, end)),
+            None => None,
+        },
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_marks() {
+        let mut scanner = Scanner::new();
+
+        // Prompt with cwd, then the command line
+        assert_eq!(
+            scanner.feed(
+                b"\x1b]7;file://web-01/home/deploy/my%20app\x07\x1b]133;A\x07$ \x1b]133;B\x07"
+            ),
+            vec![
+                Mark::Cwd("/home/deploy/my app".to_string()),
+                Mark::PromptStart,
+                Mark::CommandStart,
+            ]
+        );
+
+        // Split across chunks (even after the ESC), terminated by ST, with
+        // extra parameters; other OSC sequences are ignored
+        assert!(scanner.feed(b"ls\r\n\x1b").is_empty());
+        assert!(scanner.feed(b"]133;C\x1b").is_empty());
+        assert_eq!(
+            scanner.feed(b"\\\x1b]0;title\x07output\x1b]133;D;2;aid=1\x1b\\\x1b]133;D\x07"),
+            vec![
+                Mark::CommandExecuted,
+                Mark::CommandFinished(Some(2)),
+                Mark::CommandFinished(None),
+            ]
+        );
+
+        // Malformed URLs are ignored
+        assert!(scanner
+            .feed(b"\x1b]7;/tmp\x07\x1b]7;file://host/%zz\x07")
+            .is_empty());
+        assert!(scanner.pending.is_empty());
+    }
+
+    #[test]
+    fn test_tracker_times_commands() {
+        let mut tracker = Tracker::new();
+        let cwd = tracker.cwd();
+        let start = Instant::now();
+
+        // Empty command line: no events
+        assert!(tracker
+            .feed_at(b"\x1b]133;A\x07$ \x1b]133;D;0\x07", start)
+            .is_empty());
+
+        assert_eq!(
+            tracker.feed_at(b"\x1b]133;C\x07", start),
+            vec![Event::CommandStarted]
+        );
+        assert_eq!(
+            tracker.feed_at(
+                b"\x1b]133;D;1\x07\x1b]7;file:///srv\x07",
+                start + Duration::from_millis(1500)
+            ),
+            vec![Event::CommandFinished {
+                exit_code: Some(1),
+                duration: Duration::from_millis(1500),
+            }]
+        );
+        assert_eq!(cwd.lock().unwrap().as_deref(), Some("/srv"));
+
+        // Plain output is skipped without scanning
+        assert!(tracker.feed(b"hello\r\n").is_empty());
+    }
+}
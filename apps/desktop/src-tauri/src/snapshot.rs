@@ -0,0 +1,393 @@
+/**
+ * Session Snapshot Module
+ *
+ * Renders the backend terminal screen state to SVG/PNG using the active theme
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use crate::theme::{Theme, ThemeColors};
+
+/// Backend copy of the terminal screen, fed with all session output.
+/// Uses std::sync::Mutex because the local PTY reader runs in spawn_blocking.
+pub type ScreenState = Arc<StdMutex<vt100::Parser>>;
+
+/// Lines kept above the screen (exported with transcripts)
+pub const SCROLLBACK_LINES: usize = 5000;
+
+/// Create a screen state with the default PTY size (80x24)
+pub fn new_screen_state() -> ScreenState {
+    Arc::new(StdMutex::new(vt100::Parser::new(24, 80, SCROLLBACK_LINES)))
+}
+
+/// Plain-text transcript of the scrollback and the screen
+pub fn transcript(parser: &mut vt100::Parser) -> String {
+    let (rows, cols) = parser.screen().size();
+    parser.set_scrollback(usize::MAX);
+    let history = parser.screen().scrollback();
+
+    // The view scrolls back at most one screen height: grow the screen so the
+    // whole scrollback fits, read it, then restore the size
+    let mut lines = Vec::with_capacity(history + rows as usize);
+    if history > 0 {
+        parser.set_scrollback(0);
+        parser.set_size(rows.max(history as u16), cols);
+        parser.set_scrollback(history);
+        lines.extend(parser.screen().rows(0, cols).take(history));
+        parser.set_scrollback(0);
+        parser.set_size(rows, cols);
+    }
+    lines.extend(parser.screen().rows(0, cols));
+
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines
+        .iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of the line under the cursor (None on the alternate screen, e.g. in editors)
+pub fn cursor_line(screen: &ScreenState) -> Option<String> {
+    let parser = screen.lock().ok()?;
+    let screen = parser.screen();
+    if screen.alternate_screen() {
+        return None;
+    }
+    let (row, _) = screen.cursor_position();
+    let (_, cols) = screen.size();
+    screen.rows(0, cols).nth(row as usize)
+}
+
+/// Output format of a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    Svg,
+    Png,
+}
+
+impl SnapshotFormat {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "svg" => Ok(SnapshotFormat::Svg),
+            "png" => Ok(SnapshotFormat::Png),
+            _ => Err(anyhow!("Invalid snapshot format: {}", s)),
+        }
+    }
+
+    pub fn mime_type(&self) -> &str {
+        match self {
+            SnapshotFormat::Svg => "image/svg+xml",
+            SnapshotFormat::Png => "image/png",
+        }
+    }
+}
+
+/// Rendered snapshot returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSnapshot {
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Image bytes, base64-encoded
+    pub data: String,
+}
+
+/// Cell attributes shared by a run of consecutive cells
+#[derive(Debug, Clone, PartialEq)]
+struct RunStyle {
+    fg: String,
+    bg: Option<String>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+/// Render a screen to SVG. Returns the document and its size in pixels.
+pub fn render_svg(screen: &vt100::Screen, theme: &Theme) -> (String, u32, u32) {
+    let (rows, cols) = screen.size();
+    let font_size = theme.terminal.font_size as f32;
+    let cell_width = font_size * 0.6;
+    let cell_height = font_size * theme.terminal.line_height;
+    let width = (cell_width * cols as f32).ceil() as u32;
+    let height = (cell_height * rows as f32).ceil() as u32;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    );
+    let _ = write!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        escape_xml(&theme.colors.background)
+    );
+    let _ = write!(
+        svg,
+        r#"<g font-family="{}, monospace" font-size="{}" xml:space="preserve">"#,
+        escape_xml(&theme.terminal.font_family),
+        font_size
+    );
+
+    for row in 0..rows {
+        let y = row as f32 * cell_height;
+        let baseline = y + cell_height * 0.8;
+        let mut col = 0;
+
+        while col < cols {
+            let Some(cell) = screen.cell(row, col) else {
+                break;
+            };
+            let style = cell_style(cell, &theme.colors);
+            let start = col;
+            let mut text = String::new();
+
+            // Extend the run while consecutive cells share the same style
+            while col < cols {
+                let Some(cell) = screen.cell(row, col) else {
+                    break;
+                };
+                if cell_style(cell, &theme.colors) != style {
+                    break;
+                }
+                if !cell.is_wide_continuation() {
+                    let contents = cell.contents();
+                    if contents.is_empty() {
+                        text.push(' ');
+                    } else {
+                        text.push_str(&contents);
+                    }
+                }
+                col += 1;
+            }
+
+            let x = start as f32 * cell_width;
+            let run_width = (col - start) as f32 * cell_width;
+
+            if let Some(bg) = &style.bg {
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x,
+                    y,
+                    run_width,
+                    cell_height,
+                    escape_xml(bg)
+                );
+            }
+
+            if !text.trim().is_empty() {
+                let _ = write!(
+                    svg,
+                    r#"<text x="{}" y="{}" fill="{}""#,
+                    x,
+                    baseline,
+                    escape_xml(&style.fg)
+                );
+                if style.bold {
+                    svg.push_str(r#" font-weight="bold""#);
+                }
+                if style.italic {
+                    svg.push_str(r#" font-style="italic""#);
+                }
+                if style.underline {
+                    svg.push_str(r#" text-decoration="underline""#);
+                }
+                let _ = write!(svg, ">{}</text>", escape_xml(&text));
+            }
+        }
+    }
+
+    svg.push_str("</g>");
+
+    // Cursor (block outline)
+    if !screen.hide_cursor() {
+        let (cursor_row, cursor_col) = screen.cursor_position();
+        let _ = write!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}"/>"#,
+            cursor_col as f32 * cell_width,
+            cursor_row as f32 * cell_height,
+            cell_width,
+            cell_height,
+            escape_xml(&theme.colors.cursor)
+        );
+    }
+
+    svg.push_str("</svg>");
+    (svg, width, height)
+}
+
+/// Rasterize an SVG document to PNG bytes
+pub fn render_png(svg: &str) -> Result<Vec<u8>> {
+    let mut options = resvg::usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+
+    let tree = resvg::usvg::Tree::from_str(svg, &options)
+        .map_err(|e| anyhow!("Failed to parse snapshot SVG: {}", e))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| anyhow!("Invalid snapshot size"))?;
+
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::default(),
+        &mut pixmap.as_mut(),
+    );
+
+    pixmap
+        .encode_png()
+        .map_err(|e| anyhow!("Failed to encode PNG: {}", e))
+}
+
+/// Resolve the colors of a cell (inverse video swaps foreground and background)
+fn cell_style(cell: &vt100::Cell, colors: &ThemeColors) -> RunStyle {
+    let mut fg = resolve_color(cell.fgcolor(), colors, true, cell.bold());
+    let mut bg = match cell.bgcolor() {
+        vt100::Color::Default => None,
+        color => Some(resolve_color(color, colors, false, false)),
+    };
+
+    if cell.inverse() {
+        let old_fg = fg;
+        fg = bg.unwrap_or_else(|| colors.background.clone());
+        bg = Some(old_fg);
+    }
+
+    RunStyle {
+        fg,
+        bg,
+        bold: cell.bold(),
+        italic: cell.italic(),
+        underline: cell.underline(),
+    }
+}
+
+/// Map a vt100 color to a CSS color using the theme palette
+fn resolve_color(color: vt100::Color, colors: &ThemeColors, is_fg: bool, bold: bool) -> String {
+    match color {
+        vt100::Color::Default => {
+            if is_fg {
+                colors.foreground.clone()
+            } else {
+                colors.background.clone()
+            }
+        }
+        // Bold text uses the bright variant of the base ANSI colors
+        vt100::Color::Idx(idx) if idx < 8 && bold && is_fg => ansi_color(idx + 8, colors),
+        vt100::Color::Idx(idx) => ansi_color(idx, colors),
+        vt100::Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
+/// xterm 256-color palette, with the first 16 entries taken from the theme
+fn ansi_color(idx: u8, colors: &ThemeColors) -> String {
+    let named = match idx {
+        0 => &colors.black,
+        1 => &colors.red,
+        2 => &colors.green,
+        3 => &colors.yellow,
+        4 => &colors.blue,
+        5 => &colors.magenta,
+        6 => &colors.cyan,
+        7 => &colors.white,
+        8 => &colors.bright_black,
+        9 => &colors.bright_red,
+        10 => &colors.bright_green,
+        11 => &colors.bright_yellow,
+        12 => &colors.bright_blue,
+        13 => &colors.bright_magenta,
+        14 => &colors.bright_cyan,
+        15 => &colors.bright_white,
+        16..=231 => {
+            let idx = idx - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            return format!(
+                "#{:02x}{:02x}{:02x}",
+                level(idx / 36),
+                level((idx / 6) % 6),
+                level(idx % 6)
+            );
+        }
+        _ => {
+            let gray = 8 + (idx - 232) * 10;
+            return format!("#{:02x}{:02x}{:02x}", gray, gray, gray);
+        }
+    };
+    named.clone()
+}
+
+/// Escape text for inclusion in SVG attributes and content
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_format_parsing() {
+        assert_eq!(
+            SnapshotFormat::from_str("SVG").unwrap(),
+            SnapshotFormat::Svg
+        );
+        assert_eq!(
+            SnapshotFormat::from_str("png").unwrap(),
+            SnapshotFormat::Png
+        );
+        assert!(SnapshotFormat::from_str("gif").is_err());
+    }
+
+    #[test]
+    fn test_transcript_includes_scrollback() {
+        let mut parser = vt100::Parser::new(3, 20, 100);
+        for i in 1..=7 {
+            parser.process(format!("line {}\r\n", i).as_bytes());
+        }
+        parser.process(b"$ ");
+
+        let text = transcript(&mut parser);
+        let expected: Vec<String> = (1..=7).map(|i| format!("line {}", i)).collect();
+        assert_eq!(text, format!("{}\n$", expected.join("\n")));
+
+        // The screen is left as it was
+        assert_eq!(parser.screen().size(), (3, 20));
+        assert_eq!(parser.screen().scrollback(), 0);
+        assert_eq!(parser.screen().contents(), "line 6\nline 7\n$ ");
+        parser.process(b"ls");
+        assert_eq!(parser.screen().contents(), "line 6\nline 7\n$ ls");
+    }
+
+    #[test]
+    fn test_render_svg_contains_text_and_colors() {
+        let theme = crate::theme::load_theme("default".to_string()).unwrap();
+        let mut parser = vt100::Parser::new(4, 20, 0);
+        parser.process(b"hello \x1b[31m<red>\x1b[0m");
+
+        let (svg, width, height) = render_svg(parser.screen(), &theme);
+        assert!(width > 0 && height > 0);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("hello"));
+        assert!(svg.contains("&lt;red&gt;"));
+        assert!(svg.contains(&theme.colors.red));
+    }
+}
@@ -0,0 +1,159 @@
+/**
+ * Snippets Module
+ *
+ * Command snippets stored in the vault: `{{variable}}` placeholders in the
+ * command are filled in when the snippet is run, and the text is typed into
+ * one or more sessions (broadcast).
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::terminal::SessionManager;
+
+pub use rite_vault::{CreateSnippetInput, Snippet, UpdateSnippetInput};
+
+/// Result of running a snippet on one session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetDelivery {
+    pub session_id: String,
+    /// None when the text was sent
+    pub error: Option<String>,
+}
+
+/// Placeholder at `start` (the index of `{{`): name and end index
+fn placeholder(template: &str, start: usize) -> Option<(&str, usize)> {
+    let inner_start = start + 2;
+    let inner_len = template[inner_start..].find("}}")?;
+    let name = template[inner_start..inner_start + inner_len].trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    valid.then_some((name, inner_start + inner_len + 2))
+}
+
+/// Variable names of a command template, in order of first appearance
+///
+/// Braces that don't hold a variable name (`{{ }}`, `{{a b}}`) are plain text.
+pub fn variables(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = 0;
+    while let Some(offset) = template[rest..].find("{{") {
+        let start = rest + offset;
+        match placeholder(template, start) {
+            Some((name, end)) => {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+                rest = end;
+            }
+            None => rest = start + 2,
+        }
+    }
+    names
+}
+
+/// Fill in the variables of a command template
+pub fn render(template: &str, values: &HashMap<String, String>) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = 0;
+    while let Some(offset) = template[rest..].find("{{") {
+        let start = rest + offset;
+        match placeholder(template, start) {
+            Some((name, end)) => {
+                let value = values
+                    .get(name)
+                    .ok_or_else(|| anyhow!("No value for variable {}", name))?;
+                output.push_str(&template[rest..start]);
+                output.push_str(value);
+                rest = end;
+            }
+            None => {
+                output.push_str(&template[rest..start + 2]);
+                rest = start + 2;
+            }
+        }
+    }
+    output.push_str(&template[rest..]);
+    Ok(output)
+}
+
+/// Type a rendered command into sessions
+///
+/// Line breaks are sent as Enter (CR), like a paste in the terminal; with
+/// `execute` a final Enter runs the command. Every session is tried, the
+/// result lists which ones failed.
+pub async fn run(
+    sessions: &SessionManager,
+    command: &str,
+    session_ids: &[String],
+    execute: bool,
+) -> Vec<SnippetDelivery> {
+    let mut text = command.replace("\r\n", "\r").replace('\n', "\r");
+    if execute && !text.ends_with('\r') {
+        text.push('\r');
+    }
+
+    let mut deliveries = Vec::with_capacity(session_ids.len());
+    for session_id in session_ids {
+        let error = sessions
+            .send_input(session_id, text.clone().into_bytes())
+            .await
+            .err()
+            .map(|e| e.to_string());
+        if let Some(e) = &error {
+            tracing::warn!(
+                "[snippets.rs] Failed to send snippet to session {}: {}",
+                session_id,
+                e
+            );
+        }
+        deliveries.push(SnippetDelivery {
+            session_id: session_id.clone(),
+            error,
+        });
+    }
+    deliveries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(
+            variables("kubectl -n {{ns}} logs {{ pod }} --since={{since}} -n {{ns}}"),
+            vec!["ns", "pod", "since"]
+        );
+        // Not variables: empty, spaces inside, unterminated
+        assert!(variables("awk '{{ }}' {{a b}} {{open").is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render(
+            "ssh {{user}}@{{host}} 'echo {{user}}'",
+            &values(&[("user", "root"), ("host", "db1")]),
+        )
+        .unwrap();
+        assert_eq!(rendered, "ssh root@db1 'echo root'");
+
+        // Non-variable braces are kept as they are
+        assert_eq!(
+            render("echo {{ }} {{x}}", &values(&[("x", "1")])).unwrap(),
+            "echo {{ }} 1"
+        );
+
+        assert!(render("rm {{path}}", &values(&[])).is_err());
+    }
+}
@@ -1,18 +1,20 @@
 /**
  * SSH Config Parser Module
  *
- * Parses OpenSSH config files and converts them to Rite connections
+ * Parses OpenSSH config files into entries that can be imported as Rite
+ * connections (see `CreateConnectionInput::from`). Follows OpenSSH: Include
+ * files are read in place, `Host *` and Match blocks apply to every host
+ * they match and the first value found for an option wins. Free of
+ * app-internal dependencies so the fuzz targets can include it directly.
  */
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-
-use crate::connection::{AuthMethod, CreateConnectionInput};
+use std::path::{Path, PathBuf};
 
 /// Parsed SSH config entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SshConfigEntry {
     pub host: String,
@@ -21,64 +23,72 @@ pub struct SshConfigEntry {
     pub port: Option<u16>,
     pub identity_file: Option<String>,
     pub server_alive_interval: Option<u32>,
+    /// Hosts to go through (ProxyJump), first hop first; a hop's own
+    /// ProxyJump hops come before it
+    #[serde(default)]
+    pub proxy_jump: Vec<JumpHost>,
 }
 
-impl SshConfigEntry {
-    /// Convert to CreateConnectionInput (for import)
-    pub fn to_connection_input(&self) -> CreateConnectionInput {
-        let hostname = self.hostname.clone().unwrap_or_else(|| self.host.clone());
+/// ProxyJump hop, resolved through the config like a Host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JumpHost {
+    /// Host as written in ProxyJump
+    pub host: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+}
 
-        let username = self.user.clone().unwrap_or_else(|| "root".to_string());
+impl JumpHost {
+    /// Entry to import the hop as a connection of its own
+    pub fn to_entry(&self) -> SshConfigEntry {
+        SshConfigEntry {
+            host: self.host.clone(),
+            hostname: self.hostname.clone(),
+            user: self.user.clone(),
+            port: self.port,
+            identity_file: self.identity_file.clone(),
+            server_alive_interval: None,
+            proxy_jump: Vec::new(),
+        }
+    }
+}
 
-        let port = self.port.unwrap_or(22);
+/// Deepest Include nesting followed (as OpenSSH)
+const MAX_INCLUDE_DEPTH: usize = 16;
 
-        // Determine auth method
-        let auth_method = if let Some(identity_file) = &self.identity_file {
-            // Expand ~ to home directory
-            let key_path = expand_tilde(identity_file);
-            AuthMethod::PublicKey {
-                key_path,
-                passphrase: None, // User will be prompted if needed
-            }
-        } else {
-            // Default to password auth with empty password
-            AuthMethod::Password {
-                password: String::new(),
-            }
-        };
+/// Longest ProxyJump chain built for a host
+const MAX_JUMP_HOSTS: usize = 8;
 
-        // Convert server_alive_interval to seconds (if present)
-        let ssh_keep_alive_interval = self.server_alive_interval.map(|seconds| seconds as i64);
-
-        CreateConnectionInput {
-            name: self.host.clone(),
-            protocol: "ssh".to_string(),
-            hostname,
-            port,
-            username,
-            auth_method,
-            color: None,
-            icon: None,
-            folder: None,
-            notes: Some("Imported from SSH config".to_string()),
-            ssh_keep_alive_override: if ssh_keep_alive_interval.is_some() {
-                Some("enabled".to_string())
-            } else {
-                None
-            },
-            ssh_keep_alive_interval,
-        }
-    }
+/// Config line, with Include files read in place
+#[derive(Debug)]
+enum Item {
+    Host(Vec<String>),
+    Match(Vec<String>),
+    /// Lines of each included file
+    Include(Vec<Vec<Item>>),
+    Option(String, String),
 }
 
-/// Parse SSH config file
+/// Parse SSH config file (Include paths are relative to its folder)
 pub fn parse_ssh_config<P: AsRef<Path>>(config_path: P) -> Result<Vec<SshConfigEntry>> {
-    let content = fs::read_to_string(&config_path)
-        .with_context(|| format!("Failed to read SSH config file: {:?}", config_path.as_ref()))?;
+    let config_path = config_path.as_ref();
+    let content = fs::read_to_string(config_path)
+        .with_context(|| format!("Failed to read SSH config file: {:?}", config_path))?;
 
-    let mut entries = Vec::new();
-    let mut current_host: Option<String> = None;
-    let mut current_props: HashMap<String, String> = HashMap::new();
+    let items = parse_items(&content, config_path.parent(), 0);
+    Ok(build_entries(&items))
+}
+
+/// Parse SSH config content (Include directives are ignored)
+pub fn parse_ssh_config_str(content: &str) -> Vec<SshConfigEntry> {
+    build_entries(&parse_items(content, None, 0))
+}
+
+fn parse_items(content: &str, base_dir: Option<&Path>, depth: usize) -> Vec<Item> {
+    let mut items = Vec::new();
 
     for line in content.lines() {
         let line = line.trim();
@@ -88,81 +98,463 @@ pub fn parse_ssh_config<P: AsRef<Path>>(config_path: P) -> Result<Vec<SshConfigE
             continue;
         }
 
-        // Parse key-value pairs
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
+        // `Key value`, `Key=value` or `Key = value`
+        let key_end = line
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(line.len());
+        let key = line[..key_end].to_lowercase();
+        let rest = line[key_end..].trim_start();
+        let rest = rest.strip_prefix('=').unwrap_or(rest);
+        let args = split_args(rest);
+
+        match key.as_str() {
+            "host" => items.push(Item::Host(args)),
+            "match" => items.push(Item::Match(args)),
+            "include" => {
+                if let Some(base_dir) = base_dir.filter(|_| depth < MAX_INCLUDE_DEPTH) {
+                    let included = args
+                        .iter()
+                        .flat_map(|pattern| include_paths(base_dir, pattern))
+                        .filter_map(|path| {
+                            let content = fs::read_to_string(&path).ok()?;
+                            Some(parse_items(&content, Some(base_dir), depth + 1))
+                        })
+                        .collect();
+                    items.push(Item::Include(included));
+                }
+            }
+            _ if !args.is_empty() => items.push(Item::Option(key, args.join(" "))),
+            _ => {}
+        }
+    }
+
+    items
+}
+
+/// Split arguments on whitespace, keeping "quoted strings" together
+fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+    for c in s.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Files matched by an Include pattern (`*` and `?` in any path component)
+fn include_paths(base_dir: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = PathBuf::from(expand_tilde(pattern));
+    let pattern = if pattern.is_absolute() {
+        pattern
+    } else {
+        base_dir.join(pattern)
+    };
+
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let name = component.as_os_str().to_string_lossy();
+        if !name.contains(['*', '?']) {
+            paths.iter_mut().for_each(|path| path.push(component));
             continue;
         }
+        let mut matched = Vec::new();
+        for dir in &paths {
+            let read_from = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir.as_path()
+            };
+            let Ok(read_dir) = fs::read_dir(read_from) else {
+                continue;
+            };
+            let mut names: Vec<_> = read_dir
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|file| !file.starts_with('.') && wildcard_match(&name, file))
+                .collect();
+            // OpenSSH reads matches in lexical order
+            names.sort();
+            matched.extend(names.into_iter().map(|file| dir.join(file)));
+        }
+        paths = matched;
+    }
+    paths.retain(|path| path.is_file());
+    paths
+}
 
-        let key = parts[0].to_lowercase();
-        let value = parts[1..].join(" ");
+/// Build one entry per host alias named in a Host line
+fn build_entries(items: &[Item]) -> Vec<SshConfigEntry> {
+    let mut aliases = Vec::new();
+    collect_aliases(items, &mut aliases);
+
+    let mut resolver = JumpResolver {
+        items,
+        chains: HashMap::new(),
+        resolving: Vec::new(),
+    };
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let props = host_props(items, &alias);
+            let mut entry = build_entry(alias, &props);
+            entry.proxy_jump = resolver.chain(&entry, &props);
+            entry
+        })
+        .collect()
+}
 
-        match key.as_str() {
-            "host" => {
-                // Save previous host if exists
-                if let Some(host) = current_host.take() {
-                    if let Some(entry) = build_entry(host, &current_props) {
-                        entries.push(entry);
+/// Options applying to a host
+fn host_props(items: &[Item], alias: &str) -> HashMap<String, String> {
+    let mut props = HashMap::new();
+    let mut active = true;
+    apply_items(items, alias, &mut props, &mut active, false);
+    props
+}
+
+/// Builds ProxyJump chains, each hop resolved through the config (and
+/// its own ProxyJump followed)
+struct JumpResolver<'a> {
+    items: &'a [Item],
+    /// Chain to reach each host already resolved
+    chains: HashMap<String, Vec<JumpHost>>,
+    /// Hosts being resolved, to stop on loops
+    resolving: Vec<String>,
+}
+
+impl JumpResolver<'_> {
+    fn chain(&mut self, entry: &SshConfigEntry, props: &HashMap<String, String>) -> Vec<JumpHost> {
+        if let Some(chain) = self.chains.get(&entry.host) {
+            return chain.clone();
+        }
+        let Some(value) = props.get("proxyjump") else {
+            return Vec::new();
+        };
+        let tokens = Tokens::for_entry(entry);
+        let specs = parse_proxy_jump(&expand_tokens(value, &tokens));
+
+        self.resolving.push(entry.host.clone());
+        let mut chain = Vec::new();
+        for (user, host, port) in specs {
+            // A host reached through itself can't be imported
+            if chain.len() >= MAX_JUMP_HOSTS || self.resolving.contains(&host) {
+                break;
+            }
+            let hop_props = host_props(self.items, &host);
+            let hop = build_entry(host, &hop_props);
+            chain.extend(self.chain(&hop, &hop_props));
+            chain.push(JumpHost {
+                user: user.or(hop.user),
+                port: port.or(hop.port),
+                host: hop.host,
+                hostname: hop.hostname,
+                identity_file: hop.identity_file,
+            });
+        }
+        chain.truncate(MAX_JUMP_HOSTS);
+        self.resolving.pop();
+
+        self.chains.insert(entry.host.clone(), chain.clone());
+        chain
+    }
+}
+
+/// Hops of a ProxyJump value (`[user@]host[:port]` or
+/// `ssh://[user@]host[:port]`, comma-separated); `none` means no hops
+fn parse_proxy_jump(value: &str) -> Vec<(Option<String>, String, Option<u16>)> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .filter_map(|hop| {
+            let hop = hop.trim();
+            let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (user, address) = match hop.rsplit_once('@') {
+                Some((user, address)) => (Some(user.to_string()), address),
+                None => (None, hop),
+            };
+            // [IPv6]:port, host:port, or a bare IPv6 address
+            let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+                let (host, rest) = rest.split_once(']')?;
+                (host, rest.strip_prefix(':'))
+            } else {
+                match address.split_once(':') {
+                    Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                    _ => (address, None),
+                }
+            };
+            if host.is_empty() {
+                return None;
+            }
+            let port = port.and_then(|port| port.parse().ok());
+            Some((user.filter(|user| !user.is_empty()), host.to_string(), port))
+        })
+        .collect()
+}
+
+/// Concrete host names of Host lines (patterns and negations aren't hosts)
+fn collect_aliases(items: &[Item], aliases: &mut Vec<String>) {
+    for item in items {
+        match item {
+            Item::Host(patterns) => {
+                for pattern in patterns {
+                    if !pattern.contains(['*', '?', '!']) && !aliases.contains(pattern) {
+                        aliases.push(pattern.clone());
                     }
-                    current_props.clear();
                 }
-                current_host = Some(value);
             }
-            "hostname" => {
-                current_props.insert("hostname".to_string(), value);
+            Item::Include(files) => {
+                for items in files {
+                    collect_aliases(items, aliases);
+                }
             }
-            "user" => {
-                current_props.insert("user".to_string(), value);
+            _ => {}
+        }
+    }
+}
+
+/// Gather the options applying to `alias`, first value wins (as OpenSSH)
+fn apply_items(
+    items: &[Item],
+    alias: &str,
+    props: &mut HashMap<String, String>,
+    active: &mut bool,
+    never_match: bool,
+) {
+    for item in items {
+        match item {
+            Item::Host(patterns) => {
+                *active = !never_match && host_matches(patterns, alias);
             }
-            "port" => {
-                current_props.insert("port".to_string(), value);
+            Item::Match(criteria) => {
+                *active = !never_match && match_criteria(criteria, alias, props);
             }
-            "identityfile" => {
-                // Take first identity file only for MVP
-                current_props
-                    .entry("identityfile".to_string())
-                    .or_insert(value);
+            Item::Include(files) => {
+                // Included files only apply when the block around the
+                // Include does, each starts outside any block, and the
+                // block is back in effect afterwards
+                let outer = *active;
+                for items in files {
+                    *active = outer;
+                    apply_items(items, alias, props, active, never_match || !outer);
+                }
+                *active = outer;
+            }
+            Item::Option(key, value) => {
+                if *active {
+                    props.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Host line patterns: any pattern matches and no `!pattern` does
+fn host_matches<S: AsRef<str>>(patterns: &[S], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, host) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, host),
+        }
+    }
+    matched
+}
+
+/// Match line criteria, all of which must hold
+///
+/// `exec`, `localnetwork` and `tagged` are never considered matching: the
+/// import doesn't run commands or look at the local network.
+fn match_criteria(criteria: &[String], alias: &str, props: &HashMap<String, String>) -> bool {
+    let hostname = props
+        .get("hostname")
+        .map(|hostname| expand_tokens(hostname, &Tokens::for_hostname(alias)))
+        .unwrap_or_else(|| alias.to_string());
+    let local_user = local_user();
+    let user = props.get("user").unwrap_or(&local_user);
+
+    let mut criteria = criteria.iter();
+    let mut matched = true;
+    while let Some(criterion) = criteria.next() {
+        let (negated, criterion) = match criterion.strip_prefix('!') {
+            Some(criterion) => (true, criterion),
+            None => (false, criterion.as_str()),
+        };
+        let result = match criterion.to_lowercase().as_str() {
+            "all" | "canonical" | "final" => true,
+            keyword => {
+                let Some(list) = criteria.next() else {
+                    return false;
+                };
+                let patterns: Vec<&str> = list.split(',').collect();
+                match keyword {
+                    "host" => host_matches(&patterns, &hostname),
+                    "originalhost" => host_matches(&patterns, alias),
+                    "user" => host_matches(&patterns, user),
+                    "localuser" => host_matches(&patterns, &local_user),
+                    _ => false,
+                }
             }
-            "serveraliveinterval" => {
-                current_props.insert("serveraliveinterval".to_string(), value);
+        };
+        matched &= result != negated;
+    }
+    matched
+}
+
+/// `*` (any run) and `?` (one character) wildcards, case-insensitive
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
             }
-            _ => {
-                // Ignore other directives for MVP
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
             }
+            _ => match star {
+                // Let the last `*` take one more character
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
         }
     }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Values of the `%` tokens of a host entry
+struct Tokens {
+    alias: String,
+    hostname: String,
+    port: u16,
+    remote_user: String,
+    local_user: String,
+    home: String,
+}
 
-    // Save last host
-    if let Some(host) = current_host {
-        if let Some(entry) = build_entry(host, &current_props) {
-            entries.push(entry);
+impl Tokens {
+    /// Tokens of a resolved entry
+    fn for_entry(entry: &SshConfigEntry) -> Self {
+        Self {
+            hostname: entry.hostname.clone().unwrap_or_else(|| entry.host.clone()),
+            port: entry.port.unwrap_or(22),
+            remote_user: entry.user.clone().unwrap_or_else(local_user),
+            ..Self::for_hostname(&entry.host)
         }
     }
 
-    Ok(entries)
+    /// Tokens available in HostName (`%h` is the alias there)
+    fn for_hostname(alias: &str) -> Self {
+        Self {
+            alias: alias.to_string(),
+            hostname: alias.to_string(),
+            port: 22,
+            remote_user: String::new(),
+            local_user: local_user(),
+            home: home_dir(),
+        }
+    }
 }
 
-/// Build SshConfigEntry from parsed properties
-fn build_entry(host: String, props: &HashMap<String, String>) -> Option<SshConfigEntry> {
-    // Skip wildcard hosts for MVP
-    if host.contains('*') || host.contains('?') {
-        return None;
+/// Expand `%` tokens (`%%`, `%d`, `%h`, `%n`, `%p`, `%r`, `%u`); others
+/// are kept as written
+fn expand_tokens(value: &str, tokens: &Tokens) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('d') => expanded.push_str(&tokens.home),
+            Some('h') => expanded.push_str(&tokens.hostname),
+            Some('n') => expanded.push_str(&tokens.alias),
+            Some('p') => expanded.push_str(&tokens.port.to_string()),
+            Some('r') => expanded.push_str(&tokens.remote_user),
+            Some('u') => expanded.push_str(&tokens.local_user),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
     }
+    expanded
+}
+
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn home_dir() -> String {
+    std::env::var_os("HOME")
+        .map(|home| home.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "~".to_string())
+}
 
-    Some(SshConfigEntry {
-        host: host.clone(),
-        hostname: props.get("hostname").cloned(),
-        user: props.get("user").cloned(),
-        port: props.get("port").and_then(|p| p.parse::<u16>().ok()),
-        identity_file: props.get("identityfile").cloned(),
+/// Build SshConfigEntry from the options applying to a host
+fn build_entry(host: String, props: &HashMap<String, String>) -> SshConfigEntry {
+    let hostname = props
+        .get("hostname")
+        .map(|hostname| expand_tokens(hostname, &Tokens::for_hostname(&host)));
+    let port = props.get("port").and_then(|p| p.parse::<u16>().ok());
+    let user = props.get("user").cloned();
+
+    let mut entry = SshConfigEntry {
+        host,
+        hostname,
+        user,
+        port,
+        identity_file: None,
         server_alive_interval: props
             .get("serveraliveinterval")
             .and_then(|s| s.parse::<u32>().ok()),
-    })
+        proxy_jump: Vec::new(),
+    };
+    // OpenSSH tries every IdentityFile; the first one is imported
+    entry.identity_file = props
+        .get("identityfile")
+        .map(|path| expand_tokens(path, &Tokens::for_entry(&entry)));
+    entry
 }
 
 /// Expand ~ to home directory
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if path.starts_with("~/") {
         if let Some(home) = std::env::var_os("HOME") {
             return path.replacen("~", &home.to_string_lossy(), 1);
@@ -245,4 +637,148 @@ Host myserver
 
         fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn test_host_defaults_and_first_value_wins() {
+        let config = r#"
+Host web db
+    User deploy
+
+Host db
+    User postgres
+    Port 5432
+
+Host * !db
+    Port 2222
+    IdentityFile %d/.ssh/%r@%h-%p
+
+Host *
+    User nobody
+    ServerAliveInterval 30
+"#;
+
+        let entries = parse_ssh_config_str(config);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].host, "web");
+        assert_eq!(entries[0].user.as_deref(), Some("deploy"));
+        assert_eq!(entries[0].port, Some(2222));
+        assert_eq!(entries[0].server_alive_interval, Some(30));
+        assert_eq!(
+            entries[0].identity_file,
+            Some(format!("{}/.ssh/deploy@web-2222", home_dir()))
+        );
+        assert_eq!(entries[1].host, "db");
+        assert_eq!(entries[1].user.as_deref(), Some("deploy"));
+        assert_eq!(entries[1].port, Some(5432));
+        assert_eq!(entries[1].identity_file, None);
+    }
+
+    #[test]
+    fn test_match_blocks_and_tokens() {
+        let config = r#"
+Host bastion
+    HostName=%h.example.com
+
+Host app
+    HostName "10.0.0.7"
+
+Match host *.example.com !user admin
+    User ops
+    Port = 2200
+
+Match originalhost app exec "true"
+    User never
+
+Match all
+    IdentityFile ~/.ssh/%n_key
+"#;
+
+        let entries = parse_ssh_config_str(config);
+        assert_eq!(entries[0].hostname.as_deref(), Some("bastion.example.com"));
+        assert_eq!(entries[0].user.as_deref(), Some("ops"));
+        assert_eq!(entries[0].port, Some(2200));
+        assert_eq!(
+            entries[0].identity_file.as_deref(),
+            Some("~/.ssh/bastion_key")
+        );
+        assert_eq!(entries[1].hostname.as_deref(), Some("10.0.0.7"));
+        assert_eq!(entries[1].user, None);
+        assert_eq!(entries[1].port, None);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_d = dir.path().join("config.d");
+        fs::create_dir(&config_d).unwrap();
+        fs::write(
+            config_d.join("10-work.conf"),
+            "Host work\n    HostName work.example.com\n",
+        )
+        .unwrap();
+        // Each included file starts outside the previous file's Host block
+        fs::write(config_d.join("20-defaults.conf"), "User me\n").unwrap();
+        fs::write(
+            dir.path().join("web.conf"),
+            "Port 8022\nHost ignored\n    User other\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("config"),
+            "Include config.d/*.conf\n\nHost web\n    Include web.conf\n    IdentityFile ~/.ssh/web\n",
+        )
+        .unwrap();
+
+        let entries = parse_ssh_config(dir.path().join("config")).unwrap();
+        let hosts: Vec<_> = entries.iter().map(|e| e.host.as_str()).collect();
+        assert_eq!(hosts, ["work", "web", "ignored"]);
+        assert_eq!(entries[0].hostname.as_deref(), Some("work.example.com"));
+        assert_eq!(entries[0].user.as_deref(), Some("me"));
+        assert_eq!(entries[1].user.as_deref(), Some("me"));
+        assert_eq!(entries[1].port, Some(8022));
+        assert_eq!(entries[1].identity_file.as_deref(), Some("~/.ssh/web"));
+        // web.conf only applies within `Host web`
+        assert_eq!(entries[2].user.as_deref(), Some("me"));
+        assert_eq!(entries[2].port, None);
+    }
+
+    #[test]
+    fn test_proxy_jump() {
+        let config = r#"
+Host outer
+    HostName gw.example.com
+    User gate
+
+Host bastion
+    HostName 10.0.0.1
+    ProxyJump outer
+
+Host app
+    HostName app.internal
+    ProxyJump ops@bastion:2222,ssh://[fd00::5]:22
+
+Host loop
+    ProxyJump loop
+
+Host direct
+    ProxyJump none
+"#;
+
+        let entries = parse_ssh_config_str(config);
+        let app = &entries[2];
+        let hops: Vec<_> = app.proxy_jump.iter().map(|hop| hop.host.as_str()).collect();
+        assert_eq!(hops, ["outer", "bastion", "fd00::5"]);
+        assert_eq!(
+            app.proxy_jump[0].hostname.as_deref(),
+            Some("gw.example.com")
+        );
+        assert_eq!(app.proxy_jump[0].user.as_deref(), Some("gate"));
+        assert_eq!(app.proxy_jump[1].hostname.as_deref(), Some("10.0.0.1"));
+        assert_eq!(app.proxy_jump[1].user.as_deref(), Some("ops"));
+        assert_eq!(app.proxy_jump[1].port, Some(2222));
+        assert_eq!(app.proxy_jump[2].port, Some(22));
+        assert_eq!(entries[1].proxy_jump.len(), 1);
+        assert!(entries[3].proxy_jump.is_empty());
+        assert!(entries[4].proxy_jump.is_empty());
+    }
 }
@@ -0,0 +1,397 @@
+/**
+ * SSH Config Sync
+ *
+ * Keeps the connections imported from ~/.ssh/config in step with the file.
+ * When sync is enabled, a background task reads the config again (with its
+ * Include files) and emits `ssh-config-changed` with the hosts to add,
+ * update or remove. Nothing changes in the vault until the user applies
+ * the suggestions.
+ *
+ * Imported connections are linked to their Host alias along with the entry
+ * as last imported, so edits made in Rite are not reported as changes to
+ * the config.
+ */
+use anyhow::{anyhow, Result};
+use rite_vault::Database;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::Duration;
+
+use crate::connection::{AuthMethod, ConnectionInfo, CreateConnectionInput, UpdateConnectionInput};
+use crate::connections_manager::ConnectionsManager;
+use crate::ssh_config::SshConfigEntry;
+
+/// Setting: "true" to watch the SSH config
+pub const SETTING_ENABLED: &str = "ssh_config_sync";
+
+/// Setting: imported hosts and hosts already reported (JSON)
+pub const SETTING_STATE: &str = "ssh_config_sync_state";
+
+/// Event emitted with the suggestions when they change
+pub const EVENT_CHANGED: &str = "ssh-config-changed";
+
+/// How often the config is read again
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Change to bring the vault in line with the SSH config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncAction {
+    /// New host in the config
+    Add,
+    /// Imported host changed in the config
+    Update,
+    /// Imported host removed from the config
+    Remove,
+}
+
+/// Suggested change, applied or dismissed by the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSuggestion {
+    pub action: SyncAction,
+    /// Host alias in the config
+    pub host: String,
+    /// Linked connection (update and remove)
+    pub connection_id: Option<String>,
+    /// Entry as now in the config (add and update)
+    pub entry: Option<SshConfigEntry>,
+}
+
+/// What the vault knows of the config (stored in `SETTING_STATE`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    /// Hosts of the config when sync first ran: only hosts added after
+    /// that are suggested
+    initialized: bool,
+    /// Imported connections by Host alias
+    links: BTreeMap<String, Link>,
+    /// Hosts already imported or dismissed
+    known: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Link {
+    connection_id: String,
+    /// Entry as last imported or dismissed
+    entry: SshConfigEntry,
+}
+
+impl SyncState {
+    async fn load(db: &Database) -> Result<Self> {
+        match db.get_setting(SETTING_STATE).await? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    async fn save(&self, db: &Database) -> Result<()> {
+        db.set_setting(SETTING_STATE, &serde_json::to_string(self)?)
+            .await
+    }
+
+    fn link(&mut self, entry: &SshConfigEntry, connection_id: &str) {
+        self.known.insert(entry.host.clone());
+        self.links.insert(
+            entry.host.clone(),
+            Link {
+                connection_id: connection_id.to_string(),
+                entry: entry.clone(),
+            },
+        );
+    }
+
+    /// Suggestions for the config entries, given the saved connection IDs
+    fn diff(&self, entries: &[SshConfigEntry], saved: &HashSet<String>) -> Vec<SyncSuggestion> {
+        let mut suggestions = Vec::new();
+
+        for entry in entries {
+            match self.links.get(&entry.host) {
+                // Connections deleted in Rite are left alone
+                Some(link) if link.entry != *entry && saved.contains(&link.connection_id) => {
+                    suggestions.push(SyncSuggestion {
+                        action: SyncAction::Update,
+                        host: entry.host.clone(),
+                        connection_id: Some(link.connection_id.clone()),
+                        entry: Some(entry.clone()),
+                    });
+                }
+                None if !self.known.contains(&entry.host) => {
+                    suggestions.push(SyncSuggestion {
+                        action: SyncAction::Add,
+                        host: entry.host.clone(),
+                        connection_id: None,
+                        entry: Some(entry.clone()),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for (host, link) in &self.links {
+            if saved.contains(&link.connection_id) && !entries.iter().any(|e| e.host == *host) {
+                suggestions.push(SyncSuggestion {
+                    action: SyncAction::Remove,
+                    host: host.clone(),
+                    connection_id: Some(link.connection_id.clone()),
+                    entry: None,
+                });
+            }
+        }
+
+        suggestions
+    }
+}
+
+/// Whether sync is enabled (false if the vault can't be read, e.g. locked)
+pub async fn is_enabled(db: &Database) -> bool {
+    matches!(db.get_setting(SETTING_ENABLED).await, Ok(Some(value)) if value == "true")
+}
+
+/// Changes between the SSH config and the connections imported from it
+pub async fn suggestions(
+    db: &Database,
+    connections: &ConnectionsManager,
+) -> Result<Vec<SyncSuggestion>> {
+    let entries =
+        crate::ssh_config::parse_ssh_config(crate::ssh_config::get_default_ssh_config_path())?;
+    let mut state = SyncState::load(db).await?;
+
+    // The hosts already there were offered by the import dialog
+    if !state.initialized {
+        state.initialized = true;
+        state.known.extend(entries.iter().map(|e| e.host.clone()));
+        state.save(db).await?;
+    }
+
+    let saved: HashSet<String> = connections
+        .get_all_connections()
+        .await?
+        .into_iter()
+        .map(|info| info.id)
+        .collect();
+    Ok(state.diff(&entries, &saved))
+}
+
+/// Link connections imported from the SSH config to their hosts
+pub async fn record_import(
+    db: &Database,
+    entries: &[SshConfigEntry],
+    imported: &[ConnectionInfo],
+) -> Result<()> {
+    let mut state = SyncState::load(db).await?;
+    for entry in entries {
+        if let Some(info) = imported.iter().find(|info| info.name == entry.host) {
+            state.link(entry, &info.id);
+        }
+    }
+    state.save(db).await
+}
+
+/// Apply suggestions to the vault
+///
+/// Stops at the first failure; the suggestions applied before it are kept.
+pub async fn apply(
+    db: &Database,
+    connections: &ConnectionsManager,
+    suggestions: &[SyncSuggestion],
+) -> Result<()> {
+    let mut state = SyncState::load(db).await?;
+    let mut result = Ok(());
+    for suggestion in suggestions {
+        result = apply_one(&mut state, connections, suggestion).await;
+        if result.is_err() {
+            break;
+        }
+    }
+    state.save(db).await?;
+    result
+}
+
+async fn apply_one(
+    state: &mut SyncState,
+    connections: &ConnectionsManager,
+    suggestion: &SyncSuggestion,
+) -> Result<()> {
+    match suggestion.action {
+        SyncAction::Add => {
+            let entry = suggestion_entry(suggestion)?;
+            let imported = connections
+                .import_ssh_config(std::slice::from_ref(entry))
+                .await?;
+            let info = imported
+                .iter()
+                .rfind(|info| info.name == entry.host)
+                .ok_or_else(|| anyhow!("Failed to import {}", entry.host))?;
+            state.link(entry, &info.id);
+        }
+        SyncAction::Update => {
+            let entry = suggestion_entry(suggestion)?;
+            let link = state
+                .links
+                .get_mut(&suggestion.host)
+                .ok_or_else(|| anyhow!("{} was not imported", suggestion.host))?;
+            let info = connections
+                .get_all_connections()
+                .await?
+                .into_iter()
+                .find(|info| info.id == link.connection_id)
+                .ok_or_else(|| anyhow!("Connection of {} not found", suggestion.host))?;
+            connections
+                .update_connection(update_input(&info, &link.entry, entry))
+                .await?;
+            link.entry = entry.clone();
+        }
+        SyncAction::Remove => {
+            if let Some(link) = state.links.remove(&suggestion.host) {
+                connections.delete_connection(&link.connection_id).await?;
+            }
+            state.known.remove(&suggestion.host);
+        }
+    }
+    Ok(())
+}
+
+/// Dismiss suggestions: the config as it is now becomes the reference
+pub async fn dismiss(db: &Database, suggestions: &[SyncSuggestion]) -> Result<()> {
+    let mut state = SyncState::load(db).await?;
+    for suggestion in suggestions {
+        match suggestion.action {
+            SyncAction::Add => {
+                state.known.insert(suggestion.host.clone());
+            }
+            SyncAction::Update => {
+                let entry = suggestion_entry(suggestion)?;
+                if let Some(link) = state.links.get_mut(&suggestion.host) {
+                    link.entry = entry.clone();
+                }
+            }
+            SyncAction::Remove => {
+                state.links.remove(&suggestion.host);
+            }
+        }
+    }
+    state.save(db).await
+}
+
+fn suggestion_entry(suggestion: &SyncSuggestion) -> Result<&SshConfigEntry> {
+    suggestion
+        .entry
+        .as_ref()
+        .ok_or_else(|| anyhow!("Missing config entry for {}", suggestion.host))
+}
+
+/// Update of the fields that changed in the config since the import
+///
+/// Jump hosts and the rest of the connection are left as they are.
+fn update_input(
+    info: &ConnectionInfo,
+    before: &SshConfigEntry,
+    after: &SshConfigEntry,
+) -> UpdateConnectionInput {
+    let before = CreateConnectionInput::from(before);
+    let after = CreateConnectionInput::from(after);
+    let key_path = |auth: &AuthMethod| match auth {
+        AuthMethod::PublicKey { key_path, .. } => Some(key_path.clone()),
+        _ => None,
+    };
+    let new_key = key_path(&after.auth_method);
+
+    UpdateConnectionInput {
+        id: info.id.clone(),
+        name: None,
+        protocol: None,
+        hostname: (before.hostname != after.hostname).then(|| after.hostname.clone()),
+        port: (before.port != after.port).then_some(after.port),
+        username: (before.username != after.username).then(|| after.username.clone()),
+        auth_method: match new_key {
+            Some(_) if new_key != key_path(&before.auth_method) => Some(after.auth_method),
+            _ => None,
+        },
+        proxy_password: None,
+        color: None,
+        icon: None,
+        folder_id: None,
+        folder: None,
+        notes: None,
+        tags: None,
+        ssh_keep_alive_override: (before.ssh_keep_alive_interval != after.ssh_keep_alive_interval)
+            .then(|| after.ssh_keep_alive_override.clone()),
+        ssh_keep_alive_interval: (before.ssh_keep_alive_interval != after.ssh_keep_alive_interval)
+            .then_some(after.ssh_keep_alive_interval),
+        session_options: None,
+        revision: info.revision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(host: &str, port: u16) -> SshConfigEntry {
+        SshConfigEntry {
+            host: host.to_string(),
+            hostname: Some(format!("{}.example.com", host)),
+            user: Some("deploy".to_string()),
+            port: Some(port),
+            identity_file: None,
+            server_alive_interval: None,
+            proxy_jump: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut state = SyncState::default();
+        state.link(&entry("web", 22), "id-web");
+        state.link(&entry("db", 22), "id-db");
+        state.link(&entry("old", 22), "id-old");
+        state.link(&entry("deleted", 22), "id-deleted");
+        state.known.insert("ignored".to_string());
+        let saved: HashSet<String> = ["id-web", "id-db", "id-old"].map(String::from).into();
+
+        let entries = [
+            entry("web", 22),
+            entry("db", 5432),
+            entry("new", 22),
+            entry("ignored", 22),
+            entry("deleted", 2222),
+        ];
+        let suggestions = state.diff(&entries, &saved);
+        let actions: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.action, s.host.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                (SyncAction::Update, "db"),
+                (SyncAction::Add, "new"),
+                (SyncAction::Remove, "old"),
+            ]
+        );
+        assert_eq!(suggestions[0].connection_id.as_deref(), Some("id-db"));
+        assert_eq!(suggestions[0].entry.as_ref().unwrap().port, Some(5432));
+    }
+
+    #[test]
+    fn test_update_input() {
+        let info = CreateConnectionInput::from(&entry("db", 22));
+        let connection = crate::connection::Connection::new(info).unwrap();
+        let info = connection.to_info();
+
+        let mut after = entry("db", 5432);
+        after.identity_file = Some("/keys/db".to_string());
+        let update = update_input(&info, &entry("db", 22), &after);
+        assert_eq!(update.port, Some(5432));
+        assert_eq!(update.hostname, None);
+        assert_eq!(update.username, None);
+        assert!(matches!(
+            update.auth_method,
+            Some(AuthMethod::PublicKey { ref key_path, .. }) if key_path == "/keys/db"
+        ));
+        assert_eq!(update.revision, info.revision);
+    }
+}
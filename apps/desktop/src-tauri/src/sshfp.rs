@@ -0,0 +1,242 @@
+/**
+ * SSHFP Module
+ *
+ * Optional host key verification against SSHFP DNS records (RFC 4255),
+ * validated with DNSSEC where the zone is signed
+ */
+use anyhow::{anyhow, Result};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::system_conf::read_system_conf;
+use hickory_resolver::TokioAsyncResolver;
+use russh::keys::{Algorithm, PublicKey};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+
+/// Timeout for a single SSHFP lookup
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Resolvers kept for every lookup (and their cache), built on first use
+static RESOLVERS: StdMutex<Option<Resolvers>> = StdMutex::new(None);
+
+/// Validating and plain resolvers using the system's DNS configuration
+#[derive(Clone)]
+struct Resolvers {
+    validating: TokioAsyncResolver,
+    plain: TokioAsyncResolver,
+}
+
+impl Resolvers {
+    fn from_system_conf() -> Result<Self> {
+        let (config, opts) = read_system_conf()
+            .map_err(|e| anyhow!("Failed to read the system DNS configuration: {}", e))?;
+        let resolver = |validate: bool| {
+            let mut opts = opts.clone();
+            opts.validate = validate;
+            opts.timeout = LOOKUP_TIMEOUT;
+            opts.attempts = 1;
+            TokioAsyncResolver::tokio(config.clone(), opts)
+        };
+        Ok(Self {
+            validating: resolver(true),
+            plain: resolver(false),
+        })
+    }
+}
+
+/// Resolver for a lookup (a failed read of the configuration is retried
+/// by the next lookup)
+fn resolver(validate: bool) -> Result<TokioAsyncResolver> {
+    let mut resolvers = RESOLVERS
+        .lock()
+        .map_err(|_| anyhow!("Resolver lock poisoned"))?;
+    let resolvers = match &mut *resolvers {
+        Some(resolvers) => resolvers,
+        empty => empty.insert(Resolvers::from_system_conf()?),
+    };
+    Ok(if validate {
+        resolvers.validating.clone()
+    } else {
+        resolvers.plain.clone()
+    })
+}
+
+/// SSHFP record (algorithm and fingerprint type numbers from RFC 4255/6594/7479)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshfpRecord {
+    pub algorithm: u8,
+    pub fingerprint_type: u8,
+    pub fingerprint: Vec<u8>,
+}
+
+/// Result of SSHFP verification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum DnsVerification {
+    /// A DNSSEC-validated SSHFP record matches the key
+    Verified,
+    /// An SSHFP record matches, but the zone is not DNSSEC-signed
+    Insecure,
+    /// SSHFP records exist but none match the key
+    Mismatch,
+    /// No SSHFP records for this host
+    NoRecords,
+    /// The lookup failed
+    Error { message: String },
+}
+
+impl DnsVerification {
+    /// Whether the key may be accepted without asking the user
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified)
+    }
+}
+
+/// Verify a server's host key against its SSHFP records
+pub async fn verify(host: &str, public_key: &PublicKey) -> DnsVerification {
+    // SSHFP records only exist for host names
+    if host.parse::<IpAddr>().is_ok() {
+        return DnsVerification::NoRecords;
+    }
+
+    let (records, validated) = match lookup(host).await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("[sshfp.rs] SSHFP lookup for {} failed: {}", host, e);
+            return DnsVerification::Error {
+                message: e.to_string(),
+            };
+        }
+    };
+
+    let result = if records.is_empty() {
+        DnsVerification::NoRecords
+    } else if !matches_key(&records, public_key) {
+        DnsVerification::Mismatch
+    } else if validated {
+        DnsVerification::Verified
+    } else {
+        DnsVerification::Insecure
+    };
+
+    tracing::info!("[sshfp.rs] SSHFP verification for {}: {:?}", host, result);
+    result
+}
+
+/// Look up SSHFP records, returning whether DNSSEC validated them
+///
+/// A validating lookup is tried first; if it fails (unsigned zone or
+/// resolver without DNSSEC support) the records are fetched unvalidated.
+async fn lookup(host: &str) -> Result<(Vec<SshfpRecord>, bool)> {
+    let name = format!("{}.", host.trim_end_matches('.'));
+
+    match query(&name, true).await {
+        Ok(records) => Ok((records, true)),
+        Err(e) => {
+            tracing::debug!(
+                "[sshfp.rs] DNSSEC-validated lookup for {} failed ({}), retrying unvalidated",
+                host,
+                e
+            );
+            Ok((query(&name, false).await?, false))
+        }
+    }
+}
+
+async fn query(name: &str, validate: bool) -> Result<Vec<SshfpRecord>> {
+    let resolver = resolver(validate)?;
+    let lookup = match resolver.lookup(name, RecordType::SSHFP).await {
+        Ok(lookup) => lookup,
+        Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+            return Ok(Vec::new())
+        }
+        Err(e) => return Err(anyhow!(e)),
+    };
+
+    Ok(lookup
+        .iter()
+        .filter_map(|rdata| match rdata {
+            RData::SSHFP(sshfp) => Some(SshfpRecord {
+                algorithm: sshfp.algorithm().into(),
+                fingerprint_type: sshfp.fingerprint_type().into(),
+                fingerprint: sshfp.fingerprint().to_vec(),
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Check whether any SSHFP record matches the key
+pub fn matches_key(records: &[SshfpRecord], public_key: &PublicKey) -> bool {
+    let Some(algorithm) = algorithm_number(public_key) else {
+        return false;
+    };
+    let Ok(blob) = public_key.to_bytes() else {
+        return false;
+    };
+
+    records
+        .iter()
+        .filter(|record| record.algorithm == algorithm)
+        .any(|record| match record.fingerprint_type {
+            1 => Sha1::digest(&blob).as_slice() == record.fingerprint.as_slice(),
+            2 => Sha256::digest(&blob).as_slice() == record.fingerprint.as_slice(),
+            _ => false,
+        })
+}
+
+/// SSHFP algorithm number for a key type
+fn algorithm_number(public_key: &PublicKey) -> Option<u8> {
+    match public_key.algorithm() {
+        Algorithm::Rsa { .. } => Some(1),
+        Algorithm::Dsa => Some(2),
+        Algorithm::Ecdsa { .. } => Some(3),
+        Algorithm::Ed25519 => Some(4),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_PUBLIC: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMGPs4ghb2L6+g07e4VpkxvLvaTKuABI+2Bo4RXHu+WW";
+
+    fn record(algorithm: u8, fingerprint_type: u8, hex: &str) -> SshfpRecord {
+        SshfpRecord {
+            algorithm,
+            fingerprint_type,
+            fingerprint: (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_matches_key() {
+        let key = PublicKey::from_openssh(ED25519_PUBLIC).unwrap();
+
+        // Records as printed by `ssh-keygen -r`
+        let sha1 = record(4, 1, "6e6a36a6dee6dc71aa301130767938ffbd4a3d73");
+        let sha256 = record(
+            4,
+            2,
+            "09d3491c83ca47f7b55a1558f99aea82c24b766465f3855c4fcd82224d2babfd",
+        );
+        assert!(matches_key(&[sha1], &key));
+        assert!(matches_key(std::slice::from_ref(&sha256), &key));
+
+        // Wrong algorithm or fingerprint
+        let rsa = record(1, 2, &"09".repeat(32));
+        let other = record(4, 2, &"00".repeat(32));
+        assert!(!matches_key(&[rsa, other.clone()], &key));
+        assert!(matches_key(&[other, sha256], &key));
+        assert!(!matches_key(&[], &key));
+    }
+}
@@ -1,11 +1,22 @@
 /// Application state
 ///
 /// Manages global state across Tauri commands
-use crate::auth::AuthManager;
+use crate::auth_prompt::AuthPrompts;
+use crate::clipboard::SecretClipboard;
 use crate::connections_manager::ConnectionsManager;
-use crate::db::Database;
+use crate::events::VaultEvents;
+use crate::monitoring::HostMonitor;
+use crate::pending_host_keys::PendingHostKeysManager;
+use crate::policy::Policy;
+use crate::port_forward::PortForwardManager;
+use crate::remote_tasks::RemoteTaskRunner;
+use crate::session_restore::SessionRestore;
+use crate::share::ShareManager;
+use crate::tasks::TaskSupervisor;
 use crate::terminal::SessionManager;
-use anyhow::Result;
+use crate::transfers::TransferManager;
+use anyhow::{Context, Result};
+use rite_vault::{AuthManager, Database, Vault};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -21,6 +32,42 @@ pub struct AppState {
 
     /// Database connection
     pub db: Database,
+
+    /// Organization policy (default = unrestricted)
+    pub policy: Arc<Policy>,
+
+    /// Secrets copied to the clipboard (cleared on timeout and lock)
+    pub clipboard: SecretClipboard,
+
+    /// Change notifications for vault data (attached to the app in setup)
+    pub events: VaultEvents,
+
+    /// Background jobs (listed and restarted from the frontend)
+    pub tasks: TaskSupervisor,
+
+    /// Read-only live shares of terminal sessions
+    pub shares: ShareManager,
+
+    /// Port forwards of SSH sessions
+    pub forwards: PortForwardManager,
+
+    /// SFTP upload/download queue
+    pub transfers: TransferManager,
+
+    /// Keyboard-interactive prompts waiting for the user
+    pub auth_prompts: AuthPrompts,
+
+    /// Host keys of rejected connections, waiting for the user to accept
+    pub pending_host_keys: PendingHostKeysManager,
+
+    /// Terminals of the previous run and the ones to reopen next time
+    pub restore: SessionRestore,
+
+    /// Remote task runs in progress
+    pub remote_tasks: RemoteTaskRunner,
+
+    /// Latest host metrics and the connections monitored periodically
+    pub monitor: HostMonitor,
 }
 
 impl AppState {
@@ -32,11 +79,24 @@ impl AppState {
         // Initialize database
         let db = Database::new(&db_path).await?;
 
-        // Initialize auth manager
-        let auth = Arc::new(AuthManager::new(db.clone()));
+        // Load and enforce the organization policy before anything reads settings
+        // (an encrypted vault can't be written until it is unlocked)
+        let policy = Arc::new(Policy::load()?);
+        if !db.is_encrypted() {
+            policy.apply(&db).await?;
+        }
+
+        // Initialize vault (auth + connection storage)
+        let vault = Vault::from_database(db.clone());
+        let auth = Arc::new(vault.auth().clone());
 
         // Initialize connections manager
-        let connections = Arc::new(ConnectionsManager::new(db.clone(), auth.as_ref().clone()));
+        let events = VaultEvents::new();
+        let connections = Arc::new(ConnectionsManager::new(
+            vault,
+            Arc::clone(&policy),
+            events.clone(),
+        ));
 
         // Initialize session manager
         let sessions = Arc::new(SessionManager::new(db.clone(), auth.as_ref().clone()));
@@ -46,9 +106,34 @@ impl AppState {
             connections,
             sessions,
             db,
+            policy,
+            clipboard: SecretClipboard::new(),
+            events,
+            tasks: TaskSupervisor::new(),
+            shares: ShareManager::new(),
+            forwards: PortForwardManager::new(),
+            transfers: TransferManager::new(),
+            auth_prompts: AuthPrompts::new(),
+            pending_host_keys: PendingHostKeysManager::new(),
+            restore: SessionRestore::load(),
+            remote_tasks: RemoteTaskRunner::new(),
+            monitor: HostMonitor::new(),
         })
     }
 
+    /// Save the open terminals for the next launch (with their scrollback
+    /// while the vault is unlocked)
+    pub async fn save_open_sessions(&self) {
+        let master_key = self.auth.get_master_key().await.ok();
+        let result = self.restore.save(
+            |session_id| self.sessions.session_scrollback(session_id).ok(),
+            master_key.as_deref(),
+        );
+        if let Err(e) = result {
+            tracing::warn!("Failed to save open sessions: {}", e);
+        }
+    }
+
     /// Get the database file path
     ///
     /// Uses platform-specific data directories:
@@ -67,11 +152,8 @@ impl AppState {
         compile_error!("RITE only supports Linux, macOS, and Windows");
 
         // Runtime detection via dirs crate
-        let data_dir = dirs::data_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine data directory for {}", OS_NAME))?;
-
-        let app_dir = data_dir.join("rite");
-        let db_path = app_dir.join("vault.db");
+        let db_path = rite_vault::default_path()
+            .with_context(|| format!("Could not locate the vault on {}", OS_NAME))?;
 
         tracing::info!("Database path for {}: {}", OS_NAME, db_path.display());
 
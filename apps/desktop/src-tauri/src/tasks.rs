@@ -0,0 +1,333 @@
+/**
+ * Background Task Supervisor
+ *
+ * Registers background jobs (warm-up, periodic maintenance, ...) by name
+ * and tracks their state, last run and last error, so they can be listed
+ * and restarted individually from the frontend
+ */
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// Builds a fresh run of a job (called again on every run and restart)
+type Job = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+/// State of a background task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskState {
+    Running,
+    /// Periodic task between two runs
+    Waiting,
+    Completed,
+    Failed,
+}
+
+/// Task status reported by `list_background_tasks`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    /// Run interval of periodic tasks (None = runs once)
+    pub interval_secs: Option<u64>,
+    pub started_at: i64,
+    pub last_run_at: Option<i64>,
+    /// Error of the last run, if it failed
+    pub last_error: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+struct TaskEntry {
+    generation: u64,
+    status: TaskStatus,
+    job: Job,
+    every: Option<Duration>,
+    handle: JoinHandle<()>,
+}
+
+/// Background tasks keyed by name
+///
+/// Registering a name again (or restarting it) aborts the previous run.
+/// Every start gets a new generation, so an aborted run can't overwrite the
+/// status of its replacement.
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    tasks: Arc<Mutex<HashMap<String, TaskEntry>>>,
+    next_generation: Arc<AtomicU64>,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a job once in the background
+    pub async fn spawn<F, Fut>(&self, name: &str, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.start(name, Arc::new(move || Box::pin(job()) as JobFuture), None)
+            .await;
+    }
+
+    /// Run a job now and then every `every` (a failed run doesn't stop it)
+    pub async fn spawn_periodic<F, Fut>(&self, name: &str, every: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.start(
+            name,
+            Arc::new(move || Box::pin(job()) as JobFuture),
+            Some(every),
+        )
+        .await;
+    }
+
+    /// Abort a task and start it again
+    pub async fn restart(&self, name: &str) -> Result<()> {
+        let (job, every) = {
+            let tasks = self.tasks.lock().await;
+            let entry = tasks
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown background task: {}", name))?;
+            (Arc::clone(&entry.job), entry.every)
+        };
+
+        tracing::info!("[tasks.rs] Restarting background task {}", name);
+        self.start(name, job, every).await;
+        Ok(())
+    }
+
+    /// Abort a task and forget it (false if it wasn't registered)
+    pub async fn stop(&self, name: &str) -> bool {
+        match self.tasks.lock().await.remove(name) {
+            Some(entry) => {
+                tracing::info!("[tasks.rs] Stopping background task {}", name);
+                entry.handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Status of every registered task, by name
+    pub async fn list(&self) -> Vec<TaskStatus> {
+        let mut statuses: Vec<TaskStatus> = self
+            .tasks
+            .lock()
+            .await
+            .values()
+            .map(|entry| entry.status.clone())
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    async fn start(&self, name: &str, job: Job, every: Option<Duration>) {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed) + 1;
+
+        // Held until the entry is in place, so the run can't miss it
+        let mut tasks = self.tasks.lock().await;
+        if let Some(previous) = tasks.remove(name) {
+            previous.handle.abort();
+        }
+
+        let handle = tokio::spawn(run(
+            Arc::clone(&self.tasks),
+            name.to_string(),
+            generation,
+            Arc::clone(&job),
+            every,
+        ));
+        tasks.insert(
+            name.to_string(),
+            TaskEntry {
+                generation,
+                status: TaskStatus {
+                    name: name.to_string(),
+                    state: TaskState::Running,
+                    interval_secs: every.map(|d| d.as_secs()),
+                    started_at: chrono::Utc::now().timestamp_millis(),
+                    last_run_at: None,
+                    last_error: None,
+                    runs: 0,
+                    failures: 0,
+                },
+                job,
+                every,
+                handle,
+            },
+        );
+    }
+}
+
+async fn run(
+    tasks: Arc<Mutex<HashMap<String, TaskEntry>>>,
+    name: String,
+    generation: u64,
+    job: Job,
+    every: Option<Duration>,
+) {
+    loop {
+        let current = update(&tasks, &name, generation, |status| {
+            status.state = TaskState::Running;
+        })
+        .await;
+        if !current {
+            return;
+        }
+
+        let result = job().await;
+        if let Err(e) = &result {
+            tracing::warn!("[tasks.rs] Background task {} failed: {}", name, e);
+        }
+
+        let current = update(&tasks, &name, generation, |status| {
+            status.last_run_at = Some(chrono::Utc::now().timestamp_millis());
+            status.runs += 1;
+            status.last_error = result.as_ref().err().map(|e| e.to_string());
+            if result.is_err() {
+                status.failures += 1;
+            }
+            status.state = match (every, &result) {
+                (Some(_), _) => TaskState::Waiting,
+                (None, Ok(())) => TaskState::Completed,
+                (None, Err(_)) => TaskState::Failed,
+            };
+        })
+        .await;
+
+        match every {
+            Some(every) if current => tokio::time::sleep(every).await,
+            _ => return,
+        }
+    }
+}
+
+/// Update a task's status; false if the task was replaced or removed
+async fn update(
+    tasks: &Mutex<HashMap<String, TaskEntry>>,
+    name: &str,
+    generation: u64,
+    f: impl FnOnce(&mut TaskStatus),
+) -> bool {
+    match tasks.lock().await.get_mut(name) {
+        Some(entry) if entry.generation == generation => {
+            f(&mut entry.status);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Wait until `name` reaches `state` (or give up after a second)
+    async fn wait_for(supervisor: &TaskSupervisor, name: &str, state: TaskState) -> TaskStatus {
+        for _ in 0..100 {
+            let status = supervisor
+                .list()
+                .await
+                .into_iter()
+                .find(|s| s.name == name)
+                .unwrap();
+            if status.state == state {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} never reached {:?}", name, state);
+    }
+
+    #[tokio::test]
+    async fn test_one_shot_tasks() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("ok", || async { Ok(()) }).await;
+        supervisor
+            .spawn("broken", || async { Err(anyhow!("no route to host")) })
+            .await;
+
+        let ok = wait_for(&supervisor, "ok", TaskState::Completed).await;
+        assert_eq!(ok.runs, 1);
+        assert!(ok.last_run_at.is_some());
+
+        let broken = wait_for(&supervisor, "broken", TaskState::Failed).await;
+        assert_eq!(broken.failures, 1);
+        assert_eq!(broken.last_error.as_deref(), Some("no route to host"));
+
+        let names: Vec<_> = supervisor
+            .list()
+            .await
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["broken", "ok"]);
+    }
+
+    #[tokio::test]
+    async fn test_periodic_task_survives_failures() {
+        let supervisor = TaskSupervisor::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        supervisor
+            .spawn_periodic("flaky", Duration::from_millis(5), move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if n % 2 == 0 {
+                        Err(anyhow!("failed run {}", n))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        while calls.load(Ordering::SeqCst) < 4 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let status = wait_for(&supervisor, "flaky", TaskState::Waiting).await;
+        assert!(status.runs >= 3);
+        assert!(status.failures >= 2);
+        assert_eq!(status.interval_secs, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_restart() {
+        let supervisor = TaskSupervisor::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        supervisor
+            .spawn("once", move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok(()) }
+            })
+            .await;
+        wait_for(&supervisor, "once", TaskState::Completed).await;
+
+        supervisor.restart("once").await.unwrap();
+        wait_for(&supervisor, "once", TaskState::Completed).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        assert!(supervisor.restart("unknown").await.is_err());
+
+        assert!(supervisor.stop("once").await);
+        assert!(!supervisor.stop("once").await);
+        assert!(supervisor.list().await.is_empty());
+    }
+}
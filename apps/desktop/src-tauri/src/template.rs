@@ -0,0 +1,300 @@
+/**
+ * Connection Template Module
+ *
+ * Credential-less connection templates (every field except the host) used to
+ * add servers that share the same identity, folder, tags and session settings.
+ * The bastion travels with the session settings: `jump_hosts` holds the vault
+ * connections to go through, which the new connection reuses as they are.
+ */
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::connection::{AuthMethod, Connection, CreateConnectionInput, Protocol};
+use crate::session_options::SessionOptions;
+use rite_vault::ConnectionTemplateRow;
+
+/// Connection template (no hostname, no secrets)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTemplate {
+    pub id: String,
+    pub name: String,
+    pub protocol: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_type: String, // "password" or "publicKey"
+    pub key_path: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,                       // Tag names
+    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
+    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds
+    pub session_options: SessionOptions,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Input for creating a connection template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTemplateInput {
+    pub name: String,
+    pub protocol: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_type: String,
+    pub key_path: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>, // Tag names, created if missing
+    pub ssh_keep_alive_override: Option<String>,
+    pub ssh_keep_alive_interval: Option<i64>,
+    pub session_options: Option<SessionOptions>,
+}
+
+impl CreateTemplateInput {
+    /// Template of an existing connection: everything but the host and
+    /// the secrets
+    ///
+    /// Keys stored in the vault can't be shared by a template (it holds no
+    /// secrets); such connections are refused.
+    pub fn from_connection(connection: &Connection, name: String) -> Result<Self> {
+        let (auth_type, key_path) = match &connection.auth_method {
+            AuthMethod::Password { .. } => ("password", None),
+            AuthMethod::PublicKey { key_path, .. } => ("publicKey", Some(key_path.clone())),
+            AuthMethod::StoredKey { .. } => {
+                return Err(anyhow!(
+                    "Connections with a key stored in the vault can't be used as templates"
+                ))
+            }
+        };
+
+        Ok(Self {
+            name,
+            protocol: connection.protocol.as_str().to_string(),
+            port: connection.port,
+            username: connection.username.clone(),
+            auth_type: auth_type.to_string(),
+            key_path,
+            color: connection.metadata.color.clone(),
+            icon: connection.metadata.icon.clone(),
+            folder: connection.metadata.folder.clone(),
+            notes: connection.metadata.notes.clone(),
+            tags: connection.metadata.tags.clone(),
+            ssh_keep_alive_override: connection.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: connection.ssh_keep_alive_interval,
+            session_options: Some(connection.session_options.clone()),
+        })
+    }
+}
+
+impl ConnectionTemplate {
+    /// Create a new template
+    pub fn new(input: CreateTemplateInput) -> Result<Self> {
+        Protocol::from_str(&input.protocol)?;
+        match input.auth_type.as_str() {
+            "password" => {}
+            "publicKey" if input.key_path.as_deref().is_some_and(|p| !p.is_empty()) => {}
+            "publicKey" => return Err(anyhow!("Public key templates require a key path")),
+            other => return Err(anyhow!("Invalid auth type: {}", other)),
+        }
+        let tags = rite_vault::tag::normalize_names(&input.tags)?;
+        let session_options = input.session_options.unwrap_or_default();
+        let id = Uuid::new_v4().to_string();
+        session_options.jump_chain(&id)?;
+
+        let now = Utc::now().timestamp_millis();
+        Ok(Self {
+            id,
+            name: input.name,
+            protocol: input.protocol.to_lowercase(),
+            port: input.port,
+            username: input.username,
+            auth_type: input.auth_type,
+            key_path: input.key_path,
+            color: input.color,
+            icon: input.icon,
+            folder: input.folder,
+            notes: input.notes,
+            tags,
+            ssh_keep_alive_override: input.ssh_keep_alive_override,
+            ssh_keep_alive_interval: input.ssh_keep_alive_interval,
+            session_options,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Build a template from its database row
+    pub fn from_row(row: &ConnectionTemplateRow) -> Self {
+        Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            protocol: row.protocol.clone(),
+            port: row.port as u16,
+            username: row.username.clone(),
+            auth_type: row.auth_type.clone(),
+            key_path: row.key_path.clone(),
+            color: row.color.clone(),
+            icon: row.icon.clone(),
+            folder: row.folder.clone(),
+            notes: row.notes.clone(),
+            tags: row
+                .tags
+                .as_deref()
+                .and_then(|tags| serde_json::from_str(tags).ok())
+                .unwrap_or_default(),
+            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
+            session_options: SessionOptions::from_json(row.session_options.as_deref()),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+
+    /// Build the input for a new connection to `host`
+    ///
+    /// Password templates carry no password: the new connection starts with
+    /// an empty one that the user fills in when editing the connection.
+    pub fn to_connection_input(
+        &self,
+        host: &str,
+        name: Option<String>,
+    ) -> Result<CreateConnectionInput> {
+        let host = host.trim();
+        if host.is_empty() {
+            return Err(anyhow!("Host cannot be empty"));
+        }
+
+        let auth_method = match self.auth_type.as_str() {
+            "publicKey" => AuthMethod::PublicKey {
+                key_path: self.key_path.clone().unwrap_or_default(),
+                passphrase: None,
+            },
+            _ => AuthMethod::Password {
+                password: String::new(),
+            },
+        };
+
+        Ok(CreateConnectionInput {
+            name: name
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| host.to_string()),
+            protocol: self.protocol.clone(),
+            hostname: host.to_string(),
+            port: self.port,
+            username: self.username.clone(),
+            auth_method,
+            proxy_password: None,
+            color: self.color.clone(),
+            icon: self.icon.clone(),
+            folder_id: None,
+            folder: self.folder.clone(),
+            notes: self.notes.clone(),
+            tags: self.tags.clone(),
+            ssh_keep_alive_override: self.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: self.ssh_keep_alive_interval,
+            session_options: Some(self.session_options.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template_input(auth_type: &str, key_path: Option<&str>) -> CreateTemplateInput {
+        CreateTemplateInput {
+            name: "Prod via bastion".to_string(),
+            protocol: "ssh".to_string(),
+            port: 22,
+            username: "deploy".to_string(),
+            auth_type: auth_type.to_string(),
+            key_path: key_path.map(str::to_string),
+            color: None,
+            icon: None,
+            folder: Some("Production".to_string()),
+            notes: None,
+            tags: vec!["prod".to_string(), "web".to_string()],
+            ssh_keep_alive_override: None,
+            ssh_keep_alive_interval: None,
+            session_options: None,
+        }
+    }
+
+    #[test]
+    fn test_connection_input_from_template() {
+        let template =
+            ConnectionTemplate::new(template_input("publicKey", Some("~/.ssh/id_ed25519")))
+                .unwrap();
+        let input = template.to_connection_input(" web-03 ", None).unwrap();
+
+        assert_eq!(input.name, "web-03");
+        assert_eq!(input.hostname, "web-03");
+        assert_eq!(input.username, "deploy");
+        assert_eq!(input.folder.as_deref(), Some("Production"));
+        assert_eq!(input.tags, ["prod", "web"]);
+        assert!(matches!(
+            input.auth_method,
+            AuthMethod::PublicKey { ref key_path, passphrase: None } if key_path == "~/.ssh/id_ed25519"
+        ));
+
+        assert!(template.to_connection_input("  ", None).is_err());
+    }
+
+    #[test]
+    fn test_template_validation() {
+        assert!(ConnectionTemplate::new(template_input("password", None)).is_ok());
+        assert!(ConnectionTemplate::new(template_input("publicKey", None)).is_err());
+        assert!(ConnectionTemplate::new(template_input("kerberos", None)).is_err());
+
+        let mut input = template_input("password", None);
+        input.tags = vec!["bad,tag".to_string()];
+        assert!(ConnectionTemplate::new(input).is_err());
+    }
+
+    #[test]
+    fn test_template_from_connection() {
+        let template =
+            ConnectionTemplate::new(template_input("publicKey", Some("~/.ssh/id_ed25519")))
+                .unwrap();
+        let mut input = template.to_connection_input("web-01", None).unwrap();
+        input.session_options = Some(SessionOptions {
+            startup_commands: vec!["cd /srv".to_string()],
+            jump_hosts: vec!["bastion".to_string()],
+            ..SessionOptions::default()
+        });
+        let connection = Connection::new(input).unwrap();
+
+        let input = CreateTemplateInput::from_connection(&connection, "Web".to_string()).unwrap();
+        assert_eq!(input.name, "Web");
+        assert_eq!(input.auth_type, "publicKey");
+        assert_eq!(input.key_path.as_deref(), Some("~/.ssh/id_ed25519"));
+        assert_eq!(input.port, template.port);
+        assert_eq!(input.tags, ["prod", "web"]);
+
+        // The bastion is kept for the connections made from the template
+        let from_template = ConnectionTemplate::new(input)
+            .unwrap()
+            .to_connection_input("web-02", None)
+            .unwrap();
+        let session_options = from_template.session_options.unwrap();
+        assert_eq!(session_options.startup_commands, ["cd /srv"]);
+        assert_eq!(session_options.jump_hosts, ["bastion"]);
+        assert_eq!(from_template.tags, ["prod", "web"]);
+
+        let mut stored = connection.clone();
+        stored.auth_method = AuthMethod::StoredKey {
+            private_key: "key".to_string(),
+            passphrase: None,
+        };
+        assert!(CreateTemplateInput::from_connection(&stored, "Web".to_string()).is_err());
+    }
+}
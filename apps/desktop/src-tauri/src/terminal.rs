@@ -5,20 +5,51 @@
  */
 use anyhow::{anyhow, Result};
 use base64::Engine as _;
-use russh::client::{self};
-use russh::keys::{PrivateKeyWithHashAlg, PublicKey};
-use russh::ChannelMsg;
+use russh::client::{self, KeyboardInteractiveAuthResponse};
+use russh::keys::{HashAlg, PrivateKeyWithHashAlg, PublicKey};
+use russh::{ChannelMsg, Pty};
 use sqlx::SqlitePool;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, RwLock};
+use tauri::ipc::{Channel, InvokeResponseBody};
 use tauri::{AppHandle, Emitter, Manager};
-use tokio::sync::{mpsc, Mutex};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::sync::{mpsc, oneshot, Mutex};
 use uuid::Uuid;
-
-use crate::connection::{AuthMethod, Connection};
-use crate::db::Database;
+use zeroize::Zeroizing;
+
+use crate::audit;
+use crate::auth_prompt::{self, AuthPrompt, AuthPromptRequest, Responder};
+use crate::clipboard_bridge::{self, BridgeMode, ClipboardRequest, Osc52Scanner};
+use crate::command_guard::{self, CommandGuard, Confirmation};
+use crate::connection::{AuthMethod, Connection, Protocol, SecretKind};
+use crate::encoding;
+use crate::keep_alive;
 use crate::known_hosts::{self, HostKeyVerificationResult};
+use crate::line_mode::LineEditor;
+use crate::login_script::LoginScript;
+use crate::openssh_known_hosts;
+use crate::output::{OutputBatch, SessionOutput};
+use crate::pending_host_keys::PendingHostKeyInfo;
+use crate::power::{self, SleepDetector};
+use crate::prompt;
+use crate::recording::{Recorder, RecordingInfo, RecordingOptions};
+use crate::security_key::{self, SecurityKeyTouch};
+use crate::session_options::{self, ProtectionLevel};
+use crate::session_stats::{self, SessionStats, StatsMeter, StatsState};
+use crate::session_trace::{HandshakeStage, SessionTrace, TraceEvent};
+use crate::sftp::{self, SftpSession, SftpSessionInfo};
+use crate::shell_integration::{self, CwdState};
+use crate::snapshot::{self, ScreenState, SessionSnapshot, SnapshotFormat};
+use crate::sshfp::{self, DnsVerification};
+use crate::time_limit::{LimitEvent, TimeLimit};
+use crate::triggers::{self, Triggers};
+use crate::warm::{self, WarmPool, WarmState, WarmStatus};
+use crate::zmodem::{self, Zmodem};
 use crate::AppState;
+use rite_vault::{AuthManager, Database};
 
 /// Unique identifier for a terminal session
 pub type SessionId = String;
@@ -26,17 +57,130 @@ pub type SessionId = String;
 /// Commands that can be sent to a terminal session
 pub enum SessionCommand {
     SendInput(Vec<u8>),
-    Resize { cols: u32, rows: u32 },
+    /// Stored secret: not echoed, guarded nor recorded, and only sent at a
+    /// password prompt
+    SendSecret {
+        secret: Zeroizing<Vec<u8>>,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Resize {
+        cols: u32,
+        rows: u32,
+    },
+    ConfirmCommand(bool), // Answer to a command held back on a protected host
+    Forward(ForwardCommand),
+    Zmodem(zmodem::Reply), // Answer to a ZMODEM transfer (file to save, files to send)
     Close,
 }
 
+/// Port forwarding requests, run on the session's transport
+pub enum ForwardCommand {
+    /// Open a direct-tcpip channel (local and dynamic forwards)
+    OpenDirect {
+        host: String,
+        port: u16,
+        originator: SocketAddr,
+        reply: oneshot::Sender<Result<russh::Channel<client::Msg>>>,
+    },
+    /// Ask the server to listen (remote forwards); replies with the port it
+    /// picked when 0 was requested
+    Listen {
+        address: String,
+        port: u16,
+        reply: oneshot::Sender<Result<u32>>,
+    },
+    /// Stop a remote listener
+    CancelListen {
+        address: String,
+        port: u16,
+        reply: oneshot::Sender<Result<()>>,
+    },
+}
+
 /// SSH Client Handler with host key verification
-struct SshClientHandler {
+pub(crate) struct SshClientHandler {
     db: Arc<SqlitePool>,
     host: String,
     port: u16,
     app_handle: AppHandle,
-    force_accept_host_key: bool, // For Quick SSH: bypass host key verification
+    accept_host_key_once: bool, // Quick SSH opt-in: accept any key, don't save it
+    background: bool,           // Warm-up: never prompt or trust unknown hosts
+    trace: Arc<SessionTrace>,
+    /// Jump host transport this connection is tunnelled through; kept open
+    /// as long as this connection
+    jump: Option<SshTransport>,
+}
+
+/// Authenticated SSH transport (no channel opened yet)
+pub(crate) type SshTransport = client::Handle<SshClientHandler>;
+
+impl SshClientHandler {
+    /// Check the host key against SSHFP DNS records
+    /// Returns None unless enabled with the `host_key_dns_verification` setting
+    async fn dns_verification(&self, server_public_key: &PublicKey) -> Option<DnsVerification> {
+        let enabled = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM settings WHERE key = 'host_key_dns_verification'",
+        )
+        .fetch_optional(&*self.db)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|value| value == "true");
+        if !enabled {
+            return None;
+        }
+
+        let result = sshfp::verify(&self.host, server_public_key).await;
+        self.trace
+            .record("host_key", format!("SSHFP DNS verification: {:?}", result));
+        Some(result)
+    }
+
+    /// Check the host key against the OpenSSH known_hosts files
+    /// Returns false unless enabled with the `host_key_system_known_hosts` setting
+    async fn system_known_hosts_trust(&self, server_public_key: &PublicKey) -> bool {
+        let enabled = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM settings WHERE key = 'host_key_system_known_hosts'",
+        )
+        .fetch_optional(&*self.db)
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|value| value == "true");
+        if !enabled {
+            return false;
+        }
+
+        let trusted =
+            openssh_known_hosts::system_trusts_key(&self.host, self.port, server_public_key);
+        self.trace.record(
+            "host_key",
+            format!("OpenSSH known_hosts lists the key: {}", trusted),
+        );
+        trusted
+    }
+
+    /// Keep a rejected host key so the user can accept it from the prompt
+    async fn hold_pending_key(&self, key_type: &str, fingerprint: &str, key: &PublicKey) {
+        let public_key_data = match key.to_bytes() {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!("[terminal.rs] Failed to encode host key: {}", e);
+                return;
+            }
+        };
+        self.app_handle
+            .state::<AppState>()
+            .pending_host_keys
+            .add_pending(PendingHostKeyInfo {
+                host: self.host.clone(),
+                port: self.port,
+                key_type: key_type.to_string(),
+                fingerprint: fingerprint.to_string(),
+                public_key_data,
+            })
+            .await;
+    }
 }
 
 impl client::Handler for SshClientHandler {
@@ -51,17 +195,40 @@ impl client::Handler for SshClientHandler {
             self.host,
             self.port
         );
+        self.trace.record(
+            "host_key",
+            format!(
+                "Server host key: {} {}",
+                server_public_key.algorithm(),
+                server_public_key.fingerprint(HashAlg::Sha256)
+            ),
+        );
 
-        // Quick SSH: force accept all host keys (similar to ssh -o StrictHostKeyChecking=no)
-        if self.force_accept_host_key {
-            tracing::info!("[terminal.rs] Quick SSH mode: auto-accepting host key (TOFU)");
-
-            // Save the host key to known_hosts for future use
-            if let Err(e) =
-                known_hosts::add_host_key(&self.db, &self.host, self.port, server_public_key).await
+        // Quick SSH with explicit opt-in: accept any host key for this
+        // connection only (like ssh -o StrictHostKeyChecking=no
+        // -o UserKnownHostsFile=/dev/null), so known_hosts is never
+        // filled with unverified keys
+        if self.accept_host_key_once {
+            tracing::warn!(
+                "[terminal.rs] Insecure mode: accepting host key once without verification"
+            );
+            self.trace
+                .record("host_key", "Accepted once without verification (not saved)");
+
+            if let Err(e) = audit::record(
+                &self.db,
+                audit::EVENT_INSECURE_HOST_KEY,
+                &format!(
+                    "{}:{} {} {}",
+                    self.host,
+                    self.port,
+                    server_public_key.algorithm(),
+                    server_public_key.fingerprint(HashAlg::Sha256)
+                ),
+            )
+            .await
             {
-                tracing::warn!("[terminal.rs] Failed to save host key for Quick SSH: {}", e);
-                // Don't fail the connection if we can't save the key
+                tracing::error!("[terminal.rs] Failed to write audit log entry: {}", e);
             }
 
             return Ok(true);
@@ -98,6 +265,8 @@ impl client::Handler for SshClientHandler {
         {
             Ok(HostKeyVerificationResult::Accepted) => {
                 tracing::info!("[terminal.rs] Host key accepted (known host)");
+                self.trace
+                    .record("host_key", "Host key matches known_hosts entry");
                 Ok(true)
             }
             Ok(HostKeyVerificationResult::Unknown {
@@ -107,18 +276,75 @@ impl client::Handler for SshClientHandler {
                 fingerprint,
             }) => {
                 tracing::warn!("[terminal.rs] Unknown host {}:{}", host, port);
+                self.trace.record(
+                    "host_key",
+                    format!("Unknown host, verification mode '{}'", verification_mode),
+                );
                 tracing::warn!(
                     "[terminal.rs] Key type: {}, Fingerprint: {}",
                     key_type,
                     fingerprint
                 );
 
+                // Keys the user already trusts in OpenSSH are synced, not prompted
+                if self.system_known_hosts_trust(server_public_key).await {
+                    tracing::info!(
+                        "[terminal.rs] Host key found in OpenSSH known_hosts, accepting"
+                    );
+
+                    if let Err(e) =
+                        known_hosts::add_host_key(&self.db, &host, port, server_public_key).await
+                    {
+                        tracing::error!("[terminal.rs] Failed to save host key: {}", e);
+                    }
+                    return Ok(true);
+                }
+
+                // A DNSSEC-validated SSHFP record is as good as a known_hosts entry
+                let dns_verification = self.dns_verification(server_public_key).await;
+                if dns_verification
+                    .as_ref()
+                    .is_some_and(DnsVerification::is_verified)
+                {
+                    tracing::info!("[terminal.rs] Host key verified via SSHFP, accepting");
+
+                    if let Err(e) =
+                        known_hosts::add_host_key(&self.db, &host, port, server_public_key).await
+                    {
+                        tracing::error!("[terminal.rs] Failed to save host key: {}", e);
+                    }
+
+                    let _ = self.app_handle.emit(
+                        "ssh:host-key-added",
+                        serde_json::json!({
+                            "host": host,
+                            "port": port,
+                            "keyType": key_type,
+                            "fingerprint": fingerprint,
+                            "dnsVerification": dns_verification,
+                        }),
+                    );
+
+                    return Ok(true);
+                }
+
+                // Background warm-up never prompts or trusts on first use
+                if self.background {
+                    tracing::info!("[terminal.rs] Unknown host during warm-up, skipping");
+                    self.trace
+                        .record("host_key", "Unknown host during warm-up, skipped");
+                    return Err(russh::Error::Disconnect);
+                }
+
                 match verification_mode.as_str() {
                     "strict" => {
                         // Strict mode: Emit event and REJECT connection
                         // User must explicitly accept the key via the modal
                         tracing::warn!("[terminal.rs] Strict mode: Rejecting connection and requesting user confirmation");
 
+                        self.hold_pending_key(&key_type, &fingerprint, server_public_key)
+                            .await;
+
                         let _ = self.app_handle.emit(
                             "ssh:host-key-unknown",
                             serde_json::json!({
@@ -126,6 +352,7 @@ impl client::Handler for SshClientHandler {
                                 "port": port,
                                 "keyType": key_type,
                                 "fingerprint": fingerprint,
+                                "dnsVerification": dns_verification,
                             }),
                         );
 
@@ -149,6 +376,7 @@ impl client::Handler for SshClientHandler {
                                 "port": port,
                                 "keyType": key_type,
                                 "fingerprint": fingerprint,
+                                "dnsVerification": dns_verification,
                             }),
                         );
 
@@ -174,27 +402,39 @@ impl client::Handler for SshClientHandler {
             Ok(HostKeyVerificationResult::Changed {
                 host,
                 port,
+                key_type,
                 old_fingerprint,
                 new_fingerprint,
-                ..
             }) => {
                 // Host key changed - potential MITM attack!
                 // ALWAYS reject regardless of mode (security critical)
                 tracing::error!("[terminal.rs] ⚠️  WARNING: HOST KEY HAS CHANGED! ⚠️");
+                self.trace
+                    .record("host_key", "Host key changed, connection rejected");
                 tracing::error!("[terminal.rs] Host: {}:{}", host, port);
                 tracing::error!("[terminal.rs] Old fingerprint: {}", old_fingerprint);
                 tracing::error!("[terminal.rs] New fingerprint: {}", new_fingerprint);
                 tracing::error!("[terminal.rs] This could indicate a Man-in-the-Middle attack!");
                 tracing::error!("[terminal.rs] Connection REJECTED for security");
 
+                // SSHFP never overrides a changed key, but helps the user judge it
+                let dns_verification = self.dns_verification(server_public_key).await;
+
+                // The user may accept the new key after comparing fingerprints
+                // (update_known_host), this connection still fails
+                self.hold_pending_key(&key_type, &new_fingerprint, server_public_key)
+                    .await;
+
                 // Emit event to notify frontend of changed key
                 let _ = self.app_handle.emit(
                     "ssh:host-key-changed",
                     serde_json::json!({
                         "host": host,
                         "port": port,
+                        "keyType": key_type,
                         "oldFingerprint": old_fingerprint,
                         "newFingerprint": new_fingerprint,
+                        "dnsVerification": dns_verification,
                     }),
                 );
 
@@ -207,15 +447,224 @@ impl client::Handler for SshClientHandler {
             }
         }
     }
+
+    /// Key exchange finished: note the algorithms the server agreed on
+    async fn kex_done(
+        &mut self,
+        _shared_secret: Option<&[u8]>,
+        names: &russh::negotiation::Names,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        self.trace.record(
+            "kex",
+            format!(
+                "Negotiated kex {}, host key {}, cipher {}, mac {}/{}",
+                names.kex.as_ref(),
+                names.key,
+                names.cipher.as_ref(),
+                names.client_mac.as_ref(),
+                names.server_mac.as_ref()
+            ),
+        );
+        Ok(())
+    }
+
+    /// Connection to a remote (-R) forward: hand it to the forward's target
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        self.trace.record(
+            "forward",
+            format!(
+                "Forwarded connection to {}:{} from {}:{}",
+                connected_address, connected_port, originator_address, originator_port
+            ),
+        );
+        let accepted = self
+            .app_handle
+            .state::<AppState>()
+            .forwards
+            .accept_remote(&self.host, self.port, connected_port, channel.into_stream())
+            .await;
+        if !accepted {
+            tracing::warn!(
+                "[terminal.rs] No remote forward on port {}, connection dropped",
+                connected_port
+            );
+        }
+        Ok(())
+    }
+
+    /// Transport closed: note dead peers (unanswered keep-alives)
+    async fn disconnected(
+        &mut self,
+        reason: client::DisconnectReason<Self::Error>,
+    ) -> Result<(), Self::Error> {
+        match reason {
+            client::DisconnectReason::ReceivedDisconnect(_) => {
+                self.trace.record("transport", "Disconnected by server");
+                Ok(())
+            }
+            client::DisconnectReason::Error(russh::Error::KeepaliveTimeout) => {
+                tracing::warn!(
+                    "[terminal.rs] {}:{} left {} keep-alives unanswered, closing connection",
+                    self.host,
+                    self.port,
+                    keep_alive::MAX_UNANSWERED
+                );
+                self.trace.record(
+                    "keepalive",
+                    format!(
+                        "No answer to {} keep-alives, connection closed",
+                        keep_alive::MAX_UNANSWERED
+                    ),
+                );
+                Err(russh::Error::KeepaliveTimeout)
+            }
+            client::DisconnectReason::Error(e) => {
+                self.trace
+                    .record("transport", format!("Connection closed: {}", e));
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Deliver session output to the frontend
+///
+/// Feeds the backend screen model, then either buffers the data until the
+/// frontend attaches or streams it over the session's output channel.
+fn deliver_output(screen: &ScreenState, output: &SessionOutput, data: &[u8]) {
+    if let Ok(mut screen) = screen.lock() {
+        screen.process(data);
+    }
+    output.deliver(data);
+}
+
+/// Pass input through line mode (local echo, only complete lines sent) and
+/// the dangerous command guard; returns what to send to the host, and the
+/// command held back for confirmation if any
+fn filter_input(
+    data: Vec<u8>,
+    line_editor: Option<&mut LineEditor>,
+    guard: Option<&mut CommandGuard>,
+    screen: &ScreenState,
+    output: &SessionOutput,
+) -> (Vec<u8>, Option<Confirmation>) {
+    let data = match line_editor {
+        Some(editor) => {
+            let edited = editor.process(&data);
+            if !edited.echo.is_empty() {
+                deliver_output(screen, output, &edited.echo);
+            }
+            edited.send
+        }
+        None => data,
+    };
+    match guard {
+        Some(guard) => {
+            let screen_line = snapshot::cursor_line(screen);
+            let checked = guard.process(&data, screen_line.as_deref());
+            (checked.send, checked.confirm)
+        }
+        None => (data, None),
+    }
 }
 
 /// Represents an active SSH terminal session
 pub struct SshSession {
     pub id: SessionId,
     command_tx: mpsc::Sender<SessionCommand>,
-    /// Buffer for the initial SSH output (MOTD, welcome message, first prompt).
-    /// `Some(bytes)` = still buffering; `None` = streaming mode (frontend has claimed).
-    initial_buffer: Arc<Mutex<Option<Vec<u8>>>>,
+    /// Output sink; buffers the initial output (MOTD, first prompt) until
+    /// the frontend attaches its channel
+    output: Arc<SessionOutput>,
+    /// Backend screen state (used for snapshots)
+    screen: ScreenState,
+    /// Protocol-level debug trace
+    trace: Arc<SessionTrace>,
+    /// Quick SSH: connection details (with credentials) until saved or the
+    /// vault is locked; wiped when dropped
+    quick_connection: StdMutex<Option<Zeroizing<Connection>>>,
+    /// Server host and port (routes remote forwards back to this session)
+    endpoint: (String, u16),
+    /// Connection the session was opened from (recordings are filed under it)
+    connection_id: String,
+    /// Remote working directory reported by the shell (OSC 7)
+    cwd: CwdState,
+    /// Traffic and latency, updated every `session_stats::REPORT_INTERVAL`
+    stats: StatsState,
+    /// Latency is only measured while the frontend shows the stats
+    stats_watched: Arc<AtomicBool>,
+}
+
+/// Port forwarding access to an SSH session's transport
+#[derive(Clone)]
+pub struct ForwardHandle {
+    command_tx: mpsc::Sender<SessionCommand>,
+    endpoint: (String, u16),
+}
+
+impl ForwardHandle {
+    /// Server host and port of the session
+    pub fn endpoint(&self) -> (&str, u16) {
+        (&self.endpoint.0, self.endpoint.1)
+    }
+
+    /// Open a stream to `host:port` as seen from the server
+    pub async fn open_direct_tcpip(
+        &self,
+        host: String,
+        port: u16,
+        originator: SocketAddr,
+    ) -> Result<russh::ChannelStream<client::Msg>> {
+        let (reply, response) = oneshot::channel();
+        self.send(ForwardCommand::OpenDirect {
+            host,
+            port,
+            originator,
+            reply,
+        })
+        .await?;
+        let channel = response.await.map_err(|_| anyhow!("Session closed"))??;
+        Ok(channel.into_stream())
+    }
+
+    /// Ask the server to listen on `address:port`
+    pub async fn listen(&self, address: &str, port: u16) -> Result<u32> {
+        let (reply, response) = oneshot::channel();
+        self.send(ForwardCommand::Listen {
+            address: address.to_string(),
+            port,
+            reply,
+        })
+        .await?;
+        response.await.map_err(|_| anyhow!("Session closed"))?
+    }
+
+    /// Stop a listener started with `listen`
+    pub async fn cancel_listen(&self, address: &str, port: u16) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+        self.send(ForwardCommand::CancelListen {
+            address: address.to_string(),
+            port,
+            reply,
+        })
+        .await?;
+        response.await.map_err(|_| anyhow!("Session closed"))?
+    }
+
+    async fn send(&self, command: ForwardCommand) -> Result<()> {
+        self.command_tx
+            .send(SessionCommand::Forward(command))
+            .await
+            .map_err(|_| anyhow!("Session closed"))
+    }
 }
 
 impl SshSession {
@@ -224,14 +673,108 @@ impl SshSession {
         connection: Connection,
         auth_method: AuthMethod,
         app_handle: AppHandle,
-        keep_alive_interval: Option<u64>, // Keep-alive interval in seconds (None = disabled)
-        force_accept_host_key: bool,      // For Quick SSH: bypass host key verification
+        accept_host_key_once: bool, // For Quick SSH: accept the host key without saving it
+        debug: bool,                // Record protocol-level events in the session trace
     ) -> Result<Self> {
-        let session_id = Uuid::new_v4().to_string();
-        tracing::info!(
-            "[terminal.rs] SshSession::connect - Session ID: {}",
-            session_id
-        );
+        let trace = SessionTrace::new(debug);
+        let transport = Self::establish(
+            &connection,
+            &auth_method,
+            &app_handle,
+            accept_host_key_once,
+            false,
+            &trace,
+        )
+        .await?;
+        Self::open(transport, connection, auth_method, app_handle, trace).await
+    }
+
+    /// Connect, verify the host key and authenticate (no channel yet)
+    ///
+    /// With jump hosts, each hop is connected through the previous one and
+    /// the target is reached through the last hop.
+    pub(crate) async fn establish(
+        connection: &Connection,
+        auth_method: &AuthMethod,
+        app_handle: &AppHandle,
+        accept_host_key_once: bool,
+        background: bool, // Warm-up: unknown hosts are skipped instead of prompting
+        trace: &Arc<SessionTrace>,
+    ) -> Result<SshTransport> {
+        let mut via = None;
+        for hop in Self::jump_hosts(connection, app_handle).await? {
+            trace.record(
+                "jump",
+                format!(
+                    "Connecting to jump host {} ({}:{})",
+                    hop.name, hop.hostname, hop.port
+                ),
+            );
+            let transport = Self::handshake(
+                &hop,
+                &hop.auth_method,
+                app_handle,
+                false,
+                background,
+                trace,
+                via,
+            )
+            .await
+            .map_err(|e| anyhow!("Jump host {} failed: {}", hop.name, e))?;
+            via = Some(transport);
+        }
+
+        Self::handshake(
+            connection,
+            auth_method,
+            app_handle,
+            accept_host_key_once,
+            background,
+            trace,
+            via,
+        )
+        .await
+    }
+
+    /// Jump host connections (with decrypted credentials), first hop first
+    async fn jump_hosts(
+        connection: &Connection,
+        app_handle: &AppHandle,
+    ) -> Result<Vec<Connection>> {
+        let chain = connection.session_options.jump_chain(&connection.id)?;
+        if chain.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let state = app_handle.state::<AppState>();
+        let master_key = state.auth.get_master_key().await?;
+        let mut hops = Vec::with_capacity(chain.len());
+        for id in chain {
+            let row = state
+                .db
+                .get_connection(&id)
+                .await?
+                .ok_or_else(|| anyhow!("Jump host connection {} not found", id))?;
+            let hop = Connection::from_row(&row, &master_key)?;
+            if !matches!(hop.protocol, Protocol::SSH) {
+                return Err(anyhow!("Jump host {} is not an SSH connection", hop.name));
+            }
+            hops.push(hop);
+        }
+        Ok(hops)
+    }
+
+    /// Connect (directly, or through a jump host), verify the host key and
+    /// authenticate
+    async fn handshake(
+        connection: &Connection,
+        auth_method: &AuthMethod,
+        app_handle: &AppHandle,
+        accept_host_key_once: bool,
+        background: bool,
+        trace: &Arc<SessionTrace>,
+        via: Option<SshTransport>,
+    ) -> Result<SshTransport> {
         tracing::info!(
             "[terminal.rs] Connecting to {}:{} as {}",
             connection.hostname,
@@ -243,27 +786,149 @@ impl SshSession {
         let state = app_handle.state::<AppState>();
         let db = state.db.pool().clone();
 
+        // Resolve the client identification string (per-connection, then global setting)
+        let client_id_setting = match connection.session_options.client_id.clone() {
+            Some(client_id) => Some(client_id),
+            None => state.db.get_setting("ssh_client_id").await.ok().flatten(),
+        };
+        let client_id = match client_id_setting.as_deref() {
+            Some(value) => session_options::resolve_client_id(value)?,
+            None => None,
+        };
+
         // Create SSH client configuration
-        let config = Arc::new(client::Config::default());
+        let mut config = client::Config::default();
+        if let Some(client_id) = client_id {
+            tracing::info!("[terminal.rs] Using client identification: {}", client_id);
+            trace.record("kex", format!("Client identification: {}", client_id));
+            config.client_id = russh::SshId::Standard(client_id);
+        }
+
+        // Keep-alive (per-connection, then global setting): unanswered
+        // keep-alives close the transport
+        let keep_alive_interval = keep_alive::resolve(
+            connection.ssh_keep_alive_override.as_deref(),
+            connection.ssh_keep_alive_interval,
+            state
+                .db
+                .get_setting(keep_alive::SETTING_ENABLED)
+                .await
+                .ok()
+                .flatten()
+                .as_deref(),
+            state
+                .db
+                .get_setting(keep_alive::SETTING_INTERVAL)
+                .await
+                .ok()
+                .flatten()
+                .as_deref(),
+        );
+        match keep_alive_interval {
+            Some(interval) => {
+                tracing::info!(
+                    "[terminal.rs] Keep-alive enabled: {} seconds",
+                    interval.as_secs()
+                );
+                trace.record(
+                    "keepalive",
+                    format!(
+                        "Keep-alive every {}s, dead after {} unanswered",
+                        interval.as_secs(),
+                        keep_alive::MAX_UNANSWERED
+                    ),
+                );
+            }
+            None => tracing::info!("[terminal.rs] Keep-alive disabled"),
+        }
+        config.keepalive_interval = keep_alive_interval;
+        config.keepalive_max = keep_alive::MAX_UNANSWERED;
+
+        // A host with several keys is asked for the one that is known
+        if let Ok(Some(known)) =
+            known_hosts::get_known_host(&db, &connection.hostname, connection.port).await
+        {
+            config.preferred.key =
+                known_hosts::prefer_known_key_type(&config.preferred.key, &known.key_type).into();
+        }
+        let config = Arc::new(config);
         let handler = SshClientHandler {
             db: Arc::new(db),
             host: connection.hostname.clone(),
             port: connection.port,
             app_handle: app_handle.clone(),
-            force_accept_host_key,
+            accept_host_key_once,
+            background,
+            trace: Arc::clone(trace),
+            jump: None,
         };
 
         // Connect to SSH server (host key verification happens in handler.check_server_key())
         let addr = format!("{}:{}", connection.hostname, connection.port);
-        tracing::info!("[terminal.rs] Attempting TCP connection to {}...", addr);
-        let mut session = client::connect(config, &addr, handler).await?;
-        tracing::info!("[terminal.rs] TCP connection established");
+        let mut session = match via {
+            Some(jump) => {
+                tracing::info!(
+                    "[terminal.rs] Opening tunnel to {} through jump host...",
+                    addr
+                );
+                let channel = jump
+                    .channel_open_direct_tcpip(
+                        connection.hostname.clone(),
+                        u32::from(connection.port),
+                        "127.0.0.1",
+                        0,
+                    )
+                    .await
+                    .map_err(|e| anyhow!("Jump host cannot reach {}: {}", addr, e))?;
+                let handler = SshClientHandler {
+                    jump: Some(jump),
+                    ..handler
+                };
+                client::connect_stream(config, channel.into_stream(), handler).await?
+            }
+            None => {
+                let proxy = connection
+                    .session_options
+                    .proxy_config(connection.proxy_password.as_deref())?;
+                match proxy {
+                    Some(proxy) => {
+                        tracing::info!(
+                            "[terminal.rs] Attempting TCP connection to {} through {:?} proxy {}:{}...",
+                            addr,
+                            proxy.kind,
+                            proxy.hostname,
+                            proxy.port
+                        );
+                        trace.record(
+                            "proxy",
+                            format!(
+                                "Connecting through {:?} proxy {}:{}",
+                                proxy.kind, proxy.hostname, proxy.port
+                            ),
+                        );
+                        let stream = proxy.connect(&connection.hostname, connection.port).await?;
+                        client::connect_stream(config, stream, handler).await?
+                    }
+                    None => {
+                        tracing::info!("[terminal.rs] Attempting TCP connection to {}...", addr);
+                        client::connect(config, &addr, handler).await?
+                    }
+                }
+            }
+        };
+        tracing::info!("[terminal.rs] Connection established");
+        trace.record_stage(
+            "kex",
+            format!("Key exchange completed with {}", addr),
+            HandshakeStage::KexDone,
+        );
 
         // Authenticate
         tracing::info!("[terminal.rs] Authenticating...");
-        let auth_result = match auth_method {
+        let auth_result = match *auth_method {
             AuthMethod::Password { ref password } => {
                 tracing::debug!("[terminal.rs] Using password authentication");
+                trace_auth_attempt(trace, "password authentication");
                 session
                     .authenticate_password(&connection.username, password)
                     .await?
@@ -276,184 +941,1124 @@ impl SshSession {
                     "[terminal.rs] Using public key authentication from: {}",
                     key_path
                 );
-                // Load private key
-                let key_data = tokio::fs::read(key_path).await?;
-                let key = if let Some(pass) = passphrase {
-                    russh::keys::decode_secret_key(&String::from_utf8(key_data)?, Some(pass))?
-                } else {
-                    russh::keys::decode_secret_key(&String::from_utf8(key_data)?, None)?
-                };
+                trace_auth_attempt(trace, format!("public key authentication ({})", key_path));
+                // Load private key (OpenSSH, PKCS#8, PEM RSA or PuTTY .ppk)
+                let key = Arc::new(
+                    crate::key_format::load_private_key(key_path, passphrase.as_deref()).await?,
+                );
 
-                session
-                    .authenticate_publickey(
-                        &connection.username,
-                        PrivateKeyWithHashAlg::new(Arc::new(key), None),
+                if security_key::is_security_key(key.public_key()) {
+                    Self::authenticate_security_key(
+                        &mut session,
+                        connection,
+                        key.public_key(),
+                        app_handle,
+                        background,
+                        trace,
+                    )
+                    .await?
+                } else {
+                    // A certificate next to the key is tried first, OpenSSH style
+                    let cert_result = Self::authenticate_certificate(
+                        &mut session,
+                        connection,
+                        key_path,
+                        &key,
+                        trace,
+                    )
+                    .await?;
+                    match cert_result {
+                        Some(result) => result,
+                        None => {
+                            session
+                                .authenticate_publickey(
+                                    &connection.username,
+                                    PrivateKeyWithHashAlg::new(key, None),
+                                )
+                                .await?
+                        }
+                    }
+                }
+            }
+            AuthMethod::StoredKey {
+                ref private_key,
+                ref passphrase,
+            } => {
+                tracing::debug!("[terminal.rs] Using public key authentication (vault key)");
+                trace_auth_attempt(trace, "public key authentication (vault key)");
+                let key =
+                    crate::key_format::decode_private_key(private_key, passphrase.as_deref())?;
+
+                if security_key::is_security_key(key.public_key()) {
+                    Self::authenticate_security_key(
+                        &mut session,
+                        connection,
+                        key.public_key(),
+                        app_handle,
+                        background,
+                        trace,
                     )
                     .await?
+                } else {
+                    session
+                        .authenticate_publickey(
+                            &connection.username,
+                            PrivateKeyWithHashAlg::new(Arc::new(key), None),
+                        )
+                        .await?
+                }
             }
         };
 
-        if !matches!(auth_result, russh::client::AuthResult::Success) {
+        trace.record("auth", format!("Authentication result: {:?}", auth_result));
+        let mut authenticated = matches!(auth_result, russh::client::AuthResult::Success);
+
+        // PAM servers (password prompts, OTP codes): keyboard-interactive
+        if !authenticated {
+            authenticated = Self::authenticate_keyboard_interactive(
+                &mut session,
+                connection,
+                auth_method,
+                app_handle,
+                background,
+                trace,
+            )
+            .await?;
+        }
+
+        if !authenticated {
             tracing::error!("[terminal.rs] Authentication failed!");
             return Err(anyhow!("Authentication failed"));
         }
         tracing::info!("[terminal.rs] Authentication successful");
 
-        // Open a channel with PTY
-        tracing::info!("[terminal.rs] Opening channel...");
-        let mut channel = session.channel_open_session().await?;
-        tracing::info!("[terminal.rs] Channel opened");
+        Ok(session)
+    }
 
-        // Request PTY
-        tracing::info!("[terminal.rs] Requesting PTY (xterm-256color, 80x24)...");
-        channel
-            .request_pty(
-                true,
-                "xterm-256color",
-                80,  // cols
-                24,  // rows
-                0,   // pix_width
-                0,   // pix_height
-                &[], // terminal modes
-            )
+    /// Certificate authentication with the certificate next to a key file
+    ///
+    /// Returns None if there is no usable certificate or the server rejects
+    /// it, so the plain key can be tried.
+    async fn authenticate_certificate(
+        session: &mut SshTransport,
+        connection: &Connection,
+        key_path: &str,
+        key: &Arc<russh::keys::PrivateKey>,
+        trace: &Arc<SessionTrace>,
+    ) -> Result<Option<russh::client::AuthResult>> {
+        let (certificate, info) =
+            match crate::key_format::load_certificate(key_path, key.public_key()) {
+                Ok(Some(certificate)) => certificate,
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    tracing::warn!("[terminal.rs] Ignoring certificate: {:#}", e);
+                    trace.record("auth", format!("Ignoring certificate: {:#}", e));
+                    return Ok(None);
+                }
+            };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if !info.is_valid_at(now) {
+            tracing::warn!(
+                "[terminal.rs] Certificate {} is not valid now, skipping it",
+                info.path
+            );
+            trace.record(
+                "auth",
+                format!("Certificate {} expired or not yet valid", info.path),
+            );
+            return Ok(None);
+        }
+
+        trace_auth_attempt(
+            trace,
+            format!(
+                "certificate authentication ({}, key ID {})",
+                info.path, info.key_id
+            ),
+        );
+        let result = session
+            .authenticate_openssh_cert(&connection.username, key.clone(), certificate)
             .await?;
-        tracing::info!("[terminal.rs] PTY allocated");
+        if matches!(result, russh::client::AuthResult::Success) {
+            Ok(Some(result))
+        } else {
+            trace.record("auth", "Certificate rejected, trying the plain key");
+            Ok(None)
+        }
+    }
 
-        // Create command channel BEFORE spawning the listener
-        // This ensures we can send commands immediately
-        let (command_tx, mut command_rx) = mpsc::channel::<SessionCommand>(100);
+    /// Public key authentication with a FIDO2 security key: ssh-agent signs,
+    /// the user touches the key while `ssh:security-key-touch` is waiting
+    async fn authenticate_security_key(
+        session: &mut SshTransport,
+        connection: &Connection,
+        key: &PublicKey,
+        app_handle: &AppHandle,
+        background: bool,
+        trace: &Arc<SessionTrace>,
+    ) -> Result<russh::client::AuthResult> {
+        // Nobody is there to touch the key during warm-up
+        if background {
+            return Err(anyhow!("Security keys are not used for warm connections"));
+        }
 
-        // Buffer for initial SSH output (MOTD, welcome message, first prompt).
-        // Emitting events before the frontend has registered its listener causes those
-        // events to be silently dropped. Instead we buffer all data until the frontend
-        // calls claim_session_output(), which atomically drains the buffer and switches
-        // to streaming mode. No timing hacks needed.
-        let initial_buffer: Arc<Mutex<Option<Vec<u8>>>> = Arc::new(Mutex::new(Some(Vec::new())));
-        let initial_buffer_clone = Arc::clone(&initial_buffer);
+        let fingerprint = security_key::fingerprint(key);
+        trace_auth_attempt(
+            trace,
+            format!("security key authentication ({})", fingerprint),
+        );
+        let mut agent = security_key::connect_agent().await?;
+        security_key::ensure_agent_identity(&mut agent, key).await?;
 
-        // Spawn task to manage the SSH channel BEFORE requesting shell
-        // This ensures the listener is active when MOTD arrives
-        let session_id_clone = session_id.clone();
-        tokio::spawn(async move {
-            // Request shell (PTY was already allocated above)
-            tracing::info!("[terminal.rs] Requesting shell...");
-            if let Err(e) = channel.request_shell(true).await {
-                tracing::error!("[terminal.rs] Failed to request shell: {}", e);
-                let _ = app_handle.emit(
-                    "terminal-error",
-                    serde_json::json!({
-                        "sessionId": session_id_clone,
-                        "error": format!("Failed to start shell: {}", e),
-                    }),
-                );
-                return;
-            }
-            tracing::info!("[terminal.rs] Shell started, buffering initial output");
+        let touch = |waiting| SecurityKeyTouch {
+            host: connection.hostname.clone(),
+            port: connection.port,
+            username: connection.username.clone(),
+            fingerprint: fingerprint.clone(),
+            waiting,
+        };
+        let _ = app_handle.emit("ssh:security-key-touch", touch(true));
+        let result = session
+            .authenticate_publickey_with(&connection.username, key.clone(), None, &mut agent)
+            .await;
+        let _ = app_handle.emit("ssh:security-key-touch", touch(false));
 
-            // Start the event loop immediately to capture all output including MOTD
-            // Keep-alive timer will be initialized on first tick
-            let mut keep_alive_timer: Option<tokio::time::Interval> = None;
-            let mut keep_alive_initialized = false;
+        result.map_err(|e| anyhow!("Security key authentication failed: {:?}", e))
+    }
 
-            loop {
-                // Initialize keep-alive on first loop iteration (after we're already listening)
-                if !keep_alive_initialized {
-                    keep_alive_timer = if let Some(interval_secs) = keep_alive_interval {
-                        tracing::info!(
-                            "[terminal.rs] Keep-alive enabled: {} seconds",
-                            interval_secs
-                        );
-                        Some(tokio::time::interval(std::time::Duration::from_secs(
-                            interval_secs,
-                        )))
-                    } else {
-                        tracing::info!("[terminal.rs] Keep-alive disabled");
-                        None
-                    };
-                    keep_alive_initialized = true;
+    /// Keyboard-interactive authentication; prompts the stored password
+    /// cannot answer are shown to the user
+    async fn authenticate_keyboard_interactive(
+        session: &mut SshTransport,
+        connection: &Connection,
+        auth_method: &AuthMethod,
+        app_handle: &AppHandle,
+        background: bool,
+        trace: &Arc<SessionTrace>,
+    ) -> Result<bool> {
+        let password = match auth_method {
+            AuthMethod::Password { password } => Some(password.as_str()),
+            AuthMethod::PublicKey { .. } | AuthMethod::StoredKey { .. } => None,
+        };
+        let mut responder = Responder::new(password);
+
+        trace_auth_attempt(trace, "keyboard-interactive authentication");
+        let mut response = session
+            .authenticate_keyboard_interactive_start(&connection.username, None::<String>)
+            .await?;
+        loop {
+            let (name, instructions, prompts) = match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(true),
+                KeyboardInteractiveAuthResponse::Failure { .. } => {
+                    trace.record("auth", "Keyboard-interactive authentication failed");
+                    return Ok(false);
                 }
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => (name, instructions, prompts),
+            };
+
+            let prompts: Vec<AuthPrompt> = prompts
+                .into_iter()
+                .map(|p| AuthPrompt {
+                    prompt: p.prompt,
+                    echo: p.echo,
+                })
+                .collect();
+            // Prompt texts only: answers are never recorded
+            trace.record(
+                "auth",
+                format!(
+                    "Keyboard-interactive prompts: {:?}",
+                    prompts.iter().map(|p| &p.prompt).collect::<Vec<_>>()
+                ),
+            );
 
-                tokio::select! {
-                    // Keep-alive timer
-                    _ = async {
-                        match &mut keep_alive_timer {
-                            Some(timer) => timer.tick().await,
-                            None => std::future::pending().await, // Never completes if disabled
-                        }
-                    } => {
-                        tracing::trace!("[terminal.rs] Sending keep-alive...");
-                        // Try to send a window size query as a keep-alive heartbeat
-                        // If this fails, the connection is likely dead
-                        if let Err(e) = channel.window_change(80, 24, 0, 0).await {
-                            tracing::error!("[terminal.rs] Keep-alive failed: {}. Connection appears dead.", e);
-                            let _ = app_handle.emit(
-                                "connection-dead",
-                                serde_json::json!({
-                                    "sessionId": session_id_clone,
-                                    "reason": "Keep-alive failed",
-                                }),
-                            );
-                            let _ = app_handle.emit(
-                                "terminal-closed",
-                                serde_json::json!({
-                                    "sessionId": session_id_clone,
-                                }),
-                            );
+            let answers = responder.prefill(&prompts);
+            let unanswered: Vec<AuthPrompt> = prompts
+                .iter()
+                .zip(&answers)
+                .filter(|(_, answer)| answer.is_none())
+                .map(|(prompt, _)| prompt.clone())
+                .collect();
+            let typed = if unanswered.is_empty() {
+                Zeroizing::new(Vec::new())
+            } else if background {
+                // Warm-up never prompts
+                return Err(anyhow!("Authentication needs user input"));
+            } else {
+                app_handle
+                    .state::<AppState>()
+                    .auth_prompts
+                    .ask(
+                        app_handle,
+                        AuthPromptRequest {
+                            prompt_id: String::new(),
+                            host: connection.hostname.clone(),
+                            port: connection.port,
+                            username: connection.username.clone(),
+                            name,
+                            instructions,
+                            prompts: unanswered,
+                        },
+                    )
+                    .await?
+            };
+
+            let answers = auth_prompt::complete(answers, &typed)?;
+            response = session
+                .authenticate_keyboard_interactive_respond(answers)
+                .await?;
+        }
+    }
+
+    /// Open a shell session on an authenticated transport
+    pub(crate) async fn open(
+        mut session: SshTransport,
+        connection: Connection,
+        auth_method: AuthMethod,
+        app_handle: AppHandle,
+        trace: Arc<SessionTrace>,
+    ) -> Result<Self> {
+        let session_id = Uuid::new_v4().to_string();
+        tracing::info!(
+            "[terminal.rs] SshSession::open - Session ID: {}",
+            session_id
+        );
+
+        // Login automation for devices that prompt for credentials after connect
+        let login_password = match &auth_method {
+            AuthMethod::Password { password } => Some(password.as_str()),
+            AuthMethod::PublicKey { .. } | AuthMethod::StoredKey { .. } => None,
+        };
+        let mut login = LoginScript::new(
+            &connection.session_options.login_script,
+            &connection.username,
+            login_password,
+        )?;
+
+        // Output triggers (auto-answers, alerts, highlights)
+        let mut triggers = Triggers::new(&connection.session_options.triggers)?;
+        let connection_name = connection.name.clone();
+
+        // Open a channel with PTY
+        let mut channel = Self::open_channel(&session, &connection, (80, 24), &trace).await?;
+
+        // Custom remote command (and/or command-line locale) instead of the default shell
+        let shell_command = connection.session_options.shell_command()?;
+        if let Some(command) = &shell_command {
+            tracing::info!("[terminal.rs] Remote command: {}", command);
+            trace.record("channel", format!("Remote command: {}", command));
+        }
+
+        // Network drops: reconnect with the same credentials (policy enabled)
+        let reconnect_with = connection
+            .session_options
+            .reconnect
+            .enabled
+            .then(|| (connection.clone(), auth_method.clone()));
+
+        // Startup commands are sent one at a time, each once the shell shows a prompt
+        let mut startup = if connection.session_options.startup_commands()?.is_empty() {
+            None
+        } else {
+            let profiles = app_handle
+                .state::<AppState>()
+                .db
+                .get_setting(prompt::SETTING_PROFILES)
+                .await
+                .ok()
+                .flatten();
+            let profiles = prompt::load_profiles(profiles.as_deref()).unwrap_or_else(|e| {
+                tracing::warn!("[terminal.rs] {}, using built-in prompt profiles", e);
+                prompt::builtin_profiles()
+            });
+            connection.session_options.startup_sequence(&profiles)?
+        };
+
+        // Legacy hosts: convert output to UTF-8 and input back to the host encoding
+        let mut converter = connection.session_options.encoding_converter()?;
+        if let Some(converter) = &converter {
+            tracing::info!("[terminal.rs] Session encoding: {}", converter.name());
+            trace.record("channel", format!("Session encoding: {}", converter.name()));
+        }
+
+        // Line mode: local echo and line editing for network equipment
+        let mut line_editor = connection.session_options.line_mode.then(LineEditor::new);
+        if line_editor.is_some() {
+            trace.record("channel", "Line mode enabled");
+        }
+
+        // Protected hosts: dangerous commands wait for the user's confirmation
+        let mut guard = match connection.session_options.protection {
+            ProtectionLevel::Protected => {
+                let patterns = app_handle
+                    .state::<AppState>()
+                    .db
+                    .get_setting(command_guard::SETTING_PATTERNS)
+                    .await
+                    .ok()
+                    .flatten();
+                trace.record(
+                    "channel",
+                    "Protected host: dangerous commands need confirmation",
+                );
+                Some(CommandGuard::from_setting(patterns.as_deref())?)
+            }
+            ProtectionLevel::Standard => None,
+        };
+        let mut held_command: Option<String> = None;
+        let host = connection.hostname.clone();
+
+        // Clipboard bridge: remote rpbcopy/rpbpaste through OSC 52
+        let clipboard_mode = connection.session_options.clipboard_bridge;
+        let mut clipboard_scanner = (clipboard_mode != BridgeMode::Off).then(Osc52Scanner::new);
+        if clipboard_scanner.is_some() {
+            trace.record(
+                "channel",
+                format!("Clipboard bridge enabled ({:?})", clipboard_mode),
+            );
+        }
+
+        // Time-boxed session: disconnected once the limit is reached
+        let mut time_limit = connection.session_options.time_limit().map(TimeLimit::new);
+        if let Some(limit) = &time_limit {
+            trace.record(
+                "channel",
+                format!(
+                    "Session time limit: {} minutes",
+                    limit.limit().as_secs() / 60
+                ),
+            );
+        }
+
+        // Create command channel BEFORE spawning the listener
+        // This ensures we can send commands immediately
+        let (command_tx, mut command_rx) = mpsc::channel::<SessionCommand>(100);
+
+        // Buffer for initial SSH output (MOTD, welcome message, first prompt).
+        // Streaming before the frontend is listening would drop that data. Instead
+        // we buffer all data until the frontend calls attach_session_output(), which
+        // atomically drains the buffer and switches to streaming mode over its
+        // channel. No timing hacks needed.
+        let output = Arc::new(SessionOutput::new());
+        let output_clone = Arc::clone(&output);
+
+        let screen = snapshot::new_screen_state();
+        let screen_clone = Arc::clone(&screen);
+        let trace_clone = Arc::clone(&trace);
+
+        // Shell integration: remote cwd and command boundaries (OSC 7 / 133)
+        let mut shell = shell_integration::Tracker::new();
+        let cwd = shell.cwd();
+
+        // Traffic and latency (session-stats events)
+        let mut stats_meter = StatsMeter::new(&session_id);
+        let stats = stats_meter.state();
+        let stats_watched = Arc::new(AtomicBool::new(false));
+        let stats_watched_clone = Arc::clone(&stats_watched);
+
+        // Spawn task to manage the SSH channel BEFORE requesting shell
+        // This ensures the listener is active when MOTD arrives
+        let session_id_clone = session_id.clone();
+        let history_connection_id = connection.id.clone();
+        tokio::spawn(async move {
+            // Request shell or exec the configured command (PTY was already allocated above)
+            tracing::info!("[terminal.rs] Requesting shell...");
+            if let Err(e) = Self::start_shell(&channel, shell_command.as_deref()).await {
+                tracing::error!("[terminal.rs] Failed to request shell: {}", e);
+                let _ = app_handle.emit(
+                    "terminal-error",
+                    serde_json::json!({
+                        "sessionId": session_id_clone,
+                        "error": format!("Failed to start shell: {}", e),
+                    }),
+                );
+                return;
+            }
+            tracing::info!("[terminal.rs] Shell started, buffering initial output");
+            trace_clone.record("channel", "Shell started");
+
+            // Usage history (saved connections only), completed when the loop ends
+            let db = app_handle.state::<AppState>().db.clone();
+            let started_at = chrono::Utc::now().timestamp();
+            let history_id = match db
+                .start_connection_session(&history_connection_id, started_at)
+                .await
+            {
+                Ok(id) => id,
+                Err(e) => {
+                    tracing::warn!("[terminal.rs] Failed to record session start: {}", e);
+                    None
+                }
+            };
+            let mut bytes_in: u64 = 0;
+            let mut bytes_out: u64 = 0;
+            let mut exit_status: Option<u32> = None;
+
+            // Login script steps that don't wait for output go out first
+            if let Some(response) = login.as_mut().and_then(|script| script.start()) {
+                trace_clone.record("login", "Login script steps sent without waiting");
+                let response = Zeroizing::new(encoding::encode_input(
+                    converter.as_mut(),
+                    response.to_vec(),
+                ));
+                bytes_out += response.len() as u64;
+                if let Err(e) = channel.data(&response[..]).await {
+                    tracing::error!("[terminal.rs] Failed to send login script response: {}", e);
+                }
+            }
+            if login.as_ref().is_some_and(|script| script.is_done()) {
+                tracing::info!("[terminal.rs] Login script completed");
+                login = None;
+            }
+
+            // Each login script step waits for its output until its own timeout
+            let mut login_deadline = login
+                .as_ref()
+                .and_then(|l| l.timeout())
+                .map(|t| tokio::time::Instant::now() + t);
+            // Startup commands wait until the login script has finished
+            let mut startup_deadline = startup
+                .as_ref()
+                .filter(|_| login.is_none())
+                .map(|s| tokio::time::Instant::now() + s.timeout());
+
+            // System sleep: keep-alives missed while suspended are not sent in a
+            // burst on resume; the connection is probed once instead
+            let mut sleep_check = tokio::time::interval(power::CHECK_INTERVAL);
+            sleep_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut sleep_detector = SleepDetector::new(power::CHECK_INTERVAL);
+            let mut resumed_after: Option<std::time::Duration> = None;
+
+            // Traffic and latency report; the latency probe runs apart from
+            // the loop, on the transport it shares
+            let mut stats_tick = tokio::time::interval(session_stats::REPORT_INTERVAL);
+            stats_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut session = Arc::new(session);
+            let mut latency_probe: Option<tokio::task::JoinHandle<Option<std::time::Duration>>> =
+                None;
+
+            // Last known terminal size (reconnects reuse it)
+            let mut size: (u32, u32) = (80, 24);
+            // Connection dropped: reconnect before the next iteration
+            let mut lost: Option<String> = None;
+            // Output received but not sent to the frontend yet
+            let mut batch = OutputBatch::default();
+            // ZMODEM transfer (sz/rz) found in the output: it owns the channel
+            // until it ends
+            let mut zmodem_detector = zmodem::Detector::new();
+            let mut zmodem: Option<Zmodem> = None;
+            let mut zmodem_deadline: Option<tokio::time::Instant> = None;
+
+            // Start the event loop immediately to capture all output including MOTD
+            loop {
+                // Resumed from system sleep: check the server still answers
+                if let Some(slept) = resumed_after.take() {
+                    tracing::info!(
+                        "[terminal.rs] System resumed after {}s, probing connection",
+                        slept.as_secs()
+                    );
+                    trace_clone.record(
+                        "power",
+                        format!("System resumed after {}s", slept.as_secs()),
+                    );
+                    let _ = app_handle.emit(
+                        "session-sleep-detected",
+                        serde_json::json!({
+                            "sessionId": session_id_clone,
+                            "sleptSecs": slept.as_secs(),
+                        }),
+                    );
+
+                    if let Err(e) = probe_transport(&session).await {
+                        tracing::warn!("[terminal.rs] Connection lost during system sleep: {}", e);
+                        trace_clone.record("power", format!("Resume probe failed: {}", e));
+                        if reconnect_with.is_none() {
+                            let _ = app_handle.emit(
+                                "connection-dead",
+                                serde_json::json!({
+                                    "sessionId": session_id_clone,
+                                    "reason": "Connection lost during system sleep",
+                                    "reconnect": true,
+                                }),
+                            );
+                            // The frontend reconnects: no terminal-closed event
                             break;
                         }
+                        lost = Some("Connection lost during system sleep".to_string());
+                    } else {
+                        trace_clone.record("power", "Connection alive after resume");
+                        sleep_detector.check();
+                        let _ = app_handle.emit(
+                            "session-resumed",
+                            serde_json::json!({
+                                "sessionId": session_id_clone,
+                            }),
+                        );
+                    }
+                }
+
+                // Transfer cancelled by the user or timed out
+                if zmodem.as_ref().is_some_and(Zmodem::is_done) {
+                    zmodem = None;
+                    zmodem_deadline = None;
+                }
+
+                // Connection dropped: same session on a new transport, or give up
+                if let Some(reason) = lost.take() {
+                    batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                    if let Some(mut transfer) = zmodem.take() {
+                        let mut step = transfer.cancel(&reason);
+                        step.send.clear();
+                        send_zmodem_step(
+                            &channel,
+                            &app_handle,
+                            &session_id_clone,
+                            &trace_clone,
+                            transfer.direction(),
+                            step,
+                        )
+                        .await;
+                        zmodem_deadline = None;
+                    }
+                    let Some((connection, auth_method)) = &reconnect_with else {
+                        break;
+                    };
+                    let Some((transport, reopened)) = Self::reconnect(
+                        connection,
+                        auth_method,
+                        &app_handle,
+                        &session_id_clone,
+                        &mut size,
+                        &trace_clone,
+                        &mut command_rx,
+                        &reason,
+                    )
+                    .await
+                    else {
+                        break;
+                    };
+                    session = Arc::new(transport);
+                    channel = reopened;
+                    sleep_detector.check();
+                    // Remote forwards are requested through this loop: not awaited here
+                    let forwards = app_handle.state::<AppState>().forwards.clone();
+                    let restore_app = app_handle.clone();
+                    let restore_session = session_id_clone.clone();
+                    tokio::spawn(async move {
+                        forwards
+                            .restore_remote(&restore_app, &restore_session)
+                            .await;
+                    });
+                }
+
+                let flush_at = batch.deadline();
+                tokio::select! {
+                    // System sleep check
+                    _ = sleep_check.tick() => {
+                        resumed_after = sleep_detector.check();
+                    }
+                    // Traffic and latency report
+                    _ = stats_tick.tick() => {
+                        // Probe sent at the previous report; one still waiting is
+                        // unanswered so far (it gives up after LATENCY_TIMEOUT)
+                        let latency = match latency_probe.take_if(|probe| probe.is_finished()) {
+                            Some(probe) => probe.await.ok().flatten(),
+                            None => None,
+                        };
+                        let stats = stats_meter.update(bytes_in, bytes_out, latency, std::time::Instant::now());
+                        let _ = app_handle.emit(session_stats::EVENT_STATS, &stats);
+                        if latency_probe.is_none() && stats_watched_clone.load(Ordering::Relaxed) {
+                            latency_probe = Some(tokio::spawn(measure_latency(Arc::clone(&session))));
+                        }
+                    }
+                    // Batched output is due
+                    _ = async {
+                        match flush_at {
+                            Some(deadline) => tokio::time::sleep_until(deadline).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                    }
+                    // Time-boxed session: report the remaining time, disconnect at expiry
+                    _ = async {
+                        match &time_limit {
+                            Some(limit) => tokio::time::sleep(limit.until_next()).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        let Some(limit) = time_limit.as_mut() else {
+                            continue;
+                        };
+                        let (remaining, warning) = match limit.check() {
+                            LimitEvent::Countdown(remaining) => (remaining, false),
+                            LimitEvent::Warning(remaining) => {
+                                tracing::info!("[terminal.rs] Session time limit reached in {}s", remaining.as_secs());
+                                trace_clone.record("limit", format!("Session ends in {}s", remaining.as_secs()));
+                                (remaining, true)
+                            }
+                            LimitEvent::Expired => {
+                                let reason = format!(
+                                    "Session time limit of {} minutes reached",
+                                    limit.limit().as_secs() / 60
+                                );
+                                tracing::warn!("[terminal.rs] {} on {}, disconnecting", reason, host);
+                                trace_clone.record("limit", reason.clone());
+                                if let Err(e) = audit::record(
+                                    app_handle.state::<AppState>().db.pool(),
+                                    audit::EVENT_SESSION_EXPIRED,
+                                    &format!("{}: {}", host, reason),
+                                )
+                                .await
+                                {
+                                    tracing::error!("[terminal.rs] Failed to record audit event: {}", e);
+                                }
+                                let _ = channel.eof().await;
+                                let _ = session.disconnect(russh::Disconnect::ByApplication, &reason, "").await;
+                                let _ = app_handle.emit(
+                                    "session-expired",
+                                    serde_json::json!({
+                                        "sessionId": session_id_clone,
+                                        "reason": reason,
+                                    }),
+                                );
+                                let _ = app_handle.emit(
+                                    "terminal-closed",
+                                    serde_json::json!({
+                                        "sessionId": session_id_clone,
+                                    }),
+                                );
+                                break;
+                            }
+                        };
+                        let _ = app_handle.emit(
+                            "session-time-limit",
+                            serde_json::json!({
+                                "sessionId": session_id_clone,
+                                "remainingSecs": remaining.as_secs(),
+                                "warning": warning,
+                            }),
+                        );
+                    }
+                    // Login script step timed out: stop automation and report
+                    _ = async {
+                        match login_deadline {
+                            Some(deadline) => tokio::time::sleep_until(deadline).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        let step = login.as_ref().map(|l| l.current_step()).unwrap_or_default();
+                        tracing::warn!("[terminal.rs] Login script timed out at step {}", step);
+                        trace_clone.record("login", format!("Login script timed out at step {}", step));
+                        let _ = app_handle.emit(
+                            "login-script-failed",
+                            serde_json::json!({
+                                "sessionId": session_id_clone,
+                                "step": step,
+                                "error": "Timed out waiting for expected output",
+                            }),
+                        );
+                        login = None;
+                        login_deadline = None;
+                        startup = None;
+                        startup_deadline = None;
+                    }
+                    // No prompt seen in time: send the next startup command anyway
+                    _ = async {
+                        match startup_deadline {
+                            Some(deadline) => tokio::time::sleep_until(deadline).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        if let Some(sequence) = startup.as_mut() {
+                            tracing::warn!("[terminal.rs] No prompt detected within {:?}, sending startup command anyway", sequence.timeout());
+                            trace_clone.record("startup", "Prompt wait timed out");
+                            if let Some(command) = sequence.on_timeout() {
+                                let command = encoding::encode_input(converter.as_mut(), command.into_bytes());
+                                bytes_out += command.len() as u64;
+                                if let Err(e) = channel.data(&command[..]).await {
+                                    tracing::error!("[terminal.rs] Failed to send startup command: {}", e);
+                                }
+                            }
+                            startup_deadline = (!sequence.is_done())
+                                .then(|| tokio::time::Instant::now() + sequence.timeout());
+                        }
+                        if startup_deadline.is_none() {
+                            startup = None;
+                        }
+                    }
+                    // ZMODEM upload: next data subpacket
+                    _ = std::future::ready(()), if zmodem.as_ref().is_some_and(Zmodem::wants_to_send) => {
+                        if let Some(transfer) = zmodem.as_mut() {
+                            let step = transfer.send_next();
+                            bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, transfer.direction(), step).await;
+                            zmodem_deadline = Some(tokio::time::Instant::now() + zmodem::TIMEOUT);
+                        }
+                    }
+                    // ZMODEM transfer stalled (host gone quiet or no answer from the user)
+                    _ = async {
+                        match zmodem_deadline {
+                            Some(deadline) => tokio::time::sleep_until(deadline).await,
+                            None => std::future::pending().await,
+                        }
+                    } => {
+                        zmodem_deadline = None;
+                        if let Some(transfer) = zmodem.as_mut() {
+                            tracing::warn!("[terminal.rs] ZMODEM transfer timed out on {}", host);
+                            let step = transfer.cancel("Timed out");
+                            bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, transfer.direction(), step).await;
+                        }
                     }
                     // Handle commands from SessionManager
                     Some(cmd) = command_rx.recv() => {
                         match cmd {
                             SessionCommand::SendInput(data) => {
+                                // Keystrokes would corrupt a transfer: Ctrl+C cancels it
+                                if let Some(transfer) = zmodem.as_mut() {
+                                    if data.contains(&0x03) {
+                                        let step = transfer.cancel("Cancelled");
+                                        bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, transfer.direction(), step).await;
+                                    }
+                                    continue;
+                                }
+                                // Echo and the guard's screen line come after pending output
+                                batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                                let (data, confirm) = filter_input(data, line_editor.as_mut(), guard.as_mut(), &screen_clone, &output_clone);
+                                if let Some(confirm) = confirm {
+                                    trace_clone.record("guard", format!("Command held for confirmation: {}", confirm.command));
+                                    request_confirmation(&app_handle, &session_id_clone, &confirm);
+                                    held_command = Some(confirm.command);
+                                }
+                                if data.is_empty() {
+                                    continue;
+                                }
+                                let data = encoding::encode_input(converter.as_mut(), data);
+                                bytes_out += data.len() as u64;
+                                if let Err(e) = channel.data(&data[..]).await {
+                                    eprintln!("Error sending input: {}", e);
+                                    if reconnect_with.is_some() {
+                                        lost = Some("Connection lost".to_string());
+                                        continue;
+                                    }
+                                    break;
+                                }
+                            }
+                            SessionCommand::SendSecret { secret, reply } => {
+                                if zmodem.is_some() {
+                                    let _ = reply.send(Err(anyhow!("A ZMODEM transfer is in progress")));
+                                    continue;
+                                }
+                                // The prompt may still be in pending output
+                                batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                                let at_prompt = snapshot::cursor_line(&screen_clone)
+                                    .is_some_and(|line| prompt::is_password_prompt(&line));
+                                if !at_prompt {
+                                    let _ = reply.send(Err(anyhow!("The terminal is not at a password prompt")));
+                                    continue;
+                                }
+                                trace_clone.record("input", "Stored secret sent");
+                                let data = Zeroizing::new(encoding::encode_input(converter.as_mut(), secret.to_vec()));
+                                bytes_out += data.len() as u64;
+                                let result = channel
+                                    .data(&data[..])
+                                    .await
+                                    .map_err(|e| anyhow!("Failed to send stored secret: {}", e));
+                                let _ = reply.send(result);
+                            }
+                            SessionCommand::ConfirmCommand(approved) => {
+                                let (Some(guard), Some(command)) = (guard.as_mut(), held_command.take()) else {
+                                    continue;
+                                };
+                                if approved {
+                                    tracing::warn!("[terminal.rs] Dangerous command confirmed on {}: {}", host, command);
+                                    trace_clone.record("guard", format!("Command confirmed: {}", command));
+                                    if let Err(e) = audit::record(
+                                        app_handle.state::<AppState>().db.pool(),
+                                        audit::EVENT_DANGEROUS_COMMAND,
+                                        &format!("{}: {}", host, command),
+                                    )
+                                    .await
+                                    {
+                                        tracing::error!("[terminal.rs] Failed to record audit event: {}", e);
+                                    }
+                                } else {
+                                    tracing::info!("[terminal.rs] Dangerous command cancelled on {}", host);
+                                    trace_clone.record("guard", format!("Command cancelled: {}", command));
+                                }
+
+                                let output = guard.resolve(approved);
+                                // Input typed while waiting may hold another one
+                                if let Some(confirm) = output.confirm {
+                                    trace_clone.record("guard", format!("Command held for confirmation: {}", confirm.command));
+                                    request_confirmation(&app_handle, &session_id_clone, &confirm);
+                                    held_command = Some(confirm.command);
+                                }
+                                let data = encoding::encode_input(converter.as_mut(), output.send);
+                                bytes_out += data.len() as u64;
                                 if let Err(e) = channel.data(&data[..]).await {
                                     eprintln!("Error sending input: {}", e);
+                                    if reconnect_with.is_some() {
+                                        lost = Some("Connection lost".to_string());
+                                        continue;
+                                    }
                                     break;
                                 }
                             }
                             SessionCommand::Resize { cols, rows } => {
+                                size = (cols, rows);
+                                trace_clone.record("window", format!("Window change requested: {}x{}", cols, rows));
                                 if let Err(e) = channel.window_change(cols, rows, 0, 0).await {
                                     eprintln!("Error resizing terminal: {}", e);
                                 }
                             }
+                            SessionCommand::Forward(command) => {
+                                // Forwarding needs the transport to itself
+                                if let Some(probe) = latency_probe.take() {
+                                    let _ = probe.await;
+                                }
+                                match Arc::get_mut(&mut session) {
+                                    Some(transport) => run_forward_command(transport, &trace_clone, command).await,
+                                    None => tracing::warn!("[terminal.rs] Transport busy, port forward command dropped"),
+                                }
+                            }
+                            SessionCommand::Zmodem(reply) => {
+                                let Some(transfer) = zmodem.as_mut() else {
+                                    continue;
+                                };
+                                let step = transfer.reply(reply);
+                                bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, transfer.direction(), step).await;
+                                zmodem_deadline = Some(tokio::time::Instant::now() + zmodem::TIMEOUT);
+                            }
                             SessionCommand::Close => {
+                                trace_clone.record("channel", "Closing channel (disconnect by application)");
                                 let _ = channel.eof().await;
                                 let _ = session.disconnect(russh::Disconnect::ByApplication, "", "").await;
                                 break;
                             }
                         }
                     }
-                    // Read output from SSH channel
-                    msg = channel.wait() => {
+                    // Frontend caught up with paused output
+                    _ = output_clone.resumed(), if output_clone.is_paused() => {}
+                    // Read output from SSH channel (not while paused: the
+                    // server stalls once the channel window is used up)
+                    msg = channel.wait(), if !output_clone.is_paused() => {
+                        // Anything but output ends the batch
+                        if !matches!(msg, Some(ChannelMsg::Data { .. })) {
+                            batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                        }
                         match msg {
                             Some(ChannelMsg::Data { ref data }) => {
-                                let mut buf_guard = initial_buffer_clone.lock().await;
-                                if let Some(ref mut buf) = *buf_guard {
-                                    // Buffering mode: accumulate until frontend calls claim.
-                                    buf.extend_from_slice(data);
-                                } else {
-                                    // Streaming mode: frontend has already claimed the buffer.
-                                    let data_base64 = base64::engine::general_purpose::STANDARD.encode(data);
-                                    let _ = app_handle.emit(
-                                        "terminal-data",
-                                        serde_json::json!({
-                                            "sessionId": session_id_clone,
-                                            "data": data_base64,
-                                        }),
-                                    );
+                                bytes_in += data.len() as u64;
+                                // Transfer running: the output is protocol data (not decoded)
+                                let mut data: &[u8] = &data[..];
+                                let rest;
+                                if let Some(transfer) = zmodem.as_mut() {
+                                    let step = transfer.feed(data);
+                                    bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, transfer.direction(), step).await;
+                                    zmodem_deadline = Some(tokio::time::Instant::now() + zmodem::TIMEOUT);
+                                    if !transfer.is_done() {
+                                        continue;
+                                    }
+                                    if transfer.direction() == zmodem::Direction::Download {
+                                        zmodem_detector.skip_over_and_out();
+                                    }
+                                    rest = transfer.take_rest();
+                                    data = &rest;
+                                    zmodem = None;
+                                    zmodem_deadline = None;
+                                }
+                                let scan = zmodem_detector.feed(data);
+                                if let Some((direction, start)) = scan.start {
+                                    tracing::info!("[terminal.rs] ZMODEM {} started on {}", direction.as_str(), host);
+                                    trace_clone.record("zmodem", format!("Transfer started ({})", direction.as_str()));
+                                    let mut transfer = Zmodem::new(direction);
+                                    let step = transfer.feed(&start);
+                                    bytes_out += send_zmodem_step(&channel, &app_handle, &session_id_clone, &trace_clone, direction, step).await;
+                                    zmodem = Some(transfer);
+                                    zmodem_deadline = Some(tokio::time::Instant::now() + zmodem::TIMEOUT);
+                                }
+                                if scan.display.is_empty() {
+                                    continue;
+                                }
+
+                                let decoded;
+                                let data: &[u8] = match converter.as_mut() {
+                                    Some(converter) => {
+                                        decoded = converter.decode(&scan.display);
+                                        &decoded
+                                    }
+                                    None => &scan.display,
+                                };
+                                // Line mode: the host's echo of a sent line was shown locally
+                                let data = match line_editor.as_mut() {
+                                    Some(editor) => editor.strip_echo(data),
+                                    None => data,
+                                };
+                                if data.is_empty() {
+                                    continue;
+                                }
+
+                                if let Some(script) = login.as_mut() {
+                                    let step = script.current_step();
+                                    match script.on_output(data) {
+                                        Ok(Some(response)) => {
+                                            // Responses may contain secrets: never log their content
+                                            trace_clone.record("login", format!("Login script step {} matched", step));
+                                            let response = Zeroizing::new(encoding::encode_input(converter.as_mut(), response.to_vec()));
+                                            bytes_out += response.len() as u64;
+                                            if let Err(e) = channel.data(&response[..]).await {
+                                                tracing::error!("[terminal.rs] Failed to send login script response: {}", e);
+                                            }
+                                            login_deadline = script.timeout().map(|t| tokio::time::Instant::now() + t);
+                                        }
+                                        Ok(None) => {}
+                                        Err(e) => {
+                                            tracing::warn!("[terminal.rs] Login script failed at step {}: {}", step, e);
+                                            trace_clone.record("login", format!("Login script failed at step {}", step));
+                                            let _ = app_handle.emit(
+                                                "login-script-failed",
+                                                serde_json::json!({
+                                                    "sessionId": session_id_clone,
+                                                    "step": step,
+                                                    "error": e.to_string(),
+                                                }),
+                                            );
+                                            login = None;
+                                            login_deadline = None;
+                                            startup = None;
+                                            startup_deadline = None;
+                                        }
+                                    }
+                                    if login.as_ref().is_some_and(|script| script.is_done()) {
+                                        tracing::info!("[terminal.rs] Login script completed");
+                                        login = None;
+                                        login_deadline = None;
+                                        startup_deadline = startup
+                                            .as_ref()
+                                            .map(|s| tokio::time::Instant::now() + s.timeout());
+                                    }
+                                } else if let Some(sequence) = startup.as_mut() {
+                                    if let Some(command) = sequence.on_output(data) {
+                                        trace_clone.record("startup", "Prompt detected, sending startup command");
+                                        let command = encoding::encode_input(converter.as_mut(), command.into_bytes());
+                                        bytes_out += command.len() as u64;
+                                        if let Err(e) = channel.data(&command[..]).await {
+                                            tracing::error!("[terminal.rs] Failed to send startup command: {}", e);
+                                        }
+                                        startup_deadline = (!sequence.is_done())
+                                            .then(|| tokio::time::Instant::now() + sequence.timeout());
+                                    }
+                                    if sequence.is_done() {
+                                        tracing::info!("[terminal.rs] Startup commands sent");
+                                        startup = None;
+                                        startup_deadline = None;
+                                    }
+                                }
+
+                                if let Some(scanner) = clipboard_scanner.as_mut() {
+                                    for request in scanner.feed(data) {
+                                        // Clipboard content is never logged
+                                        let (action, length) = match request {
+                                            ClipboardRequest::Copy(text) => {
+                                                let length = text.chars().count();
+                                                if let Err(e) = app_handle.clipboard().write_text(text) {
+                                                    tracing::warn!("[terminal.rs] Failed to write clipboard: {}", e);
+                                                    continue;
+                                                }
+                                                ("copy", length)
+                                            }
+                                            ClipboardRequest::Paste if clipboard_mode == BridgeMode::CopyPaste => {
+                                                let text = Zeroizing::new(app_handle.clipboard().read_text().unwrap_or_default());
+                                                let response = Zeroizing::new(clipboard_bridge::paste_response(&text));
+                                                bytes_out += response.len() as u64;
+                                                if let Err(e) = channel.data(&response[..]).await {
+                                                    tracing::error!("[terminal.rs] Failed to send clipboard: {}", e);
+                                                    continue;
+                                                }
+                                                ("paste", text.chars().count())
+                                            }
+                                            ClipboardRequest::Paste => {
+                                                trace_clone.record("clipboard", "Clipboard read refused (copy-only bridge)");
+                                                continue;
+                                            }
+                                        };
+                                        trace_clone.record("clipboard", format!("Remote {}: {} characters", action, length));
+                                        let _ = app_handle.emit(
+                                            "remote-clipboard",
+                                            serde_json::json!({
+                                                "sessionId": session_id_clone,
+                                                "action": action,
+                                                "length": length,
+                                            }),
+                                        );
+                                    }
+                                }
+
+                                for event in shell.feed(data) {
+                                    shell_integration::emit(&app_handle, &session_id_clone, &event);
+                                }
+
+                                let mut responses = Vec::new();
+                                for fired in triggers.as_mut().map(|t| t.feed(data)).unwrap_or_default() {
+                                    trace_clone.record("trigger", format!("Trigger {} matched", fired.rule));
+                                    match triggers::response(&app_handle, &fired.action).await {
+                                        Ok(Some(text)) => responses.push(text),
+                                        Ok(None) => triggers::emit(&app_handle, &session_id_clone, &connection_name, &fired),
+                                        Err(e) => {
+                                            tracing::warn!("[terminal.rs] Trigger {} failed: {}", fired.rule, e);
+                                            trace_clone.record("trigger", format!("Trigger {} failed: {}", fired.rule, e));
+                                        }
+                                    }
+                                }
+
+                                if batch.push(data) {
+                                    batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                                }
+
+                                // Trigger responses are typed like input: through line mode and
+                                // the guard, after the output they answer
+                                if !responses.is_empty() {
+                                    batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+                                }
+                                for text in responses {
+                                    let (text, confirm) = filter_input(text.into_bytes(), line_editor.as_mut(), guard.as_mut(), &screen_clone, &output_clone);
+                                    if let Some(confirm) = confirm {
+                                        trace_clone.record("guard", format!("Trigger response held for confirmation: {}", confirm.command));
+                                        request_confirmation(&app_handle, &session_id_clone, &confirm);
+                                        held_command = Some(confirm.command);
+                                    }
+                                    if text.is_empty() {
+                                        continue;
+                                    }
+                                    let text = encoding::encode_input(converter.as_mut(), text);
+                                    bytes_out += text.len() as u64;
+                                    if let Err(e) = channel.data(&text[..]).await {
+                                        tracing::error!("[terminal.rs] Failed to send trigger response: {}", e);
+                                    }
                                 }
                             }
-                            Some(ChannelMsg::ExitStatus { exit_status }) => {
+                            Some(ChannelMsg::ExitStatus { exit_status: status }) => {
+                                trace_clone.record("channel", format!("Exit status: {}", status));
+                                exit_status = Some(status);
                                 let _ = app_handle.emit(
                                     "terminal-exit",
                                     serde_json::json!({
                                         "sessionId": session_id_clone,
-                                        "exitStatus": exit_status,
+                                        "exitStatus": status,
                                     }),
                                 );
                                 break;
                             }
                             Some(ChannelMsg::Eof) => {
+                                trace_clone.record("channel", "EOF received");
                                 let _ = app_handle.emit(
                                     "terminal-closed",
                                     serde_json::json!({
@@ -462,29 +2067,309 @@ impl SshSession {
                                 );
                                 break;
                             }
-                            None => break,
+                            Some(ChannelMsg::WindowAdjusted { new_size }) => {
+                                trace_clone.record("window", format!("Remote window adjusted: {} bytes", new_size));
+                            }
+                            None => {
+                                trace_clone.record("channel", "Channel closed");
+                                if reconnect_with.is_some() {
+                                    lost = Some("Connection lost".to_string());
+                                    continue;
+                                }
+                                // Transport gone too: the server stopped answering
+                                // keep-alives or the network dropped
+                                if session.is_closed() {
+                                    tracing::warn!("[terminal.rs] Connection lost for session {}", session_id_clone);
+                                    let _ = app_handle.emit(
+                                        "connection-dead",
+                                        serde_json::json!({
+                                            "sessionId": session_id_clone,
+                                            "reason": "Server not responding",
+                                        }),
+                                    );
+                                    let _ = app_handle.emit(
+                                        "terminal-closed",
+                                        serde_json::json!({
+                                            "sessionId": session_id_clone,
+                                        }),
+                                    );
+                                }
+                                break;
+                            }
                             other => {
                                 tracing::warn!("[terminal.rs] Unhandled channel message: {:?}", other);
+                                trace_clone.record("channel", format!("Unhandled message: {:?}", other));
                             }
                         }
                     }
                 }
             }
+            batch.flush(|data| deliver_output(&screen_clone, &output_clone, data));
+            if let Some(mut transfer) = zmodem.take() {
+                let mut step = transfer.cancel("Session closed");
+                step.send.clear();
+                send_zmodem_step(
+                    &channel,
+                    &app_handle,
+                    &session_id_clone,
+                    &trace_clone,
+                    transfer.direction(),
+                    step,
+                )
+                .await;
+            }
+
+            if let Some(id) = history_id {
+                if let Err(e) = db
+                    .finish_connection_session(
+                        id,
+                        chrono::Utc::now().timestamp(),
+                        bytes_in as i64,
+                        bytes_out as i64,
+                        exit_status.map(i64::from),
+                    )
+                    .await
+                {
+                    tracing::warn!("[terminal.rs] Failed to record session end: {}", e);
+                }
+            }
+
+            // The viewer of a share loses the screen with the session, and
+            // its forwards their transport
+            let state = app_handle.state::<AppState>();
+            state.shares.revoke(&session_id_clone).await;
+            state.forwards.stop_session(&session_id_clone).await;
         });
 
         Ok(Self {
             id: session_id,
             command_tx,
-            initial_buffer,
+            output,
+            screen,
+            trace,
+            quick_connection: StdMutex::new(None),
+            endpoint: (connection.hostname.clone(), connection.port),
+            connection_id: connection.id.clone(),
+            cwd,
+            stats,
+            stats_watched,
         })
     }
 
-    /// Drain the initial output buffer and switch to streaming mode.
-    /// Returns all bytes received before the frontend registered its listener.
-    /// After this call, new SSH data is emitted as `terminal-data` events.
-    pub async fn claim_initial_output(&self) -> Vec<u8> {
-        let mut guard = self.initial_buffer.lock().await;
-        guard.take().unwrap_or_default()
+    /// Open a session channel with a PTY of `size` (cols, rows), and send
+    /// the locale through env requests when configured
+    async fn open_channel(
+        session: &SshTransport,
+        connection: &Connection,
+        (cols, rows): (u32, u32),
+        trace: &SessionTrace,
+    ) -> Result<russh::Channel<client::Msg>> {
+        tracing::info!("[terminal.rs] Opening channel...");
+        let channel = session.channel_open_session().await?;
+        tracing::info!("[terminal.rs] Channel opened");
+        trace.record(
+            "channel",
+            format!("Session channel opened ({:?})", channel.id()),
+        );
+
+        // Request PTY
+        tracing::info!(
+            "[terminal.rs] Requesting PTY (xterm-256color, {}x{})...",
+            cols,
+            rows
+        );
+        let utf8 = connection.session_options.encoding_converter()?.is_none();
+        // Line mode echoes locally
+        let echo = !connection.session_options.line_mode;
+        channel
+            .request_pty(
+                true,
+                "xterm-256color",
+                cols,
+                rows,
+                0, // pix_width
+                0, // pix_height
+                &terminal_modes(utf8, echo),
+            )
+            .await?;
+        tracing::info!("[terminal.rs] PTY allocated");
+        trace.record(
+            "channel",
+            format!("PTY allocated (xterm-256color, {}x{})", cols, rows),
+        );
+
+        // Environment variables via env requests, the locale included when
+        // forwarded that way (fixes mojibake on servers defaulting to POSIX)
+        let env = connection.session_options.env_requests()?;
+        if !env.is_empty() {
+            // Values may be secrets: only names are logged
+            let names: Vec<&str> = env.iter().map(|(name, _)| name.as_str()).collect();
+            tracing::info!("[terminal.rs] Sending env requests: {}", names.join(", "));
+            for (name, value) in &env {
+                // Servers without a matching AcceptEnv silently ignore the request
+                if let Err(e) = channel.set_env(false, name.as_str(), value.as_str()).await {
+                    tracing::warn!("[terminal.rs] Failed to send {}: {}", name, e);
+                }
+            }
+            trace.record(
+                "channel",
+                format!(
+                    "Env requests sent (ignored unless the server accepts them): {}",
+                    names.join(", ")
+                ),
+            );
+        }
+
+        Ok(channel)
+    }
+
+    /// Request the shell, or exec the configured command
+    async fn start_shell(
+        channel: &russh::Channel<client::Msg>,
+        shell_command: Option<&str>,
+    ) -> Result<(), russh::Error> {
+        match shell_command {
+            Some(command) => channel.exec(true, command).await,
+            None => channel.request_shell(true).await,
+        }
+    }
+
+    /// Re-establish a dropped connection following the connection's
+    /// reconnect policy: new transport, PTY at the last known size and the
+    /// shell (attached again to the tmux/screen session when resume is on)
+    ///
+    /// Login scripts and startup commands are not replayed, and input typed
+    /// while reconnecting is dropped. Returns None when every attempt failed
+    /// (the session is reported dead) or the session was closed meanwhile.
+    #[allow(clippy::too_many_arguments)]
+    async fn reconnect(
+        connection: &Connection,
+        auth_method: &AuthMethod,
+        app_handle: &AppHandle,
+        session_id: &str,
+        size: &mut (u32, u32),
+        trace: &Arc<SessionTrace>,
+        command_rx: &mut mpsc::Receiver<SessionCommand>,
+        reason: &str,
+    ) -> Option<(SshTransport, russh::Channel<client::Msg>)> {
+        let policy = &connection.session_options.reconnect;
+        tracing::warn!(
+            "[terminal.rs] Session {} lost ({}), reconnecting",
+            session_id,
+            reason
+        );
+        trace.record("reconnect", format!("Connection lost: {}", reason));
+
+        for attempt in 1..=policy.max_attempts {
+            let delay = policy.delay(attempt);
+            let _ = app_handle.emit(
+                "terminal-reconnecting",
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "attempt": attempt,
+                    "maxAttempts": policy.max_attempts,
+                    "delaySecs": delay.as_secs(),
+                    "reason": reason,
+                }),
+            );
+
+            let requested = *size;
+            let reopen = async {
+                tokio::time::sleep(delay).await;
+                trace.record(
+                    "reconnect",
+                    format!("Attempt {}/{}", attempt, policy.max_attempts),
+                );
+                let transport =
+                    Self::establish(connection, auth_method, app_handle, false, false, trace)
+                        .await?;
+                let channel = Self::open_channel(&transport, connection, requested, trace).await?;
+                let shell_command = connection.session_options.shell_command()?;
+                Self::start_shell(&channel, shell_command.as_deref()).await?;
+                Ok::<_, anyhow::Error>((transport, channel))
+            };
+            tokio::pin!(reopen);
+
+            let result = loop {
+                tokio::select! {
+                    result = &mut reopen => break result,
+                    command = command_rx.recv() => match command {
+                        Some(SessionCommand::Close) | None => {
+                            trace.record("reconnect", "Session closed while reconnecting");
+                            return None;
+                        }
+                        Some(SessionCommand::Resize { cols, rows }) => *size = (cols, rows),
+                        // Input and port forwarding requests need a connection
+                        Some(_) => {}
+                    },
+                }
+            };
+
+            match result {
+                Ok((transport, channel)) => {
+                    // Resized while reconnecting
+                    if *size != requested {
+                        let _ = channel.window_change(size.0, size.1, 0, 0).await;
+                    }
+                    tracing::info!(
+                        "[terminal.rs] Session {} reconnected (attempt {})",
+                        session_id,
+                        attempt
+                    );
+                    trace.record("reconnect", format!("Reconnected (attempt {})", attempt));
+                    let _ = app_handle.emit(
+                        "terminal-reconnected",
+                        serde_json::json!({
+                            "sessionId": session_id,
+                            "attempt": attempt,
+                        }),
+                    );
+                    return Some((transport, channel));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "[terminal.rs] Reconnect attempt {}/{} failed: {}",
+                        attempt,
+                        policy.max_attempts,
+                        e
+                    );
+                    trace.record("reconnect", format!("Attempt {} failed: {}", attempt, e));
+                }
+            }
+        }
+
+        let _ = app_handle.emit(
+            "connection-dead",
+            serde_json::json!({
+                "sessionId": session_id,
+                "reason": format!("{} (reconnect failed after {} attempts)", reason, policy.max_attempts),
+            }),
+        );
+        let _ = app_handle.emit(
+            "terminal-closed",
+            serde_json::json!({
+                "sessionId": session_id,
+            }),
+        );
+        None
+    }
+
+    /// Output sink of this session
+    pub fn output(&self) -> &SessionOutput {
+        &self.output
+    }
+
+    /// Protocol-level debug trace of this session
+    pub fn trace(&self) -> &Arc<SessionTrace> {
+        &self.trace
+    }
+
+    /// Port forwarding access to this session's transport
+    pub fn forward_handle(&self) -> ForwardHandle {
+        ForwardHandle {
+            command_tx: self.command_tx.clone(),
+            endpoint: self.endpoint.clone(),
+        }
     }
 
     /// Send input to the SSH channel
@@ -496,23 +2381,294 @@ impl SshSession {
         Ok(())
     }
 
-    /// Resize the terminal
-    pub async fn resize(&self, cols: u32, rows: u32) -> Result<()> {
-        self.command_tx
-            .send(SessionCommand::Resize { cols, rows })
-            .await
-            .map_err(|_| anyhow!("Session closed"))?;
-        Ok(())
-    }
+    /// Type a secret into the SSH channel, bypassing line mode, the
+    /// dangerous command guard and recordings
+    ///
+    /// Refused unless the cursor line is a password prompt.
+    pub async fn send_secret(&self, secret: Zeroizing<Vec<u8>>) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+        self.command_tx
+            .send(SessionCommand::SendSecret { secret, reply })
+            .await
+            .map_err(|_| anyhow!("Session closed"))?;
+        response.await.map_err(|_| anyhow!("Session closed"))?
+    }
+
+    /// Confirm or cancel the dangerous command waiting for confirmation
+    pub async fn confirm_command(&self, approved: bool) -> Result<()> {
+        self.command_tx
+            .send(SessionCommand::ConfirmCommand(approved))
+            .await
+            .map_err(|_| anyhow!("Session closed"))?;
+        Ok(())
+    }
+
+    /// Answer the ZMODEM transfer running in this session
+    pub async fn answer_zmodem(&self, reply: zmodem::Reply) -> Result<()> {
+        self.command_tx
+            .send(SessionCommand::Zmodem(reply))
+            .await
+            .map_err(|_| anyhow!("Session closed"))?;
+        Ok(())
+    }
+
+    /// Resize the terminal
+    pub async fn resize(&self, cols: u32, rows: u32) -> Result<()> {
+        if let Ok(mut screen) = self.screen.lock() {
+            screen.set_size(rows as u16, cols as u16);
+        }
+        self.command_tx
+            .send(SessionCommand::Resize { cols, rows })
+            .await
+            .map_err(|_| anyhow!("Session closed"))?;
+        Ok(())
+    }
+
+    /// Close the session
+    pub async fn close(&self) -> Result<()> {
+        self.command_tx
+            .send(SessionCommand::Close)
+            .await
+            .map_err(|_| anyhow!("Session already closed"))?;
+        Ok(())
+    }
+}
+
+/// Terminal modes sent with the PTY request
+///
+/// The same line discipline OpenSSH sets up for an interactive terminal:
+/// control characters, backspace as DEL, CR to NL on input, NL to CR NL on
+/// output, echo and signals. Without them the server falls back to its own
+/// defaults, which differ between systems (stray ^H, no ^C on some hosts).
+fn terminal_modes(utf8: bool, echo: bool) -> Vec<(Pty, u32)> {
+    vec![
+        (Pty::VINTR, 0x03),   // ^C
+        (Pty::VQUIT, 0x1c),   // ^\
+        (Pty::VERASE, 0x7f),  // DEL (xterm.js backspace)
+        (Pty::VKILL, 0x15),   // ^U
+        (Pty::VEOF, 0x04),    // ^D
+        (Pty::VSUSP, 0x1a),   // ^Z
+        (Pty::VWERASE, 0x17), // ^W
+        (Pty::VLNEXT, 0x16),  // ^V
+        (Pty::VSTART, 0x11),  // ^Q
+        (Pty::VSTOP, 0x13),   // ^S
+        (Pty::ICRNL, 1),
+        (Pty::IXON, 1),
+        (Pty::ISIG, 1),
+        (Pty::ICANON, 1),
+        (Pty::IEXTEN, 1),
+        (Pty::ECHO, u32::from(echo)),
+        (Pty::ECHOE, 1),
+        (Pty::ECHOK, 1),
+        (Pty::ECHOCTL, 1),
+        (Pty::ECHOKE, 1),
+        (Pty::OPOST, 1),
+        (Pty::ONLCR, 1),
+        (Pty::CS8, 1),
+        // Multi-byte characters erased as one (legacy encodings: byte by byte)
+        (Pty::IUTF8, u32::from(utf8)),
+        (Pty::TTY_OP_ISPEED, 38400),
+        (Pty::TTY_OP_OSPEED, 38400),
+    ]
+}
+
+/// Trace an authentication attempt (marks the authentication stage)
+fn trace_auth_attempt(trace: &SessionTrace, method: impl Into<String>) {
+    let method = method.into();
+    trace.record_stage(
+        "auth",
+        format!("Trying {}", method),
+        HandshakeStage::AuthAttempt { method },
+    );
+}
+
+/// Run a port forwarding request on the transport and send back the result
+async fn run_forward_command(
+    session: &mut SshTransport,
+    trace: &SessionTrace,
+    command: ForwardCommand,
+) {
+    match command {
+        ForwardCommand::OpenDirect {
+            host,
+            port,
+            originator,
+            reply,
+        } => {
+            trace.record(
+                "forward",
+                format!("Opening direct-tcpip channel to {}:{}", host, port),
+            );
+            let result = session
+                .channel_open_direct_tcpip(
+                    host,
+                    u32::from(port),
+                    originator.ip().to_string(),
+                    u32::from(originator.port()),
+                )
+                .await
+                .map_err(|e| anyhow!("Failed to open forwarding channel: {}", e));
+            let _ = reply.send(result);
+        }
+        ForwardCommand::Listen {
+            address,
+            port,
+            reply,
+        } => {
+            trace.record(
+                "forward",
+                format!("Requesting remote listener on {}:{}", address, port),
+            );
+            let result = session
+                .tcpip_forward(address, u32::from(port))
+                .await
+                .map_err(|e| anyhow!("{}", e));
+            let _ = reply.send(result);
+        }
+        ForwardCommand::CancelListen {
+            address,
+            port,
+            reply,
+        } => {
+            trace.record(
+                "forward",
+                format!("Cancelling remote listener on {}:{}", address, port),
+            );
+            let result = session
+                .cancel_tcpip_forward(address, u32::from(port))
+                .await
+                .map_err(|e| anyhow!("{}", e));
+            let _ = reply.send(result);
+        }
+    }
+}
+
+/// Ask the frontend to confirm a dangerous command (protected hosts)
+fn request_confirmation(app_handle: &AppHandle, session_id: &str, confirm: &Confirmation) {
+    let _ = app_handle.emit(
+        "command-confirmation",
+        serde_json::json!({
+            "sessionId": session_id,
+            "command": confirm.command,
+            "pattern": confirm.pattern,
+        }),
+    );
+}
+
+/// Send a ZMODEM step's frames to the host and report its events to the
+/// frontend; returns the bytes sent
+async fn send_zmodem_step(
+    channel: &russh::Channel<client::Msg>,
+    app_handle: &AppHandle,
+    session_id: &str,
+    trace: &SessionTrace,
+    direction: zmodem::Direction,
+    step: zmodem::Step,
+) -> u64 {
+    if !step.send.is_empty() {
+        if let Err(e) = channel.data(&step.send[..]).await {
+            tracing::error!("[terminal.rs] Failed to send ZMODEM data: {}", e);
+        }
+    }
+
+    for event in step.events {
+        let (event, payload) = match event {
+            zmodem::Event::Offer { name, size } => {
+                trace.record("zmodem", format!("File offered: {}", name));
+                (
+                    "zmodem-request",
+                    serde_json::json!({
+                        "sessionId": session_id,
+                        "direction": direction.as_str(),
+                        "fileName": name,
+                        "size": size,
+                        "suggestedPath": zmodem::download_path(&name),
+                    }),
+                )
+            }
+            zmodem::Event::Ready => (
+                "zmodem-request",
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "direction": direction.as_str(),
+                }),
+            ),
+            zmodem::Event::Progress {
+                name,
+                transferred,
+                size,
+            } => (
+                "zmodem-progress",
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "direction": direction.as_str(),
+                    "fileName": name,
+                    "transferred": transferred,
+                    "size": size,
+                }),
+            ),
+            zmodem::Event::Completed { name } => {
+                trace.record("zmodem", format!("File transferred: {}", name));
+                (
+                    "zmodem-completed",
+                    serde_json::json!({
+                        "sessionId": session_id,
+                        "direction": direction.as_str(),
+                        "fileName": name,
+                    }),
+                )
+            }
+            zmodem::Event::Finished { error } => {
+                match &error {
+                    Some(error) => {
+                        tracing::warn!(
+                            "[terminal.rs] ZMODEM {} failed: {}",
+                            direction.as_str(),
+                            error
+                        );
+                        trace.record("zmodem", format!("Transfer failed: {}", error));
+                    }
+                    None => trace.record("zmodem", "Transfer finished"),
+                }
+                (
+                    "zmodem-finished",
+                    serde_json::json!({
+                        "sessionId": session_id,
+                        "direction": direction.as_str(),
+                        "error": error,
+                    }),
+                )
+            }
+        };
+        let _ = app_handle.emit(event, payload);
+    }
+    step.send.len() as u64
+}
+
+/// Check that the server still answers (after a system sleep)
+///
+/// Unlike the keep-alive heartbeat, opening a channel waits for the server's
+/// reply, so a connection that died while the machine slept is detected at
+/// once instead of on the next failed write.
+async fn probe_transport(session: &SshTransport) -> Result<()> {
+    let channel = tokio::time::timeout(power::RESUME_PROBE_TIMEOUT, session.channel_open_session())
+        .await
+        .map_err(|_| anyhow!("No answer from server"))??;
+    let _ = channel.close().await;
+    Ok(())
+}
 
-    /// Close the session
-    pub async fn close(self) -> Result<()> {
-        self.command_tx
-            .send(SessionCommand::Close)
-            .await
-            .map_err(|_| anyhow!("Session already closed"))?;
-        Ok(())
-    }
+/// Round trip to the server (None when it doesn't answer in time)
+///
+/// A `keepalive@openssh.com` request with want-reply: answered by the
+/// server's transport, without opening a channel.
+async fn measure_latency(session: Arc<SshTransport>) -> Option<std::time::Duration> {
+    let started = std::time::Instant::now();
+    tokio::time::timeout(session_stats::LATENCY_TIMEOUT, session.send_ping())
+        .await
+        .ok()?
+        .ok()?;
+    Some(started.elapsed())
 }
 
 /// Unified session type that can be either SSH or Local
@@ -522,16 +2678,49 @@ pub enum Session {
 }
 
 impl Session {
-    /// Claim the initial output buffer (SSH and local terminals).
-    pub async fn claim_initial_output(&self) -> Vec<u8> {
+    /// Output sink of the session (SSH and local terminals)
+    pub fn output(&self) -> &SessionOutput {
+        match self {
+            Session::Ssh(s) => s.output(),
+            Session::Local(s) => s.output(),
+        }
+    }
+
+    /// Backend screen state of the session
+    pub fn screen(&self) -> &ScreenState {
+        match self {
+            Session::Ssh(s) => &s.screen,
+            Session::Local(s) => s.screen(),
+        }
+    }
+
+    /// Working directory last reported by the shell (OSC 7)
+    pub fn cwd(&self) -> Option<String> {
+        let cwd = match self {
+            Session::Ssh(s) => &s.cwd,
+            Session::Local(s) => s.cwd(),
+        };
+        cwd.lock().ok().and_then(|cwd| cwd.clone())
+    }
+
+    /// Traffic and latency as of the last report (None for local terminals)
+    pub fn stats(&self) -> Option<SessionStats> {
         match self {
-            Session::Ssh(s) => s.claim_initial_output().await,
-            Session::Local(s) => s.claim_initial_output(),
+            Session::Ssh(s) => s.stats.lock().ok().map(|stats| stats.clone()),
+            Session::Local(_) => None,
+        }
+    }
+
+    /// Start or stop measuring latency (local terminals have none)
+    pub fn watch_stats(&self, watch: bool) {
+        if let Session::Ssh(s) = self {
+            s.stats_watched.store(watch, Ordering::Relaxed);
         }
     }
 
     /// Send input to the session
     pub async fn send_input(&self, data: &[u8]) -> Result<()> {
+        self.output().record_input(data);
         match self {
             Session::Ssh(s) => s.send_input(data).await,
             Session::Local(s) => s.send_input(data).await,
@@ -540,14 +2729,28 @@ impl Session {
 
     /// Resize the session
     pub async fn resize(&self, cols: u32, rows: u32) -> Result<()> {
+        self.output().record_resize(cols, rows);
         match self {
             Session::Ssh(s) => s.resize(cols, rows).await,
             Session::Local(s) => s.resize(cols, rows).await,
         }
     }
 
-    /// Close the session
-    pub async fn close(self) -> Result<()> {
+    /// Connection recordings of this session are filed under
+    pub fn recording_dir(&self) -> &str {
+        match self {
+            Session::Ssh(s) => &s.connection_id,
+            Session::Local(_) => crate::recording::LOCAL_DIR,
+        }
+    }
+
+    /// Close the session (stopping its recording)
+    pub async fn close(&self) -> Result<()> {
+        if let Some(Err(e)) = self.output().stop_recording() {
+            tracing::warn!("[terminal.rs] Failed to finish recording: {}", e);
+        }
+        // A paused reader would never see the end of the output
+        self.output().resume();
         match self {
             Session::Ssh(s) => s.close().await,
             Session::Local(s) => s.close().await,
@@ -558,15 +2761,23 @@ impl Session {
 /// Manages all active terminal sessions
 #[derive(Clone)]
 pub struct SessionManager {
-    sessions: Arc<Mutex<HashMap<SessionId, Session>>>,
+    /// Per-session handles; the lock is only held to look a session up, so
+    /// traffic in one session never waits on another
+    sessions: Arc<RwLock<HashMap<SessionId, Arc<Session>>>>,
+    /// Pre-established transports for connections marked warm_on_unlock
+    warm: Arc<Mutex<WarmPool<SshTransport>>>,
+    /// File browser sessions, apart from terminals
+    sftp: Arc<RwLock<HashMap<SessionId, Arc<SftpSession>>>>,
     db: Database,
-    auth: crate::auth::AuthManager,
+    auth: AuthManager,
 }
 
 impl SessionManager {
-    pub fn new(db: Database, auth: crate::auth::AuthManager) -> Self {
+    pub fn new(db: Database, auth: AuthManager) -> Self {
         Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            warm: Arc::new(Mutex::new(WarmPool::new())),
+            sftp: Arc::new(RwLock::new(HashMap::new())),
             db,
             auth,
         }
@@ -577,6 +2788,7 @@ impl SessionManager {
         &self,
         connection_id: String,
         app_handle: AppHandle,
+        debug: bool,
     ) -> Result<SessionId> {
         tracing::info!(
             "[terminal.rs] create_session called for connection_id: {}",
@@ -596,75 +2808,37 @@ impl SessionManager {
             row.hostname,
             row.port
         );
-
-        // Determine keep-alive settings (per-connection only, no global fallback)
-        tracing::debug!("[terminal.rs] Determining keep-alive settings...");
-        let keep_alive_interval = match row.ssh_keep_alive_override.as_deref() {
-            Some("disabled") | None => {
-                tracing::info!("[terminal.rs] Keep-alive disabled");
-                None
-            }
-            Some("enabled") => {
-                // Use connection-specific interval, default to 30 seconds
-                let interval = row.ssh_keep_alive_interval.unwrap_or(30) as u64;
-                tracing::info!(
-                    "[terminal.rs] Keep-alive enabled with interval: {} seconds",
-                    interval
-                );
-                Some(interval)
-            }
-            Some(other) => {
-                tracing::warn!("[terminal.rs] Unknown ssh_keep_alive_override value: '{}', disabling keep-alive", other);
-                None
-            }
-        };
+        if matches!(row.protocol.as_str(), "ftp" | "ftps") {
+            return Err(anyhow!(
+                "FTP connections have no terminal, open them in the file browser"
+            ));
+        }
 
         // Get master key (requires application to be unlocked)
         tracing::debug!("[terminal.rs] Getting master key...");
         let master_key = self.auth.get_master_key().await?;
         tracing::debug!("[terminal.rs] Master key obtained");
 
-        // Decrypt auth method
         tracing::debug!("[terminal.rs] Decrypting credentials...");
-        let auth_method =
-            Connection::decrypt_credentials(&row.encrypted_credentials, &row.nonce, &master_key)?;
+        let connection = Connection::from_row(&row, &master_key)?;
         tracing::info!("[terminal.rs] Credentials decrypted successfully");
+        let auth_method = connection.auth_method.clone();
 
-        // Build Connection object
-        let connection = Connection {
-            id: row.id.clone(),
-            name: row.name.clone(),
-            protocol: crate::connection::Protocol::from_str(&row.protocol)?,
-            hostname: row.hostname.clone(),
-            port: row.port as u16,
-            username: row.username.clone(),
-            auth_method: auth_method.clone(),
-            metadata: crate::connection::ConnectionMetadata {
-                color: row.color.clone(),
-                icon: row.icon.clone(),
-                folder: row.folder.clone(),
-                notes: row.notes.clone(),
-            },
-            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
-            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
-            last_used_at: row.last_used_at,
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-        };
-
-        // Create SSH session
+        // Create SSH session, reusing a warm transport when one is ready
         tracing::info!(
             "[terminal.rs] Creating SSH session for {}...",
             connection.name
         );
-        let ssh_session = SshSession::connect(
-            connection,
-            auth_method,
-            app_handle,
-            keep_alive_interval,
-            false,
-        )
-        .await?;
+        let warm_transport = self.take_warm_transport(&connection_id, &app_handle).await;
+        let ssh_session = match warm_transport {
+            Some(transport) => {
+                tracing::info!("[terminal.rs] Using warm transport for {}", connection.name);
+                let trace = SessionTrace::new(debug);
+                trace.record("kex", "Reusing pre-established (warm) transport");
+                SshSession::open(transport, connection, auth_method, app_handle, trace).await?
+            }
+            None => SshSession::connect(connection, auth_method, app_handle, false, debug).await?,
+        };
         let session_id = ssh_session.id.clone();
         tracing::info!("[terminal.rs] SSH session created with ID: {}", session_id);
 
@@ -689,6 +2863,10 @@ impl SessionManager {
             );
             // Don't fail the connection if we can't update the timestamp
         } else {
+            app_handle
+                .state::<AppState>()
+                .connections
+                .invalidate_cache();
             tracing::debug!(
                 "[terminal.rs] Updated last_used_at timestamp for connection {}",
                 connection_id
@@ -696,25 +2874,182 @@ impl SessionManager {
         }
 
         // Store session
-        let mut sessions = self.sessions.lock().await;
-        sessions.insert(session_id.clone(), session);
+        self.store_session(session_id.clone(), session)?;
         tracing::info!("[terminal.rs] Session stored in SessionManager");
 
         Ok(session_id)
     }
 
+    /// Pre-establish transports for connections marked warm_on_unlock
+    ///
+    /// Opt-in via the `warm_connections_enabled` setting. Runs in the
+    /// background after unlock; progress is reported with `ssh:warm-status`
+    /// events. Transports left unused are closed after
+    /// `warm_connections_idle_timeout` seconds.
+    pub async fn warm_up(&self, app_handle: AppHandle) -> Result<()> {
+        if self
+            .db
+            .get_setting("warm_connections_enabled")
+            .await?
+            .as_deref()
+            != Some("true")
+        {
+            return Ok(());
+        }
+        let idle_timeout = self
+            .db
+            .get_setting("warm_connections_idle_timeout")
+            .await?
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(warm::DEFAULT_IDLE_TIMEOUT_SECS);
+
+        let master_key = self.auth.get_master_key().await?;
+        for row in self.db.get_all_connections().await? {
+            let options =
+                crate::session_options::SessionOptions::from_json(row.session_options.as_deref());
+            if !options.warm_on_unlock || row.protocol != "ssh" {
+                continue;
+            }
+            // A connection that can't be read is skipped, the others still warm
+            let connection = match Connection::from_row(&row, &master_key) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::warn!("[terminal.rs] Not warming connection {}: {}", row.id, e);
+                    continue;
+                }
+            };
+
+            let (status, generation) = self.warm.lock().await.connecting(&row.id, &row.name);
+            let _ = app_handle.emit("ssh:warm-status", &status);
+
+            let warm = Arc::clone(&self.warm);
+            let app_handle = app_handle.clone();
+            tokio::spawn(async move {
+                tracing::info!("[terminal.rs] Warming connection {}", connection.name);
+                let trace = SessionTrace::new(false);
+                let result = SshSession::establish(
+                    &connection,
+                    &connection.auth_method,
+                    &app_handle,
+                    false,
+                    true,
+                    &trace,
+                )
+                .await;
+
+                let status = match result {
+                    Ok(transport) => {
+                        let ready = warm
+                            .lock()
+                            .await
+                            .ready(&connection.id, generation, transport);
+                        match ready {
+                            Ok(status) => Some(status),
+                            Err(transport) => {
+                                // Locked (or restarted) while connecting
+                                let _ = transport
+                                    .disconnect(russh::Disconnect::ByApplication, "", "")
+                                    .await;
+                                None
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "[terminal.rs] Failed to warm connection {}: {}",
+                            connection.name,
+                            e
+                        );
+                        warm.lock()
+                            .await
+                            .failed(&connection.id, generation, e.to_string())
+                    }
+                };
+                let Some(status) = status else {
+                    return;
+                };
+                let ready = status.state == WarmState::Ready;
+                let _ = app_handle.emit("ssh:warm-status", &status);
+
+                // Idle teardown
+                if ready {
+                    tokio::time::sleep(std::time::Duration::from_secs(idle_timeout)).await;
+                    let expired = warm.lock().await.expire(&connection.id, generation);
+                    if let Some((transport, status)) = expired {
+                        tracing::info!(
+                            "[terminal.rs] Closing idle warm connection {}",
+                            connection.name
+                        );
+                        let _ = transport
+                            .disconnect(russh::Disconnect::ByApplication, "", "")
+                            .await;
+                        let _ = app_handle.emit("ssh:warm-status", &status);
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Status of all warm connections
+    pub async fn warm_statuses(&self) -> Vec<WarmStatus> {
+        self.warm.lock().await.statuses()
+    }
+
+    /// Close all warm transports (on lock)
+    pub async fn clear_warm(&self, app_handle: &AppHandle) {
+        let closed = self.warm.lock().await.clear();
+        for (transport, status) in closed {
+            let _ = transport
+                .disconnect(russh::Disconnect::ByApplication, "", "")
+                .await;
+            let _ = app_handle.emit("ssh:warm-status", &status);
+        }
+    }
+
+    /// Take a ready warm transport for a connection, if it is still open
+    async fn take_warm_transport(
+        &self,
+        connection_id: &str,
+        app_handle: &AppHandle,
+    ) -> Option<SshTransport> {
+        let (transport, status) = self.warm.lock().await.take(connection_id)?;
+        let _ = app_handle.emit("ssh:warm-status", &status);
+        (!transport.is_closed()).then_some(transport)
+    }
+
     /// Create a new local terminal session
     ///
-    /// Spawns a local shell (bash/zsh/fish) based on $SHELL env variable
+    /// Spawns a local shell (bash/zsh/fish) based on $SHELL env variable.
+    /// With a saved connection, its environment variables (and locale) are
+    /// set on the shell.
     pub async fn create_local_session(
         &self,
         app_handle: AppHandle,
         shell: Option<String>,
+        connection_id: Option<String>,
+        cwd: Option<String>,
     ) -> Result<SessionId> {
         tracing::info!("[terminal.rs] create_local_session called");
 
+        let env = match connection_id {
+            Some(connection_id) => {
+                let row = self
+                    .db
+                    .get_connection(&connection_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("Connection not found: {}", connection_id))?;
+                crate::session_options::SessionOptions::from_json(row.session_options.as_deref())
+                    .local_environment()?
+            }
+            None => Vec::new(),
+        };
+
         // Create local session
-        let local_session = crate::local_terminal::LocalSession::spawn(app_handle, shell).await?;
+        let local_session =
+            crate::local_terminal::LocalSession::spawn(app_handle, shell, env, cwd).await?;
         let session_id = local_session.id.clone();
         tracing::info!(
             "[terminal.rs] Local session created with ID: {}",
@@ -725,8 +3060,7 @@ impl SessionManager {
         let session = Session::Local(local_session);
 
         // Store session
-        let mut sessions = self.sessions.lock().await;
-        sessions.insert(session_id.clone(), session);
+        self.store_session(session_id.clone(), session)?;
         tracing::info!("[terminal.rs] Local session stored in SessionManager");
 
         Ok(session_id)
@@ -740,40 +3074,26 @@ impl SessionManager {
         connection: Connection,
         auth_method: AuthMethod,
         app_handle: AppHandle,
+        accept_host_key_once: bool,
+        debug: bool,
     ) -> Result<SessionId> {
         tracing::info!(
             "[terminal.rs] create_quick_ssh_session called for {}",
             connection.name
         );
 
-        // Determine keep-alive settings (use connection override or disable)
-        let keep_alive_interval = match connection.ssh_keep_alive_override.as_deref() {
-            Some("enabled") => {
-                let interval = connection.ssh_keep_alive_interval.unwrap_or(30) as u64;
-                tracing::info!(
-                    "[terminal.rs] Keep-alive enabled with interval: {} seconds",
-                    interval
-                );
-                Some(interval)
-            }
-            _ => {
-                tracing::info!("[terminal.rs] Keep-alive disabled");
-                None
-            }
-        };
-
         // Create SSH session (no database save, no master key needed)
-        // force_accept_host_key = true: bypass host key verification for Quick SSH
+        // Host keys are verified per the global mode unless the caller explicitly opted out
         tracing::info!(
             "[terminal.rs] Creating quick SSH session for {}...",
             connection.name
         );
         let ssh_session = SshSession::connect(
-            connection,
+            connection.clone(),
             auth_method,
             app_handle,
-            keep_alive_interval,
-            true,
+            accept_host_key_once,
+            debug,
         )
         .await?;
         let session_id = ssh_session.id.clone();
@@ -782,54 +3102,70 @@ impl SessionManager {
             session_id
         );
 
+        // Kept so the session can be saved as a connection without re-typing
+        if let Ok(mut quick) = ssh_session.quick_connection.lock() {
+            *quick = Some(Zeroizing::new(connection));
+        }
+
         // Wrap in Session enum
         let session = Session::Ssh(ssh_session);
 
         // Store session (no database update for quick connects)
-        let mut sessions = self.sessions.lock().await;
-        sessions.insert(session_id.clone(), session);
+        self.store_session(session_id.clone(), session)?;
         tracing::info!("[terminal.rs] Quick SSH session stored in SessionManager");
 
         Ok(session_id)
     }
 
-    /// Claim the initial output buffer for a session.
-    /// Returns all SSH data buffered before the frontend registered its listener,
-    /// and switches the session to streaming mode (future data emitted as events).
-    pub async fn claim_session_output(&self, session_id: &str) -> Vec<u8> {
-        let sessions = self.sessions.lock().await;
-        match sessions.get(session_id) {
-            Some(session) => session.claim_initial_output().await,
-            None => Vec::new(),
-        }
+    /// Attach the frontend's output channel to a session.
+    /// Returns all data buffered before the frontend attached, and switches the
+    /// session to streaming mode over `channel` (binary frames, see output.rs).
+    pub fn attach_session_output(
+        &self,
+        session_id: &str,
+        channel: Channel<InvokeResponseBody>,
+    ) -> Result<Vec<u8>> {
+        Ok(self.session(session_id)?.output().attach(channel))
+    }
+
+    /// Stop reading a session's output until `resume_output` (the frontend
+    /// fell behind)
+    pub fn pause_output(&self, session_id: &str) -> Result<()> {
+        self.session(session_id)?.output().pause();
+        Ok(())
+    }
+
+    /// Read a session's output again after `pause_output`
+    pub fn resume_output(&self, session_id: &str) -> Result<()> {
+        self.session(session_id)?.output().resume();
+        Ok(())
+    }
+
+    /// Recent raw output of a session (bounded, see scrollback.rs)
+    pub fn session_scrollback(&self, session_id: &str) -> Result<Vec<u8>> {
+        Ok(self.session(session_id)?.output().scrollback())
     }
 
     /// Send input to a session
     pub async fn send_input(&self, session_id: &str, data: Vec<u8>) -> Result<()> {
-        let sessions = self.sessions.lock().await;
-        let session = sessions
-            .get(session_id)
-            .ok_or_else(|| anyhow!("Session not found"))?;
-
+        let session = self.session(session_id)?;
         session.send_input(&data).await?;
         Ok(())
     }
 
     /// Resize a terminal session
     pub async fn resize_terminal(&self, session_id: &str, cols: u32, rows: u32) -> Result<()> {
-        let sessions = self.sessions.lock().await;
-        let session = sessions
-            .get(session_id)
-            .ok_or_else(|| anyhow!("Session not found"))?;
-
+        let session = self.session(session_id)?;
         session.resize(cols, rows).await?;
         Ok(())
     }
 
     /// Close a session
     pub async fn close_session(&self, session_id: &str) -> Result<()> {
-        let mut sessions = self.sessions.lock().await;
-        let session = sessions
+        let session = self
+            .sessions
+            .write()
+            .map_err(|_| anyhow!("Session map poisoned"))?
             .remove(session_id)
             .ok_or_else(|| anyhow!("Session not found"))?;
 
@@ -837,9 +3173,405 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Render the current screen of a session with the given theme
+    pub async fn snapshot_session(
+        &self,
+        session_id: &str,
+        format: SnapshotFormat,
+        theme: &crate::theme::Theme,
+    ) -> Result<SessionSnapshot> {
+        let (svg, width, height) = {
+            let session = self.session(session_id)?;
+            let screen = session
+                .screen()
+                .lock()
+                .map_err(|_| anyhow!("Screen state poisoned"))?;
+            snapshot::render_svg(screen.screen(), theme)
+        };
+
+        let bytes = match format {
+            SnapshotFormat::Svg => svg.into_bytes(),
+            SnapshotFormat::Png => {
+                tokio::task::spawn_blocking(move || snapshot::render_png(&svg)).await??
+            }
+        };
+
+        Ok(SessionSnapshot {
+            mime_type: format.mime_type().to_string(),
+            width,
+            height,
+            data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        })
+    }
+
+    /// Backend screen state of a session (kept up to date with its output)
+    pub fn session_screen(&self, session_id: &str) -> Result<ScreenState> {
+        Ok(Arc::clone(self.session(session_id)?.screen()))
+    }
+
+    /// Plain-text transcript of a session (scrollback and screen)
+    pub fn session_transcript(&self, session_id: &str) -> Result<String> {
+        let session = self.session(session_id)?;
+        let mut screen = session
+            .screen()
+            .lock()
+            .map_err(|_| anyhow!("Screen state poisoned"))?;
+        Ok(snapshot::transcript(&mut screen))
+    }
+
+    /// Working directory of a session as reported by its shell (None when
+    /// the shell does not send OSC 7)
+    pub fn session_cwd(&self, session_id: &str) -> Result<Option<String>> {
+        Ok(self.session(session_id)?.cwd())
+    }
+
+    /// Traffic and latency of a session as of its last report (None for
+    /// local terminals)
+    pub fn session_stats(&self, session_id: &str) -> Result<Option<SessionStats>> {
+        Ok(self.session(session_id)?.stats())
+    }
+
+    /// Measure a session's latency while its stats are shown
+    pub fn watch_session_stats(&self, session_id: &str, watch: bool) -> Result<()> {
+        self.session(session_id)?.watch_stats(watch);
+        Ok(())
+    }
+
+    /// Type a stored secret of the session's connection into the terminal,
+    /// followed by Enter, at a password prompt (e.g. sudo's)
+    ///
+    /// The secret is decrypted here and written to the channel: it never
+    /// goes through the frontend. Each use is recorded in the audit log.
+    pub async fn send_stored_secret(&self, session_id: &str, kind: SecretKind) -> Result<()> {
+        let session = self.session(session_id)?;
+        let Session::Ssh(ssh) = session.as_ref() else {
+            return Err(anyhow!("Local terminals have no stored secrets"));
+        };
+
+        // Only the secret is copied out of the Quick SSH details
+        let quick = ssh
+            .quick_connection
+            .lock()
+            .map_err(|_| anyhow!("Session state poisoned"))?
+            .as_ref()
+            .map(|connection| {
+                let secret = crate::connection::stored_secret(connection, kind);
+                (connection.name.clone(), secret)
+            });
+        let (name, secret) = match quick {
+            Some(quick) => quick,
+            None => {
+                let row = self
+                    .db
+                    .get_connection(&ssh.connection_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("Connection not found"))?;
+                let master_key = self.auth.get_master_key().await?;
+                let connection = Zeroizing::new(Connection::from_row(&row, &master_key)?);
+                let secret = crate::connection::stored_secret(&connection, kind);
+                (connection.name.clone(), secret)
+            }
+        };
+        let secret = secret.ok_or_else(|| anyhow!("{} has no stored {}", name, kind.label()))?;
+
+        let mut data = Zeroizing::new(Vec::with_capacity(secret.len() + 1));
+        data.extend_from_slice(secret.as_bytes());
+        data.push(b'\r');
+        ssh.send_secret(data).await?;
+        tracing::info!(
+            "[terminal.rs] Stored {} sent to session {}",
+            kind.label(),
+            session_id
+        );
+        if let Err(e) = audit::record(
+            self.db.pool(),
+            audit::EVENT_SECRET_SENT,
+            &format!("{}: {}", name, kind.label()),
+        )
+        .await
+        {
+            tracing::warn!("[terminal.rs] Failed to record audit event: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Take the connection details of a Quick SSH session to save them
+    /// (None for saved connections, or when already taken)
+    ///
+    /// Taking them at once keeps two saves of the same session from both
+    /// creating a connection; `restore_quick_connection` puts them back when
+    /// saving failed.
+    pub fn take_quick_connection(&self, session_id: &str) -> Result<Option<Zeroizing<Connection>>> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => Ok(s
+                .quick_connection
+                .lock()
+                .map_err(|_| anyhow!("Session state poisoned"))?
+                .take()),
+            Session::Local(_) => Ok(None),
+        }
+    }
+
+    /// Give back the details taken by `take_quick_connection`
+    pub fn restore_quick_connection(
+        &self,
+        session_id: &str,
+        connection: Zeroizing<Connection>,
+    ) -> Result<()> {
+        if let Session::Ssh(s) = self.session(session_id)?.as_ref() {
+            *s.quick_connection
+                .lock()
+                .map_err(|_| anyhow!("Session state poisoned"))? = Some(connection);
+        }
+        Ok(())
+    }
+
+    /// Wipe the Quick SSH credentials of every session (vault locked): these
+    /// sessions can no longer be saved or have their password typed
+    pub fn clear_quick_connections(&self) {
+        let sessions: Vec<Arc<Session>> = match self.sessions.read() {
+            Ok(sessions) => sessions.values().cloned().collect(),
+            Err(_) => return,
+        };
+        for session in sessions {
+            if let Session::Ssh(s) = session.as_ref() {
+                if let Ok(mut quick) = s.quick_connection.lock() {
+                    quick.take();
+                }
+            }
+        }
+    }
+
+    /// Confirm or cancel a dangerous command held back on a protected host
+    pub async fn confirm_command(&self, session_id: &str, approved: bool) -> Result<()> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => s.confirm_command(approved).await,
+            Session::Local(_) => Err(anyhow!(
+                "Command confirmation is only available for SSH sessions"
+            )),
+        }
+    }
+
+    /// Answer the ZMODEM transfer running in an SSH session
+    pub async fn answer_zmodem(&self, session_id: &str, reply: zmodem::Reply) -> Result<()> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => s.answer_zmodem(reply).await,
+            Session::Local(_) => Err(anyhow!(
+                "ZMODEM transfers are only available for SSH sessions"
+            )),
+        }
+    }
+
+    /// Port forwarding access to an SSH session
+    pub fn forward_handle(&self, session_id: &str) -> Result<ForwardHandle> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => Ok(s.forward_handle()),
+            Session::Local(_) => Err(anyhow!(
+                "Port forwarding is only available for SSH sessions"
+            )),
+        }
+    }
+
+    /// Enable or disable protocol-level debug capture for an SSH session
+    pub async fn set_session_debug(&self, session_id: &str, enabled: bool) -> Result<()> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => {
+                s.trace().set_enabled(enabled);
+                Ok(())
+            }
+            Session::Local(_) => Err(anyhow!("Debug capture is only available for SSH sessions")),
+        }
+    }
+
+    /// Get the recorded protocol trace of an SSH session
+    pub async fn get_session_trace(&self, session_id: &str) -> Result<Vec<TraceEvent>> {
+        match self.session(session_id)?.as_ref() {
+            Session::Ssh(s) => Ok(s.trace().events()),
+            Session::Local(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Start recording a session to a cast file
+    pub async fn start_recording(
+        &self,
+        session_id: &str,
+        options: RecordingOptions,
+    ) -> Result<RecordingInfo> {
+        let session = self.session(session_id)?;
+        let key = if options.encrypt {
+            Some(self.auth.get_master_key().await?)
+        } else {
+            None
+        };
+        let size = {
+            let screen = session
+                .screen()
+                .lock()
+                .map_err(|_| anyhow!("Screen state poisoned"))?;
+            let (rows, cols) = screen.screen().size();
+            (cols, rows)
+        };
+        let title = match session.as_ref() {
+            Session::Ssh(s) => match self.db.get_connection(&s.connection_id).await {
+                Ok(Some(row)) => row.name,
+                _ => "SSH session".to_string(),
+            },
+            Session::Local(_) => "Local terminal".to_string(),
+        };
+        let recorder = Recorder::start(session.recording_dir(), &title, size, options, key)?;
+        session.output().start_recording(recorder)
+    }
+
+    /// Stop a session's recording
+    pub fn stop_recording(&self, session_id: &str) -> Result<RecordingInfo> {
+        self.session(session_id)?
+            .output()
+            .stop_recording()
+            .ok_or_else(|| anyhow!("Session is not being recorded"))?
+    }
+
+    /// Running recording of a session, if any
+    pub fn recording(&self, session_id: &str) -> Result<Option<RecordingInfo>> {
+        Ok(self.session(session_id)?.output().recording())
+    }
+
+    /// Stop encrypted recordings (the vault is being locked, so their key
+    /// must not outlive it)
+    pub fn stop_encrypted_recordings(&self) {
+        let sessions: Vec<Arc<Session>> = match self.sessions.read() {
+            Ok(sessions) => sessions.values().cloned().collect(),
+            Err(_) => return,
+        };
+        for session in sessions {
+            let output = session.output();
+            if !output
+                .recording()
+                .is_some_and(|recording| recording.encrypted)
+            {
+                continue;
+            }
+            if let Some(Err(e)) = output.stop_recording() {
+                tracing::warn!("[terminal.rs] Failed to finish recording: {}", e);
+            }
+        }
+    }
+
     /// Get all active session IDs
     pub async fn list_sessions(&self) -> Vec<SessionId> {
-        let sessions = self.sessions.lock().await;
-        sessions.keys().cloned().collect()
+        match self.sessions.read() {
+            Ok(sessions) => sessions.keys().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Open an SFTP session on a saved SSH connection (or an FTP session on
+    /// an FTP/FTPS one)
+    pub async fn create_sftp_session(
+        &self,
+        connection_id: String,
+        app_handle: AppHandle,
+    ) -> Result<SftpSessionInfo> {
+        let row = self
+            .db
+            .get_connection(&connection_id)
+            .await?
+            .ok_or_else(|| anyhow!("Connection not found"))?;
+        // Some(tls) for FTP
+        let ftp = match row.protocol.as_str() {
+            "ssh" => None,
+            "ftp" => Some(false),
+            "ftps" => Some(true),
+            _ => return Err(anyhow!("SFTP requires an SSH connection")),
+        };
+
+        let master_key = self.auth.get_master_key().await?;
+        let connection = Connection::from_row(&row, &master_key)?;
+        tracing::info!(
+            "[terminal.rs] Opening {} session for {}",
+            if ftp.is_some() { "FTP" } else { "SFTP" },
+            connection.name
+        );
+
+        let session_id = Uuid::new_v4().to_string();
+        let session = match ftp {
+            Some(tls) => {
+                let config = sftp::ftp_config(&connection)?;
+                SftpSession::open_ftp(session_id.clone(), &connection, config, tls).await?
+            }
+            None => {
+                let trace = SessionTrace::new(false);
+                let transport = SshSession::establish(
+                    &connection,
+                    &connection.auth_method,
+                    &app_handle,
+                    false,
+                    false,
+                    &trace,
+                )
+                .await?;
+                SftpSession::open(session_id.clone(), &connection, transport).await?
+            }
+        };
+        let home = match session.home().await {
+            Ok(home) => home,
+            Err(e) => {
+                let _ = session.close().await;
+                return Err(e);
+            }
+        };
+        let info = SftpSessionInfo {
+            session_id: session_id.clone(),
+            connection_id: session.connection_id.clone(),
+            home,
+            scp_only: session.is_scp_only(),
+            protected: session.protected,
+        };
+
+        self.sftp
+            .write()
+            .map_err(|_| anyhow!("Session map poisoned"))?
+            .insert(session_id, Arc::new(session));
+        tracing::info!("[terminal.rs] SFTP session {} opened", info.session_id);
+        Ok(info)
+    }
+
+    /// Resolve an SFTP session handle
+    pub fn sftp_session(&self, session_id: &str) -> Result<Arc<SftpSession>> {
+        self.sftp
+            .read()
+            .map_err(|_| anyhow!("Session map poisoned"))?
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("SFTP session not found"))
+    }
+
+    /// Close an SFTP session
+    pub async fn close_sftp_session(&self, session_id: &str) -> Result<()> {
+        let session = self
+            .sftp
+            .write()
+            .map_err(|_| anyhow!("Session map poisoned"))?
+            .remove(session_id)
+            .ok_or_else(|| anyhow!("SFTP session not found"))?;
+
+        session.close().await
+    }
+
+    /// Resolve a session handle, releasing the map lock before it is used
+    fn session(&self, session_id: &str) -> Result<Arc<Session>> {
+        self.sessions
+            .read()
+            .map_err(|_| anyhow!("Session map poisoned"))?
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Session not found"))
+    }
+
+    fn store_session(&self, session_id: SessionId, session: Session) -> Result<()> {
+        self.sessions
+            .write()
+            .map_err(|_| anyhow!("Session map poisoned"))?
+            .insert(session_id, Arc::new(session));
+        Ok(())
     }
 }
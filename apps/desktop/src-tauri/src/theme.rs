@@ -114,8 +114,14 @@ fn get_user_themes_dir() -> Option<PathBuf> {
     Some(config_dir.join("rite").join("themes"))
 }
 
+/// Parse a theme TOML file
+pub fn parse_theme(content: &str) -> Result<Theme, toml::de::Error> {
+    toml::from_str::<Theme>(content)
+}
+
 /// Load a theme by name
-#[tauri::command]
+// Fuzz targets include this module without Tauri
+#[cfg_attr(not(fuzzing), tauri::command)]
 pub fn load_theme(name: String) -> Result<Theme, String> {
     debug!("Loading theme: {}", name);
 
@@ -124,7 +130,7 @@ pub fn load_theme(name: String) -> Result<Theme, String> {
         let theme_path = themes_dir.join(format!("{}.toml", name));
         if theme_path.exists() {
             match fs::read_to_string(&theme_path) {
-                Ok(content) => match toml::from_str::<Theme>(&content) {
+                Ok(content) => match parse_theme(&content) {
                     Ok(theme) => {
                         debug!("Loaded user theme: {}", name);
                         return Ok(theme);
@@ -150,7 +156,7 @@ pub fn load_theme(name: String) -> Result<Theme, String> {
 }
 
 /// List available themes
-#[tauri::command]
+#[cfg_attr(not(fuzzing), tauri::command)]
 pub fn list_themes() -> Vec<String> {
     let mut themes = vec!["default".to_string()];
 
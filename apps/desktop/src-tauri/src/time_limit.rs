@@ -0,0 +1,153 @@
+/**
+ * Session Time Limit
+ *
+ * Time-boxed sessions (e.g. production access for at most 30 minutes):
+ * the remaining time is reported every minute, a warning is given shortly
+ * before expiry and the session is disconnected when the time is up.
+ *
+ * Uses the wall clock, so time spent in system sleep counts as well.
+ */
+use std::time::{Duration, SystemTime};
+
+/// How often the remaining time is reported
+pub const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long before expiry the user is warned
+pub const WARNING_BEFORE: Duration = Duration::from_secs(5 * 60);
+
+/// What the session should do after a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitEvent {
+    /// Report the remaining time
+    Countdown(Duration),
+    /// Report the remaining time and warn that the session ends soon
+    Warning(Duration),
+    /// Time is up: disconnect
+    Expired,
+}
+
+/// Deadline of a time-boxed session
+#[derive(Debug, Clone)]
+pub struct TimeLimit {
+    limit: Duration,
+    deadline: SystemTime,
+    warned: bool,
+    next_report: SystemTime,
+}
+
+impl TimeLimit {
+    /// Limit starting now
+    pub fn new(limit: Duration) -> Self {
+        Self::starting_at(SystemTime::now(), limit)
+    }
+
+    fn starting_at(now: SystemTime, limit: Duration) -> Self {
+        Self {
+            limit,
+            deadline: now + limit,
+            // Nothing to warn about if the whole session is shorter
+            warned: limit <= WARNING_BEFORE,
+            next_report: now,
+        }
+    }
+
+    /// Configured session length
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+
+    /// Check the limit (call again after `until_next`)
+    pub fn check(&mut self) -> LimitEvent {
+        self.observe(SystemTime::now())
+    }
+
+    /// How long to wait before the next check
+    pub fn until_next(&self) -> Duration {
+        self.next_after(SystemTime::now())
+    }
+
+    fn observe(&mut self, now: SystemTime) -> LimitEvent {
+        self.next_report = now + COUNTDOWN_INTERVAL;
+        let remaining = self.remaining(now);
+        if remaining.is_zero() {
+            LimitEvent::Expired
+        } else if !self.warned && remaining <= WARNING_BEFORE {
+            self.warned = true;
+            LimitEvent::Warning(remaining)
+        } else {
+            LimitEvent::Countdown(remaining)
+        }
+    }
+
+    fn next_after(&self, now: SystemTime) -> Duration {
+        let next_stop = match self.warned {
+            false => self.deadline - WARNING_BEFORE,
+            true => self.deadline,
+        };
+        next_stop
+            .min(self.next_report)
+            .duration_since(now)
+            .unwrap_or_default()
+    }
+
+    fn remaining(&self, now: SystemTime) -> Duration {
+        self.deadline.duration_since(now).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_countdown_warning_expiry() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut limit = TimeLimit::starting_at(start, 30 * MINUTE);
+
+        // First report right away, then every minute
+        assert_eq!(limit.next_after(start), Duration::ZERO);
+        assert_eq!(limit.observe(start), LimitEvent::Countdown(30 * MINUTE));
+        assert_eq!(limit.next_after(start), MINUTE);
+        assert_eq!(
+            limit.next_after(start + Duration::from_secs(20)),
+            Duration::from_secs(40)
+        );
+
+        // Wakes up exactly at T-5
+        let before_warning = start + 24 * MINUTE + Duration::from_secs(30);
+        limit.observe(before_warning);
+        assert_eq!(limit.next_after(before_warning), Duration::from_secs(30));
+        let warning = start + 25 * MINUTE;
+        assert_eq!(limit.observe(warning), LimitEvent::Warning(5 * MINUTE));
+        assert_eq!(
+            limit.observe(warning + MINUTE),
+            LimitEvent::Countdown(4 * MINUTE)
+        );
+
+        // Wakes up exactly at expiry
+        let last = start + 29 * MINUTE + Duration::from_secs(50);
+        limit.observe(last);
+        assert_eq!(limit.next_after(last), Duration::from_secs(10));
+        assert_eq!(limit.observe(start + 30 * MINUTE), LimitEvent::Expired);
+
+        // Long past the deadline (e.g. after a system sleep)
+        assert_eq!(limit.observe(start + 90 * MINUTE), LimitEvent::Expired);
+        assert_eq!(limit.next_after(start + 90 * MINUTE), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_short_limit_skips_warning() {
+        let start = SystemTime::UNIX_EPOCH;
+        let mut limit = TimeLimit::starting_at(start, 2 * MINUTE);
+        assert_eq!(limit.observe(start), LimitEvent::Countdown(2 * MINUTE));
+        assert_eq!(limit.next_after(start), MINUTE);
+        let last = start + MINUTE + Duration::from_secs(30);
+        assert_eq!(
+            limit.observe(last),
+            LimitEvent::Countdown(Duration::from_secs(30))
+        );
+        assert_eq!(limit.next_after(last), Duration::from_secs(30));
+    }
+}
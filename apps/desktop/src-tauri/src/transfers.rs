@@ -0,0 +1,598 @@
+/**
+ * File Transfer Queue
+ *
+ * SFTP uploads and downloads run in the background, a few at a time, each on
+ * its own channel of the SFTP session's transport. Progress (bytes, rate and
+ * ETA) is reported to the frontend as `transfer-progress` events.
+ *
+ * Pausing stops the transfer and keeps the partial file; resuming (or
+ * retrying a failed transfer) continues from the size of the partial copy,
+ * unless the source file changed size or modification time since the
+ * transfer started. Over SCP (servers without SFTP) and FTP transfers start
+ * over instead (`resumable` is false).
+ */
+use anyhow::{anyhow, Result};
+use rite_protocols::{FileTransferProtocol, ProgressCallback, Protocol, TransferProgress};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::sftp::{SftpSession, TransferClient};
+use crate::terminal::SessionId;
+
+/// Event carrying a `TransferInfo`
+pub const EVENT_PROGRESS: &str = "transfer-progress";
+
+/// Transfers running at the same time (the others wait in the queue)
+const MAX_ACTIVE: usize = 3;
+
+/// Minimum delay between two progress events of a transfer
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferState {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Transfer requested by the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRequest {
+    pub direction: TransferDirection,
+    pub local_path: PathBuf,
+    pub remote_path: String,
+}
+
+/// A transfer, as reported to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferInfo {
+    pub id: String,
+    pub session_id: SessionId,
+    #[serde(flatten)]
+    pub request: TransferRequest,
+    pub state: TransferState,
+    /// Resuming continues the partial copy (SFTP) rather than starting over
+    pub resumable: bool,
+    pub transferred: u64,
+    /// Total size, if known
+    pub total: Option<u64>,
+    /// Bytes per second (while running)
+    pub rate: u64,
+    /// Estimated seconds left (while running)
+    pub eta_secs: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Status {
+    state: TransferState,
+    transferred: u64,
+    total: Option<u64>,
+    rate: u64,
+    eta_secs: Option<u64>,
+    error: Option<String>,
+    /// Data was written: later runs continue the partial copy
+    started: bool,
+    /// Source file as the partial copy was made from it
+    source: Option<SourceVersion>,
+}
+
+impl Status {
+    /// Record the source of a new run; false when it changed since the
+    /// previous run, so the partial copy is stale
+    fn same_source(&mut self, source: SourceVersion) -> bool {
+        self.source
+            .replace(source)
+            .is_none_or(|previous| previous == source)
+    }
+}
+
+/// Size and modification time of a transfer's source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourceVersion {
+    size: u64,
+    /// Unix timestamp in seconds
+    modified: Option<i64>,
+}
+
+struct Transfer {
+    id: String,
+    session_id: SessionId,
+    request: TransferRequest,
+    resumable: bool,
+    /// Queue order
+    seq: u64,
+    status: StdMutex<Status>,
+}
+
+impl Transfer {
+    fn status(&self) -> std::sync::MutexGuard<'_, Status> {
+        // Status updates can't leave it inconsistent
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn info(&self) -> TransferInfo {
+        let status = self.status();
+        TransferInfo {
+            id: self.id.clone(),
+            session_id: self.session_id.clone(),
+            request: self.request.clone(),
+            state: status.state,
+            resumable: self.resumable,
+            transferred: status.transferred,
+            total: status.total,
+            rate: status.rate,
+            eta_secs: status.eta_secs,
+            error: status.error.clone(),
+        }
+    }
+
+    fn emit(&self, app: &AppHandle) {
+        let _ = app.emit(EVENT_PROGRESS, self.info());
+    }
+
+    /// Switch state, unless the transfer was paused or cancelled meanwhile
+    fn finish(&self, state: TransferState, error: Option<String>) -> bool {
+        let mut status = self.status();
+        if status.state != TransferState::Running {
+            return false;
+        }
+        status.state = state;
+        status.rate = 0;
+        status.eta_secs = None;
+        status.error = error;
+        true
+    }
+}
+
+struct Entry {
+    transfer: Arc<Transfer>,
+    session: Arc<SftpSession>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Entry {
+    fn abort(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Transfer queue of all SFTP sessions
+#[derive(Clone)]
+pub struct TransferManager {
+    transfers: Arc<StdMutex<HashMap<String, Entry>>>,
+    slots: Arc<Semaphore>,
+    next_seq: Arc<AtomicU64>,
+}
+
+impl Default for TransferManager {
+    fn default() -> Self {
+        Self {
+            transfers: Arc::new(StdMutex::new(HashMap::new())),
+            slots: Arc::new(Semaphore::new(MAX_ACTIVE)),
+            next_seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl TransferManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn transfers(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.transfers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Add a transfer to the queue
+    pub fn enqueue(
+        &self,
+        app: &AppHandle,
+        session: Arc<SftpSession>,
+        request: TransferRequest,
+    ) -> Result<TransferInfo> {
+        if request.remote_path.trim().is_empty() || request.local_path.as_os_str().is_empty() {
+            return Err(anyhow!("Both a local and a remote path are required"));
+        }
+
+        let transfer = Arc::new(Transfer {
+            id: Uuid::new_v4().to_string(),
+            session_id: session.id.clone(),
+            request,
+            resumable: session.supports_resume(),
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            status: StdMutex::new(Status {
+                state: TransferState::Queued,
+                transferred: 0,
+                total: None,
+                rate: 0,
+                eta_secs: None,
+                error: None,
+                started: false,
+                source: None,
+            }),
+        });
+        tracing::info!(
+            "[transfers.rs] Queued {:?} {} ({:?} <-> {})",
+            transfer.request.direction,
+            transfer.id,
+            transfer.request.local_path,
+            transfer.request.remote_path
+        );
+
+        let task = self.spawn(app, &session, &transfer);
+        let info = transfer.info();
+        self.transfers().insert(
+            transfer.id.clone(),
+            Entry {
+                transfer,
+                session,
+                task: Some(task),
+            },
+        );
+        let _ = app.emit(EVENT_PROGRESS, &info);
+        Ok(info)
+    }
+
+    /// Stop a queued or running transfer, keeping the partial copy
+    pub fn pause(&self, app: &AppHandle, transfer_id: &str) -> Result<()> {
+        let mut transfers = self.transfers();
+        let entry = transfers
+            .get_mut(transfer_id)
+            .ok_or_else(|| anyhow!("Transfer not found"))?;
+        {
+            let mut status = entry.transfer.status();
+            if !matches!(status.state, TransferState::Queued | TransferState::Running) {
+                return Err(anyhow!("Transfer is not active"));
+            }
+            status.state = TransferState::Paused;
+            status.rate = 0;
+            status.eta_secs = None;
+        }
+        entry.abort();
+        entry.transfer.emit(app);
+        Ok(())
+    }
+
+    /// Queue a paused or failed transfer again, continuing its partial copy
+    /// (over SCP and FTP, or when the source changed, starting over)
+    pub fn resume(&self, app: &AppHandle, transfer_id: &str) -> Result<()> {
+        let mut transfers = self.transfers();
+        let entry = transfers
+            .get_mut(transfer_id)
+            .ok_or_else(|| anyhow!("Transfer not found"))?;
+        {
+            let mut status = entry.transfer.status();
+            if !matches!(status.state, TransferState::Paused | TransferState::Failed) {
+                return Err(anyhow!("Transfer is not paused"));
+            }
+            status.state = TransferState::Queued;
+            status.error = None;
+        }
+        entry.task = Some(self.spawn(app, &entry.session, &entry.transfer));
+        entry.transfer.emit(app);
+        Ok(())
+    }
+
+    /// Cancel a transfer and remove it from the list
+    ///
+    /// The partial copy of an unfinished transfer is deleted.
+    pub async fn cancel(&self, app: &AppHandle, transfer_id: &str) -> Result<()> {
+        let mut entry = self
+            .transfers()
+            .remove(transfer_id)
+            .ok_or_else(|| anyhow!("Transfer not found"))?;
+        entry.abort();
+
+        let partial = {
+            let mut status = entry.transfer.status();
+            let partial = status.started && status.state != TransferState::Completed;
+            status.state = TransferState::Cancelled;
+            status.rate = 0;
+            status.eta_secs = None;
+            partial
+        };
+        entry.transfer.emit(app);
+
+        if partial {
+            let request = &entry.transfer.request;
+            let removed = match request.direction {
+                TransferDirection::Download => tokio::fs::remove_file(&request.local_path)
+                    .await
+                    .map_err(anyhow::Error::from),
+                TransferDirection::Upload => entry.session.delete(&request.remote_path).await,
+            };
+            if let Err(e) = removed {
+                tracing::warn!("[transfers.rs] Failed to delete partial file: {}", e);
+            }
+        }
+        tracing::info!("[transfers.rs] Transfer {} cancelled", transfer_id);
+        Ok(())
+    }
+
+    /// Drop every transfer of a session (when it is closed)
+    pub fn stop_session(&self, session_id: &str) {
+        self.transfers().retain(|_, entry| {
+            if entry.transfer.session_id != session_id {
+                return true;
+            }
+            entry.abort();
+            false
+        });
+    }
+
+    /// Transfers (of one session, or all) in queue order
+    pub fn list(&self, session_id: Option<&str>) -> Vec<TransferInfo> {
+        let transfers = self.transfers();
+        let mut list: Vec<&Arc<Transfer>> = transfers
+            .values()
+            .map(|entry| &entry.transfer)
+            .filter(|t| session_id.is_none_or(|id| t.session_id == id))
+            .collect();
+        list.sort_by_key(|t| t.seq);
+        list.into_iter().map(|t| t.info()).collect()
+    }
+
+    fn spawn(
+        &self,
+        app: &AppHandle,
+        session: &Arc<SftpSession>,
+        transfer: &Arc<Transfer>,
+    ) -> JoinHandle<()> {
+        let slots = Arc::clone(&self.slots);
+        let app = app.clone();
+        let session = Arc::clone(session);
+        let transfer = Arc::clone(transfer);
+        tokio::spawn(async move {
+            let Ok(_slot) = slots.acquire_owned().await else {
+                return;
+            };
+            let resume = {
+                let mut status = transfer.status();
+                if status.state != TransferState::Queued {
+                    return;
+                }
+                status.state = TransferState::Running;
+                status.started
+            };
+            transfer.emit(&app);
+
+            let result = run(&app, &session, &transfer, resume).await;
+            let finished = match result {
+                Ok(()) => transfer.finish(TransferState::Completed, None),
+                Err(e) => {
+                    tracing::warn!("[transfers.rs] Transfer {} failed: {}", transfer.id, e);
+                    transfer.finish(TransferState::Failed, Some(e.to_string()))
+                }
+            };
+            if finished {
+                transfer.emit(&app);
+            }
+        })
+    }
+}
+
+async fn run(
+    app: &AppHandle,
+    session: &SftpSession,
+    transfer: &Transfer,
+    resume: bool,
+) -> Result<()> {
+    let client = session.open_transfer_client().await?;
+    let request = &transfer.request;
+
+    let started = Instant::now();
+    let mut offset = None;
+    let mut last_emit: Option<Instant> = None;
+    let mut on_progress = |progress: TransferProgress| {
+        let offset = *offset.get_or_insert(progress.transferred);
+        let rate = rate(progress.transferred - offset, started.elapsed());
+        {
+            let mut status = transfer.status();
+            status.transferred = progress.transferred;
+            status.total = progress.total;
+            status.rate = rate;
+            status.eta_secs = eta(progress.transferred, progress.total, rate);
+            status.started = true;
+        }
+        if last_emit.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+            last_emit = Some(Instant::now());
+            transfer.emit(app);
+        }
+    };
+
+    let local_path = request.local_path.as_path();
+    let remote_path = request.remote_path.as_str();
+    let mut client = match client {
+        TransferClient::Sftp(client) => client,
+        // SCP can't seek: always a full copy
+        TransferClient::Scp(mut client) => {
+            return full_copy(&mut client, request, &mut on_progress).await;
+        }
+        TransferClient::Ftp(mut client) => {
+            let result = full_copy(&mut client, request, &mut on_progress).await;
+            let _ = client.disconnect().await;
+            return result;
+        }
+    };
+    // A partial copy of another version of the file is started over
+    let source = match request.direction {
+        TransferDirection::Download => {
+            let entry = client.stat_target(remote_path).await?;
+            SourceVersion {
+                size: entry.size,
+                modified: entry.modified,
+            }
+        }
+        TransferDirection::Upload => {
+            let metadata = tokio::fs::metadata(local_path).await?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs() as i64);
+            SourceVersion {
+                size: metadata.len(),
+                modified,
+            }
+        }
+    };
+    let same_source = transfer.status().same_source(source);
+    if resume && !same_source {
+        tracing::info!(
+            "[transfers.rs] Source of transfer {} changed, starting over",
+            transfer.id
+        );
+    }
+    let resume = resume && same_source;
+
+    // A first run replaces whatever the destination held
+    match (request.direction, resume) {
+        (TransferDirection::Download, false) => {
+            client
+                .download_with_progress(remote_path, local_path, &mut on_progress)
+                .await?
+        }
+        (TransferDirection::Upload, false) => {
+            client
+                .upload_with_progress(local_path, remote_path, &mut on_progress)
+                .await?
+        }
+        (TransferDirection::Download, true) => {
+            client
+                .resume_download(remote_path, local_path, &mut on_progress)
+                .await?
+        }
+        (TransferDirection::Upload, true) => {
+            client
+                .resume_upload(local_path, remote_path, &mut on_progress)
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Copy the whole file, replacing the destination (no resume)
+async fn full_copy<C: FileTransferProtocol>(
+    client: &mut C,
+    request: &TransferRequest,
+    progress: ProgressCallback<'_>,
+) -> Result<()> {
+    let local_path = request.local_path.as_path();
+    let remote_path = request.remote_path.as_str();
+    match request.direction {
+        TransferDirection::Download => {
+            client
+                .download_with_progress(remote_path, local_path, progress)
+                .await?
+        }
+        TransferDirection::Upload => {
+            client
+                .upload_with_progress(local_path, remote_path, progress)
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Average transfer rate in bytes per second
+fn rate(bytes: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64();
+    if secs < 0.001 {
+        return 0;
+    }
+    (bytes as f64 / secs) as u64
+}
+
+/// Seconds left at the given rate (unknown without a size or a rate)
+fn eta(transferred: u64, total: Option<u64>, rate: u64) -> Option<u64> {
+    let total = total?;
+    if rate == 0 {
+        return None;
+    }
+    Some(total.saturating_sub(transferred).div_ceil(rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(rate(1000, Duration::from_secs(2)), 500);
+        assert_eq!(rate(1000, Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn test_eta() {
+        assert_eq!(eta(250, Some(1000), 100), Some(8));
+        assert_eq!(eta(1000, Some(1000), 100), Some(0));
+        assert_eq!(eta(250, None, 100), None);
+        assert_eq!(eta(250, Some(1000), 0), None);
+    }
+
+    #[test]
+    fn test_same_source() {
+        let mut status = Status {
+            state: TransferState::Running,
+            transferred: 0,
+            total: None,
+            rate: 0,
+            eta_secs: None,
+            error: None,
+            started: false,
+            source: None,
+        };
+        let version = SourceVersion {
+            size: 1000,
+            modified: Some(1_700_000_000),
+        };
+        assert!(status.same_source(version));
+        assert!(status.same_source(version));
+
+        // Rewritten in place (same size), then truncated
+        let rewritten = SourceVersion {
+            modified: Some(1_700_000_060),
+            ..version
+        };
+        assert!(!status.same_source(rewritten));
+        assert!(status.same_source(rewritten));
+        assert!(!status.same_source(SourceVersion {
+            size: 10,
+            ..rewritten
+        }));
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let request: TransferRequest = serde_json::from_str(
+            r#"{"direction":"download","localPath":"/tmp/a.log","remotePath":"/var/log/a.log"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.direction, TransferDirection::Download);
+        assert_eq!(request.local_path, PathBuf::from("/tmp/a.log"));
+    }
+}
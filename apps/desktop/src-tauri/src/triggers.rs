@@ -0,0 +1,342 @@
+/**
+ * Triggers Module
+ *
+ * Per-connection rules matched against session output: when a line matches
+ * a rule's regex, the rule's action fires (type an answer, notify, highlight
+ * the match, run a snippet). Typical uses: answering "Are you sure (yes/no)?"
+ * prompts, alerting on "ERROR" in a long-running job.
+ *
+ * Lines are matched without ANSI codes, and the unfinished last line too so
+ * that prompts (which end without a newline) can be answered. A rule fires at
+ * most once per line, and not again within `COOLDOWN`, so a rule cannot keep
+ * answering its own echo.
+ *
+ * Answers are typed like keystrokes: they go through line mode, and on
+ * protected hosts a dangerous one waits for the user's confirmation.
+ */
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::prompt::strip_ansi;
+use crate::snippets;
+use crate::state::AppState;
+
+/// Shortest time between two firings of the same rule
+pub const COOLDOWN: Duration = Duration::from_secs(1);
+
+/// Highlight color when the rule gives none
+pub const DEFAULT_HIGHLIGHT_COLOR: &str = "#b58900";
+
+/// Most rules a connection can have
+pub const MAX_RULES: usize = 32;
+
+/// Longest unfinished line kept for matching (the end is kept)
+const MAX_LINE_LEN: usize = 4096;
+
+/// Trigger rule, stored in the connection's session options
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TriggerRule {
+    /// Regex matched against each output line
+    pub pattern: String,
+    /// What happens when the pattern matches
+    pub action: TriggerAction,
+    /// Keep the rule without matching it
+    pub disabled: bool,
+}
+
+/// Action of a trigger rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TriggerAction {
+    /// Type the text, followed by Enter
+    SendText { text: String },
+    /// Show a notification (None = the matching line)
+    Notify { message: Option<String> },
+    /// Highlight the matching line in the terminal (`#rrggbb`)
+    Highlight { color: Option<String> },
+    /// Run a vault snippet (only snippets without variables)
+    RunSnippet { snippet: String },
+}
+
+impl Default for TriggerAction {
+    fn default() -> Self {
+        TriggerAction::Notify { message: None }
+    }
+}
+
+/// Rule that matched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fired {
+    /// 1-based number of the rule in the connection's list
+    pub rule: usize,
+    pub action: TriggerAction,
+    /// Text matched by the pattern
+    pub matched: String,
+    /// Whole line (without ANSI codes)
+    pub line: String,
+}
+
+struct CompiledRule {
+    number: usize,
+    pattern: Regex,
+    action: TriggerAction,
+    /// Already fired (or skipped) on the current line
+    done_on_line: bool,
+    last_fired: Option<Instant>,
+}
+
+/// Trigger rules of one session, matched against its output
+pub struct Triggers {
+    rules: Vec<CompiledRule>,
+    /// Current line (raw bytes, up to the next newline)
+    line: Vec<u8>,
+}
+
+impl Triggers {
+    /// Compile the enabled rules (None when there are none)
+    pub fn new(rules: &[TriggerRule]) -> Result<Option<Self>> {
+        if rules.len() > MAX_RULES {
+            return Err(anyhow!(
+                "Too many triggers ({}, at most {})",
+                rules.len(),
+                MAX_RULES
+            ));
+        }
+
+        let rules = rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| !rule.disabled)
+            .map(|(index, rule)| {
+                let pattern = Regex::new(&rule.pattern)
+                    .map_err(|e| anyhow!("Invalid pattern in trigger {}: {}", index + 1, e))?;
+                if let TriggerAction::Highlight { color: Some(color) } = &rule.action {
+                    if !is_hex_color(color) {
+                        return Err(anyhow!(
+                            "Invalid highlight color in trigger {}: {}",
+                            index + 1,
+                            color
+                        ));
+                    }
+                }
+                Ok(CompiledRule {
+                    number: index + 1,
+                    pattern,
+                    action: rule.action.clone(),
+                    done_on_line: false,
+                    last_fired: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((!rules.is_empty()).then(|| Self {
+            rules,
+            line: Vec::new(),
+        }))
+    }
+
+    /// Feed session output and return the rules that fire
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Fired> {
+        self.feed_at(data, Instant::now())
+    }
+
+    fn feed_at(&mut self, data: &[u8], now: Instant) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        let mut rest = data;
+        while let Some(newline) = rest.iter().position(|b| *b == b'\n') {
+            self.line.extend_from_slice(&rest[..newline]);
+            self.match_line(now, &mut fired);
+            self.line.clear();
+            for rule in &mut self.rules {
+                rule.done_on_line = false;
+            }
+            rest = &rest[newline + 1..];
+        }
+
+        if !rest.is_empty() {
+            self.line.extend_from_slice(rest);
+            if self.line.len() > MAX_LINE_LEN {
+                let excess = self.line.len() - MAX_LINE_LEN;
+                self.line.drain(..excess);
+            }
+            self.match_line(now, &mut fired);
+        }
+        fired
+    }
+
+    fn match_line(&mut self, now: Instant, fired: &mut Vec<Fired>) {
+        let text = strip_ansi(&String::from_utf8_lossy(&self.line));
+        // Carriage returns redraw the line: match what is left visible
+        let line = text.rsplit('\r').find(|s| !s.is_empty()).unwrap_or("");
+        if line.is_empty() {
+            return;
+        }
+
+        for rule in self.rules.iter_mut().filter(|rule| !rule.done_on_line) {
+            let Some(found) = rule.pattern.find(line) else {
+                continue;
+            };
+            rule.done_on_line = true;
+            if rule
+                .last_fired
+                .is_some_and(|last| now.duration_since(last) < COOLDOWN)
+            {
+                continue;
+            }
+            rule.last_fired = Some(now);
+            fired.push(Fired {
+                rule: rule.number,
+                action: rule.action.clone(),
+                matched: found.as_str().to_string(),
+                line: line.to_string(),
+            });
+        }
+    }
+}
+
+/// Text to type into the session for a fired rule, Enter included
+/// (None for actions handled by the frontend)
+///
+/// Snippets are read from the vault when the rule fires, so a locked vault
+/// makes the rule fail instead of running a stale copy.
+pub async fn response(app_handle: &AppHandle, action: &TriggerAction) -> Result<Option<String>> {
+    let text = match action {
+        TriggerAction::SendText { text } => text.clone(),
+        TriggerAction::RunSnippet { snippet } => {
+            let snippet = app_handle
+                .state::<AppState>()
+                .connections
+                .get_snippet(snippet)
+                .await?;
+            snippets::render(&snippet.command, &HashMap::new())
+                .map_err(|e| anyhow!("Snippet {} needs input: {}", snippet.name, e))?
+        }
+        TriggerAction::Notify { .. } | TriggerAction::Highlight { .. } => return Ok(None),
+    };
+
+    // Line breaks are typed as Enter, like a snippet run from the palette
+    let mut text = text.replace("\r\n", "\r").replace('\n', "\r");
+    if !text.ends_with('\r') {
+        text.push('\r');
+    }
+    Ok(Some(text))
+}
+
+/// Send a notify or highlight rule to the frontend
+pub fn emit(app_handle: &AppHandle, session_id: &str, connection_name: &str, fired: &Fired) {
+    let (event, payload) = match &fired.action {
+        TriggerAction::Notify { message } => (
+            "trigger-notification",
+            serde_json::json!({
+                "sessionId": session_id,
+                "connectionName": connection_name,
+                "message": message.as_deref().unwrap_or(&fired.line),
+            }),
+        ),
+        TriggerAction::Highlight { color } => (
+            "trigger-highlight",
+            serde_json::json!({
+                "sessionId": session_id,
+                "text": fired.matched,
+                "color": color.as_deref().unwrap_or(DEFAULT_HIGHLIGHT_COLOR),
+            }),
+        ),
+        TriggerAction::SendText { .. } | TriggerAction::RunSnippet { .. } => return,
+    };
+    let _ = app_handle.emit(event, payload);
+}
+
+/// `#rrggbb` color (the format terminal decorations accept)
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, action: TriggerAction) -> TriggerRule {
+        TriggerRule {
+            pattern: pattern.to_string(),
+            action,
+            disabled: false,
+        }
+    }
+
+    #[test]
+    fn test_rules_fire_once_per_line() {
+        let answer = TriggerAction::SendText {
+            text: "yes".to_string(),
+        };
+        let alert = TriggerAction::Notify { message: None };
+        let mut triggers = Triggers::new(&[
+            rule(r"\(yes/no\)\?", answer.clone()),
+            rule("ERROR", alert.clone()),
+            TriggerRule {
+                disabled: true,
+                ..rule(".", alert.clone())
+            },
+        ])
+        .unwrap()
+        .unwrap();
+        let start = Instant::now();
+
+        // Prompt without a newline, split across chunks, with colors
+        assert!(triggers.feed_at(b"Are you sure (yes/", start).is_empty());
+        let fired = triggers.feed_at(b"\x1b[1mno\x1b[0m)? ", start);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule, 1);
+        assert_eq!(fired[0].action, answer);
+        assert_eq!(fired[0].line, "Are you sure (yes/no)? ");
+
+        // The echoed answer is on the same line: no second firing
+        let later = start + COOLDOWN * 2;
+        assert!(triggers.feed_at(b"yes\r\n", later).is_empty());
+
+        // One firing per line, the whole line and the match are reported
+        let fired = triggers.feed_at(b"12:00 ERROR disk full ERROR\r\nok\r\n", later);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule, 2);
+        assert_eq!(fired[0].matched, "ERROR");
+        assert_eq!(fired[0].line, "12:00 ERROR disk full ERROR");
+
+        // Not again within the cooldown, even on a new line
+        assert!(triggers.feed_at(b"ERROR again\n", later).is_empty());
+        assert_eq!(
+            triggers.feed_at(b"ERROR again\n", later + COOLDOWN).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Triggers::new(&[]).unwrap().is_none());
+        assert!(Triggers::new(&[rule("([bad", TriggerAction::default())]).is_err());
+        assert!(Triggers::new(&[rule(
+            "x",
+            TriggerAction::Highlight {
+                color: Some("red".to_string())
+            }
+        )])
+        .is_err());
+        assert!(Triggers::new(&vec![rule("x", TriggerAction::default()); MAX_RULES + 1]).is_err());
+
+        // Stored form
+        let stored: TriggerRule = serde_json::from_str(
+            r#"{"pattern":"done","action":{"type":"runSnippet","snippet":"s1"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            stored.action,
+            TriggerAction::RunSnippet {
+                snippet: "s1".to_string()
+            }
+        );
+        assert!(!stored.disabled);
+    }
+}
@@ -0,0 +1,209 @@
+/**
+ * Warm Connections Module
+ *
+ * Bookkeeping for SSH transports pre-established right after unlock
+ * (authenticated, no shell yet), so opening those connections later skips
+ * TCP setup, key exchange and authentication
+ */
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Default idle time before an unused warm transport is torn down
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// State of a warm connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WarmState {
+    Connecting,
+    Ready,
+    Failed,
+    /// Torn down (idle timeout or lock) or handed to a session
+    Closed,
+}
+
+/// Status reported to the frontend (`ssh:warm-status` events)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmStatus {
+    pub connection_id: String,
+    pub name: String,
+    pub state: WarmState,
+    pub error: Option<String>,
+}
+
+struct WarmEntry<T> {
+    generation: u64,
+    status: WarmStatus,
+    transport: Option<T>,
+}
+
+/// Warm transports keyed by connection ID
+///
+/// Generic over the transport type so the state handling can be tested
+/// without a server. Every `connecting` call starts a new generation; late
+/// results from an older generation (e.g. after a lock) are handed back to
+/// the caller to be closed.
+pub struct WarmPool<T> {
+    entries: HashMap<String, WarmEntry<T>>,
+    next_generation: u64,
+}
+
+impl<T> Default for WarmPool<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            next_generation: 0,
+        }
+    }
+}
+
+impl<T> WarmPool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start warming a connection, returning its status and generation
+    pub fn connecting(&mut self, connection_id: &str, name: &str) -> (WarmStatus, u64) {
+        self.next_generation += 1;
+        let status = WarmStatus {
+            connection_id: connection_id.to_string(),
+            name: name.to_string(),
+            state: WarmState::Connecting,
+            error: None,
+        };
+        self.entries.insert(
+            connection_id.to_string(),
+            WarmEntry {
+                generation: self.next_generation,
+                status: status.clone(),
+                transport: None,
+            },
+        );
+        (status, self.next_generation)
+    }
+
+    /// Store an established transport
+    ///
+    /// Returns the transport back if the entry was cleared or restarted
+    /// meanwhile.
+    pub fn ready(
+        &mut self,
+        connection_id: &str,
+        generation: u64,
+        transport: T,
+    ) -> Result<WarmStatus, T> {
+        match self.current(connection_id, generation) {
+            Some(entry) => {
+                entry.status.state = WarmState::Ready;
+                entry.transport = Some(transport);
+                Ok(entry.status.clone())
+            }
+            None => Err(transport),
+        }
+    }
+
+    /// Record a failed warm-up
+    pub fn failed(
+        &mut self,
+        connection_id: &str,
+        generation: u64,
+        error: String,
+    ) -> Option<WarmStatus> {
+        let entry = self.current(connection_id, generation)?;
+        entry.status.state = WarmState::Failed;
+        entry.status.error = Some(error);
+        Some(entry.status.clone())
+    }
+
+    /// Take a ready transport for a new session
+    pub fn take(&mut self, connection_id: &str) -> Option<(T, WarmStatus)> {
+        if self.entries.get(connection_id)?.transport.is_none() {
+            return None;
+        }
+        self.close(connection_id)
+    }
+
+    /// Tear down an idle transport if it is still the given generation
+    pub fn expire(&mut self, connection_id: &str, generation: u64) -> Option<(T, WarmStatus)> {
+        self.current(connection_id, generation)?;
+        self.close(connection_id)
+    }
+
+    /// Drop all entries, returning the transports to close
+    pub fn clear(&mut self) -> Vec<(T, WarmStatus)> {
+        let ids: Vec<String> = self.entries.keys().cloned().collect();
+        ids.iter().filter_map(|id| self.close(id)).collect()
+    }
+
+    /// Current status of every warm connection
+    pub fn statuses(&self) -> Vec<WarmStatus> {
+        let mut statuses: Vec<WarmStatus> =
+            self.entries.values().map(|e| e.status.clone()).collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    fn current(&mut self, connection_id: &str, generation: u64) -> Option<&mut WarmEntry<T>> {
+        self.entries
+            .get_mut(connection_id)
+            .filter(|entry| entry.generation == generation)
+    }
+
+    fn close(&mut self, connection_id: &str) -> Option<(T, WarmStatus)> {
+        let entry = self.entries.remove(connection_id)?;
+        let transport = entry.transport?;
+        let status = WarmStatus {
+            state: WarmState::Closed,
+            ..entry.status
+        };
+        Some((transport, status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_and_take() {
+        let mut pool: WarmPool<&str> = WarmPool::new();
+        let (status, generation) = pool.connecting("c1", "web");
+        assert_eq!(status.state, WarmState::Connecting);
+        assert!(pool.take("c1").is_none());
+
+        let status = pool.ready("c1", generation, "transport").unwrap();
+        assert_eq!(status.state, WarmState::Ready);
+        assert_eq!(pool.statuses(), vec![status]);
+
+        let (transport, status) = pool.take("c1").unwrap();
+        assert_eq!(transport, "transport");
+        assert_eq!(status.state, WarmState::Closed);
+        assert!(pool.statuses().is_empty());
+    }
+
+    #[test]
+    fn test_stale_generations() {
+        let mut pool: WarmPool<&str> = WarmPool::new();
+        let (_, first) = pool.connecting("c1", "web");
+
+        // Lock while connecting: the late transport is handed back
+        assert!(pool.clear().is_empty());
+        assert_eq!(pool.ready("c1", first, "late"), Err("late"));
+
+        // Restarted warm-up: the old idle timer doesn't expire the new transport
+        let (_, second) = pool.connecting("c1", "web");
+        pool.ready("c1", second, "fresh").unwrap();
+        assert!(pool.expire("c1", first).is_none());
+        assert_eq!(pool.expire("c1", second).unwrap().0, "fresh");
+
+        // Failures are reported until the next warm-up
+        let (_, third) = pool.connecting("c1", "web");
+        let status = pool
+            .failed("c1", third, "Authentication failed".to_string())
+            .unwrap();
+        assert_eq!(status.state, WarmState::Failed);
+        assert!(pool.take("c1").is_none());
+        assert_eq!(pool.statuses()[0].state, WarmState::Failed);
+    }
+}
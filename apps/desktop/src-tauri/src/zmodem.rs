@@ -0,0 +1,1336 @@
+/**
+ * ZMODEM File Transfer
+ *
+ * Runs `sz` and `rz` transfers inside SSH terminal sessions, like SecureCRT
+ * or Tabby, for hosts without SFTP. Session output is watched for the
+ * ZMODEM start headers: ZRQINIT when the host runs `sz` (the user picks
+ * where each offered file is saved) and ZRINIT when it runs `rz` (the user
+ * picks the files to upload). The transfer then owns the channel until one
+ * side ends or cancels it.
+ *
+ * Only what lrzsz uses is implemented: hex and binary headers with CRC-16
+ * or CRC-32, streaming data subpackets and ZCRC file checks. Commands sent
+ * by the host (ZCOMMAND) are refused.
+ */
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Transfer cancelled after this long without traffic or answer
+pub const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// File data per subpacket sent
+const SUBPACKET_LEN: usize = 1024;
+
+/// Largest subpacket accepted (lrzsz sends up to 8 KiB)
+const MAX_SUBPACKET_LEN: usize = 16 * 1024;
+
+/// Minimum delay between two progress events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+const ZPAD: u8 = b'*';
+const ZDLE: u8 = 0x18;
+const ZBIN: u8 = b'A';
+const ZHEX: u8 = b'B';
+const ZBIN32: u8 = b'C';
+const XON: u8 = 0x11;
+
+// Frame types
+const ZRQINIT: u8 = 0;
+const ZRINIT: u8 = 1;
+const ZSINIT: u8 = 2;
+const ZACK: u8 = 3;
+const ZFILE: u8 = 4;
+const ZSKIP: u8 = 5;
+const ZNAK: u8 = 6;
+const ZABORT: u8 = 7;
+const ZFIN: u8 = 8;
+const ZRPOS: u8 = 9;
+const ZDATA: u8 = 10;
+const ZEOF: u8 = 11;
+const ZFERR: u8 = 12;
+const ZCRC: u8 = 13;
+const ZCAN: u8 = 16;
+const ZCOMMAND: u8 = 18;
+
+// Subpacket ends: followed by a header (ZCRCE), more data (ZCRCG), more
+// data after an ack (ZCRCQ) or a header after an ack (ZCRCW)
+const ZCRCE: u8 = b'h';
+const ZCRCG: u8 = b'i';
+const ZCRCQ: u8 = b'j';
+const ZCRCW: u8 = b'k';
+const ZRUB0: u8 = b'l';
+const ZRUB1: u8 = b'm';
+
+// ZRINIT capabilities
+const CANFDX: u8 = 0x01;
+const CANOVIO: u8 = 0x02;
+const CANFC32: u8 = 0x20;
+
+/// ZFILE conversion option: binary transfer
+const ZCBIN: u8 = 1;
+
+/// Cancels the transfer on the other side (as sent by lrzsz)
+const ABORT: &[u8] =
+    b"\x18\x18\x18\x18\x18\x18\x18\x18\x18\x18\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08";
+
+/// Start of the hex header sent by `sz` (ZRQINIT, "00") or `rz` (ZRINIT, "01")
+const START: &[u8] = b"**\x18B0";
+
+/// Who sends the files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The host runs `sz`
+    Download,
+    /// The host runs `rz`
+    Upload,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Download => "download",
+            Direction::Upload => "upload",
+        }
+    }
+}
+
+/// Session output split at the start of a transfer
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Scan {
+    /// Output for the terminal
+    pub display: Vec<u8>,
+    /// Transfer found, with the output from its start header on
+    pub start: Option<(Direction, Vec<u8>)>,
+}
+
+/// Finds the start of a transfer in session output (the start header may be
+/// split across output chunks)
+#[derive(Debug, Default)]
+pub struct Detector {
+    /// Partial start header at the end of the previous chunk (already shown)
+    pending: Vec<u8>,
+    /// Drop the "OO" `sz` sends after the end of a download
+    over_and_out: bool,
+}
+
+impl Detector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop the "OO" ending a download from the next output
+    pub fn skip_over_and_out(&mut self) {
+        self.over_and_out = true;
+    }
+
+    /// Feed session output
+    pub fn feed(&mut self, mut data: &[u8]) -> Scan {
+        if self.over_and_out && !data.is_empty() {
+            self.over_and_out = false;
+            data = &data[data.iter().take(2).take_while(|&&b| b == b'O').count()..];
+        }
+
+        let shown = self.pending.len();
+        let mut buffer = std::mem::take(&mut self.pending);
+        buffer.extend_from_slice(data);
+
+        let mut search = 0;
+        while let Some(start) = find(&buffer[search..], START).map(|i| search + i) {
+            let direction = match buffer.get(start + START.len()) {
+                Some(b'0') => Direction::Download,
+                Some(b'1') => Direction::Upload,
+                Some(_) => {
+                    search = start + 1;
+                    continue;
+                }
+                None => break,
+            };
+            return Scan {
+                display: buffer[shown.min(start)..start].to_vec(),
+                start: Some((direction, buffer[start..].to_vec())),
+            };
+        }
+
+        let keep = (1..=START.len())
+            .rev()
+            .find(|n| buffer.ends_with(&START[..*n]))
+            .unwrap_or(0);
+        self.pending = buffer[buffer.len() - keep..].to_vec();
+        Scan {
+            display: data.to_vec(),
+            start: None,
+        }
+    }
+}
+
+/// What the frontend is told about a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The host offers a file: answer with `Reply::Save` or `Reply::Skip`
+    Offer { name: String, size: Option<u64> },
+    /// The host waits for files: answer with `Reply::Send`
+    Ready,
+    Progress {
+        name: String,
+        transferred: u64,
+        size: Option<u64>,
+    },
+    /// A file was transferred completely
+    Completed { name: String },
+    /// The transfer is over, with the error when it failed
+    Finished { error: Option<String> },
+}
+
+/// User's answer to an offered file or to a host waiting for files
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum Reply {
+    /// Save the offered file there
+    Save { path: PathBuf },
+    /// Skip the offered file
+    Skip,
+    /// Upload these files
+    Send { paths: Vec<PathBuf> },
+    /// Cancel the transfer
+    Cancel,
+}
+
+/// Frames to send to the host and events for the frontend
+#[derive(Debug, Default)]
+pub struct Step {
+    pub send: Vec<u8>,
+    pub events: Vec<Event>,
+}
+
+/// Where an offered file is saved unless the user picks another place: the
+/// downloads folder, without replacing an existing file
+pub fn download_path(name: &str) -> PathBuf {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default();
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Crc {
+    Crc16,
+    Crc32,
+}
+
+impl Crc {
+    fn len(self) -> usize {
+        match self {
+            Crc::Crc16 => 2,
+            Crc::Crc32 => 4,
+        }
+    }
+
+    /// CRC of the parts, in wire order
+    fn checksum(self, parts: &[&[u8]]) -> Vec<u8> {
+        match self {
+            Crc::Crc16 => parts
+                .iter()
+                .fold(0, |crc, part| crc16_update(crc, part))
+                .to_be_bytes()
+                .to_vec(),
+            Crc::Crc32 => (!parts.iter().fold(!0, |crc, part| crc32_update(crc, part)))
+                .to_le_bytes()
+                .to_vec(),
+        }
+    }
+}
+
+const CRC16_TABLE: [u16; 256] = crc16_table();
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// CRC-16/XMODEM
+const fn crc16_table() -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 (IEEE)
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc16_update(crc: u16, data: &[u8]) -> u16 {
+    data.iter().fold(crc, |crc, &byte| {
+        (crc << 8) ^ CRC16_TABLE[((crc >> 8) as u8 ^ byte) as usize]
+    })
+}
+
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (crc >> 8) ^ CRC32_TABLE[(crc as u8 ^ byte) as usize]
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    kind: u8,
+    data: [u8; 4],
+}
+
+impl Header {
+    fn new(kind: u8) -> Self {
+        Self { kind, data: [0; 4] }
+    }
+
+    /// Header carrying a file position (ZRPOS, ZDATA, ZEOF, ZACK)
+    fn at(kind: u8, position: u64) -> Self {
+        Self {
+            kind,
+            data: (position as u32).to_le_bytes(),
+        }
+    }
+
+    /// Header carrying flags (ZF0 is the last byte)
+    fn with_flags(kind: u8, zf0: u8) -> Self {
+        Self {
+            kind,
+            data: [0, 0, 0, zf0],
+        }
+    }
+
+    fn position(&self) -> u64 {
+        u32::from_le_bytes(self.data) as u64
+    }
+
+    fn zf0(&self) -> u8 {
+        self.data[3]
+    }
+
+    fn bytes(&self) -> [u8; 5] {
+        let [d0, d1, d2, d3] = self.data;
+        [self.kind, d0, d1, d2, d3]
+    }
+
+    fn to_hex(self) -> Vec<u8> {
+        let bytes = self.bytes();
+        let mut out = b"**\x18B".to_vec();
+        for byte in bytes.iter().chain(&Crc::Crc16.checksum(&[&bytes])) {
+            out.extend_from_slice(format!("{:02x}", byte).as_bytes());
+        }
+        out.extend_from_slice(b"\r\x8a");
+        if self.kind != ZACK && self.kind != ZFIN {
+            out.push(XON);
+        }
+        out
+    }
+
+    fn to_binary(self, crc: Crc) -> Vec<u8> {
+        let bytes = self.bytes();
+        let encoding = match crc {
+            Crc::Crc16 => ZBIN,
+            Crc::Crc32 => ZBIN32,
+        };
+        let mut out = vec![ZPAD, ZDLE, encoding];
+        for &byte in bytes.iter().chain(&crc.checksum(&[&bytes])) {
+            escape(&mut out, byte);
+        }
+        out
+    }
+}
+
+fn escape(out: &mut Vec<u8>, byte: u8) {
+    match byte {
+        ZDLE | 0x10 | 0x90 | XON | 0x91 | 0x13 | 0x93 | b'\r' | 0x8d => {
+            out.extend_from_slice(&[ZDLE, byte ^ 0x40])
+        }
+        _ => out.push(byte),
+    }
+}
+
+fn subpacket(data: &[u8], end: u8, crc: Crc) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 8 + 8);
+    for &byte in data {
+        escape(&mut out, byte);
+    }
+    out.extend_from_slice(&[ZDLE, end]);
+    for byte in crc.checksum(&[data, &[end]]) {
+        escape(&mut out, byte);
+    }
+    if end == ZCRCW {
+        out.push(XON);
+    }
+    out
+}
+
+/// Decoded input
+#[derive(Debug, PartialEq, Eq)]
+enum Frame {
+    Header(Header),
+    /// Data subpacket and how it ends (ZCRCE, ZCRCG, ZCRCQ or ZCRCW)
+    Data(Vec<u8>, u8),
+    /// Data subpacket with a bad CRC
+    Corrupt,
+    /// Cancel sequence (five ZDLE in a row)
+    Cancel,
+}
+
+enum Parsed<T> {
+    Incomplete,
+    Invalid,
+    /// Value and the input length it used
+    Done(T, usize),
+}
+
+enum Unescaped {
+    Byte(u8),
+    /// End of a data subpacket
+    End(u8),
+    Invalid,
+}
+
+/// Escaped byte at `i` and the position after it, None when incomplete
+fn unescape(buffer: &[u8], mut i: usize) -> Option<(Unescaped, usize)> {
+    loop {
+        let byte = *buffer.get(i)?;
+        i += 1;
+        match byte {
+            // Flow control noise
+            XON | 0x13 | 0x91 | 0x93 => continue,
+            ZDLE => {
+                let next = *buffer.get(i)?;
+                let value = match next {
+                    ZCRCE | ZCRCG | ZCRCQ | ZCRCW => Unescaped::End(next),
+                    ZRUB0 => Unescaped::Byte(0x7f),
+                    ZRUB1 => Unescaped::Byte(0xff),
+                    _ if next & 0x60 == 0x40 => Unescaped::Byte(next ^ 0x40),
+                    _ => Unescaped::Invalid,
+                };
+                return Some((value, i + 1));
+            }
+            _ => return Some((Unescaped::Byte(byte), i)),
+        }
+    }
+}
+
+fn read_bytes(buffer: &[u8], mut i: usize, n: usize) -> Parsed<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(n);
+    while bytes.len() < n {
+        match unescape(buffer, i) {
+            None => return Parsed::Incomplete,
+            Some((Unescaped::Byte(byte), next)) => {
+                bytes.push(byte);
+                i = next;
+            }
+            Some(_) => return Parsed::Invalid,
+        }
+    }
+    Parsed::Done(bytes, i)
+}
+
+fn decode_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    hex.chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+/// Header at the start of `buffer` (which starts with ZPAD)
+fn parse_header(buffer: &[u8]) -> Parsed<(Header, Crc)> {
+    let Some(i) = buffer.iter().position(|&b| b != ZPAD) else {
+        return Parsed::Incomplete;
+    };
+    if buffer[i] != ZDLE {
+        return Parsed::Invalid;
+    }
+    let Some(&encoding) = buffer.get(i + 1) else {
+        return Parsed::Incomplete;
+    };
+    let i = i + 2;
+
+    let (bytes, crc, used) = match encoding {
+        ZHEX => {
+            let Some(hex) = buffer.get(i..i + 14) else {
+                return Parsed::Incomplete;
+            };
+            let Some(bytes) = decode_hex(hex) else {
+                return Parsed::Invalid;
+            };
+            (bytes, Crc::Crc16, i + 14)
+        }
+        ZBIN | ZBIN32 => {
+            let crc = if encoding == ZBIN32 {
+                Crc::Crc32
+            } else {
+                Crc::Crc16
+            };
+            match read_bytes(buffer, i, 5 + crc.len()) {
+                Parsed::Done(bytes, used) => (bytes, crc, used),
+                Parsed::Incomplete => return Parsed::Incomplete,
+                Parsed::Invalid => return Parsed::Invalid,
+            }
+        }
+        _ => return Parsed::Invalid,
+    };
+
+    let (header, checksum) = bytes.split_at(5);
+    if crc.checksum(&[header]) != checksum {
+        return Parsed::Invalid;
+    }
+    let header = Header {
+        kind: header[0],
+        data: [header[1], header[2], header[3], header[4]],
+    };
+    Parsed::Done((header, crc), used)
+}
+
+/// Data subpacket at the start of `buffer`
+fn parse_subpacket(buffer: &[u8], crc: Crc) -> Parsed<(Vec<u8>, u8)> {
+    let mut data = Vec::new();
+    let mut i = 0;
+    loop {
+        match unescape(buffer, i) {
+            None => return Parsed::Incomplete,
+            Some((Unescaped::Byte(byte), next)) => {
+                if data.len() == MAX_SUBPACKET_LEN {
+                    return Parsed::Invalid;
+                }
+                data.push(byte);
+                i = next;
+            }
+            Some((Unescaped::End(end), next)) => {
+                return match read_bytes(buffer, next, crc.len()) {
+                    Parsed::Done(checksum, used) if checksum == crc.checksum(&[&data, &[end]]) => {
+                        Parsed::Done((data, end), used)
+                    }
+                    Parsed::Incomplete => Parsed::Incomplete,
+                    _ => Parsed::Invalid,
+                };
+            }
+            Some((Unescaped::Invalid, _)) => return Parsed::Invalid,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Decoder {
+    buffer: Vec<u8>,
+    /// CRC of the data subpackets following the last header, if any
+    data: Option<Crc>,
+}
+
+impl Decoder {
+    fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn next(&mut self) -> Option<Frame> {
+        if let Some(i) = find(&self.buffer, &[ZDLE; 5]) {
+            self.buffer.drain(..i + 5);
+            self.data = None;
+            return Some(Frame::Cancel);
+        }
+
+        loop {
+            if let Some(crc) = self.data {
+                return match parse_subpacket(&self.buffer, crc) {
+                    Parsed::Incomplete => None,
+                    Parsed::Invalid => {
+                        // Look for the next header
+                        self.buffer.drain(..1);
+                        self.data = None;
+                        Some(Frame::Corrupt)
+                    }
+                    Parsed::Done((data, end), used) => {
+                        self.buffer.drain(..used);
+                        if end == ZCRCE || end == ZCRCW {
+                            self.data = None;
+                        }
+                        Some(Frame::Data(data, end))
+                    }
+                };
+            }
+
+            let Some(start) = self.buffer.iter().position(|&b| b == ZPAD) else {
+                // Keep what may be the start of a cancel sequence
+                let keep = self.buffer.iter().rev().take_while(|&&b| b == ZDLE).count();
+                self.buffer.drain(..self.buffer.len() - keep);
+                return None;
+            };
+            self.buffer.drain(..start);
+            match parse_header(&self.buffer) {
+                Parsed::Incomplete => return None,
+                Parsed::Invalid => {
+                    self.buffer.drain(..1);
+                }
+                Parsed::Done((header, crc), used) => {
+                    self.buffer.drain(..used);
+                    if matches!(header.kind, ZSINIT | ZFILE | ZDATA | ZCOMMAND) {
+                        self.data = Some(crc);
+                    }
+                    return Some(Frame::Header(header));
+                }
+            }
+        }
+    }
+}
+
+/// File being downloaded
+struct Incoming {
+    name: String,
+    path: PathBuf,
+    file: File,
+    size: Option<u64>,
+    position: u64,
+    /// Data for `position` expected (false after an error, until the host
+    /// sends from there again)
+    in_sync: bool,
+}
+
+/// File being uploaded
+struct Outgoing {
+    name: String,
+    file: File,
+    size: u64,
+    position: u64,
+    /// ZFILE header and info subpacket, sent again when lost
+    offer: Vec<u8>,
+    /// Position of the last ZCRCW
+    acked: u64,
+}
+
+enum State {
+    /// Download: ZRINIT sent, waiting for a file or the end
+    Idle,
+    /// Download: file offered to the user
+    Offered {
+        name: String,
+        size: Option<u64>,
+    },
+    /// Download: receiving a file
+    Receiving(Incoming),
+    /// Upload: waiting for the host's ZRINIT
+    Starting,
+    /// Upload: waiting for the user to pick files
+    Ready,
+    /// Upload: ZFILE sent, waiting for the position to send from
+    Offering(Outgoing),
+    /// Upload: sending data
+    Sending(Outgoing),
+    /// Upload: waiting for the host to acknowledge a ZCRCW
+    Waiting(Outgoing),
+    /// Upload: ZEOF sent, waiting for the host to confirm
+    Closing(Outgoing),
+    /// Upload: ZFIN sent
+    Finishing,
+    Done,
+}
+
+/// A ZMODEM transfer in a terminal session
+pub struct Zmodem {
+    direction: Direction,
+    decoder: Decoder,
+    state: State,
+    /// Last header received (the data subpackets that follow belong to it)
+    last_header: u8,
+    /// Upload: CRC the host accepts
+    crc: Crc,
+    /// Upload: the host's buffer size (0: no limit)
+    window: u64,
+    /// Upload: files left to send
+    queue: VecDeque<PathBuf>,
+    step: Step,
+    progress_at: Option<Instant>,
+}
+
+impl Zmodem {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            decoder: Decoder::default(),
+            state: match direction {
+                Direction::Download => State::Idle,
+                Direction::Upload => State::Starting,
+            },
+            last_header: ZRQINIT,
+            crc: Crc::Crc16,
+            window: 0,
+            queue: VecDeque::new(),
+            step: Step::default(),
+            progress_at: None,
+        }
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Whether file data is waiting to be sent (with `send_next`)
+    pub fn wants_to_send(&self) -> bool {
+        matches!(self.state, State::Sending(_))
+    }
+
+    /// Output received after the end of the transfer
+    pub fn take_rest(&mut self) -> Vec<u8> {
+        let mut rest = std::mem::take(&mut self.decoder.buffer);
+        // End of the last hex header
+        let end = rest
+            .iter()
+            .take_while(|&&b| matches!(b, b'\r' | b'\n' | 0x8a | XON))
+            .count();
+        rest.drain(..end);
+        rest
+    }
+
+    /// Feed session output
+    pub fn feed(&mut self, data: &[u8]) -> Step {
+        self.decoder.feed(data);
+        while !self.is_done() {
+            let Some(frame) = self.decoder.next() else {
+                break;
+            };
+            if let Err(e) = self.on_frame(frame) {
+                self.abort(e.to_string());
+            }
+        }
+        std::mem::take(&mut self.step)
+    }
+
+    /// Answer an offered file or the host waiting for files
+    pub fn reply(&mut self, reply: Reply) -> Step {
+        if !self.is_done() {
+            if let Err(e) = self.on_reply(reply) {
+                self.abort(e.to_string());
+            }
+        }
+        std::mem::take(&mut self.step)
+    }
+
+    /// Send the next data subpacket of the file being uploaded
+    pub fn send_next(&mut self) -> Step {
+        if let Err(e) = self.send_data() {
+            self.abort(e.to_string());
+        }
+        std::mem::take(&mut self.step)
+    }
+
+    /// Cancel the transfer on both sides
+    pub fn cancel(&mut self, reason: &str) -> Step {
+        if !self.is_done() {
+            self.abort(reason.to_string());
+        }
+        std::mem::take(&mut self.step)
+    }
+
+    fn send(&mut self, data: Vec<u8>) {
+        self.step.send.extend(data);
+    }
+
+    fn abort(&mut self, error: String) {
+        self.send(ABORT.to_vec());
+        self.finish(Some(error));
+    }
+
+    fn finish(&mut self, error: Option<String>) {
+        if let State::Receiving(incoming) = std::mem::replace(&mut self.state, State::Done) {
+            // Partial download
+            drop(incoming.file);
+            let _ = std::fs::remove_file(&incoming.path);
+        }
+        self.step.events.push(Event::Finished { error });
+    }
+
+    fn progress(&mut self, name: &str, transferred: u64, size: Option<u64>) {
+        let now = Instant::now();
+        if self
+            .progress_at
+            .is_some_and(|at| now.duration_since(at) < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.progress_at = Some(now);
+        self.step.events.push(Event::Progress {
+            name: name.to_string(),
+            transferred,
+            size,
+        });
+    }
+
+    fn on_frame(&mut self, frame: Frame) -> Result<()> {
+        let header = match frame {
+            Frame::Header(header) => header,
+            Frame::Data(data, end) => return self.on_data(data, end),
+            Frame::Corrupt => {
+                self.on_corrupt();
+                return Ok(());
+            }
+            Frame::Cancel => {
+                self.finish(Some("Cancelled by the host".to_string()));
+                return Ok(());
+            }
+        };
+        self.last_header = header.kind;
+        match header.kind {
+            ZABORT | ZFERR | ZCAN => {
+                self.finish(Some("Cancelled by the host".to_string()));
+                Ok(())
+            }
+            ZCOMMAND => Err(anyhow!("Refused a command sent by the host")),
+            _ => match self.direction {
+                Direction::Download => self.on_download_header(header),
+                Direction::Upload => self.on_upload_header(header),
+            },
+        }
+    }
+
+    fn on_download_header(&mut self, header: Header) -> Result<()> {
+        match (header.kind, &mut self.state) {
+            (ZRQINIT, State::Idle) => {
+                self.send(Header::with_flags(ZRINIT, CANFDX | CANOVIO | CANFC32).to_hex());
+            }
+            (ZFIN, State::Idle) => {
+                self.send(Header::new(ZFIN).to_hex());
+                self.finish(None);
+            }
+            (ZDATA, State::Receiving(incoming)) => {
+                incoming.in_sync = header.position() == incoming.position;
+                if !incoming.in_sync {
+                    let position = incoming.position;
+                    self.send(Header::at(ZRPOS, position).to_hex());
+                }
+            }
+            (ZEOF, State::Receiving(incoming)) => {
+                if header.position() != incoming.position {
+                    // Data was lost: ask for it again
+                    let position = incoming.position;
+                    self.send(Header::at(ZRPOS, position).to_hex());
+                    return Ok(());
+                }
+                incoming.file.flush().context("Failed to write the file")?;
+                let name = incoming.name.clone();
+                self.state = State::Idle;
+                self.step.events.push(Event::Completed { name });
+                self.send(Header::with_flags(ZRINIT, CANFDX | CANOVIO | CANFC32).to_hex());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_data(&mut self, data: Vec<u8>, end: u8) -> Result<()> {
+        match (self.last_header, &mut self.state) {
+            // Attention string, unused
+            (ZSINIT, _) => self.send(Header::new(ZACK).to_hex()),
+            (ZFILE, State::Idle) => {
+                let (name, size) = parse_file_info(&data)?;
+                self.step.events.push(Event::Offer {
+                    name: name.clone(),
+                    size,
+                });
+                self.state = State::Offered { name, size };
+            }
+            (ZDATA, State::Receiving(incoming)) if incoming.in_sync => {
+                incoming
+                    .file
+                    .write_all(&data)
+                    .context("Failed to write the file")?;
+                incoming.position += data.len() as u64;
+                let (name, position, size) =
+                    (incoming.name.clone(), incoming.position, incoming.size);
+                if end == ZCRCQ || end == ZCRCW {
+                    self.send(Header::at(ZACK, position).to_hex());
+                }
+                self.progress(&name, position, size);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_corrupt(&mut self) {
+        match &mut self.state {
+            State::Receiving(incoming) => {
+                incoming.in_sync = false;
+                let position = incoming.position;
+                self.send(Header::at(ZRPOS, position).to_hex());
+            }
+            _ => self.send(Header::new(ZNAK).to_hex()),
+        }
+    }
+
+    fn on_upload_header(&mut self, header: Header) -> Result<()> {
+        let state = std::mem::replace(&mut self.state, State::Done);
+        self.state = match (header.kind, state) {
+            (ZRINIT, State::Starting) => {
+                self.crc = match header.zf0() & CANFC32 {
+                    0 => Crc::Crc16,
+                    _ => Crc::Crc32,
+                };
+                self.window = u16::from_le_bytes([header.data[0], header.data[1]]) as u64;
+                self.step.events.push(Event::Ready);
+                State::Ready
+            }
+            (
+                ZRPOS,
+                State::Offering(mut outgoing)
+                | State::Sending(mut outgoing)
+                | State::Waiting(mut outgoing)
+                | State::Closing(mut outgoing),
+            ) => {
+                outgoing.position = header.position();
+                outgoing.acked = outgoing.position;
+                outgoing
+                    .file
+                    .seek(SeekFrom::Start(outgoing.position))
+                    .context("Failed to read the file")?;
+                self.send(Header::at(ZDATA, outgoing.position).to_binary(self.crc));
+                State::Sending(outgoing)
+            }
+            // Offer lost
+            (ZRINIT | ZNAK, State::Offering(outgoing)) => {
+                self.send(outgoing.offer.clone());
+                State::Offering(outgoing)
+            }
+            // Resuming: the host compares what it has (0: the whole file)
+            (ZCRC, State::Offering(mut outgoing)) => {
+                let crc = file_crc(&mut outgoing.file, header.position())?;
+                self.send(
+                    Header {
+                        kind: ZCRC,
+                        data: crc.to_le_bytes(),
+                    }
+                    .to_binary(self.crc),
+                );
+                State::Offering(outgoing)
+            }
+            (ZACK, State::Waiting(outgoing)) if header.position() == outgoing.position => {
+                self.send(Header::at(ZDATA, outgoing.position).to_binary(self.crc));
+                State::Sending(outgoing)
+            }
+            (ZRINIT, State::Closing(outgoing)) => {
+                self.step.events.push(Event::Completed {
+                    name: outgoing.name,
+                });
+                return self.next_file();
+            }
+            (
+                ZSKIP,
+                State::Offering(_) | State::Sending(_) | State::Waiting(_) | State::Closing(_),
+            ) => {
+                return self.next_file();
+            }
+            (ZFIN, State::Finishing) => {
+                self.send(b"OO".to_vec());
+                self.finish(None);
+                return Ok(());
+            }
+            (_, state) => state,
+        };
+        Ok(())
+    }
+
+    fn on_reply(&mut self, reply: Reply) -> Result<()> {
+        match (reply, &self.state) {
+            (Reply::Cancel, _) => self.abort("Cancelled".to_string()),
+            (Reply::Save { path }, State::Offered { name, size }) => {
+                let file = File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                self.state = State::Receiving(Incoming {
+                    name: name.clone(),
+                    path,
+                    file,
+                    size: *size,
+                    position: 0,
+                    in_sync: false,
+                });
+                self.send(Header::at(ZRPOS, 0).to_hex());
+            }
+            (Reply::Skip, State::Offered { .. }) => {
+                self.state = State::Idle;
+                self.send(Header::new(ZSKIP).to_hex());
+            }
+            (Reply::Send { paths }, State::Ready) => {
+                if paths.is_empty() {
+                    return Err(anyhow!("No files to send"));
+                }
+                self.queue = paths.into();
+                self.next_file()?;
+            }
+            (reply, _) => tracing::warn!("[zmodem.rs] Ignoring unexpected reply: {:?}", reply),
+        }
+        Ok(())
+    }
+
+    /// Offer the next file to the host, or end the transfer
+    fn next_file(&mut self) -> Result<()> {
+        let Some(path) = self.queue.pop_front() else {
+            self.send(Header::new(ZFIN).to_hex());
+            self.state = State::Finishing;
+            return Ok(());
+        };
+
+        let file =
+            File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let metadata = file.metadata()?;
+        if metadata.is_dir() {
+            return Err(anyhow!("{} is a folder", path.display()));
+        }
+        if metadata.len() > u32::MAX as u64 {
+            return Err(anyhow!(
+                "{} is too large for ZMODEM (4 GiB)",
+                path.display()
+            ));
+        }
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("Invalid file name: {}", path.display()))?
+            .to_string();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_secs());
+        let bytes_left: u64 = self
+            .queue
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        // name, size, mtime (octal), mode (octal), serial, files and bytes left
+        let info = format!(
+            "{}\0{} {:o} 100644 0 {} {}\0",
+            name,
+            metadata.len(),
+            modified,
+            self.queue.len() + 1,
+            metadata.len() + bytes_left
+        );
+        let mut offer = Header::with_flags(ZFILE, ZCBIN).to_binary(self.crc);
+        offer.extend(subpacket(info.as_bytes(), ZCRCW, self.crc));
+        self.send(offer.clone());
+        self.state = State::Offering(Outgoing {
+            name,
+            file,
+            size: metadata.len(),
+            position: 0,
+            offer,
+            acked: 0,
+        });
+        Ok(())
+    }
+
+    fn send_data(&mut self) -> Result<()> {
+        let (crc, window) = (self.crc, self.window);
+        let State::Sending(outgoing) = &mut self.state else {
+            return Ok(());
+        };
+
+        let mut buffer = vec![0; SUBPACKET_LEN];
+        let mut length = 0;
+        while length < SUBPACKET_LEN {
+            match outgoing.file.read(&mut buffer[length..]) {
+                Ok(0) => break,
+                Ok(n) => length += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e).context("Failed to read the file"),
+            }
+        }
+        buffer.truncate(length);
+        outgoing.position += length as u64;
+
+        let last = length < SUBPACKET_LEN || outgoing.position >= outgoing.size;
+        // The host's buffer would overflow: wait for it to catch up
+        let full = window > 0 && outgoing.position - outgoing.acked + SUBPACKET_LEN as u64 > window;
+        let end = match (last, full) {
+            (true, _) => ZCRCE,
+            (false, true) => ZCRCW,
+            (false, false) => ZCRCG,
+        };
+        let (name, position, size) = (outgoing.name.clone(), outgoing.position, outgoing.size);
+
+        self.send(subpacket(&buffer, end, crc));
+        if last || full {
+            let State::Sending(mut outgoing) = std::mem::replace(&mut self.state, State::Done)
+            else {
+                return Ok(());
+            };
+            self.state = if last {
+                self.send(Header::at(ZEOF, position).to_binary(crc));
+                State::Closing(outgoing)
+            } else {
+                outgoing.acked = position;
+                State::Waiting(outgoing)
+            };
+        }
+        self.progress(&name, position, Some(size));
+        Ok(())
+    }
+}
+
+/// Name and size from a ZFILE subpacket ("name\0size mtime mode ...\0")
+fn parse_file_info(data: &[u8]) -> Result<(String, Option<u64>)> {
+    let mut fields = data.splitn(2, |&b| b == 0);
+    let name = String::from_utf8_lossy(fields.next().unwrap_or_default());
+    // The host picks the name: only keep the file name
+    let name = Path::new(name.as_ref())
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| anyhow!("Invalid file name offered: {}", name))?
+        .to_string();
+    let size = fields
+        .next()
+        .and_then(|info| info.split(|&b| b == 0).next())
+        .and_then(|info| {
+            String::from_utf8_lossy(info)
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()
+        });
+    Ok((name, size))
+}
+
+/// CRC-32 of the first `length` bytes of a file (0: all of it)
+fn file_crc(file: &mut File, length: u64) -> Result<u32> {
+    file.seek(SeekFrom::Start(0))?;
+    let limit = if length == 0 { u64::MAX } else { length };
+    let mut reader = file.take(limit);
+    let mut crc = !0;
+    let mut buffer = [0; 8192];
+    loop {
+        let n = reader
+            .read(&mut buffer)
+            .context("Failed to read the file")?;
+        if n == 0 {
+            break;
+        }
+        crc = crc32_update(crc, &buffer[..n]);
+    }
+    Ok(!crc)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_headers() {
+        // As sent by lrzsz
+        assert_eq!(
+            Header::with_flags(ZRINIT, CANFDX | CANOVIO | CANFC32).to_hex(),
+            b"**\x18B0100000023be50\r\x8a\x11"
+        );
+        assert_eq!(
+            Header::new(ZRQINIT).to_hex(),
+            b"**\x18B00000000000000\r\x8a\x11"
+        );
+
+        let mut decoder = Decoder::default();
+        decoder.feed(b"rz\r**\x18B0100000023be50\r\x8a\x11");
+        assert_eq!(
+            decoder.next(),
+            Some(Frame::Header(Header::with_flags(ZRINIT, 0x23)))
+        );
+        assert_eq!(decoder.next(), None);
+
+        decoder.feed(b"**\x18B0100000023be51\r\x8a\x11");
+        assert_eq!(decoder.next(), None);
+        decoder.feed(b"\x18\x18\x18\x18\x18\x08\x08");
+        assert_eq!(decoder.next(), Some(Frame::Cancel));
+    }
+
+    #[test]
+    fn test_detector() {
+        let mut detector = Detector::new();
+        assert_eq!(detector.feed(b"ls\r\n").display, b"ls\r\n");
+
+        // Start header split across chunks
+        assert_eq!(detector.feed(b"rz\r**").display, b"rz\r**");
+        let scan = detector.feed(b"\x18B0100000023be50\r\x8a\x11");
+        assert!(scan.display.is_empty());
+        assert_eq!(
+            scan.start,
+            Some((
+                Direction::Upload,
+                b"**\x18B0100000023be50\r\x8a\x11".to_vec()
+            ))
+        );
+
+        let scan = detector.feed(b"x**\x18B00000000000000\r\x8a\x11");
+        assert_eq!(scan.display, b"x");
+        assert_eq!(
+            scan.start.map(|(direction, _)| direction),
+            Some(Direction::Download)
+        );
+
+        detector.skip_over_and_out();
+        assert_eq!(detector.feed(b"OO$ ").display, b"$ ");
+        assert_eq!(detector.feed(b"OO").display, b"OO");
+    }
+
+    #[test]
+    fn test_parse_file_info() {
+        assert_eq!(
+            parse_file_info(b"../../.bashrc\x00120 14650573031 100644 0 1 120\x00").unwrap(),
+            (".bashrc".to_string(), Some(120))
+        );
+        assert_eq!(
+            parse_file_info(b"notes.txt\x00").unwrap(),
+            ("notes.txt".to_string(), None)
+        );
+        assert!(parse_file_info(b"..\x0012\x00").is_err());
+    }
+
+    /// Upload from one side to the other, with a corrupted subpacket
+    #[test]
+    fn test_transfer() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.bin");
+        let target = dir.path().join("target.bin");
+        let data: Vec<u8> = (0..5000u32).map(|i| (i * 7 % 256) as u8).collect();
+        std::fs::write(&source, &data).unwrap();
+
+        // RITE uploading (to `rz`) on one side, downloading (from `sz`) on the other
+        let mut sender = Zmodem::new(Direction::Upload);
+        let mut receiver = Zmodem::new(Direction::Download);
+        let mut to_sender = receiver.feed(&Header::new(ZRQINIT).to_hex()).send;
+        let mut to_receiver = Vec::new();
+        let mut corrupted = false;
+        let mut completed = Vec::new();
+
+        for _ in 0..1000 {
+            if sender.is_done() && receiver.is_done() {
+                break;
+            }
+            let step = sender.feed(&std::mem::take(&mut to_sender));
+            to_receiver.extend(step.send);
+            for event in step.events {
+                match event {
+                    Event::Ready => to_receiver.extend(
+                        sender
+                            .reply(Reply::Send {
+                                paths: vec![source.clone()],
+                            })
+                            .send,
+                    ),
+                    Event::Finished { error } => assert_eq!(error, None),
+                    _ => {}
+                }
+            }
+            if sender.wants_to_send() {
+                let mut packet = sender.send_next().send;
+                if !corrupted && packet.len() > 1000 {
+                    packet[500] ^= 0x01;
+                    corrupted = true;
+                }
+                to_receiver.extend(packet);
+            }
+
+            let step = receiver.feed(&std::mem::take(&mut to_receiver));
+            to_sender.extend(step.send);
+            for event in step.events {
+                match event {
+                    Event::Offer { name, size } => {
+                        assert_eq!((name.as_str(), size), ("source.bin", Some(5000)));
+                        to_sender.extend(
+                            receiver
+                                .reply(Reply::Save {
+                                    path: target.clone(),
+                                })
+                                .send,
+                        );
+                    }
+                    Event::Completed { name } => completed.push(name),
+                    Event::Finished { error } => assert_eq!(error, None),
+                    _ => {}
+                }
+            }
+        }
+
+        assert!(corrupted);
+        assert!(sender.is_done() && receiver.is_done());
+        assert_eq!(completed, vec!["source.bin"]);
+        assert_eq!(std::fs::read(&target).unwrap(), data);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("partial.bin");
+
+        let mut receiver = Zmodem::new(Direction::Download);
+        receiver.feed(&Header::new(ZRQINIT).to_hex());
+        let mut offer = Header::with_flags(ZFILE, ZCBIN).to_binary(Crc::Crc32);
+        offer.extend(subpacket(b"partial.bin\x0010\x00", ZCRCW, Crc::Crc32));
+        receiver.feed(&offer);
+        receiver.reply(Reply::Save {
+            path: target.clone(),
+        });
+        assert!(target.exists());
+
+        let step = receiver.cancel("Cancelled");
+        assert!(step.send.ends_with(ABORT));
+        assert_eq!(
+            step.events,
+            vec![Event::Finished {
+                error: Some("Cancelled".to_string())
+            }]
+        );
+        assert!(receiver.is_done());
+        assert!(!target.exists());
+    }
+}
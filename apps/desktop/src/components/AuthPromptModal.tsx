@@ -0,0 +1,104 @@
+/**
+ * Keyboard-Interactive Prompt Modal
+ *
+ * Shows the prompts a server sends during keyboard-interactive
+ * authentication (OTP codes, PAM questions) and sends the answers back.
+ * Prompts arrive one round at a time while a connection is being opened.
+ */
+
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { Tauri, type AuthPromptEvent } from '../utils/tauri';
+
+export function AuthPromptModal() {
+  const [request, setRequest] = useState<AuthPromptEvent | null>(null);
+  const [answers, setAnswers] = useState<string[]>([]);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    const unlisten = listen<AuthPromptEvent>('ssh:auth-prompt', (event) => {
+      setRequest(event.payload);
+      setAnswers(event.payload.prompts.map(() => ''));
+      setError('');
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  if (!request) {
+    return null;
+  }
+
+  const reply = async (values: string[] | null) => {
+    try {
+      await Tauri.Terminal.answerAuthPrompt(request.promptId, values);
+      setRequest(null);
+      setAnswers([]);
+    } catch (err) {
+      // The prompt expired or the connection was closed
+      setError(err as string);
+    }
+  };
+
+  const handleSubmit = (e: React.FormEvent) => {
+    e.preventDefault();
+    reply(answers);
+  };
+
+  return (
+    <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
+      <div className="mx-4 w-full max-w-md rounded-lg bg-background p-6 shadow-xl border border-border">
+        <h2 className="mb-2 text-xl font-bold">{request.name || 'Authentication required'}</h2>
+        <p className="mb-4 text-sm text-muted-foreground">
+          {request.username}@{request.host}:{request.port}
+        </p>
+        {request.instructions && (
+          <p className="mb-4 whitespace-pre-wrap text-sm">{request.instructions}</p>
+        )}
+
+        {error && (
+          <div className="mb-4 rounded border border-red-500 bg-red-500/10 p-3 text-sm text-red-500">
+            {error}
+          </div>
+        )}
+
+        <form onSubmit={handleSubmit} className="space-y-4">
+          {request.prompts.map((prompt, index) => (
+            <div key={index}>
+              <label className="mb-1 block text-sm font-medium">{prompt.prompt}</label>
+              <input
+                type={prompt.echo ? 'text' : 'password'}
+                value={answers[index] ?? ''}
+                onChange={(e) => {
+                  const next = [...answers];
+                  next[index] = e.target.value;
+                  setAnswers(next);
+                }}
+                autoFocus={index === 0}
+                autoComplete={prompt.echo ? 'off' : 'one-time-code'}
+                className="w-full rounded border border-border bg-background px-3 py-2 text-sm focus:outline-none focus:ring-2 focus:ring-primary"
+              />
+            </div>
+          ))}
+
+          <div className="flex justify-end gap-3">
+            <button
+              type="button"
+              onClick={() => reply(null)}
+              className="rounded bg-secondary px-4 py-2 font-medium text-secondary-foreground hover:bg-secondary/80"
+            >
+              Cancel
+            </button>
+            <button
+              type="submit"
+              className="rounded bg-primary px-4 py-2 font-medium text-primary-foreground hover:bg-primary/90"
+            >
+              Continue
+            </button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
@@ -1,23 +1,22 @@
 /**
  * Connection Form Component
  *
- * Form for creating and editing SSH/SFTP connections
+ * Form for creating and editing SSH/SFTP/FTP connections
  */
 
 import { useState, useEffect, useRef } from 'react';
 import { useTranslation } from '../i18n/i18n';
 import { useConnectionsStore, type CreateConnectionInput, type UpdateConnectionInput, type ConnectionInfo, type Protocol } from '../store/connectionsStore';
 import { useCollectionsStore } from '../store/collectionsStore';
-import type { QuickSSHConnectionInfo } from './QuickSSHModal';
+import { Tauri, type CertificateInfo } from '../utils/tauri';
 
 interface ConnectionFormProps {
   connection?: ConnectionInfo | null;
-  prefillData?: QuickSSHConnectionInfo | null;
   onClose: () => void;
   onSuccess?: () => void;
 }
 
-export function ConnectionForm({ connection, prefillData, onClose, onSuccess }: ConnectionFormProps) {
+export function ConnectionForm({ connection, onClose, onSuccess }: ConnectionFormProps) {
   const { t } = useTranslation();
   const { createConnection, updateConnection } = useConnectionsStore();
   const { collections, fetchCollections, createCollection } = useCollectionsStore();
@@ -27,16 +26,17 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
     fetchCollections();
   }, [fetchCollections]);
 
-  // Form state - use prefillData if provided, otherwise use connection data
-  const [name, setName] = useState(connection?.name || (prefillData ? `${prefillData.username}@${prefillData.host}` : ''));
+  // Form state
+  const [name, setName] = useState(connection?.name || '');
   const [protocol, setProtocol] = useState<Protocol>((connection?.protocol as Protocol) || 'SSH');
-  const [hostname, setHostname] = useState(connection?.hostname || prefillData?.host || '');
-  const [port, setPort] = useState(connection?.port || prefillData?.port || 22);
-  const [username, setUsername] = useState(connection?.username || prefillData?.username || '');
-  const [authMethod, setAuthMethod] = useState<'password' | 'publicKey'>(prefillData?.authType || 'password');
-  const [password, setPassword] = useState(prefillData?.password || '');
-  const [keyPath, setKeyPath] = useState(prefillData?.keyPath || '');
-  const [keyPassphrase, setKeyPassphrase] = useState(prefillData?.passphrase || '');
+  const [hostname, setHostname] = useState(connection?.hostname || '');
+  const [port, setPort] = useState(connection?.port || 22);
+  const [username, setUsername] = useState(connection?.username || '');
+  const [authMethod, setAuthMethod] = useState<'password' | 'publicKey'>('password');
+  const [password, setPassword] = useState('');
+  const [keyPath, setKeyPath] = useState('');
+  const [keyPassphrase, setKeyPassphrase] = useState('');
+  const [certificate, setCertificate] = useState<CertificateInfo | null>(null);
   const [collection, setCollection] = useState(connection?.folder || '');
   const color = connection?.color || ''; // TODO: Implement color picker UI
   const icon = connection?.icon || ''; // TODO: Implement icon picker UI
@@ -48,8 +48,23 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
     connection?.sshKeepAliveInterval ?? null
   );
 
+  // Look up the OpenSSH certificate next to the key (<key>-cert.pub)
+  useEffect(() => {
+    const path = keyPath.trim();
+    if (authMethod !== 'publicKey' || !path) {
+      setCertificate(null);
+      return;
+    }
+    const timer = setTimeout(() => {
+      Tauri.Connections.getKeyCertificate(path)
+        .then(setCertificate)
+        .catch(() => setCertificate(null));
+    }, 300);
+    return () => clearTimeout(timer);
+  }, [authMethod, keyPath]);
+
   // Dropdown state for keep-alive
-  const [selectedKeepAlive, setSelectedKeepAlive] = useState<number | 'disabled'>('disabled');
+  const [selectedKeepAlive, setSelectedKeepAlive] = useState<number | 'default' | 'disabled'>('default');
   const [customKeepAlive, setCustomKeepAlive] = useState<string>('');
   const [showKeepAliveDropdown, setShowKeepAliveDropdown] = useState(false);
   const keepAliveDropdownRef = useRef<HTMLDivElement>(null);
@@ -70,8 +85,10 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
   // Initialize keep-alive dropdown based on connection settings
   useEffect(() => {
     if (connection) {
-      if (sshKeepAliveOverride === null || sshKeepAliveOverride === 'disabled') {
-        // No override or disabled
+      if (sshKeepAliveOverride === null) {
+        // No override: global setting
+        setSelectedKeepAlive('default');
+      } else if (sshKeepAliveOverride === 'disabled') {
         setSelectedKeepAlive('disabled');
       } else if (sshKeepAliveOverride === 'enabled') {
         if (sshKeepAliveInterval && [15, 30, 60].includes(sshKeepAliveInterval)) {
@@ -106,6 +123,16 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
   }, []);
 
   // Validation
+  const handleGeneratePassword = async () => {
+    try {
+      const generated = await Tauri.Auth.generatePassword({ type: 'characters', length: 24, symbols: true });
+      setPassword(generated.password);
+      setShowPassword(true);
+    } catch (error) {
+      console.error('Failed to generate password:', error);
+    }
+  };
+
   const validate = (): boolean => {
     const newErrors: Record<string, string> = {};
 
@@ -154,6 +181,7 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
 
   // Keep-alive dropdown helpers
   const getKeepAliveLabel = () => {
+    if (selectedKeepAlive === 'default') return t('sshKeepAlive.default');
     if (selectedKeepAlive === 'disabled') return t('sshKeepAlive.disabled');
     if (selectedKeepAlive === 15) return `15 ${t('sshKeepAlive.seconds')}`;
     if (selectedKeepAlive === 30) return `30 ${t('sshKeepAlive.seconds')}`;
@@ -164,12 +192,16 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
     return t('sshKeepAlive.disabled');
   };
 
-  const handleKeepAliveChange = (value: number | 'disabled') => {
+  const handleKeepAliveChange = (value: number | 'default' | 'disabled') => {
     setSelectedKeepAlive(value);
     setShowKeepAliveDropdown(false);
 
     // Map to backend values
-    if (value === 'disabled') {
+    if (value === 'default') {
+      setSshKeepAliveOverride(null);
+      setSshKeepAliveInterval(null);
+      setCustomKeepAlive('');
+    } else if (value === 'disabled') {
       setSshKeepAliveOverride('disabled');
       setSshKeepAliveInterval(null);
       setCustomKeepAlive('');
@@ -209,6 +241,7 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
           ...(notes && { notes }),
           sshKeepAliveOverride: sshKeepAliveOverride,
           sshKeepAliveInterval: sshKeepAliveInterval,
+          revision: connection.revision,
         };
 
         // Only include auth method if password or key path is provided
@@ -318,8 +351,8 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
                     onClick={() => {
                       setProtocol('SSH');
                       setShowProtocolDropdown(false);
-                      // Update port to default SSH port if it's SFTP port
-                      if (port === 22 || port === 2222) setPort(22);
+                      // Update port to default SSH port if it's SFTP or FTP port
+                      if (port === 21 || port === 22 || port === 2222) setPort(22);
                     }}
                     className="w-full px-3 py-2 text-left hover:bg-muted transition-colors"
                   >
@@ -336,6 +369,29 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
                   >
                     SFTP
                   </button>
+                  <button
+                    type="button"
+                    onClick={() => {
+                      setProtocol('FTP');
+                      setShowProtocolDropdown(false);
+                      if (port === 22) setPort(21);
+                    }}
+                    className="w-full px-3 py-2 text-left hover:bg-muted transition-colors"
+                  >
+                    FTP
+                  </button>
+                  <button
+                    type="button"
+                    onClick={() => {
+                      setProtocol('FTPS');
+                      setShowProtocolDropdown(false);
+                      // Explicit TLS: same control port as FTP
+                      if (port === 22) setPort(21);
+                    }}
+                    className="w-full px-3 py-2 text-left hover:bg-muted transition-colors"
+                  >
+                    FTPS
+                  </button>
                 </div>
               )}
             </div>
@@ -415,9 +471,18 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
           {/* Password or Key Path */}
           {authMethod === 'password' ? (
             <div>
-              <label className="mb-1 block text-sm font-medium">
-                {t('connections.password')} {!connection && <span className="text-red-500">*</span>}
-              </label>
+              <div className="mb-1 flex items-center justify-between">
+                <label className="block text-sm font-medium">
+                  {t('connections.password')} {!connection && <span className="text-red-500">*</span>}
+                </label>
+                <button
+                  type="button"
+                  onClick={handleGeneratePassword}
+                  className="text-xs text-primary hover:underline"
+                >
+                  {t('connections.generatePassword')}
+                </button>
+              </div>
               <div className="relative">
                 <input
                   type={showPassword ? 'text' : 'password'}
@@ -465,6 +530,27 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
                   className="w-full rounded border border-border bg-input px-3 py-2 text-foreground focus:border-primary focus:outline-none"
                 />
                 {errors.keyPath && <p className="mt-1 text-sm text-red-500">{errors.keyPath}</p>}
+                {certificate && (
+                  <p
+                    className={`mt-1 text-sm ${
+                      certificate.validBefore !== null && certificate.validBefore * 1000 <= Date.now()
+                        ? 'text-red-500'
+                        : 'text-muted-foreground'
+                    }`}
+                  >
+                    {certificate.validBefore === null
+                      ? t('connections.certificateNoExpiry', { keyId: certificate.keyId })
+                      : certificate.validBefore * 1000 <= Date.now()
+                        ? t('connections.certificateExpired', {
+                            keyId: certificate.keyId,
+                            date: new Date(certificate.validBefore * 1000).toLocaleString(),
+                          })
+                        : t('connections.certificateValidUntil', {
+                            keyId: certificate.keyId,
+                            date: new Date(certificate.validBefore * 1000).toLocaleString(),
+                          })}
+                  </p>
+                )}
               </div>
               <div>
                 <label className="mb-1 block text-sm font-medium">{t('connections.keyPassphrase')}</label>
@@ -628,6 +714,13 @@ export function ConnectionForm({ connection, prefillData, onClose, onSuccess }:
                   {/* Dropdown Menu */}
                   {showKeepAliveDropdown && (
                     <div className="absolute z-10 mt-1 w-full rounded border border-border bg-background shadow-lg divide-y divide-border">
+                      <button
+                        type="button"
+                        onClick={() => handleKeepAliveChange('default')}
+                        className="w-full px-3 py-2 text-left hover:bg-muted transition-colors"
+                      >
+                        {t('sshKeepAlive.default')}
+                      </button>
                       <button
                         type="button"
                         onClick={() => handleKeepAliveChange('disabled')}
@@ -64,7 +64,8 @@ export function ImportSSHConfigModal({ onClose, onImported }: ImportSSHConfigMod
         const hostname = entry.hostname || entry.host;
         const user = entry.user ? `${entry.user}@` : '';
         const port = entry.port && entry.port !== 22 ? `:${entry.port}` : '';
-        const preview = `${user}${hostname}${port}`;
+        const via = entry.proxyJump.map((hop) => hop.host).join(' → ');
+        const preview = `${user}${hostname}${port}${via ? ` (via ${via})` : ''}`;
 
         return {
           ...entry,
@@ -227,6 +228,7 @@ export function ImportSSHConfigModal({ onClose, onImported }: ImportSSHConfigMod
               <p className="font-medium">Important - Workflow:</p>
               <ul className="mt-1 ml-4 list-disc text-muted-foreground">
                 <li>SSH config files never contain passwords (security)</li>
+                <li>ProxyJump hosts are imported too (reused if already saved) and set as jump hosts</li>
                 <li>Imported connections will have <span className="font-semibold text-foreground">empty passwords</span></li>
                 <li><span className="font-semibold text-foreground">To use them:</span> Click the 3 dots (⋮) on the connection card → Edit → Add password, OR use Quick SSH for temporary connections</li>
               </ul>
@@ -6,7 +6,8 @@
 
 import { useEffect, useState, useRef } from 'react';
 import { createPortal } from 'react-dom';
-import { Tauri } from '../utils/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { Tauri, VaultEvents, type RemoteTaskCompletedEvent, type RestorableSession, type TriggerNotificationEvent, type VaultChangeEvent } from '../utils/tauri';
 import { useAuthStore } from '../store/authStore';
 import { useConnectionsStore, type ConnectionInfo } from '../store/connectionsStore';
 import { useSettingsStore } from '../store/settingsStore';
@@ -18,6 +19,9 @@ import { TerminalManager, type TerminalSession } from './TerminalManager';
 import { Settings } from './Settings';
 import { QuickSSHModal, type QuickSSHConnectionInfo } from './QuickSSHModal';
 import { ImportSSHConfigModal } from './ImportSSHConfigModal';
+import { AuthPromptModal } from './AuthPromptModal';
+import { SecurityKeyTouchPrompt } from './SecurityKeyTouchPrompt';
+import { ZmodemModal } from './ZmodemModal';
 import { UnlockScreen } from './UnlockScreen';
 import { Toast } from './Toast';
 import { ErrorBoundary } from './ErrorBoundary';
@@ -44,8 +48,10 @@ export function MainScreen() {
     connections,
     fetchConnections,
     deleteConnection,
+    restoreConnection,
     selectConnection,
     selectedConnectionId,
+    saveQuickSession,
   } = useConnectionsStore();
   const { settings, fetchSettings, updateSettings } = useSettingsStore();
 
@@ -71,7 +77,7 @@ export function MainScreen() {
 
   // Toast notification state
   const [toastMessage, setToastMessage] = useState<string | null>(null);
-  const [toastType, setToastType] = useState<'success' | 'error'>('error');
+  const [toastType, setToastType] = useState<'success' | 'error' | 'info'>('error');
   const [toastAction, setToastAction] = useState<{ label: string; onClick: () => void } | undefined>(undefined);
 
   // Default shell selector state
@@ -93,9 +99,6 @@ export function MainScreen() {
   // Store Quick SSH connection info for later saving
   const [quickSSHConnections, setQuickSSHConnections] = useState<Map<string, QuickSSHConnectionInfo>>(new Map());
 
-  // Store pre-fill data for connection form (when saving Quick SSH)
-  const [connectionFormPrefill, setConnectionFormPrefill] = useState<QuickSSHConnectionInfo | null>(null);
-
   // Store pending action after unlock
   const [pendingActionAfterUnlock, setPendingActionAfterUnlock] = useState<(() => void) | null>(null);
 
@@ -108,6 +111,40 @@ export function MainScreen() {
     }
   }, [isLocked, pendingActionAfterUnlock]);
 
+  // Terminals of the last run, offered until reopened or dismissed
+  const [restorableSessions, setRestorableSessions] = useState<RestorableSession[]>([]);
+  // Open terminals are only reported once the offer is answered, so the
+  // saved ones aren't replaced before
+  const [reportOpenSessions, setReportOpenSessions] = useState(false);
+
+  useEffect(() => {
+    Tauri.SessionRestore.getRestorableSessions()
+      .then(sessions => {
+        setRestorableSessions(sessions);
+        if (sessions.length === 0) setReportOpenSessions(true);
+      })
+      .catch(err => {
+        console.warn('[MainScreen] Failed to load last session:', err);
+        setReportOpenSessions(true);
+      });
+  }, []);
+
+  // Report the open terminals (saved with their scrollback for the next launch)
+  useEffect(() => {
+    if (!reportOpenSessions) return;
+    const sessions = tabGroups
+      .flatMap(tab => getAllSessions(tab.paneTree))
+      .filter(session => !session.connectionId.startsWith('quick-'))
+      .map(session => ({
+        sessionId: session.id,
+        connectionId: session.connectionId,
+        title: session.connectionName,
+        shell: session.shell,
+      }));
+    Tauri.SessionRestore.setOpenSessions(sessions)
+      .catch(err => console.warn('[MainScreen] Failed to save open sessions:', err));
+  }, [tabGroups, reportOpenSessions]);
+
   // Load connections on mount (only if unlocked)
   useEffect(() => {
     if (!isLocked) {
@@ -120,6 +157,54 @@ export function MainScreen() {
     fetchSettings();
   }, [fetchSettings]);
 
+  // Stay in sync with changes made in other windows
+  useEffect(() => {
+    const unlisteners = [
+      listen<VaultChangeEvent>(VaultEvents.connectionsChanged, () => {
+        if (!isLocked) {
+          fetchConnections();
+        }
+      }),
+      listen<VaultChangeEvent>(VaultEvents.settingsChanged, () => {
+        fetchSettings();
+      }),
+    ];
+
+    return () => {
+      unlisteners.forEach(unlisten => unlisten.then(fn => fn()));
+    };
+  }, [isLocked, fetchConnections, fetchSettings]);
+
+  // Output triggers with a notify action
+  useEffect(() => {
+    const unlisten = listen<TriggerNotificationEvent>('trigger-notification', (event) => {
+      setToastAction(undefined);
+      setToastType('info');
+      setToastMessage(`${event.payload.connectionName}: ${event.payload.message}`);
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // Remote task runs that failed (successful runs are only listed with the task)
+  useEffect(() => {
+    const unlisten = listen<RemoteTaskCompletedEvent>('remote-task-completed', (event) => {
+      const { taskName, exitStatus, error } = event.payload;
+      if (!error && exitStatus === 0) return;
+      setToastAction(undefined);
+      setToastType('error');
+      setToastMessage(error
+        ? t('remoteTasks.failed', { name: taskName, error })
+        : t('remoteTasks.exited', { name: taskName, status: exitStatus ?? '' }));
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [t]);
+
   // Check which shells are installed
   const checkInstalledShells = async () => {
     try {
@@ -275,9 +360,22 @@ export function MainScreen() {
   const confirmDelete = async () => {
     if (connectionToDelete) {
       try {
-        await deleteConnection(connectionToDelete.id);
+        const deleted = connectionToDelete;
+        await deleteConnection(deleted.id);
         setShowDeleteConfirm(false);
         setConnectionToDelete(null);
+
+        // Deleted connections go to the trash: offer to undo
+        setToastAction({
+          label: t('connections.undoDelete'),
+          onClick: () => {
+            restoreConnection(deleted.id).catch((error) => {
+              console.error('Failed to restore connection:', error);
+            });
+          },
+        });
+        setToastType('success');
+        setToastMessage(t('connections.movedToTrash', { name: deleted.name }));
       } catch (error) {
         console.error('Failed to delete connection:', error);
       }
@@ -357,6 +455,7 @@ export function MainScreen() {
         id: sessionId,
         connectionId: 'local',
         connectionName: `Local Terminal (${shellName})`,
+        shell: shellToUse,
       };
 
       console.log('[MainScreen] Local terminal session created:', sessionId);
@@ -387,6 +486,71 @@ export function MainScreen() {
     addTerminalToTree(session);
   };
 
+  // Reopen the terminals of the last run: local ones right away, SSH ones
+  // (and the scrollback) once the vault is unlocked
+  const handleRestoreSessions = () => {
+    const sessions = restorableSessions.map((session, index) => ({ session, index }));
+    setRestorableSessions([]);
+
+    const local = sessions.filter(({ session }) => session.connectionId === 'local');
+    const ssh = sessions.filter(({ session }) => session.connectionId !== 'local');
+
+    const reopen = async ({ session, index }: { session: RestorableSession; index: number }) => {
+      try {
+        const isLocal = session.connectionId === 'local';
+        const sessionId = isLocal
+          ? await Tauri.Terminal.connectLocalTerminal(
+              session.shell ?? settings.defaultShell,
+              undefined,
+              session.cwd ?? undefined,
+            )
+          : await Tauri.Terminal.connectTerminal(session.connectionId);
+        addTerminalToTree({
+          id: sessionId,
+          connectionId: session.connectionId,
+          connectionName: session.title,
+          shell: session.shell ?? undefined,
+          restoreIndex: session.hasScrollback ? index : undefined,
+        });
+      } catch (error) {
+        console.error('[MainScreen] Failed to reopen terminal:', session.title, error);
+        const errorMsg = typeof error === 'string' ? error : error instanceof Error ? error.message : 'Failed to connect';
+        setToastAction(undefined);
+        setToastType('error');
+        setToastMessage(`${session.title}: ${errorMsg}`);
+      }
+    };
+
+    const reopenAll = async (toReopen: typeof sessions) => {
+      for (const session of toReopen) {
+        await reopen(session);
+      }
+    };
+
+    // Scrollback is encrypted: wait for unlock before reopening terminals that have some
+    const now = isLocked ? local.filter(({ session }) => !session.hasScrollback) : local;
+    const later = isLocked ? [...local.filter(({ session }) => session.hasScrollback), ...ssh] : ssh;
+
+    reopenAll(now).then(() => {
+      if (later.length === 0) {
+        setReportOpenSessions(true);
+      } else if (isLocked) {
+        setPendingActionAfterUnlock(() => () => {
+          console.log('[MainScreen] Reopening last session terminals after unlock');
+          reopenAll(later).finally(() => setReportOpenSessions(true));
+        });
+        setShowUnlockModal(true);
+      } else {
+        reopenAll(later).finally(() => setReportOpenSessions(true));
+      }
+    });
+  };
+
+  const handleDismissRestore = () => {
+    setRestorableSessions([]);
+    setReportOpenSessions(true);
+  };
+
   // Handle split pane
   const handleSplitPane = async (paneId: string, direction: SplitDirection) => {
     console.log('[MainScreen] Splitting pane:', paneId, 'direction:', direction);
@@ -403,6 +567,7 @@ export function MainScreen() {
         id: sessionId,
         connectionId: 'local',
         connectionName: `Local Terminal (${shellName})`,
+        shell: shellToUse,
       };
 
       // Split the pane in the active tab's tree
@@ -678,33 +843,39 @@ export function MainScreen() {
   const handleSaveQuickSSH = (sessionId: string) => {
     console.log('[MainScreen] Save Quick SSH connection requested for session:', sessionId);
 
-    // Get connection info for this session
-    const connectionInfo = quickSSHConnections.get(sessionId);
-    if (!connectionInfo) {
-      console.error('[MainScreen] No connection info found for session:', sessionId);
+    if (!quickSSHConnections.has(sessionId)) {
+      console.error('[MainScreen] No Quick SSH connection found for session:', sessionId);
       return;
     }
 
-    // Check if app is locked
+    // Host, user and credentials are taken from the live session by the backend
+    const save = async () => {
+      try {
+        const connection = await saveQuickSession(sessionId);
+        setQuickSSHConnections(prev => {
+          const next = new Map(prev);
+          next.delete(sessionId);
+          return next;
+        });
+        // Let the user rename or file the new connection
+        handleEditConnection(connection);
+      } catch {
+        // Already reported by the store
+      }
+    };
+
+    // Credentials are encrypted with the master key: unlock first
     if (isLocked) {
       console.log('[MainScreen] App is locked, showing unlock modal and storing pending action');
-
-      // Store the action to execute after unlock
       setPendingActionAfterUnlock(() => () => {
         console.log('[MainScreen] Executing pending Quick SSH save after unlock');
-        setConnectionFormPrefill(connectionInfo);
-        setEditingConnection(null);
-        setShowForm(true);
+        save();
       });
-
       setShowUnlockModal(true);
       return;
     }
 
-    // Store prefill data and open form for creating new connection
-    setConnectionFormPrefill(connectionInfo);
-    setEditingConnection(null); // null = creating new connection
-    setShowForm(true);
+    save();
   };
 
   return (
@@ -910,15 +1081,12 @@ export function MainScreen() {
       {showForm && (
         <ConnectionForm
           connection={editingConnection}
-          prefillData={connectionFormPrefill}
           onClose={() => {
             setShowForm(false);
             setEditingConnection(null);
-            setConnectionFormPrefill(null);
           }}
           onSuccess={() => {
             fetchConnections();
-            setConnectionFormPrefill(null);
           }}
         />
       )}
@@ -983,6 +1151,15 @@ export function MainScreen() {
         />
       )}
 
+      {/* Keyboard-interactive prompts (OTP) while connecting */}
+      <AuthPromptModal />
+
+      {/* FIDO2 security key waiting for a touch while connecting */}
+      <SecurityKeyTouchPrompt />
+
+      {/* sz/rz transfers in SSH sessions */}
+      <ZmodemModal />
+
       {/* Unlock Modal */}
       {showUnlockModal && (
         <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50 backdrop-blur-sm">
@@ -992,6 +1169,30 @@ export function MainScreen() {
         </div>
       )}
 
+      {/* Last session offer */}
+      {restorableSessions.length > 0 && (
+        <div className="fixed bottom-4 right-4 z-40 w-80 rounded-lg border border-border bg-card p-4 shadow-xl">
+          <p className="text-sm font-medium">{t('sessionRestore.title')}</p>
+          <p className="mt-1 text-xs text-muted-foreground">
+            {t('sessionRestore.description', { count: restorableSessions.length })}
+          </p>
+          <div className="mt-3 flex justify-end gap-2">
+            <button
+              onClick={handleDismissRestore}
+              className="rounded-md px-3 py-1.5 text-xs font-medium text-muted-foreground transition-colors hover:bg-accent hover:text-foreground"
+            >
+              {t('sessionRestore.dismiss')}
+            </button>
+            <button
+              onClick={handleRestoreSessions}
+              className="rounded-md bg-primary px-3 py-1.5 text-xs font-medium text-primary-foreground transition-colors hover:bg-primary/90"
+            >
+              {t('sessionRestore.reopen')}
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Toast Notification */}
       {toastMessage && (
         <Toast
@@ -168,6 +168,7 @@ export function PaneContainer({ node, onSplitRatioChange, onClosePane, onFocusPa
           connectionId={node.session.connectionId}
           connectionName={node.session.connectionName}
           sessionId={node.session.id}
+          restoreIndex={node.session.restoreIndex}
           isFocused={node.isFocused}
           onClose={() => onClosePane(node.id)}
           onSplitHorizontal={() => onSplitPane(node.id, 'horizontal')}
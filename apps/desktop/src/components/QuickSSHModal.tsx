@@ -33,6 +33,7 @@ export function QuickSSHModal({ onClose, onConnected }: QuickSSHModalProps) {
   const [password, setPassword] = useState('');
   const [keyPath, setKeyPath] = useState('');
   const [passphrase, setPassphrase] = useState('');
+  const [acceptHostKeyOnce, setAcceptHostKeyOnce] = useState(false);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState('');
 
@@ -52,7 +53,8 @@ export function QuickSSHModal({ onClose, onConnected }: QuickSSHModalProps) {
         host,
         username,
         port,
-        authMethod
+        authMethod,
+        acceptHostKeyOnce
       );
 
       console.log('[QuickSSH] Connected successfully, session:', sessionId);
@@ -243,6 +245,25 @@ export function QuickSSHModal({ onClose, onConnected }: QuickSSHModalProps) {
             </>
           )}
 
+          {/* Host key */}
+          <div>
+            <label className="flex items-start gap-2 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={acceptHostKeyOnce}
+                onChange={(e) => setAcceptHostKeyOnce(e.target.checked)}
+                className="mt-0.5 h-4 w-4 text-primary focus:ring-primary"
+                disabled={loading}
+              />
+              <span className="text-sm">
+                Accept host key once without saving
+                <span className="block text-xs text-muted-foreground">
+                  Skips host key verification for this session only. Use only on trusted networks.
+                </span>
+              </span>
+            </label>
+          </div>
+
           {/* Actions */}
           <div className="flex justify-end gap-3 pt-2">
             <button
@@ -0,0 +1,39 @@
+/**
+ * Security Key Touch Prompt
+ *
+ * Asks the user to touch their FIDO2 security key while ssh-agent waits for
+ * it to sign the authentication request, and goes away once it is done.
+ */
+
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useTranslation } from '../i18n/i18n';
+import type { SecurityKeyTouchEvent } from '../utils/tauri';
+
+export function SecurityKeyTouchPrompt() {
+  const { t } = useTranslation();
+  const [request, setRequest] = useState<SecurityKeyTouchEvent | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<SecurityKeyTouchEvent>('ssh:security-key-touch', (event) => {
+      setRequest(event.payload.waiting ? event.payload : null);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  if (!request) {
+    return null;
+  }
+
+  return (
+    <div className="fixed bottom-6 left-1/2 z-50 -translate-x-1/2 rounded-lg border border-border bg-background px-5 py-4 shadow-xl">
+      <p className="font-medium">{t('securityKey.touch')}</p>
+      <p className="mt-1 text-sm text-muted-foreground">
+        {request.username}@{request.host}:{request.port}
+      </p>
+      <p className="mt-1 font-mono text-xs text-muted-foreground">{request.fingerprint}</p>
+    </div>
+  );
+}
@@ -6,6 +6,7 @@
 
 import { useEffect, useState, useRef } from 'react';
 import { useSettingsStore } from '../store/settingsStore';
+import { Tauri } from '../utils/tauri';
 import { useTranslation } from '../i18n/i18n';
 
 interface SettingsProps {
@@ -19,6 +20,8 @@ export function Settings({ onClose }: SettingsProps) {
   const [selectedTimeout, setSelectedTimeout] = useState<number>(0);
   const [customTimeout, setCustomTimeout] = useState('');
   const [clipboardClearEnabled, setClipboardClearEnabled] = useState(settings.clipboardClearEnabled);
+  const [sshKeepAliveEnabled, setSshKeepAliveEnabled] = useState(settings.sshKeepAliveEnabled);
+  const [sshKeepAliveInterval, setSshKeepAliveInterval] = useState(String(settings.sshKeepAliveInterval));
   const [hostKeyVerificationMode, setHostKeyVerificationMode] = useState<'strict' | 'warn' | 'accept'>(settings.hostKeyVerificationMode);
   const [showTimeoutDropdown, setShowTimeoutDropdown] = useState(false);
   const [showLanguageDropdown, setShowLanguageDropdown] = useState(false);
@@ -26,12 +29,25 @@ export function Settings({ onClose }: SettingsProps) {
   const timeoutDropdownRef = useRef<HTMLDivElement>(null);
   const languageDropdownRef = useRef<HTMLDivElement>(null);
   const hostKeyDropdownRef = useRef<HTMLDivElement>(null);
+  const [biometricAvailable, setBiometricAvailable] = useState(false);
+  const [biometricEnabled, setBiometricEnabled] = useState(false);
+  const [biometricPassword, setBiometricPassword] = useState<string | null>(null);
+  const [biometricError, setBiometricError] = useState('');
 
 
   useEffect(() => {
     fetchSettings();
   }, [fetchSettings]);
 
+  useEffect(() => {
+    Tauri.Auth.isBiometricAvailable()
+      .then(setBiometricAvailable)
+      .catch(() => setBiometricAvailable(false));
+    Tauri.Settings.getSetting('biometric_unlock')
+      .then((value) => setBiometricEnabled(value === 'true'))
+      .catch(() => setBiometricEnabled(false));
+  }, []);
+
   useEffect(() => {
     // Initialize selectedTimeout based on current settings
     if (!settings.autoLockEnabled) {
@@ -46,6 +62,8 @@ export function Settings({ onClose }: SettingsProps) {
     }
 
     setClipboardClearEnabled(settings.clipboardClearEnabled);
+    setSshKeepAliveEnabled(settings.sshKeepAliveEnabled);
+    setSshKeepAliveInterval(String(settings.sshKeepAliveInterval));
     setHostKeyVerificationMode(settings.hostKeyVerificationMode);
   }, [settings]);
 
@@ -88,16 +106,47 @@ export function Settings({ onClose }: SettingsProps) {
       autoLockTimeout = selectedTimeout;
     }
 
+    const keepAliveInterval = parseInt(sshKeepAliveInterval, 10);
+
     await updateSettings({
       autoLockEnabled,
       autoLockTimeout,
       clipboardClearEnabled,
+      sshKeepAliveEnabled,
+      ...(keepAliveInterval > 0 && { sshKeepAliveInterval: keepAliveInterval }),
       hostKeyVerificationMode,
     });
 
     onClose();
   };
 
+  // Applied immediately: enabling asks for the master password
+  const handleBiometricToggle = async (enabled: boolean) => {
+    setBiometricError('');
+    if (enabled) {
+      setBiometricPassword('');
+      return;
+    }
+    try {
+      await Tauri.Auth.disableBiometricUnlock();
+      setBiometricEnabled(false);
+    } catch (error) {
+      setBiometricError(String(error));
+    }
+  };
+
+  const handleBiometricEnable = async () => {
+    if (!biometricPassword) return;
+    try {
+      await Tauri.Auth.enableBiometricUnlock(biometricPassword);
+      setBiometricEnabled(true);
+      setBiometricPassword(null);
+    } catch (error) {
+      setBiometricError(String(error));
+      setBiometricPassword('');
+    }
+  };
+
   const handleTimeoutChange = (value: number) => {
     setSelectedTimeout(value);
     setShowTimeoutDropdown(false);
@@ -274,6 +323,46 @@ export function Settings({ onClose }: SettingsProps) {
                 </div>
                 <p className="text-sm text-muted-foreground mt-2">{t('settings.hostKeyVerificationDesc')}</p>
               </div>
+
+              {/* Biometric unlock */}
+              {biometricAvailable && (
+                <div>
+                  <label className="flex items-center gap-3 cursor-pointer">
+                    <input
+                      type="checkbox"
+                      checked={biometricEnabled || biometricPassword !== null}
+                      onChange={(e) => handleBiometricToggle(e.target.checked)}
+                      className="h-5 w-5 rounded border-border bg-background text-primary focus:ring-2 focus:ring-primary"
+                    />
+                    <div>
+                      <div className="font-medium">{t('settings.biometricUnlock')}</div>
+                      <div className="text-sm text-muted-foreground">{t('settings.biometricUnlockDesc')}</div>
+                    </div>
+                  </label>
+                  {biometricPassword !== null && !biometricEnabled && (
+                    <div className="mt-3 flex gap-2">
+                      <input
+                        type="password"
+                        value={biometricPassword}
+                        onChange={(e) => setBiometricPassword(e.target.value)}
+                        onKeyDown={(e) => e.key === 'Enter' && handleBiometricEnable()}
+                        placeholder={t('settings.biometricPasswordPlaceholder')}
+                        className="flex-1 rounded-md border border-border bg-background px-3 py-2 text-sm focus:outline-none focus:ring-2 focus:ring-primary"
+                        autoFocus
+                      />
+                      <button
+                        type="button"
+                        onClick={handleBiometricEnable}
+                        disabled={!biometricPassword}
+                        className="rounded-md bg-primary px-4 py-2 text-sm font-medium text-primary-foreground hover:bg-primary/90 disabled:opacity-50"
+                      >
+                        {t('settings.biometricEnable')}
+                      </button>
+                    </div>
+                  )}
+                  {biometricError && <p className="text-sm text-red-500 mt-2">{biometricError}</p>}
+                </div>
+              )}
             </div>
           </section>
 
@@ -297,6 +386,40 @@ export function Settings({ onClose }: SettingsProps) {
             </div>
           </section>
 
+          {/* Connection settings */}
+          <section className="border-b border-border pb-6">
+            <h3 className="text-lg font-semibold mb-4">{t('settings.connections')}</h3>
+
+            <div className="space-y-4">
+              <label className="flex items-center gap-3 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={sshKeepAliveEnabled}
+                  onChange={(e) => setSshKeepAliveEnabled(e.target.checked)}
+                  className="h-5 w-5 rounded border-border bg-background text-primary focus:ring-2 focus:ring-primary"
+                />
+                <div>
+                  <div className="font-medium">{t('settings.sshKeepAliveEnabled')}</div>
+                  <div className="text-sm text-muted-foreground">{t('settings.sshKeepAliveEnabledDesc')}</div>
+                </div>
+              </label>
+
+              {sshKeepAliveEnabled && (
+                <div>
+                  <label className="mb-1 block text-sm font-medium">{t('settings.sshKeepAliveInterval')}</label>
+                  <input
+                    type="number"
+                    value={sshKeepAliveInterval}
+                    onChange={(e) => setSshKeepAliveInterval(e.target.value)}
+                    min="1"
+                    max="300"
+                    className="w-full rounded border border-border bg-input px-3 py-2 text-foreground focus:border-primary focus:outline-none"
+                  />
+                </div>
+              )}
+            </div>
+          </section>
+
           {/* Language settings */}
           <section className="pb-6">
             <h3 className="text-lg font-semibold mb-4">{t('settings.language')}</h3>
@@ -42,22 +42,43 @@ export function SetupScreen() {
     return () => clearTimeout(timer);
   }, [password]);
 
+  // Scores go from 0 (too guessable) to 4 (very unguessable)
   const getStrengthColor = (score: number): string => {
-    if (score <= 2) return 'bg-red-500';
-    if (score <= 4) return 'bg-orange-500';
-    if (score <= 5) return 'bg-yellow-500';
+    if (score <= 1) return 'bg-red-500';
+    if (score <= 2) return 'bg-orange-500';
+    if (score <= 3) return 'bg-yellow-500';
     return 'bg-green-500';
   };
 
   const getStrengthLabel = (score: number): string => {
-    if (score <= 2) return t('setup.strengthWeak');
-    if (score <= 4) return t('setup.strengthFair');
-    if (score <= 5) return t('setup.strengthGood');
+    if (score <= 1) return t('setup.strengthWeak');
+    if (score <= 2) return t('setup.strengthFair');
+    if (score <= 3) return t('setup.strengthGood');
     return t('setup.strengthExcellent');
   };
 
+  const feedback = strength
+    ? [...(strength.warning ? [strength.warning] : []), ...strength.suggestions]
+    : [];
+
+  // Diceware passphrase (6 words, 60 bits): easier to remember than random
+  // characters. The confirmation is left to the user, as a memory check
+  const handleGeneratePassphrase = async () => {
+    try {
+      const generated = await Tauri.Auth.generatePassword({
+        type: 'passphrase',
+        words: 6,
+        separator: '-',
+      });
+      setPassword(generated.password);
+      setShowPassword(true);
+    } catch (error) {
+      console.error('Failed to generate passphrase:', error);
+    }
+  };
+
   const passwordsMatch = password && confirmPassword && password === confirmPassword;
-  const canSubmit = strength?.is_valid && passwordsMatch && !isLoading;
+  const canSubmit = strength?.isValid && passwordsMatch && !isLoading;
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -93,9 +114,19 @@ export function SetupScreen() {
           <div className="space-y-4">
             {/* Password field */}
             <div className="space-y-2">
-              <label htmlFor="password" className="text-sm font-medium">
-                {t('setup.password')}
-              </label>
+              <div className="flex items-center justify-between">
+                <label htmlFor="password" className="text-sm font-medium">
+                  {t('setup.password')}
+                </label>
+                <button
+                  type="button"
+                  onClick={handleGeneratePassphrase}
+                  className="text-xs text-primary hover:underline"
+                  disabled={isLoading}
+                >
+                  {t('setup.generatePassphrase')}
+                </button>
+              </div>
               <div className="relative">
                 <input
                   id="password"
@@ -132,16 +163,19 @@ export function SetupScreen() {
                 <div className="space-y-1">
                   <div className="flex items-center justify-between text-xs">
                     <span className="text-muted-foreground">{t('setup.passwordStrength')}</span>
-                    <span className={`font-medium ${strength.is_valid ? 'text-green-600' : 'text-red-600'}`}>
+                    <span className={`font-medium ${strength.isValid ? 'text-green-600' : 'text-red-600'}`}>
                       {getStrengthLabel(strength.score)}
                     </span>
                   </div>
                   <div className="h-2 w-full overflow-hidden rounded-full bg-muted">
                     <div
                       className={`h-full transition-all duration-300 ${getStrengthColor(strength.score)}`}
-                      style={{ width: `${(strength.score / 7) * 100}%` }}
+                      style={{ width: `${((strength.score + 1) / 5) * 100}%` }}
                     />
                   </div>
+                  <p className="text-xs text-muted-foreground">
+                    {t('setup.crackTime', { time: strength.crackTimeDisplay })}
+                  </p>
                 </div>
               )}
             </div>
@@ -193,11 +227,11 @@ export function SetupScreen() {
             </div>
 
             {/* Feedback */}
-            {strength && strength.feedback.length > 0 && (
+            {feedback.length > 0 && (
               <div className="space-y-2 rounded-md bg-muted p-3">
                 <p className="text-xs font-medium">{t('setup.suggestions')}</p>
                 <ul className="list-inside list-disc space-y-1 text-xs text-muted-foreground">
-                  {strength.feedback.map((item, i) => (
+                  {feedback.map((item, i) => (
                     <li key={i}>{item}</li>
                   ))}
                 </ul>
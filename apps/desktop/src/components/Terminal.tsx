@@ -8,10 +8,11 @@ import { useEffect, useRef, useState } from 'react';
 import { Terminal as XTerm } from '@xterm/xterm';
 import { FitAddon } from '@xterm/addon-fit';
 import { SearchAddon } from '@xterm/addon-search';
+import { Channel } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import '@xterm/xterm/css/xterm.css';
 import { terminalPool } from '../utils/terminalPool';
-import { Tauri } from '../utils/tauri';
+import { Tauri, type RedactedTranscript, type SessionShare } from '../utils/tauri';
 import { errorHandler, ErrorSeverity, ErrorCategory } from '../utils/errorHandler';
 
 // Global cache to track if a terminal has already been initialized
@@ -32,12 +33,16 @@ interface TerminalProps {
   onDragOver?: (e: React.DragEvent) => void; // Optional - callback when dragging over this pane
   onDrop?: (e: React.DragEvent) => void; // Optional - callback when dropping on this pane
   isDragging?: boolean; // Optional - whether a drag is in progress (to disable pointer events)
+  restoreIndex?: number; // Optional - reopened from the last run: replay its saved scrollback
 }
 
-interface TerminalDataEvent {
-  sessionId: string;
-  data: string;
-}
+// Output frames: 8-byte big-endian sequence number followed by raw bytes
+const FRAME_HEADER_LEN = 8;
+
+// Flow control: output written to xterm.js but not yet rendered. Above the
+// high watermark the backend stops reading until it drops below the low one.
+const OUTPUT_HIGH_WATERMARK = 1024 * 1024;
+const OUTPUT_LOW_WATERMARK = 256 * 1024;
 
 interface TerminalExitEvent {
   sessionId: string;
@@ -51,9 +56,93 @@ interface TerminalClosedEvent {
 interface ConnectionDeadEvent {
   sessionId: string;
   reason: string;
+  reconnect?: boolean; // Lost during system sleep: reconnect right away
+}
+
+interface SessionSleepDetectedEvent {
+  sessionId: string;
+  sleptSecs: number;
+}
+
+interface SessionResumedEvent {
+  sessionId: string;
+}
+
+interface TerminalReconnectingEvent {
+  sessionId: string;
+  attempt: number;
+  maxAttempts: number;
+  delaySecs: number;
+  reason: string;
+}
+
+interface TerminalReconnectedEvent {
+  sessionId: string;
+  attempt: number;
+}
+
+interface ShareViewersEvent {
+  sessionId: string;
+  viewers: number;
+}
+
+// The one viewer of a share left: its URL no longer works
+interface ShareEndedEvent {
+  sessionId: string;
+}
+
+interface SessionTimeLimitEvent {
+  sessionId: string;
+  remainingSecs: number;
+  warning: boolean; // Sent once, shortly before the session is disconnected
+}
+
+interface SessionExpiredEvent {
+  sessionId: string;
+  reason: string;
+}
+
+interface LoginScriptFailedEvent {
+  sessionId: string;
+  step: number;
+  error: string;
+}
+
+interface RemoteClipboardEvent {
+  sessionId: string;
+  action: 'copy' | 'paste';
+  length: number;
+}
+
+interface TriggerHighlightEvent {
+  sessionId: string;
+  text: string;
+  color: string;
+}
+
+interface CommandStartedEvent {
+  sessionId: string;
+}
+
+interface CommandFinishedEvent {
+  sessionId: string;
+  exitCode: number | null;
+  durationMs: number;
+}
+
+interface CommandConfirmationEvent {
+  sessionId: string;
+  command: string;
+  pattern: string;
+}
+
+function formatDuration(ms: number): string {
+  if (ms < 1000) return `${ms} ms`;
+  if (ms < 60_000) return `${(ms / 1000).toFixed(1)} s`;
+  return `${Math.floor(ms / 60_000)} min ${Math.floor((ms % 60_000) / 1000)} s`;
 }
 
-export function Terminal({ connectionId, connectionName, onClose, sessionId: existingSessionId, isFocused, onSplitHorizontal, onSplitVertical, onDetach, onDragStart, onDragEnd, onDragOver, onDrop, isDragging }: TerminalProps) {
+export function Terminal({ connectionId, connectionName, onClose, sessionId: existingSessionId, isFocused, onSplitHorizontal, onSplitVertical, onDetach, onDragStart, onDragEnd, onDragOver, onDrop, isDragging, restoreIndex }: TerminalProps) {
   const terminalRef = useRef<HTMLDivElement>(null);
   const xtermRef = useRef<XTerm | null>(null);
   const fitAddonRef = useRef<FitAddon | null>(null);
@@ -63,12 +152,14 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
   const [error, setError] = useState<string | null>(null);
   const [isReconnecting, setIsReconnecting] = useState(false);
   const [showSearch, setShowSearch] = useState(false);
+  const [share, setShare] = useState<SessionShare | null>(null);
+  const [heldCommand, setHeldCommand] = useState<string | null>(null);
+  const [remainingSecs, setRemainingSecs] = useState<number | null>(null);
+  const [clipboardNotice, setClipboardNotice] = useState<string | null>(null);
+  const [lastCommand, setLastCommand] = useState<CommandFinishedEvent | null>(null);
+  const [transcript, setTranscript] = useState<RedactedTranscript | null>(null);
   const [searchTerm, setSearchTerm] = useState('');
 
-  // Intelligent prompt detection state
-  const promptDetectionTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
-  const promptDetectedRef = useRef<boolean>(false);
-
   // Store props in refs to avoid recreating terminal on prop changes
   const connectionIdRef = useRef(connectionId);
   const connectionNameRef = useRef(connectionName);
@@ -133,10 +224,24 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
   }, [isFocused, existingSessionId]);
 
   useEffect(() => {
-    let unlistenData: UnlistenFn | null = null;
     let unlistenExit: UnlistenFn | null = null;
     let unlistenClosed: UnlistenFn | null = null;
     let unlistenDead: UnlistenFn | null = null;
+    let unlistenSleepDetected: UnlistenFn | null = null;
+    let unlistenResumed: UnlistenFn | null = null;
+    let unlistenReconnecting: UnlistenFn | null = null;
+    let unlistenReconnected: UnlistenFn | null = null;
+    let unlistenViewers: UnlistenFn | null = null;
+    let unlistenShareEnded: UnlistenFn | null = null;
+    let unlistenConfirmation: UnlistenFn | null = null;
+    let unlistenTimeLimit: UnlistenFn | null = null;
+    let unlistenExpired: UnlistenFn | null = null;
+    let unlistenLoginFailed: UnlistenFn | null = null;
+    let unlistenClipboard: UnlistenFn | null = null;
+    let clipboardNoticeTimer: ReturnType<typeof setTimeout> | null = null;
+    let unlistenCommandStarted: UnlistenFn | null = null;
+    let unlistenCommandFinished: UnlistenFn | null = null;
+    let unlistenHighlight: UnlistenFn | null = null;
 
     async function initializeTerminal() {
       if (!terminalRef.current || !existingSessionId) return;
@@ -254,49 +359,69 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
         }
       });
 
+      // Working directory reported by the shell (OSC 7: file://host/path),
+      // saved so the terminal can be reopened there
+      const onCwdDisposable = term.parser.registerOscHandler(7, (data) => {
+        if (sessionIdRef.current && data.startsWith('file://')) {
+          try {
+            const cwd = decodeURIComponent(new URL(data).pathname);
+            Tauri.SessionRestore.setSessionCwd(sessionIdRef.current, cwd).catch((err) => {
+              console.warn('[Terminal] Failed to report working directory:', err);
+            });
+          } catch {
+            // Malformed URL: ignore
+          }
+        }
+        return true;
+      });
+
       // Store disposables in the pool to track them across remounts
-      instance.ioDisposables.push(onDataDisposable, onBinaryDisposable, onResizeDisposable);
+      instance.ioDisposables.push(onDataDisposable, onBinaryDisposable, onResizeDisposable, onCwdDisposable);
 
       // IMPORTANT: Always register backend event listeners on every mount
       // These listeners must be re-registered when the component remounts (e.g., after tab switch)
 
-      // Set sessionIdRef BEFORE registering listeners to avoid a race condition:
-      // if the backend emits terminal-data between listen() and the later assignment,
-      // the null-check would silently drop the event (including the initial SSH prompt).
+      // Set sessionIdRef BEFORE attaching the output channel to avoid a race condition:
+      // if the backend sends a frame before the later assignment, the null-check
+      // would silently drop it (including the initial SSH prompt).
       sessionIdRef.current = existingSessionId;
 
-      // Set up event listeners for terminal data
-      // This must be done on EVERY mount to receive backend output
-      unlistenData = await listen<TerminalDataEvent>('terminal-data', (event) => {
-        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
-          // Decode base64 to get raw bytes, then decode bytes to UTF-8 string
-          // This preserves terminal control sequences that would be lost with from_utf8_lossy
-          const dataBase64 = event.payload.data;
-          const dataBytes = Uint8Array.from(atob(dataBase64), c => c.charCodeAt(0));
-          const decoder = new TextDecoder('utf-8', { fatal: false });
-          const dataString = decoder.decode(dataBytes);
-
-          xtermRef.current.write(dataString);
-
-          // Intelligent prompt detection: check if we have printable characters
-          // Applies to all terminals (local and SSH) to cancel the fallback timer.
-          if (!promptDetectedRef.current) {
-            const hasPrintable = dataString.split('').some((c: string) => {
-              const code = c.charCodeAt(0);
-              // Printable ASCII range (space to ~) or common unicode
-              return (code >= 32 && code <= 126) || code > 127;
+      // Binary output channel for terminal data
+      // A new channel is attached on EVERY mount; it replaces the previous one in the backend
+      let lastSequence = -1;
+      let pendingBytes = 0;
+      let outputPaused = false;
+      const outputChannel = new Channel<ArrayBuffer>();
+      outputChannel.onmessage = (frame) => {
+        if (!xtermRef.current || frame.byteLength < FRAME_HEADER_LEN) return;
+
+        const sequence = Number(new DataView(frame).getBigUint64(0));
+        if (sequence <= lastSequence) return;
+        if (lastSequence >= 0 && sequence !== lastSequence + 1) {
+          console.warn(`[Terminal] Output frames ${lastSequence + 1}-${sequence - 1} missing for session:`, existingSessionId);
+        }
+        lastSequence = sequence;
+
+        // Raw bytes go straight to xterm.js, which decodes UTF-8 across chunk boundaries
+        // and preserves terminal control sequences
+        const dataBytes = new Uint8Array(frame, FRAME_HEADER_LEN);
+        pendingBytes += dataBytes.length;
+        xtermRef.current.write(dataBytes, () => {
+          pendingBytes -= dataBytes.length;
+          if (outputPaused && pendingBytes < OUTPUT_LOW_WATERMARK) {
+            outputPaused = false;
+            Tauri.Terminal.resumeOutput(existingSessionId).catch((err) => {
+              console.warn('[Terminal] Failed to resume output:', err);
             });
-
-            if (hasPrintable) {
-              promptDetectedRef.current = true;
-              if (promptDetectionTimerRef.current) {
-                clearTimeout(promptDetectionTimerRef.current);
-                promptDetectionTimerRef.current = null;
-              }
-            }
           }
+        });
+        if (!outputPaused && pendingBytes > OUTPUT_HIGH_WATERMARK) {
+          outputPaused = true;
+          Tauri.Terminal.pauseOutput(existingSessionId).catch((err) => {
+            console.warn('[Terminal] Failed to pause output:', err);
+          });
         }
-      });
+      };
 
       unlistenExit = await listen<TerminalExitEvent>('terminal-exit', (event) => {
         if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
@@ -315,6 +440,11 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
       });
 
       unlistenDead = await listen<ConnectionDeadEvent>('connection-dead', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current && event.payload.reconnect) {
+          xtermRef.current.write(`\r\n\n\x1b[33m${event.payload.reason}\x1b[0m\r\n`);
+          handleReconnectRef.current?.();
+          return;
+        }
         if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
           xtermRef.current.write(`\r\n\n\x1b[31mConnection lost: ${event.payload.reason}\x1b[0m\r\n`);
           xtermRef.current.write('\x1b[33mThe connection appears to be dead. You can try to reconnect.\x1b[0m\r\n');
@@ -324,6 +454,122 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
         }
       });
 
+      unlistenSleepDetected = await listen<SessionSleepDetectedEvent>('session-sleep-detected', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          xtermRef.current.write('\r\n\x1b[33mSystem resumed from sleep, checking connection...\x1b[0m\r\n');
+        }
+      });
+
+      unlistenResumed = await listen<SessionResumedEvent>('session-resumed', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          xtermRef.current.write('\x1b[32mConnection is still alive\x1b[0m\r\n');
+        }
+      });
+
+      // Auto-reconnect (connection policy): same session, new transport
+      unlistenReconnecting = await listen<TerminalReconnectingEvent>('terminal-reconnecting', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          const { attempt, maxAttempts, delaySecs, reason } = event.payload;
+          if (attempt === 1) {
+            xtermRef.current.write(`\r\n\x1b[33mConnection lost: ${reason}\x1b[0m\r\n`);
+          }
+          xtermRef.current.write(`\x1b[33mReconnecting in ${delaySecs}s (attempt ${attempt}/${maxAttempts})...\x1b[0m\r\n`);
+          setStatus('connecting');
+        }
+      });
+
+      unlistenReconnected = await listen<TerminalReconnectedEvent>('terminal-reconnected', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          xtermRef.current.write('\x1b[32mReconnected\x1b[0m\r\n');
+          setStatus('connected');
+        }
+      });
+
+      unlistenViewers = await listen<ShareViewersEvent>('session-share:viewers', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setShare(current => current && { ...current, viewers: event.payload.viewers });
+        }
+      });
+
+      unlistenShareEnded = await listen<ShareEndedEvent>('session-share:ended', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setShare(null);
+        }
+      });
+
+      unlistenConfirmation = await listen<CommandConfirmationEvent>('command-confirmation', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setHeldCommand(event.payload.command);
+        }
+      });
+
+      unlistenTimeLimit = await listen<SessionTimeLimitEvent>('session-time-limit', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setRemainingSecs(event.payload.remainingSecs);
+          if (event.payload.warning && xtermRef.current) {
+            const minutes = Math.ceil(event.payload.remainingSecs / 60);
+            xtermRef.current.write(`\r\n\x1b[33mSession time limit: disconnecting in ${minutes} min\x1b[0m\r\n`);
+          }
+        }
+      });
+
+      unlistenExpired = await listen<SessionExpiredEvent>('session-expired', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          xtermRef.current.write(`\r\n\n\x1b[31m${event.payload.reason}\x1b[0m`);
+        }
+      });
+
+      // Login script stopped (timeout or failure pattern): the user takes over
+      unlistenLoginFailed = await listen<LoginScriptFailedEvent>('login-script-failed', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current && xtermRef.current) {
+          const { step, error } = event.payload;
+          xtermRef.current.write(`\r\n\x1b[31mLogin script stopped at step ${step}: ${error}\x1b[0m\r\n`);
+        }
+      });
+
+      // Clipboard bridge: briefly show that the host used the local clipboard
+      unlistenClipboard = await listen<RemoteClipboardEvent>('remote-clipboard', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          const { action, length } = event.payload;
+          setClipboardNotice(action === 'copy' ? `Copied ${length} chars from host` : `Host read clipboard (${length} chars)`);
+          if (clipboardNoticeTimer) clearTimeout(clipboardNoticeTimer);
+          clipboardNoticeTimer = setTimeout(() => setClipboardNotice(null), 3000);
+        }
+      });
+
+      // Shell integration (OSC 133): exit code and duration of the last command
+      unlistenCommandStarted = await listen<CommandStartedEvent>('command-started', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setLastCommand(null);
+        }
+      });
+      unlistenCommandFinished = await listen<CommandFinishedEvent>('command-finished', (event) => {
+        if (event.payload.sessionId === sessionIdRef.current) {
+          setLastCommand(event.payload);
+        }
+      });
+
+      // Output trigger with a highlight action: mark the matching line. The
+      // output may still be on its way, so look once it has been written.
+      unlistenHighlight = await listen<TriggerHighlightEvent>('trigger-highlight', (event) => {
+        const term = xtermRef.current;
+        if (event.payload.sessionId !== sessionIdRef.current || !term) return;
+        const { text, color } = event.payload;
+        setTimeout(() => term.write('', () => {
+          const buffer = term.buffer.active;
+          const cursorLine = buffer.baseY + buffer.cursorY;
+          for (let y = cursorLine; y >= Math.max(0, cursorLine - 100); y--) {
+            if (buffer.getLine(y)?.translateToString(true).includes(text)) {
+              const marker = term.registerMarker(y - cursorLine);
+              if (marker) {
+                term.registerDecoration({ marker, width: term.cols, backgroundColor: color });
+              }
+              break;
+            }
+          }
+        }), 100);
+      });
+
       // Only initialize session if this is a new terminal (not reattached)
       const isNewTerminal = !terminalInitializedCache.has(existingSessionId);
 
@@ -331,49 +577,52 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
         setStatus('connected');
         terminalInitializedCache.add(existingSessionId);
 
+        // Reopened from the last run: its saved scrollback comes first
+        if (restoreIndex !== undefined) {
+          try {
+            const restored = await Tauri.SessionRestore.getRestoredScrollback(restoreIndex);
+            if (restored.byteLength > 0) {
+              term.write(new Uint8Array(restored));
+              // Back to the normal screen with default attributes, whatever the old output left set
+              term.write('\x1b[?1049l\x1b[0m\r\n\x1b[2m--- Restored from last session ---\x1b[0m\r\n\n');
+            }
+          } catch (err) {
+            console.warn('[Terminal] Failed to restore scrollback:', err);
+          }
+        }
+
         // Write connection banner for SSH terminals before claiming buffered output,
         // so the display order is: "Connected to X" → MOTD → shell prompt.
         if (!isLocalTerminal) {
           term.write(`Connected to ${connectionNameRef.current}\r\n\n`);
         }
 
-        // Claim the initial output buffer: SSH data that arrived before the
-        // frontend's listeners were registered is held in the Rust buffer.
-        // Claiming it atomically drains the buffer and switches to streaming mode,
-        // so future data is emitted as terminal-data events (caught by the
-        // listeners registered above). This eliminates the race condition —
-        // no timing hacks needed.
-        promptDetectedRef.current = false;
+        // Attach the output channel: SSH data that arrived before the frontend
+        // was ready is held in the Rust buffer. Attaching atomically drains the
+        // buffer and switches to streaming mode, so future data is sent as
+        // frames over the channel created above. This eliminates the race
+        // condition — no timing hacks needed. Output that hasn't arrived yet
+        // (shell still starting) is streamed as soon as it does.
         try {
-          const bufferedBase64 = await Tauri.Terminal.claimSessionOutput(existingSessionId);
-          if (bufferedBase64 && bufferedBase64.length > 0 && xtermRef.current) {
-            const dataBytes = Uint8Array.from(atob(bufferedBase64), c => c.charCodeAt(0));
-            const decoder = new TextDecoder('utf-8', { fatal: false });
-            const dataString = decoder.decode(dataBytes);
-            if (dataString.length > 0) {
-              xtermRef.current.write(dataString);
-              promptDetectedRef.current = true;
-            }
+          const buffered = await Tauri.Terminal.attachSessionOutput(existingSessionId, outputChannel);
+          if (buffered.byteLength > 0 && xtermRef.current) {
+            xtermRef.current.write(new Uint8Array(buffered));
           }
         } catch {
-          // claim is best-effort; streaming mode still activates on claim error
-        }
-
-        // Fallback: if the buffer was empty (local terminal, or SSH connected
-        // so fast that no data arrived yet), send a newline after a short delay
-        // to make the shell re-display its prompt.
-        if (!promptDetectedRef.current) {
-          promptDetectionTimerRef.current = setTimeout(() => {
-            if (!promptDetectedRef.current && sessionIdRef.current && xtermRef.current) {
-              sendTerminalInput('\n');
-            }
-          }, isLocalTerminal ? 150 : 400);
+          // Session may have closed before the frontend attached
         }
       } else {
         // Terminal already exists - reattaching to existing instance
         sessionIdRef.current = existingSessionId;
         setStatus('connected');
 
+        // Replace the previous mount's output channel
+        try {
+          await Tauri.Terminal.attachSessionOutput(existingSessionId, outputChannel);
+        } catch {
+          // Session may have closed while detached
+        }
+
         // Focus the terminal when reattaching
         if (xtermRef.current) {
           requestAnimationFrame(() => {
@@ -398,14 +647,24 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
 
     return () => {
       // Cleanup
-      if (promptDetectionTimerRef.current) {
-        clearTimeout(promptDetectionTimerRef.current);
-        promptDetectionTimerRef.current = null;
-      }
-      if (unlistenData) unlistenData();
       if (unlistenExit) unlistenExit();
       if (unlistenClosed) unlistenClosed();
       if (unlistenDead) unlistenDead();
+      if (unlistenSleepDetected) unlistenSleepDetected();
+      if (unlistenResumed) unlistenResumed();
+      if (unlistenReconnecting) unlistenReconnecting();
+      if (unlistenReconnected) unlistenReconnected();
+      if (unlistenViewers) unlistenViewers();
+      if (unlistenShareEnded) unlistenShareEnded();
+      if (unlistenConfirmation) unlistenConfirmation();
+      if (unlistenTimeLimit) unlistenTimeLimit();
+      if (unlistenExpired) unlistenExpired();
+      if (unlistenLoginFailed) unlistenLoginFailed();
+      if (unlistenClipboard) unlistenClipboard();
+      if (clipboardNoticeTimer) clearTimeout(clipboardNoticeTimer);
+      if (unlistenCommandStarted) unlistenCommandStarted();
+      if (unlistenCommandFinished) unlistenCommandFinished();
+      if (unlistenHighlight) unlistenHighlight();
 
       if (sessionIdRef.current) {
         if (isClosedByUserRef.current) {
@@ -485,6 +744,79 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
     }
   };
 
+  const handleToggleShare = async () => {
+    if (!sessionIdRef.current) return;
+
+    try {
+      if (share) {
+        await Tauri.Terminal.stopSharingSession(sessionIdRef.current);
+        setShare(null);
+      } else {
+        setShare(await Tauri.Terminal.shareSession(sessionIdRef.current));
+      }
+    } catch (err) {
+      errorHandler.handle('Failed to share session', {
+        severity: ErrorSeverity.ERROR,
+        category: ErrorCategory.TERMINAL,
+        originalError: err,
+        context: { component: 'Terminal', sessionId: sessionIdRef.current },
+      });
+    }
+  };
+
+  // Export: secrets are masked by the backend, the user reviews before copying
+  const handleExportTranscript = async () => {
+    if (!sessionIdRef.current) return;
+
+    try {
+      setTranscript(await Tauri.Terminal.exportSessionTranscript(sessionIdRef.current));
+    } catch (err) {
+      errorHandler.handle('Failed to export transcript', {
+        severity: ErrorSeverity.ERROR,
+        category: ErrorCategory.TERMINAL,
+        originalError: err,
+        context: { component: 'Terminal', sessionId: sessionIdRef.current },
+      });
+    }
+  };
+
+  // A share ends with its session
+  useEffect(() => {
+    if (share && status !== 'connected') {
+      Tauri.Terminal.stopSharingSession(share.sessionId).catch(() => {});
+      setShare(null);
+    }
+  }, [share, status]);
+
+  const handleConfirmCommand = async (approved: boolean) => {
+    if (!sessionIdRef.current) return;
+    setHeldCommand(null);
+
+    try {
+      await Tauri.Terminal.confirmTerminalCommand(sessionIdRef.current, approved);
+    } catch (err) {
+      errorHandler.handle('Failed to confirm command', {
+        severity: ErrorSeverity.ERROR,
+        category: ErrorCategory.TERMINAL,
+        originalError: err,
+        context: { component: 'Terminal', sessionId: sessionIdRef.current },
+      });
+    }
+    focusTerminalTextarea();
+  };
+
+  // A held command can't be confirmed once the session is gone
+  useEffect(() => {
+    if (status !== 'connected') {
+      setHeldCommand(null);
+      setRemainingSecs(null);
+    }
+  }, [status]);
+
+  // Listeners are set up once per session; always call the latest handler
+  const handleReconnectRef = useRef(handleReconnect);
+  handleReconnectRef.current = handleReconnect;
+
   // Handle Ctrl+F for search
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -563,8 +895,30 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
               {status === 'disconnected' && (
                 <span className="text-xs text-muted-foreground">Disconnected</span>
               )}
+              {status === 'connected' && remainingSecs !== null && (
+                <span
+                  className={`text-xs ${remainingSecs <= 300 ? 'text-red-500' : 'text-muted-foreground'}`}
+                  title="Session time limit"
+                >
+                  {Math.ceil(remainingSecs / 60)} min left
+                </span>
+              )}
+              {status === 'connected' && clipboardNotice && (
+                <span className="text-xs text-muted-foreground" title="Remote clipboard bridge">
+                  {clipboardNotice}
+                </span>
+              )}
             </>
           )}
+          {lastCommand && (
+            <span
+              className={`text-xs ${lastCommand.exitCode ? 'text-red-500' : 'text-muted-foreground'}`}
+              title="Last command"
+            >
+              {lastCommand.exitCode ? `exit ${lastCommand.exitCode} · ` : ''}
+              {formatDuration(lastCommand.durationMs)}
+            </span>
+          )}
         </div>
 
         <div className="flex items-center gap-2">
@@ -652,6 +1006,32 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
             </button>
           )}
 
+          {/* Share button - read-only live view on the local network */}
+          {!isLocalTerminal && status === 'connected' && (
+            <button
+              onClick={handleToggleShare}
+              className={`rounded p-1 hover:bg-muted transition-colors ${share ? 'text-primary' : 'text-muted-foreground hover:text-foreground'}`}
+              title={share ? 'Stop sharing' : 'Share read-only view'}
+            >
+              <svg className="h-4 w-4" fill="none" viewBox="0 0 24 24" stroke="currentColor">
+                <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M8.684 13.342C8.886 12.938 9 12.482 9 12c0-.482-.114-.938-.316-1.342m0 2.684a3 3 0 110-2.684m0 2.684l6.632 3.316m-6.632-6l6.632-3.316m0 0a3 3 0 105.367-2.684 3 3 0 00-5.367 2.684zm0 9.316a3 3 0 105.368 2.684 3 3 0 00-5.368-2.684z" />
+              </svg>
+            </button>
+          )}
+
+          {/* Export transcript button */}
+          {status === 'connected' && (
+            <button
+              onClick={handleExportTranscript}
+              className="rounded p-1 text-muted-foreground hover:bg-muted hover:text-foreground"
+              title="Export transcript"
+            >
+              <svg className="h-4 w-4" fill="none" viewBox="0 0 24 24" stroke="currentColor">
+                <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M4 16v1a3 3 0 003 3h10a3 3 0 003-3v-1m-4-4l-4 4m0 0l-4-4m4 4V4" />
+              </svg>
+            </button>
+          )}
+
           {/* Search button */}
           <button
             onClick={() => setShowSearch(!showSearch)}
@@ -675,6 +1055,90 @@ export function Terminal({ connectionId, connectionName, onClose, sessionId: exi
         </div>
       </div>
 
+      {/* Share Bar */}
+      {heldCommand && (
+        <div className="flex w-full items-center gap-2 border-b border-destructive bg-destructive/10 px-4 py-2 text-xs flex-shrink-0">
+          <span className="font-medium text-destructive">Protected host: run this command?</span>
+          <code className="truncate text-foreground">{heldCommand}</code>
+          <button
+            onClick={() => handleConfirmCommand(false)}
+            className="ml-auto rounded px-2 py-1 hover:bg-muted"
+          >
+            Cancel
+          </button>
+          <button
+            onClick={() => handleConfirmCommand(true)}
+            className="rounded bg-destructive px-2 py-1 text-destructive-foreground hover:bg-destructive/90"
+          >
+            Run command
+          </button>
+        </div>
+      )}
+
+      {share && (
+        <div className="flex w-full items-center gap-2 border-b border-border bg-card px-4 py-2 text-xs text-muted-foreground flex-shrink-0">
+          <span>Sharing read-only at</span>
+          <code className="select-all text-foreground">{share.url}</code>
+          <span>· {share.viewers} {share.viewers === 1 ? 'viewer' : 'viewers'}</span>
+          <button
+            onClick={() => navigator.clipboard.writeText(share.url)}
+            className="ml-auto rounded px-2 py-1 hover:bg-muted hover:text-foreground"
+          >
+            Copy link
+          </button>
+          <button
+            onClick={handleToggleShare}
+            className="rounded px-2 py-1 text-destructive hover:bg-muted"
+          >
+            Stop sharing
+          </button>
+        </div>
+      )}
+
+      {/* Transcript review: what was masked before the export is copied */}
+      {transcript && (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
+          <div className="mx-4 w-full max-w-lg rounded-lg bg-background p-6 shadow-xl">
+            <h2 className="mb-4 text-xl font-bold">Export transcript</h2>
+            {transcript.redactions.length === 0 ? (
+              <p className="mb-4 text-sm text-muted-foreground">No secrets were found in this transcript.</p>
+            ) : (
+              <>
+                <p className="mb-2 text-sm text-muted-foreground">
+                  {transcript.redactions.length} {transcript.redactions.length === 1 ? 'item was' : 'items were'} masked:
+                </p>
+                <ul className="mb-4 max-h-60 overflow-y-auto rounded border border-border text-xs">
+                  {transcript.redactions.map((redaction, index) => (
+                    <li key={index} className="flex gap-3 border-b border-border px-3 py-1 last:border-b-0">
+                      <span className="text-muted-foreground">line {redaction.line}</span>
+                      <span className="font-medium">{redaction.rule}</span>
+                      <code className="truncate">{redaction.text}</code>
+                    </li>
+                  ))}
+                </ul>
+              </>
+            )}
+            <div className="flex justify-end gap-3">
+              <button
+                onClick={() => setTranscript(null)}
+                className="rounded bg-secondary px-4 py-2 font-medium text-secondary-foreground hover:bg-secondary/80"
+              >
+                Cancel
+              </button>
+              <button
+                onClick={() => {
+                  navigator.clipboard.writeText(transcript.text);
+                  setTranscript(null);
+                }}
+                className="rounded bg-primary px-4 py-2 font-medium text-primary-foreground hover:bg-primary/90"
+              >
+                Copy redacted transcript
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
       {/* Search Bar */}
       {showSearch && (
         <div className="flex w-full items-center gap-2 border-b border-border bg-card px-4 py-2 flex-shrink-0">
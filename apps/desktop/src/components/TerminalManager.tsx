@@ -17,6 +17,8 @@ export interface TerminalSession {
   id: string;
   connectionId: string;
   connectionName: string;
+  shell?: string; // Local terminals: shell to reopen them with
+  restoreIndex?: number; // Reopened from the last run (index of its saved scrollback)
 }
 
 interface TerminalManagerProps {
@@ -156,6 +158,20 @@ export function TerminalManager({
         e.preventDefault();
         onSplitPane(focusedPane.id, 'vertical');
       }
+
+      // Ctrl+Shift+P: Type the connection's stored password (sudo prompts)
+      if (e.ctrlKey && e.shiftKey && e.key === 'P') {
+        e.preventDefault();
+        if (e.repeat) return;
+        Tauri.Terminal.sendStoredSecret(focusedPane.session.id, 'password').catch((error) => {
+          errorHandler.handle('Failed to send the stored password', {
+            severity: ErrorSeverity.WARNING,
+            category: ErrorCategory.TERMINAL,
+            originalError: error,
+            context: { component: 'TerminalManager', sessionId: focusedPane.session.id },
+          });
+        });
+      }
     };
 
     // Use capture phase to intercept before xterm gets the event
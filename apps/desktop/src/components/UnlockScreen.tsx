@@ -4,7 +4,7 @@
  * Allows the user to unlock the application with their master password
  */
 
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { Tauri } from '../utils/tauri';
 import { useAuthStore } from '../store/authStore';
 import { useTranslation } from '../i18n/i18n';
@@ -15,13 +15,37 @@ interface UnlockScreenProps {
 }
 
 export function UnlockScreen({ asModal = false, onClose }: UnlockScreenProps = {}) {
-  const { unlock, isLoading, error, rateLimitWaitSeconds, clearError } = useAuthStore();
+  const { unlock, unlockWithBiometric, isLoading, error, rateLimitWaitSeconds, clearError } = useAuthStore();
   const { t } = useTranslation();
 
   const [password, setPassword] = useState('');
   const [showPassword, setShowPassword] = useState(false);
   const [showResetDialog, setShowResetDialog] = useState(false);
   const [resetConfirmText, setResetConfirmText] = useState('');
+  const [resetPassword, setResetPassword] = useState('');
+  const [biometricEnabled, setBiometricEnabled] = useState(false);
+
+  useEffect(() => {
+    // Offered only when the device supports it and it was enabled in settings
+    Promise.all([
+      Tauri.Auth.isBiometricAvailable(),
+      Tauri.Settings.getSetting('biometric_unlock'),
+    ])
+      .then(([available, enabled]) => setBiometricEnabled(available && enabled === 'true'))
+      .catch(() => setBiometricEnabled(false));
+  }, []);
+
+  const handleBiometricUnlock = async () => {
+    if (isLoading) return;
+
+    clearError();
+    const success = await unlockWithBiometric();
+    if (!success) {
+      // A refused key turns biometric unlock off: check again
+      const enabled = await Tauri.Settings.getSetting('biometric_unlock').catch(() => null);
+      setBiometricEnabled(enabled === 'true');
+    }
+  };
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -37,17 +61,19 @@ export function UnlockScreen({ asModal = false, onClose }: UnlockScreenProps = {
   };
 
   const handleReset = async () => {
-    if (resetConfirmText !== 'DELETE ALL DATA') {
+    if (resetConfirmText !== 'DELETE ALL DATA' || !resetPassword) {
       return;
     }
 
     try {
-      await Tauri.Auth.resetDatabase();
+      await Tauri.Auth.resetDatabase({ password: resetPassword });
       // Reload the page to restart from first run
       window.location.reload();
     } catch (error) {
       console.error('Reset failed:', error);
       alert(t('errors.resetFailed', { error: String(error) }));
+    } finally {
+      setResetPassword('');
     }
   };
 
@@ -131,11 +157,25 @@ export function UnlockScreen({ asModal = false, onClose }: UnlockScreenProps = {
                   />
                 </div>
 
+                <div>
+                  <label htmlFor="resetPassword" className="text-sm font-medium">
+                    {t('unlock.resetPasswordLabel')}
+                  </label>
+                  <input
+                    id="resetPassword"
+                    type="password"
+                    value={resetPassword}
+                    onChange={(e) => setResetPassword(e.target.value)}
+                    className="mt-2 w-full rounded-md border border-input bg-background px-3 py-2 text-sm"
+                  />
+                </div>
+
                 <div className="flex gap-3">
                   <button
                     onClick={() => {
                       setShowResetDialog(false);
                       setResetConfirmText('');
+                      setResetPassword('');
                     }}
                     className="flex-1 rounded-md border border-border bg-background px-4 py-2 text-sm font-medium hover:bg-muted"
                   >
@@ -143,9 +183,9 @@ export function UnlockScreen({ asModal = false, onClose }: UnlockScreenProps = {
                   </button>
                   <button
                     onClick={handleReset}
-                    disabled={resetConfirmText !== 'DELETE ALL DATA'}
+                    disabled={resetConfirmText !== 'DELETE ALL DATA' || !resetPassword}
                     className={`flex-1 rounded-md px-4 py-2 text-sm font-medium ${
-                      resetConfirmText === 'DELETE ALL DATA'
+                      resetConfirmText === 'DELETE ALL DATA' && resetPassword
                         ? 'bg-red-600 text-white hover:bg-red-700'
                         : 'bg-muted text-muted-foreground cursor-not-allowed'
                     }`}
@@ -222,6 +262,17 @@ export function UnlockScreen({ asModal = false, onClose }: UnlockScreenProps = {
           {isLoading ? t('unlock.submitting') : isRateLimited ? t('unlock.locked') : t('unlock.submit')}
         </button>
 
+        {biometricEnabled && (
+          <button
+            type="button"
+            onClick={handleBiometricUnlock}
+            disabled={isLoading}
+            className="w-full rounded-md border border-border px-4 py-2 text-sm font-medium hover:bg-muted disabled:cursor-not-allowed disabled:opacity-50"
+          >
+            {t('unlock.biometric')}
+          </button>
+        )}
+
         {!asModal && (
           <div className="pt-4 border-t border-border">
             <button
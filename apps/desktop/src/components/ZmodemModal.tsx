@@ -0,0 +1,222 @@
+/**
+ * ZMODEM Transfer Modal
+ *
+ * Shown when `sz` or `rz` runs in an SSH session: asks where to save each
+ * file the host offers, or which local files to upload, then follows the
+ * transfer until it ends. The terminal is frozen meanwhile; Ctrl+C in the
+ * terminal or Cancel here stops the transfer.
+ */
+
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useTranslation } from '../i18n/i18n';
+import {
+  Tauri,
+  type ZmodemFinishedEvent,
+  type ZmodemProgressEvent,
+  type ZmodemReply,
+  type ZmodemRequestEvent,
+} from '../utils/tauri';
+
+function formatSize(bytes: number): string {
+  const units = ['B', 'KiB', 'MiB', 'GiB'];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return `${unit === 0 ? value : value.toFixed(1)} ${units[unit]}`;
+}
+
+export function ZmodemModal() {
+  const { t } = useTranslation();
+  const [request, setRequest] = useState<ZmodemRequestEvent | null>(null);
+  const [progress, setProgress] = useState<ZmodemProgressEvent | null>(null);
+  const [sessionId, setSessionId] = useState<string | null>(null);
+  const [path, setPath] = useState('');
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    const unlistenRequest = listen<ZmodemRequestEvent>('zmodem-request', (event) => {
+      setSessionId(event.payload.sessionId);
+      setRequest(event.payload);
+      setPath(event.payload.suggestedPath ?? '');
+      setError('');
+    });
+    const unlistenProgress = listen<ZmodemProgressEvent>('zmodem-progress', (event) => {
+      setSessionId(event.payload.sessionId);
+      setProgress(event.payload);
+    });
+    const unlistenFinished = listen<ZmodemFinishedEvent>('zmodem-finished', (event) => {
+      setRequest(null);
+      setProgress(null);
+      if (event.payload.error) {
+        setSessionId(event.payload.sessionId);
+        setError(event.payload.error);
+      } else {
+        setSessionId(null);
+      }
+    });
+    return () => {
+      unlistenRequest.then(fn => fn());
+      unlistenProgress.then(fn => fn());
+      unlistenFinished.then(fn => fn());
+    };
+  }, []);
+
+  if (!sessionId) {
+    return null;
+  }
+
+  const reply = async (answer: ZmodemReply) => {
+    try {
+      await Tauri.Terminal.answerZmodem(sessionId, answer);
+      setRequest(null);
+    } catch (err) {
+      setError(err as string);
+    }
+  };
+
+  const handleSubmit = (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!request) return;
+    if (request.direction === 'download') {
+      reply({ action: 'save', path: path.trim() });
+    } else {
+      const paths = path.split('\n').map(p => p.trim()).filter(Boolean);
+      reply({ action: 'send', paths });
+    }
+  };
+
+  // Failed transfer: show why until dismissed
+  if (!request && !progress) {
+    if (!error) return null;
+    return (
+      <div className="fixed bottom-6 right-6 z-50 w-96 rounded-lg border border-red-500 bg-background p-4 shadow-xl">
+        <p className="text-sm font-medium text-red-500">{t('zmodem.failed')}</p>
+        <p className="mt-1 break-words text-sm">{error}</p>
+        <div className="mt-3 flex justify-end">
+          <button
+            onClick={() => {
+              setSessionId(null);
+              setError('');
+            }}
+            className="rounded bg-secondary px-3 py-1.5 text-sm font-medium text-secondary-foreground hover:bg-secondary/80"
+          >
+            {t('zmodem.close')}
+          </button>
+        </div>
+      </div>
+    );
+  }
+
+  // Transfer running
+  if (!request && progress) {
+    const percent = progress.size ? Math.min(100, (progress.transferred / progress.size) * 100) : null;
+    return (
+      <div className="fixed bottom-6 right-6 z-50 w-96 rounded-lg border border-border bg-background p-4 shadow-xl">
+        <p className="truncate text-sm font-medium">
+          {progress.direction === 'download' ? t('zmodem.receiving') : t('zmodem.sending')} {progress.fileName}
+        </p>
+        <div className="mt-2 h-2 w-full overflow-hidden rounded bg-secondary">
+          <div
+            className="h-full bg-primary transition-all"
+            style={{ width: percent === null ? '100%' : `${percent}%` }}
+          />
+        </div>
+        <div className="mt-2 flex items-center justify-between">
+          <p className="text-xs text-muted-foreground">
+            {formatSize(progress.transferred)}
+            {progress.size !== null && ` / ${formatSize(progress.size)}`}
+          </p>
+          <button
+            onClick={() => reply({ action: 'cancel' })}
+            className="rounded bg-secondary px-3 py-1 text-xs font-medium text-secondary-foreground hover:bg-secondary/80"
+          >
+            {t('zmodem.cancel')}
+          </button>
+        </div>
+      </div>
+    );
+  }
+
+  if (!request) return null;
+  const download = request.direction === 'download';
+
+  return (
+    <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/50">
+      <div className="mx-4 w-full max-w-md rounded-lg border border-border bg-background p-6 shadow-xl">
+        <h2 className="mb-2 text-xl font-bold">
+          {download ? t('zmodem.downloadTitle') : t('zmodem.uploadTitle')}
+        </h2>
+        {download && (
+          <p className="mb-4 break-all text-sm text-muted-foreground">
+            {request.fileName}
+            {request.size != null && ` (${formatSize(request.size)})`}
+          </p>
+        )}
+
+        {error && (
+          <div className="mb-4 rounded border border-red-500 bg-red-500/10 p-3 text-sm text-red-500">
+            {error}
+          </div>
+        )}
+
+        <form onSubmit={handleSubmit} className="space-y-4">
+          <div>
+            <label className="mb-1 block text-sm font-medium">
+              {download ? t('zmodem.saveAs') : t('zmodem.files')}
+            </label>
+            {download ? (
+              <input
+                type="text"
+                value={path}
+                onChange={(e) => setPath(e.target.value)}
+                autoFocus
+                spellCheck={false}
+                className="w-full rounded border border-border bg-background px-3 py-2 font-mono text-sm focus:outline-none focus:ring-2 focus:ring-primary"
+              />
+            ) : (
+              <textarea
+                value={path}
+                onChange={(e) => setPath(e.target.value)}
+                autoFocus
+                rows={4}
+                spellCheck={false}
+                placeholder={t('zmodem.filesPlaceholder')}
+                className="w-full rounded border border-border bg-background px-3 py-2 font-mono text-sm focus:outline-none focus:ring-2 focus:ring-primary"
+              />
+            )}
+          </div>
+
+          <div className="flex justify-end gap-3">
+            <button
+              type="button"
+              onClick={() => reply({ action: 'cancel' })}
+              className="rounded bg-secondary px-4 py-2 font-medium text-secondary-foreground hover:bg-secondary/80"
+            >
+              {t('zmodem.cancel')}
+            </button>
+            {download && (
+              <button
+                type="button"
+                onClick={() => reply({ action: 'skip' })}
+                className="rounded bg-secondary px-4 py-2 font-medium text-secondary-foreground hover:bg-secondary/80"
+              >
+                {t('zmodem.skip')}
+              </button>
+            )}
+            <button
+              type="submit"
+              disabled={!path.trim()}
+              className="rounded bg-primary px-4 py-2 font-medium text-primary-foreground hover:bg-primary/90 disabled:opacity-50"
+            >
+              {download ? t('zmodem.save') : t('zmodem.send')}
+            </button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
@@ -24,6 +24,7 @@ interface AuthState {
   checkFirstRun: () => Promise<void>;
   setupMasterPassword: (password: string, confirmPassword: string) => Promise<void>;
   unlock: (password: string) => Promise<boolean>;
+  unlockWithBiometric: () => Promise<boolean>;
   lock: () => Promise<void>;
   clearError: () => void;
 }
@@ -165,6 +166,33 @@ export const useAuthStore = create<AuthState>((set, get) => ({
     }
   },
 
+  // Unlock with Touch ID / Windows Hello
+  unlockWithBiometric: async (): Promise<boolean> => {
+    try {
+      set({ isLoading: true, error: null });
+      await Tauri.Auth.unlockWithBiometric();
+      set({
+        isLocked: false,
+        isLoading: false,
+        error: null,
+        rateLimitWaitSeconds: null
+      });
+      return true;
+    } catch (error) {
+      errorHandler.handle('Biometric unlock failed', {
+        severity: ErrorSeverity.WARNING,
+        category: ErrorCategory.AUTH,
+        originalError: error,
+        context: { store: 'authStore', action: 'unlockWithBiometric' },
+      });
+      set({
+        error: `Erreur lors du déverrouillage: ${error}`,
+        isLoading: false
+      });
+      return false;
+    }
+  },
+
   // Lock the application
   lock: async () => {
     try {
@@ -8,11 +8,11 @@
  */
 
 import { create } from 'zustand';
-import { Tauri } from '../utils/tauri';
+import { Tauri, type QuickSessionMetadata } from '../utils/tauri';
 import { errorHandler, ErrorSeverity, ErrorCategory } from '../utils/errorHandler';
 
-export type Protocol = 'SSH' | 'SFTP' | 'Local';
-export type AuthType = 'password' | 'publicKey';
+export type Protocol = 'SSH' | 'SFTP' | 'Local' | 'FTP' | 'FTPS';
+export type AuthType = 'password' | 'publicKey' | 'storedKey';
 
 export interface ConnectionInfo {
   id: string;
@@ -21,13 +21,17 @@ export interface ConnectionInfo {
   hostname: string;
   port: number;
   username: string;
-  authType: string;
+  authType: 'password' | 'publicKey' | 'storedKey';
+  keyFingerprint?: string | null;
   color?: string | null;
   icon?: string | null;
+  folderId?: string | null;
   folder?: string | null;
   notes?: string | null;
+  tags?: string[];
   sshKeepAliveOverride?: string | null;
   sshKeepAliveInterval?: number | null;
+  revision: number;
   createdAt: number;
   updatedAt: number;
   lastUsedAt?: number | null;
@@ -47,10 +51,13 @@ export interface CreateConnectionInput {
     keyPath: string;
     passphrase?: string;
   };
+  proxyPassword?: string; // Proxy settings are in the session options
   color?: string;
   icon?: string;
-  folder?: string;
+  folderId?: string;
+  folder?: string; // Folder path ("Production/Databases"), created if missing
   notes?: string;
+  tags?: string[]; // Tag names, created if missing
   sshKeepAliveOverride?: string | null;
   sshKeepAliveInterval?: number | null;
 }
@@ -70,12 +77,16 @@ export interface UpdateConnectionInput {
     keyPath: string;
     passphrase?: string;
   };
+  proxyPassword?: string; // Empty string removes it
   color?: string;
   icon?: string;
-  folder?: string;
+  folderId?: string;
+  folder?: string; // Folder path ("Production/Databases"), created if missing
   notes?: string;
+  tags?: string[]; // Replaces the tags (empty = none), created if missing
   sshKeepAliveOverride?: string | null;
   sshKeepAliveInterval?: number | null;
+  revision: number; // Revision the form was opened at (update fails if it changed since)
 }
 
 interface ConnectionsState {
@@ -88,8 +99,10 @@ interface ConnectionsState {
   // Actions
   fetchConnections: () => Promise<void>;
   createConnection: (input: CreateConnectionInput) => Promise<ConnectionInfo>;
+  saveQuickSession: (sessionId: string, metadata?: QuickSessionMetadata) => Promise<ConnectionInfo>;
   updateConnection: (input: UpdateConnectionInput) => Promise<ConnectionInfo>;
   deleteConnection: (id: string) => Promise<void>;
+  restoreConnection: (id: string) => Promise<ConnectionInfo>;
   selectConnection: (id: string | null) => void;
   clearError: () => void;
 }
@@ -149,6 +162,34 @@ export const useConnectionsStore = create<ConnectionsState>((set) => ({
     }
   },
 
+  // Save a live Quick SSH session as a connection
+  saveQuickSession: async (sessionId: string, metadata?: QuickSessionMetadata) => {
+    try {
+      set({ isLoading: true, error: null });
+      const connection = await Tauri.Connections.saveQuickSession(sessionId, metadata);
+
+      // Add to local state
+      set(state => ({
+        connections: [...state.connections, connection],
+        isLoading: false
+      }));
+
+      return connection;
+    } catch (error) {
+      errorHandler.handle('Failed to save Quick SSH session', {
+        severity: ErrorSeverity.ERROR,
+        category: ErrorCategory.DATABASE,
+        originalError: error,
+        context: { store: 'connectionsStore', action: 'saveQuickSession', sessionId },
+      });
+      set({
+        error: `Failed to save Quick SSH session: ${error}`,
+        isLoading: false
+      });
+      throw error;
+    }
+  },
+
   // Update an existing connection
   updateConnection: async (input: UpdateConnectionInput) => {
     try {
@@ -206,6 +247,34 @@ export const useConnectionsStore = create<ConnectionsState>((set) => ({
     }
   },
 
+  // Take a deleted connection out of the trash (undo delete)
+  restoreConnection: async (id: string) => {
+    try {
+      set({ isLoading: true, error: null });
+      const connection = await Tauri.Connections.restoreConnection(id);
+
+      // Add back to local state
+      set(state => ({
+        connections: [...state.connections.filter(conn => conn.id !== id), connection],
+        isLoading: false
+      }));
+
+      return connection;
+    } catch (error) {
+      errorHandler.handle('Failed to restore connection', {
+        severity: ErrorSeverity.ERROR,
+        category: ErrorCategory.DATABASE,
+        originalError: error,
+        context: { store: 'connectionsStore', action: 'restoreConnection', connectionId: id },
+      });
+      set({
+        error: `Failed to restore connection: ${error}`,
+        isLoading: false
+      });
+      throw error;
+    }
+  },
+
   // Select a connection
   selectConnection: (id: string | null) => {
     set({ selectedConnectionId: id });
@@ -5,7 +5,7 @@
  * All responses from the Rust backend are validated using Zod schemas to ensure type safety.
  */
 
-import { invoke as tauriInvoke } from '@tauri-apps/api/core';
+import { Channel, invoke as tauriInvoke } from '@tauri-apps/api/core';
 import { z } from 'zod';
 import { errorHandler, ErrorSeverity, ErrorCategory } from './errorHandler';
 
@@ -68,6 +68,140 @@ const UnlockResponseSchema = z.object({
   waitSeconds: z.number().optional(),
 });
 
+const VaultFormatSchema = z.object({
+  format: z.number(),
+  currentFormat: z.number(),
+  minSupportedFormat: z.number(),
+  schemaVersion: z.number(),
+});
+
+const BackupInfoSchema = z.object({
+  path: z.string(),
+  fileName: z.string(),
+  label: z.string(),
+  createdAt: z.number(),
+  size: z.number(),
+  encrypted: z.boolean(),
+});
+
+const BackupInfoArraySchema = z.array(BackupInfoSchema);
+
+const KdfParamsSchema = z.object({
+  memoryKib: z.number(),
+  iterations: z.number(),
+  parallelism: z.number(),
+});
+
+// Session sharing schemas
+const SessionShareSchema = z.object({
+  sessionId: z.string(),
+  url: z.string(),
+  viewers: z.number(),
+});
+
+// Port forwarding schemas
+const PortForwardSpecSchema = z.object({
+  kind: z.enum(['local', 'remote', 'dynamic']),
+  bindAddress: z.string().nullable().optional(),
+  bindPort: z.number(),
+  targetHost: z.string().nullable().optional(),
+  targetPort: z.number().nullable().optional(),
+});
+
+const PortForwardSchema = PortForwardSpecSchema.extend({
+  id: z.string(),
+  sessionId: z.string(),
+  boundPort: z.number(),
+  bytesSent: z.number(),
+  bytesReceived: z.number(),
+  activeConnections: z.number(),
+  totalConnections: z.number(),
+});
+
+// SFTP schemas
+const RemoteEntrySchema = z.object({
+  name: z.string(),
+  path: z.string(),
+  isDir: z.boolean(),
+  isSymlink: z.boolean(),
+  size: z.number(),
+  modified: z.number().nullable(),
+  permissions: z.number().nullable(),
+});
+
+const SftpSessionInfoSchema = z.object({
+  sessionId: z.string(),
+  connectionId: z.string(),
+  home: z.string(),
+  scpOnly: z.boolean(),
+  protected: z.boolean(),
+});
+
+const TransferRequestSchema = z.object({
+  direction: z.enum(['upload', 'download']),
+  localPath: z.string(),
+  remotePath: z.string(),
+});
+
+const TransferInfoSchema = TransferRequestSchema.extend({
+  id: z.string(),
+  sessionId: z.string(),
+  state: z.enum(['queued', 'running', 'paused', 'completed', 'failed', 'cancelled']),
+  resumable: z.boolean(),
+  transferred: z.number(),
+  total: z.number().nullable(),
+  rate: z.number(),
+  etaSecs: z.number().nullable(),
+  error: z.string().nullable(),
+});
+
+// Session recording schemas
+const RecordingOptionsSchema = z.object({
+  recordInput: z.boolean().optional(),
+  encrypt: z.boolean().optional(),
+});
+
+const RecordingInfoSchema = z.object({
+  id: z.string(),
+  connectionId: z.string(),
+  startedAt: z.number(),
+  size: z.number(),
+  encrypted: z.boolean(),
+});
+
+// Transcript export schemas
+const RedactedTranscriptSchema = z.object({
+  text: z.string(),
+  redactions: z.array(z.object({
+    rule: z.string(),
+    line: z.number(),
+    text: z.string(),
+  })),
+});
+
+// Background task schemas
+const BackgroundTaskSchema = z.object({
+  name: z.string(),
+  state: z.enum(['running', 'waiting', 'completed', 'failed']),
+  intervalSecs: z.number().nullable(),
+  startedAt: z.number(),
+  lastRunAt: z.number().nullable(),
+  lastError: z.string().nullable(),
+  runs: z.number(),
+  failures: z.number(),
+});
+
+const BackgroundTaskArraySchema = z.array(BackgroundTaskSchema);
+
+// Session restore schemas
+const RestorableSessionSchema = z.object({
+  connectionId: z.string(),
+  title: z.string(),
+  shell: z.string().nullable(),
+  cwd: z.string().nullable(),
+  hasScrollback: z.boolean(),
+});
+
 // Settings schemas
 const SettingsRecordSchema = z.record(z.string(), z.string());
 
@@ -79,21 +213,165 @@ const ConnectionInfoSchema = z.object({
   hostname: z.string(),
   port: z.number(),
   username: z.string(),
-  authType: z.string(),
-  folder: z.string().nullable().optional(),
+  authType: z.enum(['password', 'publicKey', 'storedKey']),
+  keyFingerprint: z.string().nullable().optional(), // "SHA256:...", key auth only
+  folderId: z.string().nullable().optional(),
+  folder: z.string().nullable().optional(), // Folder path, e.g. "Production/Databases"
   color: z.string().nullable().optional(),
   icon: z.string().nullable().optional(),
   notes: z.string().nullable().optional(),
+  tags: z.array(z.string()).default([]),
   sshKeepAliveOverride: z.string().nullable().optional(),
   sshKeepAliveInterval: z.number().nullable().optional(),
+  revision: z.number(),
   createdAt: z.number(),
   updatedAt: z.number(),
   lastUsedAt: z.number().nullable().optional(),
+  deletedAt: z.number().nullable().optional(), // In the trash since (ms)
 });
 
 const ConnectionInfoArraySchema = z.array(ConnectionInfoSchema);
 
+// Rejection of update_connection: a conflict carries the stored version
+const UpdateConnectionErrorSchema = z.discriminatedUnion('type', [
+  z.object({ type: z.literal('conflict'), message: z.string(), current: ConnectionInfoSchema }),
+  z.object({ type: z.literal('failed'), message: z.string() }),
+]);
+
+/**
+ * Whether an updateConnection rejection is a revision conflict (the connection
+ * was modified elsewhere since it was read)
+ */
+export function isUpdateConflict(
+  error: unknown
+): error is Extract<UpdateConnectionError, { type: 'conflict' }> {
+  const result = UpdateConnectionErrorSchema.safeParse(error);
+  return result.success && result.data.type === 'conflict';
+}
+
+const ConnectionTestReportSchema = z.object({
+  connectionId: z.string(),
+  success: z.boolean(),
+  stages: z.array(
+    z.object({
+      stage: z.enum(['dns', 'tcp', 'banner', 'host_key', 'auth']),
+      status: z.enum(['ok', 'failed', 'skipped']),
+      durationMs: z.number(),
+      detail: z.string().nullable(), // Addresses, banner, host key... or the error
+    })
+  ),
+  durationMs: z.number(),
+});
+
+// Folder schemas
+const FolderSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  parentId: z.string().nullable(),
+  color: z.string().nullable(),
+  sortOrder: z.number(),
+  createdAt: z.number(),
+  updatedAt: z.number(),
+});
+
+type FolderNodeShape = z.infer<typeof FolderSchema> & {
+  path: string;
+  connectionCount: number;
+  children: FolderNodeShape[];
+};
+
+const FolderNodeSchema: z.ZodType<FolderNodeShape> = FolderSchema.extend({
+  path: z.string(),
+  connectionCount: z.number(),
+  children: z.lazy(() => z.array(FolderNodeSchema)),
+});
+
+// Tag schemas
+const TagSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  color: z.string().nullable(),
+  connectionCount: z.number(),
+  createdAt: z.number(),
+  updatedAt: z.number(),
+});
+
+// Key generation schemas
+const GeneratedKeySchema = z.object({
+  publicKey: z.string(),
+  fingerprint: z.string(),
+  path: z.string().nullable(),
+});
+
+const CertificateInfoSchema = z.object({
+  path: z.string(),
+  keyId: z.string(),
+  principals: z.array(z.string()),
+  validAfter: z.number(),
+  validBefore: z.number().nullable(),
+  caFingerprint: z.string(),
+});
+
+// Connection templates (every field except the host, no secrets)
+const ConnectionTemplateSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  protocol: z.string(),
+  port: z.number(),
+  username: z.string(),
+  authType: z.string(),
+  keyPath: z.string().nullable(),
+  color: z.string().nullable(),
+  icon: z.string().nullable(),
+  folder: z.string().nullable(),
+  notes: z.string().nullable(),
+  sshKeepAliveOverride: z.string().nullable(),
+  sshKeepAliveInterval: z.number().nullable(),
+  sessionOptions: z.unknown(),
+  createdAt: z.number(),
+  updatedAt: z.number(),
+});
+
+const ConnectionTemplateArraySchema = z.array(ConnectionTemplateSchema);
+
+// Connection usage history (timestamps in Unix seconds, durations in seconds)
+const SessionRecordSchema = z.object({
+  id: z.number(),
+  connectionId: z.string(),
+  startedAt: z.number(),
+  endedAt: z.number().nullable(),
+  bytesIn: z.number(),
+  bytesOut: z.number(),
+  exitStatus: z.number().nullable(),
+});
+
+const ConnectionStatsSchema = z.object({
+  connectionId: z.string(),
+  sessionCount: z.number(),
+  failedCount: z.number(),
+  totalDuration: z.number(),
+  bytesIn: z.number(),
+  bytesOut: z.number(),
+  firstUsedAt: z.number().nullable(),
+  lastUsedAt: z.number().nullable(),
+});
+
+const ConnectionUsageSchema = z.object({
+  connectionId: z.string(),
+  sessionCount: z.number(),
+  totalDuration: z.number(),
+  lastUsedAt: z.number(),
+});
+
 // SSH Config schemas
+const JumpHostSchema = z.object({
+  host: z.string(),
+  hostname: z.string().nullable(),
+  user: z.string().nullable(),
+  port: z.number().nullable(),
+  identityFile: z.string().nullable(),
+});
+
 const SshConfigEntrySchema = z.object({
   host: z.string(),
   hostname: z.string().nullable(),
@@ -101,20 +379,175 @@ const SshConfigEntrySchema = z.object({
   port: z.number().nullable(),
   identityFile: z.string().nullable(),
   serverAliveInterval: z.number().nullable(),
+  // ProxyJump hops, first hop first
+  proxyJump: z.array(JumpHostSchema).default([]),
 });
 
 const SshConfigEntryArraySchema = z.array(SshConfigEntrySchema);
 
+// SSH config sync (`ssh-config-changed` event payload)
+const SshConfigSyncSuggestionSchema = z.object({
+  action: z.enum(['add', 'update', 'remove']),
+  host: z.string(),
+  connectionId: z.string().nullable(),
+  entry: SshConfigEntrySchema.nullable(),
+});
+
+const SshConfigSyncSuggestionArraySchema = z.array(SshConfigSyncSuggestionSchema);
+
+// Import from other SSH clients
+const ImportSourceSchema = z.enum(['putty', 'termius', 'mremoteng', 'securecrt']);
+
+const ImportEntrySchema = z.object({
+  source: ImportSourceSchema,
+  name: z.string(),
+  hostname: z.string(),
+  port: z.number().nullable(),
+  username: z.string().nullable(),
+  keyPath: z.string().nullable(),
+  folder: z.string().nullable(),
+  tags: z.array(z.string()),
+  notes: z.string().nullable(),
+});
+
+const ImportEntryArraySchema = z.array(ImportEntrySchema);
+
 // Terminal schemas
 const StringArraySchema = z.array(z.string());
 
+// Snippet schemas
+const SnippetSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  folder: z.string().nullable(),
+  command: z.string(),
+  createdAt: z.number(),
+  updatedAt: z.number(),
+});
+
+const SnippetDeliverySchema = z.object({
+  sessionId: z.string(),
+  error: z.string().nullable(),
+});
+
+// Session stats schema
+const SessionStatsSchema = z.object({
+  sessionId: z.string(),
+  bytesIn: z.number(),
+  bytesOut: z.number(),
+  rateIn: z.number(), // Bytes per second since the previous report
+  rateOut: z.number(),
+  latencyMs: z.number().nullable(), // Null until measured, or when the server didn't answer
+  updatedAt: z.number(),
+});
+
+// Exec command schema
+const ExecOutputSchema = z.object({
+  stdout: z.string(),
+  stderr: z.string(),
+  exitStatus: z.number().nullable(),
+  exitSignal: z.string().nullable(),
+  timedOut: z.boolean(),
+  truncated: z.boolean(),
+  durationMs: z.number(),
+});
+
+// Remote task schemas
+const RemoteTaskSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  command: z.string(),
+  connectionIds: z.array(z.string()),
+  schedule: z.string().nullable(),
+  enabled: z.boolean(),
+  createdAt: z.number(),
+  updatedAt: z.number(),
+});
+
+const RemoteTaskRunSchema = z.object({
+  id: z.string(),
+  taskId: z.string(),
+  connectionId: z.string(),
+  startedAt: z.number(),
+  endedAt: z.number(),
+  exitStatus: z.number().nullable(),
+  error: z.string().nullable(),
+  output: z.string(),
+});
+
+// Host monitoring schemas
+const HostMetricsSchema = z.object({
+  connectionId: z.string(),
+  collectedAt: z.number(),
+  uptimeSecs: z.number().nullable(),
+  load: z.tuple([z.number(), z.number(), z.number()]).nullable(),
+  memory: z
+    .object({
+      totalKib: z.number(),
+      availableKib: z.number(),
+      swapTotalKib: z.number(),
+      swapFreeKib: z.number(),
+    })
+    .nullable(),
+  disks: z.array(
+    z.object({
+      filesystem: z.string(),
+      mount: z.string(),
+      totalKib: z.number(),
+      usedKib: z.number(),
+      availableKib: z.number(),
+    })
+  ),
+  users: z.array(
+    z.object({
+      name: z.string(),
+      tty: z.string(),
+      from: z.string().nullable(),
+    })
+  ),
+  error: z.string().nullable(),
+});
+
+// Known host schema
+const KnownHostSchema = z.object({
+  id: z.string(),
+  host: z.string(),
+  port: z.number(),
+  keyType: z.string(),
+  fingerprint: z.string(),
+  addedAt: z.number(),
+  lastSeenAt: z.number(),
+});
+
+const KnownHostsImportSchema = z.object({
+  imported: z.number(),
+  alreadyKnown: z.number(),
+  conflicts: z.array(z.string()),
+  skipped: z.number(),
+});
+
 // Password validation schema
 const PasswordStrengthSchema = z.object({
-  is_valid: z.boolean(),
-  score: z.number(),
-  feedback: z.array(z.string()),
+  isValid: z.boolean(),
+  score: z.number().int().min(0).max(4),
+  guessesLog10: z.number(),
+  crackTimeSeconds: z.number(),
+  crackTimeDisplay: z.string(),
+  warning: z.string().nullable(),
+  suggestions: z.array(z.string()),
+});
+
+const GeneratedPasswordSchema = z.object({
+  password: z.string(),
+  entropyBits: z.number(),
 });
 
+/** Random characters, or a diceware passphrase (`wordlist`: path to a
+ * diceware file, the built-in list otherwise) */
+export type PasswordOptions =
+  | { type: 'characters'; length: number; symbols: boolean }
+  | { type: 'passphrase'; words: number; separator: string; wordlist?: string };
+
 // ============================================================================
 // Type-Safe Tauri Command Wrappers
 // ============================================================================
@@ -148,6 +581,39 @@ export const TauriAuth = {
    */
   lock: () => invokeWithValidation('lock', z.null()),
 
+  /**
+   * Check if this device supports biometric unlock (Touch ID, Windows Hello)
+   */
+  isBiometricAvailable: () => invokeWithValidation('is_biometric_available', BooleanSchema),
+
+  /**
+   * Unlock with Touch ID / Windows Hello (must be enabled first)
+   */
+  unlockWithBiometric: () => invokeWithValidation('unlock_with_biometric', z.null()),
+
+  /**
+   * Enable biometric unlock (saves the master key behind the biometric check)
+   */
+  enableBiometricUnlock: (password: string) =>
+    invokeWithValidation('enable_biometric_unlock', z.null(), { password }),
+
+  /**
+   * Disable biometric unlock (deletes the saved master key)
+   */
+  disableBiometricUnlock: () => invokeWithValidation('disable_biometric_unlock', z.null()),
+
+  /**
+   * Change the master password (re-encrypts the vault)
+   */
+  changeMasterPassword: (oldPassword: string, newPassword: string) =>
+    invokeWithValidation('change_master_password', z.null(), { oldPassword, newPassword }),
+
+  /**
+   * Benchmark this machine and save suggested KDF parameters (~500ms),
+   * applied on the next master password change
+   */
+  calibrateKdf: () => invokeWithValidation('calibrate_kdf', KdfParamsSchema),
+
   /**
    * Validate password strength
    */
@@ -155,9 +621,63 @@ export const TauriAuth = {
     invokeWithValidation('validate_password', PasswordStrengthSchema, { password }),
 
   /**
-   * Reset the database (DANGEROUS - only for UnlockScreen emergency reset)
+   * Generate a random password or diceware passphrase
+   */
+  generatePassword: (options: PasswordOptions) =>
+    invokeWithValidation('generate_password', GeneratedPasswordSchema, { options }),
+
+  /**
+   * Reset the database (DANGEROUS - only for UnlockScreen emergency reset).
+   * Requires the master password while locked, a capability token while
+   * unlocked.
+   */
+  resetDatabase: (auth: { password?: string; token?: string }) =>
+    invokeWithValidation('reset_database', z.null(), auth),
+
+  /**
+   * Get the vault format and schema versions
+   */
+  getVaultFormat: () => invokeWithValidation('get_vault_format', VaultFormatSchema),
+
+  /**
+   * Write an encrypted backup of the vault (requires a capability token)
+   */
+  backupVault: (token: string) => invokeWithValidation('backup_vault', BackupInfoSchema, { token }),
+
+  /**
+   * List vault backups, newest first
+   */
+  listBackups: () => invokeWithValidation('list_backups', BackupInfoArraySchema),
+
+  /**
+   * Replace the vault with a backup, then lock (the backup's master password
+   * unlocks it). Requires a capability token while unlocked, the current
+   * master password while locked.
+   */
+  restoreVault: (
+    path: string,
+    password: string,
+    auth: { token?: string; masterPassword?: string }
+  ) => invokeWithValidation('restore_vault', z.null(), { path, password, ...auth }),
+
+  /**
+   * Whether the whole vault file is encrypted (SQLCipher)
+   */
+  isDatabaseEncrypted: () => invokeWithValidation('is_database_encrypted', z.boolean()),
+
+  /**
+   * Encrypt the whole vault file (requires a capability token). The
+   * application restarts and must be unlocked again.
+   */
+  encryptDatabase: (token: string) =>
+    invokeWithValidation('encrypt_database', z.null(), { token }),
+
+  /**
+   * Decrypt the vault file (requires a capability token). The application
+   * restarts and must be unlocked again.
    */
-  resetDatabase: () => invokeWithValidation('reset_database', z.null()),
+  decryptDatabase: (token: string) =>
+    invokeWithValidation('decrypt_database', z.null(), { token }),
 } as const;
 
 // Settings Commands
@@ -179,6 +699,17 @@ export const TauriSettings = {
     invokeWithValidation('set_setting', z.null(), { key, value }),
 } as const;
 
+// Metadata for saving a Quick SSH session (name defaults to user@host)
+export interface QuickSessionMetadata {
+  name?: string;
+  color?: string;
+  icon?: string;
+  folderId?: string;
+  folder?: string; // Folder path, created if missing (when no folderId)
+  notes?: string;
+  tags?: string[];
+}
+
 // Connection Commands
 export const TauriConnections = {
   /**
@@ -193,7 +724,15 @@ export const TauriConnections = {
     invokeWithValidation('create_connection', ConnectionInfoSchema, { input }),
 
   /**
-   * Update an existing connection
+   * Save a live Quick SSH session as a connection (host, user and
+   * credentials are taken from the session; the vault must be unlocked)
+   */
+  saveQuickSession: (sessionId: string, metadata: QuickSessionMetadata = {}) =>
+    invokeWithValidation('save_quick_session', ConnectionInfoSchema, { sessionId, metadata }),
+
+  /**
+   * Update an existing connection. Rejects with an UpdateConnectionError; a
+   * `conflict` (see isUpdateConflict) carries the stored connection to merge with
    */
   updateConnection: (input: unknown) =>
     invokeWithValidation('update_connection', ConnectionInfoSchema, { input }),
@@ -204,92 +743,938 @@ export const TauriConnections = {
   deleteConnection: (id: string) => invokeWithValidation('delete_connection', z.null(), { id }),
 
   /**
-   * Get default SSH config path (~/.ssh/config)
+   * Connections in the trash, most recently deleted first
    */
-  getDefaultSshConfigPath: () =>
-    invokeWithValidation('get_default_ssh_config_path', StringSchema),
+  listTrashedConnections: () =>
+    invokeWithValidation('list_trashed_connections', ConnectionInfoArraySchema),
 
   /**
-   * Parse SSH config file and return entries for preview
+   * Take a connection out of the trash (undo delete)
    */
-  parseSshConfig: (configPath: string) =>
-    invokeWithValidation('parse_ssh_config', SshConfigEntryArraySchema, { configPath }),
+  restoreConnection: (id: string) =>
+    invokeWithValidation('restore_connection', ConnectionInfoSchema, { id }),
 
   /**
-   * Import selected SSH config entries as connections
+   * Permanently delete the connections in the trash, returns their IDs
+   * (the trash is also purged automatically after `trash_retention_days`)
    */
-  importSshConfigEntries: (entries: unknown[]) =>
-    invokeWithValidation('import_ssh_config_entries', ConnectionInfoArraySchema, { entries }),
-} as const;
+  purgeTrash: () => invokeWithValidation('purge_trash', z.array(z.string())),
 
-// Terminal Commands
-export const TauriTerminal = {
   /**
-   * Get list of installed shells
+   * Check an SSH connection stage by stage (DNS, TCP, banner, host key,
+   * authentication) with timings, without opening a terminal
    */
-  getInstalledShells: (shells: string[]) =>
-    invokeWithValidation('get_installed_shells', StringArraySchema, { shells }),
+  testConnection: (connectionId: string) =>
+    invokeWithValidation('test_connection', ConnectionTestReportSchema, { connectionId }),
 
   /**
-   * Connect to a terminal (SSH connection)
+   * Copy a connection, credentials included (name defaults to "<name> (copy)")
    */
-  connectTerminal: (connectionId: string) =>
-    invokeWithValidation('connect_terminal', StringSchema, { connectionId }),
+  duplicateConnection: (id: string, name?: string) =>
+    invokeWithValidation('duplicate_connection', ConnectionInfoSchema, { id, name: name ?? null }),
 
   /**
-   * Connect to a local terminal with custom shell
+   * Get all connection templates
    */
-  connectLocalTerminal: (shell?: string) =>
-    invokeWithValidation('connect_local_terminal', StringSchema, { shell }),
+  listTemplates: () =>
+    invokeWithValidation('list_connection_templates', ConnectionTemplateArraySchema),
 
   /**
-   * Quick SSH connect (temporary connection)
+   * Make a template from an existing connection (host and secrets left out)
    */
-  quickSshConnect: (
-    host: string,
-    username: string,
-    port: number,
-    authMethod: {
-      type: 'password';
-      password: string;
-    } | {
-      type: 'publicKey';
-      keyPath: string;
-      passphrase?: string;
-    }
-  ) =>
-    invokeWithValidation('quick_ssh_connect', StringSchema, {
+  createTemplateFromConnection: (connectionId: string, name: string) =>
+    invokeWithValidation('create_template_from_connection', ConnectionTemplateSchema, {
+      connectionId,
+      name,
+    }),
+
+  /**
+   * Create a connection to a new host from a template (name defaults to the host)
+   */
+  createFromTemplate: (templateId: string, host: string, name?: string) =>
+    invokeWithValidation('create_from_template', ConnectionInfoSchema, {
+      templateId,
       host,
-      username,
-      port,
-      authMethod,
+      name: name ?? null,
     }),
 
   /**
-   * Send input to a terminal session
+   * Usage statistics of a connection (sessions, time connected, bytes)
    */
-  sendTerminalInput: (sessionId: string, data: number[]) =>
-    invokeWithValidation('send_terminal_input', z.null(), { sessionId, data }),
+  getConnectionStats: (id: string) =>
+    invokeWithValidation('get_connection_stats', ConnectionStatsSchema, { id }),
 
   /**
-   * Resize a terminal session
+   * Latest sessions of a connection, most recent first (50 by default)
    */
-  resizeTerminal: (sessionId: string, cols: number, rows: number) =>
-    invokeWithValidation('resize_terminal', z.null(), { sessionId, cols, rows }),
+  getConnectionHistory: (id: string, limit?: number) =>
+    invokeWithValidation('get_connection_history', z.array(SessionRecordSchema), { id, limit }),
 
   /**
-   * Claim the initial output buffer for a terminal session.
-   * Returns base64-encoded bytes that arrived before the frontend registered
-   * its listener, and switches the session to streaming mode.
+   * Connections with the most sessions in the last `days` days (30 by default)
    */
-  claimSessionOutput: (sessionId: string) =>
-    invokeWithValidation('claim_session_output', z.string(), { sessionId }),
+  getMostUsedConnections: (days?: number, limit?: number) =>
+    invokeWithValidation('get_most_used_connections', z.array(ConnectionUsageSchema), {
+      days,
+      limit,
+    }),
 
   /**
-   * Disconnect a terminal session
+   * Connections by their last session, most recent first (10 by default)
    */
-  disconnectTerminal: (sessionId: string) =>
-    invokeWithValidation('disconnect_terminal', z.null(), { sessionId }),
+  getRecentConnections: (limit?: number) =>
+    invokeWithValidation('get_recent_connections', z.array(ConnectionUsageSchema), { limit }),
+
+  /**
+   * Full-text search over name, hostname, username, notes, folder and tags
+   * (each word matches a word start), best matches first; at most `limit`
+   * results (50 by default)
+   */
+  searchConnections: (query: string, limit?: number) =>
+    invokeWithValidation('search_connections', ConnectionInfoArraySchema, { query, limit }),
+
+  /**
+   * Connections in a folder (and its subfolders if `recursive`)
+   */
+  getConnectionsByFolder: (folderId: string, recursive = false) =>
+    invokeWithValidation('get_connections_by_folder', ConnectionInfoArraySchema, {
+      folderId,
+      recursive,
+    }),
+
+  /**
+   * Move a connection to a folder (null = top level), e.g. after a drag and drop
+   */
+  moveConnectionToFolder: (id: string, folderId: string | null) =>
+    invokeWithValidation('move_connection_to_folder', ConnectionInfoSchema, { id, folderId }),
+
+  /**
+   * Store a connection's key file inside the vault (encrypted, included in
+   * backups); the connection then uses the stored key
+   */
+  importPrivateKey: (connectionId: string, keyPath: string, passphrase?: string) =>
+    invokeWithValidation('import_private_key', ConnectionInfoSchema, {
+      connectionId,
+      keyPath,
+      passphrase: passphrase || null,
+    }),
+
+  /**
+   * Write a vault-stored key to a new file (requires a capability token)
+   */
+  exportPrivateKey: (connectionId: string, path: string, token: string) =>
+    invokeWithValidation('export_private_key', z.null(), { connectionId, path, token }),
+
+  /**
+   * Generate a new keypair, written to `path` + `path.pub` or stored in the
+   * vault for a connection; returns the public key to install on servers
+   */
+  generateSshKey: (
+    keyType: SshKeyType,
+    output: { type: 'file'; path: string } | { type: 'vault'; connectionId: string },
+    comment?: string,
+    passphrase?: string,
+  ) =>
+    invokeWithValidation('generate_ssh_key', GeneratedKeySchema, {
+      keyType,
+      output,
+      comment: comment || null,
+      passphrase: passphrase || null,
+    }),
+
+  /**
+   * OpenSSH user certificate next to a key file (`<key>-cert.pub`), null if none;
+   * validBefore is null for certificates that never expire
+   */
+  getKeyCertificate: (keyPath: string) =>
+    invokeWithValidation('get_key_certificate', CertificateInfoSchema.nullable(), { keyPath }),
+
+  /**
+   * Get default SSH config path (~/.ssh/config)
+   */
+  getDefaultSshConfigPath: () =>
+    invokeWithValidation('get_default_ssh_config_path', StringSchema),
+
+  /**
+   * Parse SSH config file and return entries for preview
+   */
+  parseSshConfig: (configPath: string) =>
+    invokeWithValidation('parse_ssh_config', SshConfigEntryArraySchema, { configPath }),
+
+  /**
+   * Import selected SSH config entries as connections (ProxyJump hops
+   * become jump hosts, created if not saved yet)
+   */
+  importSshConfigEntries: (entries: unknown[]) =>
+    invokeWithValidation('import_ssh_config_entries', ConnectionInfoArraySchema, { entries }),
+
+  /**
+   * Changes between ~/.ssh/config and the connections imported from it
+   * (also emitted as `ssh-config-changed` while sync is enabled)
+   */
+  getSshConfigSyncSuggestions: () =>
+    invokeWithValidation('get_ssh_config_sync_suggestions', SshConfigSyncSuggestionArraySchema),
+
+  /**
+   * Apply SSH config sync suggestions (add, update or remove connections)
+   */
+  applySshConfigSync: (suggestions: SshConfigSyncSuggestion[]) =>
+    invokeWithValidation('apply_ssh_config_sync', z.null(), { suggestions }),
+
+  /**
+   * Dismiss SSH config sync suggestions without changing the connections
+   */
+  dismissSshConfigSync: (suggestions: SshConfigSyncSuggestion[]) =>
+    invokeWithValidation('dismiss_ssh_config_sync', z.null(), { suggestions }),
+
+  /**
+   * Read the sessions of another SSH client (a file, or a sessions folder
+   * for PuTTY and SecureCRT) for preview
+   */
+  previewImport: (source: ImportSource, path: string) =>
+    invokeWithValidation('preview_import', ImportEntryArraySchema, { source, path }),
+
+  /**
+   * Import selected sessions from another SSH client as connections
+   */
+  runImport: (entries: ImportEntry[]) =>
+    invokeWithValidation('run_import', ConnectionInfoArraySchema, { entries }),
+} as const;
+
+// Vault change events (emitted after writes, to every window)
+export const VaultEvents = {
+  connectionsChanged: 'vault:connections-changed',
+  settingsChanged: 'vault:settings-changed',
+  foldersChanged: 'vault:folders-changed',
+  tagsChanged: 'vault:tags-changed',
+} as const;
+
+// Emitted with the SSH config sync suggestions when ~/.ssh/config changes
+export const SSH_CONFIG_CHANGED_EVENT = 'ssh-config-changed';
+
+/** Key types accepted by `generate_ssh_key` (RSA is 4096 bits) */
+export type SshKeyType = 'ed25519' | 'rsa' | 'ecdsa-p256' | 'ecdsa-p384' | 'ecdsa-p521';
+
+/** Keyboard-interactive prompts shown while connecting */
+export interface AuthPromptEvent {
+  promptId: string;
+  host: string;
+  port: number;
+  username: string;
+  name: string;
+  instructions: string;
+  prompts: { prompt: string; echo: boolean }[];
+}
+
+/** FIDO2 security key waiting for a touch (`waiting` false once signed) */
+export interface SecurityKeyTouchEvent {
+  host: string;
+  port: number;
+  username: string;
+  fingerprint: string;
+  waiting: boolean;
+}
+
+/** ZMODEM transfer started by `sz` (a file offered) or `rz` (files wanted) */
+export interface ZmodemRequestEvent {
+  sessionId: string;
+  direction: 'download' | 'upload';
+  fileName?: string;
+  size?: number | null;
+  suggestedPath?: string;
+}
+
+export interface ZmodemProgressEvent {
+  sessionId: string;
+  direction: 'download' | 'upload';
+  fileName: string;
+  transferred: number;
+  size: number | null;
+}
+
+export interface ZmodemFinishedEvent {
+  sessionId: string;
+  direction: 'download' | 'upload';
+  error: string | null;
+}
+
+export type ZmodemReply =
+  | { action: 'save'; path: string }
+  | { action: 'skip' }
+  | { action: 'send'; paths: string[] }
+  | { action: 'cancel' };
+
+/**
+ * Output trigger with a notify action matched in a session
+ */
+export interface TriggerNotificationEvent {
+  sessionId: string;
+  connectionName: string;
+  message: string;
+}
+
+/**
+ * Stored secret of a connection that can be typed into its terminal
+ */
+export type StoredSecretKind = 'password' | 'passphrase' | 'proxyPassword';
+
+/**
+ * Periodic traffic and latency report of an SSH session
+ */
+export type SessionStatsEvent = SessionStats;
+
+/**
+ * Run of a remote task finished on one connection
+ */
+export interface RemoteTaskCompletedEvent {
+  taskId: string;
+  taskName: string;
+  runId: string;
+  connectionId: string;
+  exitStatus: number | null;
+  error: string | null; // Set when the command didn't run to completion
+}
+
+/**
+ * Round of periodic host monitoring (payload of `host-metrics`)
+ */
+export type HostMetricsEvent = HostMetrics[];
+
+export interface VaultChangeEvent {
+  change: 'created' | 'updated' | 'deleted';
+  ids: string[]; // Connection IDs, setting keys or folder names
+}
+
+// Terminal Commands
+export const TauriTerminal = {
+  /**
+   * Get list of installed shells
+   */
+  getInstalledShells: (shells: string[]) =>
+    invokeWithValidation('get_installed_shells', StringArraySchema, { shells }),
+
+  /**
+   * Connect to a terminal (SSH connection)
+   */
+  connectTerminal: (connectionId: string) =>
+    invokeWithValidation('connect_terminal', StringSchema, { connectionId }),
+
+  /**
+   * Connect to a local terminal with custom shell (with the environment
+   * variables and locale of a saved connection if `connectionId` is set),
+   * started in `cwd` if it exists
+   */
+  connectLocalTerminal: (shell?: string, connectionId?: string, cwd?: string) =>
+    invokeWithValidation('connect_local_terminal', StringSchema, { shell, connectionId, cwd }),
+
+  /**
+   * Quick SSH connect (temporary connection)
+   *
+   * Host keys are verified like saved connections; acceptHostKeyOnce trusts
+   * the server's key for this session only, without saving it
+   */
+  quickSshConnect: (
+    host: string,
+    username: string,
+    port: number,
+    authMethod: {
+      type: 'password';
+      password: string;
+    } | {
+      type: 'publicKey';
+      keyPath: string;
+      passphrase?: string;
+    },
+    acceptHostKeyOnce?: boolean
+  ) =>
+    invokeWithValidation('quick_ssh_connect', StringSchema, {
+      host,
+      username,
+      port,
+      authMethod,
+      acceptHostKeyOnce,
+    }),
+
+  /**
+   * Answer a keyboard-interactive prompt (`ssh:auth-prompt` event) while
+   * connecting; null cancels the authentication
+   */
+  answerAuthPrompt: (promptId: string, answers: string[] | null) =>
+    invokeWithValidation('answer_auth_prompt', z.null(), { promptId, answers }),
+
+  /**
+   * Send input to a terminal session
+   */
+  sendTerminalInput: (sessionId: string, data: number[]) =>
+    invokeWithValidation('send_terminal_input', z.null(), { sessionId, data }),
+
+  /**
+   * Type a stored secret of the session's connection into the terminal, followed
+   * by Enter (e.g. at a sudo prompt); the secret never reaches the frontend
+   */
+  sendStoredSecret: (sessionId: string, kind: StoredSecretKind) =>
+    invokeWithValidation('send_stored_secret', z.null(), { sessionId, kind }),
+
+  /**
+   * Export a session transcript (scrollback and screen) with secrets masked,
+   * plus the list of masked items for review
+   */
+  exportSessionTranscript: (sessionId: string) =>
+    invokeWithValidation('export_session_transcript', RedactedTranscriptSchema, { sessionId }),
+
+  /**
+   * Current working directory of a session as reported by its shell (OSC 7),
+   * null when the shell has no integration
+   */
+  getSessionCwd: (sessionId: string) =>
+    invokeWithValidation('get_session_cwd', z.string().nullable(), { sessionId }),
+
+  /**
+   * Traffic and latency of a session as of its last report (every 5 seconds,
+   * also sent as `session-stats` events); null for local terminals
+   */
+  getSessionStats: (sessionId: string) =>
+    invokeWithValidation('get_session_stats', SessionStatsSchema.nullable(), { sessionId }),
+
+  /**
+   * Measure a session's latency while its stats are shown; without it the
+   * reports carry traffic only
+   */
+  watchSessionStats: (sessionId: string, watch: boolean) =>
+    invokeWithValidation('watch_session_stats', z.null(), { sessionId, watch }),
+
+  /**
+   * Start recording a session as an asciinema v2 cast file. Input is only
+   * recorded with `recordInput`; `encrypt` uses the master key.
+   */
+  startSessionRecording: (sessionId: string, options?: RecordingOptions) =>
+    invokeWithValidation('start_session_recording', RecordingInfoSchema, {
+      sessionId,
+      options: options ?? null,
+    }),
+
+  /**
+   * Stop recording a session
+   */
+  stopSessionRecording: (sessionId: string) =>
+    invokeWithValidation('stop_session_recording', RecordingInfoSchema, { sessionId }),
+
+  /**
+   * Running recording of a session (null when not recording)
+   */
+  getSessionRecording: (sessionId: string) =>
+    invokeWithValidation('get_session_recording', RecordingInfoSchema.nullable(), { sessionId }),
+
+  /**
+   * List the recordings of a connection ('local' for local terminals), newest first
+   */
+  listRecordings: (connectionId: string) =>
+    invokeWithValidation('list_recordings', z.array(RecordingInfoSchema), { connectionId }),
+
+  /**
+   * Read a recording as asciicast v2 text (decrypted, for playback)
+   */
+  readRecording: (connectionId: string, recordingId: string) =>
+    invokeWithValidation('read_recording', StringSchema, { connectionId, recordingId }),
+
+  /**
+   * Delete a recording
+   */
+  deleteRecording: (connectionId: string, recordingId: string) =>
+    invokeWithValidation('delete_recording', z.null(), { connectionId, recordingId }),
+
+  /**
+   * Confirm or cancel a dangerous command held back on a protected host
+   */
+  confirmTerminalCommand: (sessionId: string, approved: boolean) =>
+    invokeWithValidation('confirm_terminal_command', z.null(), { sessionId, approved }),
+
+  /**
+   * Answer the ZMODEM transfer running in a session
+   */
+  answerZmodem: (sessionId: string, reply: ZmodemReply) =>
+    invokeWithValidation('answer_zmodem', z.null(), { sessionId, reply }),
+
+  /**
+   * Resize a terminal session
+   */
+  resizeTerminal: (sessionId: string, cols: number, rows: number) =>
+    invokeWithValidation('resize_terminal', z.null(), { sessionId, cols, rows }),
+
+  /**
+   * Attach an output channel to a terminal session.
+   * Returns the raw bytes that arrived before the frontend attached, and
+   * switches the session to streaming mode: further output is sent over
+   * `onData` as binary frames (8-byte big-endian sequence number + bytes).
+   * Attaching again (e.g. after a remount) replaces the previous channel.
+   */
+  attachSessionOutput: (sessionId: string, onData: Channel<ArrayBuffer>) =>
+    invokeWithValidation('attach_session_output', z.instanceof(ArrayBuffer), {
+      sessionId,
+      onData,
+    }),
+
+  /**
+   * Pause a session's output while the terminal catches up: the backend
+   * stops reading, so the command producing it stalls instead of flooding
+   * the UI. Attaching a new output channel resumes it.
+   */
+  pauseOutput: (sessionId: string) =>
+    invokeWithValidation('pause_output', z.null(), { sessionId }),
+
+  /**
+   * Resume a session's output after pauseOutput
+   */
+  resumeOutput: (sessionId: string) =>
+    invokeWithValidation('resume_output', z.null(), { sessionId }),
+
+  /**
+   * Get the recent raw output of a session (last 10,000 lines, at most 4 MiB),
+   * to replay history into a fresh terminal after a frontend reload
+   */
+  getSessionScrollback: (sessionId: string) =>
+    invokeWithValidation('get_session_scrollback', z.instanceof(ArrayBuffer), { sessionId }),
+
+  /**
+   * Disconnect a terminal session
+   */
+  disconnectTerminal: (sessionId: string) =>
+    invokeWithValidation('disconnect_terminal', z.null(), { sessionId }),
+
+  /**
+   * Share a session as a read-only live view (requires the
+   * session_sharing_enabled setting; on the local network only with
+   * session_sharing_lan). Returns the one-time viewer URL.
+   */
+  shareSession: (sessionId: string) =>
+    invokeWithValidation('share_session', SessionShareSchema, { sessionId }),
+
+  /**
+   * Stop sharing a session (viewers are disconnected)
+   */
+  stopSharingSession: (sessionId: string) =>
+    invokeWithValidation('stop_sharing_session', z.null(), { sessionId }),
+
+  /**
+   * List shared sessions with their viewer counts
+   */
+  listSessionShares: () =>
+    invokeWithValidation('list_session_shares', z.array(SessionShareSchema)),
+
+  /**
+   * Start a local (-L), remote (-R) or dynamic SOCKS5 (-D) port forward.
+   * Traffic counters are reported with `port-forward:traffic` events. A
+   * remote forward the server refuses after a reconnect is stopped and
+   * reported with a `port-forward:failed` event.
+   */
+  startPortForward: (sessionId: string, spec: PortForwardSpec) =>
+    invokeWithValidation('start_port_forward', PortForwardSchema, { sessionId, spec }),
+
+  /**
+   * Stop a port forward (its open connections are closed)
+   */
+  stopPortForward: (forwardId: string) =>
+    invokeWithValidation('stop_port_forward', z.null(), { forwardId }),
+
+  /**
+   * List active port forwards (of one session, or all)
+   */
+  listPortForwards: (sessionId?: string) =>
+    invokeWithValidation('list_port_forwards', z.array(PortForwardSchema), {
+      sessionId: sessionId ?? null,
+    }),
+} as const;
+
+// SFTP Commands
+export const TauriSftp = {
+  /**
+   * Open an SFTP session on a saved SSH connection. Returns the session ID
+   * and the remote home directory.
+   */
+  openSession: (connectionId: string) =>
+    invokeWithValidation('open_sftp_session', SftpSessionInfoSchema, { connectionId }),
+
+  /**
+   * List a remote directory
+   */
+  listDir: (sessionId: string, path: string) =>
+    invokeWithValidation('sftp_list_dir', z.array(RemoteEntrySchema), { sessionId, path }),
+
+  /**
+   * Get a remote file's attributes (symlinks are not followed)
+   */
+  stat: (sessionId: string, path: string) =>
+    invokeWithValidation('sftp_stat', RemoteEntrySchema, { sessionId, path }),
+
+  /**
+   * Rename or move a remote file. On protected sessions, confirmHost must be
+   * the host name typed by the user.
+   */
+  rename: (sessionId: string, from: string, to: string, confirmHost?: string) =>
+    invokeWithValidation('sftp_rename', z.null(), { sessionId, from, to, confirmHost }),
+
+  /**
+   * Change a remote file's permission bits (e.g. 0o644). On protected
+   * sessions, confirmHost must be the host name typed by the user.
+   */
+  chmod: (sessionId: string, path: string, mode: number, confirmHost?: string) =>
+    invokeWithValidation('sftp_chmod', z.null(), { sessionId, path, mode, confirmHost }),
+
+  /**
+   * Delete a remote file or empty directory. On protected sessions,
+   * confirmHost must be the host name typed by the user.
+   */
+  delete: (sessionId: string, path: string, confirmHost?: string) =>
+    invokeWithValidation('sftp_delete', z.null(), { sessionId, path, confirmHost }),
+
+  /**
+   * Create a remote directory
+   */
+  mkdir: (sessionId: string, path: string) =>
+    invokeWithValidation('sftp_mkdir', z.null(), { sessionId, path }),
+
+  /**
+   * Read a remote symlink's target
+   */
+  readLink: (sessionId: string, path: string) =>
+    invokeWithValidation('sftp_read_link', StringSchema, { sessionId, path }),
+
+  /**
+   * Close an SFTP session (its transfers are stopped)
+   */
+  closeSession: (sessionId: string) =>
+    invokeWithValidation('close_sftp_session', z.null(), { sessionId }),
+
+  /**
+   * Queue an upload or download. Progress (bytes, rate, ETA) is reported
+   * with `transfer-progress` events carrying a TransferInfo.
+   */
+  queueTransfer: (sessionId: string, request: TransferRequest) =>
+    invokeWithValidation('queue_transfer', TransferInfoSchema, { sessionId, request }),
+
+  /**
+   * Pause a transfer (the partial file is kept)
+   */
+  pauseTransfer: (transferId: string) =>
+    invokeWithValidation('pause_transfer', z.null(), { transferId }),
+
+  /**
+   * Resume a paused or failed transfer from where it stopped. Transfers that
+   * aren't `resumable` (SCP, FTP), and those whose source file changed since
+   * they started, start over.
+   */
+  resumeTransfer: (transferId: string) =>
+    invokeWithValidation('resume_transfer', z.null(), { transferId }),
+
+  /**
+   * Cancel a transfer (the partial file is deleted) or clear a finished one
+   */
+  cancelTransfer: (transferId: string) =>
+    invokeWithValidation('cancel_transfer', z.null(), { transferId }),
+
+  /**
+   * List transfers (of one session, or all) in queue order
+   */
+  listTransfers: (sessionId?: string) =>
+    invokeWithValidation('list_transfers', z.array(TransferInfoSchema), {
+      sessionId: sessionId ?? null,
+    }),
+} as const;
+
+// Snippet Commands
+export const TauriSnippets = {
+  /**
+   * List command snippets (commands are decrypted: vault must be unlocked)
+   */
+  listSnippets: () => invokeWithValidation('list_snippets', z.array(SnippetSchema)),
+
+  /**
+   * Create a command snippet; `{{name}}` placeholders become variables
+   */
+  createSnippet: (input: { name: string; folder: string | null; command: string }) =>
+    invokeWithValidation('create_snippet', SnippetSchema, { input }),
+
+  /**
+   * Update a command snippet (omitted fields are unchanged, an empty folder clears it)
+   */
+  updateSnippet: (input: { id: string; name?: string; folder?: string; command?: string }) =>
+    invokeWithValidation('update_snippet', SnippetSchema, { input }),
+
+  /**
+   * Delete a command snippet
+   */
+  deleteSnippet: (id: string) => invokeWithValidation('delete_snippet', z.null(), { id }),
+
+  /**
+   * Variables a snippet needs, in order of appearance
+   */
+  getSnippetVariables: (id: string) =>
+    invokeWithValidation('get_snippet_variables', StringArraySchema, { id }),
+
+  /**
+   * Fill in variables and type the snippet into sessions (Enter pressed if `execute`)
+   */
+  runSnippet: (id: string, variables: Record<string, string>, sessionIds: string[], execute: boolean) =>
+    invokeWithValidation('run_snippet', z.array(SnippetDeliverySchema), {
+      id,
+      variables,
+      sessionIds,
+      execute,
+    }),
+} as const;
+
+// Remote Task Commands
+export const TauriRemoteTasks = {
+  /**
+   * Run a command on an SSH connection over an exec channel (no PTY); stdout and
+   * stderr are captured separately. A command still running after `timeoutSecs`
+   * (default 60, at most 3600) is stopped and reported with `timedOut`.
+   */
+  execCommand: (connectionId: string, command: string, timeoutSecs?: number) =>
+    invokeWithValidation('exec_command', ExecOutputSchema, {
+      connectionId,
+      command,
+      timeoutSecs,
+    }),
+
+  /**
+   * List remote tasks (commands are decrypted: vault must be unlocked)
+   */
+  listRemoteTasks: () => invokeWithValidation('list_remote_tasks', z.array(RemoteTaskSchema)),
+
+  /**
+   * Create a remote task; `schedule` is a 5-field cron expression (null = on demand only)
+   */
+  createRemoteTask: (input: {
+    name: string;
+    command: string;
+    connectionIds: string[];
+    schedule: string | null;
+    enabled?: boolean;
+  }) => invokeWithValidation('create_remote_task', RemoteTaskSchema, { input }),
+
+  /**
+   * Update a remote task (omitted fields are unchanged, an empty schedule clears it)
+   */
+  updateRemoteTask: (input: {
+    id: string;
+    name?: string;
+    command?: string;
+    connectionIds?: string[];
+    schedule?: string;
+    enabled?: boolean;
+  }) => invokeWithValidation('update_remote_task', RemoteTaskSchema, { input }),
+
+  /**
+   * Delete a remote task and its runs
+   */
+  deleteRemoteTask: (id: string) => invokeWithValidation('delete_remote_task', z.null(), { id }),
+
+  /**
+   * Run a task now on its connections; returns the connections it started on
+   * (each run ends with a `remote-task-completed` event)
+   */
+  runRemoteTask: (id: string) => invokeWithValidation('run_remote_task', StringArraySchema, { id }),
+
+  /**
+   * Latest runs of a task with their output, most recent first
+   */
+  listRemoteTaskRuns: (id: string, limit?: number) =>
+    invokeWithValidation('list_remote_task_runs', z.array(RemoteTaskRunSchema), { id, limit }),
+} as const;
+
+// Host Monitoring Commands
+export const TauriMonitoring = {
+  /**
+   * Uptime, load, memory, disks and logged-in users of SSH connections, in the
+   * order given (`refresh` probes the hosts, otherwise the last results are returned)
+   */
+  getHostMetrics: (connectionIds: string[], refresh: boolean) =>
+    invokeWithValidation('get_host_metrics', z.array(HostMetricsSchema), {
+      connectionIds,
+      refresh,
+    }),
+
+  /**
+   * Probe connections every `intervalSecs` seconds (10 minimum), each round ending
+   * with a `host-metrics` event; replaces the previous set of connections
+   */
+  startHostMonitoring: (connectionIds: string[], intervalSecs: number) =>
+    invokeWithValidation('start_host_monitoring', z.null(), { connectionIds, intervalSecs }),
+
+  /**
+   * Stop periodic monitoring
+   */
+  stopHostMonitoring: () => invokeWithValidation('stop_host_monitoring', z.null()),
+} as const;
+
+// Known Hosts Commands
+export const TauriKnownHosts = {
+  /**
+   * List the stored SSH host keys
+   */
+  listKnownHosts: () => invokeWithValidation('list_known_hosts', z.array(KnownHostSchema)),
+
+  /**
+   * Remove a known host (its key is asked about again on the next connection)
+   */
+  removeKnownHost: (id: string) => invokeWithValidation('remove_known_host', z.null(), { id }),
+
+  /**
+   * Accept the key a connection was rejected for (from ssh:host-key-changed or
+   * ssh:host-key-unknown); only stored if the server sent exactly this fingerprint.
+   * Replacing the key of a known host requires a capability token.
+   */
+  updateKnownHost: (host: string, port: number, fingerprint: string, token?: string) =>
+    invokeWithValidation('update_known_host', z.null(), {
+      host,
+      port,
+      fingerprint,
+      token: token ?? null,
+    }),
+
+  /**
+   * Known hosts in OpenSSH known_hosts format
+   */
+  exportKnownHosts: () => invokeWithValidation('export_known_hosts', z.string()),
+
+  /**
+   * Import an OpenSSH known_hosts file (hashed names are matched against saved
+   * connections; existing keys are never replaced)
+   */
+  importKnownHosts: (path: string) =>
+    invokeWithValidation('import_known_hosts', KnownHostsImportSchema, { path }),
+
+  /**
+   * Default OpenSSH known_hosts path (~/.ssh/known_hosts)
+   */
+  getDefaultKnownHostsPath: () => invokeWithValidation('get_default_known_hosts_path', z.string()),
+} as const;
+
+// Folder Commands
+export const TauriFolders = {
+  /**
+   * List all folders (flat, in display order)
+   */
+  listFolders: () => invokeWithValidation('list_folders', z.array(FolderSchema)),
+
+  /**
+   * Folder tree with paths and connection counts
+   */
+  getFolderTree: () => invokeWithValidation('get_folder_tree', z.array(FolderNodeSchema)),
+
+  /**
+   * Create a folder (no parent = top level); names are unique among siblings
+   */
+  createFolder: (input: { name: string; parentId?: string | null; color?: string | null }) =>
+    invokeWithValidation('create_folder', FolderSchema, { input }),
+
+  /**
+   * Rename, recolor or reorder a folder (omitted fields are unchanged, an
+   * empty color clears it)
+   */
+  updateFolder: (input: { id: string; name?: string; color?: string; sortOrder?: number }) =>
+    invokeWithValidation('update_folder', FolderSchema, { input }),
+
+  /**
+   * Move a folder under another one (null = top level), at `sortOrder` or last
+   */
+  moveFolder: (id: string, parentId: string | null, sortOrder?: number) =>
+    invokeWithValidation('move_folder', FolderSchema, { id, parentId, sortOrder }),
+
+  /**
+   * Delete a folder; its connections and subfolders move to `moveTo`
+   * (null = top level)
+   */
+  deleteFolder: (id: string, moveTo: string | null = null) =>
+    invokeWithValidation('delete_folder', z.null(), { id, moveTo }),
+} as const;
+
+// Tag Commands
+export const TauriTags = {
+  /**
+   * List all tags (by name) with their connection counts
+   */
+  listTags: () => invokeWithValidation('list_tags', z.array(TagSchema)),
+
+  /**
+   * Create a tag; names are unique (case-insensitive)
+   */
+  createTag: (input: { name: string; color?: string | null }) =>
+    invokeWithValidation('create_tag', TagSchema, { input }),
+
+  /**
+   * Rename or recolor a tag (omitted fields are unchanged, an empty color
+   * clears it)
+   */
+  updateTag: (input: { id: string; name?: string; color?: string }) =>
+    invokeWithValidation('update_tag', TagSchema, { input }),
+
+  /**
+   * Delete a tag (removed from its connections)
+   */
+  deleteTag: (id: string) => invokeWithValidation('delete_tag', z.null(), { id }),
+
+  /**
+   * Connections with all (`'all'`, AND) or any (`'any'`, OR) of the tags,
+   * e.g. `['prod', 'db']`
+   */
+  searchConnectionsByTags: (tags: string[], mode: 'all' | 'any' = 'all') =>
+    invokeWithValidation('search_connections_by_tags', ConnectionInfoArraySchema, {
+      tags,
+      mode,
+    }),
+} as const;
+
+// Background Task Commands
+export const TauriTasks = {
+  /**
+   * List background tasks with their state, last run and last error
+   */
+  listBackgroundTasks: () =>
+    invokeWithValidation('list_background_tasks', BackgroundTaskArraySchema),
+
+  /**
+   * Restart a background task by name
+   */
+  restartBackgroundTask: (name: string) =>
+    invokeWithValidation('restart_background_task', z.null(), { name }),
+} as const;
+
+// Session Restore Commands
+/** An open terminal, saved to be reopened on next launch */
+export interface SessionDescriptor {
+  sessionId: string;
+  connectionId: string;
+  title: string;
+  shell?: string;
+  cwd?: string;
+}
+
+export const TauriSessionRestore = {
+  /**
+   * Terminals open when the app last exited, offered for reopening
+   */
+  getRestorableSessions: () =>
+    invokeWithValidation('get_restorable_sessions', z.array(RestorableSessionSchema)),
+
+  /**
+   * Saved scrollback of a terminal of the last run (by index, requires unlock)
+   */
+  getRestoredScrollback: (index: number) =>
+    invokeWithValidation('get_restored_scrollback', z.instanceof(ArrayBuffer), { index }),
+
+  /**
+   * Report the open terminals, saved to be reopened on next launch
+   */
+  setOpenSessions: (sessions: SessionDescriptor[]) =>
+    invokeWithValidation('set_open_sessions', z.null(), { sessions }),
+
+  /**
+   * Report the working directory of a terminal (OSC 7 from the shell)
+   */
+  setSessionCwd: (sessionId: string, cwd: string | null) =>
+    invokeWithValidation('set_session_cwd', z.null(), { sessionId, cwd }),
 } as const;
 
 // ============================================================================
@@ -320,11 +1705,58 @@ export const Tauri = {
   Auth: TauriAuth,
   Settings: TauriSettings,
   Connections: TauriConnections,
+  Folders: TauriFolders,
+  Tags: TauriTags,
   Terminal: TauriTerminal,
+  Sftp: TauriSftp,
+  Snippets: TauriSnippets,
+  RemoteTasks: TauriRemoteTasks,
+  Monitoring: TauriMonitoring,
+  KnownHosts: TauriKnownHosts,
+  Tasks: TauriTasks,
+  SessionRestore: TauriSessionRestore,
 } as const;
 
 // Export types for external use
 export type UnlockResponse = z.infer<typeof UnlockResponseSchema>;
+export type VaultFormat = z.infer<typeof VaultFormatSchema>;
+export type BackupInfo = z.infer<typeof BackupInfoSchema>;
+export type KdfParams = z.infer<typeof KdfParamsSchema>;
+export type BackgroundTask = z.infer<typeof BackgroundTaskSchema>;
+export type RestorableSession = z.infer<typeof RestorableSessionSchema>;
+export type SessionShare = z.infer<typeof SessionShareSchema>;
+export type PortForwardSpec = z.infer<typeof PortForwardSpecSchema>;
+export type PortForward = z.infer<typeof PortForwardSchema>;
+export type RemoteEntry = z.infer<typeof RemoteEntrySchema>;
+export type SftpSessionInfo = z.infer<typeof SftpSessionInfoSchema>;
+export type TransferRequest = z.infer<typeof TransferRequestSchema>;
+export type TransferInfo = z.infer<typeof TransferInfoSchema>;
+export type RecordingOptions = z.infer<typeof RecordingOptionsSchema>;
+export type RecordingInfo = z.infer<typeof RecordingInfoSchema>;
+export type RedactedTranscript = z.infer<typeof RedactedTranscriptSchema>;
+export type Snippet = z.infer<typeof SnippetSchema>;
+export type SnippetDelivery = z.infer<typeof SnippetDeliverySchema>;
+export type SessionStats = z.infer<typeof SessionStatsSchema>;
+export type ExecOutput = z.infer<typeof ExecOutputSchema>;
+export type RemoteTask = z.infer<typeof RemoteTaskSchema>;
+export type RemoteTaskRun = z.infer<typeof RemoteTaskRunSchema>;
+export type HostMetrics = z.infer<typeof HostMetricsSchema>;
+export type KnownHost = z.infer<typeof KnownHostSchema>;
+export type KnownHostsImport = z.infer<typeof KnownHostsImportSchema>;
+export type CertificateInfo = z.infer<typeof CertificateInfoSchema>;
 export type ConnectionInfo = z.infer<typeof ConnectionInfoSchema>;
+export type UpdateConnectionError = z.infer<typeof UpdateConnectionErrorSchema>;
+export type ConnectionTestReport = z.infer<typeof ConnectionTestReportSchema>;
+export type Folder = z.infer<typeof FolderSchema>;
+export type FolderNode = FolderNodeShape;
+export type Tag = z.infer<typeof TagSchema>;
 export type PasswordStrength = z.infer<typeof PasswordStrengthSchema>;
 export type SshConfigEntry = z.infer<typeof SshConfigEntrySchema>;
+export type ConnectionTemplate = z.infer<typeof ConnectionTemplateSchema>;
+export type SessionRecord = z.infer<typeof SessionRecordSchema>;
+export type ConnectionStats = z.infer<typeof ConnectionStatsSchema>;
+export type ConnectionUsage = z.infer<typeof ConnectionUsageSchema>;
+export type JumpHost = z.infer<typeof JumpHostSchema>;
+export type SshConfigSyncSuggestion = z.infer<typeof SshConfigSyncSuggestionSchema>;
+export type ImportSource = z.infer<typeof ImportSourceSchema>;
+export type ImportEntry = z.infer<typeof ImportEntrySchema>;
@@ -0,0 +1,37 @@
+//! Fuzz EncryptedData deserialization and decryption
+//!
+//! Covers both the serialized container (JSON) and the database layout
+//! (12-byte nonce column + ciphertext blob) used for stored credentials.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rite_crypto::{decrypt, EncryptedData, MasterKey};
+use std::sync::OnceLock;
+
+fn key() -> &'static MasterKey {
+    // Argon2 is far too slow to run per input
+    static KEY: OnceLock<MasterKey> = OnceLock::new();
+    KEY.get_or_init(|| MasterKey::derive("fuzz", &[0u8; 16]).expect("key derivation"))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&mode, data)) = data.split_first() else {
+        return;
+    };
+
+    if mode % 2 == 0 {
+        if let Ok(encrypted) = serde_json::from_slice::<EncryptedData>(data) {
+            let _ = decrypt(key(), &encrypted);
+        }
+    } else if data.len() >= 12 {
+        let (nonce, ciphertext) = data.split_at(12);
+        let encrypted = EncryptedData {
+            data: ciphertext.to_vec(),
+            nonce: nonce.try_into().unwrap(),
+            salt: None,
+        };
+        // Forged ciphertexts must never authenticate
+        assert!(decrypt(key(), &encrypted).is_err());
+    }
+});
@@ -0,0 +1,25 @@
+//! Fuzz the session importers (PuTTY, Termius, mRemoteNG, SecureCRT)
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../apps/desktop/src-tauri/src/importers.rs"]
+mod importers;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let mut entries = importers::parse_putty_str("session", content);
+    entries.extend(importers::parse_termius_str(content).unwrap_or_default());
+    entries.extend(importers::parse_mremoteng_str(content).unwrap_or_default());
+    entries.extend(importers::parse_securecrt_session("session", None, content));
+
+    for entry in entries {
+        // Sessions without a host are never imported
+        assert!(!entry.hostname.is_empty());
+    }
+});
@@ -0,0 +1,19 @@
+//! Fuzz private key loading, including the PuTTY .ppk parser (v2 and v3)
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../apps/desktop/src-tauri/src/key_format.rs"]
+mod key_format;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = key_format::KeyFormat::detect(content);
+    let _ = key_format::decode_private_key(content, None);
+    let _ = key_format::decode_private_key(content, Some("correct horse"));
+});
@@ -0,0 +1,20 @@
+//! Fuzz the OpenSSH config parser (ssh_config import)
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../apps/desktop/src-tauri/src/ssh_config.rs"]
+mod ssh_config;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    for entry in ssh_config::parse_ssh_config_str(content) {
+        // Wildcard patterns are never imported
+        assert!(!entry.host.contains('*') && !entry.host.contains('?'));
+    }
+});
@@ -0,0 +1,21 @@
+//! Fuzz the theme TOML loader (user themes in ~/.config/rite/themes)
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../apps/desktop/src-tauri/src/theme.rs"]
+mod theme;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(parsed) = theme::parse_theme(content) {
+        // Whatever parses must survive a round-trip
+        let serialized = toml::to_string(&parsed).expect("parsed theme serializes");
+        theme::parse_theme(&serialized).expect("serialized theme parses");
+    }
+});
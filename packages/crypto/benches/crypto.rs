@@ -0,0 +1,43 @@
+//! Crypto benchmarks
+//!
+//! Key derivation runs once per unlock; encrypt/decrypt run for every stored
+//! credential, so both are tracked at typical credential and bulk sizes.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rite_crypto::{decrypt, encrypt, generate_salt, MasterKey};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+fn bench_derive(c: &mut Criterion) {
+    let salt = generate_salt();
+
+    let mut group = c.benchmark_group("derive");
+    // Argon2id with 64 MiB takes a noticeable fraction of a second
+    group.sample_size(10);
+    group.bench_function("argon2id", |b| {
+        b.iter(|| MasterKey::derive(black_box(PASSWORD), black_box(&salt)).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_encrypt_decrypt(c: &mut Criterion) {
+    let key = MasterKey::derive(PASSWORD, &generate_salt()).unwrap();
+
+    let mut group = c.benchmark_group("aead");
+    for size in [64usize, 4 * 1024, 1024 * 1024] {
+        let plaintext = vec![0x5au8; size];
+        let encrypted = encrypt(&key, &plaintext).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("encrypt", size), &plaintext, |b, p| {
+            b.iter(|| encrypt(&key, black_box(p)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decrypt", size), &encrypted, |b, e| {
+            b.iter(|| decrypt(&key, black_box(e)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_derive, bench_encrypt_decrypt);
+criterion_main!(benches);
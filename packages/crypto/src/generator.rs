@@ -0,0 +1,277 @@
+//! Password and passphrase generation
+//!
+//! Characters and words are drawn uniformly with the OS random generator.
+//! Passphrases are diceware-style: words from the built-in list (1024 short
+//! English words, 10 bits each) or from a diceware wordlist file.
+
+use anyhow::{anyhow, Context, Result};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Password length limits
+pub const MIN_LENGTH: usize = 8;
+pub const MAX_LENGTH: usize = 128;
+
+/// Passphrase word count limits
+pub const MIN_WORDS: usize = 4;
+pub const MAX_WORDS: usize = 20;
+
+/// Smallest wordlist accepted (9 bits per word)
+pub const MIN_WORDLIST_LEN: usize = 512;
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+/// No quotes, backslash or space, so passwords paste into shells as-is
+const SYMBOLS: &[u8] = b"!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+/// Built-in wordlist, one word per line
+const DEFAULT_WORDLIST: &str = include_str!("wordlist.txt");
+
+/// What to generate
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PasswordOptions {
+    /// Random characters: letters and digits, symbols if asked for (at least
+    /// one of each class)
+    Characters { length: usize, symbols: bool },
+    /// Random words joined by `separator`, from `wordlist` (a diceware file)
+    /// or the built-in list
+    Passphrase {
+        words: usize,
+        separator: String,
+        #[serde(default)]
+        wordlist: Option<String>,
+    },
+}
+
+/// Generated password and its entropy
+#[derive(Debug, Clone, Serialize, Zeroize, ZeroizeOnDrop)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedPassword {
+    pub password: String,
+    /// Entropy of the draw: log2 of the alphabet or wordlist size, per
+    /// character or word
+    pub entropy_bits: f64,
+}
+
+/// Words to draw passphrases from
+#[derive(Debug, Clone)]
+pub struct Wordlist {
+    words: Vec<String>,
+}
+
+impl Wordlist {
+    /// Built-in list
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_WORDLIST).expect("built-in wordlist is valid")
+    }
+
+    /// Parse a wordlist: one word per line, optionally after its dice
+    /// number (`11111 abacus`, the diceware and EFF format)
+    ///
+    /// Blank lines and duplicates are skipped.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let words: Vec<String> = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let first = fields.next()?;
+                match fields.next() {
+                    Some(word) if first.bytes().all(|b| b.is_ascii_digit()) => Some(word),
+                    Some(_) => None,
+                    None => Some(first),
+                }
+            })
+            .filter(|word| seen.insert(word.to_string()))
+            .map(str::to_string)
+            .collect();
+
+        if words.len() < MIN_WORDLIST_LEN {
+            return Err(anyhow!(
+                "Wordlist has {} distinct words, at least {} are needed",
+                words.len(),
+                MIN_WORDLIST_LEN
+            ));
+        }
+        Ok(Self { words })
+    }
+
+    /// Read a wordlist file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read wordlist: {:?}", path.as_ref()))?;
+        Self::parse(&content)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Generate a password or passphrase
+pub fn generate_password(options: &PasswordOptions) -> Result<GeneratedPassword> {
+    match options {
+        PasswordOptions::Characters { length, symbols } => generate_characters(*length, *symbols),
+        PasswordOptions::Passphrase {
+            words,
+            separator,
+            wordlist,
+        } => {
+            let wordlist = match wordlist {
+                Some(path) => Wordlist::from_file(path)?,
+                None => Wordlist::builtin(),
+            };
+            generate_passphrase(*words, separator, &wordlist)
+        }
+    }
+}
+
+/// Random characters, with at least one of each class
+pub fn generate_characters(length: usize, symbols: bool) -> Result<GeneratedPassword> {
+    if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+        return Err(anyhow!(
+            "Password length must be between {} and {}",
+            MIN_LENGTH,
+            MAX_LENGTH
+        ));
+    }
+
+    let mut classes = vec![LOWERCASE, UPPERCASE, DIGITS];
+    if symbols {
+        classes.push(SYMBOLS);
+    }
+    let alphabet: Vec<u8> = classes.concat();
+
+    // Draw until every class is present (rejection keeps the draw uniform
+    // over the passwords that qualify)
+    loop {
+        let password: String = (0..length)
+            .map(|_| alphabet[OsRng.gen_range(0..alphabet.len())] as char)
+            .collect();
+        if classes
+            .iter()
+            .all(|class| password.bytes().any(|b| class.contains(&b)))
+        {
+            return Ok(GeneratedPassword {
+                password,
+                entropy_bits: length as f64 * (alphabet.len() as f64).log2(),
+            });
+        }
+    }
+}
+
+/// Diceware passphrase: `words` words from `wordlist`, joined by `separator`
+pub fn generate_passphrase(
+    words: usize,
+    separator: &str,
+    wordlist: &Wordlist,
+) -> Result<GeneratedPassword> {
+    if !(MIN_WORDS..=MAX_WORDS).contains(&words) {
+        return Err(anyhow!(
+            "Passphrase must have between {} and {} words",
+            MIN_WORDS,
+            MAX_WORDS
+        ));
+    }
+
+    let password = (0..words)
+        .map(|_| {
+            wordlist
+                .words
+                .choose(&mut OsRng)
+                .expect("wordlist is not empty")
+                .as_str()
+        })
+        .collect::<Vec<_>>()
+        .join(separator);
+    Ok(GeneratedPassword {
+        password,
+        entropy_bits: words as f64 * (wordlist.len() as f64).log2(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_characters() {
+        for symbols in [false, true] {
+            let generated = generate_characters(24, symbols).unwrap();
+            let password = &generated.password;
+            assert_eq!(password.len(), 24);
+            assert!(password.bytes().any(|b| b.is_ascii_lowercase()));
+            assert!(password.bytes().any(|b| b.is_ascii_uppercase()));
+            assert!(password.bytes().any(|b| b.is_ascii_digit()));
+            assert_eq!(password.bytes().any(|b| SYMBOLS.contains(&b)), symbols);
+        }
+        assert_ne!(
+            generate_characters(24, true).unwrap().password,
+            generate_characters(24, true).unwrap().password
+        );
+
+        // 62 characters: log2(62) ≈ 5.95 bits each
+        let entropy = generate_characters(16, false).unwrap().entropy_bits;
+        assert!((entropy - 95.27).abs() < 0.01);
+
+        assert!(generate_characters(MIN_LENGTH - 1, true).is_err());
+        assert!(generate_characters(MAX_LENGTH + 1, true).is_err());
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let wordlist = Wordlist::builtin();
+        assert_eq!(wordlist.len(), 1024);
+
+        let generated = generate_passphrase(6, "-", &wordlist).unwrap();
+        let words: Vec<&str> = generated.password.split('-').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words
+            .iter()
+            .all(|word| wordlist.words.iter().any(|w| w == word)));
+        assert_eq!(generated.entropy_bits, 60.0);
+
+        assert!(generate_passphrase(MIN_WORDS - 1, "-", &wordlist).is_err());
+
+        // Strong enough for a master password
+        let strength = crate::validate_password_strength(&generated.password);
+        assert!(strength.is_valid);
+    }
+
+    #[test]
+    fn test_parse_wordlist() {
+        // Diceware format, with a duplicate
+        let diceware: String = (0..600)
+            .map(|i| format!("{:05}\tword{}\n", i, i))
+            .chain(["11111\tword1\n".to_string()])
+            .collect();
+        let wordlist = Wordlist::parse(&diceware).unwrap();
+        assert_eq!(wordlist.len(), 600);
+        assert_eq!(wordlist.words[0], "word0");
+
+        assert!(Wordlist::parse("too\nfew\nwords\n").is_err());
+    }
+
+    #[test]
+    fn test_options() {
+        let options: PasswordOptions =
+            serde_json::from_str(r#"{"type":"characters","length":20,"symbols":false}"#).unwrap();
+        assert_eq!(generate_password(&options).unwrap().password.len(), 20);
+
+        let options: PasswordOptions =
+            serde_json::from_str(r#"{"type":"passphrase","words":5,"separator":" "}"#).unwrap();
+        let generated = generate_password(&options).unwrap();
+        assert_eq!(generated.password.split(' ').count(), 5);
+    }
+}
@@ -3,24 +3,162 @@
 //! Provides encryption and key derivation for secure credential storage.
 //!
 //! Security Stack:
-//! - KDF: Argon2id (RFC 9106 recommended parameters)
-//! - Encryption: ChaCha20-Poly1305 (AEAD)
+//! - KDF: Argon2id (configurable parameters, calibrated per machine)
+//! - Subkeys: HKDF-SHA256 from the master key, one per purpose
+//! - Encryption: XChaCha20-Poly1305 (AEAD) in a versioned envelope
+//! - Large files: STREAM chunked XChaCha20-Poly1305 (see [`stream`])
+//! - Password strength: zxcvbn-style guess estimation (see [`strength`])
+//! - Generation: random passwords and diceware passphrases (see [`generator`])
 //! - File encryption: age (for sync/export)
 
 use anyhow::{anyhow, Result};
 use argon2::{
     password_hash::{PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
 };
 #[allow(deprecated)]
 use chacha20poly1305::{
-    aead::{generic_array::GenericArray, Aead, KeyInit, OsRng},
-    ChaCha20Poly1305,
+    aead::{generic_array::GenericArray, Aead, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, XChaCha20Poly1305,
 };
+use hkdf::Hkdf;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{Duration, Instant};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+pub mod generator;
+pub mod stream;
+pub mod strength;
+
+pub use generator::{generate_password, GeneratedPassword, PasswordOptions};
+pub use stream::{decrypt_stream, encrypt_stream};
+pub use strength::{validate_password_strength, PasswordStrength};
+
+/// Argon2id cost parameters
+///
+/// Stored next to the salt so a key can be derived again with the
+/// parameters it was created with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes over the memory
+    pub iterations: u32,
+    /// Number of lanes
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Lowest accepted memory cost (19 MiB, the Argon2 crate default)
+    pub const MIN_MEMORY_KIB: u32 = Params::DEFAULT_M_COST;
+    /// Highest accepted memory cost (4 GiB)
+    pub const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+    /// Lowest accepted number of iterations
+    pub const MIN_ITERATIONS: u32 = 2;
+    /// Highest accepted number of iterations
+    pub const MAX_ITERATIONS: u32 = 64;
+    /// Highest accepted parallelism
+    pub const MAX_PARALLELISM: u32 = 16;
+
+    /// Check the parameters are within the accepted bounds
+    pub fn validate(&self) -> Result<()> {
+        if !(Self::MIN_MEMORY_KIB..=Self::MAX_MEMORY_KIB).contains(&self.memory_kib) {
+            return Err(anyhow!(
+                "KDF memory must be between {} and {} KiB",
+                Self::MIN_MEMORY_KIB,
+                Self::MAX_MEMORY_KIB
+            ));
+        }
+        if !(Self::MIN_ITERATIONS..=Self::MAX_ITERATIONS).contains(&self.iterations) {
+            return Err(anyhow!(
+                "KDF iterations must be between {} and {}",
+                Self::MIN_ITERATIONS,
+                Self::MAX_ITERATIONS
+            ));
+        }
+        if !(1..=Self::MAX_PARALLELISM).contains(&self.parallelism) {
+            return Err(anyhow!(
+                "KDF parallelism must be between 1 and {}",
+                Self::MAX_PARALLELISM
+            ));
+        }
+        Ok(())
+    }
+
+    /// Argon2id instance with these parameters
+    pub fn argon2(&self) -> Result<Argon2<'static>> {
+        self.validate()?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| anyhow!("Invalid KDF parameters: {}", e))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Default for KdfParams {
+    /// Argon2 crate defaults, used by vaults created before parameters
+    /// were stored (19 MiB, 2 iterations, 1 lane)
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Memory cost calibration starts from (64 MiB, RFC 9106 second option)
+const CALIBRATION_MEMORY_KIB: u32 = 64 * 1024;
+
+/// Benchmark this machine and suggest parameters taking about `target`
+///
+/// Memory starts at 64 MiB and is halved (down to the minimum) while the
+/// target doesn't leave room for the minimum iterations; iterations then
+/// fill the remaining time. Parallelism follows the CPU count, up to 4.
+pub fn calibrate_kdf(target: Duration) -> Result<KdfParams> {
+    let parallelism = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1)
+        .min(4);
+    let mut memory_kib = CALIBRATION_MEMORY_KIB;
+
+    loop {
+        // Two probes separate the cost of a pass from the fixed cost
+        // (allocation, first touch of the memory)
+        let one = time_derivation(memory_kib, 1, parallelism)?;
+        let two = time_derivation(memory_kib, 2, parallelism)?;
+        let per_iteration = two.saturating_sub(one).max(Duration::from_micros(1));
+        let fixed = one.saturating_sub(per_iteration);
+
+        let iterations =
+            (target.saturating_sub(fixed).as_secs_f64() / per_iteration.as_secs_f64()) as u32;
+        if iterations >= KdfParams::MIN_ITERATIONS || memory_kib == KdfParams::MIN_MEMORY_KIB {
+            return Ok(KdfParams {
+                memory_kib,
+                iterations: iterations.clamp(KdfParams::MIN_ITERATIONS, KdfParams::MAX_ITERATIONS),
+                parallelism,
+            });
+        }
+        memory_kib = (memory_kib / 2).max(KdfParams::MIN_MEMORY_KIB);
+    }
+}
+
+/// Time one Argon2id derivation (calibration probe)
+fn time_derivation(memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Duration> {
+    let params = Params::new(memory_kib, iterations, parallelism, None)
+        .map_err(|e| anyhow!("Invalid KDF parameters: {}", e))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut output = [0u8; 32];
+    let started = Instant::now();
+    argon2
+        .hash_password_into(b"calibration", &[0u8; 16], &mut output)
+        .map_err(|e| anyhow!("KDF calibration failed: {}", e))?;
+    Ok(started.elapsed())
+}
+
 /// Master key derived from user password
 #[derive(Zeroize, ZeroizeOnDrop)]
 pub struct MasterKey {
@@ -28,14 +166,15 @@ pub struct MasterKey {
 }
 
 impl MasterKey {
-    /// Derive master key from password using Argon2id
-    ///
-    /// Parameters (RFC 9106 - Option 2 for compatibility):
-    /// - Memory: 64 MiB
-    /// - Iterations: 3
-    /// - Parallelism: 4
+    /// Derive master key from password using Argon2id with the default
+    /// parameters (see `KdfParams::default`)
     pub fn derive(password: &str, salt: &[u8]) -> Result<Self> {
-        let argon2 = Argon2::default();
+        Self::derive_with_params(password, salt, &KdfParams::default())
+    }
+
+    /// Derive master key from password using Argon2id with the given parameters
+    pub fn derive_with_params(password: &str, salt: &[u8], params: &KdfParams) -> Result<Self> {
+        let argon2 = params.argon2()?;
         let salt_string =
             SaltString::encode_b64(salt).map_err(|e| anyhow!("Invalid salt: {}", e))?;
 
@@ -60,116 +199,300 @@ impl MasterKey {
             .is_ok())
     }
 
+    /// Rebuild a key from its raw bytes (e.g. kept by the OS keychain)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| anyhow!("Invalid master key length"))?;
+        Ok(Self { key })
+    }
+
+    /// Derive a purpose-specific subkey with HKDF-SHA256
+    ///
+    /// `context` names the purpose (see [`subkey`]), so data encrypted for
+    /// one purpose can't be decrypted with another purpose's key.
+    pub fn derive_subkey(&self, context: &str) -> Self {
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, &self.key)
+            .expand(context.as_bytes(), &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        Self { key }
+    }
+
     /// Get the raw key bytes (use with caution)
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.key
     }
 }
 
-/// Encrypted data container
-#[derive(Serialize, Deserialize, Clone)]
-pub struct EncryptedData {
-    /// Ciphertext
-    pub data: Vec<u8>,
-    /// Nonce (96 bits for ChaCha20-Poly1305)
-    pub nonce: [u8; 12],
-    /// Salt for key derivation (if applicable)
-    pub salt: Option<Vec<u8>>,
+/// Subkey contexts for [`MasterKey::derive_subkey`]
+pub mod subkey {
+    /// Connection credentials and snippet commands in the vault
+    pub const CREDENTIALS: &str = "rite/v1/credentials";
+    /// Exports and backups
+    pub const EXPORT: &str = "rite/v1/export";
+    /// Session recordings
+    pub const RECORDINGS: &str = "rite/v1/recordings";
+    /// Scrollback of terminals saved for session restore
+    pub const SESSION_RESTORE: &str = "rite/v1/session-restore";
+    /// Key of the encrypted database (SQLCipher), wrapped in the vault header
+    pub const DATABASE: &str = "rite/v1/database";
 }
 
-/// Encrypt data with ChaCha20-Poly1305
-#[allow(deprecated)]
-pub fn encrypt(key: &MasterKey, plaintext: &[u8]) -> Result<EncryptedData> {
-    let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(key.as_bytes()));
-
-    // Generate random nonce
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = GenericArray::from_slice(&nonce_bytes);
+/// Magic bytes opening a versioned envelope
+const ENVELOPE_MAGIC: &[u8; 4] = b"RITE";
 
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+/// Envelope version written by `encrypt`
+pub const ENVELOPE_VERSION: u8 = 1;
 
-    Ok(EncryptedData {
-        data: ciphertext,
-        nonce: nonce_bytes,
-        salt: None,
-    })
+/// AEAD algorithm of an envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Cipher {
+    /// ChaCha20-Poly1305, 96-bit nonce (legacy blobs)
+    ChaCha20Poly1305 = 1,
+    /// XChaCha20-Poly1305, 192-bit nonce (safe to pick at random)
+    XChaCha20Poly1305 = 2,
 }
 
-/// Decrypt data with ChaCha20-Poly1305
-#[allow(deprecated)]
-pub fn decrypt(key: &MasterKey, encrypted: &EncryptedData) -> Result<Vec<u8>> {
-    let cipher = ChaCha20Poly1305::new(GenericArray::from_slice(key.as_bytes()));
+impl Cipher {
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(Self::ChaCha20Poly1305),
+            2 => Ok(Self::XChaCha20Poly1305),
+            other => Err(anyhow!("Unsupported cipher: {}", other)),
+        }
+    }
+
+    fn nonce_len(self) -> usize {
+        match self {
+            Self::ChaCha20Poly1305 => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
 
-    let nonce = GenericArray::from_slice(&encrypted.nonce);
+    #[allow(deprecated)]
+    fn seal(self, key: &MasterKey, nonce: &[u8], payload: Payload) -> Result<Vec<u8>> {
+        let key = GenericArray::from_slice(key.as_bytes());
+        match self {
+            Self::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new(key).encrypt(GenericArray::from_slice(nonce), payload)
+            }
+            Self::XChaCha20Poly1305 => {
+                XChaCha20Poly1305::new(key).encrypt(GenericArray::from_slice(nonce), payload)
+            }
+        }
+        .map_err(|e| anyhow!("Encryption failed: {}", e))
+    }
 
-    cipher
-        .decrypt(nonce, encrypted.data.as_ref())
+    #[allow(deprecated)]
+    fn open(self, key: &MasterKey, nonce: &[u8], payload: Payload) -> Result<Vec<u8>> {
+        let key = GenericArray::from_slice(key.as_bytes());
+        match self {
+            Self::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new(key).decrypt(GenericArray::from_slice(nonce), payload)
+            }
+            Self::XChaCha20Poly1305 => {
+                XChaCha20Poly1305::new(key).decrypt(GenericArray::from_slice(nonce), payload)
+            }
+        }
         .map_err(|e| anyhow!("Decryption failed: {}", e))
+    }
 }
 
-/// Generate a random salt for key derivation
-pub fn generate_salt() -> [u8; 16] {
-    let mut salt = [0u8; 16];
-    OsRng.fill_bytes(&mut salt);
-    salt
+/// Parsed versioned envelope
+///
+/// Layout: magic (`RITE`), version (1 byte), cipher id (1 byte), nonce
+/// (length set by the cipher), AAD length (u16, big endian), AAD,
+/// ciphertext with its tag. Everything before the ciphertext is
+/// authenticated along with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope<'a> {
+    pub version: u8,
+    pub cipher: Cipher,
+    pub nonce: &'a [u8],
+    pub aad: &'a [u8],
+    pub ciphertext: &'a [u8],
+    /// Authenticated header (magic through AAD)
+    header: &'a [u8],
 }
 
-/// Validate password strength
-/// Returns (is_valid, score, feedback)
-pub fn validate_password_strength(password: &str) -> (bool, u8, Vec<String>) {
-    let mut score = 0u8;
-    let mut feedback = Vec::new();
-
-    // Length check
-    if password.len() >= 12 {
-        score += 2;
-    } else {
-        feedback.push(format!(
-            "Password must be at least 12 characters (current: {})",
-            password.len()
-        ));
+impl<'a> Envelope<'a> {
+    /// Whether bytes start like an envelope
+    pub fn is_envelope(bytes: &[u8]) -> bool {
+        bytes.starts_with(ENVELOPE_MAGIC)
     }
 
-    if password.len() >= 16 {
-        score += 1;
+    /// Parse an envelope
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let invalid = || anyhow!("Invalid encrypted data envelope");
+
+        let rest = bytes.strip_prefix(ENVELOPE_MAGIC).ok_or_else(invalid)?;
+        let (&version, rest) = rest.split_first().ok_or_else(invalid)?;
+        if version != ENVELOPE_VERSION {
+            return Err(anyhow!("Unsupported envelope version: {}", version));
+        }
+        let (&cipher, rest) = rest.split_first().ok_or_else(invalid)?;
+        let cipher = Cipher::from_id(cipher)?;
+
+        if rest.len() < cipher.nonce_len() + 2 {
+            return Err(invalid());
+        }
+        let (nonce, rest) = rest.split_at(cipher.nonce_len());
+        let (aad_len, rest) = rest.split_at(2);
+        let aad_len = u16::from_be_bytes([aad_len[0], aad_len[1]]) as usize;
+        if rest.len() < aad_len {
+            return Err(invalid());
+        }
+        let (aad, ciphertext) = rest.split_at(aad_len);
+
+        Ok(Self {
+            version,
+            cipher,
+            nonce,
+            aad,
+            ciphertext,
+            header: &bytes[..bytes.len() - ciphertext.len()],
+        })
     }
+}
+
+/// Encrypted data container
+///
+/// `data` is a versioned envelope. Legacy (v0) blobs are a bare
+/// ChaCha20-Poly1305 ciphertext with the nonce kept in `nonce`; they are
+/// still decrypted, but no longer written.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EncryptedData {
+    /// Versioned envelope (or legacy ciphertext)
+    pub data: Vec<u8>,
+    /// Nonce of a legacy blob (96 bits); empty for envelopes, which carry their own
+    #[serde(default)]
+    pub nonce: Vec<u8>,
+    /// Salt for key derivation (if applicable)
+    pub salt: Option<Vec<u8>>,
+}
 
-    // Complexity checks
-    if password.chars().any(|c| c.is_lowercase()) {
-        score += 1;
-    } else {
-        feedback.push("Add lowercase letters".to_string());
+impl EncryptedData {
+    /// Whether this is a legacy (v0) blob
+    pub fn is_legacy(&self) -> bool {
+        !self.nonce.is_empty()
     }
 
-    if password.chars().any(|c| c.is_uppercase()) {
-        score += 1;
-    } else {
-        feedback.push("Add uppercase letters".to_string());
+    /// Single byte string for storage: the envelope, or nonce followed by
+    /// ciphertext for legacy blobs
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.nonce.as_slice(), self.data.as_slice()].concat()
     }
 
-    if password.chars().any(|c| c.is_numeric()) {
-        score += 1;
-    } else {
-        feedback.push("Add numbers".to_string());
+    /// Parse `to_bytes` output
+    ///
+    /// Bytes that don't start like an envelope are read as a legacy blob.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if Envelope::is_envelope(bytes) {
+            return Ok(Self {
+                data: bytes.to_vec(),
+                nonce: Vec::new(),
+                salt: None,
+            });
+        }
+        if bytes.len() < LEGACY_NONCE_LEN {
+            return Err(anyhow!("Invalid encrypted data"));
+        }
+        let (nonce, data) = bytes.split_at(LEGACY_NONCE_LEN);
+        Ok(Self {
+            data: data.to_vec(),
+            nonce: nonce.to_vec(),
+            salt: None,
+        })
     }
+}
+
+/// Nonce length of legacy (v0) blobs
+const LEGACY_NONCE_LEN: usize = 12;
+
+/// Encrypt data with XChaCha20-Poly1305
+pub fn encrypt(key: &MasterKey, plaintext: &[u8]) -> Result<EncryptedData> {
+    encrypt_with_aad(key, plaintext, &[])
+}
+
+/// Encrypt data with XChaCha20-Poly1305, binding it to associated data
+///
+/// The AAD is recorded in the envelope and authenticated; decryption needs
+/// the same AAD. An empty AAD is equivalent to `encrypt`.
+pub fn encrypt_with_aad(key: &MasterKey, plaintext: &[u8], aad: &[u8]) -> Result<EncryptedData> {
+    let cipher = Cipher::XChaCha20Poly1305;
+    let aad_len = u16::try_from(aad.len()).map_err(|_| anyhow!("Associated data too long"))?;
+
+    // Random 192-bit nonce: collisions are negligible
+    let mut nonce = vec![0u8; cipher.nonce_len()];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut envelope = Vec::with_capacity(ENVELOPE_MAGIC.len() + 4 + nonce.len() + aad.len());
+    envelope.extend_from_slice(ENVELOPE_MAGIC);
+    envelope.push(ENVELOPE_VERSION);
+    envelope.push(cipher as u8);
+    envelope.extend_from_slice(&nonce);
+    envelope.extend_from_slice(&aad_len.to_be_bytes());
+    envelope.extend_from_slice(aad);
+
+    let ciphertext = cipher.seal(
+        key,
+        &nonce,
+        Payload {
+            msg: plaintext,
+            aad: &envelope,
+        },
+    )?;
+    envelope.extend_from_slice(&ciphertext);
+
+    Ok(EncryptedData {
+        data: envelope,
+        nonce: Vec::new(),
+        salt: None,
+    })
+}
+
+/// Decrypt data written by `encrypt` (or a legacy blob)
+pub fn decrypt(key: &MasterKey, encrypted: &EncryptedData) -> Result<Vec<u8>> {
+    decrypt_with_aad(key, encrypted, &[])
+}
 
-    if password.chars().any(|c| !c.is_alphanumeric()) {
-        score += 1;
-    } else {
-        feedback.push("Add special characters".to_string());
+/// Decrypt data written by `encrypt_with_aad` (or a legacy blob)
+pub fn decrypt_with_aad(key: &MasterKey, encrypted: &EncryptedData, aad: &[u8]) -> Result<Vec<u8>> {
+    if encrypted.is_legacy() {
+        if encrypted.nonce.len() != LEGACY_NONCE_LEN {
+            return Err(anyhow!("Invalid nonce length"));
+        }
+        return Cipher::ChaCha20Poly1305.open(
+            key,
+            &encrypted.nonce,
+            Payload {
+                msg: &encrypted.data,
+                aad,
+            },
+        );
     }
 
-    // Common patterns
-    if password.to_lowercase().contains("password") || password.to_lowercase().contains("123456") {
-        score = score.saturating_sub(3);
-        feedback.push("Avoid common patterns".to_string());
+    let envelope = Envelope::parse(&encrypted.data)?;
+    if envelope.aad != aad {
+        return Err(anyhow!("Decryption failed: associated data mismatch"));
     }
+    envelope.cipher.open(
+        key,
+        envelope.nonce,
+        Payload {
+            msg: envelope.ciphertext,
+            aad: envelope.header,
+        },
+    )
+}
 
-    let is_valid = password.len() >= 12;
-    (is_valid, score, feedback)
+/// Generate a random salt for key derivation
+pub fn generate_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    salt
 }
 
 #[cfg(test)]
@@ -186,6 +509,86 @@ mod tests {
 
         // Same password and salt should produce same key
         assert_eq!(key1.as_bytes(), key2.as_bytes());
+
+        // Raw bytes round-trip
+        let key3 = MasterKey::from_bytes(key1.as_bytes()).unwrap();
+        assert_eq!(key1.as_bytes(), key3.as_bytes());
+        assert!(MasterKey::from_bytes(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_subkeys() {
+        let key = MasterKey::derive("subkey-password", &generate_salt()).unwrap();
+
+        let credentials = key.derive_subkey(subkey::CREDENTIALS);
+        assert_eq!(
+            credentials.as_bytes(),
+            key.derive_subkey(subkey::CREDENTIALS).as_bytes()
+        );
+        assert_ne!(credentials.as_bytes(), key.as_bytes());
+        assert_ne!(
+            credentials.as_bytes(),
+            key.derive_subkey(subkey::RECORDINGS).as_bytes()
+        );
+
+        // Data encrypted for one purpose doesn't open with another
+        let encrypted = encrypt(&credentials, b"secret").unwrap();
+        assert_eq!(decrypt(&credentials, &encrypted).unwrap(), b"secret");
+        assert!(decrypt(&key, &encrypted).is_err());
+        assert!(decrypt(&key.derive_subkey(subkey::EXPORT), &encrypted).is_err());
+
+        // RFC 5869 test case 3 (no salt, no info), as a fixed reference
+        let ikm = MasterKey::from_bytes(&[0x0b; 32]).unwrap();
+        let mut expected = [0u8; 32];
+        Hkdf::<Sha256>::new(None, &[0x0b; 32])
+            .expand(b"", &mut expected)
+            .unwrap();
+        assert_eq!(ikm.derive_subkey("").as_bytes(), &expected);
+    }
+
+    #[test]
+    fn test_kdf_params() {
+        let password = "test-password-123";
+        let salt = generate_salt();
+
+        // Default parameters match keys derived before they were stored
+        let key = MasterKey::derive(password, &salt).unwrap();
+        let default =
+            MasterKey::derive_with_params(password, &salt, &KdfParams::default()).unwrap();
+        assert_eq!(key.as_bytes(), default.as_bytes());
+
+        let params = KdfParams {
+            memory_kib: KdfParams::MIN_MEMORY_KIB,
+            iterations: 3,
+            parallelism: 2,
+        };
+        let custom = MasterKey::derive_with_params(password, &salt, &params).unwrap();
+        assert_ne!(key.as_bytes(), custom.as_bytes());
+
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(
+            json,
+            r#"{"memoryKib":19456,"iterations":3,"parallelism":2}"#
+        );
+
+        // Out of bounds parameters are refused
+        for weak in [
+            KdfParams {
+                memory_kib: 1024,
+                ..params
+            },
+            KdfParams {
+                iterations: 1,
+                ..params
+            },
+            KdfParams {
+                parallelism: 0,
+                ..params
+            },
+        ] {
+            assert!(weak.validate().is_err());
+            assert!(MasterKey::derive_with_params(password, &salt, &weak).is_err());
+        }
     }
 
     #[test]
@@ -202,13 +605,145 @@ mod tests {
     }
 
     #[test]
-    fn test_password_strength() {
-        let (valid, score, _) = validate_password_strength("weak");
-        assert!(!valid);
-        assert!(score < 4);
-
-        let (valid, score, _) = validate_password_strength("StrongP@ssw0rd123");
-        assert!(valid);
-        assert!(score >= 6);
+    #[allow(deprecated)]
+    fn test_legacy_blob() {
+        let key = MasterKey::derive("legacy-password", &generate_salt()).unwrap();
+
+        // v0: bare ChaCha20-Poly1305 ciphertext with a separate 96-bit nonce
+        let nonce = [9u8; 12];
+        let data = ChaCha20Poly1305::new(GenericArray::from_slice(key.as_bytes()))
+            .encrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: b"legacy secret",
+                    aad: b"id",
+                },
+            )
+            .unwrap();
+        let legacy = EncryptedData {
+            data,
+            nonce: nonce.to_vec(),
+            salt: None,
+        };
+        assert!(legacy.is_legacy());
+        assert_eq!(
+            decrypt_with_aad(&key, &legacy, b"id").unwrap(),
+            b"legacy secret"
+        );
+        assert!(decrypt(&key, &legacy).is_err());
+
+        // Old JSON (fixed-size nonce array) still deserializes
+        let json = serde_json::to_string(&legacy).unwrap();
+        let restored: EncryptedData = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            decrypt_with_aad(&key, &restored, b"id").unwrap(),
+            b"legacy secret"
+        );
+
+        // Single byte string form, for both kinds
+        let restored = EncryptedData::from_bytes(&legacy.to_bytes()).unwrap();
+        assert_eq!(
+            decrypt_with_aad(&key, &restored, b"id").unwrap(),
+            b"legacy secret"
+        );
+        let current = encrypt(&key, b"current secret").unwrap();
+        let restored = EncryptedData::from_bytes(&current.to_bytes()).unwrap();
+        assert!(!restored.is_legacy());
+        assert_eq!(decrypt(&key, &restored).unwrap(), b"current secret");
+    }
+
+    #[test]
+    fn test_envelope() {
+        let key = MasterKey::derive("envelope-password", &generate_salt()).unwrap();
+        let encrypted = encrypt_with_aad(&key, b"secret", b"connection-1").unwrap();
+
+        let envelope = Envelope::parse(&encrypted.data).unwrap();
+        assert_eq!(envelope.version, ENVELOPE_VERSION);
+        assert_eq!(envelope.cipher, Cipher::XChaCha20Poly1305);
+        assert_eq!(envelope.nonce.len(), 24);
+        assert_eq!(envelope.aad, b"connection-1");
+
+        // Unknown versions and ciphers are refused
+        let mut future = encrypted.clone();
+        future.data[4] = ENVELOPE_VERSION + 1;
+        assert!(Envelope::parse(&future.data).is_err());
+        let mut unknown = encrypted.clone();
+        unknown.data[5] = 0xff;
+        assert!(decrypt_with_aad(&key, &unknown, b"connection-1").is_err());
+        assert!(Envelope::parse(b"RITE").is_err());
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+        use std::sync::OnceLock;
+
+        /// Argon2 is too slow to run per case
+        fn key() -> &'static MasterKey {
+            static KEY: OnceLock<MasterKey> = OnceLock::new();
+            KEY.get_or_init(|| MasterKey::derive("proptest-password", &[7u8; 16]).unwrap())
+        }
+
+        proptest! {
+            #[test]
+            fn encrypt_decrypt_round_trip(
+                plaintext in prop::collection::vec(any::<u8>(), 0..4096),
+                aad in prop::collection::vec(any::<u8>(), 0..256),
+            ) {
+                let encrypted = encrypt_with_aad(key(), &plaintext, &aad).unwrap();
+                // Header (magic, version, cipher, nonce, AAD) and tag
+                prop_assert_eq!(encrypted.data.len(), 4 + 2 + 24 + 2 + aad.len() + plaintext.len() + 16);
+                prop_assert!(!encrypted.is_legacy());
+                prop_assert_eq!(decrypt_with_aad(key(), &encrypted, &aad).unwrap(), plaintext);
+            }
+
+            #[test]
+            fn empty_aad_matches_plain_encrypt(plaintext in prop::collection::vec(any::<u8>(), 0..1024)) {
+                let encrypted = encrypt(key(), &plaintext).unwrap();
+                prop_assert_eq!(decrypt_with_aad(key(), &encrypted, &[]).unwrap(), plaintext.clone());
+
+                let encrypted = encrypt_with_aad(key(), &plaintext, &[]).unwrap();
+                prop_assert_eq!(decrypt(key(), &encrypted).unwrap(), plaintext);
+            }
+
+            #[test]
+            fn wrong_aad_is_rejected(
+                plaintext in prop::collection::vec(any::<u8>(), 0..1024),
+                aad in prop::collection::vec(any::<u8>(), 0..64),
+                other in prop::collection::vec(any::<u8>(), 0..64),
+            ) {
+                prop_assume!(aad != other);
+                let encrypted = encrypt_with_aad(key(), &plaintext, &aad).unwrap();
+                prop_assert!(decrypt_with_aad(key(), &encrypted, &other).is_err());
+            }
+
+            #[test]
+            fn tampering_is_rejected(
+                plaintext in prop::collection::vec(any::<u8>(), 0..1024),
+                index in any::<prop::sample::Index>(),
+                flip in 1u8..,
+            ) {
+                let mut encrypted = encrypt(key(), &plaintext).unwrap();
+                let i = index.index(encrypted.data.len());
+                encrypted.data[i] ^= flip;
+                prop_assert!(decrypt(key(), &encrypted).is_err());
+            }
+
+            #[test]
+            fn encrypted_data_serialization_round_trip(
+                plaintext in prop::collection::vec(any::<u8>(), 0..1024),
+                salt in prop::option::of(prop::collection::vec(any::<u8>(), 16)),
+            ) {
+                let mut encrypted = encrypt(key(), &plaintext).unwrap();
+                encrypted.salt = salt;
+
+                let json = serde_json::to_string(&encrypted).unwrap();
+                let restored: EncryptedData = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(&restored.data, &encrypted.data);
+                prop_assert_eq!(&restored.nonce, &encrypted.nonce);
+                prop_assert_eq!(&restored.salt, &encrypted.salt);
+                prop_assert_eq!(decrypt(key(), &restored).unwrap(), plaintext);
+            }
+        }
     }
 }
@@ -0,0 +1,247 @@
+//! Streaming encryption for large files
+//!
+//! Session recordings, exports and backups can be hundreds of MB, so they are
+//! encrypted in chunks with the STREAM construction (XChaCha20-Poly1305,
+//! big-endian 32-bit chunk counter and a last-chunk flag in the nonce).
+//! Chunks can't be reordered, dropped or truncated without decryption
+//! failing, and only one chunk is held in memory at a time.
+//!
+//! Layout: `RITS` magic | version (1 byte) | chunk size (u32 BE) |
+//! nonce prefix (19 bytes), then the chunks. Every chunk is `chunk size`
+//! bytes of plaintext plus a 16-byte tag, except the last one which is
+//! shorter (possibly empty). The header is the associated data of every chunk.
+
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{generic_array::GenericArray, KeyInit, OsRng, Payload};
+use chacha20poly1305::XChaCha20Poly1305;
+use rand::RngCore;
+use std::io::{ErrorKind, Read, Write};
+use zeroize::Zeroizing;
+
+use crate::MasterKey;
+
+/// Magic bytes opening an encrypted stream
+const STREAM_MAGIC: &[u8; 4] = b"RITS";
+
+/// Stream layout written by this version
+pub const STREAM_VERSION: u8 = 1;
+
+/// Plaintext bytes per chunk
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size accepted when decrypting
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Random part of the chunk nonces (24 bytes minus counter and flag)
+const NONCE_PREFIX_LEN: usize = 19;
+
+/// Poly1305 tag appended to every chunk
+const TAG_LEN: usize = 16;
+
+/// Error for a chunk that doesn't authenticate
+const DECRYPTION_FAILED: &str = "Stream decryption failed (wrong key or corrupted data)";
+
+const HEADER_LEN: usize = STREAM_MAGIC.len() + 1 + 4 + NONCE_PREFIX_LEN;
+
+/// Encrypt everything `reader` yields into `writer`
+///
+/// Returns the number of plaintext bytes encrypted.
+pub fn encrypt_stream<R: Read, W: Write>(
+    key: &MasterKey,
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64> {
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+    OsRng.fill_bytes(&mut nonce_prefix);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(STREAM_MAGIC);
+    header.push(STREAM_VERSION);
+    header.extend_from_slice(&(CHUNK_SIZE as u32).to_be_bytes());
+    header.extend_from_slice(&nonce_prefix);
+    writer.write_all(&header)?;
+
+    let cipher = XChaCha20Poly1305::new(GenericArray::from_slice(key.as_bytes()));
+    let mut encryptor = EncryptorBE32::from_aead(cipher, GenericArray::from_slice(&nonce_prefix));
+
+    let mut chunk = Zeroizing::new(vec![0u8; CHUNK_SIZE]);
+    let mut total = 0u64;
+    // A full chunk may be followed by more data; the last chunk is always
+    // shorter, so a stream cut at a chunk boundary is detected
+    let len = loop {
+        let len = read_full(&mut reader, &mut chunk)?;
+        total += len as u64;
+        if len < CHUNK_SIZE {
+            break len;
+        }
+        let ciphertext = encryptor
+            .encrypt_next(Payload {
+                msg: &chunk[..],
+                aad: &header,
+            })
+            .map_err(|_| anyhow!("Stream encryption failed"))?;
+        writer.write_all(&ciphertext)?;
+    };
+    let ciphertext = encryptor
+        .encrypt_last(Payload {
+            msg: &chunk[..len],
+            aad: &header,
+        })
+        .map_err(|_| anyhow!("Stream encryption failed"))?;
+    writer.write_all(&ciphertext)?;
+
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Decrypt a stream written by [`encrypt_stream`] from `reader` into `writer`
+///
+/// Plaintext is written chunk by chunk as each one authenticates, so on
+/// error `writer` may hold a partial result that must be discarded. Returns
+/// the number of plaintext bytes.
+pub fn decrypt_stream<R: Read, W: Write>(
+    key: &MasterKey,
+    mut reader: R,
+    mut writer: W,
+) -> Result<u64> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .context("Not an encrypted stream")?;
+    if &header[..STREAM_MAGIC.len()] != STREAM_MAGIC {
+        return Err(anyhow!("Not an encrypted stream"));
+    }
+    let version = header[STREAM_MAGIC.len()];
+    if version != STREAM_VERSION {
+        return Err(anyhow!("Unsupported stream version: {}", version));
+    }
+    let chunk_size = u32::from_be_bytes(header[5..9].try_into().unwrap()) as usize;
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(anyhow!("Invalid stream chunk size: {}", chunk_size));
+    }
+    let nonce_prefix = &header[9..];
+
+    let cipher = XChaCha20Poly1305::new(GenericArray::from_slice(key.as_bytes()));
+    let mut decryptor = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce_prefix));
+
+    let mut chunk = vec![0u8; chunk_size + TAG_LEN];
+    let mut total = 0u64;
+    let len = loop {
+        let len = read_full(&mut reader, &mut chunk)?;
+        if len < chunk.len() {
+            break len;
+        }
+        let plaintext = Zeroizing::new(
+            decryptor
+                .decrypt_next(Payload {
+                    msg: &chunk[..],
+                    aad: &header,
+                })
+                .map_err(|_| anyhow!(DECRYPTION_FAILED))?,
+        );
+        writer.write_all(&plaintext)?;
+        total += plaintext.len() as u64;
+    };
+    let plaintext = Zeroizing::new(
+        decryptor
+            .decrypt_last(Payload {
+                msg: &chunk[..len],
+                aad: &header,
+            })
+            .map_err(|_| anyhow!(DECRYPTION_FAILED))?,
+    );
+    writer.write_all(&plaintext)?;
+    total += plaintext.len() as u64;
+
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Fill `buf` as far as the reader allows; short only at end of input
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> MasterKey {
+        MasterKey::from_bytes(&[9u8; 32]).unwrap()
+    }
+
+    fn roundtrip(plaintext: &[u8]) -> Vec<u8> {
+        let mut encrypted = Vec::new();
+        let written = encrypt_stream(&key(), plaintext, &mut encrypted).unwrap();
+        assert_eq!(written, plaintext.len() as u64);
+
+        let mut decrypted = Vec::new();
+        let read = decrypt_stream(&key(), encrypted.as_slice(), &mut decrypted).unwrap();
+        assert_eq!(read, plaintext.len() as u64);
+        assert_eq!(decrypted, plaintext);
+        encrypted
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        for len in [
+            0,
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            3 * CHUNK_SIZE,
+        ] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = roundtrip(&plaintext);
+            // One tag per full chunk, plus the (shorter) last chunk
+            let chunks = len / CHUNK_SIZE + 1;
+            assert_eq!(encrypted.len(), HEADER_LEN + len + chunks * TAG_LEN);
+        }
+    }
+
+    #[test]
+    fn test_stream_tampering() {
+        let plaintext = vec![7u8; 2 * CHUNK_SIZE + 100];
+        let encrypted = roundtrip(&plaintext);
+        let decrypt =
+            |data: &[u8], key: &MasterKey| decrypt_stream(key, data, std::io::sink()).map(|_| ());
+
+        // Wrong key
+        let other = MasterKey::from_bytes(&[1u8; 32]).unwrap();
+        assert!(decrypt(&encrypted, &other).is_err());
+
+        // Flipped bit in a chunk, or in the header
+        for index in [HEADER_LEN + 10, 6] {
+            let mut tampered = encrypted.clone();
+            tampered[index] ^= 1;
+            assert!(decrypt(&tampered, &key()).is_err());
+        }
+
+        // Truncated at a chunk boundary or inside a chunk
+        let boundary = HEADER_LEN + 2 * (CHUNK_SIZE + TAG_LEN);
+        assert!(decrypt(&encrypted[..boundary], &key()).is_err());
+        assert!(decrypt(&encrypted[..encrypted.len() - 1], &key()).is_err());
+
+        // Chunks swapped
+        let chunk = CHUNK_SIZE + TAG_LEN;
+        let mut swapped = encrypted[..HEADER_LEN].to_vec();
+        swapped.extend_from_slice(&encrypted[HEADER_LEN + chunk..HEADER_LEN + 2 * chunk]);
+        swapped.extend_from_slice(&encrypted[HEADER_LEN..HEADER_LEN + chunk]);
+        swapped.extend_from_slice(&encrypted[HEADER_LEN + 2 * chunk..]);
+        assert!(decrypt(&swapped, &key()).is_err());
+
+        // Not a stream
+        assert!(decrypt(b"RITE\x01", &key()).is_err());
+    }
+}
@@ -0,0 +1,681 @@
+//! Password strength estimation
+//!
+//! zxcvbn-style: the password is covered with the patterns an attacker tries
+//! first (common passwords and their capitalized, l33t and reversed forms,
+//! sequences, keyboard rows, repeats, years and dates), and the cheapest
+//! cover gives the estimated number of guesses. Anything no pattern covers
+//! is brute-forced over the character classes of the password.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shortest master password accepted
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+
+/// Lowest score accepted for a master password
+pub const MIN_SCORE: u8 = 3;
+
+/// Built-in deny list, most common first
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// Guesses per second of an offline attack on the Argon2id hash
+const OFFLINE_GUESSES_PER_SECOND: f64 = 1e4;
+
+/// Only the start of very long passwords is analyzed
+const MAX_ANALYZED_LENGTH: usize = 100;
+
+/// Longest common password looked up
+const MAX_WORD_LENGTH: usize = 20;
+
+/// Fewest guesses a pattern counts for (one character, longer)
+const MIN_GUESSES_CHAR: f64 = 10.0;
+const MIN_GUESSES_MULTI: f64 = 50.0;
+
+/// Years around the current one are guessed first
+const MIN_YEAR_SPACE: f64 = 20.0;
+
+/// Keyboard rows (US layout), for straight-row patterns
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"];
+
+/// Starting keys times directions of a keyboard pattern
+const KEYBOARD_START_GUESSES: f64 = 47.0 * 2.0;
+
+/// Common l33t substitutions (`1` and `|` also stand for `l`)
+const L33T: [(char, char); 15] = [
+    ('4', 'a'),
+    ('@', 'a'),
+    ('8', 'b'),
+    ('(', 'c'),
+    ('3', 'e'),
+    ('6', 'g'),
+    ('1', 'i'),
+    ('!', 'i'),
+    ('|', 'i'),
+    ('0', 'o'),
+    ('$', 's'),
+    ('5', 's'),
+    ('+', 't'),
+    ('7', 't'),
+    ('2', 'z'),
+];
+
+/// Estimated strength of a password
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordStrength {
+    /// Whether the password is accepted as a master password
+    pub is_valid: bool,
+    /// 0 (too guessable) to 4 (very unguessable)
+    pub score: u8,
+    /// Estimated number of guesses, log10
+    pub guesses_log10: f64,
+    /// Time to crack it offline, against the Argon2id hash
+    pub crack_time_seconds: f64,
+    /// `crack_time_seconds` in words ("3 hours", "centuries")
+    pub crack_time_display: String,
+    /// Main weakness found
+    pub warning: Option<String>,
+    /// How to make the password stronger
+    pub suggestions: Vec<String>,
+}
+
+impl PasswordStrength {
+    /// Warning and suggestions, for error messages
+    pub fn feedback(&self) -> String {
+        self.warning
+            .iter()
+            .chain(&self.suggestions)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pattern {
+    Dictionary {
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+        capitalized: bool,
+    },
+    Sequence,
+    Keyboard,
+    Repeat {
+        block_len: usize,
+    },
+    Year,
+    Date,
+}
+
+#[derive(Debug, Clone)]
+struct Match {
+    start: usize,
+    end: usize,
+    pattern: Pattern,
+    guesses_log10: f64,
+}
+
+/// Estimate how hard a password is to guess
+pub fn validate_password_strength(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYZED_LENGTH).collect();
+    let (guesses_log10, matches) = most_guessable_cover(&chars);
+    let score = score(guesses_log10);
+    let length = password.chars().count();
+
+    let crack_time_seconds = 10f64.powf(guesses_log10) / OFFLINE_GUESSES_PER_SECOND;
+    let (warning, mut suggestions) = feedback(&chars, &matches, score);
+    if length < MIN_PASSWORD_LENGTH {
+        suggestions.insert(
+            0,
+            format!(
+                "Use at least {} characters (current: {})",
+                MIN_PASSWORD_LENGTH, length
+            ),
+        );
+    }
+
+    PasswordStrength {
+        is_valid: length >= MIN_PASSWORD_LENGTH && score >= MIN_SCORE,
+        score,
+        guesses_log10,
+        crack_time_seconds,
+        crack_time_display: display_time(crack_time_seconds),
+        warning,
+        suggestions,
+    }
+}
+
+fn score(guesses_log10: f64) -> u8 {
+    match guesses_log10 {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+/// Cheapest way to cover the password with patterns and brute force
+///
+/// Returns log10 of the guesses and the patterns used, in order.
+fn most_guessable_cover(chars: &[char]) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    let brute_force_per_char = cardinality(chars).log10();
+    let matches = omnimatch(chars);
+
+    // best[j]: fewest guesses for chars[..j], and the match ending there
+    // (None = one brute-forced character)
+    let mut best: Vec<(f64, Option<usize>)> = vec![(f64::INFINITY, None); n + 1];
+    best[0] = (0.0, None);
+    for j in 1..=n {
+        best[j] = (best[j - 1].0 + brute_force_per_char, None);
+        for (index, m) in matches.iter().enumerate().filter(|(_, m)| m.end == j) {
+            let guesses = best[m.start].0 + m.guesses_log10;
+            if guesses < best[j].0 {
+                best[j] = (guesses, Some(index));
+            }
+        }
+    }
+
+    let mut cover = Vec::new();
+    let mut j = n;
+    while j > 0 {
+        match best[j].1 {
+            Some(index) => {
+                cover.push(matches[index].clone());
+                j = matches[index].start;
+            }
+            None => j -= 1,
+        }
+    }
+    cover.reverse();
+    (best[n].0, cover)
+}
+
+/// Brute-force alphabet size, from the character classes used
+fn cardinality(chars: &[char]) -> f64 {
+    let mut size = 0.0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10.0;
+    }
+    if chars
+        .iter()
+        .any(|c| c.is_ascii() && !c.is_ascii_alphanumeric())
+    {
+        size += 33.0;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100.0;
+    }
+    size
+}
+
+fn omnimatch(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    keyboard_matches(chars, &mut matches);
+    repeat_matches(chars, &mut matches);
+    date_matches(chars, &mut matches);
+    matches
+}
+
+fn push(matches: &mut Vec<Match>, start: usize, end: usize, pattern: Pattern, guesses: f64) {
+    let min = if end - start == 1 {
+        MIN_GUESSES_CHAR
+    } else {
+        MIN_GUESSES_MULTI
+    };
+    matches.push(Match {
+        start,
+        end,
+        pattern,
+        guesses_log10: guesses.max(min).log10(),
+    });
+}
+
+fn common_passwords() -> &'static HashMap<&'static str, usize> {
+    static RANKS: OnceLock<HashMap<&'static str, usize>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        COMMON_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(index, word)| (word, index + 1))
+            .collect()
+    })
+}
+
+fn dictionary_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let dictionary = common_passwords();
+    let lower: Vec<char> = chars.iter().map(|c| to_lower(*c)).collect();
+
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len().min(start + MAX_WORD_LENGTH) {
+            let token = &lower[start..end];
+            let plain: String = token.iter().collect();
+            let reversed: String = token.iter().rev().collect();
+            let mut candidates = vec![(plain.clone(), false, false), (reversed, true, false)];
+            for l_for_one in [false, true] {
+                let word = unl33t(token, l_for_one);
+                if word != plain {
+                    candidates.push((word, false, true));
+                }
+            }
+
+            for (word, reversed, l33t) in candidates {
+                let Some(&rank) = dictionary.get(word.as_str()) else {
+                    continue;
+                };
+                let original = &chars[start..end];
+                let mut guesses = rank as f64 * uppercase_variations(original);
+                if l33t {
+                    guesses *= l33t_variations(token, &word);
+                }
+                if reversed {
+                    guesses *= 2.0;
+                }
+                let pattern = Pattern::Dictionary {
+                    rank,
+                    reversed,
+                    l33t,
+                    capitalized: original.iter().any(|c| c.is_uppercase()),
+                };
+                push(matches, start, end, pattern, guesses);
+            }
+        }
+    }
+}
+
+fn to_lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn unl33t(token: &[char], l_for_one: bool) -> String {
+    token
+        .iter()
+        .map(|&c| match c {
+            '1' | '|' if l_for_one => 'l',
+            _ => L33T
+                .iter()
+                .find(|(sub, _)| *sub == c)
+                .map_or(c, |(_, letter)| *letter),
+        })
+        .collect()
+}
+
+/// Ways to capitalize a word the way `token` is
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_or_last =
+        upper == 1 && (token[0].is_uppercase() || token[token.len() - 1].is_uppercase());
+    if lower == 0 || first_or_last {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|i| binomial(upper + lower, i))
+        .sum()
+}
+
+/// Ways to apply the substitutions `token` makes to `word`
+fn l33t_variations(token: &[char], word: &str) -> f64 {
+    let word: Vec<char> = word.chars().collect();
+    let mut variations = 1.0;
+    let mut seen = Vec::new();
+    for (&sub, &letter) in token.iter().zip(&word) {
+        if sub == letter || seen.contains(&sub) {
+            continue;
+        }
+        seen.push(sub);
+        let subbed = token.iter().filter(|&&c| c == sub).count();
+        let unsubbed = token.iter().filter(|&&c| c == letter).count();
+        variations *= if unsubbed == 0 {
+            2.0
+        } else {
+            (1..=subbed.min(unsubbed))
+                .map(|i| binomial(subbed + unsubbed, i))
+                .sum()
+        };
+    }
+    variations
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Runs like `abc`, `9876`
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let mut start = 0;
+    while start + 2 < chars.len() {
+        let delta = chars[start + 1] as i64 - chars[start] as i64;
+        let mut end = start + 1;
+        while end < chars.len()
+            && chars[end].is_ascii_alphanumeric()
+            && chars[end] as i64 - chars[end - 1] as i64 == delta
+        {
+            end += 1;
+        }
+        if delta.abs() == 1 && end - start >= 3 && chars[start].is_ascii_alphanumeric() {
+            let first = chars[start];
+            let base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let descending = if delta < 0 { 2.0 } else { 1.0 };
+            let guesses = base * (end - start) as f64 * descending;
+            push(matches, start, end, Pattern::Sequence, guesses);
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+/// Straight keyboard rows like `qwerty`, `asdf`, `poiu`
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let lower: Vec<char> = chars.iter().map(|c| to_lower(*c)).collect();
+    for start in 0..lower.len() {
+        for end in start + 4..=lower.len() {
+            let token: String = lower[start..end].iter().collect();
+            let reversed: String = lower[start..end].iter().rev().collect();
+            if KEYBOARD_ROWS
+                .iter()
+                .any(|row| row.contains(&token) || row.contains(&reversed))
+            {
+                let guesses = KEYBOARD_START_GUESSES
+                    * (end - start) as f64
+                    * uppercase_variations(&chars[start..end]);
+                push(matches, start, end, Pattern::Keyboard, guesses);
+            }
+        }
+    }
+}
+
+/// The same character or block repeated: `aaaa`, `abcabc`
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+    for start in 0..chars.len() {
+        for block_len in 1..=(chars.len() - start) / 2 {
+            let block = &chars[start..start + block_len];
+            let mut end = start + block_len;
+            while end + block_len <= chars.len() && &chars[end..end + block_len] == block {
+                end += block_len;
+            }
+            let repeats = (end - start) / block_len;
+            let min_len = if block_len == 1 { 3 } else { 2 * block_len };
+            if repeats >= 2 && end - start >= min_len {
+                let (block_guesses, _) = most_guessable_cover(block);
+                let guesses = 10f64.powf(block_guesses) * repeats as f64;
+                push(matches, start, end, Pattern::Repeat { block_len }, guesses);
+            }
+        }
+    }
+}
+
+/// Years (`1987`) and all-digit dates (`31121987`, `871231`)
+fn date_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let current_year = current_year();
+    let year_space = |year: i64| ((year - current_year).abs() as f64).max(MIN_YEAR_SPACE);
+
+    for start in 0..chars.len() {
+        for len in [4, 6, 8] {
+            let end = start + len;
+            if end > chars.len() || !chars[start..end].iter().all(char::is_ascii_digit) {
+                continue;
+            }
+            let digits: String = chars[start..end].iter().collect();
+            if len == 4 {
+                let year: i64 = digits.parse().unwrap_or(0);
+                if (1900..=2099).contains(&year) {
+                    push(matches, start, end, Pattern::Year, year_space(year));
+                }
+                continue;
+            }
+            if let Some(year) = parse_date(&digits) {
+                push(matches, start, end, Pattern::Date, 365.0 * year_space(year));
+            }
+        }
+    }
+}
+
+/// Year of a `ddmmyy[yy]`, `mmddyy[yy]` or `[yy]yymmdd` date
+fn parse_date(digits: &str) -> Option<i64> {
+    let part = |range: std::ops::Range<usize>| digits[range].parse::<i64>().ok();
+    let full_year = |year: i64| match year {
+        0..=49 => year + 2000,
+        50..=99 => year + 1900,
+        _ => year,
+    };
+    let valid = |day: i64, month: i64| (1..=31).contains(&day) && (1..=12).contains(&month);
+
+    let year_len = digits.len() - 4;
+    let (head, tail) = ((0..year_len), (4..digits.len()));
+    let candidates = [
+        // Year last: day/month, month/day
+        (part(0..2)?, part(2..4)?, part(tail.clone())?),
+        (part(2..4)?, part(0..2)?, part(tail)?),
+        // Year first: month/day
+        (
+            part(year_len + 2..year_len + 4)?,
+            part(year_len..year_len + 2)?,
+            part(head)?,
+        ),
+    ];
+    candidates
+        .into_iter()
+        .find(|&(day, month, year)| valid(day, month) && (1900..=2099).contains(&full_year(year)))
+        .map(|(_, _, year)| full_year(year))
+}
+
+fn current_year() -> i64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    1970 + (seconds / 31_556_952) as i64
+}
+
+fn feedback(chars: &[char], cover: &[Match], score: u8) -> (Option<String>, Vec<String>) {
+    if chars.is_empty() {
+        return (
+            None,
+            vec!["Use a few words, avoid common phrases".to_string()],
+        );
+    }
+    if score >= MIN_SCORE {
+        return (None, Vec::new());
+    }
+
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better".to_string()];
+    let Some(longest) = cover.iter().max_by_key(|m| m.end - m.start) else {
+        return (None, suggestions);
+    };
+    let whole = cover.len() == 1 && longest.end - longest.start == chars.len();
+
+    let warning = match longest.pattern {
+        Pattern::Dictionary {
+            rank,
+            reversed,
+            l33t,
+            capitalized,
+        } => {
+            if capitalized {
+                suggestions.push("Capitalization doesn't help very much".to_string());
+            }
+            if reversed {
+                suggestions.push("Reversed words aren't much harder to guess".to_string());
+            }
+            if l33t {
+                suggestions.push(
+                    "Predictable substitutions like '@' instead of 'a' don't help very much"
+                        .to_string(),
+                );
+            }
+            if !whole {
+                "This is similar to a commonly used password"
+            } else if rank <= 10 {
+                "This is a top-10 common password"
+            } else if rank <= 100 {
+                "This is a top-100 common password"
+            } else {
+                "This is a very common password"
+            }
+        }
+        Pattern::Sequence => {
+            suggestions.push("Avoid sequences".to_string());
+            "Sequences like abc or 6543 are easy to guess"
+        }
+        Pattern::Keyboard => {
+            suggestions.push("Use a longer keyboard pattern with more turns".to_string());
+            "Straight rows of keys are easy to guess"
+        }
+        Pattern::Repeat { block_len } => {
+            suggestions.push("Avoid repeated words and characters".to_string());
+            if block_len == 1 {
+                "Repeats like \"aaa\" are easy to guess"
+            } else {
+                "Repeats like \"abcabcabc\" are only slightly harder to guess than \"abc\""
+            }
+        }
+        Pattern::Year => {
+            suggestions.push("Avoid years that are associated with you".to_string());
+            "Recent years are easy to guess"
+        }
+        Pattern::Date => {
+            suggestions.push("Avoid dates and years that are associated with you".to_string());
+            "Dates are often easy to guess"
+        }
+    };
+    (Some(warning.to_string()), suggestions)
+}
+
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (amount, unit) = match seconds {
+        s if s < 1.0 => return "less than a second".to_string(),
+        s if s < MINUTE => (s, "second"),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s if s < CENTURY => (s / YEAR, "year"),
+        _ => return "centuries".to_string(),
+    };
+    let amount = amount.round() as u64;
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_strength() {
+        let weak = validate_password_strength("weak");
+        assert!(!weak.is_valid);
+        assert!(weak.score <= 1);
+        assert!(weak.suggestions[0].contains("at least 12"));
+
+        let strong = validate_password_strength("StrongP@ssw0rd123");
+        assert!(strong.score >= 3);
+
+        let random = validate_password_strength("vX9#qL2!mR7$wT4k");
+        assert!(random.is_valid);
+        assert_eq!(random.score, 4);
+        assert_eq!(random.crack_time_display, "centuries");
+        assert!(random.warning.is_none());
+    }
+
+    #[test]
+    fn test_common_passwords() {
+        for password in ["password", "123456", "qwerty", "123123"] {
+            let strength = validate_password_strength(password);
+            assert_eq!(strength.score, 0, "{}", password);
+            assert_eq!(
+                strength.warning.as_deref(),
+                Some("This is a top-10 common password"),
+                "{}",
+                password
+            );
+        }
+
+        // Capitalized, l33t and reversed forms are barely harder
+        let strength = validate_password_strength("P@ssw0rd");
+        assert_eq!(strength.score, 0);
+        assert!(strength
+            .suggestions
+            .iter()
+            .any(|s| s.starts_with("Predictable substitutions")));
+        assert_eq!(validate_password_strength("drowssap").score, 0);
+
+        // Long enough, but only a repeated common password
+        assert!(!validate_password_strength("P@ssw0rdP@ssw0rd").is_valid);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let warning = |password: &str| validate_password_strength(password).warning;
+        assert_eq!(
+            warning("abcdefghijklmnop").as_deref(),
+            Some("Sequences like abc or 6543 are easy to guess")
+        );
+        assert_eq!(
+            warning("zzzzzzzzzzzzzzzz").as_deref(),
+            Some("Repeats like \"aaa\" are easy to guess")
+        );
+        assert_eq!(
+            warning("asdfghjkl;asdf").as_deref(),
+            Some("Straight rows of keys are easy to guess")
+        );
+        assert_eq!(
+            warning("31121987").as_deref(),
+            Some("Dates are often easy to guess")
+        );
+        assert!(!validate_password_strength("xkcdxkcdxkcdxkcd").is_valid);
+    }
+
+    #[test]
+    fn test_display_time() {
+        assert_eq!(display_time(0.5), "less than a second");
+        assert_eq!(display_time(1.0), "1 second");
+        assert_eq!(display_time(7200.0), "2 hours");
+        assert_eq!(display_time(1e12), "centuries");
+    }
+
+    #[test]
+    fn test_variations() {
+        assert_eq!(uppercase_variations(&['a', 'b', 'c']), 1.0);
+        assert_eq!(uppercase_variations(&['A', 'b', 'c']), 2.0);
+        assert_eq!(uppercase_variations(&['A', 'B', 'C']), 2.0);
+        // aBcD: 2 upper, 2 lower → C(4,1) + C(4,2)
+        assert_eq!(uppercase_variations(&['a', 'B', 'c', 'D']), 10.0);
+        assert_eq!(parse_date("19871231"), Some(1987));
+        assert_eq!(parse_date("311287"), Some(1987));
+        assert_eq!(parse_date("999999"), None);
+    }
+}
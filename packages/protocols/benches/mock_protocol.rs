@@ -0,0 +1,167 @@
+//! Protocol trait benchmarks on an in-memory mock
+//!
+//! Measures the per-call overhead of the async `Protocol` / `TerminalProtocol`
+//! interface (boxed futures, Vec allocation per receive) independently of
+//! any network. Run with `cargo bench -p rite-protocols`.
+
+use async_trait::async_trait;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rite_protocols::{
+    AuthMethod, ConnectionConfig, Protocol, ProtocolError, ProtocolType, Result, TerminalProtocol,
+};
+use std::collections::VecDeque;
+
+/// Output pumped through the mock per iteration
+const PUMP_BYTES: usize = 100 * 1024 * 1024;
+
+/// Chunk size of one receive (a full SSH channel data packet)
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// Protocol that echoes sent data and produces synthetic terminal output
+struct MockProtocol {
+    connected: bool,
+    chunk: Vec<u8>,
+    remaining: usize,
+    echo: VecDeque<Vec<u8>>,
+}
+
+impl MockProtocol {
+    fn new(output_bytes: usize) -> Self {
+        // Colored `ls -l`-style lines, like real heavy output
+        let line =
+            b"\x1b[0m-rw-r--r-- 1 user user  4096 Jan  1 12:00 \x1b[01;34mfile.txt\x1b[0m\r\n";
+        let chunk = line.iter().copied().cycle().take(CHUNK_SIZE).collect();
+        Self {
+            connected: false,
+            chunk,
+            remaining: output_bytes,
+            echo: VecDeque::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Protocol for MockProtocol {
+    fn protocol_type(&self) -> ProtocolType {
+        ProtocolType::Local
+    }
+
+    async fn connect(&mut self, _config: &ConnectionConfig) -> Result<()> {
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn send(&mut self, data: &[u8]) -> Result<()> {
+        if !self.connected {
+            return Err(ProtocolError::NotConnected);
+        }
+        self.echo.push_back(data.to_vec());
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        if !self.connected {
+            return Err(ProtocolError::NotConnected);
+        }
+        if let Some(echo) = self.echo.pop_front() {
+            return Ok(echo);
+        }
+        let len = self.remaining.min(self.chunk.len());
+        self.remaining -= len;
+        Ok(self.chunk[..len].to_vec())
+    }
+}
+
+#[async_trait]
+impl TerminalProtocol for MockProtocol {
+    async fn request_pty(&mut self, _term: &str, _width: u32, _height: u32) -> Result<()> {
+        Ok(())
+    }
+
+    async fn resize_pty(&mut self, _width: u32, _height: u32) -> Result<()> {
+        Ok(())
+    }
+
+    async fn exec(&mut self, _command: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn shell(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn config() -> ConnectionConfig {
+    ConnectionConfig {
+        protocol: ProtocolType::Local,
+        hostname: "localhost".to_string(),
+        port: 0,
+        username: "bench".to_string(),
+        auth: AuthMethod::Agent,
+        jump_host: None,
+        timeout: None,
+        keepalive: None,
+    }
+}
+
+async fn open(output_bytes: usize) -> Box<dyn TerminalProtocol> {
+    let mut protocol: Box<dyn TerminalProtocol> = Box::new(MockProtocol::new(output_bytes));
+    protocol.connect(&config()).await.unwrap();
+    protocol
+        .request_pty("xterm-256color", 80, 24)
+        .await
+        .unwrap();
+    protocol.shell().await.unwrap();
+    protocol
+}
+
+fn bench_output_pump(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("mock_protocol");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(PUMP_BYTES as u64));
+    group.bench_function("receive_100mb", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                let mut protocol = open(PUMP_BYTES).await;
+                let mut total = 0;
+                loop {
+                    let data = protocol.receive().await.unwrap();
+                    if data.is_empty() {
+                        break;
+                    }
+                    total += black_box(data).len();
+                }
+                assert_eq!(total, PUMP_BYTES);
+            })
+        })
+    });
+    group.finish();
+}
+
+fn bench_keystroke_echo(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut protocol = runtime.block_on(open(0));
+
+    c.bench_function("mock_protocol/keystroke_echo", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                protocol.send(black_box(b"a")).await.unwrap();
+                black_box(protocol.receive().await.unwrap());
+            })
+        })
+    });
+}
+
+criterion_group!(benches, bench_output_pump, bench_keystroke_echo);
+criterion_main!(benches);
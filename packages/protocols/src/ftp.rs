@@ -0,0 +1,588 @@
+//! FTP/FTPS Protocol Implementation
+//!
+//! Plain FTP and explicit FTPS (`AUTH TLS` after connecting, server
+//! certificate checked against the webpki roots) via suppaftp. Data
+//! connections use passive mode; listings use MLSD, whose facts don't depend
+//! on the server's `ls` output format.
+//!
+//! suppaftp's client is blocking: commands run on tokio's blocking pool, one
+//! at a time (FTP has a single control connection). A transfer that must not
+//! hold up browsing needs a client of its own.
+
+use crate::{
+    AuthMethod, ConnectionConfig, FileEntry, FileTransferProtocol, ProgressCallback, Protocol,
+    ProtocolError, ProtocolType, Result, TransferProgress,
+};
+use async_trait::async_trait;
+use std::io::{Read, Write};
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use suppaftp::types::{FileType, Mode};
+use suppaftp::{FtpError, RustlsConnector, RustlsFtpStream, Status};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Default FTP control port (explicit FTPS uses it too)
+pub const DEFAULT_PORT: u16 = 21;
+
+/// Chunk size for streamed transfers
+const TRANSFER_CHUNK_SIZE: usize = 32 * 1024;
+
+/// Connect timeout when the configuration has none
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// File type bits of a mode (as in SFTP attributes)
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// FTP client, optionally over TLS
+pub struct FtpClient {
+    /// Explicit FTPS: the control and data connections are upgraded to TLS
+    tls: bool,
+    stream: Option<RustlsFtpStream>,
+}
+
+impl FtpClient {
+    /// Plain FTP client (credentials and data travel in clear text)
+    pub fn new() -> Self {
+        Self {
+            tls: false,
+            stream: None,
+        }
+    }
+
+    /// Explicit FTPS client
+    pub fn secure() -> Self {
+        Self {
+            tls: true,
+            stream: None,
+        }
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.tls
+    }
+
+    /// Current directory (the login directory right after connecting)
+    pub async fn pwd(&mut self) -> Result<String> {
+        self.run(|ftp| ftp.pwd().map_err(|e| map_ftp_error("", e)))
+            .await
+    }
+
+    /// Attributes of one file or directory (MLST)
+    pub async fn stat(&mut self, path: &str) -> Result<FileEntry> {
+        let path = path.to_string();
+        self.run(move |ftp| {
+            let line = ftp.mlst(Some(&path)).map_err(|e| map_ftp_error(&path, e))?;
+            let name = path
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .unwrap_or("/");
+            let (facts, _) = split_mlsx(line.trim_start()).ok_or_else(|| invalid_listing(&line))?;
+            Ok(file_entry(path.clone(), name.to_string(), facts))
+        })
+        .await
+    }
+
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        info!("Renaming {} -> {}", from, to);
+        let (from, to) = (from.to_string(), to.to_string());
+        self.run(move |ftp| ftp.rename(&from, &to).map_err(|e| map_ftp_error(&from, e)))
+            .await
+    }
+
+    /// Run a blocking command on the control connection
+    async fn run<T, F>(&mut self, op: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut RustlsFtpStream) -> Result<T> + Send + 'static,
+    {
+        let mut stream = self.stream.take().ok_or(ProtocolError::NotConnected)?;
+        let (stream, result) = tokio::task::spawn_blocking(move || {
+            let result = op(&mut stream);
+            (stream, result)
+        })
+        .await
+        .map_err(|e| ProtocolError::ProtocolError(format!("FTP task failed: {}", e)))?;
+        self.stream = Some(stream);
+        result
+    }
+
+    /// Run a blocking transfer, forwarding its progress reports
+    async fn transfer<F>(&mut self, op: F, progress: ProgressCallback<'_>) -> Result<()>
+    where
+        F: FnOnce(&mut RustlsFtpStream, &dyn Fn(TransferProgress)) -> Result<()> + Send + 'static,
+    {
+        let (reports, mut received) = mpsc::unbounded_channel();
+        let run = self.run(move |ftp| {
+            op(ftp, &|report| {
+                let _ = reports.send(report);
+            })
+        });
+        tokio::pin!(run);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                Some(report) = received.recv() => progress(report),
+            }
+        };
+        while let Ok(report) = received.try_recv() {
+            progress(report);
+        }
+        result
+    }
+}
+
+impl Default for FtpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Protocol for FtpClient {
+    fn protocol_type(&self) -> ProtocolType {
+        ProtocolType::Ftp
+    }
+
+    async fn connect(&mut self, config: &ConnectionConfig) -> Result<()> {
+        let password = match &config.auth {
+            AuthMethod::Password { password } => password.clone(),
+            _ => {
+                return Err(ProtocolError::AuthenticationFailed(
+                    "FTP supports password authentication only".to_string(),
+                ))
+            }
+        };
+        let hostname = config.hostname.clone();
+        let port = config.port;
+        let username = config.username.clone();
+        let timeout = config
+            .timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT);
+        let tls = self.tls;
+        info!(
+            "Connecting to {}:{} ({})",
+            hostname,
+            port,
+            if tls { "FTPS" } else { "FTP" }
+        );
+
+        let stream = tokio::task::spawn_blocking(move || {
+            open(&hostname, port, &username, &password, timeout, tls)
+        })
+        .await
+        .map_err(|e| ProtocolError::ConnectionFailed(format!("FTP task failed: {}", e)))??;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        if self.stream.is_some() {
+            let result = self.run(|ftp| ftp.quit().map_err(|e| map_ftp_error("", e)));
+            if let Err(e) = result.await {
+                warn!("FTP QUIT failed: {}", e);
+            }
+            self.stream = None;
+        }
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    async fn send(&mut self, _data: &[u8]) -> Result<()> {
+        Err(ProtocolError::ProtocolError(
+            "Raw send is not supported over FTP".to_string(),
+        ))
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        Err(ProtocolError::ProtocolError(
+            "Raw receive is not supported over FTP".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl FileTransferProtocol for FtpClient {
+    async fn list_dir(&mut self, path: &str) -> Result<Vec<FileEntry>> {
+        debug!("Listing directory: {}", path);
+        let path = path.to_string();
+        let lines = self
+            .run({
+                let path = path.clone();
+                move |ftp| ftp.mlsd(Some(&path)).map_err(|e| map_ftp_error(&path, e))
+            })
+            .await?;
+
+        let mut listing = Vec::with_capacity(lines.len());
+        for line in &lines {
+            if let Some(entry) = parse_mlsd_line(&path, line)? {
+                listing.push(entry);
+            }
+        }
+        // Directories first, then by name
+        listing.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(listing)
+    }
+
+    async fn download_with_progress(
+        &mut self,
+        remote_path: &str,
+        local_path: &Path,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        info!("Downloading {} -> {:?} (FTP)", remote_path, local_path);
+        let remote = remote_path.to_string();
+        let local = local_path.to_path_buf();
+        self.transfer(
+            move |ftp, report| {
+                // SIZE is optional: without it progress has no total
+                let total = ftp.size(&remote).ok().map(|size| size as u64);
+                let mut data = ftp
+                    .retr_as_stream(&remote)
+                    .map_err(|e| map_ftp_error(&remote, e))?;
+                let mut file = std::fs::File::create(&local)?;
+                let received = copy(&mut data, &mut file, total, report)?;
+                ftp.finalize_retr_stream(data)
+                    .map_err(|e| map_ftp_error(&remote, e))?;
+
+                debug!("Downloaded {} bytes", received);
+                Ok(())
+            },
+            progress,
+        )
+        .await
+    }
+
+    async fn upload_with_progress(
+        &mut self,
+        local_path: &Path,
+        remote_path: &str,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        info!("Uploading {:?} -> {} (FTP)", local_path, remote_path);
+        let remote = remote_path.to_string();
+        let local = local_path.to_path_buf();
+        self.transfer(
+            move |ftp, report| {
+                let mut file = std::fs::File::open(&local)?;
+                let total = file.metadata()?.len();
+                let mut data = ftp
+                    .put_with_stream(&remote)
+                    .map_err(|e| map_ftp_error(&remote, e))?;
+                let sent = copy(&mut file, &mut data, Some(total), report)?;
+                ftp.finalize_put_stream(data)
+                    .map_err(|e| map_ftp_error(&remote, e))?;
+
+                debug!("Uploaded {} bytes", sent);
+                Ok(())
+            },
+            progress,
+        )
+        .await
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<()> {
+        warn!("Deleting: {}", path);
+        let is_dir = self.stat(path).await?.is_dir;
+        let path = path.to_string();
+        self.run(move |ftp| {
+            let result = if is_dir {
+                ftp.rmdir(&path)
+            } else {
+                ftp.rm(&path)
+            };
+            result.map_err(|e| map_ftp_error(&path, e))
+        })
+        .await
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<()> {
+        info!("Creating directory: {}", path);
+        let path = path.to_string();
+        self.run(move |ftp| ftp.mkdir(&path).map_err(|e| map_ftp_error(&path, e)))
+            .await
+    }
+}
+
+/// Connect, upgrade to TLS if asked, log in and switch to binary passive mode
+fn open(
+    hostname: &str,
+    port: u16,
+    username: &str,
+    password: &str,
+    timeout: Duration,
+    tls: bool,
+) -> Result<RustlsFtpStream> {
+    let address = (hostname, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| ProtocolError::ConnectionFailed(format!("Cannot resolve {}", hostname)))?;
+    let mut ftp = RustlsFtpStream::connect_timeout(address, timeout)
+        .map_err(|e| ProtocolError::ConnectionFailed(e.to_string()))?;
+    if tls {
+        ftp = ftp.into_secure(tls_connector()?, hostname).map_err(|e| {
+            ProtocolError::ConnectionFailed(format!("TLS negotiation failed: {}", e))
+        })?;
+    }
+    ftp.login(username, password)
+        .map_err(|e| ProtocolError::AuthenticationFailed(e.to_string()))?;
+    ftp.set_mode(Mode::Passive);
+    ftp.transfer_type(FileType::Binary)
+        .map_err(|e| map_ftp_error("", e))?;
+
+    debug!(
+        "FTP session ready ({})",
+        if tls { "TLS" } else { "clear text" }
+    );
+    Ok(ftp)
+}
+
+/// TLS connector verifying the server against the webpki root certificates
+fn tls_connector() -> Result<RustlsConnector> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| ProtocolError::ConnectionFailed(format!("TLS setup failed: {}", e)))?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(RustlsConnector::from(Arc::new(config)))
+}
+
+/// Copy a data stream, reporting progress after every chunk
+///
+/// Returns the number of bytes copied.
+fn copy<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    total: Option<u64>,
+    report: &dyn Fn(TransferProgress),
+) -> std::io::Result<u64> {
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE];
+    let mut transferred = 0u64;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        transferred += read as u64;
+        report(TransferProgress { transferred, total });
+    }
+    writer.flush()?;
+    Ok(transferred)
+}
+
+/// Map an FTP error to a `ProtocolError` (with the path it concerns)
+fn map_ftp_error(path: &str, error: FtpError) -> ProtocolError {
+    match error {
+        FtpError::ConnectionError(e) => ProtocolError::IoError(e),
+        FtpError::UnexpectedResponse(response) => {
+            let message = String::from_utf8_lossy(&response.body).trim().to_string();
+            match response.status {
+                Status::NotLoggedIn => ProtocolError::AuthenticationFailed(message),
+                // 550 covers both; servers say which in the message
+                Status::FileUnavailable if message.contains("ermission denied") => {
+                    ProtocolError::PermissionDenied(path.to_string())
+                }
+                Status::FileUnavailable => ProtocolError::NotFound(path.to_string()),
+                _ => ProtocolError::ProtocolError(format!("{}: {}", path, message)),
+            }
+        }
+        other => ProtocolError::ProtocolError(format!("{}: {}", path, other)),
+    }
+}
+
+fn invalid_listing(line: &str) -> ProtocolError {
+    ProtocolError::ProtocolError(format!("Invalid MLSD entry: {:?}", line))
+}
+
+/// Split an MLSD/MLST line ("fact=value;fact=value; name") into facts and
+/// name
+fn split_mlsx(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (facts, name) = line.split_once(' ')?;
+    (!name.is_empty()).then_some((facts, name))
+}
+
+/// Value of a fact (names are case-insensitive)
+fn fact<'a>(facts: &'a str, name: &str) -> Option<&'a str> {
+    facts
+        .split(';')
+        .filter_map(|fact| fact.split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Entry of an MLSD listing of `dir`
+///
+/// Returns None for the directory itself and its parent.
+fn parse_mlsd_line(dir: &str, line: &str) -> Result<Option<FileEntry>> {
+    let (facts, name) = split_mlsx(line).ok_or_else(|| invalid_listing(line))?;
+    let kind = fact(facts, "type").unwrap_or("file").to_ascii_lowercase();
+    if kind == "cdir" || kind == "pdir" || name == "." || name == ".." {
+        return Ok(None);
+    }
+    Ok(Some(file_entry(
+        format!("{}/{}", dir.trim_end_matches('/'), name),
+        name.to_string(),
+        facts,
+    )))
+}
+
+/// File entry from MLSx facts
+fn file_entry(path: String, name: String, facts: &str) -> FileEntry {
+    let kind = fact(facts, "type").unwrap_or("file").to_ascii_lowercase();
+    // Symlinks: "OS.unix=slink:<target>" or "OS.unix=symlink"
+    let is_symlink = kind.starts_with("os.unix=slink") || kind == "os.unix=symlink";
+    let is_dir = kind == "dir" || kind == "cdir" || kind == "pdir";
+    let file_type = if is_symlink {
+        S_IFLNK
+    } else if is_dir {
+        S_IFDIR
+    } else {
+        S_IFREG
+    };
+
+    FileEntry {
+        path,
+        name,
+        is_dir,
+        is_symlink,
+        size: fact(facts, "size")
+            .or_else(|| fact(facts, "sizd"))
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0),
+        modified: fact(facts, "modify").and_then(parse_time),
+        permissions: fact(facts, "unix.mode")
+            .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+            .map(|mode| file_type | (mode & 0o7777)),
+    }
+}
+
+/// Unix time from an MLSx timestamp (`YYYYMMDDHHMMSS[.sss]`, UTC)
+fn parse_time(value: &str) -> Option<i64> {
+    let digits = value.split('.').next()?;
+    if digits.len() != 14 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits[range].parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Days since 1970-01-01 (proleptic Gregorian calendar)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("19700101000000"), Some(0));
+        assert_eq!(parse_time("20240229123045"), Some(1_709_209_845));
+        assert_eq!(parse_time("20240229123045.123"), Some(1_709_209_845));
+        assert_eq!(parse_time("2024"), None);
+        assert_eq!(parse_time("20241301000000"), None);
+    }
+
+    #[test]
+    fn test_parse_mlsd_line() {
+        let file = parse_mlsd_line(
+            "/pub/",
+            "type=file;size=1024;modify=19700101000100;UNIX.mode=0644; notes v2.txt",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(file.name, "notes v2.txt");
+        assert_eq!(file.path, "/pub/notes v2.txt");
+        assert!(!file.is_dir && !file.is_symlink);
+        assert_eq!(file.size, 1024);
+        assert_eq!(file.modified, Some(60));
+        assert_eq!(file.permissions, Some(0o100644));
+
+        let dir = parse_mlsd_line("/", "Type=dir;Modify=20240101000000; etc\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(dir.path, "/etc");
+        assert!(dir.is_dir);
+        assert_eq!(dir.permissions, None);
+
+        let link = parse_mlsd_line("/", "type=OS.unix=slink:/srv;size=4; srv")
+            .unwrap()
+            .unwrap();
+        assert!(link.is_symlink && !link.is_dir);
+
+        assert!(parse_mlsd_line("/pub", "type=cdir; /pub")
+            .unwrap()
+            .is_none());
+        assert!(parse_mlsd_line("/pub", "type=pdir; ..").unwrap().is_none());
+        assert!(parse_mlsd_line("/pub", "no-facts").is_err());
+    }
+
+    #[test]
+    fn test_copy_reports_progress() {
+        let reports = std::cell::RefCell::new(Vec::new());
+        let data = vec![7u8; TRANSFER_CHUNK_SIZE + 10];
+        let mut output = Vec::new();
+        let copied = copy(&mut &data[..], &mut output, Some(data.len() as u64), &|p| {
+            reports.borrow_mut().push(p.transferred)
+        })
+        .unwrap();
+
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(output, data);
+        assert_eq!(
+            reports.into_inner(),
+            vec![TRANSFER_CHUNK_SIZE as u64, copied]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ftp_requires_connection() {
+        let mut client = FtpClient::secure();
+        assert!(client.is_secure());
+        assert!(!client.is_connected());
+        assert!(matches!(
+            client.list_dir("/").await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(matches!(
+            client.mkdir("/tmp/x").await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(client.disconnect().await.is_ok());
+    }
+}
@@ -2,10 +2,13 @@
 //!
 //! Provides abstractions and implementations for various remote protocols:
 //! - SSH (via russh)
-//! - SFTP (via russh)
-//! - FTP/FTPS (future)
+//! - SFTP (via russh-sftp)
+//! - SCP (remote `scp` over an SSH exec channel, when SFTP is unavailable)
+//! - FTP/FTPS (via suppaftp, `ftp` feature)
 //! - Local terminal (future)
 //!
+//! Outgoing connections can go through a SOCKS5 or HTTP proxy (see `proxy`).
+//!
 //! Architecture is designed to support future protocols (Telnet, Mosh, Serial)
 //! and the "profiles/termconfs" feature.
 
@@ -14,6 +17,10 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+#[cfg(feature = "ftp")]
+pub mod ftp;
+pub mod proxy;
+pub mod scp;
 pub mod ssh;
 
 #[derive(Error, Debug)]
@@ -35,6 +42,12 @@ pub enum ProtocolError {
 
     #[error("Not connected")]
     NotConnected,
+
+    #[error("No such file or directory: {0}")]
+    NotFound(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
 }
 
 pub type Result<T> = std::result::Result<T, ProtocolError>;
@@ -46,6 +59,7 @@ pub enum ProtocolType {
     Local,
     Ssh,
     Sftp,
+    Scp,
     #[cfg(feature = "ftp")]
     Ftp,
     // Future protocols (not yet implemented)
@@ -85,6 +99,10 @@ pub struct ConnectionConfig {
 
     /// Keep-alive interval in seconds
     pub keepalive: Option<u64>,
+
+    /// Proxy to connect through (None = direct connection)
+    #[serde(default)]
+    pub proxy: Option<proxy::ProxyConfig>,
 }
 
 /// Abstract protocol trait
@@ -130,6 +148,18 @@ pub trait TerminalProtocol: Protocol {
     async fn shell(&mut self) -> Result<()>;
 }
 
+/// Progress of a file transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferProgress {
+    /// Bytes transferred so far
+    pub transferred: u64,
+    /// Total size, if known
+    pub total: Option<u64>,
+}
+
+/// Callback receiving transfer progress (called after every chunk)
+pub type ProgressCallback<'a> = &'a mut (dyn FnMut(TransferProgress) + Send);
+
 /// File transfer protocol trait
 ///
 /// For protocols that support file operations (SFTP, FTP, SCP)
@@ -139,10 +169,32 @@ pub trait FileTransferProtocol: Protocol {
     async fn list_dir(&mut self, path: &str) -> Result<Vec<FileEntry>>;
 
     /// Download file
-    async fn download(&mut self, remote_path: &str, local_path: &Path) -> Result<()>;
+    async fn download(&mut self, remote_path: &str, local_path: &Path) -> Result<()> {
+        self.download_with_progress(remote_path, local_path, &mut |_| {})
+            .await
+    }
 
     /// Upload file
-    async fn upload(&mut self, local_path: &Path, remote_path: &str) -> Result<()>;
+    async fn upload(&mut self, local_path: &Path, remote_path: &str) -> Result<()> {
+        self.upload_with_progress(local_path, remote_path, &mut |_| {})
+            .await
+    }
+
+    /// Download file, reporting progress
+    async fn download_with_progress(
+        &mut self,
+        remote_path: &str,
+        local_path: &Path,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()>;
+
+    /// Upload file, reporting progress
+    async fn upload_with_progress(
+        &mut self,
+        local_path: &Path,
+        remote_path: &str,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()>;
 
     /// Delete file or directory
     async fn delete(&mut self, path: &str) -> Result<()>;
@@ -157,6 +209,9 @@ pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    /// Symbolic link (listings and `stat` don't follow links)
+    #[serde(default)]
+    pub is_symlink: bool,
     pub size: u64,
     pub modified: Option<i64>,
     pub permissions: Option<u32>,
@@ -187,6 +242,7 @@ mod tests {
             jump_host: None,
             timeout: Some(30),
             keepalive: Some(60),
+            proxy: None,
         };
 
         assert_eq!(config.protocol, ProtocolType::Ssh);
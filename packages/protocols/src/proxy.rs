@@ -0,0 +1,360 @@
+//! Proxy Support
+//!
+//! Outgoing TCP connections through a SOCKS5 (RFC 1928, username/password
+//! auth per RFC 1929) or HTTP `CONNECT` proxy. The target hostname is passed
+//! to the proxy unresolved, so hosts only known inside the proxied network
+//! work too.
+
+use crate::{ProtocolError, Result};
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info};
+
+/// Longest HTTP proxy response header accepted
+const MAX_HTTP_HEADER: usize = 16 * 1024;
+
+/// Proxy protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    Socks5,
+    /// HTTP `CONNECT` tunnel
+    Http,
+}
+
+/// Proxy configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    pub hostname: String,
+    pub port: u16,
+    /// Proxy credentials (both or neither)
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Open a TCP connection to `host:port` through the proxy
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream> {
+        info!(
+            "Connecting to {}:{} through {:?} proxy {}:{}",
+            host, port, self.kind, self.hostname, self.port
+        );
+        let mut stream = TcpStream::connect((self.hostname.as_str(), self.port))
+            .await
+            .map_err(|e| {
+                ProtocolError::ConnectionFailed(format!(
+                    "Proxy {}:{} unreachable: {}",
+                    self.hostname, self.port, e
+                ))
+            })?;
+        stream.set_nodelay(true)?;
+
+        let credentials = self.credentials();
+        match self.kind {
+            ProxyKind::Socks5 => socks5_connect(&mut stream, host, port, credentials).await?,
+            ProxyKind::Http => http_connect(&mut stream, host, port, credentials).await?,
+        }
+        debug!("Proxy tunnel to {}:{} established", host, port);
+        Ok(stream)
+    }
+
+    fn credentials(&self) -> Option<(&str, &str)> {
+        let username = self.username.as_deref().filter(|u| !u.is_empty())?;
+        Some((username, self.password.as_deref().unwrap_or("")))
+    }
+}
+
+/// Open a TCP connection to `host:port`, through `proxy` if given
+pub async fn connect_tcp(proxy: Option<&ProxyConfig>, host: &str, port: u16) -> Result<TcpStream> {
+    match proxy {
+        Some(proxy) => proxy.connect(host, port).await,
+        None => Ok(TcpStream::connect((host, port)).await?),
+    }
+}
+
+/// SOCKS5 handshake and CONNECT request on an open proxy connection
+pub async fn socks5_connect<S>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Offer username/password only when we have credentials
+    let greeting: &[u8] = match credentials {
+        Some(_) => &[5, 2, 0x00, 0x02],
+        None => &[5, 1, 0x00],
+    };
+    stream.write_all(greeting).await?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice[0] != 5 {
+        return Err(proxy_error("Not a SOCKS5 proxy"));
+    }
+    match (choice[1], credentials) {
+        (0x00, _) => {}
+        (0x02, Some((username, password))) => {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(ProtocolError::AuthenticationFailed(
+                    "SOCKS5 credentials are limited to 255 bytes".to_string(),
+                ));
+            }
+            let mut request = vec![1, username.len() as u8];
+            request.extend_from_slice(username.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await?;
+            if status[1] != 0 {
+                return Err(ProtocolError::AuthenticationFailed(
+                    "SOCKS5 proxy rejected the credentials".to_string(),
+                ));
+            }
+        }
+        (0xFF, None) | (0x02, None) => {
+            return Err(ProtocolError::AuthenticationFailed(
+                "SOCKS5 proxy requires authentication".to_string(),
+            ))
+        }
+        _ => return Err(proxy_error("SOCKS5 proxy offered no usable authentication")),
+    }
+
+    let mut request = vec![5, 1, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(1);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.is_empty() || host.len() > 255 {
+                return Err(proxy_error("Invalid target hostname"));
+            }
+            request.push(3);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(ProtocolError::ConnectionFailed(format!(
+            "SOCKS5 proxy: {}",
+            socks5_reply_message(reply[1])
+        )));
+    }
+    // Bound address (unused) and port
+    let address_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => stream.read_u8().await? as usize,
+        _ => return Err(proxy_error("Invalid SOCKS5 reply")),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+fn socks5_reply_message(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// HTTP `CONNECT` request on an open proxy connection
+///
+/// The response is read byte by byte up to the end of its header, so no
+/// tunneled data is consumed.
+pub async fn http_connect<S>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if host.is_empty() || host.contains(['\r', '\n', ' ']) {
+        return Err(proxy_error("Invalid target hostname"));
+    }
+    let authority = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => format!("[{}]:{}", host, port),
+        _ => format!("{}:{}", host, port),
+    };
+    let mut request = format!(
+        "CONNECT {0} HTTP/1.1\r\nHost: {0}\r\nProxy-Connection: Keep-Alive\r\n",
+        authority
+    );
+    if let Some((username, password)) = credentials {
+        let token =
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut header = Vec::new();
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HTTP_HEADER {
+            return Err(proxy_error("HTTP proxy response too long"));
+        }
+        header.push(stream.read_u8().await?);
+    }
+
+    let header = String::from_utf8_lossy(&header);
+    let status_line = header.lines().next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .filter(|_| status_line.starts_with("HTTP/"))
+        .ok_or_else(|| proxy_error("Not an HTTP proxy"))?;
+    match status {
+        200..=299 => Ok(()),
+        407 => Err(ProtocolError::AuthenticationFailed(format!(
+            "HTTP proxy: {}",
+            status_line
+        ))),
+        _ => Err(ProtocolError::ConnectionFailed(format!(
+            "HTTP proxy: {}",
+            status_line
+        ))),
+    }
+}
+
+fn proxy_error(message: &str) -> ProtocolError {
+    ProtocolError::ConnectionFailed(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_socks5_connect_with_auth() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = tokio::spawn(async move {
+            let mut greeting = [0u8; 4];
+            server.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 2, 0, 2]);
+            server.write_all(&[5, 2]).await.unwrap();
+
+            let mut auth = [0u8; 11];
+            server.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            server.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0u8; 5 + 11 + 2];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..5], &[5, 1, 0, 3, 11]);
+            assert_eq!(&request[5..16], b"example.com");
+            assert_eq!(&request[16..], &22u16.to_be_bytes());
+            server
+                .write_all(&[5, 0, 0, 1, 10, 0, 0, 1, 0, 22, b'S'])
+                .await
+                .unwrap();
+        });
+
+        socks5_connect(&mut client, "example.com", 22, Some(("user", "pass")))
+            .await
+            .unwrap();
+        proxy.await.unwrap();
+        // The tunneled data is left for the caller
+        assert_eq!(client.read_u8().await.unwrap(), b'S');
+    }
+
+    #[tokio::test]
+    async fn test_socks5_refused() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let mut greeting = [0u8; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0u8; 10];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[3..8], &[1, 192, 168, 1, 10]);
+            server
+                .write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+
+        let result = socks5_connect(&mut client, "192.168.1.10", 22, None).await;
+        assert!(
+            matches!(result, Err(ProtocolError::ConnectionFailed(m)) if m.contains("connection refused"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_connect() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = tokio::spawn(async move {
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(server.read_u8().await.unwrap());
+            }
+            server
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-x")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        http_connect(&mut client, "::1", 2222, Some(("user", "pass")))
+            .await
+            .unwrap();
+        let request = proxy.await.unwrap();
+        assert!(request.starts_with("CONNECT [::1]:2222 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+
+        let mut banner = [0u8; 4];
+        client.read_exact(&mut banner).await.unwrap();
+        assert_eq!(&banner, b"SSH-");
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_auth_required() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(server.read_u8().await.unwrap());
+            }
+            server
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let result = http_connect(&mut client, "db.internal", 22, None).await;
+        assert!(matches!(
+            result,
+            Err(ProtocolError::AuthenticationFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,456 @@
+//! SCP Protocol Implementation
+//!
+//! Fallback for servers that allow SSH but not the SFTP subsystem: each
+//! transfer runs the remote `scp` in sink (`-t`) or source (`-f`) mode on an
+//! exec channel. SCP only copies single files; listings and file management
+//! need SFTP.
+
+use crate::{
+    ConnectionConfig, FileEntry, FileTransferProtocol, ProgressCallback, Protocol, ProtocolError,
+    ProtocolType, Result, TransferProgress,
+};
+use async_trait::async_trait;
+use russh::client::{Handle, Handler};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+use tracing::{debug, info};
+
+/// Chunk size for streamed transfers
+const TRANSFER_CHUNK_SIZE: usize = 32 * 1024;
+
+/// Longest control line accepted from the server
+const MAX_LINE: usize = 4096;
+
+/// Mode sent for uploads when the local one is unknown
+const DEFAULT_MODE: u32 = 0o644;
+
+/// SCP client over an authenticated SSH session
+pub struct ScpClient<H: Handler> {
+    handle: Option<Arc<Handle<H>>>,
+}
+
+impl<H: Handler> ScpClient<H> {
+    pub fn new() -> Self {
+        Self { handle: None }
+    }
+
+    /// Use an authenticated session (one exec channel per transfer)
+    pub fn from_handle(handle: Arc<Handle<H>>) -> Self {
+        Self {
+            handle: Some(handle),
+        }
+    }
+
+    /// Run `scp` with the given arguments on a new channel
+    async fn exec(
+        &self,
+        args: &str,
+        path: &str,
+    ) -> Result<impl AsyncRead + AsyncWrite + Unpin + Send> {
+        let handle = self.handle.as_ref().ok_or(ProtocolError::NotConnected)?;
+        let channel = handle
+            .channel_open_session()
+            .await
+            .map_err(|e| ProtocolError::ConnectionFailed(format!("Channel open failed: {}", e)))?;
+        let command = format!("scp {} -- {}", args, shell_quote(path));
+        debug!("Running {}", command);
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| ProtocolError::ProtocolError(format!("scp exec failed: {}", e)))?;
+        Ok(channel.into_stream())
+    }
+}
+
+impl<H: Handler> Default for ScpClient<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Protocol for ScpClient<H> {
+    fn protocol_type(&self) -> ProtocolType {
+        ProtocolType::Scp
+    }
+
+    async fn connect(&mut self, _config: &ConnectionConfig) -> Result<()> {
+        // Authentication stays with the caller: see `from_handle`
+        Err(ProtocolError::ProtocolError(
+            "SCP runs on an existing SSH session".to_string(),
+        ))
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        // Channels are closed after each transfer; the session isn't ours
+        self.handle = None;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    async fn send(&mut self, _data: &[u8]) -> Result<()> {
+        Err(unsupported("raw send"))
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        Err(unsupported("raw receive"))
+    }
+}
+
+#[async_trait]
+impl<H: Handler> FileTransferProtocol for ScpClient<H> {
+    async fn list_dir(&mut self, _path: &str) -> Result<Vec<FileEntry>> {
+        Err(unsupported("directory listing"))
+    }
+
+    async fn download_with_progress(
+        &mut self,
+        remote_path: &str,
+        local_path: &Path,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        info!("Downloading {} -> {:?} (SCP)", remote_path, local_path);
+        let stream = self.exec("-f", remote_path).await?;
+        let mut local = tokio::fs::File::create(local_path).await?;
+        let received = receive_file(stream, &mut local, progress).await?;
+        local.flush().await?;
+
+        debug!("Downloaded {} bytes", received);
+        Ok(())
+    }
+
+    async fn upload_with_progress(
+        &mut self,
+        local_path: &Path,
+        remote_path: &str,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        info!("Uploading {:?} -> {} (SCP)", local_path, remote_path);
+        let local = tokio::fs::File::open(local_path).await?;
+        let metadata = local.metadata().await?;
+        let mode = local_mode(&metadata).unwrap_or(DEFAULT_MODE);
+        let name = remote_path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| ProtocolError::NotFound(remote_path.to_string()))?;
+
+        let stream = self.exec("-t", remote_path).await?;
+        send_file(stream, local, metadata.len(), mode, name, progress).await?;
+
+        debug!("Uploaded {} bytes", metadata.len());
+        Ok(())
+    }
+
+    async fn delete(&mut self, _path: &str) -> Result<()> {
+        Err(unsupported("delete"))
+    }
+
+    async fn mkdir(&mut self, _path: &str) -> Result<()> {
+        Err(unsupported("mkdir"))
+    }
+}
+
+#[cfg(unix)]
+fn local_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn local_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+fn unsupported(operation: &str) -> ProtocolError {
+    ProtocolError::ProtocolError(format!("{} is not supported over SCP", operation))
+}
+
+/// Sink side (`scp -t`): send one file to the remote scp
+pub async fn send_file<S, R>(
+    stream: S,
+    mut local: R,
+    size: u64,
+    mode: u32,
+    name: &str,
+    progress: ProgressCallback<'_>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    if name.contains('\n') || name.contains('/') {
+        return Err(ProtocolError::ProtocolError(format!(
+            "Invalid file name for SCP: {:?}",
+            name
+        )));
+    }
+    let mut stream = tokio::io::BufReader::new(stream);
+    read_ack(&mut stream).await?;
+
+    let header = format!("C{:04o} {} {}\n", mode & 0o7777, size, name);
+    stream.get_mut().write_all(header.as_bytes()).await?;
+    stream.get_mut().flush().await?;
+    read_ack(&mut stream).await?;
+
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE];
+    let mut transferred = 0u64;
+    while transferred < size {
+        let wanted = (size - transferred).min(buffer.len() as u64) as usize;
+        let read = local.read(&mut buffer[..wanted]).await?;
+        if read == 0 {
+            return Err(ProtocolError::ProtocolError(
+                "Local file shrank during upload".to_string(),
+            ));
+        }
+        stream.get_mut().write_all(&buffer[..read]).await?;
+        transferred += read as u64;
+        progress(TransferProgress {
+            transferred,
+            total: Some(size),
+        });
+    }
+    stream.get_mut().write_all(&[0]).await?;
+    stream.get_mut().flush().await?;
+    read_ack(&mut stream).await?;
+
+    stream.get_mut().shutdown().await?;
+    Ok(())
+}
+
+/// Source side (`scp -f`): receive one file from the remote scp
+///
+/// Returns the number of bytes written.
+pub async fn receive_file<S, W>(
+    stream: S,
+    local: &mut W,
+    progress: ProgressCallback<'_>,
+) -> Result<u64>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut stream = tokio::io::BufReader::new(stream);
+    stream.get_mut().write_all(&[0]).await?;
+    stream.get_mut().flush().await?;
+
+    let header = loop {
+        let line = read_control(&mut stream).await?;
+        match line.as_bytes().first() {
+            // Times (sent with -p): acknowledge and wait for the file
+            Some(b'T') => {
+                stream.get_mut().write_all(&[0]).await?;
+                stream.get_mut().flush().await?;
+            }
+            Some(b'C') => break line,
+            Some(b'D') => return Err(unsupported("directory download")),
+            _ => {
+                return Err(ProtocolError::ProtocolError(format!(
+                    "Unexpected SCP message: {:?}",
+                    line
+                )))
+            }
+        }
+    };
+    let size = parse_header(&header)?;
+    stream.get_mut().write_all(&[0]).await?;
+    stream.get_mut().flush().await?;
+
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE];
+    let mut transferred = 0u64;
+    while transferred < size {
+        let wanted = (size - transferred).min(buffer.len() as u64) as usize;
+        let read = stream.read(&mut buffer[..wanted]).await?;
+        if read == 0 {
+            return Err(ProtocolError::ProtocolError(
+                "Connection closed during download".to_string(),
+            ));
+        }
+        local.write_all(&buffer[..read]).await?;
+        transferred += read as u64;
+        progress(TransferProgress {
+            transferred,
+            total: Some(size),
+        });
+    }
+    read_ack(&mut stream).await?;
+    stream.get_mut().write_all(&[0]).await?;
+    stream.get_mut().shutdown().await?;
+
+    Ok(transferred)
+}
+
+/// Size from a `C<mode> <size> <name>` header
+fn parse_header(line: &str) -> Result<u64> {
+    let invalid = || ProtocolError::ProtocolError(format!("Invalid SCP header: {:?}", line));
+    let mut fields = line[1..].splitn(3, ' ');
+    let _mode = fields
+        .next()
+        .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+        .ok_or_else(invalid)?;
+    let size = fields
+        .next()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(invalid)?;
+    fields.next().ok_or_else(invalid)?;
+    Ok(size)
+}
+
+/// Read a status byte (0 = OK, 1 = warning, 2 = error, followed by a message)
+async fn read_ack<S: AsyncBufRead + Unpin>(stream: &mut S) -> Result<()> {
+    match stream.read_u8().await? {
+        0 => Ok(()),
+        1 | 2 => {
+            let message = read_line(stream).await?;
+            Err(scp_error(message.trim()))
+        }
+        other => Err(ProtocolError::ProtocolError(format!(
+            "Unexpected SCP status byte: {}",
+            other
+        ))),
+    }
+}
+
+/// Read a control line, mapping status messages to errors
+async fn read_control<S: AsyncBufRead + Unpin>(stream: &mut S) -> Result<String> {
+    let line = read_line(stream).await?;
+    match line.as_bytes().first() {
+        Some(1) | Some(2) => Err(scp_error(line[1..].trim())),
+        Some(_) => Ok(line),
+        None => Err(ProtocolError::ProtocolError(
+            "SCP closed the connection".to_string(),
+        )),
+    }
+}
+
+async fn read_line<S: AsyncBufRead + Unpin>(stream: &mut S) -> Result<String> {
+    let mut line = Vec::new();
+    (&mut *stream)
+        .take(MAX_LINE as u64)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Map a remote scp message ("scp: <path>: <reason>") to an error
+fn scp_error(message: &str) -> ProtocolError {
+    let message = message.strip_prefix("scp: ").unwrap_or(message);
+    if message.ends_with("No such file or directory") {
+        ProtocolError::NotFound(message.to_string())
+    } else if message.ends_with("Permission denied") {
+        ProtocolError::PermissionDenied(message.to_string())
+    } else {
+        ProtocolError::ProtocolError(message.to_string())
+    }
+}
+
+/// Quote a path for the remote shell
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/a b"), "'/tmp/a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse_header("C0644 1234 notes.txt").unwrap(), 1234);
+        assert_eq!(parse_header("C0600 0 my file").unwrap(), 0);
+        assert!(parse_header("C0644 12").is_err());
+        assert!(parse_header("Cxyz 12 a").is_err());
+    }
+
+    #[test]
+    fn test_scp_error() {
+        assert!(matches!(
+            scp_error("scp: /nope: No such file or directory"),
+            ProtocolError::NotFound(_)
+        ));
+        assert!(matches!(
+            scp_error("scp: /root/x: Permission denied"),
+            ProtocolError::PermissionDenied(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_file() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let remote = tokio::spawn(async move {
+            let mut received = Vec::new();
+            server.write_all(&[0]).await.unwrap();
+            let mut reader = tokio::io::BufReader::new(&mut server);
+            let mut header = String::new();
+            reader.read_line(&mut header).await.unwrap();
+            reader.get_mut().write_all(&[0]).await.unwrap();
+            let mut data = vec![0u8; 6];
+            reader.read_exact(&mut data).await.unwrap();
+            received.extend_from_slice(&data);
+            assert_eq!(reader.read_u8().await.unwrap(), 0);
+            reader.get_mut().write_all(&[0]).await.unwrap();
+            (header, received)
+        });
+
+        let mut reports = Vec::new();
+        send_file(client, &b"hello\n"[..], 6, 0o640, "hi.txt", &mut |p| {
+            reports.push(p.transferred)
+        })
+        .await
+        .unwrap();
+
+        let (header, received) = remote.await.unwrap();
+        assert_eq!(header, "C0640 6 hi.txt\n");
+        assert_eq!(received, b"hello\n");
+        assert_eq!(reports.last(), Some(&6));
+    }
+
+    #[tokio::test]
+    async fn test_receive_file() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let remote = tokio::spawn(async move {
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+            server.write_all(b"C0644 5 a.txt\n").await.unwrap();
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+            server.write_all(b"abcde\0").await.unwrap();
+            assert_eq!(server.read_u8().await.unwrap(), 0);
+        });
+
+        let mut local = Vec::new();
+        let size = receive_file(client, &mut local, &mut |_| {}).await.unwrap();
+        remote.await.unwrap();
+        assert_eq!(size, 5);
+        assert_eq!(local, b"abcde");
+    }
+
+    #[tokio::test]
+    async fn test_receive_error() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let _ = server.read_u8().await;
+            server
+                .write_all(b"\x01scp: /missing: No such file or directory\n")
+                .await
+                .unwrap();
+        });
+
+        let mut local = Vec::new();
+        let result = receive_file(client, &mut local, &mut |_| {}).await;
+        assert!(matches!(result, Err(ProtocolError::NotFound(_))));
+    }
+}
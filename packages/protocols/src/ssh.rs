@@ -1,15 +1,23 @@
 //! SSH Protocol Implementation
 //!
-//! Provides SSH and SFTP support via russh.
+//! Provides SSH support via russh and SFTP via russh-sftp.
 
 use crate::{
-    ConnectionConfig, FileEntry, FileTransferProtocol, Protocol, ProtocolError, ProtocolType,
-    Result, TerminalProtocol,
+    ConnectionConfig, FileEntry, FileTransferProtocol, ProgressCallback, Protocol, ProtocolError,
+    ProtocolType, Result, TerminalProtocol, TransferProgress,
 };
 use async_trait::async_trait;
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags, StatusCode};
+use std::io::SeekFrom;
 use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::{debug, info, warn};
 
+/// Chunk size for streamed transfers
+const TRANSFER_CHUNK_SIZE: usize = 32 * 1024;
+
 /// SSH client implementation
 pub struct SshClient {
     config: Option<ConnectionConfig>,
@@ -135,16 +143,244 @@ impl TerminalProtocol for SshClient {
 }
 
 /// SFTP client implementation
+///
+/// Runs over an SSH session channel: `from_channel` starts the SFTP
+/// subsystem on a channel of an already authenticated session (host key
+/// verification and authentication stay with the caller).
 pub struct SftpClient {
     ssh_client: SshClient,
+    session: Option<SftpSession>,
 }
 
 impl SftpClient {
     pub fn new() -> Self {
         Self {
             ssh_client: SshClient::new(),
+            session: None,
         }
     }
+
+    /// Start the SFTP subsystem on a session channel
+    pub async fn from_channel(channel: russh::Channel<russh::client::Msg>) -> Result<Self> {
+        channel.request_subsystem(true, "sftp").await.map_err(|e| {
+            ProtocolError::ConnectionFailed(format!("SFTP subsystem request failed: {}", e))
+        })?;
+        let session = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| ProtocolError::ConnectionFailed(format!("SFTP init failed: {}", e)))?;
+
+        info!("SFTP session established");
+        Ok(Self {
+            ssh_client: SshClient::new(),
+            session: Some(session),
+        })
+    }
+
+    fn session(&self) -> Result<&SftpSession> {
+        self.session.as_ref().ok_or(ProtocolError::NotConnected)
+    }
+
+    /// Absolute form of a path (`.` is the login directory)
+    pub async fn canonicalize(&self, path: &str) -> Result<String> {
+        self.session()?
+            .canonicalize(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))
+    }
+
+    /// Entry for a path (symlinks are not followed)
+    pub async fn stat(&self, path: &str) -> Result<FileEntry> {
+        let metadata = self
+            .session()?
+            .symlink_metadata(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))?;
+        Ok(path_entry(path, &metadata))
+    }
+
+    /// Entry for the file a path leads to (symlinks are followed, like
+    /// transfers do)
+    pub async fn stat_target(&self, path: &str) -> Result<FileEntry> {
+        let metadata = self
+            .session()?
+            .metadata(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))?;
+        Ok(path_entry(path, &metadata))
+    }
+
+    /// Rename or move a file or directory
+    pub async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        info!("Renaming {} -> {}", from, to);
+        self.session()?
+            .rename(from, to)
+            .await
+            .map_err(|e| map_sftp_error(from, e))
+    }
+
+    /// Change permission bits (e.g. 0o644)
+    pub async fn chmod(&self, path: &str, mode: u32) -> Result<()> {
+        info!("Changing mode of {} to {:o}", path, mode);
+        let attributes = FileAttributes {
+            permissions: Some(mode & 0o7777),
+            ..FileAttributes::empty()
+        };
+        self.session()?
+            .set_metadata(path, attributes)
+            .await
+            .map_err(|e| map_sftp_error(path, e))
+    }
+
+    /// Target of a symbolic link
+    pub async fn read_link(&self, path: &str) -> Result<String> {
+        self.session()?
+            .read_link(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))
+    }
+
+    /// Download a file, continuing a partial local copy
+    ///
+    /// Starts over when the local file is larger than the remote one.
+    pub async fn resume_download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        self.download_from(remote_path, local_path, true, progress)
+            .await
+    }
+
+    /// Upload a file, continuing a partial remote copy
+    ///
+    /// Starts over when the remote file is larger than the local one.
+    pub async fn resume_upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        self.upload_from(local_path, remote_path, true, progress)
+            .await
+    }
+
+    async fn download_from(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+        resume: bool,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        let session = self.session()?;
+        let total = session
+            .metadata(remote_path)
+            .await
+            .map_err(|e| map_sftp_error(remote_path, e))?
+            .size;
+        let offset = match tokio::fs::metadata(local_path).await {
+            Ok(metadata) if resume => resume_offset(metadata.len(), total),
+            _ => 0,
+        };
+        info!(
+            "Downloading {} -> {:?} (from byte {})",
+            remote_path, local_path, offset
+        );
+
+        let mut remote = session
+            .open(remote_path)
+            .await
+            .map_err(|e| map_sftp_error(remote_path, e))?;
+        let mut local = if offset > 0 {
+            remote.seek(SeekFrom::Start(offset)).await?;
+            let mut local = tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(local_path)
+                .await?;
+            local.seek(SeekFrom::Start(offset)).await?;
+            local
+        } else {
+            tokio::fs::File::create(local_path).await?
+        };
+
+        let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE];
+        let mut transferred = offset;
+        progress(TransferProgress { transferred, total });
+        loop {
+            let read = remote.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            local.write_all(&buffer[..read]).await?;
+            transferred += read as u64;
+            progress(TransferProgress { transferred, total });
+        }
+        local.flush().await?;
+        remote.shutdown().await?;
+
+        debug!("Downloaded {} bytes", transferred - offset);
+        Ok(())
+    }
+
+    async fn upload_from(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        resume: bool,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        let session = self.session()?;
+        let mut local = tokio::fs::File::open(local_path).await?;
+        let total = Some(local.metadata().await?.len());
+        let offset = if resume {
+            match session.metadata(remote_path).await {
+                Ok(metadata) => resume_offset(metadata.size.unwrap_or(0), total),
+                Err(e) => match map_sftp_error(remote_path, e) {
+                    ProtocolError::NotFound(_) => 0,
+                    other => return Err(other),
+                },
+            }
+        } else {
+            0
+        };
+        info!(
+            "Uploading {:?} -> {} (from byte {})",
+            local_path, remote_path, offset
+        );
+
+        let mut remote = if offset > 0 {
+            let mut remote = session
+                .open_with_flags(remote_path, OpenFlags::WRITE)
+                .await
+                .map_err(|e| map_sftp_error(remote_path, e))?;
+            remote.seek(SeekFrom::Start(offset)).await?;
+            local.seek(SeekFrom::Start(offset)).await?;
+            remote
+        } else {
+            session
+                .create(remote_path)
+                .await
+                .map_err(|e| map_sftp_error(remote_path, e))?
+        };
+
+        let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE];
+        let mut transferred = offset;
+        progress(TransferProgress { transferred, total });
+        loop {
+            let read = local.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            remote.write_all(&buffer[..read]).await?;
+            transferred += read as u64;
+            progress(TransferProgress { transferred, total });
+        }
+        // Closing the handle makes the server flush the file
+        remote.shutdown().await?;
+
+        debug!("Uploaded {} bytes", transferred - offset);
+        Ok(())
+    }
 }
 
 impl Default for SftpClient {
@@ -159,19 +395,26 @@ impl Protocol for SftpClient {
         ProtocolType::Sftp
     }
 
-    async fn connect(&mut self, config: &ConnectionConfig) -> Result<()> {
-        // Reuse SSH connection
-        self.ssh_client.connect(config).await?;
-        info!("SFTP session established");
-        Ok(())
+    async fn connect(&mut self, _config: &ConnectionConfig) -> Result<()> {
+        // SshClient has no transport yet, so there is no channel to run SFTP on
+        Err(ProtocolError::ConnectionFailed(
+            "SFTP needs an authenticated SSH session: use SftpClient::from_channel".to_string(),
+        ))
     }
 
     async fn disconnect(&mut self) -> Result<()> {
+        if let Some(session) = self.session.take() {
+            info!("Closing SFTP session");
+            if let Err(e) = session.close().await {
+                // The channel is gone with the session anyway
+                warn!("Failed to close SFTP session: {}", e);
+            }
+        }
         self.ssh_client.disconnect().await
     }
 
     fn is_connected(&self) -> bool {
-        self.ssh_client.is_connected()
+        self.session.is_some()
     }
 
     async fn send(&mut self, data: &[u8]) -> Result<()> {
@@ -186,52 +429,172 @@ impl Protocol for SftpClient {
 #[async_trait]
 impl FileTransferProtocol for SftpClient {
     async fn list_dir(&mut self, path: &str) -> Result<Vec<FileEntry>> {
-        if !self.is_connected() {
-            return Err(ProtocolError::NotConnected);
-        }
-
         debug!("Listing directory: {}", path);
-        // TODO: Implement with russh SFTP
-        Ok(Vec::new())
+        let entries = self
+            .session()?
+            .read_dir(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))?;
+
+        let mut listing: Vec<FileEntry> = entries
+            .filter(|entry| entry.file_name() != "." && entry.file_name() != "..")
+            .map(|entry| {
+                file_entry(
+                    join_remote(path, &entry.file_name()),
+                    entry.file_name(),
+                    &entry.metadata(),
+                )
+            })
+            .collect();
+        // Directories first, then by name
+        listing.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(listing)
     }
 
-    async fn download(&mut self, remote_path: &str, local_path: &Path) -> Result<()> {
-        if !self.is_connected() {
-            return Err(ProtocolError::NotConnected);
-        }
-
-        info!("Downloading {} -> {:?}", remote_path, local_path);
-        // TODO: Implement with russh SFTP
-        Ok(())
+    async fn download_with_progress(
+        &mut self,
+        remote_path: &str,
+        local_path: &Path,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        self.download_from(remote_path, local_path, false, progress)
+            .await
     }
 
-    async fn upload(&mut self, local_path: &Path, remote_path: &str) -> Result<()> {
-        if !self.is_connected() {
-            return Err(ProtocolError::NotConnected);
-        }
-
-        info!("Uploading {:?} -> {}", local_path, remote_path);
-        // TODO: Implement with russh SFTP
-        Ok(())
+    async fn upload_with_progress(
+        &mut self,
+        local_path: &Path,
+        remote_path: &str,
+        progress: ProgressCallback<'_>,
+    ) -> Result<()> {
+        self.upload_from(local_path, remote_path, false, progress)
+            .await
     }
 
     async fn delete(&mut self, path: &str) -> Result<()> {
-        if !self.is_connected() {
-            return Err(ProtocolError::NotConnected);
-        }
-
         warn!("Deleting: {}", path);
-        // TODO: Implement with russh SFTP
-        Ok(())
+        let session = self.session()?;
+
+        // lstat: a symlink to a directory is removed as a file
+        let metadata = session
+            .symlink_metadata(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))?;
+        let result = if metadata.is_dir() {
+            session.remove_dir(path).await
+        } else {
+            session.remove_file(path).await
+        };
+
+        result.map_err(|e| map_sftp_error(path, e))
     }
 
     async fn mkdir(&mut self, path: &str) -> Result<()> {
-        if !self.is_connected() {
-            return Err(ProtocolError::NotConnected);
-        }
-
         info!("Creating directory: {}", path);
-        // TODO: Implement with russh SFTP
-        Ok(())
+        self.session()?
+            .create_dir(path)
+            .await
+            .map_err(|e| map_sftp_error(path, e))
+    }
+}
+
+/// Map an SFTP error to a `ProtocolError` (with the path it concerns)
+fn map_sftp_error(path: &str, error: SftpError) -> ProtocolError {
+    match error {
+        SftpError::Status(status) => match status.status_code {
+            StatusCode::NoSuchFile => ProtocolError::NotFound(path.to_string()),
+            StatusCode::PermissionDenied => ProtocolError::PermissionDenied(path.to_string()),
+            StatusCode::NoConnection | StatusCode::ConnectionLost => ProtocolError::NotConnected,
+            _ => ProtocolError::ProtocolError(format!("{}: {}", path, status.error_message)),
+        },
+        SftpError::Timeout => ProtocolError::Timeout,
+        other => ProtocolError::ProtocolError(format!("{}: {}", path, other)),
+    }
+}
+
+/// Directory listing entry from SFTP attributes
+fn file_entry(path: String, name: String, metadata: &FileAttributes) -> FileEntry {
+    FileEntry {
+        path,
+        name,
+        is_dir: metadata.is_dir(),
+        is_symlink: metadata.is_symlink(),
+        size: metadata.size.unwrap_or(0),
+        modified: metadata.mtime.map(i64::from),
+        permissions: metadata.permissions,
+    }
+}
+
+/// Entry for a path, named after its last component
+fn path_entry(path: &str, metadata: &FileAttributes) -> FileEntry {
+    let name = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("/");
+    file_entry(path.to_string(), name.to_string(), metadata)
+}
+
+/// Offset to continue a transfer from, given what the destination already
+/// holds (0 = start over)
+fn resume_offset(existing: u64, total: Option<u64>) -> u64 {
+    match total {
+        Some(total) if existing > total => 0,
+        _ => existing,
+    }
+}
+
+/// Path of an entry in a remote directory
+fn join_remote(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_remote() {
+        assert_eq!(join_remote("/", "etc"), "/etc");
+        assert_eq!(
+            join_remote("/home/user/", "notes.txt"),
+            "/home/user/notes.txt"
+        );
+        assert_eq!(join_remote("logs", "app.log"), "logs/app.log");
+    }
+
+    #[test]
+    fn test_resume_offset() {
+        assert_eq!(resume_offset(0, Some(100)), 0);
+        assert_eq!(resume_offset(40, Some(100)), 40);
+        assert_eq!(resume_offset(100, Some(100)), 100);
+        // Destination larger than the source: not a partial copy
+        assert_eq!(resume_offset(150, Some(100)), 0);
+        assert_eq!(resume_offset(40, None), 40);
+    }
+
+    #[tokio::test]
+    async fn test_sftp_requires_session() {
+        let mut client = SftpClient::new();
+        assert!(!client.is_connected());
+        assert!(matches!(
+            client.list_dir("/").await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(matches!(
+            client.mkdir("/tmp/x").await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(matches!(
+            client.stat("/etc/hosts").await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(matches!(
+            client.chmod("/tmp/x", 0o644).await,
+            Err(ProtocolError::NotConnected)
+        ));
+        assert!(client.disconnect().await.is_ok());
     }
 }
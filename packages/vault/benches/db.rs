@@ -0,0 +1,104 @@
+//! Database benchmarks
+//!
+//! Connection listing with 10k stored connections (the sidebar load on
+//! unlock) and connection search, against a real SQLite file with the
+//! production schema.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rite_vault::Database;
+
+const CONNECTIONS: usize = 10_000;
+const FOLDERS: usize = 50;
+
+async fn populate(db: &Database) {
+    // Realistic blob size: envelope (header, nonce, connection ID) around
+    // the encrypted credentials; the nonce column is only used by legacy blobs
+    let credentials = vec![0xa5u8; 160];
+    let nonce: [u8; 0] = [];
+
+    for i in 0..FOLDERS {
+        db.create_folder(
+            &format!("folder-{}", i),
+            &format!("Folder {}", i),
+            None,
+            None,
+            i as i64,
+            1_700_000_000,
+        )
+        .await
+        .unwrap();
+    }
+
+    for i in 0..CONNECTIONS {
+        let folder = format!("folder-{}", i % FOLDERS);
+        db.create_connection(
+            &format!("conn-{:05}", i),
+            &format!("server-{:05}", i),
+            "ssh",
+            &format!("host-{}.example.com", i),
+            22,
+            "admin",
+            &credentials,
+            &nonce,
+            "password",
+            None,
+            Some("#3b82f6"),
+            None,
+            Some(&folder),
+            None,
+            None,
+            None,
+            None,
+            1_700_000_000,
+            1_700_000_000,
+        )
+        .await
+        .unwrap();
+    }
+}
+
+fn bench_listing(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db = runtime.block_on(async {
+        let db = Database::new(&dir.path().join("bench.db")).await.unwrap();
+        populate(&db).await;
+        db
+    });
+
+    let mut group = c.benchmark_group("db");
+    group.throughput(Throughput::Elements(CONNECTIONS as u64));
+    group.bench_function("get_all_connections_10k", |b| {
+        b.iter(|| {
+            let rows = runtime.block_on(db.get_all_connections()).unwrap();
+            assert_eq!(rows.len(), CONNECTIONS);
+        })
+    });
+
+    group.throughput(Throughput::Elements((CONNECTIONS / FOLDERS) as u64));
+    group.bench_function("get_connections_by_folder_10k", |b| {
+        b.iter(|| {
+            let rows = runtime
+                .block_on(db.get_connections_by_folder("folder-7", false))
+                .unwrap();
+            assert_eq!(rows.len(), CONNECTIONS / FOLDERS);
+        })
+    });
+
+    // host-123 and host-1230 to host-1239 (the index is built on the first
+    // search)
+    let expression = rite_vault::search::match_expression("host-123").unwrap();
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("search_connections_10k", |b| {
+        b.iter(|| {
+            let rows = runtime
+                .block_on(db.search_connections(&expression, 50))
+                .unwrap();
+            assert_eq!(rows.len(), 11);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_listing);
+criterion_main!(benches);
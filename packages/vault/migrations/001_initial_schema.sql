@@ -0,0 +1,388 @@
+-- RITE Database Schema v1 (Consolidated)
+-- This file contains the complete initial database schema for RITE
+-- All tables and settings required for MVP functionality
+--
+-- NOTE: This is the CONSOLIDATED schema. Do NOT add new migrations for alpha/beta.
+-- Only add migrations (002+) after first stable release.
+
+-- =============================================================================
+-- Schema Version Tracking
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    applied_at INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO schema_version (version, applied_at) VALUES (1, strftime('%s', 'now'));
+
+-- Vault data format (how encrypted data is laid out, see format.rs)
+-- Only one row should ever exist; it is written when the vault is opened
+CREATE TABLE IF NOT EXISTS vault_format (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    version INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- =============================================================================
+-- Authentication & Security
+-- =============================================================================
+
+-- Master password storage
+-- Only one row should ever exist in this table
+CREATE TABLE IF NOT EXISTS master_password (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    hash TEXT NOT NULL,
+    salt BLOB NOT NULL,
+    kdf_params TEXT,  -- Argon2 parameters (JSON), NULL = Argon2 defaults
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Unlock attempt tracking for rate limiting
+CREATE TABLE IF NOT EXISTS unlock_attempts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    success INTEGER NOT NULL CHECK (success IN (0, 1))
+);
+
+CREATE INDEX IF NOT EXISTS idx_unlock_attempts_timestamp
+ON unlock_attempts(timestamp DESC);
+
+-- =============================================================================
+-- Application Settings
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Insert default settings
+INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES
+    ('auto_lock_enabled', 'false', strftime('%s', 'now')),
+    ('auto_lock_timeout', '0', strftime('%s', 'now')),
+    ('clipboard_clear_enabled', 'false', strftime('%s', 'now')),
+    ('clipboard_clear_timeout', '30', strftime('%s', 'now')),
+    ('ssh_keep_alive_enabled', 'false', strftime('%s', 'now')),
+    ('ssh_keep_alive_interval', '30', strftime('%s', 'now')),
+    ('host_key_verification_mode', 'strict', strftime('%s', 'now')),
+    ('host_key_dns_verification', 'false', strftime('%s', 'now')),
+    ('host_key_system_known_hosts', 'false', strftime('%s', 'now')),
+    ('warm_connections_enabled', 'false', strftime('%s', 'now')),
+    ('warm_connections_idle_timeout', '300', strftime('%s', 'now')),
+    ('theme', 'default', strftime('%s', 'now')),
+    ('backup_schedule', 'off', strftime('%s', 'now')),
+    ('backup_retention', '7', strftime('%s', 'now')),
+    ('session_sharing_enabled', 'false', strftime('%s', 'now')),
+    ('session_sharing_lan', 'false', strftime('%s', 'now')),
+    ('ssh_client_id', '', strftime('%s', 'now'));
+
+-- =============================================================================
+-- Folders
+-- =============================================================================
+
+-- Folder tree for connections (a connection points at its folder by ID, so
+-- renaming or moving a folder doesn't touch the connections in it)
+CREATE TABLE IF NOT EXISTS folders (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,   -- No '/' (paths are built from names)
+    parent_id TEXT REFERENCES folders(id) ON DELETE CASCADE,  -- NULL = top level
+    color TEXT,           -- Hex color code (e.g., "#3B82F6")
+    sort_order INTEGER NOT NULL DEFAULT 0,  -- Position among its siblings
+
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Sibling names are unique (case-insensitive)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_folders_name
+ON folders(COALESCE(parent_id, ''), name COLLATE NOCASE);
+
+CREATE INDEX IF NOT EXISTS idx_folders_parent
+ON folders(parent_id, sort_order);
+
+-- =============================================================================
+-- Connections
+-- =============================================================================
+
+-- Saved connections (credentials encrypted with master key)
+CREATE TABLE IF NOT EXISTS connections (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,
+    protocol TEXT NOT NULL CHECK (protocol IN ('ssh', 'sftp', 'local', 'ftp', 'ftps')),
+    hostname TEXT NOT NULL,
+    port INTEGER NOT NULL CHECK (port > 0 AND port <= 65535),
+    username TEXT NOT NULL,
+
+    -- Encrypted credentials (JSON containing auth_method details)
+    -- Format: { "type": "password", "password": "..." } or
+    --         { "type": "publickey", "key_path": "...", "passphrase": "..." }
+    encrypted_credentials BLOB NOT NULL,
+    nonce BLOB NOT NULL,  -- Legacy ChaCha20-Poly1305 nonce, empty for versioned envelopes
+
+    -- Auth method and key fingerprint, in clear so listings work without
+    -- decrypting the credentials (kept in sync on create/update)
+    auth_type TEXT NOT NULL DEFAULT 'password'
+        CHECK (auth_type IN ('password', 'publicKey', 'storedKey')),
+    key_fingerprint TEXT,  -- "SHA256:...", NULL for password auth or when unknown
+
+    -- Per-connection SSH keep-alive settings
+    ssh_keep_alive_override TEXT DEFAULT NULL,  -- NULL (use global), 'disabled', 'enabled'
+    ssh_keep_alive_interval INTEGER DEFAULT NULL,  -- Value in seconds, NULL = use global
+
+    -- Per-connection session options (JSON, see session_options.rs)
+    session_options TEXT DEFAULT NULL,
+
+    -- Optional metadata for UI
+    color TEXT,      -- Hex color code (e.g., "#3B82F6")
+    icon TEXT,       -- Icon name or emoji
+    folder_id TEXT REFERENCES folders(id) ON DELETE SET NULL,  -- NULL = top level
+    notes TEXT,      -- User notes (not encrypted for MVP)
+
+    -- Incremented on every update (optimistic concurrency)
+    revision INTEGER NOT NULL DEFAULT 1,
+
+    -- Timestamps
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    last_used_at INTEGER,
+    deleted_at INTEGER  -- In the trash since (restorable until purged), NULL = not deleted
+);
+
+-- Indexes for performance
+-- Folder listings are sorted by name
+CREATE INDEX IF NOT EXISTS idx_connections_folder
+ON connections(folder_id, name COLLATE NOCASE);
+
+CREATE INDEX IF NOT EXISTS idx_connections_deleted
+ON connections(deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_connections_last_used
+ON connections(last_used_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_connections_name
+ON connections(name COLLATE NOCASE);
+
+-- =============================================================================
+-- Tags
+-- =============================================================================
+
+-- Connection tags ("prod", "db"), a connection can have any number of them
+CREATE TABLE IF NOT EXISTS tags (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,
+    color TEXT,           -- Hex color code (e.g., "#3B82F6")
+
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Tag names are unique (case-insensitive)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_name
+ON tags(name COLLATE NOCASE);
+
+CREATE TABLE IF NOT EXISTS connection_tags (
+    connection_id TEXT NOT NULL REFERENCES connections(id) ON DELETE CASCADE,
+    tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (connection_id, tag_id)
+);
+
+-- Tag filters look connections up by tag
+CREATE INDEX IF NOT EXISTS idx_connection_tags_tag
+ON connection_tags(tag_id, connection_id);
+
+-- =============================================================================
+-- Connection Usage History
+-- =============================================================================
+
+-- One row per session, for usage statistics and "most used" listings
+CREATE TABLE IF NOT EXISTS connection_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    connection_id TEXT NOT NULL REFERENCES connections(id) ON DELETE CASCADE,
+    started_at INTEGER NOT NULL,         -- Unix timestamp in seconds
+    ended_at INTEGER,                    -- NULL while open (or if the app quit)
+    bytes_in INTEGER NOT NULL DEFAULT 0,
+    bytes_out INTEGER NOT NULL DEFAULT 0,
+    exit_status INTEGER                  -- NULL unless the remote command exited
+);
+
+CREATE INDEX IF NOT EXISTS idx_connection_history_connection
+ON connection_history(connection_id, started_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_connection_history_started
+ON connection_history(started_at DESC);
+
+-- =============================================================================
+-- Connection Search
+-- =============================================================================
+
+-- Full-text index over the connections (folder path and tag names included).
+-- Paths and tags come from other tables, so instead of keeping it in sync row
+-- by row the triggers below mark it stale, and the next search rebuilds it.
+CREATE VIRTUAL TABLE IF NOT EXISTS connections_fts USING fts5(
+    connection_id UNINDEXED,
+    name,
+    hostname,
+    username,
+    notes,
+    folder,
+    tags,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Only one row should ever exist in this table
+CREATE TABLE IF NOT EXISTS connections_fts_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    stale INTEGER NOT NULL CHECK (stale IN (0, 1))
+);
+
+INSERT OR IGNORE INTO connections_fts_state (id, stale) VALUES (1, 1);
+
+CREATE TRIGGER IF NOT EXISTS connections_fts_insert AFTER INSERT ON connections
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS connections_fts_update
+AFTER UPDATE OF name, hostname, username, notes, folder_id, deleted_at ON connections
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS connections_fts_delete AFTER DELETE ON connections
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS folders_fts_update AFTER UPDATE OF name, parent_id ON folders
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS folders_fts_delete AFTER DELETE ON folders
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS tags_fts_update AFTER UPDATE OF name ON tags
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS tags_fts_delete AFTER DELETE ON tags
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS connection_tags_fts_insert AFTER INSERT ON connection_tags
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+CREATE TRIGGER IF NOT EXISTS connection_tags_fts_delete AFTER DELETE ON connection_tags
+BEGIN UPDATE connections_fts_state SET stale = 1; END;
+
+-- Connection templates: every connection field except the host, no secrets
+CREATE TABLE IF NOT EXISTS connection_templates (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,
+    protocol TEXT NOT NULL CHECK (protocol IN ('ssh', 'sftp', 'local', 'ftp', 'ftps')),
+    port INTEGER NOT NULL CHECK (port > 0 AND port <= 65535),
+    username TEXT NOT NULL,
+    auth_type TEXT NOT NULL CHECK (auth_type IN ('password', 'publicKey')),
+    key_path TEXT,  -- Public key templates only (path, not key material)
+
+    ssh_keep_alive_override TEXT DEFAULT NULL,
+    ssh_keep_alive_interval INTEGER DEFAULT NULL,
+    session_options TEXT DEFAULT NULL,
+
+    color TEXT,
+    icon TEXT,
+    folder TEXT,  -- Folder path ("Production/Databases"), created if missing
+    notes TEXT,
+    tags TEXT DEFAULT NULL,  -- JSON array of tag names, created if missing
+
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- =============================================================================
+-- Command Snippets
+-- =============================================================================
+
+-- Reusable commands with {{variables}} (command encrypted with master key,
+-- it may hold secrets)
+CREATE TABLE IF NOT EXISTS snippets (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,
+    folder TEXT,
+
+    encrypted_command BLOB NOT NULL,
+    nonce BLOB NOT NULL,  -- Legacy ChaCha20-Poly1305 nonce, empty for versioned envelopes
+
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_snippets_folder
+ON snippets(folder);
+
+-- =============================================================================
+-- Remote Tasks
+-- =============================================================================
+
+-- Commands run over SSH on one or more connections, on demand or on a
+-- cron-like schedule (command encrypted like snippets)
+CREATE TABLE IF NOT EXISTS remote_tasks (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    name TEXT NOT NULL,
+
+    encrypted_command BLOB NOT NULL,
+    nonce BLOB NOT NULL,  -- Empty for versioned envelopes
+
+    schedule TEXT,        -- 5-field cron expression, NULL = on demand only
+    enabled INTEGER NOT NULL DEFAULT 1 CHECK (enabled IN (0, 1)),
+
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS remote_task_targets (
+    task_id TEXT NOT NULL REFERENCES remote_tasks(id) ON DELETE CASCADE,
+    connection_id TEXT NOT NULL REFERENCES connections(id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, connection_id)
+);
+
+-- One row per task run on one connection, written when the run ends
+-- (output encrypted, it may hold anything the command printed)
+CREATE TABLE IF NOT EXISTS remote_task_runs (
+    id TEXT PRIMARY KEY,  -- UUID v4
+    task_id TEXT NOT NULL REFERENCES remote_tasks(id) ON DELETE CASCADE,
+    connection_id TEXT NOT NULL REFERENCES connections(id) ON DELETE CASCADE,
+    started_at INTEGER NOT NULL,  -- Unix timestamp in milliseconds
+    ended_at INTEGER NOT NULL,
+    exit_status INTEGER,          -- NULL when the command didn't exit (error, signal)
+    error TEXT,                   -- Why the run failed before the command exited
+
+    encrypted_output BLOB NOT NULL,
+    nonce BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_remote_task_runs_task
+ON remote_task_runs(task_id, started_at DESC);
+
+-- =============================================================================
+-- SSH Host Key Verification (MITM Protection)
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS known_hosts (
+    id TEXT PRIMARY KEY NOT NULL,
+    host TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    key_type TEXT NOT NULL,           -- e.g., 'ssh-rsa', 'ssh-ed25519', 'ecdsa-sha2-nistp256'
+    fingerprint TEXT NOT NULL,        -- SHA256 fingerprint for display
+    public_key_data BLOB NOT NULL,    -- Full public key data
+    added_at INTEGER NOT NULL,        -- Unix timestamp in seconds
+    last_seen_at INTEGER NOT NULL,    -- Unix timestamp in seconds
+    UNIQUE(host, port)
+);
+
+CREATE INDEX IF NOT EXISTS idx_known_hosts_host_port
+ON known_hosts(host, port);
+
+-- =============================================================================
+-- Audit Log (security-relevant actions, see audit.rs)
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,  -- Unix timestamp in seconds
+    event TEXT NOT NULL,         -- e.g., 'insecure_host_key_accepted'
+    detail TEXT NOT NULL
+);
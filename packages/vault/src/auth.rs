@@ -0,0 +1,997 @@
+//! Authentication module
+//!
+//! Handles master password setup, verification, and unlock rate limiting.
+
+use crate::db::{self, Database};
+use crate::format;
+use anyhow::{anyhow, Context, Result};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use base64::Engine as _;
+use rand::RngCore;
+use rite_crypto::{generate_salt, validate_password_strength, KdfParams};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use zeroize::Zeroizing;
+
+// Re-export MasterKey for use in other modules
+pub use rite_crypto::MasterKey;
+
+/// Setting holding the KDF parameters (JSON) to use the next time the
+/// master password is set or changed, written by KDF calibration
+pub const SETTING_KDF_PARAMS: &str = "kdf_params";
+
+/// Lifetime of a capability token for sensitive commands (milliseconds)
+const CAPABILITY_TTL_MS: i64 = 5 * 60 * 1000;
+
+/// Capability token required by sensitive commands (reset, reveal, export)
+///
+/// Minted only after re-entering the master password while unlocked, and
+/// dropped on lock, so script running in the webview can't use these
+/// commands on its own.
+struct Capability {
+    token: Zeroizing<String>,
+    expires_at: i64,
+}
+
+/// Data encrypted with the master key outside the vault database (e.g.
+/// recording files), re-encrypted when the master password changes
+///
+/// `prepare` runs inside the password change transaction; `commit` runs
+/// once the transaction is committed, `abort` if the change fails.
+pub trait Rekey: Send {
+    /// Write copies encrypted with the new key, leaving the originals as is
+    fn prepare(&mut self, old_key: &MasterKey, new_key: &MasterKey) -> Result<()>;
+    /// Replace the originals with the copies
+    fn commit(&mut self) -> Result<()>;
+    /// Remove the copies
+    fn abort(&mut self);
+}
+
+/// Authentication manager
+#[derive(Clone)]
+pub struct AuthManager {
+    db: Database,
+    /// Master key in memory (zeroized on drop)
+    /// None when locked, Some when unlocked
+    master_key: Arc<RwLock<Option<Arc<MasterKey>>>>,
+    /// Current capability token for sensitive commands (None until re-authenticated)
+    capability: Arc<RwLock<Option<Capability>>>,
+}
+
+impl AuthManager {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            master_key: Arc::new(RwLock::new(None)),
+            capability: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Check if this is the first run (no master password set)
+    pub async fn is_first_run(&self) -> Result<bool> {
+        self.db.is_first_run().await
+    }
+
+    /// Set up master password (first run only)
+    pub async fn setup_master_password(&self, password: &str) -> Result<()> {
+        // Verify this is first run
+        if !self.is_first_run().await? {
+            return Err(anyhow!("Master password already set"));
+        }
+
+        // Validate password strength
+        let strength = validate_password_strength(password);
+        if !strength.is_valid {
+            return Err(anyhow!(
+                "Password too weak (score: {}/4): {}",
+                strength.score,
+                strength.feedback()
+            ));
+        }
+
+        info!(
+            "Setting up master password (strength score: {}/4)",
+            strength.score
+        );
+
+        // Generate salt for Argon2
+        let salt = generate_salt();
+        let kdf_params = self.target_kdf_params().await?;
+        let password_hash = hash_master_password(password, &salt, &kdf_params)?;
+
+        // Store hash, salt and KDF parameters in database
+        self.db
+            .store_master_password(&password_hash, &salt, &kdf_params)
+            .await
+            .context("Failed to store master password")?;
+
+        // Derive and store master key in memory
+        let master_key = derive_master_key(password, &salt, &kdf_params).await?;
+
+        // Nothing to re-encrypt yet, but a reset vault may still carry an old format
+        format::upgrade(&self.db, &master_key).await?;
+
+        *self.master_key.write().await = Some(master_key);
+
+        info!("Master password setup completed");
+        Ok(())
+    }
+
+    /// Unlock the application with master password
+    pub async fn unlock(&self, password: &str) -> Result<UnlockResult> {
+        self.unlock_with_progress(password, |_| {}).await
+    }
+
+    /// Unlock, reporting when the (slow) Argon2 work starts and finishes
+    ///
+    /// Verification and key derivation run on the blocking thread pool so
+    /// the async runtime stays responsive.
+    pub async fn unlock_with_progress(
+        &self,
+        password: &str,
+        on_progress: impl Fn(UnlockProgress),
+    ) -> Result<UnlockResult> {
+        // Check rate limiting
+        if let Some(wait_time) = self.check_rate_limit().await? {
+            return Ok(UnlockResult::RateLimited {
+                wait_seconds: wait_time,
+            });
+        }
+
+        on_progress(UnlockProgress::DerivationStarted);
+        let master_key = self.verify_and_derive(password).await;
+        on_progress(UnlockProgress::DerivationFinished);
+
+        let Some(master_key) = master_key? else {
+            warn!("Failed unlock attempt");
+            return Ok(UnlockResult::InvalidPassword);
+        };
+
+        // An encrypted database can only be read from now on
+        self.db.unlock(&master_key).await?;
+
+        // Bring older vaults to the current format while we have the key
+        format::upgrade(&self.db, &master_key)
+            .await
+            .context("Failed to upgrade vault format")?;
+
+        // Store in memory
+        *self.master_key.write().await = Some(master_key);
+
+        info!("Application unlocked successfully");
+
+        // Clean old attempts
+        let _ = self.db.clean_old_unlock_attempts().await;
+
+        Ok(UnlockResult::Success)
+    }
+
+    /// Unlock with a master key kept by the OS (biometric unlock)
+    ///
+    /// `salt` is the key derivation salt the key was saved with. A different
+    /// salt means the master password changed since, and the key is refused;
+    /// the key must also decrypt the stored credentials.
+    pub async fn unlock_with_key(&self, master_key: MasterKey, salt: &[u8]) -> Result<()> {
+        let (_, current_salt) = self
+            .db
+            .get_master_password()
+            .await?
+            .ok_or_else(|| anyhow!("No master password set"))?;
+        if !constant_time_eq(&current_salt, salt) {
+            return Err(anyhow!("Master password changed since the key was saved"));
+        }
+
+        self.db.unlock(&master_key).await?;
+
+        format::check_key(&self.db, &master_key)
+            .await
+            .context("Saved master key does not match the vault")?;
+
+        *self.master_key.write().await = Some(Arc::new(master_key));
+        info!("Application unlocked with a saved master key");
+        Ok(())
+    }
+
+    /// Lock the application (zeroize master key)
+    pub async fn lock(&self) -> Result<()> {
+        info!("Locking application");
+        *self.master_key.write().await = None;
+        *self.capability.write().await = None;
+        self.db.lock();
+        Ok(())
+    }
+
+    /// Change the master password, re-encrypting the vault with the new key
+    ///
+    /// The old password is verified (attempts count towards the unlock rate
+    /// limit) and a backup of the vault is written first. Credentials and the
+    /// password hash are replaced in one transaction, so a failure leaves the
+    /// vault unchanged. Capability tokens are dropped. The new key is derived
+    /// with the calibrated KDF parameters, if any.
+    pub async fn change_master_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        self.change_master_password_with(old_password, new_password, &mut [])
+            .await
+    }
+
+    /// Change the master password, also re-encrypting data kept outside the
+    /// database (see `Rekey`)
+    pub async fn change_master_password_with(
+        &self,
+        old_password: &str,
+        new_password: &str,
+        rekeys: &mut [&mut dyn Rekey],
+    ) -> Result<()> {
+        if self.is_locked().await {
+            return Err(anyhow!("Application is locked"));
+        }
+
+        if let Some(wait_time) = self.check_rate_limit().await? {
+            return Err(anyhow!(
+                "Too many attempts, try again in {} seconds",
+                wait_time
+            ));
+        }
+
+        let Some(old_key) = self.verify_and_derive(old_password).await? else {
+            warn!("Failed re-authentication for master password change");
+            return Err(anyhow!("Invalid password"));
+        };
+
+        if old_password == new_password {
+            return Err(anyhow!("New password must differ from the current one"));
+        }
+        let strength = validate_password_strength(new_password);
+        if !strength.is_valid {
+            return Err(anyhow!(
+                "Password too weak (score: {}/4): {}",
+                strength.score,
+                strength.feedback()
+            ));
+        }
+
+        // Re-encryption below reads the current format only
+        format::upgrade(&self.db, &old_key).await?;
+
+        let backup_path = self
+            .db
+            .create_timestamped_backup("pre_password_change")
+            .await
+            .context("Failed to back up the vault")?;
+        info!("Pre-change backup written to {}", backup_path.display());
+
+        let salt = generate_salt();
+        let kdf_params = self.target_kdf_params().await?;
+        let password_hash = hash_master_password(new_password, &salt, &kdf_params)?;
+        let new_key = derive_master_key(new_password, &salt, &kdf_params).await?;
+
+        // Nothing can be encrypted with the old key while the rows are rewritten
+        let mut master_key = self.master_key.write().await;
+
+        let mut tx = self.db.pool().begin().await?;
+        let count =
+            format::reencrypt_credentials(&mut tx, format::CURRENT_FORMAT, &old_key, &new_key)
+                .await?;
+        for record in [
+            format::Record::Snippet,
+            format::Record::RemoteTask,
+            format::Record::RemoteTaskRun,
+        ] {
+            format::reencrypt_records(&mut tx, record, format::CURRENT_FORMAT, &old_key, &new_key)
+                .await?;
+        }
+        db::write_master_password(&mut *tx, &password_hash, &salt, &kdf_params).await?;
+
+        let mut prepared = 0;
+        let mut result = Ok(());
+        for rekey in rekeys.iter_mut() {
+            result = rekey.prepare(&old_key, &new_key);
+            if result.is_err() {
+                break;
+            }
+            prepared += 1;
+        }
+        if result.is_ok() {
+            result = tx.commit().await.map_err(Into::into);
+        }
+        if let Err(e) = result {
+            for rekey in rekeys.iter_mut().take(prepared + 1) {
+                rekey.abort();
+            }
+            return Err(e);
+        }
+        for rekey in rekeys.iter_mut() {
+            if let Err(e) = rekey.commit() {
+                warn!("Failed to replace re-encrypted data: {:#}", e);
+            }
+        }
+
+        *master_key = Some(Arc::clone(&new_key));
+        drop(master_key);
+        *self.capability.write().await = None;
+
+        self.db
+            .rewrap_key(&new_key, &password_hash, &salt, &kdf_params)
+            .await
+            .context("Failed to update the database key")?;
+
+        info!(
+            "Master password changed ({} connections re-encrypted, KDF {} KiB × {})",
+            count, kdf_params.memory_kib, kdf_params.iterations
+        );
+        Ok(())
+    }
+
+    /// Re-authenticate and mint a capability token for sensitive commands
+    pub async fn authorize_sensitive_action(&self, password: &str) -> Result<String> {
+        self.reauthenticate(password).await?;
+
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill_bytes(&mut bytes[..]);
+        let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes[..]);
+
+        *self.capability.write().await = Some(Capability {
+            token: Zeroizing::new(token.clone()),
+            expires_at: chrono::Utc::now().timestamp_millis() + CAPABILITY_TTL_MS,
+        });
+
+        info!("Capability token issued for sensitive actions");
+        Ok(token)
+    }
+
+    /// Re-verify the master password while unlocked
+    ///
+    /// Attempts count towards the unlock rate limit.
+    pub async fn reauthenticate(&self, password: &str) -> Result<()> {
+        if self.is_locked().await {
+            return Err(anyhow!("Application is locked"));
+        }
+
+        if let Some(wait_time) = self.check_rate_limit().await? {
+            return Err(anyhow!(
+                "Too many attempts, try again in {} seconds",
+                wait_time
+            ));
+        }
+
+        if self.verify_master_password(password).await?.is_none() {
+            warn!("Failed re-authentication for sensitive action");
+            return Err(anyhow!("Invalid password"));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the master password while locked, for the recovery actions
+    /// offered on the unlock screen (reset, restore)
+    ///
+    /// The key is derived again and, for a plain vault, checked against the
+    /// stored credentials. Attempts count towards the unlock rate limit.
+    pub async fn verify_while_locked(&self, password: &str) -> Result<()> {
+        if let Some(wait_time) = self.check_rate_limit().await? {
+            return Err(anyhow!(
+                "Too many attempts, try again in {} seconds",
+                wait_time
+            ));
+        }
+
+        let Some(master_key) = self.verify_and_derive(password).await? else {
+            warn!("Failed verification for a locked recovery action");
+            return Err(anyhow!("Invalid password"));
+        };
+
+        // An encrypted vault can't be read until it is opened with the key
+        if !self.db.is_encrypted() {
+            format::check_key(&self.db, &master_key)
+                .await
+                .context("Master password does not match the vault")?;
+        }
+        Ok(())
+    }
+
+    /// Check a capability token presented by a sensitive command
+    pub async fn check_capability(&self, token: &str) -> Result<()> {
+        let guard = self.capability.read().await;
+        let capability = guard
+            .as_ref()
+            .ok_or_else(|| anyhow!("Re-authentication required"))?;
+
+        if chrono::Utc::now().timestamp_millis() > capability.expires_at {
+            return Err(anyhow!("Re-authentication expired"));
+        }
+        if !constant_time_eq(capability.token.as_bytes(), token.as_bytes()) {
+            return Err(anyhow!("Invalid capability token"));
+        }
+
+        Ok(())
+    }
+
+    /// KDF parameters to use for a new master password: the calibrated
+    /// ones if set, otherwise the current ones
+    async fn target_kdf_params(&self) -> Result<KdfParams> {
+        let params = match self.db.get_setting(SETTING_KDF_PARAMS).await? {
+            Some(params) => {
+                serde_json::from_str(&params).context("Invalid KDF parameters setting")?
+            }
+            None if self.is_first_run().await? => KdfParams::default(),
+            None => self.db.get_kdf_params().await?,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Verify the master password and derive the master key
+    /// Returns None if the password is wrong
+    async fn verify_and_derive(&self, password: &str) -> Result<Option<Arc<MasterKey>>> {
+        match self.verify_master_password(password).await? {
+            Some(salt) => {
+                let kdf_params = self.db.get_kdf_params().await?;
+                Ok(Some(derive_master_key(password, &salt, &kdf_params).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Verify the master password and record the attempt
+    /// Returns the key derivation salt if the password is correct
+    async fn verify_master_password(&self, password: &str) -> Result<Option<Vec<u8>>> {
+        let (stored_hash, salt) = self
+            .db
+            .get_master_password()
+            .await?
+            .ok_or_else(|| anyhow!("No master password set"))?;
+
+        let password = Zeroizing::new(password.to_string());
+        let is_valid = tokio::task::spawn_blocking(move || {
+            let parsed_hash = PasswordHash::new(&stored_hash)
+                .map_err(|e| anyhow!("Invalid stored password hash: {}", e))?;
+            Ok::<_, anyhow::Error>(
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed_hash)
+                    .is_ok(),
+            )
+        })
+        .await??;
+
+        // Record attempt
+        self.db.record_unlock_attempt(is_valid).await?;
+
+        Ok(is_valid.then_some(salt))
+    }
+
+    /// Check if the application is locked
+    pub async fn is_locked(&self) -> bool {
+        self.master_key.read().await.is_none()
+    }
+
+    /// Get master key (if unlocked)
+    pub async fn get_master_key(&self) -> Result<Arc<MasterKey>> {
+        self.master_key
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow!("Application is locked"))
+    }
+
+    /// Check rate limiting for unlock attempts
+    /// Returns Some(wait_seconds) if rate limited, None if okay to proceed
+    async fn check_rate_limit(&self) -> Result<Option<u64>> {
+        const MAX_ATTEMPTS: usize = 5;
+        const WINDOW_MINUTES: i64 = 1;
+        const LOCKOUT_SECONDS: u64 = 30;
+
+        let attempts = self.db.get_recent_unlock_attempts(WINDOW_MINUTES).await?;
+
+        // Count failed attempts
+        let failed_count = attempts.iter().filter(|a| !a.is_success()).count();
+
+        if failed_count >= MAX_ATTEMPTS {
+            // Check if we should still be locked out
+            if let Some(last_attempt) = attempts.first() {
+                let now = chrono::Utc::now().timestamp_millis();
+                let elapsed_ms = (now - last_attempt.timestamp) as u64;
+                let elapsed_secs = elapsed_ms / 1000;
+
+                if elapsed_secs < LOCKOUT_SECONDS {
+                    let remaining = LOCKOUT_SECONDS - elapsed_secs;
+                    debug!("Rate limited: {} seconds remaining", remaining);
+                    return Ok(Some(remaining));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reset the database (emergency recovery)
+    /// WARNING: This will delete ALL data including connections!
+    ///
+    /// An encrypted database is moved to the backups directory instead and
+    /// closed: it must be opened again, which creates a new vault.
+    pub async fn reset_database(&self) -> Result<()> {
+        warn!("EMERGENCY: Resetting database - all data will be lost!");
+
+        // Lock the application first
+        self.lock().await?;
+
+        // An encrypted database can't be read any more: set it aside whole
+        if self.db.is_encrypted() {
+            let path = crate::encryption::discard(&self.db).await?;
+            info!("Encrypted vault moved to {}", path.display());
+            return Ok(());
+        }
+
+        // Keep a copy so an unintended (or scripted) reset can be undone
+        let backup_path = self.db.create_timestamped_backup("pre_reset").await?;
+        info!("Pre-reset backup written to {}", backup_path.display());
+
+        // Reset database
+        self.db.reset().await?;
+
+        info!("Database reset completed");
+        Ok(())
+    }
+
+    /// Get statistics about recent unlock attempts (used in tests)
+    #[cfg(test)]
+    pub async fn get_unlock_stats(&self) -> Result<UnlockStats> {
+        let attempts = self.db.get_recent_unlock_attempts(60).await?; // Last hour
+
+        let total = attempts.len();
+        let successful = attempts.iter().filter(|a| a.is_success()).count();
+        let failed = total - successful;
+
+        Ok(UnlockStats {
+            total_attempts_last_hour: total,
+            successful_attempts: successful,
+            failed_attempts: failed,
+        })
+    }
+}
+
+/// Hash the master password with Argon2id (stored to verify it later)
+///
+/// The parameters are part of the PHC hash string, so verification doesn't
+/// need them separately.
+fn hash_master_password(password: &str, salt: &[u8], kdf_params: &KdfParams) -> Result<String> {
+    let salt_string =
+        SaltString::encode_b64(salt).map_err(|e| anyhow!("Failed to encode salt: {}", e))?;
+
+    Ok(kdf_params
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt_string)
+        .map_err(|e| anyhow!("Password hashing failed: {}", e))?
+        .to_string())
+}
+
+/// Derive the master key on the blocking thread pool (Argon2 takes ~1s)
+async fn derive_master_key(
+    password: &str,
+    salt: &[u8],
+    kdf_params: &KdfParams,
+) -> Result<Arc<MasterKey>> {
+    let password = Zeroizing::new(password.to_string());
+    let salt = salt.to_vec();
+    let kdf_params = *kdf_params;
+    let master_key = tokio::task::spawn_blocking(move || {
+        MasterKey::derive_with_params(&password, &salt, &kdf_params)
+    })
+    .await?
+    .context("Failed to derive master key")?;
+    Ok(Arc::new(master_key))
+}
+
+/// Compare two byte strings without early exit
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Unlock progress (emitted to the frontend as `unlock:progress` events)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnlockProgress {
+    /// Password verification and key derivation started
+    DerivationStarted,
+    /// Key derivation finished (whether or not the password was correct)
+    DerivationFinished,
+}
+
+/// Result of an unlock attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnlockResult {
+    Success,
+    InvalidPassword,
+    RateLimited { wait_seconds: u64 },
+}
+
+/// Statistics about unlock attempts (used in tests)
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct UnlockStats {
+    pub total_attempts_last_hour: usize,
+    pub successful_attempts: usize,
+    pub failed_attempts: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn create_test_auth() -> (AuthManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::new(&db_path).await.unwrap();
+        let auth = AuthManager::new(db);
+        (auth, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_first_run() {
+        let (auth, _temp) = create_test_auth().await;
+        assert!(auth.is_first_run().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_setup_master_password() {
+        let (auth, _temp) = create_test_auth().await;
+
+        // Weak password should fail
+        let result = auth.setup_master_password("weak").await;
+        assert!(result.is_err());
+
+        // Strong password should succeed
+        let strong_password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(strong_password).await.unwrap();
+
+        // Should no longer be first run
+        assert!(!auth.is_first_run().await.unwrap());
+
+        // Should be unlocked after setup
+        assert!(!auth.is_locked().await);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_correct_password() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+
+        // Lock the app
+        auth.lock().await.unwrap();
+        assert!(auth.is_locked().await);
+
+        // Unlock with correct password
+        let progress = std::sync::Mutex::new(Vec::new());
+        let result = auth
+            .unlock_with_progress(password, |p| progress.lock().unwrap().push(p))
+            .await
+            .unwrap();
+        assert_eq!(result, UnlockResult::Success);
+        assert!(!auth.is_locked().await);
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            vec![
+                UnlockProgress::DerivationStarted,
+                UnlockProgress::DerivationFinished
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_wrong_password() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let correct_password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(correct_password).await.unwrap();
+        auth.lock().await.unwrap();
+
+        // Try wrong password
+        let result = auth.unlock("WrongPassword123!").await.unwrap();
+        assert_eq!(result, UnlockResult::InvalidPassword);
+        assert!(auth.is_locked().await);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiting() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+        auth.lock().await.unwrap();
+
+        // Make 5 failed attempts
+        for _ in 0..5 {
+            let result = auth.unlock("WrongPassword").await.unwrap();
+            assert_eq!(result, UnlockResult::InvalidPassword);
+        }
+
+        // 6th attempt should be rate limited
+        let result = auth.unlock("WrongPassword").await.unwrap();
+        match result {
+            UnlockResult::RateLimited { wait_seconds } => {
+                assert!(wait_seconds > 0 && wait_seconds <= 30);
+            }
+            _ => panic!("Expected rate limiting"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capability_token() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+
+        // No token before re-authentication
+        assert!(auth.check_capability("anything").await.is_err());
+
+        // Wrong password doesn't mint a token
+        assert!(auth
+            .authorize_sensitive_action("WrongPassword")
+            .await
+            .is_err());
+
+        assert!(auth.reauthenticate("WrongPassword").await.is_err());
+        assert!(auth.reauthenticate(password).await.is_ok());
+
+        let token = auth.authorize_sensitive_action(password).await.unwrap();
+        assert!(auth.check_capability(&token).await.is_ok());
+        assert!(auth.check_capability("forged").await.is_err());
+
+        // Locking drops the token
+        auth.lock().await.unwrap();
+        assert!(auth.check_capability(&token).await.is_err());
+        assert!(auth.authorize_sensitive_action(password).await.is_err());
+
+        // Recovery actions while locked take the password itself
+        assert!(auth.verify_while_locked("WrongPassword").await.is_err());
+        assert!(auth.verify_while_locked(password).await.is_ok());
+        assert!(auth.is_locked().await);
+    }
+
+    #[tokio::test]
+    async fn test_change_master_password() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        let new_password = "An0ther!Str0ng#Passw0rd";
+        auth.setup_master_password(password).await.unwrap();
+
+        let key = auth.get_master_key().await.unwrap();
+        let encrypted =
+            format::encrypt_record(format::Record::Connection, "a", b"secret", &key).unwrap();
+        auth.db
+            .create_connection(
+                "a",
+                "a",
+                "ssh",
+                "example.com",
+                22,
+                "root",
+                &encrypted.data,
+                &encrypted.nonce,
+                "password",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                1,
+                1,
+            )
+            .await
+            .unwrap();
+        let token = auth.authorize_sensitive_action(password).await.unwrap();
+
+        // Wrong current password, weak or unchanged new password
+        assert!(auth
+            .change_master_password("WrongPassword", new_password)
+            .await
+            .is_err());
+        assert!(auth.change_master_password(password, "weak").await.is_err());
+        assert!(auth
+            .change_master_password(password, password)
+            .await
+            .is_err());
+
+        // Calibrated KDF parameters are applied on the change
+        assert_eq!(
+            auth.db.get_kdf_params().await.unwrap(),
+            KdfParams::default()
+        );
+        let kdf_params = KdfParams {
+            iterations: 3,
+            ..KdfParams::default()
+        };
+        auth.db
+            .set_setting(
+                SETTING_KDF_PARAMS,
+                &serde_json::to_string(&kdf_params).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        auth.change_master_password(password, new_password)
+            .await
+            .unwrap();
+        assert!(auth.check_capability(&token).await.is_err());
+        assert_eq!(auth.db.get_kdf_params().await.unwrap(), kdf_params);
+
+        // Credentials are readable with the new key only
+        let row = auth.db.get_connection("a").await.unwrap().unwrap();
+        let decrypt = |key: &MasterKey| {
+            format::decrypt_record(
+                format::CURRENT_FORMAT,
+                format::Record::Connection,
+                "a",
+                &row.encrypted_credentials,
+                &row.nonce,
+                key,
+            )
+        };
+        assert!(decrypt(&key).is_err());
+        let new_key = auth.get_master_key().await.unwrap();
+        assert_eq!(decrypt(&new_key).unwrap(), b"secret");
+
+        auth.lock().await.unwrap();
+        assert_eq!(
+            auth.unlock(password).await.unwrap(),
+            UnlockResult::InvalidPassword
+        );
+        assert_eq!(
+            auth.unlock(new_password).await.unwrap(),
+            UnlockResult::Success
+        );
+    }
+
+    #[derive(Default)]
+    struct TestRekey {
+        fail: bool,
+        calls: Vec<&'static str>,
+    }
+
+    impl Rekey for TestRekey {
+        fn prepare(&mut self, old_key: &MasterKey, new_key: &MasterKey) -> Result<()> {
+            assert_ne!(old_key.as_bytes(), new_key.as_bytes());
+            self.calls.push("prepare");
+            if self.fail {
+                return Err(anyhow!("Disk full"));
+            }
+            Ok(())
+        }
+
+        fn commit(&mut self) -> Result<()> {
+            self.calls.push("commit");
+            Ok(())
+        }
+
+        fn abort(&mut self) {
+            self.calls.push("abort");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_change_master_password_rekey() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        let new_password = "An0ther!Str0ng#Passw0rd";
+        auth.setup_master_password(password).await.unwrap();
+
+        // A failed re-encryption leaves the password unchanged
+        let mut files = TestRekey::default();
+        let mut failing = TestRekey {
+            fail: true,
+            ..TestRekey::default()
+        };
+        assert!(auth
+            .change_master_password_with(password, new_password, &mut [&mut files, &mut failing])
+            .await
+            .is_err());
+        assert_eq!(files.calls, ["prepare", "abort"]);
+        assert_eq!(failing.calls, ["prepare", "abort"]);
+        assert!(auth.reauthenticate(password).await.is_ok());
+
+        let mut files = TestRekey::default();
+        auth.change_master_password_with(password, new_password, &mut [&mut files])
+            .await
+            .unwrap();
+        assert_eq!(files.calls, ["prepare", "commit"]);
+        assert!(auth.reauthenticate(new_password).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_key() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+        let (_, salt) = auth.db.get_master_password().await.unwrap().unwrap();
+        let key = auth.get_master_key().await.unwrap();
+        let saved = || MasterKey::from_bytes(key.as_bytes()).unwrap();
+
+        let encrypted =
+            format::encrypt_record(format::Record::Connection, "a", b"secret", &key).unwrap();
+        auth.db
+            .create_connection(
+                "a",
+                "a",
+                "ssh",
+                "example.com",
+                22,
+                "root",
+                &encrypted.data,
+                &encrypted.nonce,
+                "password",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                1,
+                1,
+            )
+            .await
+            .unwrap();
+        auth.lock().await.unwrap();
+
+        // Wrong salt (password changed) or wrong key
+        assert!(auth.unlock_with_key(saved(), &[0u8; 16]).await.is_err());
+        let other = MasterKey::derive("other", &salt).unwrap();
+        assert!(auth.unlock_with_key(other, &salt).await.is_err());
+        assert!(auth.is_locked().await);
+
+        auth.unlock_with_key(saved(), &salt).await.unwrap();
+        assert!(!auth.is_locked().await);
+    }
+
+    #[tokio::test]
+    async fn test_database_reset() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+
+        // Reset database
+        auth.reset_database().await.unwrap();
+
+        // Should be first run again
+        assert!(auth.is_first_run().await.unwrap());
+        assert!(auth.is_locked().await);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_stats() {
+        let (auth, _temp) = create_test_auth().await;
+
+        let password = "MyStr0ng!P@ssw0rd#2024";
+        auth.setup_master_password(password).await.unwrap();
+        auth.lock().await.unwrap();
+
+        // Make some attempts
+        auth.unlock("wrong").await.unwrap();
+        auth.unlock("wrong2").await.unwrap();
+        auth.unlock(password).await.unwrap();
+
+        let stats = auth.get_unlock_stats().await.unwrap();
+        assert_eq!(stats.total_attempts_last_hour, 3);
+        assert_eq!(stats.successful_attempts, 1);
+        assert_eq!(stats.failed_attempts, 2);
+    }
+}
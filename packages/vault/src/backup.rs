@@ -0,0 +1,678 @@
+//! Vault backups
+//!
+//! Backups are `VACUUM INTO` snapshots of the vault database, kept in the
+//! `backups` directory next to it. Manual and scheduled backups are
+//! encrypted with the export subkey of the master key (see
+//! `rite_crypto::stream`), so a copied backup file doesn't even leak the
+//! hostnames and usernames the database holds in clear. The automatic
+//! `pre_*` backups (before migrations, format upgrades, resets and
+//! restores) stay plain SQLite files.
+//!
+//! Encrypted layout: `RITB` magic | version (1 byte) | salt length (1 byte)
+//! | salt | KDF parameters length (u16 BE) | KDF parameters (JSON), then
+//! the encrypted stream. The salt and parameters are those of the master key
+//! at backup time, so the master password of that time is all a restore
+//! needs, even after the password has changed.
+
+use anyhow::{anyhow, Context, Result};
+use rite_crypto::{decrypt_stream, encrypt_stream, subkey, KdfParams, MasterKey};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, Row, SqliteConnection};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::db::Database;
+use crate::format::CURRENT_FORMAT;
+
+/// Setting: automatic backup schedule (`off`, `daily`, `weekly`)
+pub const SETTING_SCHEDULE: &str = "backup_schedule";
+
+/// Setting: number of scheduled backups kept
+pub const SETTING_RETENTION: &str = "backup_retention";
+
+/// Scheduled backups kept when the retention setting is missing
+pub const DEFAULT_RETENTION: usize = 7;
+
+/// Label of backups made on request
+pub const LABEL_MANUAL: &str = "manual";
+
+/// Label of backups made by the schedule (the only ones pruned)
+pub const LABEL_SCHEDULED: &str = "scheduled";
+
+/// Magic bytes opening an encrypted backup
+const BACKUP_MAGIC: &[u8; 4] = b"RITB";
+
+/// Backup layout written by this version
+const BACKUP_VERSION: u8 = 1;
+
+/// Extension of encrypted backups (plain ones are `.db`)
+const ENCRYPTED_EXTENSION: &str = "ritebackup";
+
+/// Timestamp in backup file names (UTC)
+const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Tables replaced by a restore
+///
+/// Unlock attempts are kept (rate limiting outlives the restore) and the
+/// schema version must already match.
+const RESTORED_TABLES: &[&str] = &[
+    "master_password",
+    "settings",
+    "folders",
+    "connections",
+    "tags",
+    "connection_tags",
+    "connection_history",
+    "connection_templates",
+    "snippets",
+    "remote_tasks",
+    "remote_task_targets",
+    "remote_task_runs",
+    "known_hosts",
+    "audit_log",
+    "vault_format",
+];
+
+/// How often scheduled backups are made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupSchedule {
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl BackupSchedule {
+    /// Parse a schedule setting value
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "off" => Ok(Self::Off),
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            other => Err(anyhow!("Invalid backup schedule: {}", other)),
+        }
+    }
+
+    /// Time between two scheduled backups (None = no automatic backups)
+    pub fn interval(self) -> Option<chrono::Duration> {
+        match self {
+            Self::Off => None,
+            Self::Daily => Some(chrono::Duration::days(1)),
+            Self::Weekly => Some(chrono::Duration::weeks(1)),
+        }
+    }
+}
+
+/// Backup file found in the backups directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub file_name: String,
+    /// Why it was made (`manual`, `scheduled`, `pre_restore`, ...)
+    pub label: String,
+    /// Backup time (milliseconds since epoch)
+    pub created_at: i64,
+    pub size: u64,
+    /// Encrypted with the master key (plain SQLite file otherwise)
+    pub encrypted: bool,
+}
+
+/// Write an encrypted backup of the vault (`backups/vault_<label>_<timestamp>.ritebackup`)
+///
+/// `master_key` must be the key of the vault as it is now.
+pub async fn create(db: &Database, master_key: &MasterKey, label: &str) -> Result<BackupInfo> {
+    let (_, salt) = db
+        .get_master_password()
+        .await?
+        .ok_or_else(|| anyhow!("No master password set"))?;
+    let kdf_params = db.get_kdf_params().await?;
+
+    let dir = db.backup_dir()?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .context("Failed to create backup directory")?;
+
+    let timestamp = chrono::Utc::now().format(TIMESTAMP_FORMAT);
+    let file_name = format!("vault_{}_{}.{}", label, timestamp, ENCRYPTED_EXTENSION);
+    let path = dir.join(&file_name);
+    if path.exists() {
+        return Err(anyhow!("Backup already exists: {}", file_name));
+    }
+
+    // The snapshot is no more exposed than the vault file it's copied from,
+    // and only lives until it's encrypted
+    let snapshot = dir.join(format!(".{}.tmp", file_name));
+    remove_if_exists(&snapshot)?;
+    crate::encryption::export_plain(db, &snapshot).await?;
+
+    let key = master_key.derive_subkey(subkey::EXPORT);
+    let (source, target) = (snapshot.clone(), path.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        encrypt_file(&key, &salt, &kdf_params, &source, &target)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    remove_if_exists(&snapshot)?;
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&path);
+        return Err(e.context("Failed to encrypt backup"));
+    }
+
+    info!("Encrypted backup written to {}", path.display());
+    backup_info(&path)?.ok_or_else(|| anyhow!("Backup not found after writing it"))
+}
+
+/// Backups in the backups directory, newest first
+pub async fn list(db: &Database) -> Result<Vec<BackupInfo>> {
+    let dir = db.backup_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&dir).context("Failed to read backup directory")? {
+        if let Some(info) = backup_info(&entry?.path())? {
+            backups.push(info);
+        }
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// Delete scheduled backups beyond the newest `keep`
+///
+/// Returns the number of backups deleted. Manual and `pre_*` backups are
+/// never pruned.
+pub async fn prune(db: &Database, keep: usize) -> Result<usize> {
+    let stale: Vec<BackupInfo> = list(db)
+        .await?
+        .into_iter()
+        .filter(|backup| backup.label == LABEL_SCHEDULED)
+        .skip(keep.max(1))
+        .collect();
+
+    for backup in &stale {
+        std::fs::remove_file(&backup.path)
+            .with_context(|| format!("Failed to delete backup {}", backup.file_name))?;
+    }
+    if !stale.is_empty() {
+        info!("Pruned {} scheduled backup(s)", stale.len());
+    }
+    Ok(stale.len())
+}
+
+/// Automatic backup schedule setting
+pub async fn schedule(db: &Database) -> Result<BackupSchedule> {
+    match db.get_setting(SETTING_SCHEDULE).await? {
+        Some(value) => BackupSchedule::parse(&value),
+        None => Ok(BackupSchedule::Off),
+    }
+}
+
+/// Scheduled backups kept (retention setting)
+pub async fn retention(db: &Database) -> Result<usize> {
+    match db.get_setting(SETTING_RETENTION).await? {
+        Some(value) => value
+            .parse()
+            .map_err(|_| anyhow!("Invalid backup retention: {}", value)),
+        None => Ok(DEFAULT_RETENTION),
+    }
+}
+
+/// Make a scheduled backup if one is due, then prune old ones
+///
+/// Returns the new backup, or None when the schedule is off or the last
+/// scheduled backup is recent enough.
+pub async fn run_scheduled(db: &Database, master_key: &MasterKey) -> Result<Option<BackupInfo>> {
+    let Some(interval) = schedule(db).await?.interval() else {
+        return Ok(None);
+    };
+
+    let last = list(db)
+        .await?
+        .into_iter()
+        .find(|backup| backup.label == LABEL_SCHEDULED);
+    let now = chrono::Utc::now().timestamp_millis();
+    if last.is_some_and(|last| now - last.created_at < interval.num_milliseconds()) {
+        return Ok(None);
+    }
+
+    let backup = create(db, master_key, LABEL_SCHEDULED).await?;
+    prune(db, retention(db).await?).await?;
+    Ok(Some(backup))
+}
+
+/// Replace the vault contents with a backup
+///
+/// `password` is the master password of the backed up vault. The current
+/// contents are saved first (`pre_restore` backup). The master key in memory
+/// belongs to the replaced vault: the caller must lock afterwards.
+pub async fn restore(db: &Database, path: &Path, password: &str) -> Result<()> {
+    if db.cipher().is_some_and(|cipher| !cipher.has_key()) {
+        return Err(anyhow!("Database is locked"));
+    }
+
+    let dir = db.backup_dir()?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .context("Failed to create backup directory")?;
+    let snapshot = dir.join(".restore.tmp");
+    remove_if_exists(&snapshot)?;
+
+    let encrypted = is_encrypted(path)?;
+    let result = async {
+        if encrypted {
+            let password = Zeroizing::new(password.to_string());
+            let (source, target) = (path.to_path_buf(), snapshot.clone());
+            tokio::task::spawn_blocking(move || decrypt_file(&password, &source, &target))
+                .await??;
+        } else {
+            std::fs::copy(path, &snapshot).context("Failed to read backup")?;
+        }
+        // Decryption already proved the password of an encrypted backup
+        restore_snapshot(db, &snapshot, password, !encrypted).await
+    }
+    .await;
+
+    remove_if_exists(&snapshot)?;
+    result?;
+    info!("Vault restored from {}", path.display());
+    Ok(())
+}
+
+/// Copy the tables of a decrypted backup over the vault, in one transaction
+async fn restore_snapshot(
+    db: &Database,
+    snapshot: &Path,
+    password: &str,
+    verify_password: bool,
+) -> Result<()> {
+    let schema_version = db.get_schema_version().await?;
+
+    let mut conn = db.pool().acquire().await?;
+    crate::encryption::attach(db, &mut conn, snapshot, "backup")
+        .await
+        .context("Failed to open backup")?;
+
+    let result = async {
+        let (hash, salt, kdf_params) = check_snapshot(&mut conn, schema_version).await?;
+        if verify_password {
+            let password = Zeroizing::new(password.to_string());
+            let hash = hash.clone();
+            let valid =
+                tokio::task::spawn_blocking(move || MasterKey::verify(&password, &hash)).await??;
+            if !valid {
+                return Err(anyhow!("Invalid password"));
+            }
+        }
+
+        // Keep what's about to be replaced
+        db.create_timestamped_backup("pre_restore").await?;
+
+        let mut tx = conn.begin().await?;
+        // Subfolders can be copied before their parent
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+            .await?;
+        for table in RESTORED_TABLES {
+            copy_table(&mut tx, table).await?;
+        }
+        tx.commit().await?;
+
+        // The database key of an encrypted vault is now opened with the
+        // restored master password
+        if db.is_encrypted() {
+            let password = Zeroizing::new(password.to_string());
+            let master_key = tokio::task::spawn_blocking({
+                let salt = salt.clone();
+                move || MasterKey::derive_with_params(&password, &salt, &kdf_params)
+            })
+            .await??;
+            db.rewrap_key(&master_key, &hash, &salt, &kdf_params)
+                .await?;
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+
+    if let Err(e) = sqlx::query("DETACH DATABASE backup")
+        .execute(&mut *conn)
+        .await
+    {
+        warn!("Failed to detach backup: {}", e);
+    }
+    result
+}
+
+/// Check that an attached backup is a vault this version can restore
+///
+/// Returns its master password hash, key derivation salt and parameters.
+async fn check_snapshot(
+    conn: &mut SqliteConnection,
+    schema_version: i64,
+) -> Result<(String, Vec<u8>, KdfParams)> {
+    let backup_schema: i64 = sqlx::query_scalar("SELECT MAX(version) FROM backup.schema_version")
+        .fetch_one(&mut *conn)
+        .await
+        .context("Not a RITE vault backup")?;
+    if backup_schema != schema_version {
+        return Err(anyhow!(
+            "Backup schema version ({}) doesn't match the vault ({})",
+            backup_schema,
+            schema_version
+        ));
+    }
+
+    let format: u32 = sqlx::query_scalar("SELECT version FROM backup.vault_format WHERE id = 1")
+        .fetch_optional(&mut *conn)
+        .await
+        .context("Not a RITE vault backup")?
+        .unwrap_or(crate::format::MIN_SUPPORTED_FORMAT);
+    if format > CURRENT_FORMAT {
+        return Err(anyhow!(
+            "Backup vault format ({}) is newer than application supports ({})",
+            format,
+            CURRENT_FORMAT
+        ));
+    }
+
+    let row = sqlx::query("SELECT hash, salt, kdf_params FROM backup.master_password WHERE id = 1")
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| anyhow!("Backup has no master password"))?;
+    let kdf_params = match row.get::<Option<String>, _>("kdf_params") {
+        Some(params) => serde_json::from_str(&params).context("Invalid backup KDF parameters")?,
+        None => KdfParams::default(),
+    };
+    Ok((row.get("hash"), row.get("salt"), kdf_params))
+}
+
+/// Replace a table with its copy in the attached backup
+///
+/// Columns are named from the vault's table, so a backup missing one fails
+/// instead of shifting values into the wrong columns.
+async fn copy_table(conn: &mut SqliteConnection, table: &str) -> Result<()> {
+    let columns: Vec<String> = sqlx::query("SELECT name FROM pragma_table_info(?1, 'main')")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| format!("\"{}\"", row.get::<String, _>("name")))
+        .collect();
+    let columns = columns.join(", ");
+
+    sqlx::query(&format!("DELETE FROM main.{}", table))
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(&format!(
+        "INSERT INTO main.{table} ({columns}) SELECT {columns} FROM backup.{table}"
+    ))
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to restore {}", table))?;
+    Ok(())
+}
+
+/// Encrypt a database snapshot into a backup file
+fn encrypt_file(
+    key: &MasterKey,
+    salt: &[u8],
+    kdf_params: &KdfParams,
+    source: &Path,
+    target: &Path,
+) -> Result<()> {
+    let params = serde_json::to_vec(kdf_params)?;
+    let salt_len = u8::try_from(salt.len()).map_err(|_| anyhow!("Salt too long"))?;
+    let params_len = u16::try_from(params.len()).map_err(|_| anyhow!("KDF parameters too long"))?;
+
+    let mut writer = BufWriter::new(File::options().write(true).create_new(true).open(target)?);
+    writer.write_all(BACKUP_MAGIC)?;
+    writer.write_all(&[BACKUP_VERSION, salt_len])?;
+    writer.write_all(salt)?;
+    writer.write_all(&params_len.to_be_bytes())?;
+    writer.write_all(&params)?;
+
+    encrypt_stream(key, BufReader::new(File::open(source)?), &mut writer)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+/// Decrypt a backup file with the master password it was made with
+fn decrypt_file(password: &str, source: &Path, target: &Path) -> Result<()> {
+    let mut reader = BufReader::new(File::open(source).context("Failed to open backup")?);
+
+    let mut header = [0u8; 6];
+    reader
+        .read_exact(&mut header)
+        .context("Not a RITE backup")?;
+    if &header[..4] != BACKUP_MAGIC {
+        return Err(anyhow!("Not a RITE backup"));
+    }
+    if header[4] != BACKUP_VERSION {
+        return Err(anyhow!("Unsupported backup version: {}", header[4]));
+    }
+    let mut salt = vec![0u8; header[5] as usize];
+    reader.read_exact(&mut salt)?;
+    let mut params_len = [0u8; 2];
+    reader.read_exact(&mut params_len)?;
+    let mut params = vec![0u8; u16::from_be_bytes(params_len) as usize];
+    reader.read_exact(&mut params)?;
+    let kdf_params: KdfParams =
+        serde_json::from_slice(&params).context("Invalid backup KDF parameters")?;
+    kdf_params.validate()?;
+
+    let key =
+        MasterKey::derive_with_params(password, &salt, &kdf_params)?.derive_subkey(subkey::EXPORT);
+    let mut writer = BufWriter::new(File::create(target)?);
+    decrypt_stream(&key, reader, &mut writer)
+        .map_err(|_| anyhow!("Invalid password or corrupted backup"))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Whether a file is an encrypted backup (as opposed to a plain SQLite file)
+fn is_encrypted(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path).context("Failed to open backup")?;
+    Ok(file.read_exact(&mut magic).is_ok() && &magic == BACKUP_MAGIC)
+}
+
+/// Describe a backup file from its name (`vault_<label>_<timestamp>.<ext>`)
+///
+/// Returns None for anything else in the directory.
+fn backup_info(path: &Path) -> Result<Option<BackupInfo>> {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let Some((label, created_at, encrypted)) = parse_file_name(file_name) else {
+        return Ok(None);
+    };
+    Ok(Some(BackupInfo {
+        path: path.to_path_buf(),
+        file_name: file_name.to_string(),
+        label,
+        created_at,
+        size: std::fs::metadata(path)?.len(),
+        encrypted,
+    }))
+}
+
+fn parse_file_name(file_name: &str) -> Option<(String, i64, bool)> {
+    let (stem, encrypted) = match file_name.rsplit_once('.')? {
+        (stem, "db") => (stem, false),
+        (stem, ENCRYPTED_EXTENSION) => (stem, true),
+        _ => return None,
+    };
+    let rest = stem.strip_prefix("vault_")?;
+    // `<label>_<YYYYmmdd>_<HHMMSS>`
+    let split = rest.len().checked_sub("_YYYYmmdd_HHMMSS".len())?;
+    let (label, timestamp) = (rest.get(..split)?, rest.get(split + 1..)?);
+    let created_at = chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()?
+        .and_utc()
+        .timestamp_millis();
+    (!label.is_empty()).then(|| (label.to_string(), created_at, encrypted))
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{connection_input, create_test_vault, PASSWORD};
+    use crate::{ConnectionInfo, Vault};
+
+    async fn add_connection(vault: &Vault, name: &str) {
+        vault
+            .create_connection(connection_input(name, None))
+            .await
+            .unwrap();
+    }
+
+    async fn names(vault: &Vault) -> Vec<String> {
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        let mut names: Vec<String> = connections.into_iter().map(|c| c.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let (vault, _temp) = create_test_vault().await;
+        add_connection(&vault, "web").await;
+
+        let backup = vault.backup().await.unwrap();
+        assert!(backup.encrypted);
+        assert_eq!(backup.label, LABEL_MANUAL);
+        // Nothing readable in the file
+        let content = std::fs::read(&backup.path).unwrap();
+        assert!(content.starts_with(BACKUP_MAGIC));
+        assert!(!content.windows(15).any(|w| w == b"web.example.com"));
+
+        add_connection(&vault, "db").await;
+        assert_eq!(names(&vault).await, ["db", "web"]);
+
+        assert!(vault.restore(&backup.path, "wrong password").await.is_err());
+        assert_eq!(names(&vault).await, ["db", "web"]);
+
+        vault.restore(&backup.path, PASSWORD).await.unwrap();
+        assert!(vault.is_locked().await);
+        vault.unlock(PASSWORD).await.unwrap();
+        assert_eq!(names(&vault).await, ["web"]);
+
+        // The replaced contents were kept
+        let backups = vault.list_backups().await.unwrap();
+        assert!(backups
+            .iter()
+            .any(|b| b.label == "pre_restore" && !b.encrypted));
+        assert!(!vault
+            .database()
+            .backup_dir()
+            .unwrap()
+            .join(".restore.tmp")
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_after_password_change() {
+        let (vault, _temp) = create_test_vault().await;
+        add_connection(&vault, "web").await;
+        let backup = vault.backup().await.unwrap();
+
+        let new_password = "An0ther!Str0ng#Passw0rd";
+        vault
+            .change_master_password(PASSWORD, new_password)
+            .await
+            .unwrap();
+
+        // The backup opens with the password it was made with
+        assert!(vault.restore(&backup.path, new_password).await.is_err());
+        vault.restore(&backup.path, PASSWORD).await.unwrap();
+        vault.unlock(PASSWORD).await.unwrap();
+        assert_eq!(names(&vault).await, ["web"]);
+    }
+
+    #[tokio::test]
+    async fn test_restore_plain_backup() {
+        let (vault, _temp) = create_test_vault().await;
+        add_connection(&vault, "web").await;
+        let path = vault
+            .database()
+            .create_timestamped_backup("pre_test")
+            .await
+            .unwrap();
+        add_connection(&vault, "db").await;
+
+        assert!(vault.restore(&path, "wrong password").await.is_err());
+        vault.restore(&path, PASSWORD).await.unwrap();
+        vault.unlock(PASSWORD).await.unwrap();
+        assert_eq!(names(&vault).await, ["web"]);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backups() {
+        let (vault, _temp) = create_test_vault().await;
+        let db = vault.database();
+        let master_key = vault.auth().get_master_key().await.unwrap();
+
+        // Off by default
+        assert!(run_scheduled(db, &master_key).await.unwrap().is_none());
+
+        db.set_setting(SETTING_SCHEDULE, "daily").await.unwrap();
+        assert!(run_scheduled(db, &master_key).await.unwrap().is_some());
+        // Not due yet
+        assert!(run_scheduled(db, &master_key).await.unwrap().is_none());
+
+        // Older scheduled backups beyond the retention are pruned
+        let dir = db.backup_dir().unwrap();
+        for day in 1..=3 {
+            let name = format!("vault_scheduled_2020010{}_120000.ritebackup", day);
+            std::fs::write(dir.join(name), b"RITB").unwrap();
+        }
+        std::fs::write(dir.join("vault_manual_20200101_120000.ritebackup"), b"RITB").unwrap();
+        assert_eq!(prune(db, 2).await.unwrap(), 2);
+
+        let backups = list(db).await.unwrap();
+        let scheduled: Vec<_> = backups
+            .iter()
+            .filter(|b| b.label == LABEL_SCHEDULED)
+            .collect();
+        assert_eq!(scheduled.len(), 2);
+        assert_eq!(
+            scheduled[1].file_name,
+            "vault_scheduled_20200103_120000.ritebackup"
+        );
+        assert!(backups.iter().any(|b| b.label == LABEL_MANUAL));
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(
+            parse_file_name("vault_pre_migration_20240102_030405.db"),
+            Some(("pre_migration".to_string(), 1704164645000, false))
+        );
+        assert_eq!(
+            parse_file_name("vault_scheduled_20240102_030405.ritebackup"),
+            Some(("scheduled".to_string(), 1704164645000, true))
+        );
+        assert_eq!(
+            parse_file_name(".vault_manual_20240102_030405.ritebackup.tmp"),
+            None
+        );
+        assert_eq!(parse_file_name("vault_20240102_030405.db"), None);
+        assert_eq!(parse_file_name("notes.txt"), None);
+    }
+}
@@ -0,0 +1,926 @@
+/**
+ * Connection Management Module
+ *
+ * Handles SSH connection data with encrypted credentials
+ */
+use anyhow::Result;
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::db::ConnectionRow;
+use crate::fingerprint;
+use crate::format::{self, Record, CURRENT_FORMAT};
+use crate::tag;
+use rite_crypto::MasterKey;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Per-connection session options, stored as JSON next to the connection
+///
+/// The vault doesn't interpret them: the desktop app plugs in its own
+/// `SessionOptions`, other tools can keep the raw `serde_json::Value`.
+pub trait SessionOptionsData:
+    Clone + Default + fmt::Debug + Send + Sync + 'static + Serialize + DeserializeOwned
+{
+}
+
+impl<T> SessionOptionsData for T where
+    T: Clone + Default + fmt::Debug + Send + Sync + 'static + Serialize + DeserializeOwned
+{
+}
+
+/// Parse session options from the database column (NULL or invalid JSON = defaults)
+fn options_from_json<O: SessionOptionsData>(json: Option<&str>) -> O {
+    match json {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(json).unwrap_or_else(|e| {
+            tracing::warn!("Invalid session options, using defaults: {}", e);
+            O::default()
+        }),
+        _ => O::default(),
+    }
+}
+
+/// Connection protocol type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Protocol {
+    SSH,
+    SFTP,
+    Local,
+    /// Plain FTP (file browser only)
+    FTP,
+    /// FTP over explicit TLS (file browser only)
+    FTPS,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Protocol::SSH => "ssh",
+            Protocol::SFTP => "sftp",
+            Protocol::Local => "local",
+            Protocol::FTP => "ftp",
+            Protocol::FTPS => "ftps",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ssh" => Ok(Protocol::SSH),
+            "sftp" => Ok(Protocol::SFTP),
+            "local" => Ok(Protocol::Local),
+            "ftp" => Ok(Protocol::FTP),
+            "ftps" => Ok(Protocol::FTPS),
+            _ => Err(anyhow::anyhow!("Invalid protocol: {}", s)),
+        }
+    }
+}
+
+/// Authentication method for SSH
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AuthMethod {
+    Password {
+        password: String,
+    },
+    PublicKey {
+        key_path: String,
+        passphrase: Option<String>,
+    },
+    /// Private key kept in the vault (encrypted with the other credentials),
+    /// so it travels with vault backups
+    StoredKey {
+        /// Key file content (OpenSSH, PKCS#8, PEM RSA or PuTTY .ppk)
+        private_key: String,
+        passphrase: Option<String>,
+    },
+}
+
+/// Wipes the secrets (password, passphrase, private key)
+impl Zeroize for AuthMethod {
+    fn zeroize(&mut self) {
+        match self {
+            AuthMethod::Password { password } => password.zeroize(),
+            AuthMethod::PublicKey { passphrase, .. } => passphrase.zeroize(),
+            AuthMethod::StoredKey {
+                private_key,
+                passphrase,
+            } => {
+                private_key.zeroize();
+                passphrase.zeroize();
+            }
+        }
+    }
+}
+
+impl AuthMethod {
+    /// Type stored in clear next to the credentials: "password",
+    /// "publicKey" or "storedKey"
+    pub fn auth_type(&self) -> &'static str {
+        match self {
+            AuthMethod::Password { .. } => "password",
+            AuthMethod::PublicKey { .. } => "publicKey",
+            AuthMethod::StoredKey { .. } => "storedKey",
+        }
+    }
+
+    /// Fingerprint of the key (see [`fingerprint`]), None for password
+    /// auth or when the key doesn't expose it
+    ///
+    /// For a key file, `<key_path>.pub` is read when it exists, else the
+    /// key file itself.
+    pub fn key_fingerprint(&self) -> Option<String> {
+        match self {
+            AuthMethod::Password { .. } => None,
+            AuthMethod::PublicKey { key_path, .. } => {
+                let path = expand_home(key_path);
+                let public = format!("{}.pub", path.display());
+                [std::path::PathBuf::from(public), path]
+                    .iter()
+                    .filter_map(|path| std::fs::read_to_string(path).ok())
+                    .find_map(|text| fingerprint::of_key(&text))
+            }
+            AuthMethod::StoredKey { private_key, .. } => fingerprint::of_key(private_key),
+        }
+    }
+}
+
+/// `~/...` relative to the home directory
+fn expand_home(path: &str) -> std::path::PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => std::path::PathBuf::from(path),
+    }
+}
+
+/// Encrypted credentials blob: the auth method's fields, plus the proxy
+/// password when there is one
+#[derive(Serialize)]
+struct StoredCredentials<'a> {
+    #[serde(flatten)]
+    auth_method: &'a AuthMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_password: Option<&'a str>,
+}
+
+/// Decrypted credentials blob (see `StoredCredentials`)
+#[derive(Deserialize)]
+struct Credentials {
+    #[serde(flatten)]
+    auth_method: AuthMethod,
+    #[serde(default)]
+    proxy_password: Option<String>,
+}
+
+/// Connection metadata (not encrypted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionMetadata {
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    /// Folder path (`Production/Databases`), derived from `folder_id`
+    pub folder: Option<String>,
+    pub notes: Option<String>,
+    /// Tag names
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Full connection data (for database storage)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<O = serde_json::Value> {
+    pub id: String,
+    pub name: String,
+    pub protocol: Protocol,
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_method: AuthMethod,
+    /// Password of the proxy the connection goes through (encrypted with
+    /// the credentials)
+    #[serde(default)]
+    pub proxy_password: Option<String>,
+    pub metadata: ConnectionMetadata,
+    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
+    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds, NULL = use global
+    pub session_options: O,
+    pub revision: i64, // Incremented on every stored update
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+/// Wipes the decrypted credentials, so a connection kept in memory can be
+/// held in `Zeroizing`
+impl<O> Zeroize for Connection<O> {
+    fn zeroize(&mut self) {
+        self.auth_method.zeroize();
+        self.proxy_password.zeroize();
+    }
+}
+
+/// Connection data for frontend (without sensitive credentials)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo<O = serde_json::Value> {
+    pub id: String,
+    pub name: String,
+    pub protocol: String,
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_type: String, // "password", "publicKey" or "storedKey"
+    /// SHA256 fingerprint of the key, None for password auth or when unknown
+    #[serde(default)]
+    pub key_fingerprint: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder_id: Option<String>,
+    pub folder: Option<String>, // Folder path, derived from folder_id
+    pub notes: Option<String>,
+    pub tags: Vec<String>,                       // Tag names
+    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
+    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds
+    pub session_options: O,
+    pub revision: i64, // Pass back in UpdateConnectionInput
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub last_used_at: Option<i64>,
+    /// In the trash since, None = not deleted
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
+}
+
+/// Input for creating a new connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateConnectionInput<O = serde_json::Value> {
+    pub name: String,
+    pub protocol: String,
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_method: AuthMethod,
+    #[serde(default)]
+    pub proxy_password: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    pub folder: Option<String>, // Folder path, created if missing (when no folder_id)
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>, // Tag names, created if missing
+    pub ssh_keep_alive_override: Option<String>, // NULL, "disabled", or "enabled"
+    pub ssh_keep_alive_interval: Option<i64>,    // Interval in seconds
+    pub session_options: Option<O>,
+}
+
+/// Input for updating a connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateConnectionInput<O = serde_json::Value> {
+    pub id: String,
+    pub name: Option<String>,
+    pub protocol: Option<String>,
+    pub hostname: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub auth_method: Option<AuthMethod>,
+    #[serde(default)]
+    pub proxy_password: Option<String>, // Empty string = remove
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    pub folder: Option<String>, // Folder path, created if missing (when no folder_id)
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>, // Replaces the tags (empty = none), created if missing
+    pub ssh_keep_alive_override: Option<Option<String>>, // Nested Option to allow setting to NULL
+    pub ssh_keep_alive_interval: Option<Option<i64>>,    // Nested Option to allow setting to NULL
+    pub session_options: Option<O>,
+    pub revision: i64, // Revision the update is based on (conflict if it changed since)
+}
+
+/// Error returned when a connection was updated since the caller read it
+///
+/// Carried inside the `anyhow::Error` (use `downcast_ref`), with both sides
+/// so the caller can merge deliberately instead of overwriting.
+#[derive(Debug)]
+pub struct UpdateConflict<O = serde_json::Value> {
+    /// Connection as currently stored
+    pub current: Connection<O>,
+    /// The rejected update (based on an older revision)
+    pub attempted: UpdateConnectionInput<O>,
+}
+
+impl<O> fmt::Display for UpdateConflict<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Connection was modified elsewhere (revision {}, update based on {})",
+            self.current.revision, self.attempted.revision
+        )
+    }
+}
+
+impl<O: fmt::Debug> std::error::Error for UpdateConflict<O> {}
+
+impl<O: SessionOptionsData> Connection<O> {
+    /// Create a new connection
+    pub fn new(input: CreateConnectionInput<O>) -> Result<Self> {
+        let now = Utc::now().timestamp_millis();
+        let protocol = Protocol::from_str(&input.protocol)?;
+
+        Ok(Connection {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            protocol,
+            hostname: input.hostname,
+            port: input.port,
+            username: input.username,
+            auth_method: input.auth_method,
+            proxy_password: input.proxy_password.filter(|p| !p.is_empty()),
+            metadata: ConnectionMetadata {
+                color: input.color,
+                icon: input.icon,
+                folder_id: input.folder_id,
+                folder: input.folder,
+                notes: input.notes,
+                tags: input.tags,
+            },
+            ssh_keep_alive_override: input.ssh_keep_alive_override,
+            ssh_keep_alive_interval: input.ssh_keep_alive_interval,
+            session_options: input.session_options.unwrap_or_default(),
+            revision: 1,
+            created_at: now,
+            updated_at: now,
+            last_used_at: None,
+        })
+    }
+
+    /// Input for a copy of the connection named `name`: same host,
+    /// credentials, folder, tags and options
+    pub fn duplicate_input(&self, name: String) -> CreateConnectionInput<O> {
+        CreateConnectionInput {
+            name,
+            protocol: self.protocol.as_str().to_string(),
+            hostname: self.hostname.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            auth_method: self.auth_method.clone(),
+            proxy_password: self.proxy_password.clone(),
+            color: self.metadata.color.clone(),
+            icon: self.metadata.icon.clone(),
+            folder_id: self.metadata.folder_id.clone(),
+            folder: None,
+            notes: self.metadata.notes.clone(),
+            tags: self.metadata.tags.clone(),
+            ssh_keep_alive_override: self.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: self.ssh_keep_alive_interval,
+            session_options: Some(self.session_options.clone()),
+        }
+    }
+
+    /// Build a connection (with decrypted credentials) from a database row
+    pub fn from_row(row: &ConnectionRow, master_key: &MasterKey) -> Result<Self> {
+        let (auth_method, proxy_password) =
+            Self::decrypt_credentials(&row.id, &row.encrypted_credentials, &row.nonce, master_key)?;
+
+        Ok(Connection {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            protocol: Protocol::from_str(&row.protocol)?,
+            hostname: row.hostname.clone(),
+            port: row.port as u16,
+            username: row.username.clone(),
+            auth_method,
+            proxy_password,
+            metadata: ConnectionMetadata {
+                color: row.color.clone(),
+                icon: row.icon.clone(),
+                folder_id: row.folder_id.clone(),
+                folder: row.folder.clone(),
+                notes: row.notes.clone(),
+                tags: tag::from_json(row.tags.as_deref()),
+            },
+            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
+            session_options: options_from_json(row.session_options.as_deref()),
+            revision: row.revision,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            last_used_at: row.last_used_at,
+        })
+    }
+
+    /// Session options as stored in the database (None when unset)
+    pub fn session_options_json(&self) -> Result<Option<String>> {
+        let value = serde_json::to_value(&self.session_options)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(value.to_string()))
+    }
+
+    /// Encrypt credentials for database storage (current vault format)
+    pub fn encrypt_credentials(&self, master_key: &MasterKey) -> Result<(Vec<u8>, Vec<u8>)> {
+        let credentials_json = Zeroizing::new(serde_json::to_string(&StoredCredentials {
+            auth_method: &self.auth_method,
+            proxy_password: self.proxy_password.as_deref(),
+        })?);
+        let encrypted = format::encrypt_record(
+            Record::Connection,
+            &self.id,
+            credentials_json.as_bytes(),
+            master_key,
+        )?;
+        Ok((encrypted.data, encrypted.nonce))
+    }
+
+    /// Decrypt credentials from database (current vault format): the auth
+    /// method and the proxy password
+    pub fn decrypt_credentials(
+        connection_id: &str,
+        encrypted_credentials: &[u8],
+        nonce: &[u8],
+        master_key: &MasterKey,
+    ) -> Result<(AuthMethod, Option<String>)> {
+        let decrypted = Zeroizing::new(format::decrypt_record(
+            CURRENT_FORMAT,
+            Record::Connection,
+            connection_id,
+            encrypted_credentials,
+            nonce,
+            master_key,
+        )?);
+        let credentials: Credentials = serde_json::from_slice(&decrypted)?;
+        Ok((credentials.auth_method, credentials.proxy_password))
+    }
+
+    /// Convert to ConnectionInfo (safe for frontend)
+    pub fn to_info(&self) -> ConnectionInfo<O> {
+        ConnectionInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            protocol: self.protocol.as_str().to_string(),
+            hostname: self.hostname.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            auth_type: self.auth_method.auth_type().to_string(),
+            key_fingerprint: self.auth_method.key_fingerprint(),
+            color: self.metadata.color.clone(),
+            icon: self.metadata.icon.clone(),
+            folder_id: self.metadata.folder_id.clone(),
+            folder: self.metadata.folder.clone(),
+            notes: self.metadata.notes.clone(),
+            tags: self.metadata.tags.clone(),
+            ssh_keep_alive_override: self.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: self.ssh_keep_alive_interval,
+            session_options: self.session_options.clone(),
+            revision: self.revision,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            last_used_at: self.last_used_at,
+            deleted_at: None,
+        }
+    }
+
+    /// Update connection with partial data
+    ///
+    /// Leaves `revision` alone; it's bumped when the update is stored.
+    pub fn update(&mut self, input: UpdateConnectionInput<O>) -> Result<()> {
+        if let Some(name) = input.name {
+            self.name = name;
+        }
+        if let Some(protocol) = input.protocol {
+            self.protocol = Protocol::from_str(&protocol)?;
+        }
+        if let Some(hostname) = input.hostname {
+            self.hostname = hostname;
+        }
+        if let Some(port) = input.port {
+            self.port = port;
+        }
+        if let Some(username) = input.username {
+            self.username = username;
+        }
+        if let Some(auth_method) = input.auth_method {
+            self.auth_method = auth_method;
+        }
+        if let Some(proxy_password) = input.proxy_password {
+            self.proxy_password = Some(proxy_password).filter(|p| !p.is_empty());
+        }
+        if let Some(color) = input.color {
+            self.metadata.color = Some(color);
+        }
+        if let Some(icon) = input.icon {
+            self.metadata.icon = Some(icon);
+        }
+        if let Some(folder_id) = input.folder_id {
+            // The path is looked up when the connection is stored
+            if self.metadata.folder_id.as_ref() != Some(&folder_id) {
+                self.metadata.folder = None;
+            }
+            self.metadata.folder_id = Some(folder_id);
+        }
+        if let Some(notes) = input.notes {
+            self.metadata.notes = Some(notes);
+        }
+        if let Some(tags) = input.tags {
+            self.metadata.tags = tags;
+        }
+        if let Some(ssh_keep_alive_override) = input.ssh_keep_alive_override {
+            self.ssh_keep_alive_override = ssh_keep_alive_override;
+        }
+        if let Some(ssh_keep_alive_interval) = input.ssh_keep_alive_interval {
+            self.ssh_keep_alive_interval = ssh_keep_alive_interval;
+        }
+        if let Some(session_options) = input.session_options {
+            self.session_options = session_options;
+        }
+
+        self.updated_at = Utc::now().timestamp_millis();
+        Ok(())
+    }
+}
+
+impl<O: SessionOptionsData> ConnectionInfo<O> {
+    /// Build connection info from a database row (credentials stay encrypted)
+    pub fn from_row(row: &ConnectionRow) -> Self {
+        ConnectionInfo {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            protocol: row.protocol.clone(),
+            hostname: row.hostname.clone(),
+            port: row.port as u16,
+            username: row.username.clone(),
+            auth_type: row.auth_type.clone(),
+            key_fingerprint: row.key_fingerprint.clone(),
+            color: row.color.clone(),
+            icon: row.icon.clone(),
+            folder_id: row.folder_id.clone(),
+            folder: row.folder.clone(),
+            notes: row.notes.clone(),
+            tags: tag::from_json(row.tags.as_deref()),
+            ssh_keep_alive_override: row.ssh_keep_alive_override.clone(),
+            ssh_keep_alive_interval: row.ssh_keep_alive_interval,
+            session_options: options_from_json(row.session_options.as_deref()),
+            revision: row.revision,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            last_used_at: row.last_used_at,
+            deleted_at: row.deleted_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use proptest::prelude::*;
+    use std::sync::OnceLock;
+
+    /// Session options stay opaque JSON outside the app
+    type Connection = super::Connection<serde_json::Value>;
+
+    /// Argon2 is too slow to run per case
+    fn master_key() -> &'static MasterKey {
+        static KEY: OnceLock<MasterKey> = OnceLock::new();
+        KEY.get_or_init(|| MasterKey::derive("proptest-password", &[3u8; 16]).unwrap())
+    }
+
+    /// AuthMethod has no PartialEq; compare the serialized form
+    fn same_auth(a: &AuthMethod, b: &AuthMethod) -> bool {
+        serde_json::to_value(a).unwrap() == serde_json::to_value(b).unwrap()
+    }
+
+    fn auth_method() -> impl Strategy<Value = AuthMethod> {
+        prop_oneof![
+            any::<String>().prop_map(|password| AuthMethod::Password { password }),
+            (any::<String>(), proptest::option::of(any::<String>())).prop_map(
+                |(key_path, passphrase)| AuthMethod::PublicKey {
+                    key_path,
+                    passphrase
+                }
+            ),
+            (any::<String>(), proptest::option::of(any::<String>())).prop_map(
+                |(private_key, passphrase)| AuthMethod::StoredKey {
+                    private_key,
+                    passphrase
+                }
+            ),
+        ]
+    }
+
+    fn keep_alive_override() -> impl Strategy<Value = Option<String>> {
+        proptest::option::of(prop_oneof![Just("enabled"), Just("disabled")].prop_map(String::from))
+    }
+
+    /// Printable text (what users type into the connection form)
+    fn text() -> impl Strategy<Value = String> {
+        "\\PC{0,40}"
+    }
+
+    fn create_input() -> impl Strategy<Value = CreateConnectionInput> {
+        (
+            text(),
+            prop_oneof![
+                Just("ssh"),
+                Just("sftp"),
+                Just("local"),
+                Just("ftp"),
+                Just("ftps")
+            ],
+            "[a-z0-9.-]{1,40}",
+            any::<u16>(),
+            text(),
+            auth_method(),
+            proptest::option::of(text()),
+            proptest::option::of("[a-f0-9]{32}"),
+            proptest::option::of(text()),
+            keep_alive_override(),
+            proptest::option::of(1i64..86_400),
+        )
+            .prop_map(
+                |(
+                    name,
+                    protocol,
+                    hostname,
+                    port,
+                    username,
+                    auth_method,
+                    color,
+                    folder_id,
+                    notes,
+                    ka,
+                    kai,
+                )| {
+                    CreateConnectionInput {
+                        name,
+                        protocol: protocol.to_string(),
+                        hostname,
+                        port,
+                        username,
+                        auth_method,
+                        proxy_password: None,
+                        color,
+                        icon: None,
+                        folder_id,
+                        folder: None,
+                        notes,
+                        tags: Vec::new(),
+                        ssh_keep_alive_override: ka,
+                        ssh_keep_alive_interval: kai,
+                        session_options: None,
+                    }
+                },
+            )
+    }
+
+    fn update_input() -> impl Strategy<Value = UpdateConnectionInput> {
+        (
+            proptest::option::of(any::<String>()),
+            proptest::option::of(prop_oneof![
+                Just("ssh"),
+                Just("sftp"),
+                Just("local"),
+                Just("ftp"),
+                Just("ftps")
+            ]),
+            proptest::option::of("[a-z0-9.-]{1,40}"),
+            proptest::option::of(any::<u16>()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(auth_method()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(keep_alive_override()),
+            proptest::option::of(proptest::option::of(1i64..86_400)),
+        )
+            .prop_map(
+                |(
+                    name,
+                    protocol,
+                    hostname,
+                    port,
+                    username,
+                    auth_method,
+                    folder_id,
+                    notes,
+                    ka,
+                    kai,
+                )| {
+                    UpdateConnectionInput {
+                        id: String::new(),
+                        name,
+                        protocol: protocol.map(String::from),
+                        hostname,
+                        port,
+                        username,
+                        auth_method,
+                        proxy_password: None,
+                        color: None,
+                        icon: None,
+                        folder_id,
+                        folder: None,
+                        notes,
+                        tags: None,
+                        ssh_keep_alive_override: ka,
+                        ssh_keep_alive_interval: kai,
+                        session_options: None,
+                        revision: 1,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn credentials_round_trip(auth in auth_method(), proxy_password in proptest::option::of(any::<String>())) {
+            let connection = Connection::new(CreateConnectionInput {
+                name: "test".to_string(),
+                protocol: "ssh".to_string(),
+                hostname: "example.com".to_string(),
+                port: 22,
+                username: "user".to_string(),
+                auth_method: auth.clone(),
+                proxy_password: proxy_password.clone(),
+                color: None,
+                icon: None,
+                folder_id: None,
+                folder: None,
+                notes: None,
+                tags: Vec::new(),
+                ssh_keep_alive_override: None,
+                ssh_keep_alive_interval: None,
+                session_options: None,
+            })
+            .unwrap();
+
+            let (encrypted, nonce) = connection.encrypt_credentials(master_key()).unwrap();
+            // The envelope carries its own nonce
+            prop_assert!(nonce.is_empty());
+            let (decrypted, decrypted_proxy_password) =
+                Connection::decrypt_credentials(&connection.id, &encrypted, &nonce, master_key()).unwrap();
+            prop_assert!(same_auth(&decrypted, &auth));
+            prop_assert_eq!(decrypted_proxy_password, proxy_password.filter(|p| !p.is_empty()));
+
+            // Credentials are bound to their connection
+            prop_assert!(Connection::decrypt_credentials("other", &encrypted, &nonce, master_key()).is_err());
+        }
+
+        #[test]
+        fn zeroize_wipes_credentials(input in create_input(), auth in auth_method()) {
+            let mut connection = Connection::new(CreateConnectionInput {
+                auth_method: auth,
+                proxy_password: Some("proxy-secret".to_string()),
+                ..input
+            })
+            .unwrap();
+            let name = connection.name.clone();
+            connection.zeroize();
+
+            prop_assert_eq!(connection.proxy_password, None);
+            let wiped = match connection.auth_method {
+                AuthMethod::Password { password } => password.is_empty(),
+                AuthMethod::PublicKey { passphrase, .. } => passphrase.is_none(),
+                AuthMethod::StoredKey { private_key, passphrase } => {
+                    private_key.is_empty() && passphrase.is_none()
+                }
+            };
+            prop_assert!(wiped);
+            // Only the secrets
+            prop_assert_eq!(connection.name, name);
+        }
+
+        #[test]
+        fn update_merges_only_provided_fields(input in create_input(), update in update_input()) {
+            let original = Connection::new(input).unwrap();
+            let mut updated = original.clone();
+            updated.update(update.clone()).unwrap();
+
+            prop_assert_eq!(&updated.id, &original.id);
+            prop_assert_eq!(updated.revision, original.revision);
+            prop_assert_eq!(&updated.name, update.name.as_ref().unwrap_or(&original.name));
+            prop_assert_eq!(
+                updated.protocol.as_str(),
+                update.protocol.as_deref().unwrap_or(original.protocol.as_str())
+            );
+            prop_assert_eq!(&updated.hostname, update.hostname.as_ref().unwrap_or(&original.hostname));
+            prop_assert_eq!(updated.port, update.port.unwrap_or(original.port));
+            prop_assert_eq!(&updated.username, update.username.as_ref().unwrap_or(&original.username));
+            prop_assert!(same_auth(
+                &updated.auth_method,
+                update.auth_method.as_ref().unwrap_or(&original.auth_method)
+            ));
+            // Metadata can be set but not cleared through update()
+            prop_assert_eq!(&updated.metadata.color, &original.metadata.color);
+            prop_assert_eq!(&updated.metadata.folder_id, &update.folder_id.clone().or(original.metadata.folder_id.clone()));
+            prop_assert_eq!(&updated.metadata.notes, &update.notes.clone().or(original.metadata.notes.clone()));
+            // Keep-alive settings use a nested Option so they can be reset to NULL
+            prop_assert_eq!(
+                &updated.ssh_keep_alive_override,
+                update.ssh_keep_alive_override.as_ref().unwrap_or(&original.ssh_keep_alive_override)
+            );
+            prop_assert_eq!(
+                updated.ssh_keep_alive_interval,
+                update.ssh_keep_alive_interval.unwrap_or(original.ssh_keep_alive_interval)
+            );
+            prop_assert_eq!(updated.created_at, original.created_at);
+            prop_assert!(updated.updated_at >= original.updated_at);
+        }
+
+        #[test]
+        fn duplicate_keeps_all_but_identity(input in create_input()) {
+            let original = Connection::new(input).unwrap();
+            let copy = Connection::new(original.duplicate_input("copy".to_string())).unwrap();
+
+            prop_assert_ne!(&copy.id, &original.id);
+            prop_assert_eq!(&copy.name, "copy");
+            prop_assert_eq!(copy.protocol.as_str(), original.protocol.as_str());
+            prop_assert_eq!(&copy.hostname, &original.hostname);
+            prop_assert_eq!(copy.port, original.port);
+            prop_assert_eq!(&copy.username, &original.username);
+            prop_assert!(same_auth(&copy.auth_method, &original.auth_method));
+            prop_assert_eq!(&copy.proxy_password, &original.proxy_password);
+            prop_assert_eq!(
+                serde_json::to_value(&copy.metadata).unwrap(),
+                serde_json::to_value(&original.metadata).unwrap()
+            );
+            prop_assert_eq!(&copy.ssh_keep_alive_override, &original.ssh_keep_alive_override);
+            prop_assert_eq!(copy.ssh_keep_alive_interval, original.ssh_keep_alive_interval);
+            prop_assert_eq!(&copy.session_options, &original.session_options);
+            prop_assert_eq!(copy.revision, 1);
+        }
+    }
+
+    proptest! {
+        // Each case creates a database
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn stored_connection_round_trip(input in create_input()) {
+            // Folders are referenced by ID (the path is looked up when read)
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let connection = Connection::new(input).unwrap();
+
+            let row = runtime.block_on(async {
+                let db = Database::new(&temp_dir.path().join("test.db")).await.unwrap();
+                if let Some(folder_id) = &connection.metadata.folder_id {
+                    db.create_folder(folder_id, "Folder", None, None, 0, 0).await.unwrap();
+                }
+                let (encrypted, nonce) = connection.encrypt_credentials(master_key()).unwrap();
+                let session_options = connection.session_options_json().unwrap();
+                db.create_connection(
+                    &connection.id,
+                    &connection.name,
+                    connection.protocol.as_str(),
+                    &connection.hostname,
+                    connection.port,
+                    &connection.username,
+                    &encrypted,
+                    &nonce,
+                    connection.auth_method.auth_type(),
+                    connection.auth_method.key_fingerprint().as_deref(),
+                    connection.metadata.color.as_deref(),
+                    connection.metadata.icon.as_deref(),
+                    connection.metadata.folder_id.as_deref(),
+                    connection.metadata.notes.as_deref(),
+                    connection.ssh_keep_alive_override.as_deref(),
+                    connection.ssh_keep_alive_interval,
+                    session_options.as_deref(),
+                    connection.created_at,
+                    connection.updated_at,
+                )
+                .await
+                .unwrap();
+                db.get_connection(&connection.id).await.unwrap().unwrap()
+            });
+
+            prop_assert_eq!(&row.name, &connection.name);
+            prop_assert_eq!(&row.protocol, connection.protocol.as_str());
+            prop_assert_eq!(&row.hostname, &connection.hostname);
+            prop_assert_eq!(row.port as u16, connection.port);
+            prop_assert_eq!(&row.username, &connection.username);
+            prop_assert_eq!(row.auth_type.as_str(), connection.auth_method.auth_type());
+            prop_assert_eq!(&row.color, &connection.metadata.color);
+            prop_assert_eq!(&row.folder_id, &connection.metadata.folder_id);
+            prop_assert_eq!(row.folder.is_some(), connection.metadata.folder_id.is_some());
+            prop_assert_eq!(&row.notes, &connection.metadata.notes);
+            prop_assert_eq!(&row.ssh_keep_alive_override, &connection.ssh_keep_alive_override);
+            prop_assert_eq!(row.ssh_keep_alive_interval, connection.ssh_keep_alive_interval);
+
+            let stored = Connection::from_row(&row, master_key()).unwrap();
+            prop_assert_eq!(&stored.session_options, &connection.session_options);
+            prop_assert!(same_auth(&stored.auth_method, &connection.auth_method));
+        }
+    }
+}
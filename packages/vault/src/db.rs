@@ -0,0 +1,2312 @@
+//! Database module
+//!
+//! Handles SQLite database initialization, migrations, and CRUD operations.
+
+use anyhow::{Context, Result};
+use rite_crypto::{KdfParams, MasterKey};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use sqlx::Row;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::encryption::Cipher;
+use crate::history::{ConnectionStats, ConnectionUsage, SessionRecord};
+
+/// Folder paths (`Production/Databases`), for the queries that need them
+const FOLDER_PATHS: &str = r#"
+    folder_paths(id, path) AS (
+        SELECT id, name FROM folders WHERE parent_id IS NULL
+        UNION ALL
+        SELECT f.id, p.path || '/' || f.name
+        FROM folders f JOIN folder_paths p ON f.parent_id = p.id
+    )
+"#;
+
+/// Tag names of connection `c` (JSON array, sorted)
+const CONNECTION_TAGS: &str = r#"
+    (SELECT json_group_array(name) FROM (
+        SELECT t.name FROM connection_tags ct JOIN tags t ON t.id = ct.tag_id
+        WHERE ct.connection_id = c.id ORDER BY t.name COLLATE NOCASE
+    ))
+"#;
+
+/// Tag rows with their connection count, trash left out (add WHERE, then
+/// GROUP BY t.id)
+const SELECT_TAGS: &str = "SELECT t.*, COUNT(ct.connection_id) AS connection_count FROM tags t \
+     LEFT JOIN connection_tags ct ON ct.tag_id = t.id \
+     AND ct.connection_id IN (SELECT id FROM connections WHERE deleted_at IS NULL)";
+
+/// Pool connections (WAL lets readers run next to the writer)
+const POOL_SIZE: u32 = 5;
+
+/// How long a statement waits for a lock held by another connection before
+/// failing with "database is locked"
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Prepared statements kept per pool connection
+const STATEMENT_CACHE_CAPACITY: usize = 256;
+
+/// SQL of a hot query, built once
+///
+/// Statements are prepared once per pool connection and cached by their
+/// SQL, so the hot paths also skip building it on every call.
+fn cached_sql(cell: &'static OnceLock<String>, build: impl FnOnce() -> String) -> &'static str {
+    cell.get_or_init(build)
+}
+
+/// Connection rows with the path of their folder and their tags (trash
+/// left out)
+fn select_connections(filter: &str) -> String {
+    select_connection_rows("deleted_at IS NULL", filter)
+}
+
+/// Connection rows in the trash, same columns as [`select_connections`]
+fn select_trashed_connections(filter: &str) -> String {
+    select_connection_rows("deleted_at IS NOT NULL", filter)
+}
+
+fn select_connection_rows(state: &str, filter: &str) -> String {
+    format!(
+        "WITH RECURSIVE {FOLDER_PATHS} \
+         SELECT c.*, p.path AS folder, {CONNECTION_TAGS} AS tags \
+         FROM (SELECT * FROM connections WHERE {state}) c \
+         LEFT JOIN folder_paths p ON p.id = c.folder_id {filter}"
+    )
+}
+
+/// Per-connection usage of the sessions started since ?1, `order` first, ?2 rows
+fn select_usage(order: &str) -> String {
+    format!(
+        "SELECT connection_id, COUNT(*) AS session_count, \
+         COALESCE(SUM(ended_at - started_at), 0) AS total_duration, \
+         MAX(started_at) AS last_used_at FROM connection_history \
+         WHERE started_at >= ?1 GROUP BY connection_id ORDER BY {order} LIMIT ?2"
+    )
+}
+
+/// Settings the unlock screen reads; an encrypted vault keeps them in its
+/// header, where they can be read while locked
+pub(crate) const HEADER_SETTINGS: &[&str] = &["biometric_unlock"];
+
+/// Database connection pool
+#[derive(Clone)]
+pub struct Database {
+    pool: SqlitePool,
+    db_path: std::path::PathBuf,
+    /// Key state when the vault file is encrypted (SQLCipher)
+    cipher: Option<Arc<Cipher>>,
+}
+
+impl Database {
+    /// Initialize database connection
+    ///
+    /// Creates the database file if it doesn't exist and runs migrations.
+    /// An encrypted database can't be read yet: migrations run on
+    /// [`Database::unlock`].
+    pub async fn new(db_path: &Path) -> Result<Self> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = db_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create database directory")?;
+        }
+
+        info!("Connecting to database at: {}", db_path.display());
+
+        let cipher = Cipher::open(db_path)?;
+
+        // Set up connection options. WAL with synchronous=NORMAL stays
+        // durable across app crashes (only a power loss can drop the last
+        // commits) and lets the tabs read while a write is in progress.
+        // The journal mode of an encrypted database is set once it is keyed
+        // (see `Cipher::configure`).
+        let mut options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT)
+            .foreign_keys(true)
+            .statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        if cipher.is_none() {
+            options = options.journal_mode(SqliteJournalMode::Wal);
+        }
+
+        // Create connection pool (connections to an encrypted database are
+        // opened once there is a key)
+        let pool_options = SqlitePoolOptions::new().max_connections(POOL_SIZE);
+        let pool = match &cipher {
+            Some(cipher) => cipher.configure(pool_options).connect_lazy_with(options),
+            None => pool_options
+                .connect_with(options)
+                .await
+                .context("Failed to connect to database")?,
+        };
+
+        let db = Self {
+            pool,
+            db_path: db_path.to_path_buf(),
+            cipher,
+        };
+
+        if db.is_encrypted() {
+            info!("Database is encrypted, opening it on unlock");
+            return Ok(db);
+        }
+
+        // Run migrations
+        db.run_migrations().await?;
+
+        // Check (or record) the vault format
+        crate::format::init(&db).await?;
+
+        Ok(db)
+    }
+
+    /// Get the database pool
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Path of the database file
+    pub fn path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// Whether the database file is encrypted (see [`crate::encryption`])
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub(crate) fn cipher(&self) -> Option<&Cipher> {
+        self.cipher.as_deref()
+    }
+
+    /// Open an encrypted database with the master key, then run pending
+    /// migrations (no-op for a plain database)
+    pub async fn unlock(&self, master_key: &MasterKey) -> Result<()> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(());
+        };
+        cipher.set_key(master_key)?;
+
+        if let Err(e) = sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+            .fetch_one(&self.pool)
+            .await
+        {
+            cipher.clear_key();
+            return Err(anyhow::Error::from(e).context("Failed to open the encrypted database"));
+        }
+
+        self.run_migrations().await?;
+        crate::format::init(self).await?;
+        Ok(())
+    }
+
+    /// Drop the key of an encrypted database (no-op for a plain database)
+    pub fn lock(&self) {
+        if let Some(cipher) = &self.cipher {
+            cipher.clear_key();
+        }
+    }
+
+    /// Re-wrap the key of an encrypted database after a master password
+    /// change or restore (no-op for a plain database)
+    pub async fn rewrap_key(
+        &self,
+        master_key: &MasterKey,
+        hash: &str,
+        salt: &[u8],
+        kdf_params: &KdfParams,
+    ) -> Result<()> {
+        match &self.cipher {
+            Some(cipher) => cipher.rewrap(master_key, hash, salt, kdf_params).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Run database migrations
+    async fn run_migrations(&self) -> Result<()> {
+        info!("Running database migrations");
+
+        // Get current schema version (0 if fresh DB)
+        let current_version = self.get_current_schema_version().await?;
+        info!("Current schema version: {}", current_version);
+
+        // Define all migrations with their SQL and version number
+        let migrations = vec![
+            (1, include_str!("../migrations/001_initial_schema.sql")),
+            // Future migrations go here:
+            // (2, include_str!("../migrations/002_new_feature.sql")),
+            // (3, include_str!("../migrations/003_another_feature.sql")),
+        ];
+
+        // Expected latest version
+        let latest_version = migrations.last().map(|(v, _)| *v).unwrap_or(0);
+
+        // Safety check: DB version too new for this app version
+        if current_version > latest_version {
+            anyhow::bail!(
+                "Database schema version ({}) is newer than application supports ({}). \
+                 Please upgrade RITE to the latest version.",
+                current_version,
+                latest_version
+            );
+        }
+
+        // Run only pending migrations
+        for (version, sql) in migrations {
+            if version > current_version {
+                info!("Applying migration {}/{}", version, latest_version);
+
+                // Backup before migration (only if not initial setup)
+                if current_version > 0 {
+                    info!("Creating backup before migration {}...", version);
+                    if let Err(e) = self.create_migration_backup().await {
+                        warn!(
+                            "Failed to create backup: {}. Continuing with migration...",
+                            e
+                        );
+                        // Don't fail migration if backup fails, but warn user
+                    }
+                }
+
+                let mut conn = self.pool.acquire().await?;
+
+                sqlx::raw_sql(sql)
+                    .execute(&mut *conn)
+                    .await
+                    .with_context(|| format!("Failed to run migration {}", version))?;
+
+                info!("Migration {} completed successfully", version);
+            }
+        }
+
+        if current_version == latest_version {
+            info!(
+                "Database schema is up to date (version {})",
+                current_version
+            );
+        } else {
+            info!(
+                "Database migrations completed: {} → {}",
+                current_version, latest_version
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Get current schema version (returns 0 if schema_version table doesn't exist)
+    async fn get_current_schema_version(&self) -> Result<i64> {
+        // Check if schema_version table exists
+        let table_exists: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM sqlite_master \
+             WHERE type='table' AND name='schema_version'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !table_exists {
+            return Ok(0); // Fresh database
+        }
+
+        // Get max version from schema_version table
+        let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(version.unwrap_or(0))
+    }
+
+    /// Check if this is the first run (no master password set)
+    pub async fn is_first_run(&self) -> Result<bool> {
+        // The header of an encrypted database holds the master password
+        if self.is_encrypted() {
+            return Ok(false);
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM master_password")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count == 0)
+    }
+
+    /// Get the current schema version
+    pub async fn get_schema_version(&self) -> Result<i64> {
+        let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    /// Store master password hash, with the salt and KDF parameters of the master key
+    pub async fn store_master_password(
+        &self,
+        hash: &str,
+        salt: &[u8],
+        kdf_params: &KdfParams,
+    ) -> Result<()> {
+        write_master_password(&self.pool, hash, salt, kdf_params).await
+    }
+
+    /// Get master password hash and salt
+    pub async fn get_master_password(&self) -> Result<Option<(String, Vec<u8>)>> {
+        if let Some(cipher) = &self.cipher {
+            let (hash, salt, _) = cipher.master_password()?;
+            return Ok(Some((hash, salt)));
+        }
+
+        let result = sqlx::query("SELECT hash, salt FROM master_password WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result.map(|row| {
+            let hash: String = row.get("hash");
+            let salt: Vec<u8> = row.get("salt");
+            (hash, salt)
+        }))
+    }
+
+    /// Get the KDF parameters the master key is derived with
+    ///
+    /// Vaults created before parameters were stored use the defaults.
+    pub async fn get_kdf_params(&self) -> Result<KdfParams> {
+        if let Some(cipher) = &self.cipher {
+            return Ok(cipher.master_password()?.2);
+        }
+
+        let params: Option<String> =
+            sqlx::query_scalar("SELECT kdf_params FROM master_password WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?
+                .flatten();
+
+        match params {
+            Some(params) => serde_json::from_str(&params).context("Invalid stored KDF parameters"),
+            None => Ok(KdfParams::default()),
+        }
+    }
+
+    /// Record an unlock attempt
+    pub async fn record_unlock_attempt(&self, success: bool) -> Result<()> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Attempts are made before an encrypted database can be read
+        if let Some(cipher) = &self.cipher {
+            return cipher
+                .record_unlock_attempt(UnlockAttempt {
+                    timestamp: now,
+                    success: success as i32,
+                })
+                .await;
+        }
+
+        sqlx::query("INSERT INTO unlock_attempts (timestamp, success) VALUES (?1, ?2)")
+            .bind(now)
+            .bind(success as i32)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record unlock attempt")?;
+
+        Ok(())
+    }
+
+    /// Get recent unlock attempts (last N minutes)
+    pub async fn get_recent_unlock_attempts(&self, minutes: i64) -> Result<Vec<UnlockAttempt>> {
+        let cutoff = chrono::Utc::now().timestamp_millis() - (minutes * 60 * 1000);
+        if let Some(cipher) = &self.cipher {
+            return cipher.unlock_attempts(cutoff);
+        }
+
+        let attempts = sqlx::query_as::<_, UnlockAttempt>(
+            "SELECT timestamp, success FROM unlock_attempts WHERE timestamp > ?1 ORDER BY timestamp DESC"
+        )
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attempts)
+    }
+
+    /// Clean old unlock attempts (older than 24 hours)
+    pub async fn clean_old_unlock_attempts(&self) -> Result<()> {
+        // The header only keeps the last 24 hours
+        if self.is_encrypted() {
+            return Ok(());
+        }
+
+        let cutoff = chrono::Utc::now().timestamp_millis() - (24 * 60 * 60 * 1000);
+
+        sqlx::query("DELETE FROM unlock_attempts WHERE timestamp < ?1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reset the database (delete master password and all connections)
+    /// WARNING: This will permanently delete all data!
+    pub async fn reset(&self) -> Result<()> {
+        warn!("Resetting database - all data will be lost!");
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM connections")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM folders").execute(&mut *tx).await?;
+
+        sqlx::query("DELETE FROM tags").execute(&mut *tx).await?;
+
+        sqlx::query("DELETE FROM connection_templates")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM snippets")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM master_password")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM unlock_attempts")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM audit_log")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        info!("Database reset completed");
+        Ok(())
+    }
+
+    /// Create a backup of the database file
+    pub async fn create_backup(&self, backup_path: &Path) -> Result<()> {
+        info!("Creating database backup at: {}", backup_path.display());
+
+        // Create parent directories if they don't exist
+        if let Some(parent) = backup_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create backup directory")?;
+        }
+
+        // Execute SQLite VACUUM INTO to create a clean, compacted backup
+        // This is the recommended way to backup SQLite databases
+        let backup_path_str = backup_path.to_string_lossy();
+        let query = format!("VACUUM INTO '{}'", backup_path_str);
+
+        sqlx::query(&query)
+            .execute(&self.pool)
+            .await
+            .context("Failed to create database backup")?;
+
+        info!("Database backup created successfully");
+        Ok(())
+    }
+
+    /// Directory holding the vault backups (`backups` next to the vault)
+    pub fn backup_dir(&self) -> Result<PathBuf> {
+        Ok(self
+            .db_path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Invalid database path"))?
+            .join("backups"))
+    }
+
+    /// Create automatic migration backup with timestamp
+    async fn create_migration_backup(&self) -> Result<()> {
+        self.create_timestamped_backup("pre_migration").await?;
+        Ok(())
+    }
+
+    /// Create a timestamped backup (`backups/vault_<label>_<timestamp>.db`)
+    pub async fn create_timestamped_backup(&self, label: &str) -> Result<PathBuf> {
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+        let backup_filename = format!("vault_{}_{}.db", label, timestamp);
+
+        let backup_path = self.backup_dir()?.join(backup_filename);
+
+        self.create_backup(&backup_path).await?;
+        Ok(backup_path)
+    }
+
+    // ===== Connection CRUD Operations =====
+
+    /// Create a new connection
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_connection(
+        &self,
+        id: &str,
+        name: &str,
+        protocol: &str,
+        hostname: &str,
+        port: u16,
+        username: &str,
+        encrypted_credentials: &[u8],
+        nonce: &[u8],
+        auth_type: &str,
+        key_fingerprint: Option<&str>,
+        color: Option<&str>,
+        icon: Option<&str>,
+        folder_id: Option<&str>,
+        notes: Option<&str>,
+        ssh_keep_alive_override: Option<&str>,
+        ssh_keep_alive_interval: Option<i64>,
+        session_options: Option<&str>,
+        created_at: i64,
+        updated_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO connections (
+                id, name, protocol, hostname, port, username,
+                encrypted_credentials, nonce, auth_type, key_fingerprint,
+                color, icon, folder_id, notes,
+                ssh_keep_alive_override, ssh_keep_alive_interval,
+                session_options,
+                created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                      ?18, ?19)
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(protocol)
+        .bind(hostname)
+        .bind(port as i64)
+        .bind(username)
+        .bind(encrypted_credentials)
+        .bind(nonce)
+        .bind(auth_type)
+        .bind(key_fingerprint)
+        .bind(color)
+        .bind(icon)
+        .bind(folder_id)
+        .bind(notes)
+        .bind(ssh_keep_alive_override)
+        .bind(ssh_keep_alive_interval)
+        .bind(session_options)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get connection by ID
+    pub async fn get_connection(&self, id: &str) -> Result<Option<ConnectionRow>> {
+        static SQL: OnceLock<String> = OnceLock::new();
+        let sql = cached_sql(&SQL, || select_connections("WHERE c.id = ?1"));
+        let connection = sqlx::query_as::<_, ConnectionRow>(sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(connection)
+    }
+
+    /// Get all connections
+    pub async fn get_all_connections(&self) -> Result<Vec<ConnectionRow>> {
+        static SQL: OnceLock<String> = OnceLock::new();
+        let sql = cached_sql(&SQL, || {
+            select_connections("ORDER BY c.name COLLATE NOCASE")
+        });
+        let connections = sqlx::query_as::<_, ConnectionRow>(sql)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(connections)
+    }
+
+    /// Get the connections in a folder (and its subfolders if `recursive`)
+    pub async fn get_connections_by_folder(
+        &self,
+        folder_id: &str,
+        recursive: bool,
+    ) -> Result<Vec<ConnectionRow>> {
+        static RECURSIVE_SQL: OnceLock<String> = OnceLock::new();
+        static SQL: OnceLock<String> = OnceLock::new();
+        let sql = if recursive {
+            cached_sql(&RECURSIVE_SQL, || {
+                select_connections(
+                    r#"WHERE c.folder_id IN (
+                        WITH RECURSIVE subtree(id) AS (
+                            SELECT ?1
+                            UNION ALL
+                            SELECT f.id FROM folders f JOIN subtree s ON f.parent_id = s.id
+                        )
+                        SELECT id FROM subtree
+                    ) ORDER BY c.name COLLATE NOCASE"#,
+                )
+            })
+        } else {
+            cached_sql(&SQL, || {
+                select_connections("WHERE c.folder_id = ?1 ORDER BY c.name COLLATE NOCASE")
+            })
+        };
+        let connections = sqlx::query_as::<_, ConnectionRow>(sql)
+            .bind(folder_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(connections)
+    }
+
+    /// Update connection if it is still at `revision`
+    ///
+    /// Bumps the revision. Returns false (and changes nothing) when the
+    /// connection was updated since that revision, or doesn't exist.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_connection(
+        &self,
+        id: &str,
+        name: &str,
+        protocol: &str,
+        hostname: &str,
+        port: u16,
+        username: &str,
+        encrypted_credentials: &[u8],
+        nonce: &[u8],
+        auth_type: &str,
+        key_fingerprint: Option<&str>,
+        color: Option<&str>,
+        icon: Option<&str>,
+        folder_id: Option<&str>,
+        notes: Option<&str>,
+        ssh_keep_alive_override: Option<&str>,
+        ssh_keep_alive_interval: Option<i64>,
+        session_options: Option<&str>,
+        revision: i64,
+        updated_at: i64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE connections SET
+                name = ?2,
+                protocol = ?3,
+                hostname = ?4,
+                port = ?5,
+                username = ?6,
+                encrypted_credentials = ?7,
+                nonce = ?8,
+                auth_type = ?9,
+                key_fingerprint = ?10,
+                color = ?11,
+                icon = ?12,
+                folder_id = ?13,
+                notes = ?14,
+                ssh_keep_alive_override = ?15,
+                ssh_keep_alive_interval = ?16,
+                session_options = ?17,
+                updated_at = ?18,
+                revision = revision + 1
+            WHERE id = ?1 AND revision = ?19
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(protocol)
+        .bind(hostname)
+        .bind(port as i64)
+        .bind(username)
+        .bind(encrypted_credentials)
+        .bind(nonce)
+        .bind(auth_type)
+        .bind(key_fingerprint)
+        .bind(color)
+        .bind(icon)
+        .bind(folder_id)
+        .bind(notes)
+        .bind(ssh_keep_alive_override)
+        .bind(ssh_keep_alive_interval)
+        .bind(session_options)
+        .bind(updated_at)
+        .bind(revision)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Update connection last used timestamp
+    pub async fn update_connection_last_used(&self, id: &str, last_used_at: i64) -> Result<()> {
+        sqlx::query("UPDATE connections SET last_used_at = ?1, updated_at = ?2 WHERE id = ?3")
+            .bind(last_used_at)
+            .bind(last_used_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete connection (permanently, see [`Database::trash_connection`])
+    pub async fn delete_connection(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM connections WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Move a connection to the trash
+    ///
+    /// Bumps the revision. Returns false when the connection doesn't exist
+    /// or is already in the trash.
+    pub async fn trash_connection(&self, id: &str, deleted_at: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE connections SET deleted_at = ?2, updated_at = ?2, revision = revision + 1 \
+             WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(deleted_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Take a connection out of the trash
+    ///
+    /// Bumps the revision. Returns false when the connection isn't in the
+    /// trash.
+    pub async fn restore_connection(&self, id: &str, updated_at: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE connections SET deleted_at = NULL, updated_at = ?2, revision = revision + 1 \
+             WHERE id = ?1 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Connections in the trash, most recently deleted first
+    pub async fn get_trashed_connections(&self) -> Result<Vec<ConnectionRow>> {
+        let connections = sqlx::query_as::<_, ConnectionRow>(&select_trashed_connections(
+            "ORDER BY c.deleted_at DESC, c.name COLLATE NOCASE",
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(connections)
+    }
+
+    /// Permanently delete the connections in the trash (only those deleted
+    /// before `deleted_before` if set)
+    ///
+    /// Returns the IDs of the deleted connections.
+    pub async fn purge_trash(&self, deleted_before: Option<i64>) -> Result<Vec<String>> {
+        let purged = sqlx::query_scalar(
+            "DELETE FROM connections WHERE deleted_at IS NOT NULL \
+             AND (?1 IS NULL OR deleted_at < ?1) RETURNING id",
+        )
+        .bind(deleted_before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(purged)
+    }
+
+    /// Move a connection to a folder (None = top level)
+    ///
+    /// Bumps the revision. Returns false when the connection doesn't exist.
+    pub async fn set_connection_folder(
+        &self,
+        id: &str,
+        folder_id: Option<&str>,
+        updated_at: i64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE connections SET folder_id = ?2, updated_at = ?3, revision = revision + 1 \
+             WHERE id = ?1",
+        )
+        .bind(id)
+        .bind(folder_id)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl Database {
+    // ===== Folder Operations =====
+
+    /// Create a folder
+    pub async fn create_folder(
+        &self,
+        id: &str,
+        name: &str,
+        parent_id: Option<&str>,
+        color: Option<&str>,
+        sort_order: i64,
+        created_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO folders (id, name, parent_id, color, sort_order, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(parent_id)
+        .bind(color)
+        .bind(sort_order)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get folder by ID
+    pub async fn get_folder(&self, id: &str) -> Result<Option<FolderRow>> {
+        let folder = sqlx::query_as::<_, FolderRow>("SELECT * FROM folders WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(folder)
+    }
+
+    /// Get all folders, in display order
+    pub async fn get_all_folders(&self) -> Result<Vec<FolderRow>> {
+        let folders = sqlx::query_as::<_, FolderRow>(
+            "SELECT * FROM folders ORDER BY sort_order, name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(folders)
+    }
+
+    /// Find a folder by name among the children of `parent_id`
+    /// (case-insensitive)
+    pub async fn find_folder(
+        &self,
+        parent_id: Option<&str>,
+        name: &str,
+    ) -> Result<Option<FolderRow>> {
+        let folder = sqlx::query_as::<_, FolderRow>(
+            "SELECT * FROM folders WHERE parent_id IS ?1 AND name = ?2 COLLATE NOCASE",
+        )
+        .bind(parent_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(folder)
+    }
+
+    /// Path of a folder (`Production/Databases`)
+    pub async fn get_folder_path(&self, id: &str) -> Result<Option<String>> {
+        let path = sqlx::query_scalar::<_, String>(&format!(
+            "WITH RECURSIVE {FOLDER_PATHS} SELECT path FROM folder_paths WHERE id = ?1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(path)
+    }
+
+    /// IDs of a folder and all the folders below it
+    pub async fn get_folder_subtree(&self, id: &str) -> Result<Vec<String>> {
+        let ids = sqlx::query_scalar::<_, String>(
+            r#"
+            WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM folders WHERE id = ?1
+                UNION ALL
+                SELECT f.id FROM folders f JOIN subtree s ON f.parent_id = s.id
+            )
+            SELECT id FROM subtree
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Position after the last child of `parent_id`
+    pub async fn next_folder_sort_order(&self, parent_id: Option<&str>) -> Result<i64> {
+        let next = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(MAX(sort_order) + 1, 0) FROM folders WHERE parent_id IS ?1",
+        )
+        .bind(parent_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(next)
+    }
+
+    /// Number of connections directly in each folder
+    pub async fn count_connections_by_folder(
+        &self,
+    ) -> Result<std::collections::HashMap<String, i64>> {
+        let rows = sqlx::query(
+            "SELECT folder_id, COUNT(*) AS count FROM connections \
+             WHERE folder_id IS NOT NULL AND deleted_at IS NULL GROUP BY folder_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("folder_id"), row.get("count")))
+            .collect())
+    }
+
+    /// Update a folder (name, parent, color, position)
+    ///
+    /// Returns false when the folder doesn't exist.
+    pub async fn update_folder(
+        &self,
+        id: &str,
+        name: &str,
+        parent_id: Option<&str>,
+        color: Option<&str>,
+        sort_order: i64,
+        updated_at: i64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE folders SET
+                name = ?2,
+                parent_id = ?3,
+                color = ?4,
+                sort_order = ?5,
+                updated_at = ?6
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(parent_id)
+        .bind(color)
+        .bind(sort_order)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a folder, moving its connections and subfolders to `target`
+    /// (None = top level) first
+    ///
+    /// Returns the IDs of the moved connections (their revision is bumped).
+    pub async fn delete_folder(&self, id: &str, target: Option<&str>) -> Result<Vec<String>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut tx = self.pool.begin().await?;
+
+        let moved: Vec<String> =
+            sqlx::query_scalar("SELECT id FROM connections WHERE folder_id = ?1")
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?;
+        sqlx::query(
+            "UPDATE connections SET folder_id = ?2, updated_at = ?3, revision = revision + 1 \
+             WHERE folder_id = ?1",
+        )
+        .bind(id)
+        .bind(target)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE folders SET parent_id = ?2, updated_at = ?3 WHERE parent_id = ?1")
+            .bind(id)
+            .bind(target)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .context("A subfolder has the same name as a folder in the destination")?;
+
+        sqlx::query("DELETE FROM folders WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(moved)
+    }
+}
+
+impl Database {
+    // ===== Tag Operations =====
+
+    /// Create a tag
+    pub async fn create_tag(
+        &self,
+        id: &str,
+        name: &str,
+        color: Option<&str>,
+        created_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO tags (id, name, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        )
+        .bind(id)
+        .bind(name)
+        .bind(color)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get tag by ID
+    pub async fn get_tag(&self, id: &str) -> Result<Option<TagRow>> {
+        let tag =
+            sqlx::query_as::<_, TagRow>(&format!("{SELECT_TAGS} WHERE t.id = ?1 GROUP BY t.id"))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(tag)
+    }
+
+    /// Get all tags, by name
+    pub async fn get_all_tags(&self) -> Result<Vec<TagRow>> {
+        let tags = sqlx::query_as::<_, TagRow>(&format!(
+            "{SELECT_TAGS} GROUP BY t.id ORDER BY t.name COLLATE NOCASE"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    /// Find a tag by name (case-insensitive)
+    pub async fn find_tag(&self, name: &str) -> Result<Option<TagRow>> {
+        let tag = sqlx::query_as::<_, TagRow>(&format!(
+            "{SELECT_TAGS} WHERE t.name = ?1 COLLATE NOCASE GROUP BY t.id"
+        ))
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(tag)
+    }
+
+    /// Rename or recolor a tag
+    ///
+    /// Returns false when the tag doesn't exist.
+    pub async fn update_tag(
+        &self,
+        id: &str,
+        name: &str,
+        color: Option<&str>,
+        updated_at: i64,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE tags SET name = ?2, color = ?3, updated_at = ?4 WHERE id = ?1")
+                .bind(id)
+                .bind(name)
+                .bind(color)
+                .bind(updated_at)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a tag, removing it from its connections
+    ///
+    /// Returns the IDs of the connections that had it (their revision is
+    /// bumped).
+    pub async fn delete_tag(&self, id: &str) -> Result<Vec<String>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut tx = self.pool.begin().await?;
+
+        let tagged: Vec<String> =
+            sqlx::query_scalar("SELECT connection_id FROM connection_tags WHERE tag_id = ?1")
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?;
+        sqlx::query(
+            "UPDATE connections SET updated_at = ?2, revision = revision + 1 \
+             WHERE id IN (SELECT connection_id FROM connection_tags WHERE tag_id = ?1)",
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM tags WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(tagged)
+    }
+
+    /// Replace the tags of a connection
+    pub async fn set_connection_tags(&self, connection_id: &str, tag_ids: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM connection_tags WHERE connection_id = ?1")
+            .bind(connection_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for tag_id in tag_ids {
+            sqlx::query(
+                "INSERT OR IGNORE INTO connection_tags (connection_id, tag_id) VALUES (?1, ?2)",
+            )
+            .bind(connection_id)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get the connections tagged with all (`match_all`) or any of `names`
+    /// (case-insensitive)
+    pub async fn search_connections_by_tags(
+        &self,
+        names: &[String],
+        match_all: bool,
+    ) -> Result<Vec<ConnectionRow>> {
+        // Tags are matched by name, unknown names simply match nothing
+        let filter = r#"WHERE c.id IN (
+                SELECT ct.connection_id FROM connection_tags ct JOIN tags t ON t.id = ct.tag_id
+                WHERE t.name COLLATE NOCASE IN (SELECT value FROM json_each(?1))
+                GROUP BY ct.connection_id
+                HAVING COUNT(*) >= ?2
+            ) ORDER BY c.name COLLATE NOCASE"#;
+        let required = if match_all { names.len() as i64 } else { 1 };
+        let connections = sqlx::query_as::<_, ConnectionRow>(&select_connections(filter))
+            .bind(serde_json::to_string(names)?)
+            .bind(required)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(connections)
+    }
+}
+
+impl Database {
+    // ===== Connection Search =====
+
+    /// Search connections with an FTS5 query (see [`crate::search`]), best
+    /// matches first
+    ///
+    /// Rebuilds the search index first if the connections changed since the
+    /// last search.
+    pub async fn search_connections(
+        &self,
+        match_expression: &str,
+        limit: u32,
+    ) -> Result<Vec<ConnectionRow>> {
+        self.refresh_search_index().await?;
+
+        static SQL: OnceLock<String> = OnceLock::new();
+        let sql = cached_sql(&SQL, || {
+            select_connections(&format!(
+                "JOIN connections_fts ON connections_fts.connection_id = c.id \
+                 WHERE connections_fts MATCH ?1 \
+                 ORDER BY bm25(connections_fts, {}), c.name COLLATE NOCASE LIMIT ?2",
+                crate::search::RANK_WEIGHTS
+            ))
+        });
+        let connections = sqlx::query_as::<_, ConnectionRow>(sql)
+            .bind(match_expression)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(connections)
+    }
+
+    /// Rebuild the search index if it is stale
+    async fn refresh_search_index(&self) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Clearing the flag first takes the write lock, so concurrent
+        // searches don't rebuild twice
+        let stale = sqlx::query("UPDATE connections_fts_state SET stale = 0 WHERE stale = 1")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        if !stale {
+            return Ok(());
+        }
+
+        sqlx::query("DELETE FROM connections_fts")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "INSERT INTO connections_fts \
+             (connection_id, name, hostname, username, notes, folder, tags) \
+             SELECT id, name, hostname, username, notes, folder, tags FROM ({})",
+            select_connections("")
+        ))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+impl Database {
+    // ===== Connection Template Operations =====
+
+    /// Create a connection template
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_connection_template(
+        &self,
+        id: &str,
+        name: &str,
+        protocol: &str,
+        port: u16,
+        username: &str,
+        auth_type: &str,
+        key_path: Option<&str>,
+        color: Option<&str>,
+        icon: Option<&str>,
+        folder: Option<&str>,
+        notes: Option<&str>,
+        tags: Option<&str>,
+        ssh_keep_alive_override: Option<&str>,
+        ssh_keep_alive_interval: Option<i64>,
+        session_options: Option<&str>,
+        created_at: i64,
+        updated_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO connection_templates (
+                id, name, protocol, port, username, auth_type, key_path,
+                color, icon, folder, notes, tags,
+                ssh_keep_alive_override, ssh_keep_alive_interval,
+                session_options,
+                created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(protocol)
+        .bind(port as i64)
+        .bind(username)
+        .bind(auth_type)
+        .bind(key_path)
+        .bind(color)
+        .bind(icon)
+        .bind(folder)
+        .bind(notes)
+        .bind(tags)
+        .bind(ssh_keep_alive_override)
+        .bind(ssh_keep_alive_interval)
+        .bind(session_options)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get connection template by ID
+    pub async fn get_connection_template(&self, id: &str) -> Result<Option<ConnectionTemplateRow>> {
+        let template = sqlx::query_as::<_, ConnectionTemplateRow>(
+            "SELECT * FROM connection_templates WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
+
+    /// Get all connection templates
+    pub async fn get_all_connection_templates(&self) -> Result<Vec<ConnectionTemplateRow>> {
+        let templates = sqlx::query_as::<_, ConnectionTemplateRow>(
+            "SELECT * FROM connection_templates ORDER BY name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(templates)
+    }
+
+    /// Delete connection template
+    pub async fn delete_connection_template(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM connection_templates WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Database {
+    // ===== Snippet Operations =====
+
+    /// Create a snippet
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_snippet(
+        &self,
+        id: &str,
+        name: &str,
+        folder: Option<&str>,
+        encrypted_command: &[u8],
+        nonce: &[u8],
+        created_at: i64,
+        updated_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO snippets (
+                id, name, folder, encrypted_command, nonce, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(folder)
+        .bind(encrypted_command)
+        .bind(nonce)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get snippet by ID
+    pub async fn get_snippet(&self, id: &str) -> Result<Option<SnippetRow>> {
+        let snippet = sqlx::query_as::<_, SnippetRow>("SELECT * FROM snippets WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(snippet)
+    }
+
+    /// Get all snippets, by folder then name
+    pub async fn get_all_snippets(&self) -> Result<Vec<SnippetRow>> {
+        let snippets = sqlx::query_as::<_, SnippetRow>(
+            "SELECT * FROM snippets ORDER BY folder COLLATE NOCASE, name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snippets)
+    }
+
+    /// Update snippet (returns false if it doesn't exist)
+    pub async fn update_snippet(
+        &self,
+        id: &str,
+        name: &str,
+        folder: Option<&str>,
+        encrypted_command: &[u8],
+        nonce: &[u8],
+        updated_at: i64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE snippets SET
+                name = ?2,
+                folder = ?3,
+                encrypted_command = ?4,
+                nonce = ?5,
+                updated_at = ?6
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(folder)
+        .bind(encrypted_command)
+        .bind(nonce)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete snippet
+    pub async fn delete_snippet(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM snippets WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Database {
+    // ===== Connection History =====
+
+    /// Record the start of a session of a saved connection
+    ///
+    /// Returns the history record ID, or None when the connection isn't
+    /// saved (quick connections have no history).
+    pub async fn start_connection_session(
+        &self,
+        connection_id: &str,
+        started_at: i64,
+    ) -> Result<Option<i64>> {
+        let result = sqlx::query(
+            "INSERT INTO connection_history (connection_id, started_at) \
+             SELECT id, ?2 FROM connections WHERE id = ?1",
+        )
+        .bind(connection_id)
+        .bind(started_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+    }
+
+    /// Record the end of a session
+    pub async fn finish_connection_session(
+        &self,
+        id: i64,
+        ended_at: i64,
+        bytes_in: i64,
+        bytes_out: i64,
+        exit_status: Option<i64>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE connection_history \
+             SET ended_at = ?2, bytes_in = ?3, bytes_out = ?4, exit_status = ?5 WHERE id = ?1",
+        )
+        .bind(id)
+        .bind(ended_at)
+        .bind(bytes_in)
+        .bind(bytes_out)
+        .bind(exit_status)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Latest sessions of a connection, most recent first
+    pub async fn get_connection_history(
+        &self,
+        connection_id: &str,
+        limit: i64,
+    ) -> Result<Vec<SessionRecord>> {
+        let records = sqlx::query_as::<_, SessionRecord>(
+            "SELECT * FROM connection_history WHERE connection_id = ?1 \
+             ORDER BY started_at DESC, id DESC LIMIT ?2",
+        )
+        .bind(connection_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Usage statistics of a connection (zero counts when it was never used)
+    pub async fn get_connection_stats(&self, connection_id: &str) -> Result<ConnectionStats> {
+        let stats = sqlx::query_as::<_, ConnectionStats>(
+            r#"
+            SELECT
+                ?1 AS connection_id,
+                COUNT(*) AS session_count,
+                COUNT(CASE WHEN exit_status != 0 THEN 1 END) AS failed_count,
+                COALESCE(SUM(ended_at - started_at), 0) AS total_duration,
+                COALESCE(SUM(bytes_in), 0) AS bytes_in,
+                COALESCE(SUM(bytes_out), 0) AS bytes_out,
+                MIN(started_at) AS first_used_at,
+                MAX(started_at) AS last_used_at
+            FROM connection_history WHERE connection_id = ?1
+            "#,
+        )
+        .bind(connection_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(stats)
+    }
+
+    /// Connections with the most sessions started since `since`
+    pub async fn get_most_used_connections(
+        &self,
+        since: i64,
+        limit: i64,
+    ) -> Result<Vec<ConnectionUsage>> {
+        let usage = sqlx::query_as::<_, ConnectionUsage>(&select_usage(
+            "session_count DESC, last_used_at DESC",
+        ))
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(usage)
+    }
+
+    /// Connections by their last session, most recent first
+    pub async fn get_recent_connections(&self, limit: i64) -> Result<Vec<ConnectionUsage>> {
+        let usage = sqlx::query_as::<_, ConnectionUsage>(&select_usage("last_used_at DESC"))
+            .bind(0)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(usage)
+    }
+}
+
+impl Database {
+    // ===== Remote Task Operations =====
+
+    /// Create a remote task with its target connections
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_remote_task(
+        &self,
+        id: &str,
+        name: &str,
+        encrypted_command: &[u8],
+        nonce: &[u8],
+        schedule: Option<&str>,
+        enabled: bool,
+        connection_ids: &[String],
+        created_at: i64,
+        updated_at: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO remote_tasks (
+                id, name, encrypted_command, nonce, schedule, enabled, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(encrypted_command)
+        .bind(nonce)
+        .bind(schedule)
+        .bind(enabled as i32)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+        insert_remote_task_targets(&mut tx, id, connection_ids).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get remote task by ID
+    pub async fn get_remote_task(&self, id: &str) -> Result<Option<RemoteTaskRow>> {
+        let task = sqlx::query_as::<_, RemoteTaskRow>("SELECT * FROM remote_tasks WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(task)
+    }
+
+    /// Get all remote tasks, by name
+    pub async fn get_all_remote_tasks(&self) -> Result<Vec<RemoteTaskRow>> {
+        let tasks = sqlx::query_as::<_, RemoteTaskRow>(
+            "SELECT * FROM remote_tasks ORDER BY name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tasks)
+    }
+
+    /// Target connections of a remote task
+    pub async fn get_remote_task_targets(&self, task_id: &str) -> Result<Vec<String>> {
+        let targets = sqlx::query_scalar(
+            "SELECT connection_id FROM remote_task_targets WHERE task_id = ?1 \
+             ORDER BY connection_id",
+        )
+        .bind(task_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(targets)
+    }
+
+    /// Update remote task and replace its targets (returns false if it
+    /// doesn't exist)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_remote_task(
+        &self,
+        id: &str,
+        name: &str,
+        encrypted_command: &[u8],
+        nonce: &[u8],
+        schedule: Option<&str>,
+        enabled: bool,
+        connection_ids: &[String],
+        updated_at: i64,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE remote_tasks SET
+                name = ?2,
+                encrypted_command = ?3,
+                nonce = ?4,
+                schedule = ?5,
+                enabled = ?6,
+                updated_at = ?7
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(encrypted_command)
+        .bind(nonce)
+        .bind(schedule)
+        .bind(enabled as i32)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM remote_task_targets WHERE task_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        insert_remote_task_targets(&mut tx, id, connection_ids).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Delete remote task (with its runs)
+    pub async fn delete_remote_task(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM remote_tasks WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record a finished run, keeping the latest `keep` runs of the task
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_remote_task_run(
+        &self,
+        id: &str,
+        task_id: &str,
+        connection_id: &str,
+        started_at: i64,
+        ended_at: i64,
+        exit_status: Option<i64>,
+        error: Option<&str>,
+        encrypted_output: &[u8],
+        nonce: &[u8],
+        keep: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO remote_task_runs (
+                id, task_id, connection_id, started_at, ended_at, exit_status, error,
+                encrypted_output, nonce
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )
+        .bind(id)
+        .bind(task_id)
+        .bind(connection_id)
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(exit_status)
+        .bind(error)
+        .bind(encrypted_output)
+        .bind(nonce)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM remote_task_runs WHERE task_id = ?1 AND id NOT IN ( \
+             SELECT id FROM remote_task_runs WHERE task_id = ?1 \
+             ORDER BY started_at DESC, rowid DESC LIMIT ?2)",
+        )
+        .bind(task_id)
+        .bind(keep)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Latest runs of a remote task, most recent first
+    pub async fn get_remote_task_runs(
+        &self,
+        task_id: &str,
+        limit: i64,
+    ) -> Result<Vec<RemoteTaskRunRow>> {
+        let runs = sqlx::query_as::<_, RemoteTaskRunRow>(
+            "SELECT * FROM remote_task_runs WHERE task_id = ?1 \
+             ORDER BY started_at DESC, rowid DESC LIMIT ?2",
+        )
+        .bind(task_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+}
+
+async fn insert_remote_task_targets(
+    conn: &mut sqlx::SqliteConnection,
+    task_id: &str,
+    connection_ids: &[String],
+) -> Result<()> {
+    for connection_id in connection_ids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO remote_task_targets (task_id, connection_id) VALUES (?1, ?2)",
+        )
+        .bind(task_id)
+        .bind(connection_id)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to add target connection {}", connection_id))?;
+    }
+    Ok(())
+}
+
+/// Unlock attempt record
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UnlockAttempt {
+    pub timestamp: i64,
+    pub success: i32,
+}
+
+impl UnlockAttempt {
+    pub fn is_success(&self) -> bool {
+        self.success != 0
+    }
+}
+
+/// Connection row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ConnectionRow {
+    pub id: String,
+    pub name: String,
+    pub protocol: String,
+    pub hostname: String,
+    pub port: i64,
+    pub username: String,
+    pub encrypted_credentials: Vec<u8>,
+    pub nonce: Vec<u8>,
+    /// "password", "publicKey" or "storedKey"
+    pub auth_type: String,
+    /// SHA256 fingerprint of the key, None for password auth or when unknown
+    pub key_fingerprint: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder_id: Option<String>,
+    /// Path of the folder (`Production/Databases`), joined from `folders`
+    pub folder: Option<String>,
+    /// Tag names (JSON array), joined from `tags`
+    pub tags: Option<String>,
+    pub notes: Option<String>,
+    pub ssh_keep_alive_override: Option<String>,
+    pub ssh_keep_alive_interval: Option<i64>,
+    pub session_options: Option<String>,
+    pub revision: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub last_used_at: Option<i64>,
+    /// In the trash since, None = not deleted
+    pub deleted_at: Option<i64>,
+}
+
+/// Connection template row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ConnectionTemplateRow {
+    pub id: String,
+    pub name: String,
+    pub protocol: String,
+    pub port: i64,
+    pub username: String,
+    pub auth_type: String,
+    pub key_path: Option<String>,
+    pub ssh_keep_alive_override: Option<String>,
+    pub ssh_keep_alive_interval: Option<i64>,
+    pub session_options: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub folder: Option<String>,
+    pub notes: Option<String>,
+    /// Tag names (JSON array)
+    pub tags: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Folder row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FolderRow {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Tag row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TagRow {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Number of connections with the tag
+    pub connection_count: i64,
+}
+
+/// Snippet row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SnippetRow {
+    pub id: String,
+    pub name: String,
+    pub folder: Option<String>,
+    pub encrypted_command: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Remote task row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RemoteTaskRow {
+    pub id: String,
+    pub name: String,
+    pub encrypted_command: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub schedule: Option<String>,
+    pub enabled: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Remote task run row from database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RemoteTaskRunRow {
+    pub id: String,
+    pub task_id: String,
+    pub connection_id: String,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub exit_status: Option<i64>,
+    pub error: Option<String>,
+    pub encrypted_output: Vec<u8>,
+    pub nonce: Vec<u8>,
+}
+
+impl Database {
+    /// Get a setting value
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        if let Some(cipher) = self.header_settings(key) {
+            return cipher.setting(key);
+        }
+        let result = sqlx::query("SELECT value FROM settings WHERE key = ?1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result.map(|row| row.get("value")))
+    }
+
+    /// Set a setting value
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        match self.header_settings(key) {
+            Some(cipher) => cipher.set_setting(key, value).await,
+            None => write_setting(&self.pool, key, value).await,
+        }
+    }
+
+    /// Get all settings
+    pub async fn get_all_settings(&self) -> Result<std::collections::HashMap<String, String>> {
+        let rows = sqlx::query("SELECT key, value FROM settings")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut settings = std::collections::HashMap::new();
+        for row in rows {
+            let key: String = row.get("key");
+            let value: String = row.get("value");
+            settings.insert(key, value);
+        }
+        if let Some(cipher) = self.cipher() {
+            for key in HEADER_SETTINGS {
+                if let Some(value) = cipher.setting(key)? {
+                    settings.insert(key.to_string(), value);
+                }
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Cipher holding `key` when it is kept in the header of an encrypted vault
+    fn header_settings(&self, key: &str) -> Option<&Cipher> {
+        self.cipher().filter(|_| HEADER_SETTINGS.contains(&key))
+    }
+}
+
+/// Write a setting to the settings table
+pub(crate) async fn write_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query(
+        r#"
+        INSERT INTO settings (key, value, updated_at)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(key) DO UPDATE SET
+            value = excluded.value,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(key)
+    .bind(value)
+    .bind(now)
+    .execute(pool)
+    .await
+    .context("Failed to set setting")?;
+
+    Ok(())
+}
+
+/// Store the master password hash (also used inside transactions)
+pub(crate) async fn write_master_password<'e, E>(
+    executor: E,
+    hash: &str,
+    salt: &[u8],
+    kdf_params: &KdfParams,
+) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let now = chrono::Utc::now().timestamp_millis();
+
+    sqlx::query(
+        r#"
+        INSERT INTO master_password (id, hash, salt, kdf_params, created_at, updated_at)
+        VALUES (1, ?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(id) DO UPDATE SET
+            hash = excluded.hash,
+            salt = excluded.salt,
+            kdf_params = excluded.kdf_params,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(hash)
+    .bind(salt)
+    .bind(serde_json::to_string(kdf_params)?)
+    .bind(now)
+    .bind(now)
+    .execute(executor)
+    .await
+    .context("Failed to store master password")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn create_test_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let db = Database::new(&db_path).await.unwrap();
+        (db, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_database_initialization() {
+        let (db, _temp) = create_test_db().await;
+
+        // Should be first run
+        assert!(db.is_first_run().await.unwrap());
+
+        // Schema version should be 1
+        assert_eq!(db.get_schema_version().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connection_pragmas() {
+        let (db, _temp) = create_test_db().await;
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        for (pragma, expected) in [
+            ("synchronous", 1), // NORMAL
+            ("foreign_keys", 1),
+            ("busy_timeout", 10_000),
+        ] {
+            let value: i64 = sqlx::query_scalar(&format!("PRAGMA {}", pragma))
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+            assert_eq!(value, expected, "{}", pragma);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_master_password_storage() {
+        let (db, _temp) = create_test_db().await;
+
+        let hash = "test_hash_123";
+        let salt = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+        // Vault without stored parameters
+        assert_eq!(db.get_kdf_params().await.unwrap(), KdfParams::default());
+
+        // Store master password
+        let kdf_params = KdfParams {
+            iterations: 4,
+            ..KdfParams::default()
+        };
+        db.store_master_password(hash, &salt, &kdf_params)
+            .await
+            .unwrap();
+
+        // Should no longer be first run
+        assert!(!db.is_first_run().await.unwrap());
+
+        // Retrieve and verify
+        let (retrieved_hash, retrieved_salt) = db.get_master_password().await.unwrap().unwrap();
+        assert_eq!(retrieved_hash, hash);
+        assert_eq!(retrieved_salt, salt);
+        assert_eq!(db.get_kdf_params().await.unwrap(), kdf_params);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_attempts() {
+        let (db, _temp) = create_test_db().await;
+
+        // Record some attempts
+        db.record_unlock_attempt(false).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        db.record_unlock_attempt(false).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        db.record_unlock_attempt(true).await.unwrap();
+
+        // Get recent attempts
+        let attempts = db.get_recent_unlock_attempts(10).await.unwrap();
+        assert_eq!(attempts.len(), 3);
+
+        // Most recent should be success (DESC order)
+        assert!(attempts[0].is_success());
+        assert!(!attempts[1].is_success());
+        assert!(!attempts[2].is_success());
+    }
+
+    #[tokio::test]
+    async fn test_connection_templates() {
+        let (db, _temp) = create_test_db().await;
+
+        db.create_connection_template(
+            "tpl-1",
+            "Prod via bastion",
+            "ssh",
+            22,
+            "deploy",
+            "publicKey",
+            Some("~/.ssh/id_ed25519"),
+            None,
+            None,
+            Some("Production"),
+            None,
+            Some(r#"["prod","web"]"#),
+            None,
+            None,
+            None,
+            1,
+            1,
+        )
+        .await
+        .unwrap();
+
+        let template = db.get_connection_template("tpl-1").await.unwrap().unwrap();
+        assert_eq!(template.username, "deploy");
+        assert_eq!(template.folder.as_deref(), Some("Production"));
+        assert_eq!(template.tags.as_deref(), Some(r#"["prod","web"]"#));
+        assert_eq!(db.get_all_connection_templates().await.unwrap().len(), 1);
+
+        db.delete_connection_template("tpl-1").await.unwrap();
+        assert!(db.get_connection_template("tpl-1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_database_reset() {
+        let (db, _temp) = create_test_db().await;
+
+        // Set up master password
+        db.store_master_password("hash", &[1, 2, 3], &KdfParams::default())
+            .await
+            .unwrap();
+        assert!(!db.is_first_run().await.unwrap());
+
+        // Reset
+        db.reset().await.unwrap();
+
+        // Should be first run again
+        assert!(db.is_first_run().await.unwrap());
+        assert!(db.get_master_password().await.unwrap().is_none());
+    }
+
+    /// Plan of a connection listing query (see `benches/db.rs` for timings)
+    async fn query_plan(db: &Database, sql: &str) -> String {
+        let rows: Vec<(i64, i64, i64, String)> =
+            sqlx::query_as(&format!("EXPLAIN QUERY PLAN {}", sql))
+                .bind("folder")
+                .fetch_all(db.pool())
+                .await
+                .unwrap();
+        rows.into_iter()
+            .map(|(_, _, _, detail)| detail)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_listing_many_connections() {
+        let (db, _temp) = create_test_db().await;
+        db.create_folder("prod", "Prod", None, None, 0, 0)
+            .await
+            .unwrap();
+        db.create_folder("web", "Web", Some("prod"), None, 0, 0)
+            .await
+            .unwrap();
+        db.create_tag("db", "db", None, 0).await.unwrap();
+
+        const CONNECTIONS: usize = 1_000;
+        for i in 0..CONNECTIONS {
+            let id = format!("conn-{:04}", i);
+            let folder = ["prod", "web"][i % 2];
+            db.create_connection(
+                &id,
+                &format!("Server-{:04}", CONNECTIONS - i),
+                "ssh",
+                "host.example.com",
+                22,
+                "admin",
+                &[0xa5; 160],
+                &[],
+                "password",
+                None,
+                None,
+                None,
+                Some(folder),
+                None,
+                None,
+                None,
+                None,
+                0,
+                0,
+            )
+            .await
+            .unwrap();
+            if i.is_multiple_of(10) {
+                db.set_connection_tags(&id, &["db".to_string()])
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // One query returns every row with its folder path and tags
+        let rows = db.get_all_connections().await.unwrap();
+        assert_eq!(rows.len(), CONNECTIONS);
+        assert!(rows.windows(2).all(|pair| pair[0].name < pair[1].name));
+        for row in &rows {
+            let index: usize = row.id[5..].parse().unwrap();
+            let folder = ["Prod", "Prod/Web"][index % 2];
+            assert_eq!(row.folder.as_deref(), Some(folder));
+            let tags = if index.is_multiple_of(10) {
+                r#"["db"]"#
+            } else {
+                "[]"
+            };
+            assert_eq!(row.tags.as_deref(), Some(tags));
+        }
+        assert_eq!(
+            db.get_connections_by_folder("prod", false)
+                .await
+                .unwrap()
+                .len(),
+            CONNECTIONS / 2
+        );
+        assert_eq!(
+            db.get_connections_by_folder("prod", true)
+                .await
+                .unwrap()
+                .len(),
+            CONNECTIONS
+        );
+
+        // Listings read the connections in order from their indexes
+        let plan = query_plan(&db, &select_connections("ORDER BY c.name COLLATE NOCASE")).await;
+        assert!(plan.contains("idx_connections_name"), "{}", plan);
+        let plan = query_plan(
+            &db,
+            &select_connections("WHERE c.folder_id = ?1 ORDER BY c.name COLLATE NOCASE"),
+        )
+        .await;
+        assert!(
+            plan.contains("SEARCH connections USING INDEX idx_connections_folder"),
+            "{}",
+            plan
+        );
+    }
+}
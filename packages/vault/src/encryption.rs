@@ -0,0 +1,721 @@
+//! Full database encryption (SQLCipher)
+//!
+//! Credentials are encrypted field by field, but hostnames, usernames,
+//! folders, notes and known hosts are stored in clear. With database
+//! encryption on, SQLCipher encrypts the whole vault file.
+//!
+//! The database key is random and never changes. It is kept in a header
+//! file next to the vault (`vault.db.header`), encrypted with the database
+//! subkey of the master key, along with what unlocking needs before the
+//! database can be read: the master password hash, the key derivation salt
+//! and parameters, the recent unlock attempts, and the settings the unlock
+//! screen reads (`HEADER_SETTINGS`, e.g. whether biometric unlock is on).
+//! Changing the master password only re-wraps the key. Pool connections are keyed on unlock,
+//! and connections keyed before a lock aren't reused.
+//!
+//! SQLCipher must be linked in (`sqlcipher` feature). Switching encryption
+//! on or off rewrites the vault file and closes the database, which must be
+//! reopened (application restart). A switch interrupted by a crash is
+//! finished or rolled back the next time the vault is opened.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use rand::RngCore;
+use rite_crypto::{decrypt_with_aad, encrypt_with_aad, subkey, EncryptedData, KdfParams};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqliteConnection;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::auth::MasterKey;
+use crate::db::{Database, UnlockAttempt, HEADER_SETTINGS};
+
+/// Header layout written by this version
+const HEADER_VERSION: u32 = 1;
+
+/// Associated data of the wrapped database key
+const WRAPPED_KEY_AAD: &[u8] = b"database-key";
+
+/// First bytes of a plain SQLite file (SQLCipher files look random)
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Unlock attempts kept in the header (milliseconds)
+const UNLOCK_ATTEMPTS_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Header of an encrypted vault (JSON, next to the vault file)
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    version: u32,
+    /// Master password hash (PHC string)
+    hash: String,
+    /// Key derivation salt (base64)
+    salt: String,
+    kdf_params: KdfParams,
+    /// Database key encrypted with the database subkey (base64 envelope)
+    wrapped_key: String,
+    #[serde(default)]
+    unlock_attempts: Vec<UnlockAttempt>,
+    /// Settings readable while locked (`HEADER_SETTINGS`)
+    #[serde(default)]
+    settings: BTreeMap<String, String>,
+}
+
+/// Key state of an encrypted vault, shared by its pool connections
+pub(crate) struct Cipher {
+    header_path: PathBuf,
+    /// `PRAGMA key` value (`x'<hex>'`), set while unlocked
+    key: Mutex<Option<Zeroizing<String>>>,
+    /// When the key was last set or dropped
+    key_changed_at: Mutex<Instant>,
+    /// Serializes header rewrites
+    header_lock: tokio::sync::Mutex<()>,
+}
+
+impl Cipher {
+    /// Key state of the vault at `db_path`, or None if it isn't encrypted
+    ///
+    /// Finishes (or rolls back) a switch interrupted by a crash first.
+    pub(crate) fn open(db_path: &Path) -> Result<Option<Arc<Self>>> {
+        let header_path = header_path(db_path);
+        let pending = pending_path(db_path);
+
+        if !header_path.exists() {
+            // Encrypting stopped before the header was written
+            remove_if_exists(&pending)?;
+            return Ok(None);
+        }
+        if is_plain_sqlite(db_path)? {
+            if pending.exists() {
+                warn!("Finishing interrupted database encryption");
+                std::fs::rename(&pending, db_path)?;
+            } else {
+                warn!("Finishing interrupted database decryption");
+                std::fs::remove_file(&header_path)?;
+                return Ok(None);
+            }
+        } else {
+            remove_if_exists(&pending)?;
+        }
+
+        Ok(Some(Arc::new(Self {
+            header_path,
+            key: Mutex::new(None),
+            key_changed_at: Mutex::new(Instant::now()),
+            header_lock: tokio::sync::Mutex::new(()),
+        })))
+    }
+
+    /// Key new pool connections, and drop the ones opened before the key
+    /// was last set or dropped instead of reusing them
+    pub(crate) fn configure(self: &Arc<Self>, options: SqlitePoolOptions) -> SqlitePoolOptions {
+        let on_connect = Arc::clone(self);
+        let on_acquire = Arc::clone(self);
+        options
+            .after_connect(move |conn, _| {
+                let key = on_connect.pragma_key();
+                Box::pin(async move {
+                    if let Some(key) = key {
+                        apply_key(conn, &key).await?;
+                        // Reads the database, so only possible once keyed
+                        sqlx::query("PRAGMA journal_mode = WAL")
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
+            .before_acquire(move |_, meta| {
+                let current = on_acquire.has_key()
+                    && meta.age < on_acquire.key_changed_at.lock().unwrap().elapsed();
+                Box::pin(async move { Ok(current) })
+            })
+    }
+
+    /// Whether the database key is set (vault unlocked)
+    pub(crate) fn has_key(&self) -> bool {
+        self.key.lock().unwrap().is_some()
+    }
+
+    /// Drop the database key
+    pub(crate) fn clear_key(&self) {
+        *self.key.lock().unwrap() = None;
+        *self.key_changed_at.lock().unwrap() = Instant::now();
+    }
+
+    /// Unwrap the database key with the master key and use it from now on
+    pub(crate) fn set_key(&self, master_key: &MasterKey) -> Result<()> {
+        let header = self.read_header()?;
+        let wrapped = base64::engine::general_purpose::STANDARD.decode(&header.wrapped_key)?;
+        let db_key = Zeroizing::new(
+            decrypt_with_aad(
+                &master_key.derive_subkey(subkey::DATABASE),
+                &EncryptedData::from_bytes(&wrapped)?,
+                WRAPPED_KEY_AAD,
+            )
+            .map_err(|_| anyhow!("Master key does not open the database"))?,
+        );
+
+        *self.key.lock().unwrap() = Some(pragma_value(&db_key));
+        *self.key_changed_at.lock().unwrap() = Instant::now();
+        Ok(())
+    }
+
+    /// Re-wrap the database key for a new master password (requires the key)
+    pub(crate) async fn rewrap(
+        &self,
+        master_key: &MasterKey,
+        hash: &str,
+        salt: &[u8],
+        kdf_params: &KdfParams,
+    ) -> Result<()> {
+        let db_key = self
+            .pragma_key()
+            .ok_or_else(|| anyhow!("Database is locked"))?;
+        let db_key = Zeroizing::new(parse_pragma_value(&db_key)?);
+
+        let _guard = self.header_lock.lock().await;
+        let mut header = self.read_header()?;
+        header.hash = hash.to_string();
+        header.salt = base64::engine::general_purpose::STANDARD.encode(salt);
+        header.kdf_params = *kdf_params;
+        header.wrapped_key = wrap_key(master_key, &db_key)?;
+        self.write_header(&header)
+    }
+
+    /// Master password hash, key derivation salt and parameters
+    pub(crate) fn master_password(&self) -> Result<(String, Vec<u8>, KdfParams)> {
+        let header = self.read_header()?;
+        let salt = base64::engine::general_purpose::STANDARD.decode(&header.salt)?;
+        Ok((header.hash, salt, header.kdf_params))
+    }
+
+    pub(crate) async fn record_unlock_attempt(&self, attempt: UnlockAttempt) -> Result<()> {
+        let _guard = self.header_lock.lock().await;
+        let mut header = self.read_header()?;
+        let cutoff = attempt.timestamp - UNLOCK_ATTEMPTS_TTL_MS;
+        header.unlock_attempts.retain(|a| a.timestamp >= cutoff);
+        header.unlock_attempts.push(attempt);
+        self.write_header(&header)
+    }
+
+    /// Unlock attempts since `cutoff`, newest first
+    pub(crate) fn unlock_attempts(&self, cutoff: i64) -> Result<Vec<UnlockAttempt>> {
+        let mut attempts: Vec<UnlockAttempt> = self
+            .read_header()?
+            .unlock_attempts
+            .into_iter()
+            .filter(|a| a.timestamp > cutoff)
+            .collect();
+        attempts.sort_by_key(|a| std::cmp::Reverse(a.timestamp));
+        Ok(attempts)
+    }
+
+    /// Setting kept in the header (readable while locked)
+    pub(crate) fn setting(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.read_header()?.settings.remove(key))
+    }
+
+    pub(crate) async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let _guard = self.header_lock.lock().await;
+        let mut header = self.read_header()?;
+        header.settings.insert(key.to_string(), value.to_string());
+        self.write_header(&header)
+    }
+
+    fn pragma_key(&self) -> Option<Zeroizing<String>> {
+        self.key.lock().unwrap().clone()
+    }
+
+    fn read_header(&self) -> Result<Header> {
+        let json = std::fs::read(&self.header_path).context("Failed to read vault header")?;
+        let header: Header = serde_json::from_slice(&json).context("Invalid vault header")?;
+        if header.version > HEADER_VERSION {
+            return Err(anyhow!(
+                "Vault header version {} is newer than supported ({})",
+                header.version,
+                HEADER_VERSION
+            ));
+        }
+        Ok(header)
+    }
+
+    fn write_header(&self, header: &Header) -> Result<()> {
+        write_header_file(&self.header_path, header)
+    }
+}
+
+/// Whether this build can encrypt databases (SQLCipher linked in)
+pub async fn is_available(db: &Database) -> bool {
+    matches!(
+        sqlx::query_scalar::<_, String>("PRAGMA cipher_version")
+            .fetch_optional(db.pool())
+            .await,
+        Ok(Some(_))
+    )
+}
+
+/// Encrypt the vault file with a new random database key
+///
+/// An encrypted backup is written first (`pre_encryption`), and the plain
+/// `.db` backups, which hold the same data in clear, are deleted. The
+/// database is closed afterwards and must be reopened.
+pub async fn enable(db: &Database, master_key: &MasterKey) -> Result<()> {
+    if db.is_encrypted() {
+        return Err(anyhow!("Database is already encrypted"));
+    }
+    if !is_available(db).await {
+        return Err(anyhow!(
+            "Database encryption is not available (built without SQLCipher)"
+        ));
+    }
+    let (hash, salt) = db
+        .get_master_password()
+        .await?
+        .ok_or_else(|| anyhow!("No master password set"))?;
+    let kdf_params = db.get_kdf_params().await?;
+    let mut settings = BTreeMap::new();
+    for key in HEADER_SETTINGS {
+        if let Some(value) = db.get_setting(key).await? {
+            settings.insert(key.to_string(), value);
+        }
+    }
+
+    let backup = crate::backup::create(db, master_key, "pre_encryption").await?;
+    info!("Pre-encryption backup written to {}", backup.path.display());
+
+    let mut db_key = Zeroizing::new([0u8; 32]);
+    rand::rngs::OsRng.fill_bytes(&mut db_key[..]);
+
+    let pending = pending_path(db.path());
+    remove_if_exists(&pending)?;
+    export(db, &pending, &pragma_value(&db_key[..]))
+        .await
+        .context("Failed to encrypt the database")?;
+
+    write_header_file(
+        &header_path(db.path()),
+        &Header {
+            version: HEADER_VERSION,
+            hash,
+            salt: base64::engine::general_purpose::STANDARD.encode(&salt),
+            kdf_params,
+            wrapped_key: wrap_key(master_key, &db_key[..])?,
+            unlock_attempts: Vec::new(),
+            settings,
+        },
+    )?;
+
+    db.pool().close().await;
+    std::fs::rename(&pending, db.path()).context("Failed to replace the vault file")?;
+    remove_plain_backups(db).await?;
+
+    info!("Database encrypted");
+    Ok(())
+}
+
+/// Decrypt the vault file (requires the vault to be unlocked)
+///
+/// An encrypted backup is written first (`pre_decryption`). The `.db`
+/// backups made while encrypted can't be opened without the database key,
+/// which is discarded, so they are deleted. The database is closed
+/// afterwards and must be reopened.
+pub async fn disable(db: &Database, master_key: &MasterKey) -> Result<()> {
+    let cipher = db
+        .cipher()
+        .ok_or_else(|| anyhow!("Database is not encrypted"))?;
+    if !cipher.has_key() {
+        return Err(anyhow!("Database is locked"));
+    }
+
+    let backup = crate::backup::create(db, master_key, "pre_decryption").await?;
+    info!("Pre-decryption backup written to {}", backup.path.display());
+
+    // The header goes away: its settings move back to the settings table
+    for (key, value) in cipher.read_header()?.settings {
+        crate::db::write_setting(db.pool(), &key, &value).await?;
+    }
+
+    let pending = pending_path(db.path());
+    remove_if_exists(&pending)?;
+    export(db, &pending, "")
+        .await
+        .context("Failed to decrypt the database")?;
+
+    db.pool().close().await;
+    std::fs::rename(&pending, db.path()).context("Failed to replace the vault file")?;
+    std::fs::remove_file(&cipher.header_path)?;
+    remove_plain_backups(db).await?;
+
+    info!("Database decrypted");
+    Ok(())
+}
+
+/// Set an encrypted vault aside (reset with a forgotten master password)
+///
+/// The vault file and its header are moved to the backups directory; the
+/// database is closed and a new vault is created when it is reopened.
+/// Returns the path of the moved vault file.
+pub(crate) async fn discard(db: &Database) -> Result<PathBuf> {
+    let cipher = db
+        .cipher()
+        .ok_or_else(|| anyhow!("Database is not encrypted"))?;
+
+    let dir = db.backup_dir()?;
+    std::fs::create_dir_all(&dir).context("Failed to create backup directory")?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let target = dir.join(format!("vault_pre_reset_{}.db", timestamp));
+
+    db.pool().close().await;
+    std::fs::rename(db.path(), &target).context("Failed to move the vault file")?;
+    std::fs::rename(&cipher.header_path, header_path(&target))
+        .context("Failed to move the vault header")?;
+    Ok(target)
+}
+
+/// Write a plain (unencrypted) copy of the database
+///
+/// `VACUUM INTO` copies an encrypted database with its key, so encrypted
+/// vaults are exported instead.
+pub(crate) async fn export_plain(db: &Database, path: &Path) -> Result<()> {
+    if db.is_encrypted() {
+        export(db, path, "").await
+    } else {
+        db.create_backup(path).await
+    }
+}
+
+/// Attach another database file as `alias` on `conn`
+///
+/// Plain files attached to an encrypted vault need an empty key; anything
+/// else uses the key of the vault.
+pub(crate) async fn attach(
+    db: &Database,
+    conn: &mut SqliteConnection,
+    path: &Path,
+    alias: &str,
+) -> Result<()> {
+    let path = path.to_string_lossy();
+    if db.is_encrypted() && is_plain_sqlite(Path::new(path.as_ref()))? {
+        sqlx::query(&format!("ATTACH DATABASE ?1 AS {} KEY ''", alias))
+            .bind(path.as_ref())
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        sqlx::query(&format!("ATTACH DATABASE ?1 AS {}", alias))
+            .bind(path.as_ref())
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Header file of the vault at `db_path` (`<vault>.header`)
+pub fn header_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".header");
+    PathBuf::from(path)
+}
+
+/// Vault file written while switching encryption on or off
+fn pending_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".pending");
+    PathBuf::from(path)
+}
+
+/// Copy the database into a new file keyed with `key` (empty = plain)
+async fn export(db: &Database, path: &Path, key: &str) -> Result<()> {
+    let mut conn = db.pool().acquire().await?;
+    sqlx::query("ATTACH DATABASE ?1 AS export KEY ?2")
+        .bind(path.to_string_lossy().as_ref())
+        .bind(key)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = sqlx::query("SELECT sqlcipher_export('export')")
+        .execute(&mut *conn)
+        .await;
+
+    if let Err(e) = sqlx::query("DETACH DATABASE export")
+        .execute(&mut *conn)
+        .await
+    {
+        warn!("Failed to detach export: {}", e);
+    }
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Delete the `.db` backups (plain copies, or keyed with a discarded key)
+async fn remove_plain_backups(db: &Database) -> Result<()> {
+    for backup in crate::backup::list(db).await? {
+        if !backup.encrypted {
+            std::fs::remove_file(&backup.path)
+                .with_context(|| format!("Failed to delete backup {}", backup.file_name))?;
+        }
+    }
+    Ok(())
+}
+
+async fn apply_key(conn: &mut SqliteConnection, key: &str) -> sqlx::Result<()> {
+    // PRAGMA values can't be bound; the key is hex only
+    sqlx::query(&format!("PRAGMA key = \"{}\"", key))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Raw key as SQLCipher takes it (`x'<hex>'`, no key derivation)
+fn pragma_value(db_key: &[u8]) -> Zeroizing<String> {
+    let hex: String = db_key.iter().map(|b| format!("{:02x}", b)).collect();
+    Zeroizing::new(format!("x'{}'", hex))
+}
+
+fn parse_pragma_value(value: &str) -> Result<Vec<u8>> {
+    let hex = value
+        .strip_prefix("x'")
+        .and_then(|rest| rest.strip_suffix('\''))
+        .ok_or_else(|| anyhow!("Invalid database key"))?;
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("Invalid database key"))
+        })
+        .collect()
+}
+
+fn wrap_key(master_key: &MasterKey, db_key: &[u8]) -> Result<String> {
+    let wrapped = encrypt_with_aad(
+        &master_key.derive_subkey(subkey::DATABASE),
+        db_key,
+        WRAPPED_KEY_AAD,
+    )?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(wrapped.to_bytes()))
+}
+
+/// Replace the header file atomically
+fn write_header_file(path: &Path, header: &Header) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    std::fs::write(&tmp, serde_json::to_vec_pretty(header)?)
+        .context("Failed to write vault header")?;
+    std::fs::rename(&tmp, path).context("Failed to write vault header")?;
+    Ok(())
+}
+
+/// Whether a file is a plain SQLite database
+pub(crate) fn is_plain_sqlite(path: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    let mut magic = [0u8; 16];
+    let mut file = std::fs::File::open(path)?;
+    Ok(file.read_exact(&mut magic).is_ok() && &magic == SQLITE_MAGIC)
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{connection_input, create_test_vault};
+    #[cfg(feature = "sqlcipher")]
+    use crate::{test_util::PASSWORD, UnlockResult};
+    use crate::{ConnectionInfo, Vault};
+    use tempfile::TempDir;
+
+    /// A test vault holding one connection
+    async fn create_vault_with_connection() -> (Vault, TempDir) {
+        let (vault, temp_dir) = create_test_vault().await;
+        vault
+            .create_connection(connection_input("web", None))
+            .await
+            .unwrap();
+        (vault, temp_dir)
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[tokio::test]
+    async fn test_encrypt_and_decrypt() {
+        let (vault, temp) = create_vault_with_connection().await;
+        let path = temp.path().join("vault.db");
+        vault
+            .database()
+            .create_timestamped_backup("pre_test")
+            .await
+            .unwrap();
+        // The search index is copied too
+        let found: Vec<ConnectionInfo> = vault.search_connections("web", None).await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        vault.encrypt_database().await.unwrap();
+        drop(vault);
+        assert!(!is_plain_sqlite(&path).unwrap());
+        let content = std::fs::read(&path).unwrap();
+        assert!(!content.windows(15).any(|w| w == b"web.example.com"));
+
+        let vault = Vault::open(&path).await.unwrap();
+        // Plain backups are replaced by an encrypted one
+        let backups = vault.list_backups().await.unwrap();
+        assert!(backups.iter().all(|b| b.encrypted));
+        assert!(backups.iter().any(|b| b.label == "pre_encryption"));
+
+        // Nothing readable until unlocked
+        assert!(vault.database().is_encrypted());
+        assert!(vault.is_initialized().await.unwrap());
+        assert!(vault.database().get_all_settings().await.is_err());
+        assert_eq!(
+            vault.unlock("wrong password").await.unwrap(),
+            UnlockResult::InvalidPassword
+        );
+        assert_eq!(vault.unlock(PASSWORD).await.unwrap(), UnlockResult::Success);
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        assert_eq!(connections.len(), 1);
+        let found: Vec<ConnectionInfo> = vault.search_connections("web", None).await.unwrap();
+        assert_eq!(found.len(), 1);
+
+        // Keyed connections aren't reused once locked
+        vault.lock().await.unwrap();
+        assert!(vault.database().get_all_settings().await.is_err());
+
+        // A password change re-wraps the key
+        let new_password = "An0ther!Str0ng#Passw0rd";
+        vault.unlock(PASSWORD).await.unwrap();
+        vault
+            .change_master_password(PASSWORD, new_password)
+            .await
+            .unwrap();
+        drop(vault);
+        let vault = Vault::open(&path).await.unwrap();
+        assert_eq!(
+            vault.unlock(PASSWORD).await.unwrap(),
+            UnlockResult::InvalidPassword
+        );
+        assert_eq!(
+            vault.unlock(new_password).await.unwrap(),
+            UnlockResult::Success
+        );
+
+        vault.decrypt_database().await.unwrap();
+        drop(vault);
+        assert!(is_plain_sqlite(&path).unwrap());
+        assert!(!header_path(&path).exists());
+
+        let vault = Vault::open(&path).await.unwrap();
+        assert!(!vault.database().is_encrypted());
+        vault.unlock(new_password).await.unwrap();
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        assert_eq!(connections.len(), 1);
+        let found: Vec<ConnectionInfo> = vault.search_connections("web", None).await.unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[tokio::test]
+    async fn test_encrypted_backup_and_reset() {
+        let (vault, temp) = create_vault_with_connection().await;
+        let path = temp.path().join("vault.db");
+        let plain_backup = vault.backup().await.unwrap();
+        vault.encrypt_database().await.unwrap();
+
+        let vault = Vault::open(&path).await.unwrap();
+        vault.unlock(PASSWORD).await.unwrap();
+        vault
+            .delete_connection(&vault_connection_id(&vault).await)
+            .await
+            .unwrap();
+
+        // Backups made before encryption restore into the encrypted vault
+        vault.restore(&plain_backup.path, PASSWORD).await.unwrap();
+        vault.unlock(PASSWORD).await.unwrap();
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        assert_eq!(connections.len(), 1);
+
+        // Reset sets the encrypted vault aside and starts over
+        vault.auth().reset_database().await.unwrap();
+        drop(vault);
+        let vault = Vault::open(&path).await.unwrap();
+        assert!(!vault.database().is_encrypted());
+        assert!(!vault.is_initialized().await.unwrap());
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    async fn vault_connection_id(vault: &Vault) -> String {
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        connections[0].id.clone()
+    }
+
+    #[cfg(not(feature = "sqlcipher"))]
+    #[tokio::test]
+    async fn test_encryption_unavailable() {
+        let (vault, temp) = create_vault_with_connection().await;
+        let error = vault.encrypt_database().await.unwrap_err();
+        assert!(error.to_string().contains("SQLCipher"));
+        assert!(!header_path(&temp.path().join("vault.db")).exists());
+        let connections: Vec<ConnectionInfo> = vault.list_connections().await.unwrap();
+        assert_eq!(connections.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_header_settings() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("vault.db");
+        std::fs::write(&path, [0x5au8; 64]).unwrap();
+        write_header_file(
+            &header_path(&path),
+            &Header {
+                version: HEADER_VERSION,
+                hash: String::new(),
+                salt: String::new(),
+                kdf_params: KdfParams::default(),
+                wrapped_key: String::new(),
+                unlock_attempts: Vec::new(),
+                settings: BTreeMap::new(),
+            },
+        )
+        .unwrap();
+
+        // Readable and writable without the database key
+        let cipher = Cipher::open(&path).unwrap().unwrap();
+        assert!(!cipher.has_key());
+        assert_eq!(cipher.setting(HEADER_SETTINGS[0]).unwrap(), None);
+        cipher
+            .set_setting(HEADER_SETTINGS[0], "true")
+            .await
+            .unwrap();
+        assert_eq!(
+            cipher.setting(HEADER_SETTINGS[0]).unwrap().as_deref(),
+            Some("true")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_decryption() {
+        let (vault, temp) = create_vault_with_connection().await;
+        drop(vault);
+
+        // Header left behind next to a plain vault
+        let path = temp.path().join("vault.db");
+        std::fs::write(header_path(&path), b"{}").unwrap();
+
+        let vault = Vault::open(&path).await.unwrap();
+        assert!(!vault.database().is_encrypted());
+        assert!(!header_path(&path).exists());
+        assert!(vault.is_initialized().await.unwrap());
+    }
+}
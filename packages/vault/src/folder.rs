@@ -0,0 +1,208 @@
+//! Folder module
+//!
+//! Folders organize connections in a tree. A connection points at its
+//! folder by ID, so renaming or moving a folder doesn't touch the
+//! connections in it. Paths (`Production/Databases`) are built from the
+//! names, for display and for callers that only know a path (templates,
+//! imports).
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db::FolderRow;
+
+/// Separator between the folder names of a path
+pub const PATH_SEPARATOR: char = '/';
+
+/// Longest folder name accepted
+pub const MAX_NAME_LEN: usize = 100;
+
+/// Connection folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    /// None = top level
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
+    /// Position among its siblings
+    pub sort_order: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Folder with its subfolders, for tree views
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderNode {
+    #[serde(flatten)]
+    pub folder: Folder,
+    /// Full path (`Production/Databases`)
+    pub path: String,
+    /// Connections directly in this folder
+    pub connection_count: i64,
+    pub children: Vec<FolderNode>,
+}
+
+/// Input for creating a folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFolderInput {
+    pub name: String,
+    /// None = top level
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Input for updating a folder (None = unchanged)
+///
+/// Moving a folder to another parent is [`crate::Vault::move_folder`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFolderInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub color: Option<String>, // Empty string = no color
+    pub sort_order: Option<i64>,
+}
+
+impl Folder {
+    pub fn from_row(row: &FolderRow) -> Self {
+        Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            parent_id: row.parent_id.clone(),
+            color: row.color.clone(),
+            sort_order: row.sort_order,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Check a folder name and return it trimmed
+pub fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Folder name cannot be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(anyhow!(
+            "Folder name is too long (at most {} characters)",
+            MAX_NAME_LEN
+        ));
+    }
+    if name.contains(PATH_SEPARATOR) || name.chars().any(char::is_control) {
+        return Err(anyhow!(
+            "Folder name cannot contain '{}' or control characters",
+            PATH_SEPARATOR
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Folder names of a path, without empty segments
+pub fn split_path(path: &str) -> Vec<&str> {
+    path.split(PATH_SEPARATOR)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Build the folder tree from a flat list (in display order)
+///
+/// Folders whose parent isn't in the list are shown at the top level.
+pub fn build_tree(folders: Vec<Folder>, counts: &HashMap<String, i64>) -> Vec<FolderNode> {
+    let ids: std::collections::HashSet<String> = folders.iter().map(|f| f.id.clone()).collect();
+    let mut children: HashMap<Option<String>, Vec<Folder>> = HashMap::new();
+    for folder in folders {
+        let parent = folder.parent_id.clone().filter(|id| ids.contains(id));
+        children.entry(parent).or_default().push(folder);
+    }
+
+    fn nodes(
+        parent: Option<String>,
+        prefix: &str,
+        children: &mut HashMap<Option<String>, Vec<Folder>>,
+        counts: &HashMap<String, i64>,
+    ) -> Vec<FolderNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|folder| {
+                let path = if prefix.is_empty() {
+                    folder.name.clone()
+                } else {
+                    format!("{}{}{}", prefix, PATH_SEPARATOR, folder.name)
+                };
+                FolderNode {
+                    connection_count: counts.get(&folder.id).copied().unwrap_or(0),
+                    children: nodes(Some(folder.id.clone()), &path, children, counts),
+                    path,
+                    folder,
+                }
+            })
+            .collect()
+    }
+
+    nodes(None, "", &mut children, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(id: &str, parent_id: Option<&str>) -> Folder {
+        Folder {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            parent_id: parent_id.map(String::from),
+            color: None,
+            sort_order: 0,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name("  Production ").unwrap(), "Production");
+        assert!(validate_name("   ").is_err());
+        assert!(validate_name("Prod/DB").is_err());
+        assert!(validate_name("Prod\nDB").is_err());
+        assert!(validate_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_split_path() {
+        assert_eq!(
+            split_path("Production / Databases/"),
+            ["Production", "Databases"]
+        );
+        assert!(split_path(" / ").is_empty());
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let folders = vec![
+            folder("prod", None),
+            folder("db", Some("prod")),
+            folder("replica", Some("db")),
+            folder("lab", None),
+        ];
+        let counts = HashMap::from([("db".to_string(), 3)]);
+
+        let tree = build_tree(folders, &counts);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].path, "PROD");
+        assert_eq!(tree[0].children[0].path, "PROD/DB");
+        assert_eq!(tree[0].children[0].connection_count, 3);
+        assert_eq!(tree[0].children[0].children[0].path, "PROD/DB/REPLICA");
+        assert!(tree[1].children.is_empty());
+    }
+}
@@ -0,0 +1,530 @@
+//! Vault format
+//!
+//! The format version describes how vault contents are encoded, separately
+//! from the SQL schema version handled by migrations. Anything sharing a
+//! vault (the app, sync, other tools) checks it before touching encrypted
+//! data, and refuses formats newer than it understands.
+//!
+//! Formats:
+//! - 1: credentials encrypted with the master key
+//! - 2: credentials also bound to their connection ID (AEAD associated
+//!   data), so an encrypted blob can't be moved to another connection
+//! - 3: credentials in a versioned envelope (XChaCha20-Poly1305, nonce
+//!   inside the blob, empty `nonce` column)
+//! - 4: credentials and snippet commands bound to their record type as
+//!   well (`connection:<id>`, `snippet:<id>`), so a blob can't be moved
+//!   between tables either
+//! - 5: credentials and snippet commands encrypted with the credentials
+//!   subkey (HKDF of the master key) instead of the master key itself.
+//!   Vaults upgraded to it from formats 3 and 4 rewrote their rows lazily,
+//!   so some may still be in an older format
+//! - 6: every row rewritten in the current format; blobs bound to their ID
+//!   only or encrypted with the master key are no longer accepted
+//!
+//! Older formats are read as-is and upgraded to the current one the next
+//! time the master key is available (setup or unlock): every row is
+//! re-encrypted in one transaction, after a backup of the vault file.
+
+use anyhow::{anyhow, Context, Result};
+use rite_crypto::{
+    decrypt, decrypt_with_aad, encrypt_with_aad, subkey, EncryptedData, Envelope, MasterKey,
+};
+use serde::Serialize;
+use sqlx::{Row, SqliteConnection};
+use tracing::{info, warn};
+
+use crate::db::Database;
+
+/// Format written by this version
+pub const CURRENT_FORMAT: u32 = 6;
+
+/// Oldest format this version can read (vaults that predate the format record)
+pub const MIN_SUPPORTED_FORMAT: u32 = 1;
+
+/// Kind of encrypted record, bound to its ciphertext along with the row ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record {
+    /// Connection credentials (`connections.encrypted_credentials`)
+    Connection,
+    /// Snippet command (`snippets.encrypted_command`)
+    Snippet,
+    /// Remote task command (`remote_tasks.encrypted_command`)
+    RemoteTask,
+    /// Remote task run output (`remote_task_runs.encrypted_output`)
+    RemoteTaskRun,
+}
+
+impl Record {
+    /// Associated data of a record: `<kind>:<id>`
+    pub fn aad(self, id: &str) -> Vec<u8> {
+        let kind = match self {
+            Self::Connection => "connection",
+            Self::Snippet => "snippet",
+            Self::RemoteTask => "remote_task",
+            Self::RemoteTaskRun => "remote_task_run",
+        };
+        format!("{}:{}", kind, id).into_bytes()
+    }
+
+    /// Table and blob column of the record
+    fn column(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Connection => ("connections", "encrypted_credentials"),
+            Self::Snippet => ("snippets", "encrypted_command"),
+            Self::RemoteTask => ("remote_tasks", "encrypted_command"),
+            Self::RemoteTaskRun => ("remote_task_runs", "encrypted_output"),
+        }
+    }
+}
+
+/// Vault format and schema versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultFormatInfo {
+    /// Format of the vault on disk
+    pub format: u32,
+    /// Format written by this version
+    pub current_format: u32,
+    /// Oldest format this version can read
+    pub min_supported_format: u32,
+    /// SQL schema version (migrations)
+    pub schema_version: i64,
+}
+
+/// Create the format record, or check the existing one
+///
+/// Called when the database is opened. A vault without a record is new if
+/// it has no connections, otherwise it predates the record (format 1).
+pub(crate) async fn init(db: &Database) -> Result<u32> {
+    if let Some(format) = stored_format(db).await? {
+        if format > CURRENT_FORMAT {
+            anyhow::bail!(
+                "Vault format ({}) is newer than application supports ({}). \
+                 Please upgrade RITE to the latest version.",
+                format,
+                CURRENT_FORMAT
+            );
+        }
+        return Ok(format);
+    }
+
+    let connections: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM connections")
+        .fetch_one(db.pool())
+        .await?;
+    let format = if connections == 0 {
+        CURRENT_FORMAT
+    } else {
+        MIN_SUPPORTED_FORMAT
+    };
+
+    info!("Recording vault format {}", format);
+    write_format(db.pool(), format).await?;
+    Ok(format)
+}
+
+/// Read the vault format
+pub async fn read_format(db: &Database) -> Result<u32> {
+    Ok(stored_format(db).await?.unwrap_or(MIN_SUPPORTED_FORMAT))
+}
+
+/// Vault format and schema versions
+pub async fn info(db: &Database) -> Result<VaultFormatInfo> {
+    Ok(VaultFormatInfo {
+        format: read_format(db).await?,
+        current_format: CURRENT_FORMAT,
+        min_supported_format: MIN_SUPPORTED_FORMAT,
+        schema_version: db.get_schema_version().await?,
+    })
+}
+
+/// Re-encrypt an older vault in the current format
+///
+/// Runs in a single transaction, after a backup of the vault file. Only
+/// connections and snippets predate format 5: other records were always
+/// written in it.
+pub(crate) async fn upgrade(db: &Database, master_key: &MasterKey) -> Result<()> {
+    let format = read_format(db).await?;
+    if format == CURRENT_FORMAT {
+        return Ok(());
+    }
+
+    info!("Upgrading vault format {} → {}", format, CURRENT_FORMAT);
+    if let Err(e) = db.create_timestamped_backup("pre_format_upgrade").await {
+        warn!("Failed to create backup: {}. Continuing with upgrade...", e);
+    }
+
+    let mut tx = db.pool().begin().await?;
+    let count = reencrypt_credentials(&mut tx, format, master_key, master_key).await?;
+    let snippets =
+        reencrypt_records(&mut tx, Record::Snippet, format, master_key, master_key).await?;
+    write_format(&mut *tx, CURRENT_FORMAT).await?;
+    tx.commit().await?;
+
+    info!(
+        "Vault format upgraded to {} ({} connections and {} snippets re-encrypted)",
+        CURRENT_FORMAT, count, snippets
+    );
+    Ok(())
+}
+
+/// Re-encrypt every connection's credentials in the current format
+///
+/// Reads them as `format` with `old_key` and writes them with `new_key`
+/// (the same key for a format upgrade). Returns the number of connections.
+pub(crate) async fn reencrypt_credentials(
+    conn: &mut SqliteConnection,
+    format: u32,
+    old_key: &MasterKey,
+    new_key: &MasterKey,
+) -> Result<usize> {
+    let rows = sqlx::query("SELECT id, encrypted_credentials, nonce FROM connections")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for row in &rows {
+        let id: String = row.get("id");
+        let data: Vec<u8> = row.get("encrypted_credentials");
+        let nonce: Vec<u8> = row.get("nonce");
+        let plaintext = zeroize::Zeroizing::new(
+            decrypt_record(format, Record::Connection, &id, &data, &nonce, old_key)
+                .with_context(|| format!("Failed to read credentials of connection {}", id))?,
+        );
+        let encrypted = encrypt_record(Record::Connection, &id, &plaintext, new_key)?;
+        rewrite_record(&mut *conn, Record::Connection, &id, &data, &encrypted).await?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Re-encrypt every record of a kind in the current format
+///
+/// Reads them as `format` with `old_key` and writes them with `new_key`,
+/// like [`reencrypt_credentials`]. Returns the number of records.
+pub(crate) async fn reencrypt_records(
+    conn: &mut SqliteConnection,
+    record: Record,
+    format: u32,
+    old_key: &MasterKey,
+    new_key: &MasterKey,
+) -> Result<usize> {
+    let (table, column) = record.column();
+    let rows = sqlx::query(&format!("SELECT id, {column} AS data, nonce FROM {table}"))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for row in &rows {
+        let id: String = row.get("id");
+        let data: Vec<u8> = row.get("data");
+        let nonce: Vec<u8> = row.get("nonce");
+        let plaintext = zeroize::Zeroizing::new(
+            decrypt_record(format, record, &id, &data, &nonce, old_key)
+                .with_context(|| format!("Failed to read {:?} {}", record, id))?,
+        );
+        let encrypted = encrypt_record(record, &id, &plaintext, new_key)?;
+        rewrite_record(&mut *conn, record, &id, &data, &encrypted).await?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Check that a master key decrypts the vault's credentials
+///
+/// Tries one connection; an empty vault accepts any key.
+pub(crate) async fn check_key(db: &Database, master_key: &MasterKey) -> Result<()> {
+    let format = read_format(db).await?;
+    let row = sqlx::query("SELECT id, encrypted_credentials, nonce FROM connections LIMIT 1")
+        .fetch_optional(db.pool())
+        .await?;
+
+    if let Some(row) = row {
+        let id: String = row.get("id");
+        let data: Vec<u8> = row.get("encrypted_credentials");
+        let nonce: Vec<u8> = row.get("nonce");
+        decrypt_record(format, Record::Connection, &id, &data, &nonce, master_key)
+            .map(zeroize::Zeroizing::new)
+            .map_err(|_| anyhow!("Wrong master key"))?;
+    }
+    Ok(())
+}
+
+/// Key records are encrypted with from format 5 on
+fn records_key(master_key: &MasterKey) -> MasterKey {
+    master_key.derive_subkey(subkey::CREDENTIALS)
+}
+
+/// Encrypt a record in the current format
+pub(crate) fn encrypt_record(
+    record: Record,
+    id: &str,
+    plaintext: &[u8],
+    master_key: &MasterKey,
+) -> Result<EncryptedData> {
+    encrypt_with_aad(&records_key(master_key), plaintext, &record.aad(id))
+}
+
+/// Decrypt a record written in `format`
+pub(crate) fn decrypt_record(
+    format: u32,
+    record: Record,
+    id: &str,
+    data: &[u8],
+    nonce: &[u8],
+    master_key: &MasterKey,
+) -> Result<Vec<u8>> {
+    // Formats 2 and 3 differ in the blob only, which rite_crypto tells apart
+    let encrypted = EncryptedData {
+        data: data.to_vec(),
+        nonce: nonce.to_vec(),
+        salt: None,
+    };
+
+    let aad = record.aad(id);
+    match format {
+        1 => decrypt(master_key, &encrypted),
+        // Rows bound to their ID only: formats 2 and 3, and format 3 rows a
+        // lazy upgrade to 5 hadn't rewritten yet
+        2..=5 if !is_typed(record, id, data, nonce) => {
+            decrypt_with_aad(master_key, &encrypted, id.as_bytes())
+        }
+        2..=4 => decrypt_with_aad(master_key, &encrypted, &aad),
+        // Format 4 rows a lazy upgrade hadn't rewritten stay on the master key
+        5 => decrypt_with_aad(&records_key(master_key), &encrypted, &aad)
+            .or_else(|_| decrypt_with_aad(master_key, &encrypted, &aad)),
+        CURRENT_FORMAT => decrypt_with_aad(&records_key(master_key), &encrypted, &aad),
+        other => Err(anyhow!("Unsupported vault format: {}", other)),
+    }
+}
+
+/// Whether a stored record is bound to its record type (format 4 and up)
+///
+/// Reads the envelope header only, no key needed.
+fn is_typed(record: Record, id: &str, data: &[u8], nonce: &[u8]) -> bool {
+    nonce.is_empty()
+        && Envelope::parse(data).is_ok_and(|envelope| envelope.aad == record.aad(id).as_slice())
+}
+
+/// Replace a record's blob, if the row still holds `previous`
+async fn rewrite_record<'e, E>(
+    executor: E,
+    record: Record,
+    id: &str,
+    previous: &[u8],
+    encrypted: &EncryptedData,
+) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let (table, column) = record.column();
+    let query =
+        format!("UPDATE {table} SET {column} = ?1, nonce = ?2 WHERE id = ?3 AND {column} = ?4");
+    sqlx::query(&query)
+        .bind(&encrypted.data)
+        .bind(&encrypted.nonce[..])
+        .bind(id)
+        .bind(previous)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+async fn stored_format(db: &Database) -> Result<Option<u32>> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT version FROM vault_format WHERE id = 1")
+        .fetch_optional(db.pool())
+        .await?;
+    Ok(version.map(|v| v as u32))
+}
+
+async fn write_format<'e, E>(executor: E, format: u32) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO vault_format (id, version, updated_at) VALUES (1, ?1, ?2)
+        ON CONFLICT(id) DO UPDATE SET
+            version = excluded.version,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(format as i64)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn create_test_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        (db, temp_dir)
+    }
+
+    fn master_key() -> MasterKey {
+        MasterKey::derive("format-test", &[7u8; 16]).unwrap()
+    }
+
+    /// Insert a connection with format 1 credentials (not bound to the
+    /// connection; legacy blob decryption is covered in rite_crypto)
+    async fn insert_legacy_connection(db: &Database, id: &str, key: &MasterKey) {
+        let encrypted =
+            rite_crypto::encrypt(key, br#"{"type":"password","password":"pw"}"#).unwrap();
+        insert_connection(db, id, &encrypted).await;
+    }
+
+    async fn insert_connection(db: &Database, id: &str, encrypted: &EncryptedData) {
+        db.create_connection(
+            id,
+            id,
+            "ssh",
+            "example.com",
+            22,
+            "root",
+            &encrypted.data,
+            &encrypted.nonce,
+            "password",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            1,
+            1,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_vault_uses_current_format() {
+        let (db, _temp) = create_test_db().await;
+        let info = info(&db).await.unwrap();
+        assert_eq!(info.format, CURRENT_FORMAT);
+        assert_eq!(info.schema_version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_legacy_vault() {
+        let (db, temp) = create_test_db().await;
+        let key = master_key();
+
+        // A vault written before the format was recorded
+        insert_legacy_connection(&db, "a", &key).await;
+        insert_legacy_connection(&db, "b", &key).await;
+        sqlx::query("DELETE FROM vault_format")
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        // Reopening detects format 1
+        let db = Database::new(&temp.path().join("test.db")).await.unwrap();
+        assert_eq!(read_format(&db).await.unwrap(), 1);
+
+        upgrade(&db, &key).await.unwrap();
+        assert_eq!(read_format(&db).await.unwrap(), CURRENT_FORMAT);
+
+        // Rewritten as envelopes recording the record type and ID
+        let row = db.get_connection("a").await.unwrap().unwrap();
+        assert!(row.nonce.is_empty());
+        let envelope = Envelope::parse(&row.encrypted_credentials).unwrap();
+        assert_eq!(envelope.aad, b"connection:a");
+        let plaintext = decrypt_record(
+            CURRENT_FORMAT,
+            Record::Connection,
+            "a",
+            &row.encrypted_credentials,
+            &row.nonce,
+            &key,
+        )
+        .unwrap();
+        assert_eq!(plaintext, br#"{"type":"password","password":"pw"}"#);
+
+        // Bound to the connection: another row's ID or a snippet with the
+        // same ID doesn't authenticate
+        for (record, id) in [(Record::Connection, "b"), (Record::Snippet, "a")] {
+            assert!(decrypt_record(
+                CURRENT_FORMAT,
+                record,
+                id,
+                &row.encrypted_credentials,
+                &row.nonce,
+                &key
+            )
+            .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_rewrites_lazy_rows() {
+        let (db, _temp) = create_test_db().await;
+        let key = master_key();
+
+        // Left by a lazy upgrade to format 5. Format 3: bound to the
+        // connection ID only. Format 4: bound to the record type, but
+        // encrypted with the master key itself
+        let plaintext = br#"{"type":"password","password":"pw"}"#;
+        let format_3 = rite_crypto::encrypt_with_aad(&key, plaintext, b"a").unwrap();
+        insert_connection(&db, "a", &format_3).await;
+        let format_4 =
+            rite_crypto::encrypt_with_aad(&key, plaintext, &Record::Connection.aad("b")).unwrap();
+        insert_connection(&db, "b", &format_4).await;
+        write_format(db.pool(), 5).await.unwrap();
+
+        for (id, legacy) in [("a", &format_3), ("b", &format_4)] {
+            let read = |format: u32| {
+                decrypt_record(
+                    format,
+                    Record::Connection,
+                    id,
+                    &legacy.data,
+                    &legacy.nonce,
+                    &key,
+                )
+            };
+            assert_eq!(read(5).unwrap(), plaintext);
+            // Not accepted once every row was rewritten
+            assert!(read(CURRENT_FORMAT).is_err());
+        }
+
+        upgrade(&db, &key).await.unwrap();
+        assert_eq!(read_format(&db).await.unwrap(), CURRENT_FORMAT);
+
+        for id in ["a", "b"] {
+            let row = db.get_connection(id).await.unwrap().unwrap();
+            let read = decrypt_record(
+                CURRENT_FORMAT,
+                Record::Connection,
+                id,
+                &row.encrypted_credentials,
+                &row.nonce,
+                &key,
+            );
+            assert_eq!(read.unwrap(), plaintext);
+
+            // On the credentials subkey: the master key alone doesn't open it
+            let encrypted = EncryptedData {
+                data: row.encrypted_credentials,
+                nonce: row.nonce,
+                salt: None,
+            };
+            assert!(decrypt_with_aad(&key, &encrypted, &Record::Connection.aad(id)).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_newer_format_is_refused() {
+        let (db, temp) = create_test_db().await;
+        write_format(db.pool(), CURRENT_FORMAT + 1).await.unwrap();
+
+        assert!(Database::new(&temp.path().join("test.db")).await.is_err());
+    }
+}
@@ -0,0 +1,52 @@
+//! History module
+//!
+//! Connection usage: one record per session (start and end time, bytes
+//! transferred, exit status), and the per-connection statistics and
+//! "most used / recently used" listings built from them. Timestamps are
+//! Unix seconds, like `last_used_at`.
+
+use serde::{Deserialize, Serialize};
+
+/// One session of a saved connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    pub id: i64,
+    pub connection_id: String,
+    pub started_at: i64,
+    /// None while the session is open (or if the app quit during it)
+    pub ended_at: Option<i64>,
+    /// Bytes received from the server
+    pub bytes_in: i64,
+    /// Bytes sent to the server
+    pub bytes_out: i64,
+    /// Remote exit status, None when the session ended otherwise
+    pub exit_status: Option<i64>,
+}
+
+/// Usage statistics of a connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStats {
+    pub connection_id: String,
+    pub session_count: i64,
+    /// Sessions that ended with a non-zero exit status
+    pub failed_count: i64,
+    /// Time connected in seconds (ended sessions only)
+    pub total_duration: i64,
+    pub bytes_in: i64,
+    pub bytes_out: i64,
+    pub first_used_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+/// Connection in a "most used / recently used" listing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionUsage {
+    pub connection_id: String,
+    pub session_count: i64,
+    /// Time connected in seconds (ended sessions only)
+    pub total_duration: i64,
+    pub last_used_at: i64,
+}
@@ -0,0 +1,189 @@
+//! Remote task module
+//!
+//! Commands run over SSH on a set of connections, on demand or on a
+//! cron-like schedule, and the record of each run (exit status and output).
+//! The command and the output are encrypted with the master key like
+//! snippet commands; the schedule is stored as written and interpreted by
+//! the application.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::db::{RemoteTaskRow, RemoteTaskRunRow};
+use crate::format::{self, Record, CURRENT_FORMAT};
+use rite_crypto::MasterKey;
+
+/// Runs kept per task (older ones are deleted as new ones are recorded)
+pub const RUNS_KEPT_PER_TASK: i64 = 50;
+
+/// Remote task (with decrypted command)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTask {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    /// Connections the command runs on
+    pub connection_ids: Vec<String>,
+    /// 5-field cron expression, None = on demand only
+    pub schedule: Option<String>,
+    /// Scheduled runs happen only when enabled
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Input for creating a remote task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRemoteTaskInput {
+    pub name: String,
+    pub command: String,
+    pub connection_ids: Vec<String>,
+    pub schedule: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Input for updating a remote task (None = unchanged)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRemoteTaskInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub connection_ids: Option<Vec<String>>,
+    pub schedule: Option<String>, // Empty string = on demand only
+    pub enabled: Option<bool>,
+}
+
+/// One run of a task on one connection (with decrypted output)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTaskRun {
+    pub id: String,
+    pub task_id: String,
+    pub connection_id: String,
+    /// Unix timestamps in milliseconds
+    pub started_at: i64,
+    pub ended_at: i64,
+    /// None when the command didn't exit (connection error, signal)
+    pub exit_status: Option<i64>,
+    /// Why the run failed before the command exited
+    pub error: Option<String>,
+    /// stdout, then stderr
+    pub output: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RemoteTask {
+    /// Create a new remote task
+    pub fn new(input: CreateRemoteTaskInput) -> Self {
+        let now = Utc::now().timestamp_millis();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            command: input.command,
+            connection_ids: input.connection_ids,
+            schedule: input.schedule.filter(|s| !s.trim().is_empty()),
+            enabled: input.enabled,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Build a task (with decrypted command) from a database row
+    pub fn from_row(
+        row: &RemoteTaskRow,
+        connection_ids: Vec<String>,
+        master_key: &MasterKey,
+    ) -> Result<Self> {
+        let command = Zeroizing::new(format::decrypt_record(
+            CURRENT_FORMAT,
+            Record::RemoteTask,
+            &row.id,
+            &row.encrypted_command,
+            &row.nonce,
+            master_key,
+        )?);
+
+        Ok(Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            command: String::from_utf8(command.to_vec())?,
+            connection_ids,
+            schedule: row.schedule.clone(),
+            enabled: row.enabled != 0,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+
+    /// Encrypt the command for database storage
+    pub fn encrypt_command(&self, master_key: &MasterKey) -> Result<(Vec<u8>, Vec<u8>)> {
+        let encrypted = format::encrypt_record(
+            Record::RemoteTask,
+            &self.id,
+            self.command.as_bytes(),
+            master_key,
+        )?;
+        Ok((encrypted.data, encrypted.nonce))
+    }
+}
+
+impl RemoteTaskRun {
+    /// Start a run record (filled in as the run goes)
+    pub fn new(task_id: &str, connection_id: &str) -> Self {
+        let now = Utc::now().timestamp_millis();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            task_id: task_id.to_string(),
+            connection_id: connection_id.to_string(),
+            started_at: now,
+            ended_at: now,
+            exit_status: None,
+            error: None,
+            output: String::new(),
+        }
+    }
+
+    /// Build a run (with decrypted output) from a database row
+    pub fn from_row(row: &RemoteTaskRunRow, master_key: &MasterKey) -> Result<Self> {
+        let output = Zeroizing::new(format::decrypt_record(
+            CURRENT_FORMAT,
+            Record::RemoteTaskRun,
+            &row.id,
+            &row.encrypted_output,
+            &row.nonce,
+            master_key,
+        )?);
+
+        Ok(Self {
+            id: row.id.clone(),
+            task_id: row.task_id.clone(),
+            connection_id: row.connection_id.clone(),
+            started_at: row.started_at,
+            ended_at: row.ended_at,
+            exit_status: row.exit_status,
+            error: row.error.clone(),
+            output: String::from_utf8_lossy(&output).into_owned(),
+        })
+    }
+
+    /// Encrypt the output for database storage
+    pub fn encrypt_output(&self, master_key: &MasterKey) -> Result<(Vec<u8>, Vec<u8>)> {
+        let encrypted = format::encrypt_record(
+            Record::RemoteTaskRun,
+            &self.id,
+            self.output.as_bytes(),
+            master_key,
+        )?;
+        Ok((encrypted.data, encrypted.nonce))
+    }
+}
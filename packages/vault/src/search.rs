@@ -0,0 +1,50 @@
+//! Search module
+//!
+//! Connection search runs on an SQLite FTS5 index over the name, hostname,
+//! username, notes, folder path and tags (see `connections_fts` in the
+//! schema). This module turns what the user typed into an FTS5 query.
+
+/// Results returned when the caller doesn't set a limit
+pub const DEFAULT_LIMIT: u32 = 50;
+
+/// Column weights for ranking (bm25), in index column order:
+/// connection_id, name, hostname, username, notes, folder, tags
+pub(crate) const RANK_WEIGHTS: &str = "0.0, 10.0, 5.0, 2.0, 1.0, 3.0, 4.0";
+
+/// FTS5 query for the text typed by the user, None when there is nothing
+/// to search
+///
+/// Every word must match the start of a word in some column: `prod db`
+/// finds "db-01.prod.example.com". FTS5 syntax in the text is not
+/// interpreted (`web-01` or `"` are plain text).
+pub fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        // Words made only of separators (`-`, `/`) would match nothing
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(
+            match_expression("prod  db").as_deref(),
+            Some("\"prod\"* \"db\"*")
+        );
+        assert_eq!(
+            match_expression("web-01 say\"hi").as_deref(),
+            Some("\"web-01\"* \"say\"\"hi\"*")
+        );
+        assert_eq!(match_expression("  - / "), None);
+        assert_eq!(match_expression(""), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Snippet module
+//!
+//! Reusable command templates. The command is encrypted with the master key
+//! (bound to the snippet ID, like connection credentials) since it may hold
+//! secrets; name and folder stay readable for listings.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::db::SnippetRow;
+use crate::format::{self, Record, CURRENT_FORMAT};
+use rite_crypto::MasterKey;
+
+/// Command snippet (with decrypted command)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub folder: Option<String>,
+    /// Command template, may contain `{{variable}}` placeholders
+    pub command: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Input for creating a snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSnippetInput {
+    pub name: String,
+    pub folder: Option<String>,
+    pub command: String,
+}
+
+/// Input for updating a snippet (None = unchanged)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSnippetInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub folder: Option<String>, // Empty string = no folder
+    pub command: Option<String>,
+}
+
+impl Snippet {
+    /// Create a new snippet
+    pub fn new(input: CreateSnippetInput) -> Self {
+        let now = Utc::now().timestamp_millis();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            folder: input.folder.filter(|f| !f.trim().is_empty()),
+            command: input.command,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Build a snippet (with decrypted command) from a database row
+    pub fn from_row(row: &SnippetRow, master_key: &MasterKey) -> Result<Self> {
+        let command = Zeroizing::new(format::decrypt_record(
+            CURRENT_FORMAT,
+            Record::Snippet,
+            &row.id,
+            &row.encrypted_command,
+            &row.nonce,
+            master_key,
+        )?);
+
+        Ok(Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            folder: row.folder.clone(),
+            command: String::from_utf8(command.to_vec())?,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+
+    /// Encrypt the command for database storage
+    pub fn encrypt_command(&self, master_key: &MasterKey) -> Result<(Vec<u8>, Vec<u8>)> {
+        let encrypted = format::encrypt_record(
+            Record::Snippet,
+            &self.id,
+            self.command.as_bytes(),
+            master_key,
+        )?;
+        Ok((encrypted.data, encrypted.nonce))
+    }
+}
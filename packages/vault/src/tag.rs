@@ -0,0 +1,141 @@
+//! Tag module
+//!
+//! Tags label connections across folders ("prod", "db"). Connections list
+//! their tags by name; a name that doesn't exist yet is created when a
+//! connection is tagged with it.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::db::TagRow;
+
+/// Longest tag name accepted
+pub const MAX_NAME_LEN: usize = 50;
+
+/// Connection tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    /// Number of connections with the tag
+    pub connection_count: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Input for creating a tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTagInput {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Input for updating a tag (None = unchanged)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTagInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub color: Option<String>, // Empty string = no color
+}
+
+/// How a tag filter combines its tags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Connections with every tag (AND)
+    #[default]
+    All,
+    /// Connections with at least one of the tags (OR)
+    Any,
+}
+
+impl Tag {
+    pub fn from_row(row: &TagRow) -> Self {
+        Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            color: row.color.clone(),
+            connection_count: row.connection_count,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Check a tag name and return it trimmed
+pub fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Tag name cannot be empty"));
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(anyhow!(
+            "Tag name is too long (at most {} characters)",
+            MAX_NAME_LEN
+        ));
+    }
+    // Tag lists are often written comma-separated (imports, filters)
+    if name.contains(',') || name.chars().any(char::is_control) {
+        return Err(anyhow!("Tag name cannot contain ',' or control characters"));
+    }
+    Ok(name.to_string())
+}
+
+/// Check tag names and drop the duplicates (case-insensitive like the
+/// database: ASCII only; first spelling wins)
+pub fn normalize_names(names: &[String]) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for name in names {
+        let name = validate_name(name)?;
+        if !normalized.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            normalized.push(name);
+        }
+    }
+    Ok(normalized)
+}
+
+/// IDs of the tags
+pub(crate) fn ids(tags: &[Tag]) -> Vec<String> {
+    tags.iter().map(|tag| tag.id.clone()).collect()
+}
+
+/// Tag names stored on a connection row (JSON array)
+pub(crate) fn from_json(json: Option<&str>) -> Vec<String> {
+    json.and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name(" prod ").unwrap(), "prod");
+        assert!(validate_name("").is_err());
+        assert!(validate_name("prod,db").is_err());
+        assert!(validate_name("prod\tdb").is_err());
+        assert!(validate_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_names() {
+        let names = ["prod", " DB", "Prod", "db "].map(String::from);
+        assert_eq!(normalize_names(&names).unwrap(), ["prod", "DB"]);
+        assert!(normalize_names(&["ok".to_string(), " ".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_tag_match_format() {
+        assert_eq!(serde_json::to_value(TagMatch::Any).unwrap(), "any");
+        assert_eq!(
+            serde_json::from_str::<TagMatch>("\"all\"").unwrap(),
+            TagMatch::All
+        );
+    }
+}
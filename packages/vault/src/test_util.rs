@@ -0,0 +1,44 @@
+//! Test helpers
+//!
+//! Fixtures shared by the unit tests of the vault modules.
+
+use tempfile::TempDir;
+
+use crate::{AuthMethod, CreateConnectionInput, Vault};
+
+pub(crate) const PASSWORD: &str = "MyStr0ng!P@ssw0rd#2024";
+
+/// A vault initialized with [`PASSWORD`] in a temporary directory (removed
+/// when the `TempDir` is dropped)
+pub(crate) async fn create_test_vault() -> (Vault, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let vault = Vault::open(&temp_dir.path().join("vault.db"))
+        .await
+        .unwrap();
+    vault.initialize(PASSWORD).await.unwrap();
+    (vault, temp_dir)
+}
+
+/// A password connection to `<name>.example.com`
+pub(crate) fn connection_input(name: &str, folder: Option<&str>) -> CreateConnectionInput {
+    CreateConnectionInput {
+        name: name.to_string(),
+        protocol: "ssh".to_string(),
+        hostname: format!("{}.example.com", name),
+        port: 22,
+        username: "admin".to_string(),
+        auth_method: AuthMethod::Password {
+            password: "hunter2".to_string(),
+        },
+        proxy_password: None,
+        color: None,
+        icon: None,
+        folder_id: None,
+        folder: folder.map(String::from),
+        notes: None,
+        tags: Vec::new(),
+        ssh_keep_alive_override: None,
+        ssh_keep_alive_interval: None,
+        session_options: Some(serde_json::json!({ "locale": "de_DE.UTF-8" })),
+    }
+}
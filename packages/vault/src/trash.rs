@@ -0,0 +1,47 @@
+//! Trash
+//!
+//! Deleting a connection moves it to the trash (`deleted_at` set) where it
+//! can be restored until it is purged, either by emptying the trash or
+//! automatically once it has been there longer than the retention setting.
+//! Trashed connections are left out of every listing, search and lookup.
+
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+use crate::db::Database;
+
+/// Setting: days a connection stays in the trash (0 = until emptied)
+pub const SETTING_RETENTION: &str = "trash_retention_days";
+
+/// Retention when the setting is missing
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Trash retention in days (0 = no automatic purge)
+pub async fn retention_days(db: &Database) -> Result<i64> {
+    match db.get_setting(SETTING_RETENTION).await? {
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|days: &i64| *days >= 0)
+            .ok_or_else(|| anyhow!("Invalid trash retention: {}", value)),
+        None => Ok(DEFAULT_RETENTION_DAYS),
+    }
+}
+
+/// Purge the connections that have been in the trash longer than the
+/// retention setting
+///
+/// Returns the IDs of the purged connections.
+pub async fn purge_expired(db: &Database) -> Result<Vec<String>> {
+    let days = retention_days(db).await?;
+    if days == 0 {
+        return Ok(Vec::new());
+    }
+
+    let cutoff = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+    let purged = db.purge_trash(Some(cutoff)).await?;
+    if !purged.is_empty() {
+        info!("Purged {} connection(s) from the trash", purged.len());
+    }
+    Ok(purged)
+}